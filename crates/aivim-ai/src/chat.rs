@@ -0,0 +1,62 @@
+//! 对话请求
+//!
+//! 对话由按顺序排列的消息组成，每次请求发送全部历史消息，模型的回复流式返回
+
+use serde_json::Value;
+
+use crate::config::AiConfig;
+
+/// 消息的发送者
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    /// 请求中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        }
+    }
+}
+
+/// 对话中的一条消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, content)
+    }
+}
+
+/// 按服务的格式生成流式请求的 JSON，最多生成 `max_tokens` 个 token
+pub fn request_body(config: &AiConfig, messages: &[ChatMessage], max_tokens: usize, temperature: f64) -> Value {
+    config
+        .provider
+        .provider()
+        .request_body(&config.model, messages, max_tokens, temperature)
+}
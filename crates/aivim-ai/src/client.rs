@@ -0,0 +1,188 @@
+//! 流式请求客户端
+//!
+//! 请求由 curl 发送：地址、请求头（包括 API 密钥）和请求体写入 curl 的标准输入（`--config -`），
+//! 不出现在进程的命令行参数中。后台线程逐行读取响应，解析后通过通道发送，
+//! 每收到一个事件调用一次 `notify`（编辑器用它唤醒事件循环），主线程调用 `AiStream::poll` 取出
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use serde_json::Value;
+
+use crate::config::AiConfig;
+use crate::provider::Provider;
+use crate::stream::{self, StreamEvent};
+
+/// 发送请求的程序
+pub const CURL: &str = "curl";
+
+/// 请求产生的事件，由 `AiStream::poll` 返回
+pub type AiEvent = StreamEvent;
+
+/// 一个正在进行的流式请求，被丢弃时终止请求
+pub struct AiStream {
+    child: Child,
+    receiver: Receiver<AiEvent>,
+    finished: bool,
+}
+
+impl std::fmt::Debug for AiStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AiStream").field("finished", &self.finished).finish()
+    }
+}
+
+/// curl 配置文件中的字符串：用双引号包含，转义 `\`、`"` 和换行
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// 写入 curl 标准输入的配置，请求头（包括密钥）由服务决定
+pub fn curl_config(config: &AiConfig, body: &Value) -> String {
+    let mut lines = vec![
+        format!("url = {}", quote(&config.endpoint)),
+        format!("header = {}", quote("Content-Type: application/json")),
+    ];
+    let headers = config.provider.provider().headers(config.api_key().as_deref());
+    lines.extend(headers.iter().map(|header| format!("header = {}", quote(header))));
+    lines.push(format!("data-binary = {}", quote(&body.to_string())));
+    lines.join("\n") + "\n"
+}
+
+impl AiStream {
+    /// 发送请求，有新事件时在后台线程中调用 `notify`
+    pub fn start(config: &AiConfig, body: &Value, notify: impl Fn() + Send + 'static) -> io::Result<Self> {
+        let mut child = Command::new(CURL)
+            .args(["--silent", "--show-error", "--no-buffer", "--max-time"])
+            .arg(config.timeout.max(1).to_string())
+            .args(["--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let input = curl_config(config, body);
+        if let Some(mut stdin) = child.stdin.take() {
+            thread::spawn(move || {
+                let _ = stdin.write_all(input.as_bytes());
+            });
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let provider = config.provider.provider();
+        thread::spawn(move || read_response(provider, stdout, stderr, sender, notify));
+
+        Ok(Self {
+            child,
+            receiver,
+            finished: false,
+        })
+    }
+
+    /// 取出上次调用之后的事件，`Done` 或 `Error` 之后不再有事件
+    pub fn poll(&mut self) -> Vec<AiEvent> {
+        let mut events = Vec::new();
+        while !self.finished {
+            let Ok(event) = self.receiver.try_recv() else {
+                break;
+            };
+            self.finished = matches!(event, AiEvent::Done | AiEvent::Error(_));
+            events.push(event);
+        }
+        events
+    }
+
+    /// 是否已经收到 `Done` 或 `Error`
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// 终止请求
+    pub fn cancel(&mut self) {
+        if self.child.try_wait().ok().flatten().is_none() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+        self.finished = true;
+    }
+}
+
+impl Drop for AiStream {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// 读取响应直到结束，最后总是发送 `Done` 或 `Error`
+///
+/// 不是 SSE 格式的响应（如请求出错时返回的 JSON）收集起来，结束时从中取出错误消息
+fn read_response(
+    provider: &dyn Provider,
+    stdout: Option<impl Read>,
+    stderr: Option<impl Read>,
+    sender: Sender<AiEvent>,
+    notify: impl Fn(),
+) {
+    let send = |event: AiEvent| {
+        let sent = sender.send(event).is_ok();
+        notify();
+        sent
+    };
+
+    let mut other = String::new();
+    if let Some(stdout) = stdout {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            match provider.parse_line(&line) {
+                Some(event) => {
+                    let last = matches!(event, AiEvent::Done | AiEvent::Error(_));
+                    if !send(event) || last {
+                        return;
+                    }
+                }
+                None if !stream::is_sse_line(&line) => {
+                    other.push_str(&line);
+                    other.push('\n');
+                }
+                None => {}
+            }
+        }
+    }
+
+    let mut error = String::new();
+    if let Some(mut stderr) = stderr {
+        let _ = stderr.read_to_string(&mut error);
+    }
+    let event = if let Some(message) = serde_json::from_str::<Value>(&other)
+        .ok()
+        .and_then(|value| stream::error_message(&value))
+    {
+        AiEvent::Error(message)
+    } else if !error.trim().is_empty() {
+        AiEvent::Error(error.trim().to_string())
+    } else if !other.trim().is_empty() {
+        tracing::warn!("Unexpected AI response: {}", other.trim());
+        AiEvent::Error(other.lines().next().unwrap_or_default().to_string())
+    } else {
+        AiEvent::Done
+    };
+    send(event);
+}
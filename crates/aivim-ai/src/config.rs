@@ -0,0 +1,162 @@
+//! AI 服务设置
+//!
+//! 服务在 `config.toml` 的 `[ai]` 表中选择，每个服务的设置在各自的子表中，省略的项使用服务的默认值：
+//!
+//! ```toml
+//! [ai]
+//! provider = "anthropic"
+//!
+//! [ai.anthropic]
+//! model = "claude-3-5-haiku-latest"
+//! api_key_env = "ANTHROPIC_API_KEY"
+//! chat_max_tokens = 4096
+//!
+//! [ai.ollama]
+//! endpoint = "http://localhost:8080/v1/chat/completions"  # llama.cpp server
+//! model = "qwen2.5-coder"
+//! timeout = 120
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::provider::ProviderKind;
+
+/// AI 服务设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AiConfig {
+    /// 请求和响应的格式（见 provider 模块）
+    pub provider: ProviderKind,
+    pub endpoint: String,
+    pub model: String,
+    /// 保存 API 密钥的环境变量，未设置时不发送密钥
+    pub api_key_env: String,
+    /// 每次补全最多生成的 token 数
+    pub max_tokens: usize,
+    /// 对话等较长的回复最多生成的 token 数
+    pub chat_max_tokens: usize,
+    /// 请求的超时时间（秒）
+    pub timeout: u64,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self::for_provider(ProviderKind::default())
+    }
+}
+
+impl AiConfig {
+    /// 服务的默认设置
+    pub fn for_provider(kind: ProviderKind) -> Self {
+        let provider = kind.provider();
+        Self {
+            provider: kind,
+            endpoint: provider.default_endpoint().to_string(),
+            model: provider.default_model().to_string(),
+            api_key_env: provider.default_api_key_env().to_string(),
+            max_tokens: 64,
+            chat_max_tokens: 2048,
+            timeout: provider.default_timeout(),
+        }
+    }
+
+    /// 从环境变量读取 API 密钥，未设置或为空时为 None
+    pub fn api_key(&self) -> Option<String> {
+        if self.api_key_env.is_empty() {
+            return None;
+        }
+        std::env::var(&self.api_key_env).ok().filter(|key| !key.is_empty())
+    }
+}
+
+/// `config.toml` 中一个服务的设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderSettings {
+    pub endpoint: Option<String>,
+    pub model: Option<String>,
+    pub api_key_env: Option<String>,
+    pub timeout: Option<u64>,
+    pub max_tokens: Option<usize>,
+    pub chat_max_tokens: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    ai: AiSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AiSection {
+    provider: Option<String>,
+    openai: Option<ProviderSettings>,
+    anthropic: Option<ProviderSettings>,
+    ollama: Option<ProviderSettings>,
+}
+
+/// `config.toml` 中的 AI 设置：选择的服务和各服务的设置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AiSettings {
+    pub provider: ProviderKind,
+    providers: HashMap<ProviderKind, ProviderSettings>,
+}
+
+impl AiSettings {
+    /// 解析配置文件的内容，错误信息包括出错的位置
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // 完整的错误信息有多行（包括出错行的内容），只取第一行的位置和错误原因
+        let file: ConfigFile = toml::from_str(text).map_err(|e| {
+            let location = e.to_string().lines().next().unwrap_or_default().to_string();
+            format!("{}: {}", location, e.message())
+        })?;
+        let provider = match file.ai.provider.as_deref() {
+            Some(name) => ProviderKind::parse(name).ok_or_else(|| format!("unknown AI provider: {}", name))?,
+            None => ProviderKind::default(),
+        };
+        let providers = [
+            (ProviderKind::OpenAi, file.ai.openai),
+            (ProviderKind::Anthropic, file.ai.anthropic),
+            (ProviderKind::Ollama, file.ai.ollama),
+        ]
+        .into_iter()
+        .filter_map(|(kind, settings)| Some((kind, settings?)))
+        .collect();
+        Ok(Self { provider, providers })
+    }
+
+    /// 读取配置文件
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&text)
+    }
+
+    /// 服务的设置：配置文件中的值，省略的项为服务的默认值
+    pub fn config(&self, kind: ProviderKind) -> AiConfig {
+        let mut config = AiConfig::for_provider(kind);
+        let Some(settings) = self.providers.get(&kind) else {
+            return config;
+        };
+        if let Some(endpoint) = &settings.endpoint {
+            config.endpoint = endpoint.clone();
+        }
+        if let Some(model) = &settings.model {
+            config.model = model.clone();
+        }
+        if let Some(api_key_env) = &settings.api_key_env {
+            config.api_key_env = api_key_env.clone();
+        }
+        config.timeout = settings.timeout.unwrap_or(config.timeout);
+        config.max_tokens = settings.max_tokens.unwrap_or(config.max_tokens);
+        config.chat_max_tokens = settings.chat_max_tokens.unwrap_or(config.chat_max_tokens);
+        config
+    }
+
+    /// 选择的服务的设置
+    pub fn active(&self) -> AiConfig {
+        self.config(self.provider)
+    }
+}
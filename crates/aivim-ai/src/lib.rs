@@ -0,0 +1,23 @@
+//! AIVim 的 AI 子系统
+//!
+//! 与 OpenAI、Anthropic 或本地模型服务通信，不依赖编辑器本身：
+//! - config: 服务地址、模型、API 密钥所在的环境变量等设置，可以从 config.toml 读取
+//! - provider: 各服务的请求格式、请求头和流格式
+//! - prompt: 根据光标前后的文本生成补全请求
+//! - chat: 由多条消息组成的对话请求
+//! - stream: 解析服务返回的 SSE (server-sent events) 流
+//! - client: 在后台线程中发送请求，流式返回生成的文本
+
+pub mod chat;
+pub mod client;
+pub mod config;
+pub mod prompt;
+pub mod provider;
+pub mod stream;
+
+pub use chat::{ChatMessage, ChatRole};
+pub use client::{AiEvent, AiStream};
+pub use config::{AiConfig, AiSettings};
+pub use prompt::CompletionContext;
+pub use provider::{Provider, ProviderKind};
+pub use stream::StreamEvent;
@@ -0,0 +1,57 @@
+//! 补全请求
+//!
+//! 把光标前后的文本发给模型，要求只返回应插入光标处的文本。
+//! 前后文本按字符数截断，避免大文件产生过大的请求
+
+use serde_json::Value;
+
+use crate::chat::{self, ChatMessage};
+use crate::config::AiConfig;
+
+/// 光标之前最多发送的字符数
+pub const MAX_PREFIX_CHARS: usize = 4000;
+/// 光标之后最多发送的字符数
+pub const MAX_SUFFIX_CHARS: usize = 1000;
+
+/// 请求中标记光标位置的文本
+pub const CURSOR_MARKER: &str = "<CURSOR>";
+
+const SYSTEM_PROMPT: &str = "You are a code completion engine inside a text editor. \
+The user sends a file with the cursor position marked as <CURSOR>. \
+Reply with only the text to insert at the cursor: no explanations, no markdown fences, \
+and do not repeat text that is already before or after the cursor. \
+Reply with nothing if no completion is appropriate.";
+
+/// 补全请求的上下文
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompletionContext {
+    /// 光标之前的文本
+    pub prefix: String,
+    /// 光标之后的文本
+    pub suffix: String,
+    /// 文件类型（如 rust），用于提示模型
+    pub filetype: Option<String>,
+}
+
+impl CompletionContext {
+    /// 发送给模型的用户消息，前后文本超出上限时保留靠近光标的部分
+    pub fn user_message(&self) -> String {
+        let prefix_start = self.prefix.chars().count().saturating_sub(MAX_PREFIX_CHARS);
+        let prefix: String = self.prefix.chars().skip(prefix_start).collect();
+        let suffix: String = self.suffix.chars().take(MAX_SUFFIX_CHARS).collect();
+        let mut message = String::new();
+        if let Some(filetype) = &self.filetype {
+            message.push_str(&format!("Language: {}\n\n", filetype));
+        }
+        message.push_str(&prefix);
+        message.push_str(CURSOR_MARKER);
+        message.push_str(&suffix);
+        message
+    }
+
+    /// 流式 chat completions 请求的 JSON
+    pub fn request_body(&self, config: &AiConfig) -> Value {
+        let messages = [ChatMessage::system(SYSTEM_PROMPT), ChatMessage::user(self.user_message())];
+        chat::request_body(config, &messages, config.max_tokens, 0.2)
+    }
+}
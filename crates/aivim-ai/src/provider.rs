@@ -0,0 +1,214 @@
+//! AI 服务提供方
+//!
+//! 不同的服务使用不同的请求格式和流格式，由 `Provider` 描述：
+//! - openai: OpenAI Chat Completions（`Authorization: Bearer` 密钥）
+//! - anthropic: Anthropic Messages API（`x-api-key` 密钥，系统提示放在 `system` 字段）
+//! - ollama: 本地模型服务，使用 Ollama、llama.cpp 等提供的 OpenAI 兼容接口，不需要密钥
+
+use serde_json::{json, Value};
+
+use crate::chat::{ChatMessage, ChatRole};
+use crate::stream::{self, StreamEvent};
+
+/// Anthropic Messages API 的版本
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// 一种 AI 服务的请求和响应格式
+pub trait Provider: Send + Sync {
+    /// 默认的服务地址
+    fn default_endpoint(&self) -> &'static str;
+    fn default_model(&self) -> &'static str;
+    /// 默认保存 API 密钥的环境变量，不需要密钥时为空
+    fn default_api_key_env(&self) -> &'static str;
+    /// 默认的请求超时时间（秒）
+    fn default_timeout(&self) -> u64 {
+        10
+    }
+    /// 除 `Content-Type` 以外的请求头
+    fn headers(&self, api_key: Option<&str>) -> Vec<String>;
+    /// 流式请求的 JSON
+    fn request_body(&self, model: &str, messages: &[ChatMessage], max_tokens: usize, temperature: f64) -> Value;
+    /// 解析一行响应，不是数据行或没有文本时返回 None
+    fn parse_line(&self, line: &str) -> Option<StreamEvent>;
+}
+
+/// 可以选择的服务
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ProviderKind {
+    #[default]
+    OpenAi,
+    Anthropic,
+    Ollama,
+}
+
+impl ProviderKind {
+    pub const ALL: [ProviderKind; 3] = [ProviderKind::OpenAi, ProviderKind::Anthropic, ProviderKind::Ollama];
+
+    /// 配置文件和 `:set aiprovider` 中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProviderKind::OpenAi => "openai",
+            ProviderKind::Anthropic => "anthropic",
+            ProviderKind::Ollama => "ollama",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn provider(&self) -> &'static dyn Provider {
+        match self {
+            ProviderKind::OpenAi => &OpenAi,
+            ProviderKind::Anthropic => &Anthropic,
+            ProviderKind::Ollama => &Ollama,
+        }
+    }
+}
+
+/// OpenAI 格式的消息列表，系统提示是 `system` 角色的消息
+fn openai_request_body(model: &str, messages: &[ChatMessage], max_tokens: usize, temperature: f64) -> Value {
+    let messages: Vec<Value> = messages
+        .iter()
+        .map(|message| json!({ "role": message.role.name(), "content": message.content }))
+        .collect();
+    json!({
+        "model": model,
+        "stream": true,
+        "max_tokens": max_tokens,
+        "temperature": temperature,
+        "messages": messages,
+    })
+}
+
+/// OpenAI Chat Completions
+#[derive(Debug, Clone, Copy)]
+pub struct OpenAi;
+
+impl Provider for OpenAi {
+    fn default_endpoint(&self) -> &'static str {
+        "https://api.openai.com/v1/chat/completions"
+    }
+
+    fn default_model(&self) -> &'static str {
+        "gpt-4o-mini"
+    }
+
+    fn default_api_key_env(&self) -> &'static str {
+        "OPENAI_API_KEY"
+    }
+
+    fn headers(&self, api_key: Option<&str>) -> Vec<String> {
+        api_key.map(|key| format!("Authorization: Bearer {}", key)).into_iter().collect()
+    }
+
+    fn request_body(&self, model: &str, messages: &[ChatMessage], max_tokens: usize, temperature: f64) -> Value {
+        openai_request_body(model, messages, max_tokens, temperature)
+    }
+
+    fn parse_line(&self, line: &str) -> Option<StreamEvent> {
+        stream::parse_line(line)
+    }
+}
+
+/// Anthropic Messages API
+#[derive(Debug, Clone, Copy)]
+pub struct Anthropic;
+
+impl Provider for Anthropic {
+    fn default_endpoint(&self) -> &'static str {
+        "https://api.anthropic.com/v1/messages"
+    }
+
+    fn default_model(&self) -> &'static str {
+        "claude-3-5-haiku-latest"
+    }
+
+    fn default_api_key_env(&self) -> &'static str {
+        "ANTHROPIC_API_KEY"
+    }
+
+    fn headers(&self, api_key: Option<&str>) -> Vec<String> {
+        let mut headers = vec![format!("anthropic-version: {}", ANTHROPIC_VERSION)];
+        headers.extend(api_key.map(|key| format!("x-api-key: {}", key)));
+        headers
+    }
+
+    fn request_body(&self, model: &str, messages: &[ChatMessage], max_tokens: usize, temperature: f64) -> Value {
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|message| message.role == ChatRole::System)
+            .map(|message| message.content.as_str())
+            .collect();
+        let messages: Vec<Value> = messages
+            .iter()
+            .filter(|message| message.role != ChatRole::System)
+            .map(|message| json!({ "role": message.role.name(), "content": message.content }))
+            .collect();
+        let mut body = json!({
+            "model": model,
+            "stream": true,
+            "max_tokens": max_tokens,
+            "temperature": temperature,
+            "messages": messages,
+        });
+        if !system.is_empty() {
+            body["system"] = Value::String(system.join("\n\n"));
+        }
+        body
+    }
+
+    /// 文本在 `content_block_delta` 事件的 `delta.text` 中，`message_stop` 表示结束
+    fn parse_line(&self, line: &str) -> Option<StreamEvent> {
+        let data = line.strip_prefix("data:")?.trim();
+        let value: Value = serde_json::from_str(data).ok()?;
+        if let Some(message) = stream::error_message(&value) {
+            return Some(StreamEvent::Error(message));
+        }
+        match value.get("type")?.as_str()? {
+            "content_block_delta" => value
+                .get("delta")
+                .and_then(|delta| delta.get("text"))
+                .and_then(Value::as_str)
+                .filter(|text| !text.is_empty())
+                .map(|text| StreamEvent::Delta(text.to_string())),
+            "message_stop" => Some(StreamEvent::Done),
+            _ => None,
+        }
+    }
+}
+
+/// 本地模型服务的 OpenAI 兼容接口（Ollama、llama.cpp server 等）
+#[derive(Debug, Clone, Copy)]
+pub struct Ollama;
+
+impl Provider for Ollama {
+    fn default_endpoint(&self) -> &'static str {
+        "http://localhost:11434/v1/chat/completions"
+    }
+
+    fn default_model(&self) -> &'static str {
+        "qwen2.5-coder"
+    }
+
+    fn default_api_key_env(&self) -> &'static str {
+        ""
+    }
+
+    /// 本地模型加载和生成都较慢
+    fn default_timeout(&self) -> u64 {
+        60
+    }
+
+    fn headers(&self, api_key: Option<&str>) -> Vec<String> {
+        OpenAi.headers(api_key)
+    }
+
+    fn request_body(&self, model: &str, messages: &[ChatMessage], max_tokens: usize, temperature: f64) -> Value {
+        openai_request_body(model, messages, max_tokens, temperature)
+    }
+
+    fn parse_line(&self, line: &str) -> Option<StreamEvent> {
+        stream::parse_line(line)
+    }
+}
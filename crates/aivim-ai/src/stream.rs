@@ -0,0 +1,58 @@
+//! SSE 流解析
+//!
+//! 流式接口逐行返回 `data: {json}`，每个 JSON 的 `choices[0].delta.content` 是新生成的文本，
+//! 最后以 `data: [DONE]` 结束。空行、注释（`:` 开头）和其他字段被忽略
+
+use serde_json::Value;
+
+/// 一行 SSE 数据对应的事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// 新生成的文本
+    Delta(String),
+    /// 生成结束
+    Done,
+    /// 服务返回的错误
+    Error(String),
+}
+
+/// 解析一行 SSE 数据，不是数据行或没有文本时返回 None
+pub fn parse_line(line: &str) -> Option<StreamEvent> {
+    let data = line.strip_prefix("data:")?.trim();
+    if data == "[DONE]" {
+        return Some(StreamEvent::Done);
+    }
+    let value: Value = serde_json::from_str(data).ok()?;
+    if let Some(message) = error_message(&value) {
+        return Some(StreamEvent::Error(message));
+    }
+    let choice = value.get("choices")?.get(0)?;
+    let content = choice
+        .get("delta")
+        .and_then(|delta| delta.get("content"))
+        .and_then(Value::as_str)
+        .filter(|content| !content.is_empty())
+        .map(|content| StreamEvent::Delta(content.to_string()));
+    // 只有 finish_reason 没有文本的最后一个块也表示结束
+    content.or_else(|| {
+        choice
+            .get("finish_reason")
+            .is_some_and(|reason| !reason.is_null())
+            .then_some(StreamEvent::Done)
+    })
+}
+
+/// 是否是 SSE 格式的行：数据、事件名等字段或注释（空行也属于 SSE）
+pub fn is_sse_line(line: &str) -> bool {
+    line.is_empty() || ["data:", "event:", "id:", "retry:", ":"].iter().any(|prefix| line.starts_with(prefix))
+}
+
+/// 错误响应（`{"error": {"message": ...}}` 或 `{"error": "..."}`）中的消息
+pub fn error_message(value: &Value) -> Option<String> {
+    let error = value.get("error")?;
+    error
+        .get("message")
+        .and_then(Value::as_str)
+        .or_else(|| error.as_str())
+        .map(str::to_string)
+}
@@ -0,0 +1,102 @@
+//! 流式请求客户端测试
+//!
+//! 对应源文件: src/client.rs
+//!
+//! 用 `file://` 地址让 curl 读取事先写好的响应，不访问网络；系统没有 curl 时跳过
+
+use aivim_ai::{AiConfig, AiEvent, AiStream, CompletionContext, ProviderKind};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+fn has_curl() -> bool {
+    Command::new("curl").arg("--version").output().is_ok()
+}
+
+/// 把响应写入文件，返回读取它的设置
+fn config_for(name: &str, response: &str) -> AiConfig {
+    let path = PathBuf::from(format!("/tmp/aivim_ai_client_{}.txt", name));
+    fs::write(&path, response).unwrap();
+    AiConfig {
+        endpoint: format!("file://{}", path.display()),
+        api_key_env: "AIVIM_TEST_UNSET_KEY".to_string(),
+        ..AiConfig::default()
+    }
+}
+
+/// 收集事件直到请求结束
+fn collect(config: &AiConfig) -> Vec<AiEvent> {
+    let body = CompletionContext::default().request_body(config);
+    let mut stream = AiStream::start(config, &body, || {}).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut events = Vec::new();
+    while !stream.is_finished() && Instant::now() < deadline {
+        events.extend(stream.poll());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    events
+}
+
+#[test]
+fn test_stream_deltas() {
+    if !has_curl() {
+        return;
+    }
+    let config = config_for(
+        "deltas",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"hello\"}}]}\n\n\
+         data: {\"choices\":[{\"delta\":{\"content\":\" world\"}}]}\n\n\
+         data: [DONE]\n",
+    );
+    assert_eq!(
+        collect(&config),
+        vec![
+            AiEvent::Delta("hello".to_string()),
+            AiEvent::Delta(" world".to_string()),
+            AiEvent::Done,
+        ]
+    );
+}
+
+#[test]
+fn test_error_response() {
+    if !has_curl() {
+        return;
+    }
+    let config = config_for("error", "{\"error\":{\"message\":\"Invalid API key\"}}\n");
+    assert_eq!(collect(&config), vec![AiEvent::Error("Invalid API key".to_string())]);
+}
+
+#[test]
+fn test_connection_error() {
+    if !has_curl() {
+        return;
+    }
+    let config = AiConfig {
+        endpoint: "file:///tmp/aivim_ai_client_missing/none.txt".to_string(),
+        ..AiConfig::default()
+    };
+    let events = collect(&config);
+    assert!(matches!(events.as_slice(), [AiEvent::Error(_)]), "{:?}", events);
+}
+
+#[test]
+fn test_anthropic_stream() {
+    if !has_curl() {
+        return;
+    }
+    let config = AiConfig {
+        provider: ProviderKind::Anthropic,
+        ..config_for(
+            "anthropic",
+            "event: message_start\n\
+             data: {\"type\":\"message_start\",\"message\":{}}\n\n\
+             event: content_block_delta\n\
+             data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"hi\"}}\n\n\
+             event: message_stop\n\
+             data: {\"type\":\"message_stop\"}\n",
+        )
+    };
+    assert_eq!(collect(&config), vec![AiEvent::Delta("hi".to_string()), AiEvent::Done]);
+}
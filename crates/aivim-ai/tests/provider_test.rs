@@ -0,0 +1,142 @@
+//! AI 服务提供方和 config.toml 测试
+//!
+//! 对应源文件: src/provider.rs, src/config.rs
+
+use aivim_ai::client::curl_config;
+use aivim_ai::provider::ANTHROPIC_VERSION;
+use aivim_ai::{AiConfig, AiSettings, ChatMessage, ProviderKind, StreamEvent};
+use serde_json::json;
+
+fn messages() -> [ChatMessage; 3] {
+    [ChatMessage::system("be brief"), ChatMessage::user("hi"), ChatMessage::assistant("hello")]
+}
+
+// ==================== 服务 ====================
+
+#[test]
+fn test_provider_names() {
+    for kind in ProviderKind::ALL {
+        assert_eq!(ProviderKind::parse(kind.name()), Some(kind));
+    }
+    assert_eq!(ProviderKind::parse("gemini"), None);
+    assert_eq!(ProviderKind::default(), ProviderKind::OpenAi);
+}
+
+#[test]
+fn test_anthropic_request_body() {
+    let provider = ProviderKind::Anthropic.provider();
+    let body = provider.request_body("claude", &messages(), 100, 0.5);
+    assert_eq!(body["model"], "claude");
+    assert_eq!(body["max_tokens"], 100);
+    assert_eq!(body["stream"], true);
+    // 系统提示放在 system 字段，不在消息列表中
+    assert_eq!(body["system"], "be brief");
+    assert_eq!(
+        body["messages"],
+        json!([{ "role": "user", "content": "hi" }, { "role": "assistant", "content": "hello" }])
+    );
+}
+
+#[test]
+fn test_openai_compatible_request_body() {
+    for kind in [ProviderKind::OpenAi, ProviderKind::Ollama] {
+        let body = kind.provider().request_body("m", &messages(), 10, 0.0);
+        assert_eq!(body["messages"][0], json!({ "role": "system", "content": "be brief" }));
+        assert!(body.get("system").is_none());
+    }
+}
+
+#[test]
+fn test_anthropic_parse_line() {
+    let provider = ProviderKind::Anthropic.provider();
+    assert_eq!(
+        provider.parse_line(r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#),
+        Some(StreamEvent::Delta("Hi".to_string()))
+    );
+    assert_eq!(provider.parse_line(r#"data: {"type":"message_stop"}"#), Some(StreamEvent::Done));
+    assert_eq!(
+        provider.parse_line(r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#),
+        Some(StreamEvent::Error("Overloaded".to_string()))
+    );
+    assert_eq!(provider.parse_line("event: content_block_delta"), None);
+    assert_eq!(provider.parse_line(r#"data: {"type":"ping"}"#), None);
+}
+
+#[test]
+fn test_provider_headers() {
+    let config = AiConfig {
+        api_key_env: "AIVIM_TEST_UNSET_KEY".to_string(),
+        ..AiConfig::for_provider(ProviderKind::Anthropic)
+    };
+    let curl = curl_config(&config, &json!({}));
+    assert!(curl.contains(&format!("header = \"anthropic-version: {}\"", ANTHROPIC_VERSION)));
+    assert!(!curl.contains("x-api-key"));
+
+    assert_eq!(
+        ProviderKind::Anthropic.provider().headers(Some("k")),
+        vec![format!("anthropic-version: {}", ANTHROPIC_VERSION), "x-api-key: k".to_string()]
+    );
+    assert_eq!(ProviderKind::OpenAi.provider().headers(Some("k")), vec!["Authorization: Bearer k".to_string()]);
+    assert!(ProviderKind::Ollama.provider().headers(None).is_empty());
+}
+
+#[test]
+fn test_local_provider_needs_no_key() {
+    let config = AiConfig::for_provider(ProviderKind::Ollama);
+    assert!(config.endpoint.starts_with("http://localhost"));
+    assert_eq!(config.api_key_env, "");
+    assert_eq!(config.api_key(), None);
+    assert!(config.timeout > AiConfig::default().timeout);
+}
+
+// ==================== config.toml ====================
+
+#[test]
+fn test_settings_defaults() {
+    let settings = AiSettings::parse("").unwrap();
+    assert_eq!(settings.active(), AiConfig::default());
+}
+
+#[test]
+fn test_settings_per_provider() {
+    let text = r#"
+[ai]
+provider = "ollama"
+
+[ai.ollama]
+endpoint = "http://localhost:8080/v1/chat/completions"
+model = "codellama"
+timeout = 120
+chat_max_tokens = 512
+
+[ai.anthropic]
+api_key_env = "MY_KEY"
+max_tokens = 16
+"#;
+    let settings = AiSettings::parse(text).unwrap();
+    assert_eq!(settings.provider, ProviderKind::Ollama);
+    let active = settings.active();
+    assert_eq!(active.provider, ProviderKind::Ollama);
+    assert_eq!(active.endpoint, "http://localhost:8080/v1/chat/completions");
+    assert_eq!(active.model, "codellama");
+    assert_eq!(active.timeout, 120);
+    assert_eq!(active.chat_max_tokens, 512);
+    assert_eq!(active.max_tokens, AiConfig::default().max_tokens);
+
+    // 其他服务的设置在切换时使用，省略的项为服务的默认值
+    let anthropic = settings.config(ProviderKind::Anthropic);
+    assert_eq!(anthropic.api_key_env, "MY_KEY");
+    assert_eq!(anthropic.max_tokens, 16);
+    assert_eq!(anthropic.endpoint, AiConfig::for_provider(ProviderKind::Anthropic).endpoint);
+    assert_eq!(settings.config(ProviderKind::OpenAi), AiConfig::default());
+}
+
+#[test]
+fn test_settings_errors() {
+    assert!(AiSettings::parse("[ai]\nprovider = \"gemini\"").unwrap_err().contains("gemini"));
+    let error = AiSettings::parse("[ai.openai]\nmodle = \"x\"").unwrap_err();
+    assert!(error.contains("line 2"), "{}", error);
+    assert!(AiSettings::parse("[ai.openai]\ntimeout = \"slow\"").is_err());
+    // 其他表留给编辑器的其他设置
+    assert!(AiSettings::parse("[editor]\nnumber = true").is_ok());
+}
@@ -0,0 +1,110 @@
+//! SSE 流解析和请求构造测试
+//!
+//! 对应源文件: src/stream.rs, src/prompt.rs, src/chat.rs, src/client.rs (curl 配置)
+
+use aivim_ai::chat;
+use aivim_ai::client::curl_config;
+use aivim_ai::prompt::{CURSOR_MARKER, MAX_PREFIX_CHARS};
+use aivim_ai::stream::parse_line;
+use aivim_ai::{AiConfig, ChatMessage, CompletionContext, StreamEvent};
+
+// ==================== SSE 解析 ====================
+
+#[test]
+fn test_parse_delta() {
+    let line = r#"data: {"choices":[{"index":0,"delta":{"content":"fn main"}}]}"#;
+    assert_eq!(parse_line(line), Some(StreamEvent::Delta("fn main".to_string())));
+}
+
+#[test]
+fn test_parse_done() {
+    assert_eq!(parse_line("data: [DONE]"), Some(StreamEvent::Done));
+    let finish = r#"data: {"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#;
+    assert_eq!(parse_line(finish), Some(StreamEvent::Done));
+}
+
+#[test]
+fn test_parse_ignores_other_lines() {
+    assert_eq!(parse_line(""), None);
+    assert_eq!(parse_line(": keep-alive"), None);
+    assert_eq!(parse_line("event: message"), None);
+    // 第一个块通常只有 role
+    let role = r#"data: {"choices":[{"index":0,"delta":{"role":"assistant"},"finish_reason":null}]}"#;
+    assert_eq!(parse_line(role), None);
+}
+
+#[test]
+fn test_parse_error() {
+    let line = r#"data: {"error":{"message":"rate limited","type":"rate_limit"}}"#;
+    assert_eq!(parse_line(line), Some(StreamEvent::Error("rate limited".to_string())));
+}
+
+// ==================== 请求 ====================
+
+#[test]
+fn test_user_message_marks_cursor() {
+    let context = CompletionContext {
+        prefix: "let x = ".to_string(),
+        suffix: ";\n".to_string(),
+        filetype: Some("rust".to_string()),
+    };
+    let message = context.user_message();
+    assert!(message.starts_with("Language: rust\n\n"));
+    assert!(message.ends_with(&format!("let x = {};\n", CURSOR_MARKER)));
+}
+
+#[test]
+fn test_user_message_keeps_text_near_cursor() {
+    let context = CompletionContext {
+        prefix: format!("{}{}", "a".repeat(10), "b".repeat(MAX_PREFIX_CHARS)),
+        suffix: String::new(),
+        filetype: None,
+    };
+    let message = context.user_message();
+    assert!(!message.contains('a'));
+    assert_eq!(message.chars().filter(|c| *c == 'b').count(), MAX_PREFIX_CHARS);
+}
+
+#[test]
+fn test_request_body() {
+    let config = AiConfig {
+        model: "local-model".to_string(),
+        max_tokens: 32,
+        ..AiConfig::default()
+    };
+    let body = CompletionContext::default().request_body(&config);
+    assert_eq!(body["model"], "local-model");
+    assert_eq!(body["stream"], true);
+    assert_eq!(body["max_tokens"], 32);
+    assert_eq!(body["messages"][0]["role"], "system");
+    assert_eq!(body["messages"][1]["content"], CURSOR_MARKER);
+}
+
+#[test]
+fn test_chat_request_body() {
+    let messages = [
+        ChatMessage::system("be brief"),
+        ChatMessage::user("hi"),
+        ChatMessage::assistant("hello"),
+        ChatMessage::user("bye"),
+    ];
+    let body = chat::request_body(&AiConfig::default(), &messages, 1000, 0.7);
+    assert_eq!(body["max_tokens"], 1000);
+    let roles: Vec<_> = (0..4).map(|i| body["messages"][i]["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, ["system", "user", "assistant", "user"]);
+    assert_eq!(body["messages"][3]["content"], "bye");
+}
+
+#[test]
+fn test_curl_config_quotes_body() {
+    let config = AiConfig {
+        endpoint: "http://localhost:8080/v1/chat/completions".to_string(),
+        api_key_env: "AIVIM_TEST_UNSET_KEY".to_string(),
+        ..AiConfig::default()
+    };
+    let body = serde_json::json!({ "text": "say \"hi\"\\n" });
+    let text = curl_config(&config, &body);
+    assert!(text.contains("url = \"http://localhost:8080/v1/chat/completions\"\n"));
+    assert!(!text.contains("Authorization"));
+    assert!(text.contains(r#"data-binary = "{\"text\":\"say \\\"hi\\\"\\\\n\"}""#));
+}
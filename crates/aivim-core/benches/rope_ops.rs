@@ -0,0 +1,72 @@
+//! 大文件中单行编辑操作的基准测试
+//!
+//! 这些操作只应访问光标附近的文本，耗时不随文件大小增长：
+//! `cargo bench -p aivim-core --bench rope_ops`
+
+use aivim_core::motion::Motion;
+use aivim_core::search::{SearchDirection, SearchState};
+use aivim_core::text_object::TextObject;
+use aivim_core::{Cursor, Editor};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// 文件的行数
+const LINES: usize = 100_000;
+
+fn large_editor() -> Editor {
+    let mut editor = Editor::new();
+    let text: String = (0..LINES).map(|i| format!("fn item_{}() {{ let value = {}; }}\n", i, i)).collect();
+    editor.current_buffer_mut().insert(0, &text);
+    *editor.cursor_mut() = Cursor::new(LINES / 2, 3);
+    editor
+}
+
+/// 修改操作之后撤销，使每次迭代都从相同的文本开始（撤销只交换 rope，不复制文本）
+fn bench_operators(c: &mut Criterion) {
+    let mut editor = large_editor();
+    c.bench_function("dw", |b| {
+        b.iter(|| {
+            black_box(editor.delete_to_motion(Motion::WordForward));
+            editor.undo();
+        })
+    });
+    c.bench_function("yw", |b| b.iter(|| editor.yank_to_motion(Motion::WordForward)));
+    c.bench_function("diw", |b| {
+        b.iter(|| {
+            black_box(editor.delete_text_object(TextObject::InnerWord));
+            editor.undo();
+        })
+    });
+    c.bench_function("substitute current line", |b| {
+        b.iter(|| {
+            black_box(editor.execute_command("s/value/v")).ok();
+            editor.undo();
+        })
+    });
+}
+
+fn bench_search(c: &mut Criterion) {
+    let editor = large_editor();
+    c.bench_function("search all matches", |b| {
+        b.iter(|| {
+            let mut search = SearchState::new();
+            search.set_pattern("item_9999", SearchDirection::Forward, editor.current_buffer());
+            black_box(search.matches.len())
+        })
+    });
+
+    // 修改之后的 n 只重新查找修改过的行
+    let mut editor = large_editor();
+    editor.start_search(SearchDirection::Forward, "value");
+    let at = editor.current_buffer().line_to_char(LINES / 2);
+    c.bench_function("n after an edit", |b| {
+        b.iter(|| {
+            editor.current_buffer_mut().insert(at, "value ");
+            editor.search_next();
+            editor.current_buffer_mut().remove(at, 6);
+            black_box(editor.search_count())
+        })
+    });
+}
+
+criterion_group!(benches, bench_operators, bench_search);
+criterion_main!(benches);
@@ -0,0 +1,113 @@
+//! AI 编辑模块 (:AiEdit)
+//!
+//! `:{range}AiEdit {instruction}` 把范围内的行（默认为当前行）和指令发给模型，请求在后台进行：
+//! - 回复结束后在下方的分割窗口中显示修改的 diff 预览，并在命令行请求确认
+//! - 确认后用回复替换范围内的行，作为一次修改（一次 `u` 即可撤销）；取消时丢弃回复
+//! - 等待期间缓冲区被切换或范围内的行被修改时不应用回复
+//! - 回复被代码块包含时去掉围栏（```）
+
+use aivim_ai::chat::{self, ChatMessage};
+use aivim_ai::{AiConfig, AiEvent, AiStream};
+use serde_json::Value;
+
+use crate::buffer::BufferId;
+use crate::range::LineRange;
+
+const SYSTEM_PROMPT: &str = "You are a code editing engine inside a text editor. \
+Rewrite the text the user sends according to the instruction. \
+Reply with only the complete replacement text: no explanations and no markdown fences. \
+Keep the indentation style of the original text.";
+
+/// 编辑请求的 JSON
+pub fn request_body(config: &AiConfig, instruction: &str, text: &str, filetype: Option<&str>) -> Value {
+    let message = format!(
+        "Instruction: {}\n\n```{}\n{}\n```",
+        instruction,
+        filetype.unwrap_or(""),
+        text.trim_end_matches('\n')
+    );
+    let messages = [ChatMessage::system(SYSTEM_PROMPT), ChatMessage::user(message)];
+    chat::request_body(config, &messages, config.chat_max_tokens, 0.2)
+}
+
+/// 去掉回复外层的代码块围栏，结果以换行符结尾（与整行替换的文本一致）
+pub fn strip_code_fence(reply: &str) -> String {
+    let trimmed = reply.trim_matches('\n');
+    let text = match trimmed.strip_prefix("```") {
+        // 第一行是围栏和语言名
+        Some(rest) => {
+            let body = rest.split_once('\n').map_or("", |(_, body)| body);
+            body.trim_end().strip_suffix("```").unwrap_or(body).trim_end_matches('\n')
+        }
+        None => trimmed,
+    };
+    if text.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", text)
+    }
+}
+
+/// 正在进行或等待确认的编辑
+#[derive(Debug)]
+pub struct AiEdit {
+    /// 要修改的缓冲区和行
+    pub buffer: BufferId,
+    pub range: LineRange,
+    /// 发送请求时范围内的文本
+    pub original: String,
+    reply: String,
+    stream: Option<AiStream>,
+    /// 回复结束后替换范围内的行的文本
+    replacement: Option<String>,
+    /// 显示 diff 预览的缓冲区
+    pub preview: Option<BufferId>,
+}
+
+impl AiEdit {
+    pub fn new(buffer: BufferId, range: LineRange, original: String, stream: AiStream) -> Self {
+        Self {
+            buffer,
+            range,
+            original,
+            reply: String::new(),
+            stream: Some(stream),
+            replacement: None,
+            preview: None,
+        }
+    }
+
+    /// 是否正在接收回复
+    pub fn is_responding(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    pub fn set_replacement(&mut self, replacement: String) {
+        self.replacement = Some(replacement);
+    }
+
+    pub fn take_replacement(&mut self) -> Option<String> {
+        self.replacement.take()
+    }
+
+    /// 取出回复，结束时返回 `Some(Ok(替换文本))` 或 `Some(Err(错误))`
+    pub fn poll(&mut self) -> Option<Result<String, String>> {
+        let stream = self.stream.as_mut()?;
+        let mut error = None;
+        for event in stream.poll() {
+            match event {
+                AiEvent::Delta(text) => self.reply.push_str(&text),
+                AiEvent::Done => {}
+                AiEvent::Error(message) => error = Some(message),
+            }
+        }
+        if !stream.is_finished() {
+            return None;
+        }
+        self.stream = None;
+        Some(match error {
+            Some(message) => Err(message),
+            None => Ok(strip_code_fence(&self.reply)),
+        })
+    }
+}
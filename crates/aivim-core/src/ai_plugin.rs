@@ -0,0 +1,550 @@
+//! AI 插件（内置插件，见 plugin 模块）
+//!
+//! - Insert 模式的补全建议 (:set aicomplete)：光标停留 aidelay 毫秒后请求，Tab 接受、Esc 丢弃（见 suggestion 模块）
+//! - `:AiChat` 对话（见 chat 模块）、`:AiEdit` 按指令修改（见 ai_edit 模块）
+//! - `:AiExplain` 在浮动窗口中解释代码，Normal 模式的 `K` 映射为 `:AiExplain`，Esc 关闭（见 explain 模块）
+//! - `:AiCommitMsg` 生成提交信息（见 commit_msg 模块）
+//!
+//! 请求都在后台进行，回复由事件循环定期调用的 `poll` 取出。
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use aivim_ai::{AiStream, CompletionContext};
+
+use crate::ai_edit::{self, AiEdit};
+use crate::buffer::{Buffer, BufferId};
+use crate::chat::{self, ChatContext, ChatSession};
+use crate::commit_msg::{self, CommitMessage};
+use crate::cursor::Cursor;
+use crate::editor::{ConfirmAnswer, Editor};
+use crate::ex_command::{spec, CommandSpec, ExCommand};
+use crate::explain::{self, Explanation};
+use crate::mapping::{MapKey, MapMode};
+use crate::mode::Mode;
+use crate::plugin::{Plugin, PluginKeymap};
+use crate::range::LineRange;
+use crate::suggestion::{SuggestionAnchor, Suggestions};
+use crate::window::{SplitDirection, WindowId};
+use crate::{tr, with_save_state};
+
+/// 请求 AI 补全时发送光标之前的最多行数
+const SUGGESTION_CONTEXT_LINES: usize = 200;
+
+/// AI 补全、对话、编辑、解释和提交信息
+#[derive(Default)]
+pub struct AiPlugin {
+    /// Insert 模式的 AI 补全建议
+    suggestions: Suggestions,
+    /// AI 对话 (:AiChat)
+    chat: Option<ChatSession>,
+    /// AI 编辑 (:AiEdit)，从发送请求到确认或取消
+    edit: Option<AiEdit>,
+    /// 浮动窗口中的 AI 解释 (:AiExplain / K)
+    explanation: Option<Explanation>,
+    /// 正在生成的提交信息 (:AiCommitMsg)
+    commit_message: Option<CommitMessage>,
+}
+
+impl AiPlugin {
+    /// 光标处显示的 AI 补全建议（只在 Insert 模式下显示）
+    pub fn suggestion(&self, editor: &Editor) -> Option<&str> {
+        if editor.mode() != Mode::Insert {
+            return None;
+        }
+        self.suggestions.text(&suggestion_anchor(editor))
+    }
+
+    /// 不经过模型直接在光标处显示建议
+    pub fn show_suggestion(&mut self, editor: &Editor, text: &str) {
+        self.suggestions.show(suggestion_anchor(editor), text);
+    }
+
+    /// 是否正在等待模型返回建议
+    pub fn suggestion_pending(&self) -> bool {
+        self.suggestions.is_requesting()
+    }
+
+    /// 进行中的对话
+    pub fn chat(&self) -> Option<&ChatSession> {
+        self.chat.as_ref()
+    }
+
+    /// 对话缓冲区，没有进行中的对话时为 None
+    pub fn chat_buffer(&self) -> Option<BufferId> {
+        self.chat.as_ref().map(ChatSession::buffer)
+    }
+
+    /// 正在进行或等待确认的 AI 编辑
+    pub fn edit(&self) -> Option<&AiEdit> {
+        self.edit.as_ref()
+    }
+
+    /// 浮动窗口中的 AI 解释，只在请求时的缓冲区中显示
+    pub fn explanation(&self, editor: &Editor) -> Option<&Explanation> {
+        self.explanation
+            .as_ref()
+            .filter(|explanation| explanation.buffer() == editor.current_buffer_id())
+    }
+
+    /// 是否正在生成提交信息
+    pub fn commit_message_pending(&self) -> bool {
+        self.commit_message.is_some()
+    }
+
+    /// 把光标处的建议插入缓冲区 (Insert 模式下 Tab)，没有建议时返回 false
+    fn accept_suggestion(&mut self, editor: &mut Editor) -> bool {
+        if self.suggestion(editor).is_none() {
+            return false;
+        }
+        let Some(text) = self.suggestions.accept(&suggestion_anchor(editor)) else {
+            return false;
+        };
+        editor.insert_text_at_cursor(&text);
+        true
+    }
+
+    /// 丢弃光标处的建议 (Insert 模式下 Esc)，没有建议时返回 false
+    fn dismiss_suggestion(&mut self, editor: &Editor) -> bool {
+        if self.suggestion(editor).is_none() {
+            return false;
+        }
+        self.suggestions.dismiss(&suggestion_anchor(editor))
+    }
+
+    /// 光标停留 aidelay 毫秒后请求建议，并取出已返回的文本；返回显示的建议是否变化
+    fn poll_suggestion(&mut self, editor: &mut Editor, now: Instant) -> bool {
+        let options = editor.options();
+        if !options.aicomplete
+            || editor.mode() != Mode::Insert
+            || editor.completion().is_some()
+            || editor.expression_prompt_active()
+            || options.paste
+        {
+            return self.suggestions.clear();
+        }
+        let anchor = suggestion_anchor(editor);
+        let delay = Duration::from_millis(options.aidelay as u64);
+        let (cleared, due) = self.suggestions.update(&anchor, now, delay);
+        if due {
+            self.request_suggestion(editor, anchor);
+        }
+        let (changed, error) = self.suggestions.poll();
+        if let Some(error) = error {
+            editor.set_message(tr!(editor.locale(), "AI completion failed: {}", error));
+        }
+        cleared || changed
+    }
+
+    /// 把光标前后的文本发给模型
+    fn request_suggestion(&mut self, editor: &mut Editor, anchor: SuggestionAnchor) {
+        let buffer = editor.current_buffer();
+        let line = editor.cursor().line.min(buffer.len_lines().saturating_sub(1));
+        let cursor = buffer.line_to_char(line) + editor.cursor().column.min(buffer.line_len(line));
+        let start = buffer.line_to_char(line.saturating_sub(SUGGESTION_CONTEXT_LINES));
+        let end = buffer.line_to_char((line + SUGGESTION_CONTEXT_LINES).min(buffer.len_lines()));
+        let context = CompletionContext {
+            prefix: buffer.slice(start..cursor).to_string(),
+            suffix: buffer.slice(cursor..end).to_string(),
+            filetype: buffer.filetype().map(str::to_string),
+        };
+        let body = context.request_body(editor.ai_config());
+        match start_stream(editor, &body) {
+            Ok(stream) => self.suggestions.start(anchor, Some(stream)),
+            Err(e) => {
+                self.suggestions.start(anchor, None);
+                editor.set_message(tr!(editor.locale(), "AI completion failed: {}", e));
+            }
+        }
+    }
+
+    /// :AiChat - 打开对话窗口或发送消息（见 chat 模块）
+    ///
+    /// `range` 附带范围内的行，`visible` 附带当前窗口中可见的行
+    fn ai_chat(
+        &mut self,
+        editor: &mut Editor,
+        range: Option<LineRange>,
+        visible: bool,
+        message: &str,
+    ) -> Result<(), String> {
+        let message = message.trim();
+        if message.is_empty() {
+            if range.is_some() || visible {
+                return Err(tr!(editor.locale(), "Argument required"));
+            }
+            let window = self.open_chat_window(editor);
+            editor.focus_window(window);
+            return Ok(());
+        }
+        if self.chat.as_ref().is_some_and(ChatSession::is_responding) {
+            return Err(tr!(editor.locale(), "AI is still responding"));
+        }
+
+        // 对话缓冲区本身不作为上下文
+        let range = if visible { Some(editor.visible_range()) } else { range };
+        let context = range
+            .filter(|_| Some(editor.current_buffer_id()) != self.chat_buffer())
+            .map(|range| {
+                let buffer = editor.current_buffer();
+                ChatContext {
+                    name: buffer.display_name().unwrap_or("[No Name]").to_string(),
+                    filetype: buffer.filetype().map(str::to_string),
+                    range,
+                    text: buffer.lines_text(range),
+                }
+            });
+
+        let window = editor.windows().current_id();
+        self.open_chat_window(editor);
+        editor.focus_window(window);
+
+        let content = match &context {
+            Some(context) => context.prompt(message),
+            None => message.to_string(),
+        };
+        let Some(session) = self.chat.as_mut() else {
+            return Ok(());
+        };
+        let body = session.push_user(content, editor.ai_config());
+        match start_stream(editor, &body) {
+            Ok(stream) => {
+                session.start(Some(stream));
+                let buffer = session.buffer();
+                editor.append_and_follow(buffer, &chat::format_user_message(message, context.as_ref()));
+                Ok(())
+            }
+            Err(e) => {
+                session.start(None);
+                Err(tr!(editor.locale(), "AI chat failed: {}", e))
+            }
+        }
+    }
+
+    /// 在右侧显示对话缓冲区的窗口，没有时分割当前窗口创建；不改变当前窗口
+    fn open_chat_window(&mut self, editor: &mut Editor) -> WindowId {
+        if let Some(id) = self.chat_buffer().and_then(|buffer| editor.window_showing(buffer)) {
+            return id;
+        }
+        let (window, buffer) = editor.split_window_showing(SplitDirection::Vertical, self.chat_buffer(), |id| {
+            let mut buffer = Buffer::scratch(id, chat::CHAT_BUFFER_NAME, "");
+            buffer.set_filetype("markdown");
+            buffer
+        });
+        if self.chat_buffer() != Some(buffer) {
+            self.chat = Some(ChatSession::new(buffer));
+        }
+        window
+    }
+
+    /// 取出对话的回复并追加到对话缓冲区，返回是否有新内容
+    ///
+    /// 对话缓冲区被删除时结束对话
+    fn poll_chat(&mut self, editor: &mut Editor) -> bool {
+        let Some(session) = self.chat.as_mut() else {
+            return false;
+        };
+        if editor.buffer(session.buffer()).is_none() {
+            self.chat = None;
+            return false;
+        }
+        let (text, error) = session.poll();
+        if let Some(error) = error {
+            editor.set_message(tr!(editor.locale(), "AI chat failed: {}", error));
+        }
+        if text.is_empty() {
+            return false;
+        }
+        editor.append_and_follow(session.buffer(), &text);
+        true
+    }
+
+    /// :AiEdit - 按指令修改范围内的行（默认为当前行），见 ai_edit 模块
+    fn ai_edit(&mut self, editor: &mut Editor, range: Option<LineRange>, instruction: &str) -> Result<(), String> {
+        let instruction = instruction.trim();
+        if instruction.is_empty() {
+            return Err(tr!(editor.locale(), "Argument required"));
+        }
+        if self.edit.is_some() {
+            return Err(tr!(editor.locale(), "AI is still responding"));
+        }
+        let range = range.unwrap_or_else(|| LineRange::single(editor.cursor().line));
+        let original = editor.range_text(range);
+        let filetype = editor.current_buffer().filetype().map(str::to_string);
+        let body = ai_edit::request_body(editor.ai_config(), instruction, &original, filetype.as_deref());
+        let stream = start_stream(editor, &body).map_err(|e| tr!(editor.locale(), "AI edit failed: {}", e))?;
+        self.edit = Some(AiEdit::new(editor.current_buffer_id(), range, original, stream));
+        editor.set_message(tr!(editor.locale(), "Waiting for AI edit of {} line(s)...", range.line_count()));
+        Ok(())
+    }
+
+    /// 取出 AI 编辑的回复，结束时显示 diff 预览并请求确认，返回是否需要重绘
+    fn poll_edit(&mut self, editor: &mut Editor) -> bool {
+        let Some(edit) = self.edit.as_mut() else {
+            return false;
+        };
+        let replacement = match edit.poll() {
+            None => return false,
+            Some(Err(error)) => {
+                self.edit = None;
+                editor.set_message(tr!(editor.locale(), "AI edit failed: {}", error));
+                return true;
+            }
+            Some(Ok(replacement)) => replacement,
+        };
+        let name = editor
+            .buffer(edit.buffer)
+            .and_then(Buffer::display_name)
+            .unwrap_or("[No Name]")
+            .to_string();
+        let diff = crate::diff::unified_diff(&name, &format!("{} (AI edit)", name), &edit.original, &replacement, 3);
+        let Some(diff) = diff.filter(|_| !replacement.is_empty()) else {
+            self.edit = None;
+            editor.set_message(tr!(editor.locale(), "AI suggested no changes"));
+            return true;
+        };
+        edit.set_replacement(replacement);
+
+        // 预览显示在下方的新窗口中，光标留在原来的窗口
+        let (_, preview) = editor.split_window_showing(SplitDirection::Horizontal, None, |id| {
+            let mut buffer = Buffer::scratch(id, &format!("[AiEdit] {}", name), &diff);
+            buffer.set_filetype("diff");
+            buffer
+        });
+        edit.preview = Some(preview);
+
+        let range = edit.range;
+        let prompt = tr!(editor.locale(), "Apply AI edit to lines {}-{}? (y/n)", range.start + 1, range.end + 1);
+        editor.request_confirmation::<AiPlugin>(prompt);
+        true
+    }
+
+    /// 结束 AI 编辑：关闭预览窗口并删除预览缓冲区，返回编辑和替换的文本
+    fn finish_edit(&mut self, editor: &mut Editor) -> Option<(AiEdit, String)> {
+        let mut edit = self.edit.take()?;
+        if let Some(preview) = edit.preview.filter(|id| editor.buffer(*id).is_some()) {
+            if let Some(window) = editor.window_showing(preview) {
+                let _ = editor.close_window(window);
+            }
+            let _ = editor.delete_buffer_force(preview);
+        }
+        let replacement = edit.take_replacement()?;
+        Some((edit, replacement))
+    }
+
+    /// :AiExplain / K - 在浮动窗口中解释范围内的行、光标所在的函数或光标下的单词（见 explain 模块）
+    fn ai_explain(&mut self, editor: &Editor, range: Option<LineRange>) -> Result<(), String> {
+        let buffer = editor.current_buffer();
+        let line = editor.cursor().line;
+        let lines: Vec<_> = buffer.text_lines().collect();
+        let lines: Vec<&str> = lines.iter().map(AsRef::as_ref).collect();
+        let (range, word) = match range {
+            Some(range) => (range, None),
+            None => match crate::symbols::function_range(&lines, line) {
+                Some(range) => (range, None),
+                None => {
+                    let word = lines.get(line).and_then(|text| explain::word_at(text, editor.cursor().column));
+                    let word = word.ok_or_else(|| tr!(editor.locale(), "Nothing to explain under the cursor"))?;
+                    (LineRange::single(line), Some(word))
+                }
+            },
+        };
+        let context = ChatContext {
+            name: buffer.display_name().unwrap_or("[No Name]").to_string(),
+            filetype: buffer.filetype().map(str::to_string),
+            range,
+            text: buffer.lines_text(range),
+        };
+        let title = word.clone().unwrap_or_else(|| context.label());
+
+        let body = explain::request_body(editor.ai_config(), &context, word.as_deref());
+        let stream = start_stream(editor, &body).map_err(|e| tr!(editor.locale(), "AI explain failed: {}", e))?;
+        // 替换之前的解释，之前的请求随之取消
+        self.explanation = Some(Explanation::new(editor.current_buffer_id(), title, stream));
+        Ok(())
+    }
+
+    /// 取出 AI 解释的回复，返回是否有新内容
+    fn poll_explanation(&mut self, editor: &mut Editor) -> bool {
+        let Some(explanation) = self.explanation.as_mut() else {
+            return false;
+        };
+        let (changed, error) = explanation.poll();
+        if let Some(error) = error {
+            self.explanation = None;
+            editor.set_message(tr!(editor.locale(), "AI explain failed: {}", error));
+            return true;
+        }
+        changed
+    }
+
+    /// :AiCommitMsg - 根据仓库的修改生成提交信息，插入 COMMIT_EDITMSG 缓冲区（见 commit_msg 模块）
+    fn ai_commit_msg(&mut self, editor: &mut Editor) -> Result<(), String> {
+        if self.commit_message.is_some() {
+            return Err(tr!(editor.locale(), "AI is still responding"));
+        }
+        // 在当前文件所在的目录中运行 git，没有文件名时在工作目录中运行
+        let dir = editor
+            .current_buffer()
+            .file_path()
+            .and_then(Path::parent)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        let locale = editor.locale();
+        let git_error = |e: io::Error| tr!(locale, "git failed: {}", e);
+        let mut diff = crate::git::diff(&dir, true).map_err(git_error)?;
+        if diff.trim().is_empty() {
+            diff = crate::git::diff(&dir, false).map_err(git_error)?;
+        }
+        if diff.trim().is_empty() {
+            return Err(tr!(locale, "No changes to commit"));
+        }
+
+        let is_commit_file = editor
+            .current_buffer()
+            .file_path()
+            .is_some_and(|path| path.file_name().is_some_and(|name| name == commit_msg::COMMIT_EDITMSG));
+        if !is_commit_file {
+            let path = crate::git::git_path(&dir, commit_msg::COMMIT_EDITMSG).map_err(git_error)?;
+            editor
+                .switch_to_file(&path)
+                .map_err(|e| tr!(locale, "Failed to open {}: {}", path.display(), e))?;
+        }
+
+        let body = commit_msg::request_body(editor.ai_config(), &diff);
+        let stream = start_stream(editor, &body).map_err(|e| tr!(locale, "AI commit message failed: {}", e))?;
+        self.commit_message = Some(CommitMessage::new(editor.current_buffer_id(), stream));
+        editor.set_message(tr!(locale, "Waiting for AI commit message..."));
+        Ok(())
+    }
+
+    /// 取出生成的提交信息，结束时插入缓冲区的开头，返回是否修改了缓冲区
+    fn poll_commit_message(&mut self, editor: &mut Editor) -> bool {
+        let Some(result) = self.commit_message.as_mut().and_then(CommitMessage::poll) else {
+            return false;
+        };
+        let Some(request) = self.commit_message.take() else {
+            return false;
+        };
+        let message = match result {
+            Ok(message) if !message.is_empty() => message,
+            Ok(_) => {
+                editor.set_message(tr!(editor.locale(), "AI returned an empty commit message"));
+                return false;
+            }
+            Err(error) => {
+                editor.set_message(tr!(editor.locale(), "AI commit message failed: {}", error));
+                return false;
+            }
+        };
+        // 与 :r !cmd 相同，等待期间切换了缓冲区时不修改其他缓冲区
+        if request.buffer != editor.current_buffer_id() {
+            editor.set_message(tr!(editor.locale(), "Buffer changed, AI commit message discarded"));
+            return false;
+        }
+        with_save_state!(editor, {
+            editor.current_buffer_mut().insert_lines(0, &message);
+            *editor.cursor_mut() = Cursor::at_origin();
+        });
+        editor.set_message(tr!(editor.locale(), "Inserted AI commit message"));
+        true
+    }
+}
+
+/// 建议所属的当前位置
+fn suggestion_anchor(editor: &Editor) -> SuggestionAnchor {
+    let cursor = editor.cursor();
+    SuggestionAnchor {
+        buffer: editor.current_buffer_id(),
+        line: cursor.line,
+        column: cursor.column,
+        line_text: editor.get_line_text(cursor.line).unwrap_or_default(),
+    }
+}
+
+/// 按当前的 AI 设置发送请求，回复到达时唤醒事件循环
+fn start_stream(editor: &Editor, body: &serde_json::Value) -> Result<AiStream, String> {
+    let waker = editor.waker().clone();
+    AiStream::start(editor.ai_config(), body, move || waker.wake()).map_err(|e| e.to_string())
+}
+
+impl Plugin for AiPlugin {
+    fn name(&self) -> &'static str {
+        "ai"
+    }
+
+    fn commands(&self) -> Vec<CommandSpec> {
+        vec![
+            spec("AiChat", 5, true, true),
+            spec("AiEdit", 6, false, true),
+            spec("AiExplain", 9, false, true),
+            spec("AiCommitMsg", 11, false, false),
+        ]
+    }
+
+    fn keymaps(&self) -> Vec<PluginKeymap> {
+        vec![PluginKeymap::new(MapMode::Normal, "K", ":AiExplain<CR>")]
+    }
+
+    fn execute(&mut self, editor: &mut Editor, command: &ExCommand) -> Result<(), String> {
+        match command.name {
+            "AiChat" => self.ai_chat(editor, command.range, command.bang, command.args),
+            "AiEdit" => self.ai_edit(editor, command.range, command.args),
+            "AiExplain" => self.ai_explain(editor, command.range),
+            "AiCommitMsg" => self.ai_commit_msg(editor),
+            _ => Ok(()),
+        }
+    }
+
+    fn poll(&mut self, editor: &mut Editor, now: Instant) -> bool {
+        let mut changed = self.poll_suggestion(editor, now);
+        changed |= self.poll_chat(editor);
+        changed |= self.poll_explanation(editor);
+        changed |= self.poll_edit(editor);
+        changed |= self.poll_commit_message(editor);
+        changed
+    }
+
+    fn on_option_set(&mut self, editor: &mut Editor) {
+        if !editor.options().aicomplete {
+            self.suggestions.clear();
+        }
+    }
+
+    fn on_key(&mut self, editor: &mut Editor, key: MapKey) -> bool {
+        match (editor.mode(), key) {
+            // Esc 关闭 AI 解释的浮动窗口并取消请求
+            (Mode::Normal, MapKey::Esc) => {
+                if self.explanation(editor).is_none() {
+                    return false;
+                }
+                self.explanation = None;
+                true
+            }
+            // 显示 AI 补全建议时 Esc 只丢弃建议，Tab 接受建议
+            (Mode::Insert, MapKey::Esc) => self.dismiss_suggestion(editor),
+            (Mode::Insert, MapKey::Tab) => self.accept_suggestion(editor),
+            _ => false,
+        }
+    }
+
+    /// 应用 AI 编辑的确认：无论是否确认都关闭预览
+    fn on_confirm(&mut self, editor: &mut Editor, answer: ConfirmAnswer) -> Result<(), String> {
+        let edit = self.finish_edit(editor);
+        if answer != ConfirmAnswer::Yes {
+            editor.set_message(tr!(editor.locale(), "Cancelled"));
+            return Ok(());
+        }
+        let Some((edit, replacement)) = edit else {
+            return Ok(());
+        };
+        // 等待期间切换了缓冲区或修改了这些行，替换会覆盖别的内容
+        if edit.buffer != editor.current_buffer_id() || editor.range_text(edit.range) != edit.original {
+            return Err(tr!(editor.locale(), "Buffer changed, AI edit discarded"));
+        }
+        editor.replace_lines(edit.range, &replacement);
+        editor.set_message(tr!(editor.locale(), "Applied AI edit to {} line(s)", edit.range.line_count()));
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+//! 自动命令 (:autocmd)
+//!
+//! 编辑器在下列事件发生时通知插件，再执行用户为事件注册的 Ex 命令：
+//! - `BufReadPost`：读入文件之后；`BufNewFile`：开始编辑不存在的文件
+//! - `BufWritePre` / `BufWritePost`：写入文件之前 / 之后
+//! - `InsertEnter` / `InsertLeave`：进入 / 离开 Insert（Replace）模式
+//! - `CursorHold`：Normal 模式下光标停留 `updatetime` 毫秒（默认 4000），每次停留只触发一次
+//! - `FocusGained`：终端重新获得焦点，或从 `:shell` / Ctrl+Z 回到编辑器
+//!
+//! 插件（见 plugin 模块）先于用户命令收到事件：内置的 Git 插件在写入之后重新比较差异标记和 blame、
+//! 获得焦点时重新读取 HEAD 和分支，LSP 插件在读入文件之后立即打开语言服务器的文档。
+//!
+//! 用户命令在配置文件 aivimrc 中注册即可在启动时生效：
+//!
+//! ```text
+//! " 保存前删除行尾空白
+//! autocmd BufWritePre *.rs,*.py StripWhitespace
+//! au InsertLeave * set nopaste
+//! ```
+//!
+//! - 事件名不区分大小写，可以用逗号分隔多个事件
+//! - 模式用逗号分隔多个 glob（`*` 匹配任意字符，`?` 匹配一个字符），不含 `/` 时匹配文件名，
+//!   否则匹配完整路径；没有文件名的缓冲区只匹配 `*`
+//! - `:autocmd [event] [pattern]` 列出注册的命令，`:autocmd! [event] [pattern]` 删除
+//! - 用户命令在事件的缓冲区中执行（不是当前缓冲区时临时切换到它）；命令中再触发的事件不执行自动命令
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// 自动命令的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    BufReadPost,
+    BufNewFile,
+    BufWritePre,
+    BufWritePost,
+    InsertEnter,
+    InsertLeave,
+    CursorHold,
+    FocusGained,
+}
+
+impl Event {
+    pub const ALL: [Event; 8] = [
+        Event::BufReadPost,
+        Event::BufNewFile,
+        Event::BufWritePre,
+        Event::BufWritePost,
+        Event::InsertEnter,
+        Event::InsertLeave,
+        Event::CursorHold,
+        Event::FocusGained,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::BufReadPost => "BufReadPost",
+            Event::BufNewFile => "BufNewFile",
+            Event::BufWritePre => "BufWritePre",
+            Event::BufWritePost => "BufWritePost",
+            Event::InsertEnter => "InsertEnter",
+            Event::InsertLeave => "InsertLeave",
+            Event::CursorHold => "CursorHold",
+            Event::FocusGained => "FocusGained",
+        }
+    }
+
+    /// 按名字（不区分大小写）查找事件，`BufRead` / `BufWrite` 是 `BufReadPost` / `BufWritePre` 的别名
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bufread" => Some(Event::BufReadPost),
+            "bufwrite" => Some(Event::BufWritePre),
+            name => Self::ALL.into_iter().find(|event| event.name().eq_ignore_ascii_case(name)),
+        }
+    }
+}
+
+/// 一条自动命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Autocmd {
+    pub event: Event,
+    pub pattern: String,
+    pub command: String,
+}
+
+impl Autocmd {
+    /// 文件 `path` 是否匹配模式，没有文件名时只有 `*` 匹配
+    pub fn matches(&self, path: Option<&Path>) -> bool {
+        self.pattern.split(',').any(|pattern| match path {
+            None => pattern == "*",
+            Some(path) if pattern.contains('/') => glob_match(pattern, &path.to_string_lossy()),
+            Some(path) => {
+                let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+                glob_match(pattern, &name)
+            }
+        })
+    }
+}
+
+/// 注册的自动命令，按注册顺序执行
+#[derive(Debug, Clone, Default)]
+pub struct Autocmds {
+    commands: Vec<Autocmd>,
+}
+
+impl Autocmds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, event: Event, pattern: &str, command: &str) {
+        self.commands.push(Autocmd { event, pattern: pattern.to_string(), command: command.to_string() });
+    }
+
+    /// 删除事件和模式相同的命令，None 表示任意事件或模式
+    pub fn remove(&mut self, event: Option<Event>, pattern: Option<&str>) {
+        self.commands.retain(|autocmd| {
+            !(event.is_none_or(|event| autocmd.event == event) && pattern.is_none_or(|pattern| autocmd.pattern == pattern))
+        });
+    }
+
+    /// 事件和模式相同的命令，None 表示任意事件或模式
+    pub fn list(&self, event: Option<Event>, pattern: Option<&str>) -> Vec<&Autocmd> {
+        self.commands
+            .iter()
+            .filter(|autocmd| event.is_none_or(|event| autocmd.event == event))
+            .filter(|autocmd| pattern.is_none_or(|pattern| autocmd.pattern == pattern))
+            .collect()
+    }
+
+    /// 文件 `path` 发生事件时要执行的命令
+    pub fn commands_for(&self, event: Event, path: Option<&Path>) -> Vec<String> {
+        self.commands
+            .iter()
+            .filter(|autocmd| autocmd.event == event && autocmd.matches(path))
+            .map(|autocmd| autocmd.command.clone())
+            .collect()
+    }
+}
+
+/// `:autocmd` 的参数，缺少的部分为 None
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutocmdArgs<'a> {
+    pub events: Option<Vec<Event>>,
+    pub pattern: Option<&'a str>,
+    pub command: Option<&'a str>,
+}
+
+/// 解析 `:autocmd` 的参数，未知的事件名作为错误返回
+pub fn parse_args(args: &str) -> Result<AutocmdArgs<'_>, String> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(AutocmdArgs::default());
+    }
+    let (events, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let events = events
+        .split(',')
+        .map(|name| Event::from_name(name).ok_or_else(|| name.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let rest = rest.trim_start();
+    if rest.is_empty() {
+        return Ok(AutocmdArgs { events: Some(events), ..AutocmdArgs::default() });
+    }
+    let (pattern, command) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let command = command.trim();
+    Ok(AutocmdArgs { events: Some(events), pattern: Some(pattern), command: (!command.is_empty()).then_some(command) })
+}
+
+/// 简单的 glob 匹配：`*` 匹配任意个字符，`?` 匹配一个字符
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 * 的位置和它匹配到的文本位置，失配时回到这里让 * 多匹配一个字符
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// CursorHold 的计时：光标位置（缓冲区, 行, 列）变化时重新计时，停留够时间之后只触发一次
+#[derive(Debug, Clone, Default)]
+pub struct HoldTimer {
+    position: Option<(usize, usize, usize)>,
+    since: Option<Instant>,
+    fired: bool,
+}
+
+impl HoldTimer {
+    /// 记录光标当前的位置，返回是否应该触发 CursorHold
+    pub fn update(&mut self, position: (usize, usize, usize), now: Instant, delay: Duration) -> bool {
+        if self.position != Some(position) {
+            *self = Self { position: Some(position), since: Some(now), fired: false };
+            return false;
+        }
+        let due = self.since.is_some_and(|since| now.saturating_duration_since(since) >= delay);
+        if self.fired || !due {
+            return false;
+        }
+        self.fired = true;
+        true
+    }
+
+    /// 离开 Normal 模式时停止计时，回来后重新开始
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
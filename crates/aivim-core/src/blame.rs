@@ -0,0 +1,136 @@
+//! Git blame 模块 (:GitBlame)
+//!
+//! 用 `git blame --porcelain --contents -` 按缓冲区当前的内容逐行查询最后修改它的提交，
+//! 在每行末尾以虚拟文本显示作者、日期和提交说明：
+//! - `:GitBlame` 打开 / 关闭当前缓冲区的 blame；保存文件时重新查询，修改缓冲区之后到保存之前不显示
+//! - 显示 blame 时在行上按 Enter 在新的缓冲区中打开这一行的提交（`git show`）
+//! - 还没有提交的行显示 "Not committed yet"
+
+use std::collections::HashMap;
+
+use crate::buffer::BufferId;
+
+/// 还没有提交的行的提交号
+const UNCOMMITTED: &str = "0000000000000000000000000000000000000000";
+
+/// 一行的 blame 信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub commit: String,
+    pub author: String,
+    /// 作者时间（Unix 时间戳）
+    pub time: i64,
+    /// 作者时区相对 UTC 的秒数
+    pub tz_offset: i64,
+    pub summary: String,
+}
+
+impl BlameLine {
+    /// 是否已经提交
+    pub fn is_committed(&self) -> bool {
+        self.commit != UNCOMMITTED
+    }
+
+    /// 提交号的前 8 位
+    pub fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(8)]
+    }
+
+    /// 作者时区的日期，如 `2024-03-09`
+    pub fn date(&self) -> String {
+        let (year, month, day) = civil_from_days((self.time + self.tz_offset).div_euclid(86400));
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    /// 行尾显示的文本：`作者, 日期 · 提交说明`
+    pub fn annotation(&self) -> String {
+        format!("{}, {} · {}", self.author, self.date(), self.summary)
+    }
+}
+
+/// 从 1970-01-01 起的天数对应的（年, 月, 日）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// 时区 `+0800` 相对 UTC 的秒数
+fn parse_tz(tz: &str) -> i64 {
+    let sign = if tz.starts_with('-') { -1 } else { 1 };
+    let digits = tz.trim_start_matches(['+', '-']);
+    let hours: i64 = digits.get(..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+    let minutes: i64 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+    sign * (hours * 3600 + minutes * 60)
+}
+
+/// 解析 `git blame --porcelain` 的输出，返回按行排列的 blame 信息
+///
+/// 每行以 `{提交号} {原行号} {行号} [{行数}]` 开头，提交第一次出现时后面跟着作者等信息，最后是以 Tab 开头的行内容
+pub fn parse_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<String, BlameLine> = HashMap::new();
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            if let Some((commit, number)) = current.take() {
+                lines.push((number, commit));
+            }
+            continue;
+        }
+        let Some((commit, _)) = &current else {
+            let mut fields = line.split(' ');
+            let (Some(commit), Some(_), Some(number)) = (fields.next(), fields.next(), fields.next()) else {
+                continue;
+            };
+            let Ok(number) = number.parse::<usize>() else {
+                continue;
+            };
+            commits.entry(commit.to_string()).or_insert_with(|| BlameLine {
+                commit: commit.to_string(),
+                author: String::new(),
+                time: 0,
+                tz_offset: 0,
+                summary: String::new(),
+            });
+            current = Some((commit.to_string(), number));
+            continue;
+        };
+        let Some(info) = commits.get_mut(commit) else {
+            continue;
+        };
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "author" => info.author = value.to_string(),
+            "author-time" => info.time = value.parse().unwrap_or(0),
+            "author-tz" => info.tz_offset = parse_tz(value),
+            "summary" => info.summary = value.to_string(),
+            _ => {}
+        }
+    }
+    lines.sort_by_key(|(number, _)| *number);
+    lines.into_iter().filter_map(|(_, commit)| commits.get(&commit).cloned()).collect()
+}
+
+/// 一个缓冲区的 blame 信息
+#[derive(Debug, Clone)]
+pub struct Blame {
+    pub buffer: BufferId,
+    /// 查询时缓冲区的 changedtick，缓冲区修改之后不再显示
+    pub tick: u64,
+    pub lines: Vec<BlameLine>,
+}
+
+impl Blame {
+    /// 第 `line` 行的 blame 信息
+    pub fn line(&self, line: usize) -> Option<&BlameLine> {
+        self.lines.get(line)
+    }
+}
@@ -1,7 +1,17 @@
 use ropey::Rope;
+use std::borrow::Cow;
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::edit_log::{EditLog, TextEdit};
+use crate::encoding::{self, Encoding};
+use crate::fold::FoldSet;
+use crate::hex;
+use crate::line_ending::{self, LineEnding};
+use crate::range::LineRange;
+use crate::syntax::SyntaxCache;
 
 #[derive(Debug, Clone)]
 pub struct Buffer {
@@ -10,6 +20,32 @@ pub struct Buffer {
     file_path: Option<PathBuf>,
     modified: bool,
     read_only: bool,
+    /// 没有对应文件的缓冲区显示的名称（如 :DiffOrig 打开的临时缓冲区）
+    name: Option<String>,
+    /// 文件类型，用于选择高亮方式
+    filetype: Option<String>,
+    /// 文件格式（:set fileformat）：CRLF 时缓冲区中只有 `\n`，写入时转换
+    line_ending: LineEnding,
+    /// 写入时是否在最后一行末尾补上换行符：新文件总是补上，读入的文件保持原样
+    final_newline: bool,
+    /// 文件编码（:set fileencoding），写入时按这个编码
+    encoding: Encoding,
+    /// 读入时无法解码而被替换的字符数
+    decode_errors: usize,
+    /// 文件以 BOM 开头：BOM 不放入文本，写入时写在最前面
+    bom: bool,
+    /// 二进制文件：缓冲区中是十六进制转储，写入时转换回字节（见 hex 模块）
+    binary: bool,
+    /// 读入或写入文件时文件的修改时间，用于发现文件在外部被修改
+    mtime: Option<SystemTime>,
+    /// 内容每次变化时加一（类似 Vim 的 b:changedtick），用于发现缓冲区被修改（如通知语言服务器）
+    changedtick: u64,
+    /// 最近的修改位置（见 edit_log 模块）
+    edits: EditLog,
+    /// 语法树缓存（见 syntax 模块）
+    syntax: SyntaxCache,
+    /// 折叠（见 fold 模块）
+    folds: FoldSet,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -33,20 +69,66 @@ impl Buffer {
             file_path: None,
             modified: false,
             read_only: false,
+            name: None,
+            filetype: None,
+            line_ending: LineEnding::Lf,
+            final_newline: true,
+            encoding: Encoding::Utf8,
+            decode_errors: 0,
+            bom: false,
+            binary: false,
+            mtime: None,
+            changedtick: 0,
+            edits: EditLog::default(),
+            syntax: SyntaxCache::default(),
+            folds: FoldSet::default(),
         }
     }
 
     pub fn from_file(id: BufferId, path: &Path) -> io::Result<Self> {
-        let content = fs::read_to_string(path)?;
-        let rope = Rope::from_str(&content);
+        Ok(Self::from_file_bytes(id, path, &fs::read(path)?))
+    }
 
-        Ok(Self {
-            id,
-            rope,
-            file_path: Some(path.to_path_buf()),
-            modified: false,
-            read_only: false,
-        })
+    /// 从已经读取的文件内容创建缓冲区（后台读入大文件时，见 loader 模块）
+    pub fn from_file_bytes(id: BufferId, path: &Path, bytes: &[u8]) -> Self {
+        let mut buffer = Self::new_with_path(id, path);
+        buffer.load(bytes);
+        buffer.update_mtime();
+        buffer
+    }
+
+    /// 从读入的内容创建没有文件名的缓冲区（如标准输入）
+    pub fn from_bytes(id: BufferId, bytes: &[u8]) -> Self {
+        let mut buffer = Self::new(id);
+        buffer.load(bytes);
+        buffer
+    }
+
+    /// 解码文件内容，记录编码、文件格式和最后一行是否有换行符
+    ///
+    /// 二进制文件读入为十六进制转储
+    fn load(&mut self, bytes: &[u8]) {
+        self.changedtick += 1;
+        self.edits.reset(self.changedtick);
+        self.binary = hex::is_binary(bytes);
+        if self.binary {
+            self.rope = Rope::from_str(&hex::dump(bytes));
+            self.line_ending = LineEnding::Lf;
+            self.final_newline = true;
+            self.encoding = Encoding::Utf8;
+            self.decode_errors = 0;
+            self.bom = false;
+            return;
+        }
+        let decoded = encoding::decode(bytes);
+        let (line_ending, text) = line_ending::detect(&decoded.text);
+        self.rope = Rope::from_str(&text);
+        self.line_ending = line_ending;
+        self.final_newline = decoded.text.is_empty() || decoded.text.ends_with('\n');
+        self.encoding = decoded.encoding;
+        self.decode_errors = decoded.replaced;
+        self.bom = decoded.bom;
+        self.folds.clamp(self.last_content_line());
     }
 
     pub fn new_with_path(id: BufferId, path: &Path) -> Self {
@@ -56,6 +138,43 @@ impl Buffer {
             file_path: Some(path.to_path_buf()),
             modified: false,
             read_only: false,
+            name: None,
+            filetype: None,
+            line_ending: LineEnding::Lf,
+            final_newline: true,
+            encoding: Encoding::Utf8,
+            decode_errors: 0,
+            bom: false,
+            binary: false,
+            mtime: None,
+            changedtick: 0,
+            edits: EditLog::default(),
+            syntax: SyntaxCache::default(),
+            folds: FoldSet::default(),
+        }
+    }
+
+    /// 创建只读的临时缓冲区，内容不对应任何文件
+    pub fn scratch(id: BufferId, name: &str, text: &str) -> Self {
+        Self {
+            id,
+            rope: Rope::from_str(text),
+            file_path: None,
+            modified: false,
+            read_only: true,
+            name: Some(name.to_string()),
+            filetype: None,
+            line_ending: LineEnding::Lf,
+            final_newline: true,
+            encoding: Encoding::Utf8,
+            decode_errors: 0,
+            bom: false,
+            binary: false,
+            mtime: None,
+            changedtick: 0,
+            edits: EditLog::default(),
+            syntax: SyntaxCache::default(),
+            folds: FoldSet::default(),
         }
     }
 
@@ -71,6 +190,135 @@ impl Buffer {
         self.file_path = Some(path);
     }
 
+    /// 显示用的名称：文件名，临时缓冲区为创建时指定的名称
+    pub fn display_name(&self) -> Option<&str> {
+        self.file_path
+            .as_deref()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .or(self.name.as_deref())
+    }
+
+    pub fn filetype(&self) -> Option<&str> {
+        self.filetype.as_deref()
+    }
+
+    pub fn set_filetype(&mut self, filetype: &str) {
+        self.filetype = Some(filetype.to_string());
+    }
+
+    /// 文件格式：写入时使用的行尾符
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// 修改文件格式（:set fileformat），下次写入时转换所有行尾
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        if self.line_ending != line_ending {
+            self.line_ending = line_ending;
+            self.modified = true;
+        }
+    }
+
+    /// 文件编码
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// 修改文件编码（:set fileencoding），下次写入时按新的编码
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        if self.encoding != encoding {
+            self.encoding = encoding;
+            self.modified = true;
+        }
+    }
+
+    /// 读入时无法解码而替换为 U+FFFD 的字符数
+    pub fn decode_errors(&self) -> usize {
+        self.decode_errors
+    }
+
+    /// 文件是否以 BOM 开头（写入时保留）
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    /// 文本中无法用文件编码表示的字符数
+    pub fn unencodable_chars(&self) -> usize {
+        match self.encoding {
+            Encoding::Utf8 => 0,
+            encoding => self.rope.chars().filter(|&c| !encoding.can_encode(c)).count(),
+        }
+    }
+
+    /// 读入的文件最后一行是否以换行符结尾
+    /// 是否为以十六进制转储显示的二进制文件
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    pub fn has_final_newline(&self) -> bool {
+        self.final_newline
+    }
+
+    /// 记录文件当前的修改时间
+    fn update_mtime(&mut self) {
+        self.mtime = self.file_path.as_ref().and_then(|path| fs::metadata(path).and_then(|m| m.modified()).ok());
+    }
+
+    /// 文件在读入或上次写入之后是否被外部修改过
+    ///
+    /// 没有对应文件、新文件或文件已被删除时返回 false
+    pub fn changed_on_disk(&self) -> bool {
+        let (Some(path), Some(mtime)) = (&self.file_path, self.mtime) else {
+            return false;
+        };
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|current| current != mtime)
+    }
+
+    /// 内容变化的次数，内容相同的两次调用之间不变
+    pub fn changedtick(&self) -> u64 {
+        self.changedtick
+    }
+
+    pub(crate) fn syntax_cache(&self) -> &SyntaxCache {
+        &self.syntax
+    }
+
+    pub fn folds(&self) -> &FoldSet {
+        &self.folds
+    }
+
+    pub fn folds_mut(&mut self) -> &mut FoldSet {
+        &mut self.folds
+    }
+
+    /// 在 `char_idx` 处插入 `text` 之前移动折叠
+    fn shift_folds_for_insert(&mut self, char_idx: usize, text: &str) {
+        let count = text.matches('\n').count();
+        if count == 0 || self.folds.is_empty() {
+            return;
+        }
+        let line = self.rope.char_to_line(char_idx);
+        self.folds.insert_lines(line, count, char_idx == self.rope.line_to_char(line));
+    }
+
+    /// 删除 `start..end` 之前移动折叠
+    fn shift_folds_for_remove(&mut self, start: usize, end: usize) {
+        if self.folds.is_empty() {
+            return;
+        }
+        let (first, last) = (self.rope.char_to_line(start), self.rope.char_to_line(end));
+        if first == last {
+            return;
+        }
+        let at_line_start = |idx: usize| idx == self.rope.line_to_char(self.rope.char_to_line(idx));
+        let whole_lines = at_line_start(start) && at_line_start(end);
+        self.folds.remove_lines(first, last, whole_lines);
+    }
+
     pub fn is_modified(&self) -> bool {
         self.modified
     }
@@ -95,7 +343,7 @@ impl Buffer {
         self.rope.len_chars() == 0
     }
 
-    pub fn line(&self, line_idx: usize) -> Option<ropey::RopeSlice> {
+    pub fn line(&self, line_idx: usize) -> Option<ropey::RopeSlice<'_>> {
         if line_idx < self.rope.len_lines() {
             Some(self.rope.line(line_idx))
         } else {
@@ -103,6 +351,19 @@ impl Buffer {
         }
     }
 
+    /// 一行的文本，不包括换行符
+    pub fn line_text(&self, line_idx: usize) -> Option<String> {
+        self.line(line_idx).map(|l| {
+            let text = l.to_string();
+            text.strip_suffix('\n').map(String::from).unwrap_or(text)
+        })
+    }
+
+    /// 各行的文本，不包括行尾（见 `rope_lines`）
+    pub fn text_lines(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        rope_lines(&self.rope)
+    }
+
     pub fn line_len(&self, line_idx: usize) -> usize {
         self.line(line_idx).map(|l| l.len_chars()).unwrap_or(0)
     }
@@ -111,16 +372,22 @@ impl Buffer {
         if self.read_only {
             return;
         }
+        self.shift_folds_for_insert(char_idx, text);
         self.rope.insert(char_idx, text);
         self.modified = true;
+        self.record_edit(char_idx, 0, text.chars().count());
     }
 
     pub fn insert_char(&mut self, char_idx: usize, ch: char) {
         if self.read_only {
             return;
         }
+        if ch == '\n' {
+            self.shift_folds_for_insert(char_idx, "\n");
+        }
         self.rope.insert_char(char_idx, ch);
         self.modified = true;
+        self.record_edit(char_idx, 0, 1);
     }
 
     pub fn remove(&mut self, char_idx: usize, len: usize) {
@@ -128,8 +395,10 @@ impl Buffer {
             return;
         }
         let end_idx = (char_idx + len).min(self.rope.len_chars());
+        self.shift_folds_for_remove(char_idx, end_idx);
         self.rope.remove(char_idx..end_idx);
         self.modified = true;
+        self.record_edit(char_idx, end_idx.saturating_sub(char_idx), 0);
     }
 
     pub fn remove_char(&mut self, char_idx: usize) -> Option<char> {
@@ -137,16 +406,49 @@ impl Buffer {
             return None;
         }
         let ch = self.rope.char(char_idx);
+        self.shift_folds_for_remove(char_idx, char_idx + 1);
         self.rope.remove(char_idx..char_idx + 1);
         self.modified = true;
+        self.record_edit(char_idx, 1, 0);
         Some(ch)
     }
 
+    /// 在末尾追加外部内容（如 :tail 读取的新日志）
+    ///
+    /// 不受只读限制，也不标记为已修改
+    pub fn append_external(&mut self, text: &str) {
+        let end = self.rope.len_chars();
+        self.rope.insert(end, text);
+        self.record_edit(end, 0, text.chars().count());
+    }
+
+    /// 用外部内容替换全部内容（如终端缓冲区中程序的输出）
+    ///
+    /// 不受只读限制，也不标记为已修改
+    pub fn replace_external(&mut self, text: &str) {
+        self.rope = Rope::from_str(text);
+        self.folds.clamp(self.last_content_line());
+        self.changedtick += 1;
+        self.edits.reset(self.changedtick);
+    }
+
+    /// 从磁盘重新加载文件内容，丢弃未保存的修改
+    pub fn reload(&mut self) -> io::Result<()> {
+        let path = self.file_path.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "No file path set")
+        })?;
+        let bytes = fs::read(path)?;
+        self.load(&bytes);
+        self.update_mtime();
+        self.modified = false;
+        Ok(())
+    }
+
     pub fn char(&self, char_idx: usize) -> char {
         self.rope.char(char_idx)
     }
 
-    pub fn slice(&self, range: std::ops::Range<usize>) -> ropey::RopeSlice {
+    pub fn slice(&self, range: std::ops::Range<usize>) -> ropey::RopeSlice<'_> {
         self.rope.slice(range)
     }
 
@@ -158,19 +460,101 @@ impl Buffer {
         self.rope.char_to_line(char_idx)
     }
 
+    /// 最后一个有内容的行号（文件以换行符结尾时不计末尾的空行）
+    pub fn last_content_line(&self) -> usize {
+        let lines = self.rope.len_lines().saturating_sub(1);
+        if lines > 0 && self.rope.char(self.rope.len_chars() - 1) == '\n' {
+            lines - 1
+        } else {
+            lines
+        }
+    }
+
+    /// 范围内各行（包括换行符）的字符范围
+    fn lines_char_range(&self, range: LineRange) -> std::ops::Range<usize> {
+        let start = self.rope.line_to_char(range.start);
+        let end = if range.end + 1 < self.rope.len_lines() {
+            self.rope.line_to_char(range.end + 1)
+        } else {
+            self.rope.len_chars()
+        };
+        start..end
+    }
+
+    /// 范围内各行的文本，总是以换行符结尾
+    pub fn lines_text(&self, range: LineRange) -> String {
+        let mut text = self.rope.slice(self.lines_char_range(range)).to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text
+    }
+
+    /// 在第 `line` 行之前插入以换行符结尾的整行文本，`line` 可以是最后一行的下一行
+    ///
+    /// 最后一行没有换行符时在它后面补上换行符，插入的文本末尾不加
+    pub fn insert_lines(&mut self, line: usize, text: &str) {
+        let len = self.rope.len_chars();
+        if line <= self.last_content_line() && len > 0 {
+            let idx = self.rope.line_to_char(line);
+            self.insert(idx, text);
+        } else if len == 0 || self.rope.char(len - 1) == '\n' {
+            self.insert(len, text);
+        } else {
+            self.insert(len, &format!("\n{}", text.strip_suffix('\n').unwrap_or(text)));
+        }
+    }
+
+    /// 用以换行符结尾的文本替换范围内的行，范围超出文件时截断到最后一行
+    ///
+    /// 返回实际替换的范围；最后一行原本没有换行符时，替换后也不加
+    pub fn replace_lines(&mut self, range: LineRange, text: &str) -> LineRange {
+        let last = self.last_content_line();
+        let range = LineRange::new(range.start.min(last), range.end.min(last));
+        let chars = self.lines_char_range(range);
+        let text = if chars.end > chars.start && self.rope.char(chars.end - 1) != '\n' {
+            text.strip_suffix('\n').unwrap_or(text)
+        } else {
+            text
+        };
+        self.remove(chars.start, chars.end - chars.start);
+        self.insert(chars.start, text);
+        range
+    }
+
+    /// 把范围内的行写入文件，`append` 为 true 时追加到文件末尾
+    pub fn write_lines(&self, range: LineRange, path: &Path, append: bool) -> io::Result<()> {
+        let text = self.lines_text(range);
+        match self.encoding.unencodable_chars(&text) {
+            0 => crate::file_ops::write_text(path, &self.encode(&text), append),
+            count => Err(self.unencodable_error(count)),
+        }
+    }
+
+    /// 写入文件：按文件格式转换行尾、按文件编码编码，最后一行没有换行符时按读入时的状态决定是否补上
+    ///
+    /// 有文件编码无法表示的字符时不写入，返回 `InvalidData` 错误
     pub fn save(&mut self) -> io::Result<()> {
+        match self.unencodable_chars() {
+            0 => self.write_file(),
+            count => Err(self.unencodable_error(count)),
+        }
+    }
+
+    /// 写入文件，文件编码无法表示的字符替换为 `?`
+    pub fn save_lossy(&mut self) -> io::Result<()> {
+        self.write_file()
+    }
+
+    fn write_file(&mut self) -> io::Result<()> {
+        if self.binary {
+            return self.write_binary();
+        }
         if let Some(ref path) = self.file_path {
             let mut file = fs::File::create(path)?;
-            for chunk in self.rope.chunks() {
-                file.write_all(chunk.as_bytes())?;
-            }
-            // 确保文件以换行符结尾（Unix文本文件惯例）
-            if self.rope.len_chars() > 0 {
-                let last_char = self.rope.char(self.rope.len_chars() - 1);
-                if last_char != '\n' {
-                    file.write_all(b"\n")?;
-                }
-            }
+            self.write_text(&mut file)?;
+            drop(file);
+            self.update_mtime();
             self.modified = false;
             Ok(())
         } else {
@@ -181,16 +565,199 @@ impl Buffer {
         }
     }
 
+    /// 按文件格式和编码输出文本（读入时有 BOM 则先写 BOM），最后一行没有换行符时按读入时的状态决定是否补上
+    fn write_text(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.bom {
+            out.write_all(self.encoding.bom())?;
+        }
+        for chunk in self.rope.chunks() {
+            out.write_all(&self.encode(chunk))?;
+        }
+        let len = self.rope.len_chars();
+        if self.final_newline && len > 0 && self.rope.char(len - 1) != '\n' {
+            out.write_all(&self.encode("\n"))?;
+        }
+        Ok(())
+    }
+
+    /// 把内容按写入文件时的格式输出到 `out`（如标准输出），不改变修改状态
+    ///
+    /// 二进制文件输出十六进制转储对应的字节，无法编码的字符替换为 `?`
+    pub fn write_contents(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.binary {
+            let bytes = hex::parse(&self.rope.to_string()).map_err(|line| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("Invalid hex dump at line {}", line + 1))
+            })?;
+            return out.write_all(&bytes);
+        }
+        self.write_text(out)
+    }
+
+    /// 把十六进制转储转换回字节写入，写入后重新生成转储
+    fn write_binary(&mut self) -> io::Result<()> {
+        let path = self.file_path.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "No file path set")
+        })?;
+        let bytes = hex::parse(&self.rope.to_string()).map_err(|line| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Invalid hex dump at line {}", line + 1))
+        })?;
+        fs::write(path, &bytes)?;
+        self.update_mtime();
+        let dump = hex::dump(&bytes);
+        if self.rope != dump.as_str() {
+            self.rope = Rope::from_str(&dump);
+            self.changedtick += 1;
+            self.edits.reset(self.changedtick);
+        }
+        self.modified = false;
+        Ok(())
+    }
+
+    /// 按文件格式和编码转换要写入的文本
+    fn encode(&self, text: &str) -> Vec<u8> {
+        match self.line_ending {
+            LineEnding::Lf => encoding::encode_lossy(text, self.encoding),
+            LineEnding::Crlf => encoding::encode_lossy(&text.replace('\n', "\r\n"), self.encoding),
+        }
+    }
+
+    fn unencodable_error(&self, count: usize) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Cannot encode {} character(s) as {}", count, self.encoding.name()),
+        )
+    }
+
     pub fn save_as(&mut self, path: &Path) -> io::Result<()> {
         self.file_path = Some(path.to_path_buf());
         self.save()
     }
 
-    pub fn to_string(&self) -> String {
-        self.rope.to_string()
-    }
-
     pub fn rope(&self) -> &Rope {
         &self.rope
     }
+
+    /// 替换整个缓冲区的内容（撤销/重做时恢复历史状态）
+    pub fn set_rope(&mut self, rope: Rope) {
+        self.rope = rope;
+        self.folds.clamp(self.last_content_line());
+        self.modified = true;
+        self.changedtick += 1;
+        self.edits.reset(self.changedtick);
+    }
+
+    /// 记下一处修改并把 changedtick 加一
+    fn record_edit(&mut self, start: usize, removed: usize, inserted: usize) {
+        self.edits.record(TextEdit { start, removed, inserted });
+        self.changedtick += 1;
+    }
+
+    /// changedtick 为 `tick` 之后的修改合并成的一处修改（见 `EditLog::since`）
+    pub fn edits_since(&self, tick: u64) -> Option<Option<TextEdit>> {
+        self.edits.since(tick)
+    }
+}
+
+/// 文本的各行，与 `str::lines` 相同：不包括 `\n` 或 `\r\n`，末尾的换行符之后没有空行
+///
+/// 行在 rope 的一个块中时借用，跨块的行才复制，不复制整个文本
+pub fn rope_lines(rope: &Rope) -> impl Iterator<Item = Cow<'_, str>> {
+    let ends_with_newline = rope.len_chars() > 0 && rope.char(rope.len_chars() - 1) == '\n';
+    let count = if rope.len_chars() == 0 { 0 } else { rope.len_lines() - usize::from(ends_with_newline) };
+    rope.lines().take(count).map(|line| match Cow::from(line) {
+        Cow::Borrowed(text) => {
+            let text = text.strip_suffix('\n').map_or(text, |text| text.strip_suffix('\r').unwrap_or(text));
+            Cow::Borrowed(text)
+        }
+        Cow::Owned(mut text) => {
+            if text.ends_with('\n') {
+                text.pop();
+                if text.ends_with('\r') {
+                    text.pop();
+                }
+            }
+            Cow::Owned(text)
+        }
+    })
+}
+
+#[cfg(test)]
+thread_local! {
+    static FULL_TEXT_COPIES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+impl std::fmt::Display for Buffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(test)]
+        FULL_TEXT_COPIES.with(|copies| copies.set(copies.get() + 1));
+        for chunk in self.rope.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// 行内容长度（不包括行尾换行符）
+pub(crate) fn line_content_len(line: ropey::RopeSlice) -> usize {
+    let mut len = line.len_chars();
+    if len > 0 && line.char(len - 1) == '\n' {
+        len -= 1;
+        if len > 0 && line.char(len - 1) == '\r' {
+            len -= 1;
+        }
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::Cursor;
+    use crate::editor::Editor;
+    use crate::motion::Motion;
+    use crate::search::SearchDirection;
+    use crate::text_object::TextObject;
+
+    /// 当前线程中格式化整个缓冲区（如 `buffer.to_string()`）的次数
+    fn full_text_copies() -> usize {
+        FULL_TEXT_COPIES.with(std::cell::Cell::get)
+    }
+
+    #[test]
+    fn test_rope_lines() {
+        let lines = |text: &str| rope_lines(&Rope::from_str(text)).map(Cow::into_owned).collect::<Vec<_>>();
+        for text in ["", "\n", "a", "a\nb", "a\nb\n", "a\r\nb\r\n", "a\n\nb\n\n"] {
+            assert_eq!(lines(text), text.lines().collect::<Vec<_>>(), "{:?}", text);
+        }
+        // 跨块的长行
+        let long = "x".repeat(10_000);
+        assert_eq!(lines(&format!("a\n{}\nb", long)), vec!["a", long.as_str(), "b"]);
+    }
+
+    #[test]
+    fn test_operations_do_not_copy_buffer() {
+        let mut editor = Editor::new();
+        let text: String = (0..1000).map(|i| format!("line {} 单词 word\n", i)).collect();
+        editor.current_buffer_mut().insert(0, &text);
+        *editor.cursor_mut() = Cursor::new(500, 0);
+        let before = full_text_copies();
+
+        assert_eq!(editor.delete_to_motion(Motion::WordForward).as_deref(), Some("line "));
+        editor.yank_to_motion(Motion::WordForward);
+        assert_eq!(editor.register_manager().get('0').unwrap().content, "500 ");
+        assert_eq!(editor.delete_text_object(TextObject::InnerWord).as_deref(), Some("500"));
+        editor.start_search(SearchDirection::Forward, "单词 word");
+        assert_eq!(editor.search_state().matches.len(), 1000);
+        editor.execute_command("s/word/w").unwrap();
+        assert_eq!(editor.current_buffer().line_text(500).unwrap(), " 单词 w");
+
+        // 整个缓冲区的操作逐行处理
+        assert!(editor.execute_command("symbols").is_err());
+        assert_eq!(editor.fix_line_endings(LineEnding::Crlf), 1000);
+        assert_eq!(editor.current_buffer().line_text(1).unwrap(), "line 1 单词 word\r");
+        assert_eq!(editor.fix_line_endings(LineEnding::Lf), 1000);
+        assert_eq!(editor.current_buffer().line_text(1).unwrap(), "line 1 单词 word");
+
+        assert_eq!(full_text_copies(), before);
+    }
 }
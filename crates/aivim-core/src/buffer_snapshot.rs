@@ -1,9 +1,9 @@
-/// 缓冲区快照系统
-/// 
-/// 提供自动状态管理，确保所有修改操作都能正确撤销/重做，
-/// 并保留文件路径等元数据。
+//! 缓冲区快照系统
+//! 
+//! 提供自动状态管理，确保所有修改操作都能正确撤销/重做，
+//! 并保留文件路径等元数据。
 
-use crate::buffer::{Buffer, BufferId};
+use crate::buffer::Buffer;
 use crate::cursor::Cursor;
 use std::path::PathBuf;
 
@@ -46,7 +46,7 @@ impl BufferSnapshot {
 /// 修改操作包装器
 /// 
 /// 使用方式：
-/// ```
+/// ```ignore
 /// let result = editor.with_snapshot(|editor| {
 ///     editor.execute_edit(edit)
 /// });
@@ -149,6 +149,16 @@ impl<'a> AutoSnapshot<'a> {
         }
     }
 
+    /// 获取被守卫的缓冲区
+    pub fn buffer_mut(&mut self) -> &mut Buffer {
+        self.buffer
+    }
+
+    /// 获取被守卫的光标
+    pub fn cursor_mut(&mut self) -> &mut Cursor {
+        self.cursor
+    }
+
     /// 提交修改（不恢复）
     pub fn commit(mut self) {
         self.committed = true;
@@ -164,7 +174,7 @@ impl<'a> Drop for AutoSnapshot<'a> {
     fn drop(&mut self) {
         if !self.committed {
             // 如果没有提交，撤销最后一次保存
-            if let Some(_) = self.manager.undo_stack.pop() {
+            if self.manager.undo_stack.pop().is_some() {
                 // 从 undo 栈移除，但不恢复（因为修改已经发生）
                 // 这种情况下应该手动调用 undo 来恢复
             }
@@ -175,6 +185,7 @@ impl<'a> Drop for AutoSnapshot<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::buffer::BufferId;
 
     #[test]
     fn test_snapshot_basic() {
@@ -0,0 +1,150 @@
+//! AI 对话模块 (:AiChat)
+//!
+//! 对话显示在右侧分割窗口的只读缓冲区 `[AI Chat]` 中：
+//! - `:AiChat {message}` 发送消息，`:{range}AiChat {message}` 附带范围内的行（`:%AiChat` 为整个缓冲区），
+//!   `:AiChat! {message}` 附带当前窗口中可见的行；不带消息时打开并切换到对话窗口
+//! - 每条消息以 `## You` / `## AI` 标题开头，回复流式追加到缓冲区末尾，
+//!   光标在最后一行的窗口跟随显示最新的内容
+//! - 每次请求发送完整的对话历史；附带的文本只发送给模型，缓冲区中只显示来源
+//! - 删除对话缓冲区（:bd）即结束对话，下次 :AiChat 开始新的对话
+
+use aivim_ai::chat::{self, ChatMessage};
+use aivim_ai::{AiConfig, AiEvent, AiStream};
+use serde_json::Value;
+
+use crate::buffer::BufferId;
+use crate::range::LineRange;
+
+/// 对话缓冲区的名称
+pub const CHAT_BUFFER_NAME: &str = "[AI Chat]";
+
+const SYSTEM_PROMPT: &str = "You are a helpful programming assistant inside the aivim text editor. \
+Answer concisely. Use Markdown; put code in fenced code blocks.";
+
+/// 随消息附带的缓冲区内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatContext {
+    /// 缓冲区的名称
+    pub name: String,
+    pub filetype: Option<String>,
+    /// 附带的行
+    pub range: LineRange,
+    pub text: String,
+}
+
+impl ChatContext {
+    /// 缓冲区中显示的来源，如 `main.rs:10-20`
+    pub fn label(&self) -> String {
+        if self.range.start == self.range.end {
+            format!("{}:{}", self.name, self.range.start + 1)
+        } else {
+            format!("{}:{}-{}", self.name, self.range.start + 1, self.range.end + 1)
+        }
+    }
+
+    /// 发送给模型的消息：附带的文本放在代码块中，之后是用户的消息
+    pub fn prompt(&self, message: &str) -> String {
+        format!(
+            "{}:\n```{}\n{}\n```\n\n{}",
+            self.label(),
+            self.filetype.as_deref().unwrap_or(""),
+            self.text.trim_end_matches('\n'),
+            message
+        )
+    }
+}
+
+/// 用户消息在对话缓冲区中的显示，之后是回复的标题
+pub fn format_user_message(message: &str, context: Option<&ChatContext>) -> String {
+    let mut text = format!("## You\n{}\n", message);
+    if let Some(context) = context {
+        text.push_str(&format!("> {}\n", context.label()));
+    }
+    text.push_str("\n## AI\n");
+    text
+}
+
+/// 一次对话
+#[derive(Debug)]
+pub struct ChatSession {
+    /// 显示对话的缓冲区
+    buffer: BufferId,
+    /// 发送给模型的历史消息（第一条为系统消息）
+    messages: Vec<ChatMessage>,
+    /// 正在接收的回复
+    reply: String,
+    stream: Option<AiStream>,
+}
+
+impl ChatSession {
+    pub fn new(buffer: BufferId) -> Self {
+        Self {
+            buffer,
+            messages: vec![ChatMessage::system(SYSTEM_PROMPT)],
+            reply: String::new(),
+            stream: None,
+        }
+    }
+
+    pub fn buffer(&self) -> BufferId {
+        self.buffer
+    }
+
+    /// 对话历史，不包括系统消息
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages[1..]
+    }
+
+    /// 是否正在接收回复
+    pub fn is_responding(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// 加入用户消息，返回包含完整历史的请求
+    pub fn push_user(&mut self, content: String, config: &AiConfig) -> Value {
+        self.messages.push(ChatMessage::user(content));
+        chat::request_body(config, &self.messages, config.chat_max_tokens, 0.7)
+    }
+
+    /// 开始接收回复，请求无法发送时撤回最后一条用户消息
+    pub fn start(&mut self, stream: Option<AiStream>) {
+        if stream.is_none() {
+            self.messages.pop();
+        }
+        self.reply.clear();
+        self.stream = stream;
+    }
+
+    /// 取出回复，返回 `(追加到缓冲区的文本, 请求的错误)`
+    ///
+    /// 回复结束时把它加入历史，并在缓冲区中空一行
+    pub fn poll(&mut self) -> (String, Option<String>) {
+        let Some(stream) = self.stream.as_mut() else {
+            return (String::new(), None);
+        };
+        let mut text = String::new();
+        let mut error = None;
+        for event in stream.poll() {
+            match event {
+                AiEvent::Delta(delta) => text.push_str(&delta),
+                AiEvent::Done => {}
+                AiEvent::Error(message) => error = Some(message),
+            }
+        }
+        self.reply.push_str(&text);
+        if stream.is_finished() {
+            self.stream = None;
+            if let Some(message) = &error {
+                text.push_str(&format!("\n[{}]", message));
+            }
+            if !self.reply.is_empty() {
+                self.messages.push(ChatMessage::assistant(std::mem::take(&mut self.reply)));
+            } else {
+                // 没有回复时撤回用户消息，历史中的消息保持一问一答
+                self.messages.pop();
+            }
+            text.push_str("\n\n");
+        }
+        (text, error)
+    }
+}
@@ -0,0 +1,208 @@
+//! 配色方案模块
+//!
+//! 保存 `:colorscheme` 选择的内置配色和 `:highlight` 对单个高亮组的修改，
+//! 具体的颜色由界面（aivim-tui 的 theme 模块）决定：
+//! - 内置配色见 `COLORSCHEMES`，切换配色会清除之前的 `:highlight` 修改（与 Vim 相同）
+//! - 高亮组见 `HIGHLIGHT_GROUPS`，命名参照 Vim（`LineNr`、`CursorLine`、`Pmenu` 等）
+//! - `:highlight {group} fg={color} bg={color} attr=bold,italic`，颜色可以是颜色名、
+//!   `#rrggbb`、0-255 的终端颜色编号或 `none`（终端默认颜色）；也接受 Vim 的 `guifg=`、`gui=` 写法
+//!
+//! 在配置文件中写 `colorscheme gruvbox` 和 `highlight` 命令即可在启动时应用。
+
+/// 内置的配色方案
+pub const COLORSCHEMES: &[&str] = &["default", "gruvbox", "light"];
+
+/// 默认的配色方案
+pub const DEFAULT_COLORSCHEME: &str = "default";
+
+/// 可以用 :highlight 修改的高亮组
+pub const HIGHLIGHT_GROUPS: &[&str] = &[
+    // 文本区域
+    "Normal",
+    "LineNr",
+    "CurrentLineNr",
+    "CursorLineNr",
+    "Gutter",
+    "CursorLine",
+    "CursorColumn",
+    "EndOfBuffer",
+    "Folded",
+    "Visual",
+    "Search",
+    "DiffAdd",
+    "DiffDelete",
+    "DiffHunk",
+    "DiffHeader",
+    "AiSuggestion",
+    "DiagnosticError",
+    "DiagnosticWarn",
+    "DiagnosticInfo",
+    "DiagnosticHint",
+    "GitSignsAdd",
+    "GitSignsChange",
+    "GitSignsDelete",
+    "GitBlame",
+    "DiagnosticUnderlineError",
+    "DiagnosticUnderlineWarn",
+    "DiagnosticUnderlineInfo",
+    "DiagnosticUnderlineHint",
+    // 语法
+    "Comment",
+    "String",
+    "Number",
+    "Keyword",
+    "Function",
+    "Type",
+    // 状态栏和命令行
+    "StatusLine",
+    "StatusLineNC",
+    "StatusRegister",
+    "WinSeparator",
+    "TabLine",
+    "TabLineSel",
+    "TabLineFill",
+    "ModeNormal",
+    "ModeInsert",
+    "ModeVisual",
+    "ModeCommand",
+    "ModeReplace",
+    "ModeSearch",
+    "Message",
+    "ErrorMsg",
+    "WarningMsg",
+    // 菜单和面板
+    "Pmenu",
+    "PmenuSel",
+    "PmenuExtra",
+    "FloatBorder",
+    "FloatBorderDim",
+    "Title",
+    "Label",
+    "NonText",
+    "Hint",
+    "HintKey",
+];
+
+/// 高亮颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightColor {
+    /// 终端默认颜色（none）
+    Reset,
+    /// 终端颜色编号（0-15 为按 ANSI 顺序的 16 色）
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// 颜色名对应的终端颜色编号
+const COLOR_NAMES: &[(&str, u8)] = &[
+    ("black", 0),
+    ("red", 1),
+    ("green", 2),
+    ("yellow", 3),
+    ("blue", 4),
+    ("magenta", 5),
+    ("cyan", 6),
+    ("gray", 7),
+    ("grey", 7),
+    ("darkgray", 8),
+    ("darkgrey", 8),
+    ("lightred", 9),
+    ("lightgreen", 10),
+    ("lightyellow", 11),
+    ("lightblue", 12),
+    ("lightmagenta", 13),
+    ("lightcyan", 14),
+    ("white", 15),
+];
+
+/// 解析颜色：颜色名、`#rrggbb`、0-255 的编号或 `none`
+pub fn parse_color(text: &str) -> Option<HighlightColor> {
+    let lower = text.to_ascii_lowercase();
+    if lower == "none" || lower == "reset" {
+        return Some(HighlightColor::Reset);
+    }
+    if let Some(hex) = lower.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(HighlightColor::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = lower.parse::<u8>() {
+        return Some(HighlightColor::Indexed(index));
+    }
+    COLOR_NAMES
+        .iter()
+        .find(|(name, _)| *name == lower)
+        .map(|(_, index)| HighlightColor::Indexed(*index))
+}
+
+/// 文字属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HighlightAttrs {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+/// :highlight 对一个高亮组的修改，未指定的部分沿用配色方案
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Highlight {
+    pub fg: Option<HighlightColor>,
+    pub bg: Option<HighlightColor>,
+    /// 指定时替换配色方案的全部文字属性
+    pub attrs: Option<HighlightAttrs>,
+}
+
+/// :highlight 参数错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HighlightError {
+    /// 未知的高亮组
+    UnknownGroup(String),
+    /// 无法识别的参数
+    InvalidArgument(String),
+}
+
+/// 解析 :highlight 的参数，返回高亮组名（规范的大小写）和修改
+pub fn parse_highlight(args: &[String]) -> Result<(&'static str, Highlight), HighlightError> {
+    let Some((group, rest)) = args.split_first() else {
+        return Err(HighlightError::InvalidArgument(String::new()));
+    };
+    let group = HIGHLIGHT_GROUPS
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(group))
+        .ok_or_else(|| HighlightError::UnknownGroup(group.clone()))?;
+    if rest.is_empty() {
+        return Err(HighlightError::InvalidArgument(String::new()));
+    }
+
+    let mut highlight = Highlight::default();
+    for arg in rest {
+        let invalid = || HighlightError::InvalidArgument(arg.clone());
+        let (key, value) = arg.split_once('=').ok_or_else(invalid)?;
+        match key.to_ascii_lowercase().as_str() {
+            "fg" | "guifg" | "ctermfg" => highlight.fg = Some(parse_color(value).ok_or_else(invalid)?),
+            "bg" | "guibg" | "ctermbg" => highlight.bg = Some(parse_color(value).ok_or_else(invalid)?),
+            "attr" | "gui" | "cterm" => highlight.attrs = Some(parse_attrs(value).ok_or_else(invalid)?),
+            _ => return Err(invalid()),
+        }
+    }
+    Ok((group, highlight))
+}
+
+/// 解析逗号分隔的文字属性，`none` 表示没有属性
+fn parse_attrs(text: &str) -> Option<HighlightAttrs> {
+    let mut attrs = HighlightAttrs::default();
+    for name in text.split(',') {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => attrs = HighlightAttrs::default(),
+            "bold" => attrs.bold = true,
+            "italic" => attrs.italic = true,
+            "underline" => attrs.underline = true,
+            "reverse" | "inverse" => attrs.reverse = true,
+            _ => return None,
+        }
+    }
+    Some(attrs)
+}
@@ -0,0 +1,76 @@
+//! AI 提交信息模块 (:AiCommitMsg)
+//!
+//! 读取仓库的修改（见 git 模块），请求模型写一条 Conventional Commits 格式的提交信息，
+//! 插入到 `COMMIT_EDITMSG` 缓冲区的开头供编辑：
+//! - 有暂存的修改时只使用暂存区的 diff（即将提交的内容），否则使用工作区的 diff
+//! - 当前缓冲区是 `COMMIT_EDITMSG`（aivim 作为 `git commit` 的编辑器）时插入当前缓冲区，
+//!   否则打开仓库的 `.git/COMMIT_EDITMSG`
+//! - 请求在后台进行，回复结束后作为一次修改插入；等待期间切换了缓冲区时丢弃回复
+//! - diff 超过 `MAX_DIFF_CHARS` 个字符时截断
+
+use aivim_ai::chat::{self, ChatMessage};
+use aivim_ai::{AiConfig, AiEvent, AiStream};
+use serde_json::Value;
+
+use crate::ai_edit::strip_code_fence;
+use crate::buffer::BufferId;
+
+/// git 提交信息文件的名称
+pub const COMMIT_EDITMSG: &str = "COMMIT_EDITMSG";
+
+/// 发送给模型的 diff 的最大字符数
+pub const MAX_DIFF_CHARS: usize = 12000;
+
+const SYSTEM_PROMPT: &str = "You write git commit messages. \
+Given a diff, reply with only a commit message in the Conventional Commits format: \
+a subject line `type(scope): summary` of at most 72 characters, \
+then a blank line and a short body explaining what changed and why when the change is not trivial. \
+No explanations and no markdown fences.";
+
+/// 提交信息请求的 JSON，过长的 diff 被截断
+pub fn request_body(config: &AiConfig, diff: &str) -> Value {
+    let message = match diff.char_indices().nth(MAX_DIFF_CHARS) {
+        Some((end, _)) => format!("{}\n[diff truncated]", &diff[..end]),
+        None => diff.to_string(),
+    };
+    let messages = [ChatMessage::system(SYSTEM_PROMPT), ChatMessage::user(message)];
+    chat::request_body(config, &messages, config.chat_max_tokens, 0.2)
+}
+
+/// 正在生成的提交信息
+#[derive(Debug)]
+pub struct CommitMessage {
+    /// 插入提交信息的缓冲区
+    pub buffer: BufferId,
+    reply: String,
+    stream: AiStream,
+}
+
+impl CommitMessage {
+    pub fn new(buffer: BufferId, stream: AiStream) -> Self {
+        Self {
+            buffer,
+            reply: String::new(),
+            stream,
+        }
+    }
+
+    /// 取出回复，结束时返回 `Some(Ok(提交信息))` 或 `Some(Err(错误))`
+    pub fn poll(&mut self) -> Option<Result<String, String>> {
+        let mut error = None;
+        for event in self.stream.poll() {
+            match event {
+                AiEvent::Delta(text) => self.reply.push_str(&text),
+                AiEvent::Done => {}
+                AiEvent::Error(message) => error = Some(message),
+            }
+        }
+        if !self.stream.is_finished() {
+            return None;
+        }
+        Some(match error {
+            Some(message) => Err(message),
+            None => Ok(strip_code_fence(&self.reply)),
+        })
+    }
+}
@@ -0,0 +1,241 @@
+//! 补全菜单模块
+//!
+//! 实现 Insert 模式下的补全弹出菜单：
+//! - Ctrl+N / Ctrl+P - 打开菜单或选择下一个/上一个候选项
+//! - Ctrl+Y - 接受当前候选项
+//! - Ctrl+E - 取消补全
+//! - Ctrl+Space 或输入服务器的触发字符 - 打开 LSP 补全菜单（:set lsp，没有服务器时为单词补全）
+//!
+//! 候选项可以携带文档或来源行，用于侧边预览。
+//! 候选来源是缓冲区中的单词或语言服务器，两者使用同一套菜单。
+
+use crate::motion::is_word_char;
+
+/// 默认最多同时显示的候选项数量
+pub const DEFAULT_MAX_VISIBLE: usize = 8;
+
+/// 补全候选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionItem {
+    /// 插入的文本
+    pub label: String,
+    /// 简短说明（如类型、来源）
+    pub detail: Option<String>,
+    /// 文档内容，用于预览
+    pub documentation: Option<String>,
+    /// 来源行号（从 0 开始），没有文档时预览该行
+    pub source_line: Option<usize>,
+    /// 类型图标（LSP 候选项）
+    pub kind: Option<&'static str>,
+    /// 语言服务器的候选项，接受时按它的修改插入
+    pub lsp_item: Option<aivim_lsp::CompletionItem>,
+}
+
+impl CompletionItem {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            detail: None,
+            documentation: None,
+            source_line: None,
+            kind: None,
+            lsp_item: None,
+        }
+    }
+
+    /// 语言服务器的候选项
+    pub fn from_lsp(item: aivim_lsp::CompletionItem) -> Self {
+        Self {
+            label: item.label.clone(),
+            detail: item.detail.clone(),
+            documentation: item.documentation.clone(),
+            source_line: None,
+            kind: item.kind.map(|kind| kind.icon()),
+            lsp_item: Some(item),
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn with_documentation(mut self, documentation: impl Into<String>) -> Self {
+        self.documentation = Some(documentation.into());
+        self
+    }
+
+    pub fn with_source_line(mut self, line: usize) -> Self {
+        self.source_line = Some(line);
+        self
+    }
+}
+
+/// 补全菜单状态
+#[derive(Debug, Clone)]
+pub struct CompletionMenu {
+    items: Vec<CompletionItem>,
+    selected: usize,
+    scroll_offset: usize,
+    max_visible: usize,
+    /// 补全所在行
+    line: usize,
+    /// 被替换文本（前缀）的起始列
+    start_column: usize,
+}
+
+impl CompletionMenu {
+    /// 创建补全菜单
+    ///
+    /// `line` 和 `start_column` 指定接受补全时被替换的前缀起点
+    pub fn new(items: Vec<CompletionItem>, line: usize, start_column: usize) -> Self {
+        Self {
+            items,
+            selected: 0,
+            scroll_offset: 0,
+            max_visible: DEFAULT_MAX_VISIBLE,
+            line,
+            start_column,
+        }
+    }
+
+    pub fn items(&self) -> &[CompletionItem] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_item(&self) -> Option<&CompletionItem> {
+        self.items.get(self.selected)
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn start_column(&self) -> usize {
+        self.start_column
+    }
+
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    pub fn max_visible(&self) -> usize {
+        self.max_visible
+    }
+
+    pub fn set_max_visible(&mut self, max_visible: usize) {
+        self.max_visible = max_visible.max(1);
+        self.ensure_selected_visible();
+    }
+
+    /// 当前可见的候选项（带索引）
+    pub fn visible_items(&self) -> impl Iterator<Item = (usize, &CompletionItem)> {
+        self.items
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(self.max_visible)
+    }
+
+    /// 选择下一个候选项（到末尾后回到开头）
+    pub fn select_next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        self.selected = (self.selected + 1) % self.items.len();
+        self.ensure_selected_visible();
+    }
+
+    /// 选择上一个候选项（到开头后回到末尾）
+    pub fn select_prev(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        self.selected = if self.selected == 0 {
+            self.items.len() - 1
+        } else {
+            self.selected - 1
+        };
+        self.ensure_selected_visible();
+    }
+
+    /// 向下翻一页
+    pub fn page_down(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        self.selected = (self.selected + self.max_visible).min(self.items.len() - 1);
+        self.ensure_selected_visible();
+    }
+
+    /// 向上翻一页
+    pub fn page_up(&mut self) {
+        self.selected = self.selected.saturating_sub(self.max_visible);
+        self.ensure_selected_visible();
+    }
+
+    /// 调整滚动偏移，确保选中项可见
+    fn ensure_selected_visible(&mut self) {
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if self.selected >= self.scroll_offset + self.max_visible {
+            self.scroll_offset = self.selected + 1 - self.max_visible;
+        }
+    }
+}
+
+/// 判断是否是单词字符（与文本对象一致）
+/// 语言服务器的候选项中 filterText 以 `prefix` 开头（不区分大小写）的候选项
+pub fn filter_lsp_items(items: &[aivim_lsp::CompletionItem], prefix: &str) -> Vec<CompletionItem> {
+    let prefix = prefix.to_lowercase();
+    items
+        .iter()
+        .filter(|item| item.filter_text.to_lowercase().starts_with(&prefix))
+        .cloned()
+        .map(CompletionItem::from_lsp)
+        .collect()
+}
+
+/// 从文本中收集以 `prefix` 开头的单词作为候选项
+///
+/// `lines` 为 (行号, 行文本)，结果按首次出现的顺序去重，
+/// 并记录每个单词首次出现的行号用于预览。与前缀完全相同的单词会被忽略。
+pub fn collect_word_candidates<'a, I>(lines: I, prefix: &str) -> Vec<CompletionItem>
+where
+    I: IntoIterator<Item = (usize, &'a str)>,
+{
+    let mut seen = std::collections::HashSet::new();
+    let mut items = Vec::new();
+
+    for (line_idx, text) in lines {
+        for word in text.split(|c: char| !is_word_char(c)) {
+            if word.is_empty() || word == prefix || !word.starts_with(prefix) {
+                continue;
+            }
+            if seen.insert(word.to_string()) {
+                items.push(CompletionItem::new(word).with_source_line(line_idx));
+            }
+        }
+    }
+
+    items
+}
+
+/// 获取光标前的单词前缀的起始列（字符索引）
+pub fn word_prefix_start(line_text: &str, column: usize) -> usize {
+    let chars: Vec<char> = line_text.chars().take(column).collect();
+    let word_len = chars.iter().rev().take_while(|c| is_word_char(**c)).count();
+    chars.len() - word_len
+}
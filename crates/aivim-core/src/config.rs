@@ -0,0 +1,55 @@
+//! 配置文件模块
+//!
+//! 启动时逐行执行配置文件中的 Ex 命令（与 Vim 的 vimrc 相同），例如：
+//!
+//! ```text
+//! " 用空格作为 leader 键
+//! let mapleader = " "
+//! set number shiftwidth=2 timeoutlen=500
+//! nnoremap <leader>w :w<CR>
+//! ```
+//!
+//! - 配置文件为 `$XDG_CONFIG_HOME/aivim/aivimrc`（未设置时为 `~/.config/aivim/aivimrc`）
+//! - 空行和以 `"` 开头的注释行被忽略，命令开头的 `:` 可以省略
+//! - `:source {file}` 执行其他文件中的命令
+//!
+//! AI 服务的设置在同一目录的 `config.toml` 中（格式见 `aivim_ai::config`），在 aivimrc 之前读取，
+//! aivimrc 中的 `:set aimodel=...` 等可以覆盖其中的值。语言服务器的设置也在 `config.toml` 中
+//! （格式见 `aivim_lsp::config`），写入前需要确认的受保护路径见 `file_ops` 模块
+
+use std::path::PathBuf;
+
+/// 配置目录 `$XDG_CONFIG_HOME/aivim`，无法确定时返回 None
+fn config_dir() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("aivim"))
+}
+
+/// 默认的配置文件路径，无法确定配置目录时返回 None
+pub fn default_config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("aivimrc"))
+}
+
+/// AI 服务和语言服务器设置文件 (config.toml) 的路径
+pub fn default_ai_config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// 配置文本中要执行的命令及其行号（从 1 开始）
+pub fn config_commands(text: &str) -> Vec<(usize, &str)> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.trim_start();
+            let command = line.strip_prefix(':').unwrap_or(line).trim_start();
+            if command.is_empty() || command.starts_with('"') {
+                None
+            } else {
+                Some((index + 1, command))
+            }
+        })
+        .collect()
+}
@@ -1,9 +1,12 @@
 use crate::buffer::Buffer;
+use crate::display::{self, DEFAULT_TABSTOP};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Cursor {
     pub line: usize,
+    /// 列（字符下标），总是在字形簇的起始位置
     pub column: usize,
+    /// 上下移动时保持的屏幕列（显示宽度，Tab 按默认的 tabstop 计算），None 时保持当前列
     pub preferred_column: Option<usize>,
 }
 
@@ -32,34 +35,48 @@ impl Cursor {
         Self::new(line, column)
     }
 
+    /// 向左移动 `count` 个字形簇，超过行首时移到上一行
     pub fn move_left(&mut self, buffer: &Buffer, count: usize) {
-        let _current_line_len = self.get_line_len(buffer);
-        
-        if self.column >= count {
-            self.column -= count;
+        let text = self.line_string(buffer);
+        let mut column = self.column;
+        let mut steps = 0;
+        while steps < count && column > 0 {
+            column = display::prev_grapheme(&text, column);
+            steps += 1;
+        }
+
+        if steps == count {
+            self.column = column;
         } else if self.line > 0 {
             self.line -= 1;
-            let prev_line_len = self.get_line_len(buffer);
-            self.column = prev_line_len.saturating_sub(1).min(self.preferred_column.unwrap_or(0));
+            self.column = 0;
+            self.adjust_column(buffer);
         } else {
             self.column = 0;
         }
-        self.update_preferred_column();
+        self.update_preferred_column(buffer);
     }
 
+    /// 向右移动 `count` 个字形簇，超过行尾时移到下一行
     pub fn move_right(&mut self, buffer: &Buffer, count: usize) {
-        let current_line_len = self.get_line_len(buffer);
-        let max_col = current_line_len.saturating_sub(1);
-        
-        if self.column + count <= max_col {
-            self.column += count;
+        let text = self.line_string(buffer);
+        let max_col = display::last_grapheme(&text);
+        let mut column = self.column;
+        let mut steps = 0;
+        while steps < count && column < max_col {
+            column = display::next_grapheme(&text, column);
+            steps += 1;
+        }
+
+        if steps == count {
+            self.column = column;
         } else if self.line + 1 < buffer.len_lines() {
             self.line += 1;
             self.column = 0;
         } else {
             self.column = max_col;
         }
-        self.update_preferred_column();
+        self.update_preferred_column(buffer);
     }
 
     pub fn move_up(&mut self, buffer: &Buffer, count: usize) {
@@ -79,24 +96,21 @@ impl Cursor {
 
     pub fn move_to_line_start(&mut self) {
         self.column = 0;
-        self.update_preferred_column();
+        self.preferred_column = Some(0);
     }
 
     pub fn move_to_line_end(&mut self, buffer: &Buffer) {
-        let line_len = self.get_line_len(buffer);
-        self.column = line_len.saturating_sub(1);
-        self.update_preferred_column();
+        self.column = display::last_grapheme(&self.line_string(buffer));
+        self.update_preferred_column(buffer);
     }
 
     pub fn move_to_first_non_blank(&mut self, buffer: &Buffer) {
-        if let Some(line) = buffer.line(self.line) {
-            let line_str = line.to_string();
-            let first_non_blank = line_str
-                .chars()
-                .position(|c| !c.is_whitespace())
-                .unwrap_or(0);
-            self.column = first_non_blank;
-            self.update_preferred_column();
+        if buffer.line(self.line).is_some() {
+            let text = self.line_string(buffer);
+            self.column = display::graphemes(&text)
+                .find(|(_, grapheme)| !grapheme.chars().all(char::is_whitespace))
+                .map_or(0, |(start, _)| start);
+            self.update_preferred_column(buffer);
         }
     }
 
@@ -116,31 +130,47 @@ impl Cursor {
         self.adjust_column(buffer);
     }
 
-    fn get_line_len(&self, buffer: &Buffer) -> usize {
-        buffer.line(self.line).map(|l| l.len_chars()).unwrap_or(0)
+    /// 当前行的文本，包括换行符
+    fn line_string(&self, buffer: &Buffer) -> String {
+        buffer.line(self.line).map(|l| l.to_string()).unwrap_or_default()
     }
 
     fn adjust_column(&mut self, buffer: &Buffer) {
-        let line_len = self.get_line_len(buffer);
-        let max_col = line_len.saturating_sub(1);
-        
-        if let Some(preferred) = self.preferred_column {
-            self.column = preferred.min(max_col);
-        } else {
-            self.column = self.column.min(max_col);
-        }
+        let text = self.line_string(buffer);
+        self.column = self.column_in(&text);
     }
 
-    pub fn update_preferred_column(&mut self) {
-        self.preferred_column = Some(self.column);
+    /// 移到 `text` 所在的行时光标的列：preferred_column 对应的字形簇，行不够长时为最后一个字形簇
+    fn column_in(&self, text: &str) -> usize {
+        let column = match self.preferred_column {
+            Some(preferred) => display::char_at_column(text, preferred, DEFAULT_TABSTOP),
+            None => display::grapheme_start(text, self.column),
+        };
+        column.min(display::last_grapheme(text))
+    }
+
+    /// 光标在屏幕上的列（显示宽度）
+    pub fn display_column(&self, buffer: &Buffer) -> usize {
+        let text = buffer.line_text(self.line).unwrap_or_default();
+        display::display_column(&text, self.column, DEFAULT_TABSTOP)
+    }
+
+    /// 记住光标当前的屏幕列，之后上下移动时保持在这一列
+    pub fn update_preferred_column(&mut self, buffer: &Buffer) {
+        self.preferred_column = Some(self.display_column(buffer));
+    }
+
+    /// 上下移动到新的一行之后，回到 preferred_column 对应的列（Normal 模式下不超过最后一个字形簇）
+    pub fn restore_preferred_column(&mut self, buffer: &Buffer) {
+        let text = buffer.line_text(self.line).unwrap_or_default();
+        self.column = self.column_in(&text);
     }
 
     pub fn ensure_valid(&mut self, buffer: &Buffer) {
         let max_line = buffer.len_lines().saturating_sub(1);
         self.line = self.line.min(max_line);
-        
-        let line_len = self.get_line_len(buffer);
-        let max_col = line_len.saturating_sub(1);
-        self.column = self.column.min(max_col);
+
+        let text = self.line_string(buffer);
+        self.column = display::grapheme_start(&text, self.column).min(display::last_grapheme(&text));
     }
 }
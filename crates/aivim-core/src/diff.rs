@@ -0,0 +1,214 @@
+//! 行级差异比较模块
+//!
+//! 用 Myers 算法计算两段文本之间最短的行编辑序列，并生成 unified diff 格式的输出，
+//! 供 :DiffOrig 比较缓冲区和磁盘上的文件使用。
+
+/// 差异中一行的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    /// 两边都有的行
+    Equal,
+    /// 只在旧文本中的行
+    Delete,
+    /// 只在新文本中的行
+    Insert,
+}
+
+/// 差异中的一行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffLine<'a> {
+    pub op: DiffOp,
+    pub text: &'a str,
+    /// 在旧文本中的行号（从 0 开始），Insert 行为 None
+    pub old_line: Option<usize>,
+    /// 在新文本中的行号（从 0 开始），Delete 行为 None
+    pub new_line: Option<usize>,
+}
+
+/// 比较两组行，返回按顺序排列的所有行（包括相同的行）
+pub fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // 先去掉相同的开头和结尾，缩小需要搜索的范围
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut result = Vec::with_capacity(old.len().max(new.len()));
+    let equal = |result: &mut Vec<DiffLine<'a>>, x: usize, y: usize| {
+        result.push(DiffLine {
+            op: DiffOp::Equal,
+            text: old[x],
+            old_line: Some(x),
+            new_line: Some(y),
+        });
+    };
+
+    for i in 0..prefix {
+        equal(&mut result, i, i);
+    }
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    for (op, x, y) in myers(old_mid, new_mid) {
+        let (x, y) = (x + prefix, y + prefix);
+        match op {
+            DiffOp::Equal => equal(&mut result, x, y),
+            DiffOp::Delete => result.push(DiffLine {
+                op,
+                text: old[x],
+                old_line: Some(x),
+                new_line: None,
+            }),
+            DiffOp::Insert => result.push(DiffLine {
+                op,
+                text: new[y],
+                old_line: None,
+                new_line: Some(y),
+            }),
+        }
+    }
+    for i in 0..suffix {
+        equal(&mut result, old.len() - suffix + i, new.len() - suffix + i);
+    }
+    result
+}
+
+/// Myers 差异算法，返回 (操作, 旧文本行号, 新文本行号) 序列
+///
+/// Delete 时新文本行号、Insert 时旧文本行号没有意义
+fn myers(old: &[&str], new: &[&str]) -> Vec<(DiffOp, usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // 记录每一步开始时的 V 数组，用于回溯编辑路径
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace = Vec::new();
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            ops.push((DiffOp::Equal, x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push((DiffOp::Insert, x as usize, prev_y as usize));
+            } else {
+                ops.push((DiffOp::Delete, prev_x as usize, y as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// 生成 unified diff，每处修改前后保留 `context` 行上下文；没有差异时返回 None
+pub fn unified_diff(old_name: &str, new_name: &str, old: &str, new: &str, context: usize) -> Option<String> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    unified_diff_lines(old_name, new_name, &old_lines, &new_lines, context)
+}
+
+/// 与 `unified_diff` 相同，比较已经分好的行（如缓冲区的各行，不需要先复制整个缓冲区）
+pub fn unified_diff_lines(
+    old_name: &str,
+    new_name: &str,
+    old_lines: &[&str],
+    new_lines: &[&str],
+    context: usize,
+) -> Option<String> {
+    let lines = diff_lines(old_lines, new_lines);
+
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.op != DiffOp::Equal)
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return None;
+    }
+
+    // 相距不超过两倍上下文的修改合并为一个块
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        let hunk = &lines[start..end];
+        let old_count = hunk.iter().filter(|l| l.op != DiffOp::Insert).count();
+        let new_count = hunk.iter().filter(|l| l.op != DiffOp::Delete).count();
+        let old_start = hunk_start(&lines[..end], old_count, |l| l.old_line);
+        let new_start = hunk_start(&lines[..end], new_count, |l| l.new_line);
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+        for line in hunk {
+            let prefix = match line.op {
+                DiffOp::Equal => ' ',
+                DiffOp::Delete => '-',
+                DiffOp::Insert => '+',
+            };
+            output.push(prefix);
+            output.push_str(line.text);
+            output.push('\n');
+        }
+    }
+    Some(output)
+}
+
+/// unified diff 块头中的起始行号（从 1 开始）
+///
+/// `upto` 为从文件开头到块末尾的所有行；该侧在块中没有行时取块之前的最后一行（与 diff -u 一致）
+fn hunk_start(upto: &[DiffLine], count: usize, side: impl Fn(&DiffLine) -> Option<usize>) -> usize {
+    let side_lines = upto.iter().filter(|l| side(l).is_some()).count();
+    if count == 0 {
+        side_lines
+    } else {
+        side_lines - count + 1
+    }
+}
@@ -0,0 +1,186 @@
+//! 显示宽度模块
+//!
+//! 缓冲区中的列号按字符计数，屏幕上的列按显示宽度计数：
+//! - Tab 占据到下一个 tabstop 整数倍的列（`:set tabstop=`，默认为 8）
+//! - 中日韩文字等宽字符占两列
+//! - 开启 wrap 时超过屏幕宽度的行折成多个屏幕行，放不下的宽字符移到下一个屏幕行
+//!
+//! 光标按字形簇（grapheme cluster，用户看到的一个字符）移动：带组合符号的字母、
+//! emoji 序列由多个字符组成，光标不会停在它们中间，显示宽度也按整个字形簇计算。
+//!
+//! 界面绘制文本和放置光标、光标移动、编辑器计算 Tab 插入的空格数和 gj / gk 都使用这里的函数。
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// 默认的 Tab 显示宽度
+pub const DEFAULT_TABSTOP: usize = 8;
+
+/// 文本区域中的一个屏幕行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenRow {
+    /// 所在的缓冲区行
+    pub line: usize,
+    /// 是否为该行的第一个屏幕行（行号只显示在第一个屏幕行）
+    pub first: bool,
+    /// 屏幕行第一列在该行中的显示列（折行的后续屏幕行或 nowrap 水平滚动时不为 0）
+    pub column: usize,
+    /// 显示的文本（Tab 已展开）
+    pub text: String,
+    /// 关闭的折叠显示的摘要行：折叠的最后一行（`line` 为折叠的第一行）
+    pub fold_end: Option<usize>,
+}
+
+/// 字符从第 `column` 列（显示宽度）开始时占据的列数
+pub fn char_width(c: char, column: usize, tabstop: usize) -> usize {
+    if c == '\t' {
+        let tabstop = tabstop.max(1);
+        tabstop - column % tabstop
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+/// 字形簇从第 `column` 列开始时占据的列数
+pub fn grapheme_width(grapheme: &str, column: usize, tabstop: usize) -> usize {
+    if grapheme == "\t" {
+        char_width('\t', column, tabstop)
+    } else {
+        grapheme.width()
+    }
+}
+
+/// 文本中的字形簇及其起始的字符下标
+pub fn graphemes(text: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    text.graphemes(true).scan(0, |start, grapheme| {
+        let current = *start;
+        *start += grapheme.chars().count();
+        Some((current, grapheme))
+    })
+}
+
+/// 第 `column` 个字符所在字形簇的起始下标，超过行尾时返回字符数
+pub fn grapheme_start(text: &str, column: usize) -> usize {
+    let mut result = 0;
+    for (start, grapheme) in graphemes(text) {
+        if start > column {
+            break;
+        }
+        result = start + grapheme.chars().count();
+        if result > column {
+            return start;
+        }
+    }
+    result
+}
+
+/// 第 `column` 个字符之后的下一个字形簇的起始下标，已经是最后一个时返回字符数
+pub fn next_grapheme(text: &str, column: usize) -> usize {
+    let mut end = 0;
+    for (start, grapheme) in graphemes(text) {
+        end = start + grapheme.chars().count();
+        if end > column {
+            break;
+        }
+    }
+    end
+}
+
+/// 第 `column` 个字符之前的字形簇的起始下标，已经是第一个时返回 0
+pub fn prev_grapheme(text: &str, column: usize) -> usize {
+    graphemes(text)
+        .map(|(start, _)| start)
+        .take_while(|&start| start < column)
+        .last()
+        .unwrap_or(0)
+}
+
+/// 最后一个字形簇的起始下标，即 Normal 模式下光标在行中的最大列，空行为 0
+pub fn last_grapheme(text: &str) -> usize {
+    graphemes(text).last().map_or(0, |(start, _)| start)
+}
+
+/// 文本前 `chars` 个字符的显示宽度，即第 `chars` 个字符在屏幕上的列
+///
+/// `chars` 在字形簇中间时返回该字形簇的列
+pub fn display_column(text: &str, chars: usize, tabstop: usize) -> usize {
+    graphemes(text)
+        .take_while(|&(start, grapheme)| start + grapheme.chars().count() <= chars)
+        .fold(0, |column, (_, grapheme)| column + grapheme_width(grapheme, column, tabstop))
+}
+
+/// 把 Tab 展开为空格，用于绘制
+pub fn expand_tabs(text: &str, tabstop: usize) -> String {
+    if !text.contains('\t') {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut column = 0;
+    for grapheme in text.graphemes(true) {
+        let width = grapheme_width(grapheme, column, tabstop);
+        if grapheme == "\t" {
+            result.push_str(&" ".repeat(width));
+        } else {
+            result.push_str(grapheme);
+        }
+        column += width;
+    }
+    result
+}
+
+/// 第 `column` 列（显示宽度）所在字形簇的起始字符下标，超过行尾时返回字符数
+pub fn char_at_column(text: &str, column: usize, tabstop: usize) -> usize {
+    let mut current = 0;
+    let mut end = 0;
+    for (start, grapheme) in graphemes(text) {
+        current += grapheme_width(grapheme, current, tabstop);
+        if current > column {
+            return start;
+        }
+        end = start + grapheme.chars().count();
+    }
+    end
+}
+
+/// 把一行文本按屏幕宽度 `width` 折成多个屏幕行（Tab 已展开）
+///
+/// 返回每个屏幕行起始的显示列和文本，空行也有一个屏幕行
+pub fn wrap_line(text: &str, width: usize, tabstop: usize) -> Vec<(usize, String)> {
+    let expanded = expand_tabs(text, tabstop);
+    let width = width.max(1);
+    let mut rows = vec![(0, String::new())];
+    let mut column = 0;
+    let mut row_width = 0;
+    for grapheme in expanded.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if row_width + grapheme_width > width && row_width > 0 {
+            rows.push((column, String::new()));
+            row_width = 0;
+        }
+        if let Some((_, row)) = rows.last_mut() {
+            row.push_str(grapheme);
+        }
+        row_width += grapheme_width;
+        column += grapheme_width;
+    }
+    rows
+}
+
+/// 从第 `left` 列开始截取最多 `width` 列的文本（Tab 已展开），用于不折行时水平滚动
+///
+/// 跨过起始列的宽字符不显示
+pub fn clip_line(text: &str, left: usize, width: usize, tabstop: usize) -> String {
+    let mut result = String::new();
+    let mut column = 0;
+    for grapheme in expand_tabs(text, tabstop).graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if column >= left && column + grapheme_width <= left + width {
+            result.push_str(grapheme);
+        }
+        column += grapheme_width;
+        if column >= left + width {
+            break;
+        }
+    }
+    result
+}
@@ -32,7 +32,7 @@ impl Edit {
             Edit::DeleteChar => {
                 let char_idx = cursor.to_char_idx(buffer);
                 let deleted = buffer.remove_char(char_idx);
-                deleted.map(|ch| EditResult::DeletedChar(ch))
+                deleted.map(EditResult::DeletedChar)
             }
             Edit::DeleteLine => {
                 let line_idx = cursor.line;
@@ -58,8 +58,9 @@ impl Edit {
                 if cursor.column > 0 {
                     let char_idx = cursor.to_char_idx(buffer);
                     let deleted = buffer.remove_char(char_idx - 1);
-                    cursor.move_left(buffer, 1);
-                    deleted.map(|ch| EditResult::DeletedChar(ch))
+                    *cursor = Cursor::from_char_idx(buffer, char_idx - 1);
+                    cursor.update_preferred_column(buffer);
+                    deleted.map(EditResult::DeletedChar)
                 } else if cursor.line > 0 {
                     let current_line = cursor.line;
                     let prev_line_len = buffer.line_len(current_line - 1);
@@ -77,19 +78,18 @@ impl Edit {
             }
             Edit::DeleteWord => {
                 let start_idx = cursor.to_char_idx(buffer);
-                let text = buffer.rope().to_string();
-                
-                if start_idx >= text.len() {
+                if start_idx >= buffer.len_chars() {
                     return None;
                 }
                 
-                let remaining = &text[start_idx..];
-                let mut chars = remaining.chars().peekable();
+                let mut chars = buffer.rope().chars_at(start_idx).peekable();
+                let mut consumed = 0;
                 
                 // 第一阶段：删除单词字符
                 while let Some(&ch) = chars.peek() {
                     if ch.is_alphanumeric() || ch == '_' {
                         chars.next();
+                        consumed += 1;
                     } else {
                         break;
                     }
@@ -103,6 +103,7 @@ impl Edit {
                     }
                     if !ch.is_alphanumeric() && ch != '_' && !ch.is_whitespace() {
                         chars.next();
+                        consumed += 1;
                     } else {
                         break;
                     }
@@ -116,15 +117,16 @@ impl Edit {
                     }
                     if ch.is_whitespace() {
                         chars.next();
+                        consumed += 1;
                     } else {
                         break;
                     }
                 }
                 
-                let consumed = remaining.len() - chars.collect::<String>().len();
                 if consumed > 0 {
+                    let deleted = buffer.slice(start_idx..start_idx + consumed).to_string();
                     buffer.remove(start_idx, consumed);
-                    Some(EditResult::DeletedText(remaining[..consumed].to_string()))
+                    Some(EditResult::DeletedText(deleted))
                 } else {
                     None
                 }
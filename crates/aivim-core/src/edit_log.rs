@@ -0,0 +1,79 @@
+//! 缓冲区的修改记录
+//!
+//! 缓冲区每次插入或删除文本时记下修改的位置，使依赖缓冲区内容的索引（如搜索的匹配）
+//! 只需要重新处理修改过的区域：
+//! - 每条记录对应 changedtick 加一，调用方记住自己处理过的 changedtick，用 `since` 取出之后的修改
+//! - 多条修改合并为一个覆盖所有修改的区域（`TextEdit::then`）
+//! - 只保留最近的 `MAX_EDITS` 条；整体替换内容（重新读入、撤销）时清空，调用方需要重新处理整个缓冲区
+
+use std::collections::VecDeque;
+
+/// 最多保留的修改条数
+const MAX_EDITS: usize = 256;
+
+/// 一处修改：把从 `start` 开始的 `removed` 个字符替换为 `inserted` 个字符（字符索引）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub removed: usize,
+    pub inserted: usize,
+}
+
+impl TextEdit {
+    /// 修改之前被替换区域的结束位置
+    pub fn old_end(&self) -> usize {
+        self.start + self.removed
+    }
+
+    /// 修改之后新文本的结束位置
+    pub fn new_end(&self) -> usize {
+        self.start + self.inserted
+    }
+
+    /// 先进行这处修改、再进行 `next`（位置按这处修改之后的文本计算）等价的一处修改
+    ///
+    /// 合并后的区域覆盖两处修改，可能包括两处之间没有变化的文本
+    pub fn then(self, next: TextEdit) -> TextEdit {
+        let start = self.start.min(next.start);
+        // 两处修改覆盖的区域在中间文本中的结束位置，之后的文本与原文本相同
+        let end = self.new_end().max(next.old_end());
+        let old_end = self.old_end() + (end - self.new_end());
+        let new_end = end - next.removed + next.inserted;
+        TextEdit { start, removed: old_end - start, inserted: new_end - start }
+    }
+}
+
+/// 一个缓冲区最近的修改
+#[derive(Debug, Clone, Default)]
+pub struct EditLog {
+    /// 第一条记录之前的 changedtick
+    base: u64,
+    edits: VecDeque<TextEdit>,
+}
+
+impl EditLog {
+    /// 记下一处修改，调用方同时把 changedtick 加一
+    pub fn record(&mut self, edit: TextEdit) {
+        if self.edits.len() == MAX_EDITS {
+            self.edits.pop_front();
+            self.base += 1;
+        }
+        self.edits.push_back(edit);
+    }
+
+    /// 内容被整体替换，之前的修改不再有意义；`tick` 为替换之后的 changedtick
+    pub fn reset(&mut self, tick: u64) {
+        self.base = tick;
+        self.edits.clear();
+    }
+
+    /// changedtick 为 `tick` 之后的所有修改合并成的一处修改，没有修改时为 `Some(None)`；
+    /// 记录已经不包括 `tick` 之后的全部修改时为 None
+    pub fn since(&self, tick: u64) -> Option<Option<TextEdit>> {
+        let skip = usize::try_from(tick.checked_sub(self.base)?).ok()?;
+        if skip > self.edits.len() {
+            return None;
+        }
+        Some(self.edits.iter().skip(skip).copied().reduce(TextEdit::then))
+    }
+}
@@ -1,15 +1,122 @@
-use crate::buffer::{Buffer, BufferId};
+use crate::buffer::{line_content_len, Buffer, BufferId};
+use crate::autocmd::{self, AutocmdArgs, Autocmds, Event, HoldTimer};
+use crate::colorscheme::{self, Highlight, HighlightError};
+use crate::completion::{CompletionMenu, collect_word_candidates, filter_lsp_items, word_prefix_start};
 use crate::cursor::Cursor;
+use crate::display::{self, ScreenRow, DEFAULT_TABSTOP};
+use crate::encoding::Encoding;
+use crate::positions::FilePositions;
+use crate::input::KeyInput;
+use crate::script::{ScriptInput, ScriptResult, ScriptStep};
+use crate::session;
+use crate::watch::{self, FileWatcher};
 use crate::edit::{Edit, EditResult};
+use crate::expr::ExprError;
+use crate::ex_command::{self, ExCommand, ExError};
+use crate::fillchars::FillChars;
+use crate::fold::{self, FoldMethod};
+use crate::finder::FileFinder;
+use crate::loader::{FileLoad, ASYNC_LOAD_SIZE};
+use crate::grep::GrepSearch;
+use crate::history::History;
+use crate::i18n::Locale;
+use crate::job::{JobEvent, JobId, JobManager, JobSpec, JobStatus};
+use crate::tr;
+use crate::line_ending::{self, LineEnding, LineEndingStats};
+use crate::local_options::{self, BufferOptions, LocalOption, WindowOptions};
+use crate::mapping::{self, KeyMappings, MapCommandKind, MapKey, MapMode, MapScope};
+use crate::keymap::{is_register_name, Operator, OperatorCommand, OperatorTarget};
 use crate::mode::Mode;
-use crate::motion::Motion;
+use crate::multi_cursor::{self, MultiCursor};
+use crate::number;
+use crate::pairs;
+use crate::plugin::{self, Plugin, Plugins};
+use crate::paste::PasteDetector;
+use crate::quickfix::{parse_errors, ListKind, QuickfixList};
+use crate::picker::{Picker, PickerItem};
+use crate::range::{self, LineRange, RangeError};
+use crate::replace::ConfirmSubstitute;
+use crate::motion::{Motion, Viewport};
 use crate::register::RegisterManager;
-use crate::search::{SearchDirection, SearchState};
+use crate::search::{SearchDirection, SearchOffset, SearchState};
+use crate::shell::{self, ShellAction, ShellCommand, TerminalRequest};
+use crate::terminal::TerminalJob;
+use crate::statusline::StatuslineSettings;
+use crate::tail::{TailState, TailUpdate};
 use crate::text_object::TextObject;
+use crate::tabpage::{TabLabel, TabPages};
+use crate::waker::Waker;
+use crate::window::{Side, SplitDirection, WindowId, WindowManager, WindowRect};
+use crate::visual::VisualSelection;
+use crate::undo_tree::{UndoLimits, UndoState, UndoTree, DEFAULT_UNDO_LEVELS, DEFAULT_UNDO_MAX_MEM};
 use crate::with_save_state;
+use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use aivim_ai::{AiConfig, AiSettings, ProviderKind};
+use aivim_lsp::LspSettings;
+
+/// 默认缩进宽度（expandtab 时 Insert 模式下 Tab 插入的空格与之一致）
+pub const DEFAULT_SHIFTWIDTH: usize = 4;
+
+/// 映射前缀等待后续按键的默认时间（毫秒）
+pub const DEFAULT_TIMEOUTLEN: usize = 1000;
+
+/// Normal 模式下光标停留多久之后触发 CursorHold 的默认时间（毫秒）
+pub const DEFAULT_UPDATETIME: usize = 4000;
+
+/// Insert 模式下光标停留多久之后请求 AI 补全的默认时间（毫秒）
+pub const DEFAULT_AIDELAY: usize = 300;
+
+/// 界面设置之前使用的文本区域大小（80x24 的终端去掉命令行和状态栏）
+const DEFAULT_TEXT_SIZE: (usize, usize) = (80, 22);
+
+/// textwidth 为 0 时 gq 重排文本的每行最大显示宽度
+const FORMAT_WIDTH: usize = 79;
+
+/// :set all 列出的选项，按名字排序
+const ALL_OPTIONS: &[&str] = &[
+    "aicomplete",
+    "aidelay",
+    "aiendpoint",
+    "aikeyenv",
+    "aimodel",
+    "aiprovider",
+    "asciiglyphs",
+    "autoindent",
+    "autopairs",
+    "autoread",
+    "cursorcolumn",
+    "cursorline",
+    "expandtab",
+    "fileencoding",
+    "fileformat",
+    "fillchars",
+    "foldmethod",
+    "gitsigns",
+    "language",
+    "lsp",
+    "makeprg",
+    "number",
+    "paste",
+    "pastedetect",
+    "protectedpaths",
+    "relativenumber",
+    "shiftwidth",
+    "tabstop",
+    "textwidth",
+    "timeoutlen",
+    "trash",
+    "undodir",
+    "undofile",
+    "undolevels",
+    "undomaxmem",
+    "updatetime",
+    "wrap",
+];
 
 /// 编辑器配置选项
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -20,6 +127,54 @@ pub struct EditorOptions {
     pub relativenumber: bool,
     /// 高亮当前行
     pub cursorline: bool,
+    /// 高亮光标所在列
+    pub cursorcolumn: bool,
+    /// 删除文件时移到回收站而不是直接删除
+    pub trash: bool,
+    /// > 和 < 每次增加或减少的空格数
+    pub shiftwidth: usize,
+    /// Tab 字符的显示宽度
+    pub tabstop: usize,
+    /// Insert 模式下 Tab 键插入空格（到下一个 shiftwidth 整数倍的列）而不是制表符
+    pub expandtab: bool,
+    /// 超过屏幕宽度的行折成多个屏幕行，关闭时水平滚动
+    pub wrap: bool,
+    /// 新行自动复制当前行的缩进
+    pub autoindent: bool,
+    /// 粘贴模式：原样插入输入的文本，暂停自动缩进和 Insert 模式映射
+    pub paste: bool,
+    /// 按键速度明显快于手动输入时自动开启粘贴模式
+    pub pastedetect: bool,
+    /// Insert 模式下自动插入右括号和引号（见 pairs 模块）
+    pub autopairs: bool,
+    /// 分隔线、折叠标记、缓冲区末尾 `~` 等界面填充字符
+    pub fillchars: FillChars,
+    /// 终端字体缺少 Unicode 框线字符时使用 ASCII 字符
+    pub asciiglyphs: bool,
+    /// 每个缓冲区最多保留的撤销次数
+    pub undolevels: usize,
+    /// 每个缓冲区撤销历史的内存上限（MB）
+    pub undomaxmem: usize,
+    /// 保存文件时把撤销历史写入撤销目录，打开文件时恢复
+    pub undofile: bool,
+    /// gq 重排文本时每行的最大显示宽度，0 时为 79
+    pub textwidth: usize,
+    /// 映射前缀等待后续按键的最长时间（毫秒）
+    pub timeoutlen: usize,
+    /// Normal 模式下光标停留多久之后触发 CursorHold 自动命令（毫秒）
+    pub updatetime: usize,
+    /// 监视打开的文件，文件在外部被修改且缓冲区没有修改时自动重新读入
+    pub autoread: bool,
+    /// Insert 模式下请求 AI 补全，以 ghost text 显示在光标之后（见 suggestion 模块）
+    pub aicomplete: bool,
+    /// 光标停留多久之后请求 AI 补全（毫秒）
+    pub aidelay: usize,
+    /// 为打开的文件启动语言服务器，显示诊断（见 lsp 模块）
+    pub lsp: bool,
+    /// 在标记列中显示缓冲区与 HEAD 的差异（见 git_signs 模块）
+    pub gitsigns: bool,
+    /// 折叠方式：手动、按缩进或按语法树（见 fold 模块）
+    pub foldmethod: FoldMethod,
 }
 
 impl Default for EditorOptions {
@@ -28,10 +183,187 @@ impl Default for EditorOptions {
             number: false,
             relativenumber: false,
             cursorline: false,
+            cursorcolumn: false,
+            trash: false,
+            shiftwidth: DEFAULT_SHIFTWIDTH,
+            tabstop: DEFAULT_TABSTOP,
+            expandtab: true,
+            wrap: true,
+            autoindent: false,
+            paste: false,
+            pastedetect: true,
+            autopairs: false,
+            fillchars: FillChars::default(),
+            asciiglyphs: false,
+            undolevels: DEFAULT_UNDO_LEVELS,
+            undomaxmem: DEFAULT_UNDO_MAX_MEM,
+            undofile: false,
+            textwidth: 0,
+            timeoutlen: DEFAULT_TIMEOUTLEN,
+            updatetime: DEFAULT_UPDATETIME,
+            autoread: false,
+            aicomplete: false,
+            aidelay: DEFAULT_AIDELAY,
+            lsp: false,
+            gitsigns: false,
+            foldmethod: FoldMethod::Manual,
         }
     }
 }
 
+/// zt / zz / zb 把光标所在行滚动到的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollAnchor {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// 等待用户确认的操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingConfirmation {
+    /// 删除磁盘文件（:Delete）
+    DeleteFile(PathBuf),
+    /// 写入受保护的路径（:w）
+    WriteProtected(PathBuf),
+    /// 统一行尾符（:fixlineendings），记录目标行尾和需要修改的行数
+    FixLineEndings { target: LineEnding, lines: usize },
+    /// 写入时把文件编码无法表示的字符替换为 `?`（:w），`path` 为 None 时写入原文件
+    LossyWrite { path: Option<PathBuf>, encoding: Encoding, chars: usize },
+    /// 覆盖读入之后在外部被修改过的文件（:w）
+    OverwriteChanged(PathBuf),
+    /// 插件的 y/n 提示（如 :AiEdit 应用修改），回答交给插件的 `on_confirm`
+    Plugin { plugin: TypeId, prompt: String },
+    /// 覆盖只读缓冲区的文件（:w）
+    WriteReadOnly(PathBuf),
+    /// 退出前依次询问是否保存有修改的缓冲区（:q、:qa），`buffers` 的第一个为正在询问的缓冲区，`name` 为它的名字
+    SaveBeforeQuit { buffers: Vec<BufferId>, name: String },
+    /// 逐个确认的替换（:s/old/new/c），当前候选匹配在缓冲区中高亮
+    Substitute(ConfirmSubstitute),
+}
+
+/// 确认提示的回答
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmAnswer {
+    /// y - 执行
+    Yes,
+    /// n - 不执行这一项，继续询问下一项
+    No,
+    /// a - 执行这一项和剩下的所有项
+    All,
+    /// q - 取消，不再询问剩下的项
+    Quit,
+    /// l - 执行这一项之后停止
+    Last,
+}
+
+impl ConfirmAnswer {
+    /// 按键对应的回答
+    pub fn from_key(key: char) -> Option<Self> {
+        match key.to_ascii_lowercase() {
+            'y' => Some(ConfirmAnswer::Yes),
+            'n' => Some(ConfirmAnswer::No),
+            'a' => Some(ConfirmAnswer::All),
+            'q' => Some(ConfirmAnswer::Quit),
+            'l' => Some(ConfirmAnswer::Last),
+            _ => None,
+        }
+    }
+
+    /// 回答的按键
+    pub fn key(&self) -> char {
+        match self {
+            ConfirmAnswer::Yes => 'y',
+            ConfirmAnswer::No => 'n',
+            ConfirmAnswer::All => 'a',
+            ConfirmAnswer::Quit => 'q',
+            ConfirmAnswer::Last => 'l',
+        }
+    }
+
+    /// 提示中显示的说明
+    pub fn label(&self, locale: Locale) -> &'static str {
+        match self {
+            ConfirmAnswer::Yes => crate::i18n::translate(locale, "Yes"),
+            ConfirmAnswer::No => crate::i18n::translate(locale, "No"),
+            ConfirmAnswer::All => crate::i18n::translate(locale, "All"),
+            ConfirmAnswer::Quit => crate::i18n::translate(locale, "Quit"),
+            ConfirmAnswer::Last => crate::i18n::translate(locale, "Last"),
+        }
+    }
+}
+
+impl PendingConfirmation {
+    /// 可以选择的回答：只有 y / n 时其他按键都是取消
+    pub fn choices(&self) -> &'static [ConfirmAnswer] {
+        match self {
+            PendingConfirmation::SaveBeforeQuit { .. } => {
+                &[ConfirmAnswer::Yes, ConfirmAnswer::No, ConfirmAnswer::All, ConfirmAnswer::Quit]
+            }
+            PendingConfirmation::Substitute(_) => &[
+                ConfirmAnswer::Yes,
+                ConfirmAnswer::No,
+                ConfirmAnswer::All,
+                ConfirmAnswer::Quit,
+                ConfirmAnswer::Last,
+            ],
+            _ => &[ConfirmAnswer::Yes, ConfirmAnswer::No],
+        }
+    }
+
+    /// 显示在命令行的确认提示
+    pub fn prompt(&self, locale: Locale) -> String {
+        match self {
+            PendingConfirmation::DeleteFile(path) => {
+                tr!(locale, "Delete file {}? (y/n)", path.display())
+            }
+            PendingConfirmation::WriteProtected(path) => {
+                tr!(locale, "{} is a protected path, write anyway? (y/n)", path.display())
+            }
+            PendingConfirmation::FixLineEndings { target, lines } => {
+                tr!(locale, "Convert {} line(s) to {}? (y/n)", lines, target.name())
+            }
+            PendingConfirmation::LossyWrite { encoding, chars, .. } => {
+                tr!(locale, "{} character(s) cannot be encoded as {}, replace them with '?'? (y/n)", chars, encoding.name())
+            }
+            PendingConfirmation::OverwriteChanged(path) => {
+                tr!(locale, "{} has changed on disk since reading it, overwrite it? (y/n)", path.display())
+            }
+            PendingConfirmation::Plugin { prompt, .. } => prompt.clone(),
+            PendingConfirmation::WriteReadOnly(path) => {
+                tr!(locale, "{} is read-only, write anyway? (y/n)", path.display())
+            }
+            PendingConfirmation::SaveBeforeQuit { name, .. } => {
+                tr!(locale, "Save changes to {}? (y/n/a/q)", name)
+            }
+            PendingConfirmation::Substitute(state) => {
+                tr!(locale, "Replace with {}? (y/n/a/q/l)", state.replacement)
+            }
+        }
+    }
+}
+
+/// :normal 命令要回放的按键，由界面按 Normal 模式处理
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalRequest {
+    /// 依次在范围内每一行的行首回放；None 表示在光标处回放一次
+    pub range: Option<LineRange>,
+    pub keys: Vec<MapKey>,
+    /// 是否展开用户映射（`:normal!` 不展开）
+    pub remap: bool,
+}
+
+/// Insert 模式中插件提供的补全（如语言服务器）：等待插件的候选项，或按输入的前缀过滤候选项
+#[derive(Debug)]
+struct PluginCompletion {
+    buffer: BufferId,
+    line: usize,
+    /// 插件提供的候选项，还在等待时为 None
+    items: Option<Vec<aivim_lsp::CompletionItem>>,
+    /// 手动触发（Ctrl+Space），没有候选项时提示
+    manual: bool,
+}
+
 pub struct Editor {
     buffers: HashMap<BufferId, Buffer>,
     current_buffer: BufferId,
@@ -43,25 +375,138 @@ pub struct Editor {
     command_line: String,
     message: Option<String>,
     register_manager: RegisterManager,
-    undo_stack: Vec<EditState>,
-    redo_stack: Vec<EditState>,
+    // 每个缓冲区的撤销树
+    undo_trees: HashMap<BufferId, UndoTree>,
     search_state: SearchState,
     // UI 状态
     show_buffer_list: bool,
     show_registers_panel: bool,
     // 编辑器配置
     options: EditorOptions,
-}
-
-#[derive(Clone)]
-struct EditState {
-    buffer_content: String,
-    cursor: Cursor,
-    file_path: Option<std::path::PathBuf>,
+    /// 缓冲区局部选项 (:setlocal)，窗口局部选项在 Window 中
+    buffer_options: HashMap<BufferId, BufferOptions>,
+    // 等待确认的操作
+    pending_confirmation: Option<PendingConfirmation>,
+    /// 退出前保存的缓冲区需要确认时，确认并保存之后继续退出：（从正在保存的缓冲区开始的缓冲区, 是否保存所有）
+    quit_after_write: Option<(Vec<BufferId>, bool)>,
+    // 写入前需要确认的路径模式
+    protected_paths: Vec<String>,
+    /// 撤销文件所在的目录（undofile）
+    undo_dir: PathBuf,
+    /// 记住文件上次光标位置的状态文件，None 时不记录（见 positions 模块）
+    positions_file: Option<PathBuf>,
+    /// 只读模式（aivim -R）：打开的缓冲区都是只读的
+    read_only_mode: bool,
+    // Insert 模式补全菜单
+    completion: Option<CompletionMenu>,
+    plugin_completion: Option<PluginCompletion>,
+    /// 模糊选择器（如 :symbols）
+    picker: Option<Picker>,
+    /// 文件选择器在后台查找文件的任务，选择器关闭时丢弃
+    finder: Option<FileFinder>,
+    /// Quickfix 列表（:grep、:make 的结果）
+    quickfix: QuickfixList,
+    /// 正在后台进行的 :grep，结果陆续加入 Quickfix 列表
+    grep: Option<GrepSearch>,
+    /// 正在后台读入的大文件 (:e)
+    file_load: Option<FileLoad>,
+    /// 超过这个大小（字节）的文件在后台读入
+    async_load_size: u64,
+    /// 列表面板显示的列表 (:copen / :lopen)，None 表示面板关闭
+    quickfix_panel: Option<ListKind>,
+    /// 列表面板是否有焦点（按键用于在列表中选择）
+    quickfix_focused: bool,
+    /// :make 运行的命令 (makeprg)
+    make_program: String,
+    /// 正在跟踪的文件（:tail）
+    tail: Option<TailState>,
+    /// 开启 autoread 时监视打开的文件
+    watcher: Option<FileWatcher>,
+    /// 后台任务（外部进程）
+    jobs: JobManager,
+    /// 后台线程产生结果时唤醒界面的事件循环
+    waker: Waker,
+    show_jobs_panel: bool,
+    /// 显示所有选项的面板 (:set all)
+    show_options_panel: bool,
+    /// 用户按键映射（:map 等）
+    mappings: KeyMappings,
+    /// 映射中 `<Leader>` 代表的按键（:let mapleader）
+    map_leader: Vec<MapKey>,
+    /// 当前窗口文本区域的（宽, 高），界面在处理按键前设置；gj / gk、滚动和翻页按这个大小计算
+    text_size: (usize, usize),
+    /// 窗口布局；当前窗口的光标和缓冲区保存在 cursor / current_buffer 中
+    windows: WindowManager,
+    /// 其他标签页的窗口布局
+    tabs: TabPages,
+    /// 当前的配色方案（:colorscheme）
+    colorscheme: &'static str,
+    /// :highlight 对高亮组的修改，按组名合并
+    highlights: Vec<(&'static str, Highlight)>,
+    /// Replace 模式下每个输入字符覆盖的原字符（None 表示新插入的字符），Backspace 时恢复
+    replace_stack: Vec<Option<char>>,
+    /// 界面语言
+    locale: Locale,
+    /// 粘贴检测
+    paste_detector: PasteDetector,
+    /// 粘贴模式是否由检测自动开启（粘贴结束后自动关闭）
+    auto_paste: bool,
+    /// 本次 Insert 会话输入的文本，离开 Insert 模式时存入 . 寄存器
+    inserted_text: String,
+    /// 主光标之外的其他光标（Ctrl+N 添加）
+    multi_cursor: Option<MultiCursor>,
+    /// Visual 模式的锚点和是否按行选择 (v / V)
+    visual_start: Option<(Cursor, bool)>,
+    /// 每个缓冲区离开 Visual 模式时的选择区域 (gv)
+    last_visual: HashMap<BufferId, VisualSelection>,
+    /// 正在对多个光标执行同一个命令，已经保存过撤销状态
+    undo_grouped: bool,
+    /// 用户注册的自动命令 (:autocmd)
+    autocmds: Autocmds,
+    /// 正在执行自动命令，其中再触发的事件不执行自动命令
+    autocmd_running: bool,
+    /// CursorHold 的计时
+    cursor_hold: HoldTimer,
+    /// 注册的插件（内置的 Git、LSP、AI 和第三方插件）
+    plugins: Plugins,
+    /// 是否正在命令行输入表达式寄存器的表达式（Insert 模式下 Ctrl+R =）
+    expression_prompt: bool,
+    /// 执行过的 : 命令
+    command_history: History,
+    /// 执行过的 / ? 搜索
+    search_history: History,
+    /// 执行了退出命令，由界面负责退出
+    quit_requested: bool,
+    /// 等待界面回放的 :normal 按键
+    normal_request: Option<NormalRequest>,
+    /// 等待界面交出终端的请求（:shell、Ctrl+Z）
+    terminal_request: Option<TerminalRequest>,
+    /// 正在运行的外部命令（:!cmd 等），结束后处理输出
+    shell_commands: HashMap<JobId, ShellCommand>,
+    /// 终端缓冲区中运行的程序 (:terminal)
+    terminals: HashMap<BufferId, TerminalJob>,
+    /// AI 服务设置（:set aiendpoint 等）
+    ai_config: AiConfig,
+    /// config.toml 中各 AI 服务的设置，`:set aiprovider` 切换服务时使用
+    ai_settings: AiSettings,
+    /// config.toml 中各语言的服务器设置，由 lsp 插件使用
+    lsp_settings: LspSettings,
+    /// 状态栏的片段（config.toml 的 [statusline]）
+    statusline: StatuslineSettings,
+    /// 正在录制的宏：寄存器和录制的按键（q{register}）
+    recording: Option<(char, Vec<MapKey>)>,
+    /// 上一次执行的宏所在的寄存器（`@@`）
+    last_macro: Option<char>,
 }
 
 impl Editor {
+    /// 注册了所有内置插件的编辑器
     pub fn new() -> Self {
+        Self::with_plugins(plugin::builtin_plugins()).expect("builtin plugin commands are unique")
+    }
+
+    /// 只注册 `plugins` 的编辑器，没有注册的内置插件的功能不可用
+    pub fn with_plugins(plugins: Vec<Box<dyn Plugin>>) -> Result<Self, String> {
         let mut buffers = HashMap::new();
         let mut buffer_cursors = HashMap::new();
         let initial_buffer = Buffer::new(BufferId::new(0));
@@ -69,7 +514,7 @@ impl Editor {
         buffers.insert(buffer_id, initial_buffer);
         buffer_cursors.insert(buffer_id, Cursor::at_origin());
 
-        Self {
+        let mut editor = Self {
             buffers,
             current_buffer: buffer_id,
             cursor: Cursor::at_origin(),
@@ -79,13 +524,77 @@ impl Editor {
             command_line: String::new(),
             message: None,
             register_manager: RegisterManager::new(),
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
+            undo_trees: HashMap::new(),
             search_state: SearchState::new(),
             show_buffer_list: false,
             show_registers_panel: false,
             options: EditorOptions::default(),
+            pending_confirmation: None,
+            quit_after_write: None,
+            protected_paths: crate::file_ops::DEFAULT_PROTECTED_PATHS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            undo_dir: crate::undo_file::default_undo_dir(),
+            positions_file: None,
+            read_only_mode: false,
+            completion: None,
+            plugin_completion: None,
+            picker: None,
+            finder: None,
+            quickfix: QuickfixList::default(),
+            grep: None,
+            file_load: None,
+            async_load_size: ASYNC_LOAD_SIZE,
+            quickfix_panel: None,
+            quickfix_focused: false,
+            make_program: "make".to_string(),
+            tail: None,
+            watcher: None,
+            jobs: JobManager::new(),
+            waker: Waker::default(),
+            show_jobs_panel: false,
+            show_options_panel: false,
+            mappings: KeyMappings::new(),
+            map_leader: vec![mapping::DEFAULT_LEADER],
+            text_size: DEFAULT_TEXT_SIZE,
+            windows: WindowManager::new(buffer_id),
+            tabs: TabPages::new(),
+            colorscheme: colorscheme::DEFAULT_COLORSCHEME,
+            highlights: Vec::new(),
+            replace_stack: Vec::new(),
+            locale: Locale::default(),
+            paste_detector: PasteDetector::new(),
+            auto_paste: false,
+            inserted_text: String::new(),
+            multi_cursor: None,
+            visual_start: None,
+            last_visual: HashMap::new(),
+            undo_grouped: false,
+            autocmds: Autocmds::new(),
+            autocmd_running: false,
+            cursor_hold: HoldTimer::default(),
+            plugins: Plugins::new(),
+            expression_prompt: false,
+            command_history: History::default(),
+            search_history: History::default(),
+            quit_requested: false,
+            normal_request: None,
+            terminal_request: None,
+            shell_commands: HashMap::new(),
+            terminals: HashMap::new(),
+            buffer_options: HashMap::new(),
+            ai_config: AiConfig::default(),
+            ai_settings: AiSettings::default(),
+            lsp_settings: LspSettings::default(),
+            statusline: StatuslineSettings::default(),
+            recording: None,
+            last_macro: None,
+        };
+        for plugin in plugins {
+            editor.register_plugin(plugin)?;
         }
+        Ok(editor)
     }
 
     pub fn with_file(path: &Path) -> io::Result<Self> {
@@ -102,6 +611,11 @@ impl Editor {
         self.buffers.get_mut(&self.current_buffer).unwrap()
     }
 
+    /// 所有缓冲区，顺序不定
+    pub fn buffers(&self) -> impl Iterator<Item = &Buffer> {
+        self.buffers.values()
+    }
+
     pub fn buffers_mut(&mut self) -> &mut HashMap<BufferId, Buffer> {
         &mut self.buffers
     }
@@ -123,12 +637,38 @@ impl Editor {
     }
 
     pub fn set_mode(&mut self, mode: Mode) {
-        // 如果从 Normal 模式进入 Insert 模式，保存状态用于撤销
-        // 这样整个 Insert 会话可以作为一个单元撤销
-        if self.mode == Mode::Normal && mode == Mode::Insert {
+        // 进入 Insert/Replace 模式时保存状态用于撤销，
+        // 会话中的修改不再单独记录，整个 Insert 会话作为一个单元撤销
+        if !self.mode.is_insert() && mode.is_insert() {
             self.save_state();
+            self.inserted_text.clear();
+        }
+        // 离开 Insert 模式时记录本次输入的文本 (". 寄存器)
+        if self.mode.is_insert() && !mode.is_insert() {
+            self.register_manager.set_readonly('.', self.inserted_text.clone());
+            self.expression_prompt = false;
+        }
+        if mode != Mode::Replace {
+            self.replace_stack.clear();
         }
+        // 离开 Insert 模式时结束自动检测到的粘贴
+        if !mode.is_insert() {
+            self.paste_detector.reset();
+            self.stop_auto_paste();
+        }
+        // 离开 Insert 模式时关闭补全菜单
+        if !mode.is_insert() {
+            self.cancel_completion();
+        }
+        let event = match (self.mode.is_insert(), mode.is_insert()) {
+            (false, true) => Some(Event::InsertEnter),
+            (true, false) => Some(Event::InsertLeave),
+            _ => None,
+        };
         self.mode = mode;
+        if let Some(event) = event {
+            self.emit_event(event, self.current_buffer);
+        }
     }
 
     pub fn command_line(&self) -> &str {
@@ -139,6 +679,43 @@ impl Editor {
         &mut self.command_line
     }
 
+    pub fn command_history(&self) -> &History {
+        &self.command_history
+    }
+
+    pub fn search_history(&self) -> &History {
+        &self.search_history
+    }
+
+    /// 记录执行过的 : 命令
+    pub fn add_command_history(&mut self, command: &str) {
+        self.command_history.push(command);
+    }
+
+    /// 命令行显示上一条以已输入文本为前缀的历史（Command / 搜索模式下的上方向键）
+    pub fn history_prev(&mut self) {
+        let current = self.command_line.clone();
+        if let Some(text) = self.active_history_mut().and_then(|history| history.prev(&current)) {
+            self.command_line = text;
+        }
+    }
+
+    /// 命令行显示下一条历史，越过最新的一条时恢复已输入的文本（下方向键）
+    pub fn history_next(&mut self) {
+        let current = self.command_line.clone();
+        if let Some(text) = self.active_history_mut().and_then(|history| history.next(&current)) {
+            self.command_line = text;
+        }
+    }
+
+    fn active_history_mut(&mut self) -> Option<&mut History> {
+        match self.mode {
+            Mode::Command => Some(&mut self.command_history),
+            Mode::SearchForward | Mode::SearchBackward => Some(&mut self.search_history),
+            _ => None,
+        }
+    }
+
     pub fn message(&self) -> Option<&str> {
         self.message.as_deref()
     }
@@ -159,6 +736,26 @@ impl Editor {
         &mut self.options
     }
 
+    /// 窗口 `id` 中生效的选项：全局值被窗口和它的缓冲区的局部值覆盖
+    pub fn window_options(&self, id: WindowId) -> EditorOptions {
+        let Some((window, buffer)) = self.windows.get(id).zip(self.window_view(id)) else {
+            return self.options;
+        };
+        let buffer = self.buffer_options.get(&buffer.0.id()).copied().unwrap_or_default();
+        local_options::effective(&self.options, &window.options, &buffer)
+    }
+
+    /// 当前窗口中生效的选项，见 window_options
+    pub fn current_options(&self) -> EditorOptions {
+        self.window_options(self.windows.current_id())
+    }
+
+    /// 切换界面字符集，用 :set fillchars 设置过的字符保持不变
+    pub fn set_ascii_glyphs(&mut self, ascii: bool) {
+        self.options.asciiglyphs = ascii;
+        self.options.fillchars = self.options.fillchars.with_defaults(ascii);
+    }
+
     pub fn show_buffer_list(&self) -> bool {
         self.show_buffer_list
     }
@@ -175,66 +772,185 @@ impl Editor {
         self.show_registers_panel = show;
     }
 
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// 翻译界面文本（英文原文作为键）
+    pub fn tr(&self, msg: &'static str) -> &'static str {
+        crate::i18n::translate(self.locale, msg)
+    }
+
+    pub fn show_jobs_panel(&self) -> bool {
+        self.show_jobs_panel
+    }
+
+    pub fn set_show_jobs_panel(&mut self, show: bool) {
+        self.show_jobs_panel = show;
+    }
+
+    pub fn show_options_panel(&self) -> bool {
+        self.show_options_panel
+    }
+
+    pub fn set_show_options_panel(&mut self, show: bool) {
+        self.show_options_panel = show;
+    }
+
+    /// 修改前把当前文本记录到撤销树
+    ///
+    /// Insert 会话中不记录：进入 Insert 模式时已经保存了会话开始前的状态
     pub fn save_state(&mut self) {
-        let buffer = self.current_buffer();
-        let state = EditState {
-            buffer_content: buffer.to_string(),
-            cursor: self.cursor,
-            file_path: buffer.file_path().map(|p| p.to_path_buf()),
-        };
-        self.undo_stack.push(state);
-        self.redo_stack.clear();
+        if self.mode.is_insert() || self.undo_grouped {
+            return;
+        }
+        let limits = self.undo_limits();
+        let buffer = &self.buffers[&self.current_buffer];
+        self.undo_trees
+            .entry(self.current_buffer)
+            .or_insert_with(|| UndoTree::with_limits(limits))
+            .record(buffer.rope(), self.cursor);
     }
 
-    pub fn undo(&mut self) {
-        if let Some(state) = self.undo_stack.pop() {
-            let current_buffer = self.current_buffer();
-            let current_state = EditState {
-                buffer_content: current_buffer.to_string(),
-                cursor: self.cursor,
-                file_path: current_buffer.file_path().map(|p| p.to_path_buf()),
-            };
-            self.redo_stack.push(current_state);
+    /// 由 undolevels 和 undomaxmem 选项决定的撤销历史限制
+    fn undo_limits(&self) -> UndoLimits {
+        UndoLimits {
+            levels: self.options.undolevels,
+            max_bytes: self.options.undomaxmem.saturating_mul(1024 * 1024),
+        }
+    }
 
-            let current_buffer_id = self.current_buffer;
-            let buffer = self.buffers.get_mut(&current_buffer_id).unwrap();
-            *buffer = Buffer::new(current_buffer_id);
-            buffer.insert(0, &state.buffer_content);
-            // 恢复文件路径
-            if let Some(path) = state.file_path {
-                buffer.set_file_path(path);
-            }
-            self.cursor = state.cursor;
+    /// 修改限制后立即淘汰所有缓冲区超出的历史
+    fn apply_undo_limits(&mut self) {
+        let limits = self.undo_limits();
+        for tree in self.undo_trees.values_mut() {
+            tree.set_limits(limits);
         }
     }
 
+    pub fn undo(&mut self) {
+        self.undo_steps(1);
+    }
+
     pub fn redo(&mut self) {
-        if let Some(state) = self.redo_stack.pop() {
-            let current_buffer = self.current_buffer();
-            let current_state = EditState {
-                buffer_content: current_buffer.to_string(),
-                cursor: self.cursor,
-                file_path: current_buffer.file_path().map(|p| p.to_path_buf()),
-            };
-            self.undo_stack.push(current_state);
+        self.redo_steps(1);
+    }
+
+    /// 撤销 `count` 次修改 (Nu)，到达最早的状态时停止
+    pub fn undo_steps(&mut self, count: usize) {
+        self.navigate_undo_tree(
+            |tree, text, cursor| repeat_undo_step(tree, text, cursor, count, UndoTree::undo),
+            "Already at oldest change",
+        );
+    }
+
+    /// 重做 `count` 次修改 (N Ctrl+R)，到达最新的状态时停止
+    pub fn redo_steps(&mut self, count: usize) {
+        self.navigate_undo_tree(
+            |tree, text, cursor| repeat_undo_step(tree, text, cursor, count, UndoTree::redo),
+            "Already at newest change",
+        );
+    }
+
+    /// 按时间顺序在撤销树中前后移动 `steps` 个状态 (g- / g+)，可以跨越分支
+    pub fn undo_travel(&mut self, steps: isize) {
+        let limit = if steps < 0 {
+            "Already at oldest change"
+        } else {
+            "Already at newest change"
+        };
+        self.navigate_undo_tree(|tree, text, cursor| tree.travel(text, cursor, steps), limit);
+    }
 
-            let current_buffer_id = self.current_buffer;
-            let buffer = self.buffers.get_mut(&current_buffer_id).unwrap();
-            *buffer = Buffer::new(current_buffer_id);
-            buffer.insert(0, &state.buffer_content);
-            // 恢复文件路径
-            if let Some(path) = state.file_path {
-                buffer.set_file_path(path);
+    fn navigate_undo_tree(
+        &mut self,
+        step: impl FnOnce(&mut UndoTree, &ropey::Rope, Cursor) -> Option<UndoState>,
+        limit: &'static str,
+    ) {
+        // 撤销后其他光标的位置不再有意义，只保留主光标
+        self.multi_cursor = None;
+        let buffer_id = self.current_buffer;
+        let limits = self.undo_limits();
+        let tree = self
+            .undo_trees
+            .entry(buffer_id)
+            .or_insert_with(|| UndoTree::with_limits(limits));
+        let buffer = self.buffers.get_mut(&buffer_id).unwrap();
+        match step(tree, buffer.rope(), self.cursor) {
+            Some(state) => {
+                buffer.set_rope(state.text);
+                self.cursor = state.cursor;
+            }
+            None => {
+                let message = self.tr(limit);
+                self.set_message(message);
             }
-            self.cursor = state.cursor;
         }
     }
 
+    /// :undolist 的内容：每个分支末端的编号、修改次数和时间
+    pub fn format_undo_list(&self) -> String {
+        let leaves = self
+            .undo_trees
+            .get(&self.current_buffer)
+            .map(UndoTree::leaves)
+            .unwrap_or_default();
+        if leaves.is_empty() {
+            return tr!(self.locale, "Nothing to undo");
+        }
+        let now = Instant::now();
+        let mut entries = vec![tr!(self.locale, "number changes when")];
+        entries.extend(leaves.iter().map(|leaf| {
+            let seconds = now.saturating_duration_since(leaf.time).as_secs();
+            format!("{} {} {}", leaf.seq, leaf.changes, tr!(self.locale, "{}s ago", seconds))
+        }));
+        entries.join(" | ")
+    }
+
+    /// 记住光标当前的屏幕列，之后 j / k 保持在这一列
+    fn update_preferred_column(&mut self) {
+        self.cursor.update_preferred_column(&self.buffers[&self.current_buffer]);
+    }
+
     pub fn execute_motion(&mut self, motion: Motion) {
+        self.update_folds();
+        let viewport = self.viewport();
         let buffer = self.buffers.get(&self.current_buffer).unwrap();
+        let folds = buffer.folds();
         let mut cursor = self.cursor;
-        motion.execute(&mut cursor, buffer);
+        // 上下移动时关闭的折叠只算一行：从折叠的最后一行向下、第一行向上移动，停在折叠上时位于第一行（翻半页相同）
+        let vertical = matches!(motion, Motion::Up | Motion::Down);
+        if let Some((start, end)) = folds.closed_range(cursor.line).filter(|_| vertical) {
+            cursor.line = if motion == Motion::Down { end } else { start };
+        }
+        motion.execute_in(&mut cursor, buffer, &viewport);
+        let half_page = matches!(motion, Motion::PageDown | Motion::PageUp);
+        if let Some((start, _)) = folds.closed_range(cursor.line).filter(|_| vertical || half_page) {
+            cursor.line = start;
+            cursor.restore_preferred_column(buffer);
+        }
+        if let Some((start, _)) = folds.closed_range(cursor.line).filter(|_| motion.uses_viewport()) {
+            cursor.line = start;
+            Motion::FirstNonBlank.execute(&mut cursor, buffer);
+        }
+        let moved = cursor.line as isize - self.cursor.line as isize;
         self.cursor = cursor;
+        // 翻页时光标所在行滚动到窗口顶部 (Ctrl+F) 或底部 (Ctrl+B)，翻半页时窗口和光标滚动相同的行数
+        let (width, height) = self.text_size;
+        match motion {
+            Motion::PageDown | Motion::PageUp => {
+                let last = self.current_buffer().last_content_line() as isize;
+                let offset = (viewport.top as isize + moved).clamp(0, last);
+                self.set_scroll_offset(offset as usize);
+            }
+            Motion::PageForward => self.set_scroll_offset(self.cursor.line),
+            Motion::PageBackward if viewport.top > 0 => self.scroll_cursor_to(ScrollAnchor::Bottom, width, height),
+            _ => {}
+        }
     }
 
     pub fn execute_edit(&mut self, edit: Edit) -> Option<EditResult> {
@@ -246,6 +962,11 @@ impl Editor {
 
     pub fn insert_char(&mut self, ch: char) {
         if self.mode.is_insert() {
+            self.inserted_text.push(ch);
+        }
+        if self.mode == Mode::Replace {
+            self.replace_char_at_cursor(ch);
+        } else if self.mode.is_insert() {
             // Get all needed info first
             let cursor_line = self.cursor.line;
             let cursor_col = self.cursor.column;
@@ -261,8 +982,23 @@ impl Editor {
                 line_start + cursor_col.min(line_len)
             };
 
+            // autopairs：跳过光标后相同的右字符，或者同时插入右字符
+            let mut close = None;
+            if self.autopairs_active() {
+                let (prev, next) = self.chars_around_cursor();
+                if pairs::skips_over(ch, next) {
+                    self.cursor.column = cursor_col + 1;
+                    self.update_preferred_column();
+                    return;
+                }
+                close = pairs::auto_close(ch, prev, next);
+            }
+
             let buffer = self.current_buffer_mut();
             buffer.insert_char(char_idx, ch);
+            if let Some(close) = close {
+                buffer.insert_char(char_idx + 1, close);
+            }
 
             // Update cursor position manually - move right by 1
             // After insertion, the line length increased by 1
@@ -270,8 +1006,131 @@ impl Editor {
             // cursor_col + 1 is the new position after insertion
             // 在 Insert 模式下，光标可以在 line_len 位置（最后一个字符之后）
             self.cursor.column = (cursor_col + 1).min(line_len);
-            self.cursor.preferred_column = Some(self.cursor.column);
+            self.update_preferred_column();
+        }
+    }
+
+    /// Insert 模式下在光标处插入寄存器的内容 (Ctrl+R {reg})
+    ///
+    /// 内容按原样插入，不做自动缩进；寄存器为空时什么也不做
+    pub fn insert_register(&mut self, name: char) -> Result<(), String> {
+        if !is_register_name(name) && name != '=' {
+            return Err(tr!(self.locale, "Invalid register: {}", name));
+        }
+        let Some(register) = self.register_manager.get(name) else {
+            return Ok(());
+        };
+        self.insert_text_at_cursor(&register.content);
+        Ok(())
+    }
+
+    /// Command / 搜索模式下把寄存器的内容追加到命令行 (Ctrl+R {reg})
+    ///
+    /// 命令行只有一行：去掉行末的换行符，其余换行符替换为空格
+    pub fn insert_register_in_command_line(&mut self, name: char) -> Result<(), String> {
+        if !is_register_name(name) {
+            return Err(tr!(self.locale, "Invalid register: {}", name));
+        }
+        let Some(register) = self.register_manager.get(name) else {
+            return Ok(());
+        };
+        let text = register.content.strip_suffix('\n').unwrap_or(&register.content);
+        let text = text.replace('\n', " ");
+        self.command_line.push_str(&text);
+        Ok(())
+    }
+
+    /// 插入终端的括号粘贴 (bracketed paste) 的内容：整段文本一次插入缓冲区，作为一个撤销单元
+    ///
+    /// - Insert 模式插入到光标处，不做自动缩进
+    /// - Replace 模式逐个覆盖字符，换行符断开行
+    /// - Normal 模式插入到光标之前，光标停在插入的最后一个字符上
+    /// - Command / 搜索模式（以及 Ctrl+R = 的表达式）追加到命令行，换行符替换为空格
+    /// - Visual 模式忽略
+    /// - Terminal 模式发送给终端中的程序
+    pub fn paste_text(&mut self, text: &str) {
+        // 终端发送的换行可能是 \r 或 \r\n
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        match self.mode {
+            Mode::Insert if self.expression_prompt => self.paste_into_command_line(&text),
+            Mode::Insert => self.insert_text_at_cursor(&text),
+            Mode::Replace => {
+                for ch in text.chars() {
+                    if ch == '\n' {
+                        self.insert_text_at_cursor("\n");
+                    } else {
+                        self.insert_char(ch);
+                    }
+                }
+            }
+            Mode::Normal => {
+                self.set_mode(Mode::Insert);
+                self.insert_text_at_cursor(&text);
+                self.set_mode(Mode::Normal);
+                let text = self.get_line_text(self.cursor.line).unwrap_or_default();
+                self.cursor.column = display::prev_grapheme(&text, self.cursor.column);
+                self.update_preferred_column();
+            }
+            Mode::Command | Mode::SearchForward | Mode::SearchBackward => self.paste_into_command_line(&text),
+            Mode::Visual => {}
+            Mode::Terminal => {
+                let result = self.terminals.get_mut(&self.current_buffer).map(|job| job.paste(&text));
+                if let Some(Err(e)) = result {
+                    self.set_message(tr!(self.locale, "Terminal error: {}", e));
+                }
+            }
+        }
+    }
+
+    fn paste_into_command_line(&mut self, text: &str) {
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        self.command_line.push_str(&text.replace('\n', " "));
+    }
+
+    pub fn insert_text_at_cursor(&mut self, text: &str) {
+        if !self.mode.is_insert() || text.is_empty() {
+            return;
+        }
+        let char_idx = {
+            let buffer = self.current_buffer();
+            let line_len = buffer.line_len(self.cursor.line);
+            buffer.line_to_char(self.cursor.line) + self.cursor.column.min(line_len)
+        };
+        self.current_buffer_mut().insert(char_idx, text);
+        self.cursor = Cursor::from_char_idx(self.current_buffer(), char_idx + text.chars().count());
+        self.update_preferred_column();
+        self.inserted_text.push_str(text);
+    }
+
+    /// 开始输入表达式寄存器的表达式 (Insert 模式下 Ctrl+R =)
+    pub fn start_expression_prompt(&mut self) {
+        self.expression_prompt = true;
+        self.command_line.clear();
+    }
+
+    pub fn expression_prompt_active(&self) -> bool {
+        self.expression_prompt
+    }
+
+    pub fn cancel_expression_prompt(&mut self) {
+        self.expression_prompt = false;
+        self.command_line.clear();
+    }
+
+    /// 求值命令行中的表达式，结果存入 = 寄存器并插入到光标处
+    pub fn finish_expression_prompt(&mut self) -> Result<(), String> {
+        self.expression_prompt = false;
+        let expr = std::mem::take(&mut self.command_line);
+        if expr.trim().is_empty() {
+            return Ok(());
         }
+        let result = crate::expr::evaluate(&expr).map_err(|e| match e {
+            ExprError::Invalid => tr!(self.locale, "Invalid expression: {}", expr),
+            ExprError::DivisionByZero => tr!(self.locale, "Division by zero"),
+        })?;
+        self.register_manager.set_readonly('=', expr);
+        self.insert_text_at_cursor(&result);
+        Ok(())
     }
 
     /// 在光标后插入字符（用于 'a' 命令在行尾的情况）
@@ -281,12 +1140,10 @@ impl Editor {
         // 'a' 命令应该在当前字符之后插入
 
         // 获取当前光标位置信息
-        let buffer = self.current_buffer();
-        let line_len = buffer.line_len(self.cursor.line);
+        let text = self.get_line_text(self.cursor.line).unwrap_or_default();
         // text_len 是实际文本长度（不包括换行符）
-        let text_len = line_len.saturating_sub(1);
-        let max_col = text_len.saturating_sub(1);
-        let at_end = self.cursor.column >= max_col;
+        let text_len = text.chars().count();
+        let at_end = self.cursor.column >= display::last_grapheme(&text);
 
         if at_end {
             // 在行尾：将光标设置为 text_len（在最后一个字符之后，但在换行符之前）
@@ -295,7 +1152,6 @@ impl Editor {
             self.set_mode(Mode::Insert);
         } else {
             // 不在行尾：向右移动一位，然后进入 Insert 模式
-            drop(buffer);
             self.execute_motion(Motion::Right);
             self.set_mode(Mode::Insert);
         }
@@ -303,19 +1159,270 @@ impl Editor {
 
     pub fn insert_newline(&mut self) {
         if self.mode.is_insert() {
+            self.inserted_text.push('\n');
+            let indent = self.auto_indent_for(self.cursor.line);
             let char_idx = {
                 let buffer = self.current_buffer();
                 self.cursor.to_char_idx(buffer)
             };
             let buffer = self.current_buffer_mut();
-            buffer.insert(char_idx, "\n");
+            buffer.insert(char_idx, &format!("\n{}", indent));
             self.cursor.line += 1;
-            self.cursor.column = 0;
+            self.cursor.column = indent.chars().count();
+            // Replace 模式下换行不覆盖字符，Backspace 时逐个删除
+            if self.mode == Mode::Replace {
+                let inserted = 1 + self.cursor.column;
+                self.replace_stack.extend(std::iter::repeat_n(None, inserted));
+            }
+        }
+    }
+
+    /// Replace 模式输入：覆盖光标处的字符，行尾之后则追加
+    fn replace_char_at_cursor(&mut self, ch: char) {
+        let line = self.cursor.line;
+        let column = self.cursor.column;
+        let (char_idx, content_len) = {
+            let buffer = self.current_buffer();
+            let content_len = buffer.line(line).map(line_content_len).unwrap_or(0);
+            (buffer.line_to_char(line) + column.min(content_len), content_len)
+        };
+
+        let original = if column < content_len {
+            let buffer = self.current_buffer_mut();
+            let original = buffer.remove_char(char_idx);
+            buffer.insert_char(char_idx, ch);
+            original
+        } else {
+            self.current_buffer_mut().insert_char(char_idx, ch);
+            None
+        };
+        self.replace_stack.push(original);
+        self.cursor.column = column.min(content_len) + 1;
+        self.update_preferred_column();
+    }
+
+    /// Replace 模式的 Backspace：恢复被覆盖的原字符，删除新插入的字符
+    fn replace_backspace(&mut self) {
+        match self.replace_stack.pop() {
+            Some(Some(original)) => {
+                self.cursor.column -= 1;
+                let char_idx = self.cursor.to_char_idx(self.current_buffer());
+                let buffer = self.current_buffer_mut();
+                buffer.remove_char(char_idx);
+                buffer.insert_char(char_idx, original);
+            }
+            Some(None) => {
+                if self.cursor.column > 0 {
+                    self.cursor.column -= 1;
+                    let char_idx = self.cursor.to_char_idx(self.current_buffer());
+                    self.current_buffer_mut().remove_char(char_idx);
+                } else if self.cursor.line > 0 {
+                    // 删除 Replace 模式中插入的换行
+                    let line = self.cursor.line - 1;
+                    let (newline_idx, column) = {
+                        let buffer = self.current_buffer();
+                        let content_len = buffer.line(line).map(line_content_len).unwrap_or(0);
+                        (buffer.line_to_char(line) + content_len, content_len)
+                    };
+                    self.current_buffer_mut().remove_char(newline_idx);
+                    self.cursor.line = line;
+                    self.cursor.column = column;
+                }
+            }
+            // 没有可恢复的字符时只移动光标
+            None => self.cursor.column = self.cursor.column.saturating_sub(1),
+        }
+        self.update_preferred_column();
+    }
+
+    /// 用 `ch` 替换光标处开始的 `count` 个字符 (r)
+    ///
+    /// 当前行剩余字符不足 `count` 个时不做修改并返回 false。
+    /// `ch` 为换行符时这些字符整体替换为一个换行。光标停在最后一个替换的字符上
+    pub fn replace_chars(&mut self, ch: char, count: usize) -> bool {
+        let count = count.max(1);
+        let line = self.cursor.line;
+        let column = self.cursor.column;
+        let (start, content_len) = {
+            let buffer = self.current_buffer();
+            let content_len = buffer.line(line).map(line_content_len).unwrap_or(0);
+            (buffer.line_to_char(line) + column, content_len)
+        };
+        if column + count > content_len {
+            return false;
+        }
+
+        with_save_state!(self, {
+            let buffer = self.current_buffer_mut();
+            buffer.remove(start, count);
+            if ch == '\n' {
+                buffer.insert_char(start, '\n');
+                self.cursor.line = line + 1;
+                self.cursor.column = 0;
+            } else {
+                buffer.insert(start, &ch.to_string().repeat(count));
+                self.cursor.column = column + count - 1;
+            }
+            self.update_preferred_column();
+        });
+        true
+    }
+
+    /// 在当前行下方新建一行并进入 Insert 模式 (o)
+    pub fn open_line_below(&mut self) {
+        self.set_mode(Mode::Insert);
+        let line = self.cursor.line;
+        let indent = self.auto_indent_for(line);
+        let insert_idx = {
+            let buffer = self.current_buffer();
+            buffer.line_to_char(line) + buffer.line(line).map(line_content_len).unwrap_or(0)
+        };
+        self.current_buffer_mut().insert(insert_idx, &format!("\n{}", indent));
+        self.cursor.line = line + 1;
+        self.cursor.column = indent.chars().count();
+        self.update_preferred_column();
+    }
+
+    /// 在当前行上方新建一行并进入 Insert 模式 (O)
+    pub fn open_line_above(&mut self) {
+        self.set_mode(Mode::Insert);
+        let line = self.cursor.line;
+        let indent = self.auto_indent_for(line);
+        let insert_idx = self.current_buffer().line_to_char(line);
+        self.current_buffer_mut().insert(insert_idx, &format!("{}\n", indent));
+        self.cursor.column = indent.chars().count();
+        self.update_preferred_column();
+    }
+
+    /// 开启 autoindent 时返回指定行的前导空白，否则返回空字符串
+    fn auto_indent_for(&self, line_idx: usize) -> String {
+        if !self.current_options().autoindent || self.options.paste {
+            return String::new();
+        }
+        self.current_buffer()
+            .line(line_idx)
+            .map(|line| line.chars().take_while(|c| *c == ' ' || *c == '\t').collect())
+            .unwrap_or_default()
+    }
+
+    /// 合并当前行与下面的行 (J / gJ)
+    ///
+    /// `count` 为参与合并的总行数，小于 2 时按 2 处理，超出文件末尾时合并到最后一行。
+    /// `insert_space` 为 true 时（J）去掉后续行的前导空白并用一个空格连接，
+    /// 否则（gJ）原样拼接。返回是否进行了合并
+    pub fn join_lines(&mut self, count: usize, insert_space: bool) -> bool {
+        let first = self.cursor.line;
+        self.join_line_range(first, first + count.max(2) - 1, insert_space)
+    }
+
+    /// 合并 `first..=last` 行（Visual 模式下的 J / gJ），只有一行时与下一行合并
+    pub fn join_line_range(&mut self, first: usize, last: usize, insert_space: bool) -> bool {
+        let last_line = self.current_buffer().last_content_line();
+        let last = last.max(first + 1).min(last_line);
+        if first >= last {
+            return false;
+        }
+
+        with_save_state!(self, {
+            let mut join_column = 0;
+            for _ in first..last {
+                let buffer = self.current_buffer();
+                let current = buffer.line(first).unwrap();
+                let current_len = line_content_len(current);
+                let join_idx = buffer.line_to_char(first) + current_len;
+                let next_start = buffer.line_to_char(first + 1);
+                let next = buffer.line(first + 1).unwrap();
+                let next_len = line_content_len(next);
+
+                let mut remove_end = next_start;
+                let mut separator = "";
+                if insert_space {
+                    let indent = next
+                        .chars()
+                        .take(next_len)
+                        .take_while(|c| *c == ' ' || *c == '\t')
+                        .count();
+                    remove_end += indent;
+                    // 与 Vim 一致：当前行为空、以空白结尾，或下一行为空、以 ) 开头时不加空格
+                    let next_char = (indent < next_len).then(|| next.char(indent));
+                    let ends_with_blank =
+                        current_len > 0 && matches!(current.char(current_len - 1), ' ' | '\t');
+                    if current_len > 0 && !ends_with_blank && !matches!(next_char, None | Some(')')) {
+                        separator = " ";
+                    }
+                }
+
+                let buffer = self.current_buffer_mut();
+                buffer.remove(join_idx, remove_end - join_idx);
+                buffer.insert(join_idx, separator);
+                join_column = current_len;
+            }
+
+            self.cursor.line = first;
+            self.cursor.column = join_column;
+            let buffer = self.buffers.get(&self.current_buffer).unwrap();
+            self.cursor.ensure_valid(buffer);
+            self.update_preferred_column();
+        });
+        true
+    }
+
+    /// 将光标处或之后的第一个数字加上 `delta` (Ctrl+A / Ctrl+X)
+    ///
+    /// 光标移到修改后数字的最后一位。当前行光标之后没有数字时返回 false
+    pub fn increment_number(&mut self, delta: i64) -> bool {
+        let line = self.cursor.line;
+        let Some(text) = self.get_line_text(line) else {
+            return false;
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let Some(found) = number::find_number(&chars, self.cursor.column) else {
+            return false;
+        };
+        let old: String = chars[found.start..found.end].iter().collect();
+        let Some(new) = number::add_to_number(&old, found.format, delta) else {
+            return false;
+        };
+
+        with_save_state!(self, {
+            let start = self.current_buffer().line_to_char(line) + found.start;
+            let buffer = self.current_buffer_mut();
+            buffer.remove(start, found.end - found.start);
+            buffer.insert(start, &new);
+            self.cursor.column = found.start + new.chars().count() - 1;
+            self.update_preferred_column();
+        });
+        true
+    }
+
+    /// Insert 模式下的 Tab 键
+    ///
+    /// expandtab 时插入空格到下一个 shiftwidth 整数倍的列，否则（以及粘贴模式下）插入制表符
+    pub fn insert_tab(&mut self) {
+        let options = self.current_options();
+        if options.paste || !options.expandtab {
+            self.insert_char('\t');
+            return;
         }
+        let shiftwidth = options.shiftwidth.max(1);
+        let column = self.display_column(self.cursor.line, self.cursor.column);
+        for _ in 0..shiftwidth - column % shiftwidth {
+            self.insert_char(' ');
+        }
+    }
+
+    /// 第 `line` 行第 `column` 个字符在屏幕上的列（Tab 按 tabstop 展开）
+    pub fn display_column(&self, line: usize, column: usize) -> usize {
+        buffer_display_column(self.current_buffer(), Cursor::new(line, column), self.current_options().tabstop)
     }
 
     pub fn backspace(&mut self) {
         if self.mode.is_insert() {
+            self.inserted_text.pop();
+        }
+        if self.mode == Mode::Replace {
+            self.replace_backspace();
+        } else if self.mode.is_insert() {
             let should_edit = self.cursor.column > 0 || self.cursor.line > 0;
             if !should_edit {
                 return;
@@ -330,12 +1437,14 @@ impl Editor {
                         let buffer = self.current_buffer();
                         self.cursor.to_char_idx(buffer)
                     };
+                    let (prev, next) = self.chars_around_cursor();
+                    let remove = if self.autopairs_active() && pairs::deletes_pair(prev, next) { 2 } else { 1 };
                     let buffer = self.current_buffer_mut();
-                    buffer.remove_char(char_idx - 1);
+                    buffer.remove(char_idx - 1, remove);
 
                     // Update cursor position manually
                     self.cursor.column -= 1;
-                    self.cursor.preferred_column = Some(self.cursor.column);
+                    self.update_preferred_column();
                 } else if line > 0 {
                     let prev_line_len = {
                         let buffer = self.current_buffer();
@@ -354,35 +1463,56 @@ impl Editor {
         }
     }
 
-    pub fn delete_char(&mut self) {
-        if self.mode == Mode::Normal {
-            with_save_state!(self, {
-                let char_idx = {
-                    let buffer = self.current_buffer();
-                    self.cursor.to_char_idx(buffer)
-                };
-                let cursor_line = self.cursor.line;
-                let cursor_col = self.cursor.column;
-
-                let buffer = self.current_buffer_mut();
-                buffer.remove_char(char_idx);
+    /// Insert 模式下自动补全括号和引号：粘贴模式中暂停
+    fn autopairs_active(&self) -> bool {
+        self.mode == Mode::Insert && self.options.autopairs && !self.options.paste
+    }
 
-                // Ensure cursor is valid manually
-                let max_line = buffer.len_lines().saturating_sub(1);
-                let new_line = cursor_line.min(max_line);
-                let line_len = buffer.line_len(new_line);
-                let max_col = line_len.saturating_sub(1);
-                let new_col = cursor_col.min(max_col);
+    /// 光标前后的字符（不包括换行符）
+    fn chars_around_cursor(&self) -> (Option<char>, Option<char>) {
+        let Some(line) = self.current_buffer().line(self.cursor.line) else {
+            return (None, None);
+        };
+        let len = line_content_len(line);
+        let column = self.cursor.column.min(len);
+        let prev = (column > 0).then(|| line.char(column - 1));
+        let next = (column < len).then(|| line.char(column));
+        (prev, next)
+    }
 
-                self.cursor.line = new_line;
-                self.cursor.column = new_col;
+    pub fn delete_char(&mut self) {
+        if self.mode == Mode::Normal {
+            with_save_state!(self, {
+                self.remove_grapheme_at_cursor();
             });
         }
     }
 
+    /// 光标处字形簇的字符数，光标在行尾之后时为 1（删除换行符）
+    fn grapheme_len_at_cursor(&self) -> usize {
+        let text = self.get_line_text(self.cursor.line).unwrap_or_default();
+        let column = self.cursor.column;
+        (display::next_grapheme(&text, column) - column.min(text.chars().count())).max(1)
+    }
+
+    /// 删除光标处的字形簇（带组合符号的字母、emoji 序列整体删除），返回删除的文本
+    fn remove_grapheme_at_cursor(&mut self) -> Option<String> {
+        let len = self.grapheme_len_at_cursor();
+        let char_idx = self.cursor.to_char_idx(self.current_buffer());
+        let buffer = self.current_buffer_mut();
+        if char_idx >= buffer.len_chars() {
+            return None;
+        }
+        let len = len.min(buffer.len_chars() - char_idx);
+        let deleted = buffer.slice(char_idx..char_idx + len).to_string();
+        buffer.remove(char_idx, len);
+        self.cursor.ensure_valid(&self.buffers[&self.current_buffer]);
+        Some(deleted)
+    }
+
     pub fn open_file(&mut self, path: &Path) -> io::Result<()> {
         // 保存当前缓冲区的光标位置
-        self.buffer_cursors.insert(self.current_buffer, self.cursor.clone());
+        self.buffer_cursors.insert(self.current_buffer, self.cursor);
 
         let buffer_id = BufferId::new(self.next_buffer_id);
         self.next_buffer_id += 1;
@@ -393,619 +1523,5543 @@ impl Editor {
         } else {
             Buffer::new_with_path(buffer_id, path)
         };
+        self.show_opened_buffer(buffer, path);
+        Ok(())
+    }
 
-        self.buffers.insert(buffer_id, buffer);
+    /// 切换到刚读入的文件缓冲区：监视文件、读取撤销历史，回到上次编辑这个文件时的位置
+    fn show_opened_buffer(&mut self, buffer: Buffer, path: &Path) {
+        let buffer_id = buffer.id();
+        let decode_errors = buffer.decode_errors();
+        let binary = buffer.is_binary();
+        self.insert_buffer(buffer);
+        self.watch_file(path);
         self.current_buffer = buffer_id;
-        
-        // 恢复该缓冲区的光标位置，如果没有则使用默认位置
-        self.cursor = self.buffer_cursors.get(&buffer_id).cloned().unwrap_or_else(Cursor::at_origin);
+        self.read_undo_history(buffer_id);
+        if binary {
+            self.set_message(tr!(self.locale, "Binary file: editing as hex dump"));
+        } else if decode_errors > 0 {
+            self.set_message(tr!(self.locale, "{} character(s) could not be decoded", decode_errors));
+        }
+
+        // 回到上次编辑这个文件时的位置
+        self.cursor = self.last_position(path).unwrap_or_else(Cursor::at_origin);
+        self.mode = Mode::Normal;
+        let event = if path.exists() { Event::BufReadPost } else { Event::BufNewFile };
+        self.emit_event(event, buffer_id);
+    }
+
+    /// 大文件（超过 `async_load_size`）在后台读入 (:e)，返回是否开始了后台读入
+    ///
+    /// 读入期间当前缓冲区可以继续编辑，读完后由 `poll_file_load` 切换到新的缓冲区；之前还没读完的文件被放弃
+    fn start_file_load(&mut self, path: &Path) -> bool {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        if !metadata.is_file() || metadata.len() <= self.async_load_size {
+            return false;
+        }
+        let buffer_id = BufferId::new(self.next_buffer_id);
+        self.next_buffer_id += 1;
+        self.file_load = Some(FileLoad::start(path, buffer_id, metadata.len(), Instant::now(), self.waker.clone()));
+        true
+    }
+
+    /// 正在后台读入的文件
+    pub fn file_load(&self) -> Option<&FileLoad> {
+        self.file_load.as_ref()
+    }
+
+    /// 设置在后台读入的文件大小（字节），默认为 `loader::ASYNC_LOAD_SIZE`
+    pub fn set_async_load_size(&mut self, size: u64) {
+        self.async_load_size = size;
+    }
+
+    /// 取出后台读入的进度，读完时切换到新的缓冲区；返回是否需要重绘（进度、转圈符号变化或读入结束）
+    ///
+    /// 由事件循环定期调用
+    pub fn poll_file_load(&mut self, now: Instant) -> bool {
+        let Some(load) = self.file_load.as_mut() else {
+            return false;
+        };
+        let (changed, result) = load.poll(now);
+        let Some(result) = result else {
+            return changed;
+        };
+        let path = load.path().to_path_buf();
+        self.file_load = None;
+        match result {
+            Ok(buffer) => {
+                self.buffer_cursors.insert(self.current_buffer, self.cursor);
+                self.set_message(tr!(self.locale, "Opened {}", path.display()));
+                self.show_opened_buffer(buffer, &path);
+            }
+            Err(e) => self.set_message(tr!(self.locale, "Failed to open {}: {}", path.display(), e)),
+        }
+        true
+    }
+
+    /// 把读入的内容（如标准输入）放入新的没有文件名的缓冲区并切换到它（aivim -）
+    ///
+    /// 和读入文件一样检测编码和行尾符，缓冲区不算被修改
+    pub fn open_bytes(&mut self, bytes: &[u8]) {
+        self.buffer_cursors.insert(self.current_buffer, self.cursor);
+
+        let buffer_id = BufferId::new(self.next_buffer_id);
+        self.next_buffer_id += 1;
+        self.insert_buffer(Buffer::from_bytes(buffer_id, bytes));
+        self.current_buffer = buffer_id;
+        self.cursor = Cursor::at_origin();
         self.mode = Mode::Normal;
+    }
+
+    /// 加入新打开的缓冲区，只读模式下设为只读
+    fn insert_buffer(&mut self, mut buffer: Buffer) {
+        if self.read_only_mode {
+            buffer.set_read_only(true);
+        }
+        self.buffers.insert(buffer.id(), buffer);
+    }
+
+    /// :w - 检查之后写入当前缓冲区，`file` 为 None 时写入原文件
+    ///
+    /// 只读的缓冲区、受保护的路径和在外部被修改过的文件先确认（`bang` 时跳过确认）
+    fn write_current(&mut self, file: Option<PathBuf>, bang: bool) -> Result<(), String> {
+        let target = file.clone().or_else(|| self.current_buffer().file_path().map(|p| p.to_path_buf()));
+        if file.is_none() && !bang && self.current_buffer().is_read_only() {
+            // 只读打开的文件（-R）确认后覆盖
+            let path = self.current_buffer().file_path().map(Path::to_path_buf);
+            let path = path.ok_or_else(|| tr!(self.locale, "Buffer is read-only (add ! to override)"))?;
+            let confirmation = PendingConfirmation::WriteReadOnly(path);
+            self.set_message(confirmation.prompt(self.locale));
+            self.pending_confirmation = Some(confirmation);
+            return Ok(());
+        }
+
+        // 写入受保护路径前需要确认（! 跳过确认）
+        if let Some(path) = target.filter(|p| !bang && self.is_protected_path(p)) {
+            let confirmation = PendingConfirmation::WriteProtected(path);
+            self.set_message(confirmation.prompt(self.locale));
+            self.pending_confirmation = Some(confirmation);
+            return Ok(());
+        }
+        // 文件在外部被修改过时先确认，避免覆盖别人的修改（! 跳过确认）
+        if file.is_none() && !bang && self.current_buffer().changed_on_disk() {
+            let path = self.current_buffer().file_path().unwrap_or(Path::new("")).to_path_buf();
+            let confirmation = PendingConfirmation::OverwriteChanged(path);
+            self.set_message(confirmation.prompt(self.locale));
+            self.pending_confirmation = Some(confirmation);
+            return Ok(());
+        }
+
+        self.write_buffer(file)
+    }
+
+    /// 写入当前缓冲区，`path` 为 None 时写入原文件
+    ///
+    /// 有文件编码无法表示的字符时先确认是否替换为 `?`
+    fn write_buffer(&mut self, path: Option<PathBuf>) -> Result<(), String> {
+        let chars = self.current_buffer().unencodable_chars();
+        if chars > 0 {
+            let encoding = self.current_buffer().encoding();
+            let confirmation = PendingConfirmation::LossyWrite { path, encoding, chars };
+            self.set_message(confirmation.prompt(self.locale));
+            self.pending_confirmation = Some(confirmation);
+            return Ok(());
+        }
+        match path {
+            Some(path) => self.save_as(&path).map_err(|e| e.to_string())?,
+            None => self.save().map_err(|e| e.to_string())?,
+        }
+        self.set_message(tr!(self.locale, "Saved"));
+        Ok(())
+    }
+
+    /// 从磁盘重新读入当前文件（:e），丢弃未保存的修改
+    fn reload_current_buffer(&mut self) -> Result<(), String> {
+        let path = self
+            .current_buffer()
+            .file_path()
+            .map(|p| p.to_path_buf())
+            .ok_or_else(|| tr!(self.locale, "No file name"))?;
+        self.reload_buffer(self.current_buffer)
+            .map_err(|e| tr!(self.locale, "Failed to open {}: {}", path.display(), e))?;
+        self.set_message(tr!(self.locale, "Reloaded {}", path.display()));
+        Ok(())
+    }
+
+    /// 从磁盘重新读入缓冲区，把缓冲区的光标限制在新的内容中
+    fn reload_buffer(&mut self, buffer_id: BufferId) -> io::Result<()> {
+        let Some(buffer) = self.buffers.get_mut(&buffer_id) else {
+            return Ok(());
+        };
+        buffer.reload()?;
+        let buffer = &self.buffers[&buffer_id];
+        let cursor = if buffer_id == self.current_buffer {
+            Some(&mut self.cursor)
+        } else {
+            self.buffer_cursors.get_mut(&buffer_id)
+        };
+        if let Some(cursor) = cursor {
+            cursor.line = cursor.line.min(buffer.last_content_line());
+            cursor.ensure_valid(buffer);
+        }
+        if buffer_id == self.current_buffer {
+            self.update_preferred_column();
+        }
+        Ok(())
+    }
+
+    /// 检查当前文件是否在外部被修改过（:checktime，终端重新获得焦点时调用）
+    pub fn check_file_changed(&mut self) {
+        self.handle_file_change(self.current_buffer);
+    }
+
+    /// 缓冲区的文件在外部被修改过时：开启 autoread 且缓冲区没有修改时重新读入，否则提示
+    ///
+    /// 重新读入时返回 true
+    fn handle_file_change(&mut self, buffer_id: BufferId) -> bool {
+        let Some(buffer) = self.buffers.get(&buffer_id) else {
+            return false;
+        };
+        if !buffer.changed_on_disk() {
+            return false;
+        }
+        let name = buffer.display_name().unwrap_or_default().to_string();
+        if !self.options.autoread || buffer.is_modified() {
+            if buffer_id == self.current_buffer {
+                self.set_message(self.changed_on_disk_message());
+            }
+            return false;
+        }
+        if let Err(e) = self.reload_buffer(buffer_id) {
+            tracing::warn!("Failed to reload {}: {}", name, e);
+            return false;
+        }
+        self.set_message(tr!(self.locale, "{} changed on disk, reloaded", name));
+        true
+    }
+
+    /// 处理文件监视器报告的变化（开启 autoread 时），有缓冲区被重新读入时返回 true
+    pub fn poll_file_changes(&mut self) -> bool {
+        let Some(watcher) = self.watcher.as_mut() else {
+            return false;
+        };
+        let mut reloaded = false;
+        for path in watcher.poll() {
+            let ids: Vec<BufferId> = self
+                .buffers
+                .iter()
+                .filter(|(_, buffer)| buffer.file_path().is_some_and(|p| watch::absolute(p) == path))
+                .map(|(id, _)| *id)
+                .collect();
+            for id in ids {
+                reloaded |= self.handle_file_change(id);
+            }
+        }
+        reloaded
+    }
 
+    /// 开始监视所有打开的缓冲区的文件（:set autoread）
+    fn start_watching(&mut self) -> Result<(), String> {
+        if self.watcher.is_some() {
+            return Ok(());
+        }
+        let mut watcher = FileWatcher::new(self.waker.clone()).map_err(|e| e.to_string())?;
+        for buffer in self.buffers.values() {
+            if let Some(path) = buffer.file_path() {
+                watcher.watch(path);
+            }
+        }
+        self.watcher = Some(watcher);
         Ok(())
     }
 
+    /// 开启 autoread 时监视文件
+    fn watch_file(&mut self, path: &Path) {
+        if let Some(watcher) = self.watcher.as_mut() {
+            watcher.watch(path);
+        }
+    }
+
+    fn changed_on_disk_message(&self) -> String {
+        let name = self.current_buffer().display_name().unwrap_or_default().to_string();
+        tr!(self.locale, "{} has changed on disk (:e! to reload, :w! to overwrite)", name)
+    }
+
     pub fn save(&mut self) -> io::Result<()> {
+        self.emit_event(Event::BufWritePre, self.current_buffer);
         let buffer = self.current_buffer_mut();
         buffer.save()?;
+        // 二进制文件写入后重新生成了转储，行可能变短
+        self.cursor.ensure_valid(&self.buffers[&self.current_buffer]);
+        self.write_undo_history(self.current_buffer);
+        self.emit_event(Event::BufWritePost, self.current_buffer);
         Ok(())
     }
 
     pub fn save_as(&mut self, path: &Path) -> io::Result<()> {
+        self.emit_event(Event::BufWritePre, self.current_buffer);
         let buffer = self.current_buffer_mut();
         buffer.save_as(path)?;
+        self.watch_file(path);
+        // 二进制文件写入后重新生成了转储，行可能变短
+        self.cursor.ensure_valid(&self.buffers[&self.current_buffer]);
+        self.write_undo_history(self.current_buffer);
+        self.emit_event(Event::BufWritePost, self.current_buffer);
         Ok(())
     }
 
-    pub fn execute_command(&mut self, command: &str) -> Result<(), String> {
-        let parts: Vec<&str> = command.split_whitespace().collect();
-        if parts.is_empty() {
-            return Ok(());
+    /// 设置只读模式（aivim -R），之后打开的缓冲区都是只读的，写入原文件需要加 !
+    pub fn set_read_only_mode(&mut self, read_only: bool) {
+        self.read_only_mode = read_only;
+    }
+
+    /// 设置记住文件光标位置的状态文件，None 时不记录也不恢复
+    pub fn set_positions_file(&mut self, path: Option<PathBuf>) {
+        self.positions_file = path;
+    }
+
+    /// 状态文件中记录的文件上次的光标位置，限制在当前缓冲区的内容中
+    fn last_position(&self, path: &Path) -> Option<Cursor> {
+        let positions = FilePositions::load(self.positions_file.as_deref()?);
+        let (line, column) = positions.get(path)?;
+        let buffer = self.current_buffer();
+        let line = line.min(buffer.last_content_line());
+        let text = buffer.line_text(line).unwrap_or_default();
+        let mut cursor = Cursor::new(line, column.min(display::last_grapheme(&text)));
+        cursor.ensure_valid(buffer);
+        Some(cursor)
+    }
+
+    /// 把缓冲区的光标位置写入状态文件，`buffers` 为空时写入所有缓冲区（退出时调用）
+    pub fn save_positions(&mut self, buffers: &[BufferId]) {
+        let Some(state) = self.positions_file.clone() else {
+            return;
+        };
+        self.buffer_cursors.insert(self.current_buffer, self.cursor);
+        let mut positions = FilePositions::load(&state);
+        for (id, buffer) in &self.buffers {
+            if !buffers.is_empty() && !buffers.contains(id) {
+                continue;
+            }
+            if let (Some(path), Some(cursor)) = (buffer.file_path(), self.buffer_cursors.get(id)) {
+                positions.set(path, cursor.line, cursor.column);
+            }
+        }
+        if let Err(e) = positions.save(&state) {
+            tracing::warn!("Failed to write {}: {}", state.display(), e);
         }
+    }
 
-        match parts[0] {
-            "w" | "write" => {
-                if parts.len() > 1 {
-                    let path = Path::new(parts[1]);
-                    self.save_as(path).map_err(|e| e.to_string())?;
-                } else {
-                    self.save().map_err(|e| e.to_string())?;
+    pub fn undo_dir(&self) -> &Path {
+        &self.undo_dir
+    }
+
+    pub fn set_undo_dir(&mut self, dir: PathBuf) {
+        self.undo_dir = dir;
+    }
+
+    /// 开启 undofile 时把缓冲区的撤销历史写入撤销文件
+    ///
+    /// 在保存文件后和关闭未修改的缓冲区时调用，此时缓冲区文本与文件内容一致；
+    /// 写入失败不影响保存本身，只记录日志
+    fn write_undo_history(&mut self, buffer_id: BufferId) {
+        if !self.options.undofile {
+            return;
+        }
+        let Some(buffer) = self.buffers.get(&buffer_id) else {
+            return;
+        };
+        let Some(path) = buffer.file_path() else {
+            return;
+        };
+        let cursor = if buffer_id == self.current_buffer {
+            self.cursor
+        } else {
+            self.buffer_cursors.get(&buffer_id).copied().unwrap_or_else(Cursor::at_origin)
+        };
+        let limits = self.undo_limits();
+        let tree = self
+            .undo_trees
+            .entry(buffer_id)
+            .or_insert_with(|| UndoTree::with_limits(limits));
+        // 撤销文件中的当前状态必须是保存的文本
+        if tree.current_text() != Some(buffer.rope()) {
+            tree.record(buffer.rope(), cursor);
+        }
+        if let Err(e) = crate::undo_file::write_undo_file(&self.undo_dir, path, tree) {
+            tracing::warn!("Failed to write undo file for {}: {}", path.display(), e);
+        }
+    }
+
+    /// 开启 undofile 时从撤销文件恢复刚打开的缓冲区的撤销历史
+    fn read_undo_history(&mut self, buffer_id: BufferId) {
+        if !self.options.undofile {
+            return;
+        }
+        let buffer = &self.buffers[&buffer_id];
+        let Some(path) = buffer.file_path() else {
+            return;
+        };
+        if let Some(tree) = crate::undo_file::read_undo_file(&self.undo_dir, path, buffer.rope(), self.undo_limits()) {
+            self.undo_trees.insert(buffer_id, tree);
+        }
+    }
+
+    pub fn execute_command(&mut self, command: &str) -> Result<(), String> {
+        let last = self.current_buffer().last_content_line();
+        let parsed = ex_command::parse_with(command, self.cursor.line, last, self.plugins.commands())
+            .map_err(|e| self.ex_error_message(e))?;
+        let Some(cmd) = parsed else {
+            return Ok(());
+        };
+        let ExCommand { range, name, bang, args } = cmd;
+        let current_line = LineRange::single(self.cursor.line);
+
+        match name {
+            // 只有范围时跳转到范围的最后一行
+            "" => {
+                if let Some(range) = range {
+                    self.cursor = Cursor::new(range.end, 0);
+                    self.execute_motion(Motion::FirstNonBlank);
                 }
-                self.set_message("Saved");
             }
-            "q" | "quit" => {
-                if self.current_buffer().is_modified() {
-                    return Err("No write since last change (add ! to override)".to_string());
-                }
+            "write" if range.is_some() || args.starts_with(">>") => {
+                // :10,20w {file} 写入部分行，:w >> {file} 把整个缓冲区追加到文件
+                self.write_range(range.unwrap_or(LineRange::new(0, last)), args, bang)?;
             }
-            "q!" => {
-                // 强制退出，不保存修改
+            "write" => {
+                let file = self.single_arg(args)?.map(PathBuf::from);
+                self.write_current(file, bang)?;
             }
+            // 有多个窗口时 :q 只关闭当前窗口，缓冲区仍然保留
+            "quit" if self.windows.len() > 1 => self.close_window(self.windows.current_id())?,
+            // 标签页中只剩一个窗口时关闭标签页
+            "quit" if self.tabs.len() > 1 => self.close_tab()?,
+            // 最后一个窗口：有修改的缓冲区时询问是否保存，:q! 强制退出，不保存修改
+            "quit" => self.quit_all(bang),
             "wq" => {
+                if !bang && self.current_buffer().is_read_only() {
+                    return Err(tr!(self.locale, "Buffer is read-only (add ! to override)"));
+                }
+                if let Some(path) = self.current_buffer().file_path() {
+                    if !bang && self.is_protected_path(path) {
+                        return Err(tr!(self.locale, "{} is a protected path (use :w! to write)", path.display()));
+                    }
+                }
+                if !bang && self.current_buffer().changed_on_disk() {
+                    return Err(self.changed_on_disk_message());
+                }
+                let chars = self.current_buffer().unencodable_chars();
+                if chars > 0 {
+                    let encoding = self.current_buffer().encoding();
+                    return Err(tr!(self.locale, "Cannot encode {} character(s) as {} (use :w to replace them)", chars, encoding.name()));
+                }
                 self.save().map_err(|e| e.to_string())?;
+                if self.windows.len() > 1 {
+                    self.close_window(self.windows.current_id())?;
+                } else if self.tabs.len() > 1 {
+                    self.close_tab()?;
+                } else {
+                    self.quit_requested = true;
+                }
+            }
+            "close" => self.close_window(self.windows.current_id())?,
+            "only" => self.only_window(),
+            "tabnew" | "tabedit" => {
+                let file = self.single_arg(args)?;
+                self.new_tab(file.as_deref())?;
+            }
+            "tabclose" => self.close_tab()?,
+            "tabonly" => self.tabs.only(),
+            "tabnext" | "tabprevious" => {
+                let count = match self.single_arg(args)? {
+                    Some(arg) => Some(
+                        arg.parse::<usize>()
+                            .map_err(|_| tr!(self.locale, "Invalid argument: {}", arg))?,
+                    ),
+                    None => None,
+                };
+                if name == "tabnext" {
+                    self.next_tab(count);
+                } else {
+                    self.previous_tab(count.unwrap_or(1));
+                }
             }
-            "qa" => {
+            "split" | "vsplit" => {
+                let direction = if name == "split" { SplitDirection::Horizontal } else { SplitDirection::Vertical };
+                let file = self.single_arg(args)?;
+                self.split_window(direction, file.as_deref())?;
             }
+            "qall" => self.quit_all(bang),
             "set" => {
-                if parts.len() > 1 {
-                    self.set_option(parts[1])?;
+                for option in self.arg_list(args)? {
+                    self.set_option(&option)?;
+                    // 当前窗口和缓冲区也使用新的全局值
+                    if let Some(local) = LocalOption::parse(&option) {
+                        self.update_local_options(|window, buffer| local.clear(window, buffer));
+                    }
+                    self.notify_option_set();
+                }
+            }
+            "setlocal" => {
+                for option in self.arg_list(args)? {
+                    self.set_local_option(&option)?;
+                    self.notify_option_set();
+                }
+            }
+            "setglobal" => {
+                for option in self.arg_list(args)? {
+                    self.set_option(&option)?;
+                    self.notify_option_set();
                 }
             }
-            "reg" | "registers" => {
+            "DiffOrig" => self.diff_orig()?,
+            "undolist" => {
+                let list = self.format_undo_list();
+                self.set_message(list);
+            }
+            "registers" => {
                 // 显示寄存器列表面板而不是消息
                 self.show_registers_panel = true;
             }
-            "ls" | "buffers" => {
-                // 显示缓冲区列表面板而不是消息
-                self.set_show_buffer_list(true);
+            "jobs" => {
+                // 显示任务列表面板
+                self.show_jobs_panel = true;
             }
-            "b" | "buffer" => {
-                if parts.len() > 1 {
-                    if let Ok(id) = parts[1].parse::<usize>() {
-                        let buffer_id = BufferId::new(id);
-                        self.switch_buffer(buffer_id)?;
-                        self.set_message(&format!("Switched to buffer {}", id));
-                        // 切换缓冲区后关闭缓冲区列表面板
-                        self.show_buffer_list = false;
-                    } else {
-                        return Err("Invalid buffer ID".to_string());
+            "job" => {
+                let args = self.arg_list(args)?;
+                match (args.first().map(String::as_str), args.get(1)) {
+                    (Some("kill"), Some(id)) => {
+                        let id = id
+                            .parse::<usize>()
+                            .map_err(|_| tr!(self.locale, "Invalid job ID: {}", id))?;
+                        let job_id = JobId::new(id);
+                        match self.jobs.get(job_id) {
+                            None => return Err(tr!(self.locale, "No job {}", id)),
+                            Some(job) if !job.status().is_running() => {
+                                return Err(tr!(self.locale, "Job {} is not running", id));
+                            }
+                            Some(_) => self.jobs.kill(job_id).map_err(|e| e.to_string())?,
+                        }
+                        self.set_message(tr!(self.locale, "Killed job {}", id));
                     }
-                } else {
-                    return Err("Buffer ID required".to_string());
-                }
-            }
-            "bn" | "bnext" | "next" => {
-                match self.next_buffer() {
-                    Ok(_) => {
-                        let id = self.current_buffer_id().as_usize();
-                        self.set_message(&format!("Switched to buffer {}", id));
-                        // 切换缓冲区后关闭缓冲区列表面板
-                        self.show_buffer_list = false;
+                    (Some("kill"), None) => return Err(tr!(self.locale, "Job ID required")),
+                    (Some("clear"), _) => {
+                        let count = self.jobs.clear_finished();
+                        self.set_message(tr!(self.locale, "Cleared {} finished job(s)", count));
                     }
-                    Err(e) => self.set_message(&e),
+                    _ => return Err(tr!(self.locale, "Usage: :job kill {id} | :job clear")),
                 }
             }
-            "bp" | "bprev" | "bprevious" | "prev" => {
-                match self.prev_buffer() {
+            "ls" | "buffers" => {
+                // 显示缓冲区列表面板而不是消息
+                self.set_show_buffer_list(true);
+            }
+            "buffer" => {
+                let arg = self
+                    .single_arg(args)?
+                    .ok_or_else(|| tr!(self.locale, "Buffer ID required"))?;
+                // 参数可以是缓冲区编号或已打开的文件名
+                let id = match arg.parse::<usize>() {
+                    Ok(id) => id,
+                    Err(_) => self
+                        .find_file_buffer(Path::new(&arg))
+                        .ok_or_else(|| tr!(self.locale, "No matching buffer for {}", arg))?
+                        .as_usize(),
+                };
+                self.switch_buffer(BufferId::new(id))?;
+                self.set_message(tr!(self.locale, "Switched to buffer {}", id));
+                // 切换缓冲区后关闭缓冲区列表面板
+                self.show_buffer_list = false;
+            }
+            "bnext" | "next" | "bprevious" | "previous" => {
+                let result = if name.ends_with("next") {
+                    self.next_buffer()
+                } else {
+                    self.prev_buffer()
+                };
+                match result {
                     Ok(_) => {
                         let id = self.current_buffer_id().as_usize();
-                        self.set_message(&format!("Switched to buffer {}", id));
+                        self.set_message(tr!(self.locale, "Switched to buffer {}", id));
                         // 切换缓冲区后关闭缓冲区列表面板
                         self.show_buffer_list = false;
                     }
                     Err(e) => self.set_message(&e),
                 }
             }
-            "bd" | "bdelete" => {
-                let buffer_id = if parts.len() > 1 {
-                    if let Ok(id) = parts[1].parse::<usize>() {
-                        BufferId::new(id)
-                    } else {
-                        return Err("Invalid buffer ID".to_string());
-                    }
-                } else {
-                    self.current_buffer_id()
+            "bdelete" => {
+                let buffer_id = match self.single_arg(args)? {
+                    Some(id) => BufferId::new(id.parse::<usize>().map_err(|_| tr!(self.locale, "Invalid buffer ID"))?),
+                    None => self.current_buffer_id(),
                 };
-                
-                match self.delete_buffer(buffer_id) {
-                    Ok(_) => {
-                        self.set_message(&format!("Deleted buffer {}", buffer_id.as_usize()));
-                    }
-                    Err(e) => return Err(e),
-                }
-            }
-            "bd!" | "bdelete!" => {
-                let buffer_id = if parts.len() > 1 {
-                    if let Ok(id) = parts[1].parse::<usize>() {
-                        BufferId::new(id)
-                    } else {
-                        return Err("Invalid buffer ID".to_string());
-                    }
+                if bang {
+                    self.delete_buffer_force(buffer_id)?;
                 } else {
-                    self.current_buffer_id()
-                };
-                
-                match self.delete_buffer_force(buffer_id) {
-                    Ok(_) => {
-                        self.set_message(&format!("Deleted buffer {}", buffer_id.as_usize()));
-                    }
-                    Err(e) => return Err(e),
+                    self.delete_buffer(buffer_id)?;
                 }
+                self.set_message(tr!(self.locale, "Deleted buffer {}", buffer_id.as_usize()));
             }
             "new" => {
                 // 创建新的空缓冲区
                 self.create_new_buffer();
                 let id = self.current_buffer_id().as_usize();
-                self.set_message(&format!("Created new buffer {}", id));
-            }
-            "e" | "edit" => {
-                if parts.len() > 1 {
-                    let path = Path::new(parts[1]);
-                    match self.open_file(path) {
-                        Ok(_) => {
-                            self.set_message(&format!("Opened {}", parts[1]));
-                        }
-                        Err(e) => {
-                            return Err(format!("Failed to open {}: {}", parts[1], e));
-                        }
-                    }
+                self.set_message(tr!(self.locale, "Created new buffer {}", id));
+            }
+            // 不带文件名时重新读入当前文件，:e! 丢弃未保存的修改
+            "edit" if args.is_empty() => {
+                if !bang && self.current_buffer().is_modified() {
+                    return Err(tr!(self.locale, "No write since last change (add ! to override)"));
+                }
+                self.reload_current_buffer()?;
+            }
+            "checktime" => self.check_file_changed(),
+            "edit" => {
+                let file = self
+                    .single_arg(args)?
+                    .ok_or_else(|| tr!(self.locale, "Filename required"))?;
+                if self.start_file_load(Path::new(&file)) {
+                    self.set_message(tr!(self.locale, "Loading {}...", file));
+                    return Ok(());
+                }
+                match self.open_file(Path::new(&file)) {
+                    Ok(_) => self.set_message(tr!(self.locale, "Opened {}", file)),
+                    Err(e) => return Err(tr!(self.locale, "Failed to open {}: {}", file, e)),
+                }
+            }
+            "Rename" => {
+                let file = self
+                    .single_arg(args)?
+                    .ok_or_else(|| tr!(self.locale, "Filename required"))?;
+                self.rename_current_file(Path::new(&file), bang)?;
+                self.set_message(tr!(self.locale, "Renamed to {}", file));
+            }
+            "Delete" if bang => self.delete_current_file()?,
+            "Delete" => {
+                let path = self
+                    .current_buffer()
+                    .file_path()
+                    .map(|p| p.to_path_buf())
+                    .ok_or_else(|| tr!(self.locale, "No file name"))?;
+                let confirmation = PendingConfirmation::DeleteFile(path);
+                self.set_message(confirmation.prompt(self.locale));
+                self.pending_confirmation = Some(confirmation);
+            }
+            "fixlineendings" => {
+                let stats = self.line_ending_stats();
+                let target = match self.single_arg(args)? {
+                    Some(name) => LineEnding::parse(&name)
+                        .ok_or_else(|| tr!(self.locale, "Invalid line ending: {} (use lf or crlf)", name))?,
+                    None => stats.dominant(),
+                };
+                let lines = stats.lines_to_convert(target);
+                if lines == 0 {
+                    self.set_message(tr!(self.locale, "All lines already use {}", target.name()));
+                } else if bang {
+                    self.fix_line_endings(target);
                 } else {
-                    return Err("Filename required".to_string());
+                    // 先预览需要修改的行数，确认后再转换
+                    let confirmation = PendingConfirmation::FixLineEndings { target, lines };
+                    self.set_message(confirmation.prompt(self.locale));
+                    self.pending_confirmation = Some(confirmation);
                 }
             }
-            cmd if cmd.starts_with("s/") || cmd.starts_with("%s/") => {
-                // 处理替换命令
-                if let Some((pattern, replacement, global, full_file)) = crate::replace::parse_substitute_command(command) {
-                    with_save_state!(self, {
-                        let line_range = if full_file {
-                            None // None 表示整个文件
-                        } else {
-                            let current_line = self.cursor.line;
-                            Some((current_line, current_line + 1))
-                        };
-                        
-                        let buffer = self.current_buffer_mut();
-                        let result = crate::replace::replace_in_buffer(
-                            buffer,
-                            &pattern,
-                            &replacement,
-                            global,
-                            line_range,
-                        );
-                        
-                        self.set_message(&format!("Replaced {} occurrence(s)", result.count));
-                    });
+            "tail" => {
+                if self.is_tailing() {
+                    self.stop_tail();
                 } else {
-                    return Err("Invalid substitute command".to_string());
+                    self.start_tail()?;
                 }
             }
-            _ => return Err(format!("Unknown command: {}", parts[0])),
-        }
-
-        Ok(())
-    }
-
-    fn set_option(&mut self, option: &str) -> Result<(), String> {
-        match option {
-            "nu" | "number" => {
-                self.options.number = true;
-                self.set_message("Enabled line numbers");
+            "grep" => {
+                let mut args = self.arg_list(args)?.into_iter();
+                let pattern = args.next().ok_or_else(|| tr!(self.locale, "Argument required"))?;
+                let dir = args.next();
+                if args.next().is_some() {
+                    return Err(tr!(self.locale, "Too many arguments"));
+                }
+                self.start_grep(&pattern, dir.as_deref().map(Path::new))?;
             }
-            "nonu" | "nonumber" => {
-                self.options.number = false;
-                self.set_message("Disabled line numbers");
+            "make" => self.start_make(args, !bang)?,
+            "cnext" => self.list_next(ListKind::Quickfix, 1)?,
+            "cprevious" => self.list_previous(ListKind::Quickfix, 1)?,
+            "cfirst" => self.list_jump(ListKind::Quickfix, 0)?,
+            "clast" => self.list_jump(ListKind::Quickfix, usize::MAX)?,
+            "cc" => {
+                let index = self.list_index_arg(ListKind::Quickfix, args)?;
+                self.list_jump(ListKind::Quickfix, index)?;
             }
-            "rnu" | "relativenumber" => {
-                self.options.relativenumber = true;
-                self.set_message("Enabled relative line numbers");
+            "copen" => self.open_quickfix(),
+            "cclose" => self.close_list(ListKind::Quickfix),
+            "lnext" => self.list_next(ListKind::Location, 1)?,
+            "lprevious" => self.list_previous(ListKind::Location, 1)?,
+            "lfirst" => self.list_jump(ListKind::Location, 0)?,
+            "llast" => self.list_jump(ListKind::Location, usize::MAX)?,
+            "ll" => {
+                let index = self.list_index_arg(ListKind::Location, args)?;
+                self.list_jump(ListKind::Location, index)?;
             }
-            "nornu" | "norelativenumber" => {
-                self.options.relativenumber = false;
-                self.set_message("Disabled relative line numbers");
+            "lopen" => self.open_location_list()?,
+            "lclose" => self.close_list(ListKind::Location),
+            "Files" => {
+                let dir = self.single_arg(args)?;
+                self.open_file_finder(dir.as_deref().map(Path::new))?;
             }
-            "cursorline" => {
-                self.options.cursorline = true;
-                self.set_message("Enabled cursor line highlighting");
+            "symbols" => match self.single_arg(args)?.as_deref() {
+                None => self.open_document_symbols()?,
+                Some("workspace") | Some("ws") => self.open_workspace_symbols()?,
+                Some(scope) => return Err(tr!(self.locale, "Invalid symbols scope: {}", scope)),
+            },
+            // 不带范围时只处理当前行
+            "substitute" => self.substitute(range.unwrap_or(current_line), &format!("s{}", args))?,
+            "yank" => self.yank_lines(range.unwrap_or(current_line), args.trim_end())?,
+            "delete" => self.delete_lines(range.unwrap_or(current_line), args.trim_end())?,
+            "move" => self.move_lines(range.unwrap_or(current_line), args)?,
+            "copy" | "t" => self.copy_lines(range.unwrap_or(current_line), args)?,
+            "fold" => {
+                let range = range.unwrap_or(current_line);
+                self.create_fold(range.start, range.end)?;
             }
-            "nocursorline" => {
-                self.options.cursorline = false;
-                self.set_message("Disabled cursor line highlighting");
+            "foldopen" => self.fold_range(range.unwrap_or(current_line), true, bang),
+            "foldclose" => self.fold_range(range.unwrap_or(current_line), false, bang),
+            "!" if args.is_empty() => return Err(tr!(self.locale, "Argument required")),
+            "!" => match range {
+                None => self.start_shell_command(args, ShellAction::Show, None)?,
+                Some(range) => {
+                    // 范围内的行作为命令的输入
+                    let input = self.range_text(range);
+                    let action = ShellAction::Filter { buffer: self.current_buffer, range };
+                    self.start_shell_command(args, action, Some(input))?;
+                }
+            },
+            "shell" => self.terminal_request = Some(TerminalRequest::Shell(shell::interactive_shell())),
+            "terminal" => self.open_terminal(Some(args).filter(|args| !args.is_empty()))?,
+            // 不会自动保存，`!` 只为与 Vim 兼容
+            "suspend" | "stop" => self.suspend(),
+            "read" => {
+                // 插入到范围的最后一行（默认为当前行）之后，地址 0 插入到第一行之前
+                let at = if range::is_zero_address(command) {
+                    0
+                } else {
+                    range.map_or(self.cursor.line, |range| range.end) + 1
+                };
+                match args.strip_prefix('!').map(str::trim) {
+                    Some("") => return Err(tr!(self.locale, "Argument required")),
+                    Some(shell) => {
+                        let action = ShellAction::Read { buffer: self.current_buffer, at };
+                        self.start_shell_command(shell, action, None)?;
+                    }
+                    None => self.read_file(at, args)?,
+                }
             }
-            _ => return Err(format!("Unknown option: {}", option)),
-        }
-        Ok(())
-    }
-
+            "normal" => {
+                // 按键可以使用 <Esc>、<CR> 等记法
+                let keys = mapping::parse_keys(args);
+                if keys.is_empty() {
+                    return Err(tr!(self.locale, "Argument required"));
+                }
+                self.normal_request = Some(NormalRequest { range, keys, remap: !bang });
+            }
+            "let" => self.let_command(args)?,
+            "colorscheme" => match self.single_arg(args)? {
+                None => self.set_message(self.colorscheme),
+                Some(name) => {
+                    self.colorscheme = colorscheme::COLORSCHEMES
+                        .iter()
+                        .find(|scheme| **scheme == name)
+                        .ok_or_else(|| tr!(self.locale, "Cannot find color scheme: {}", name))?;
+                    // 与 Vim 相同，切换配色方案时清除之前的修改
+                    self.highlights.clear();
+                }
+            },
+            "highlight" => self.highlight_command(args)?,
+            "mksession" => {
+                let file = self.single_arg(args)?.unwrap_or_else(|| session::DEFAULT_SESSION_FILE.to_string());
+                let path = PathBuf::from(&file);
+                if !bang && path.exists() {
+                    return Err(tr!(self.locale, "File exists (add ! to override)"));
+                }
+                let script = self.session_script().map_err(|e| e.to_string())?;
+                std::fs::write(&path, script).map_err(|e| tr!(self.locale, "Failed to write {}: {}", file, e))?;
+                self.set_message(tr!(self.locale, "Session saved to {}", file));
+            }
+            "cd" => {
+                let dir = self
+                    .single_arg(args)?
+                    .ok_or_else(|| tr!(self.locale, "Filename required"))?;
+                std::env::set_current_dir(&dir).map_err(|e| tr!(self.locale, "Failed to change directory to {}: {}", dir, e))?;
+                self.set_message(dir);
+            }
+            "wincmd" => {
+                let mut chars = args.trim().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(key), None) => self.window_command(key, 1)?,
+                    _ => return Err(tr!(self.locale, "Invalid argument: {}", args)),
+                }
+            }
+            "call" => self.call_function(args)?,
+            "autocmd" => self.autocmd_command(bang, args)?,
+            "StripWhitespace" => self.strip_trailing_whitespace(range.unwrap_or(LineRange::new(0, last))),
+            "source" => {
+                let file = self
+                    .single_arg(args)?
+                    .ok_or_else(|| tr!(self.locale, "Filename required"))?;
+                self.source_file(Path::new(&file))?;
+            }
+            _ if self.plugins.command_owner(name).is_some() => {
+                self.execute_plugin_command(&ExCommand { range, name, bang, args })?
+            }
+            _ => {
+                // 映射的右侧可能包含空格，使用原始参数
+                let name = if bang { format!("{}!", name) } else { name.to_string() };
+                self.map_command(&name, args)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn ex_error_message(&self, error: ExError) -> String {
+        match error {
+            ExError::Range(RangeError::Invalid) => tr!(self.locale, "Invalid range"),
+            ExError::Range(RangeError::Backwards) => tr!(self.locale, "Backwards range given"),
+            ExError::Unknown(name) => tr!(self.locale, "Unknown command: {}", name),
+            ExError::Ambiguous(name) => tr!(self.locale, "Ambiguous command: {}", name),
+            ExError::NoRange(name) => tr!(self.locale, "No range allowed: {}", name),
+            ExError::NoBang(name) => tr!(self.locale, "No ! allowed: {}", name),
+            ExError::UnterminatedQuote => tr!(self.locale, "Unterminated quote"),
+        }
+    }
+
+    fn arg_list(&self, args: &str) -> Result<Vec<String>, String> {
+        ex_command::split_args(args).map_err(|e| self.ex_error_message(e))
+    }
+
+    /// 只接受一个参数的命令（如文件名），没有参数时返回 None
+    fn single_arg(&self, args: &str) -> Result<Option<String>, String> {
+        let mut args = self.arg_list(args)?;
+        if args.len() > 1 {
+            return Err(tr!(self.locale, "Too many arguments"));
+        }
+        Ok(args.pop())
+    }
+
+    /// :cc / :ll 的参数（从 1 开始的项号），没有参数时为列表的当前项
+    fn list_index_arg(&self, kind: ListKind, args: &str) -> Result<usize, String> {
+        match self.single_arg(args)? {
+            Some(arg) => arg
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n > 0)
+                .map(|n| n - 1)
+                .ok_or_else(|| tr!(self.locale, "Invalid argument: {}", arg)),
+            None => Ok(self.list(kind).map_or(0, QuickfixList::current_index)),
+        }
+    }
+
+    /// 是否执行了退出命令（:q、:wq、:qa）
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    /// 挂起编辑器 (Ctrl+Z / :suspend)，由界面恢复终端后挂起进程
+    pub fn suspend(&mut self) {
+        self.terminal_request = Some(TerminalRequest::Suspend);
+    }
+
+    /// 取出等待界面交出终端的请求
+    pub fn take_terminal_request(&mut self) -> Option<TerminalRequest> {
+        self.terminal_request.take()
+    }
+
+    /// 取出等待回放的 :normal 按键
+    pub fn take_normal_request(&mut self) -> Option<NormalRequest> {
+        self.normal_request.take()
+    }
+
+    /// 范围内各行的文本，总是以换行符结尾
+    pub fn range_text(&self, range: LineRange) -> String {
+        self.current_buffer().lines_text(range)
+    }
+
+    /// 把范围内的行写入文件 (:10,20w {file}) 或追加到文件末尾 (:10,20w >> {file})
+    ///
+    /// 不指定文件时写入当前文件：写入整个文件等同于 :w，只写入一部分需要加 `!`
+    fn write_range(&mut self, range: LineRange, args: &str, bang: bool) -> Result<(), String> {
+        let (append, target) = match args.strip_prefix(">>") {
+            Some(target) => (true, target),
+            None => (false, args),
+        };
+
+        let path = if let Some(target) = self.single_arg(target)? {
+            let path = PathBuf::from(target);
+            if !append && !bang && path.exists() {
+                return Err(tr!(self.locale, "File exists (add ! to override)"));
+            }
+            path
+        } else {
+            let path = self
+                .current_buffer()
+                .file_path()
+                .map(Path::to_path_buf)
+                .ok_or_else(|| tr!(self.locale, "No file name"))?;
+            if !append {
+                let last = self.current_buffer().last_content_line();
+                if range == LineRange::new(0, last) {
+                    return self.execute_command(if bang { "w!" } else { "w" });
+                }
+                if !bang {
+                    return Err(tr!(self.locale, "Use ! to write partial buffer"));
+                }
+            }
+            path
+        };
+        if !bang && self.is_protected_path(&path) {
+            return Err(tr!(self.locale, "{} is a protected path (use :w! to write)", path.display()));
+        }
+
+        self.current_buffer().write_lines(range, &path, append).map_err(|e| e.to_string())?;
+        let lines = range.line_count();
+        if append {
+            self.set_message(tr!(self.locale, "\"{}\" {}L appended", path.display(), lines));
+        } else {
+            self.set_message(tr!(self.locale, "\"{}\" {}L written", path.display(), lines));
+        }
+        Ok(())
+    }
+
+    /// 范围命令的寄存器参数（:y a、:d a），没有参数时返回 None
+    fn parse_register_arg(&self, args: &str) -> Result<Option<char>, String> {
+        let mut chars = args.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => Ok(None),
+            (Some(reg), None) if is_register_name(reg) => Ok(Some(reg)),
+            _ => Err(tr!(self.locale, "Invalid register: {}", args)),
+        }
+    }
+
+    /// 把范围内的行复制到寄存器 (:10,20y a)，不指定寄存器时只复制到无名寄存器
+    fn yank_lines(&mut self, range: LineRange, args: &str) -> Result<(), String> {
+        let register = self.parse_register_arg(args)?;
+        let text = self.range_text(range);
+        self.store_operator_text(&text, true, register, false);
+        self.set_message(tr!(self.locale, "{} line(s) yanked", range.line_count()));
+        Ok(())
+    }
+
+    /// 删除范围内的行到寄存器 (:1,5d a)，不指定寄存器时只保存到无名寄存器
+    fn delete_lines(&mut self, range: LineRange, args: &str) -> Result<(), String> {
+        let register = self.parse_register_arg(args)?;
+        let (start, end, _) = self.line_range(range.start, range.end);
+        self.delete_range(start, end, true, register);
+        self.set_message(tr!(self.locale, "{} line(s) deleted", range.line_count()));
+        Ok(())
+    }
+
+    /// 把范围内的行移动到目标行之后 (:m {address})，`:m 0` 移动到第一行之前
+    fn move_lines(&mut self, range: LineRange, args: &str) -> Result<(), String> {
+        let dest = self.parse_destination(args)?;
+        // 目标在范围内部时无法移动，目标紧邻范围时文本不变
+        if dest > range.start && dest <= range.end {
+            return Err(tr!(self.locale, "Cannot move a range of lines into itself"));
+        }
+        let text = self.range_text(range);
+        let count = range.line_count();
+        with_save_state!(self, {
+            let (start, end, _) = self.line_range(range.start, range.end);
+            self.current_buffer_mut().remove(start, end - start);
+            let dest = if dest > range.end { dest - count } else { dest };
+            self.current_buffer_mut().insert_lines(dest, &text);
+            self.cursor = Cursor::new(dest + count - 1, 0);
+            self.execute_motion(Motion::FirstNonBlank);
+        });
+        self.set_message(tr!(self.locale, "{} line(s) moved", count));
+        Ok(())
+    }
+
+    /// 把范围内的行复制到目标行之后 (:t {address} / :co {address})
+    fn copy_lines(&mut self, range: LineRange, args: &str) -> Result<(), String> {
+        let dest = self.parse_destination(args)?;
+        let text = self.range_text(range);
+        let count = range.line_count();
+        with_save_state!(self, {
+            self.current_buffer_mut().insert_lines(dest, &text);
+            self.cursor = Cursor::new(dest + count - 1, 0);
+            self.execute_motion(Motion::FirstNonBlank);
+        });
+        Ok(())
+    }
+
+    fn parse_destination(&self, args: &str) -> Result<usize, String> {
+        let last = self.current_buffer().last_content_line();
+        range::parse_destination(args, self.cursor.line, last).map_err(|_| tr!(self.locale, "Invalid address"))
+    }
+
+    /// 把文件的内容插入到第 `line` 行之后 (:r {file})
+    fn read_file(&mut self, at: usize, args: &str) -> Result<(), String> {
+        let file = self
+            .single_arg(args)?
+            .ok_or_else(|| tr!(self.locale, "Filename required"))?;
+        let text = std::fs::read_to_string(&file).map_err(|e| tr!(self.locale, "Failed to open {}: {}", file, e))?;
+        let lines = text.lines().count();
+        self.insert_lines_at(at, &text);
+        self.set_message(tr!(self.locale, "\"{}\" {}L read", file, lines));
+        Ok(())
+    }
+
+    /// 在第 `at` 行之前插入文本（`at` 超出文件时插入到末尾），光标移到插入的第一行
+    fn insert_lines_at(&mut self, at: usize, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let mut text = text.to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        let at = at.min(self.current_buffer().last_content_line() + 1);
+        with_save_state!(self, {
+            self.current_buffer_mut().insert_lines(at, &text);
+            self.cursor = Cursor::new(at, 0);
+            self.execute_motion(Motion::FirstNonBlank);
+        });
+    }
+
+    /// 用以换行符结尾的文本替换范围内的行，范围超出文件时截断到最后一行
+    pub fn replace_lines(&mut self, range: LineRange, text: &str) {
+        with_save_state!(self, {
+            let range = self.current_buffer_mut().replace_lines(range, text);
+            let last = self.current_buffer().last_content_line();
+            self.cursor = Cursor::new(range.start.min(last), 0);
+            self.execute_motion(Motion::FirstNonBlank);
+        });
+    }
+
+    /// 在范围内的行执行 :s 替换，有 c 标志时逐个确认
+    fn substitute(&mut self, range: LineRange, command: &str) -> Result<(), String> {
+        let mut command = crate::replace::parse_substitute_command(command)
+            .ok_or_else(|| tr!(self.locale, "Invalid substitute command"))?;
+        // 模式为空时使用上一次搜索的模式
+        if command.pattern.is_empty() {
+            let last = self.register_manager.get('/').map(|register| register.content).unwrap_or_default();
+            if last.is_empty() {
+                return Err(tr!(self.locale, "No previous search pattern"));
+            }
+            command.pattern = last;
+        }
+        if command.count_only {
+            let (count, lines) = crate::replace::count_matches(
+                self.current_buffer(),
+                &command.pattern,
+                command.global,
+                (range.start, range.end + 1),
+            );
+            if count == 0 {
+                return Err(tr!(self.locale, "Pattern not found: {}", command.pattern));
+            }
+            self.set_message(tr!(self.locale, "{} match(es) on {} line(s)", count, lines));
+            return Ok(());
+        }
+        if command.confirm {
+            match ConfirmSubstitute::start(command, self.current_buffer(), range.start, range.end) {
+                Some(state) => self.ask_substitute(state),
+                None => self.set_message(tr!(self.locale, "Replaced {} occurrence(s)", 0)),
+            }
+            return Ok(());
+        }
+        with_save_state!(self, {
+            let buffer = self.current_buffer_mut();
+            let result = crate::replace::replace_in_buffer(
+                buffer,
+                &command.pattern,
+                &command.replacement,
+                command.global,
+                Some((range.start, range.end + 1)),
+            );
+            self.set_message(tr!(self.locale, "Replaced {} occurrence(s)", result.count));
+        });
+        Ok(())
+    }
+
+    /// 把光标移到候选匹配并询问是否替换
+    fn ask_substitute(&mut self, state: ConfirmSubstitute) {
+        self.cursor = Cursor::new(state.line, state.column);
+        let confirmation = PendingConfirmation::Substitute(state);
+        self.set_message(confirmation.prompt(self.locale));
+        self.pending_confirmation = Some(confirmation);
+    }
+
+    /// 回答逐个确认的替换，所有替换作为一次修改撤销
+    fn answer_substitute(&mut self, mut state: ConfirmSubstitute, answer: ConfirmAnswer) {
+        let replace = |editor: &mut Self, state: &mut ConfirmSubstitute| {
+            // 第一次替换前记录撤销状态
+            if state.count == 0 {
+                editor.save_state();
+            }
+            state.replace(editor.current_buffer_mut());
+        };
+        let more = match answer {
+            ConfirmAnswer::Yes => {
+                replace(self, &mut state);
+                state.advance(self.current_buffer(), true)
+            }
+            ConfirmAnswer::No => state.advance(self.current_buffer(), false),
+            ConfirmAnswer::All => {
+                replace(self, &mut state);
+                while state.advance(self.current_buffer(), true) {
+                    replace(self, &mut state);
+                }
+                false
+            }
+            ConfirmAnswer::Last => {
+                replace(self, &mut state);
+                false
+            }
+            ConfirmAnswer::Quit => false,
+        };
+        if more {
+            self.ask_substitute(state);
+            return;
+        }
+        self.cursor = Cursor::new(state.line, 0);
+        self.execute_motion(Motion::FirstNonBlank);
+        self.set_message(tr!(self.locale, "Replaced {} occurrence(s)", state.count));
+    }
+
+    /// 逐个确认的替换中当前候选匹配：（缓冲区, 行, 起始列, 结束列）
+    pub fn substitute_candidate(&self) -> Option<(BufferId, usize, usize, usize)> {
+        match &self.pending_confirmation {
+            Some(PendingConfirmation::Substitute(state)) => {
+                let (start, end) = state.columns();
+                Some((self.current_buffer, state.line, start, end))
+            }
+            _ => None,
+        }
+    }
+
+    // ==================== 文件管理 ====================
+
+    /// 获取等待确认的操作
+    pub fn pending_confirmation(&self) -> Option<&PendingConfirmation> {
+        self.pending_confirmation.as_ref()
+    }
+
+    /// 处理用户对等待操作的确认（y 确认，其他取消）
+    pub fn resolve_confirmation(&mut self, accepted: bool) -> Result<(), String> {
+        self.answer_confirmation(if accepted { ConfirmAnswer::Yes } else { ConfirmAnswer::Quit })
+    }
+
+    /// 回答等待确认的操作；只有 y / n 的提示中 y 以外的回答都是取消
+    pub fn answer_confirmation(&mut self, answer: ConfirmAnswer) -> Result<(), String> {
+        let Some(confirmation) = self.pending_confirmation.take() else {
+            return Ok(());
+        };
+        match confirmation {
+            PendingConfirmation::SaveBeforeQuit { buffers, .. } => return self.answer_save_before_quit(buffers, answer),
+            PendingConfirmation::Substitute(state) => {
+                self.answer_substitute(state, answer);
+                return Ok(());
+            }
+            PendingConfirmation::Plugin { plugin, .. } => return self.answer_plugin_confirmation(plugin, answer),
+            _ => {}
+        }
+        // 退出前保存的缓冲区需要确认：取消或保存失败时不退出
+        let quit = self.quit_after_write.take();
+
+        if answer != ConfirmAnswer::Yes {
+            self.set_message(tr!(self.locale, "Cancelled"));
+            return Ok(());
+        }
+
+        let result = match confirmation {
+            PendingConfirmation::DeleteFile(_) => self.delete_current_file(),
+            PendingConfirmation::WriteProtected(path) => self.write_buffer(Some(path)),
+            PendingConfirmation::OverwriteChanged(_) => self.write_buffer(None),
+            PendingConfirmation::FixLineEndings { target, .. } => {
+                self.fix_line_endings(target);
+                Ok(())
+            }
+            PendingConfirmation::LossyWrite { path, .. } => {
+                if let Some(path) = path {
+                    self.current_buffer_mut().set_file_path(path);
+                }
+                self.current_buffer_mut().save_lossy().map_err(|e| e.to_string())?;
+                self.write_undo_history(self.current_buffer);
+                self.set_message(tr!(self.locale, "Saved"));
+                Ok(())
+            }
+            PendingConfirmation::WriteReadOnly(path) => {
+                // 文件在外部被修改过时还要再确认一次
+                if self.current_buffer().changed_on_disk() {
+                    let confirmation = PendingConfirmation::OverwriteChanged(path);
+                    self.set_message(confirmation.prompt(self.locale));
+                    self.pending_confirmation = Some(confirmation);
+                    Ok(())
+                } else {
+                    self.write_buffer(None)
+                }
+            }
+            PendingConfirmation::SaveBeforeQuit { .. }
+            | PendingConfirmation::Substitute(_)
+            | PendingConfirmation::Plugin { .. } => Ok(()),
+        };
+        result?;
+        if let Some((buffers, all)) = quit {
+            if self.pending_confirmation.is_some() {
+                // 还要再确认一次（如覆盖只读文件之后的编码提示）
+                self.quit_after_write = Some((buffers, all));
+            } else if all {
+                self.save_before_quit(buffers[1..].to_vec(), true)?;
+            } else {
+                self.ask_or_quit(buffers[1..].to_vec());
+            }
+        }
+        Ok(())
+    }
+
+    /// 退出（:q、:qa）：有修改的缓冲区时从当前缓冲区开始依次询问是否保存，`force`（!）时不保存直接退出
+    fn quit_all(&mut self, force: bool) {
+        let mut buffers: Vec<BufferId> =
+            self.buffers.values().filter(|buffer| buffer.is_modified()).map(|buffer| buffer.id()).collect();
+        buffers.sort_by_key(|&id| (id != self.current_buffer, id.as_usize()));
+        if force || buffers.is_empty() {
+            self.quit_requested = true;
+            return;
+        }
+        self.ask_save_before_quit(buffers);
+    }
+
+    /// 询问是否保存 `buffers` 的第一个缓冲区
+    fn ask_save_before_quit(&mut self, buffers: Vec<BufferId>) {
+        let name = self.buffers[&buffers[0]].display_name().unwrap_or(self.tr("[No Name]")).to_string();
+        let confirmation = PendingConfirmation::SaveBeforeQuit { buffers, name };
+        self.set_message(confirmation.prompt(self.locale));
+        self.pending_confirmation = Some(confirmation);
+    }
+
+    /// 回答退出前的保存提示：y 保存、n 不保存，之后询问下一个缓冲区；a 保存所有剩下的缓冲区；q 不退出
+    ///
+    /// 保存失败时不退出，剩下的缓冲区不再询问
+    fn answer_save_before_quit(&mut self, buffers: Vec<BufferId>, answer: ConfirmAnswer) -> Result<(), String> {
+        match answer {
+            ConfirmAnswer::Yes => self.save_before_quit(buffers, false),
+            ConfirmAnswer::All => self.save_before_quit(buffers, true),
+            ConfirmAnswer::No => {
+                self.ask_or_quit(buffers[1..].to_vec());
+                Ok(())
+            }
+            // 保存提示没有 l
+            ConfirmAnswer::Quit | ConfirmAnswer::Last => {
+                self.set_message(tr!(self.locale, "Cancelled"));
+                Ok(())
+            }
+        }
+    }
+
+    /// 保存 `buffers` 的第一个缓冲区（`all` 时保存所有），之后询问下一个缓冲区或退出
+    ///
+    /// 与 :w 一样检查：保存需要确认时切换到这个缓冲区询问，确认并保存之后继续
+    fn save_before_quit(&mut self, buffers: Vec<BufferId>, all: bool) -> Result<(), String> {
+        let saved = if all { buffers.len() } else { buffers.len().min(1) };
+        for (i, &id) in buffers[..saved].iter().enumerate() {
+            self.switch_buffer(id)?;
+            self.write_current(None, false).map_err(|e| {
+                let name = self.current_buffer().display_name().unwrap_or(self.tr("[No Name]")).to_string();
+                tr!(self.locale, "Cannot save {}: {}", name, e)
+            })?;
+            if self.pending_confirmation.is_some() {
+                self.quit_after_write = Some((buffers[i..].to_vec(), all));
+                return Ok(());
+            }
+        }
+        self.ask_or_quit(buffers[saved..].to_vec());
+        Ok(())
+    }
+
+    /// 还有没询问的缓冲区时询问第一个，否则退出
+    fn ask_or_quit(&mut self, buffers: Vec<BufferId>) {
+        if buffers.is_empty() {
+            self.quit_requested = true;
+        } else {
+            self.ask_save_before_quit(buffers);
+        }
+    }
+
+    /// 获取受保护的路径模式
+    pub fn protected_paths(&self) -> &[String] {
+        &self.protected_paths
+    }
+
+    /// 设置受保护的路径模式（如 `/etc/*`）
+    pub fn set_protected_paths(&mut self, patterns: Vec<String>) {
+        self.protected_paths = patterns;
+    }
+
+    /// 检查路径是否受保护
+    pub fn is_protected_path(&self, path: &Path) -> bool {
+        crate::file_ops::is_protected(path, &self.protected_paths)
+    }
+
+    /// 重命名当前缓冲区对应的文件 (:Rename)
+    ///
+    /// 磁盘文件存在时执行重命名，然后更新缓冲区路径
+    pub fn rename_current_file(&mut self, new_path: &Path, force: bool) -> Result<(), String> {
+        let old_path = self
+            .current_buffer()
+            .file_path()
+            .map(|p| p.to_path_buf())
+            .ok_or_else(|| tr!(self.locale, "No file name"))?;
+
+        crate::file_ops::rename_file(&old_path, new_path, force)
+            .map_err(|e| tr!(self.locale, "Failed to rename {}: {}", old_path.display(), e))?;
+
+        self.current_buffer_mut().set_file_path(new_path.to_path_buf());
+        Ok(())
+    }
+
+    /// 删除当前缓冲区对应的文件并关闭缓冲区 (:Delete!)
+    pub fn delete_current_file(&mut self) -> Result<(), String> {
+        let path = self
+            .current_buffer()
+            .file_path()
+            .map(|p| p.to_path_buf())
+            .ok_or_else(|| tr!(self.locale, "No file name"))?;
+
+        let use_trash = self.options.trash;
+        if use_trash {
+            crate::file_ops::move_to_trash(&path)
+        } else {
+            crate::file_ops::delete_file(&path)
+        }
+        .map_err(|e| tr!(self.locale, "Failed to delete {}: {}", path.display(), e))?;
+
+        let buffer_id = self.current_buffer_id();
+        self.delete_buffer_force(buffer_id)?;
+        if use_trash {
+            self.set_message(tr!(self.locale, "Moved {} to trash", path.display()));
+        } else {
+            self.set_message(tr!(self.locale, "Deleted {}", path.display()));
+        }
+        Ok(())
+    }
+
+    /// :setlocal：只设置当前窗口或缓冲区的局部值，只有全局值的选项设置全局值
+    fn set_local_option(&mut self, option: &str) -> Result<(), String> {
+        let Some(local) = LocalOption::parse(option) else {
+            return self.set_option(option);
+        };
+        // 按 :set 检查值和显示提示，再把新值存为局部值并恢复全局值
+        let global = self.options;
+        let result = self.set_option(option);
+        let options = std::mem::replace(&mut self.options, global);
+        result?;
+        self.update_local_options(|window, buffer| local.store(&options, window, buffer));
+        Ok(())
+    }
+
+    /// 修改当前窗口和当前缓冲区的局部选项
+    fn update_local_options(&mut self, update: impl FnOnce(&mut WindowOptions, &mut BufferOptions)) {
+        let buffer = self.buffer_options.entry(self.current_buffer).or_default();
+        update(&mut self.windows.current_mut().options, buffer);
+    }
+
+    fn set_option(&mut self, option: &str) -> Result<(), String> {
+        // :set all 在面板中列出所有选项，:set name? 显示选项的值
+        if option == "all" {
+            self.show_options_panel = true;
+            return Ok(());
+        }
+        if let Some(name) = option.strip_suffix('?') {
+            let value = self.option_value(name).ok_or_else(|| tr!(self.locale, "Unknown option: {}", name))?;
+            self.set_message(value);
+            return Ok(());
+        }
+
+        // 带值的选项：name=value
+        if let Some((name, value)) = option.split_once('=') {
+            return match name {
+                "sw" | "shiftwidth" => {
+                    let width = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|w| *w > 0)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.shiftwidth = width;
+                    self.set_message(tr!(self.locale, "shiftwidth={}", width));
+                    Ok(())
+                }
+                "ts" | "tabstop" => {
+                    let tabstop = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|w| *w > 0)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.tabstop = tabstop;
+                    self.set_message(format!("tabstop={}", tabstop));
+                    Ok(())
+                }
+                "tw" | "textwidth" => {
+                    let width = value
+                        .parse::<usize>()
+                        .map_err(|_| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.textwidth = width;
+                    self.set_message(format!("textwidth={}", width));
+                    Ok(())
+                }
+                "tm" | "timeoutlen" => {
+                    let timeout = value
+                        .parse::<usize>()
+                        .map_err(|_| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.timeoutlen = timeout;
+                    self.set_message(format!("timeoutlen={}", timeout));
+                    Ok(())
+                }
+                "ut" | "updatetime" => {
+                    let time = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|t| *t > 0)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.updatetime = time;
+                    self.set_message(format!("updatetime={}", time));
+                    Ok(())
+                }
+                "ul" | "undolevels" | "undomaxmem" => {
+                    let limit = value
+                        .parse::<usize>()
+                        .map_err(|_| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    if name == "undomaxmem" {
+                        self.options.undomaxmem = limit;
+                    } else {
+                        self.options.undolevels = limit;
+                    }
+                    self.apply_undo_limits();
+                    self.set_message(format!("{}={}", name, limit));
+                    Ok(())
+                }
+                "lang" | "language" => {
+                    let locale = Locale::parse(value)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.locale = locale;
+                    self.set_message(tr!(self.locale, "Language set to {}", locale.code()));
+                    Ok(())
+                }
+                "fcs" | "fillchars" => {
+                    let fillchars = FillChars::parse(value, self.options.asciiglyphs)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.fillchars = fillchars;
+                    self.set_message(format!("fillchars={}", fillchars.format()));
+                    Ok(())
+                }
+                "fdm" | "foldmethod" => {
+                    let method = FoldMethod::parse(value)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.foldmethod = method;
+                    self.update_folds();
+                    self.set_message(format!("foldmethod={}", method.name()));
+                    Ok(())
+                }
+                "protectedpaths" => {
+                    self.protected_paths = crate::file_ops::split_patterns(value);
+                    self.set_message(format!("protectedpaths={}", self.protected_paths.join(",")));
+                    Ok(())
+                }
+                "udir" | "undodir" => {
+                    self.undo_dir = PathBuf::from(value);
+                    self.set_message(format!("undodir={}", value));
+                    Ok(())
+                }
+                "ff" | "fileformat" => {
+                    let format = match value {
+                        "unix" => LineEnding::Lf,
+                        "dos" => LineEnding::Crlf,
+                        _ => return Err(tr!(self.locale, "Invalid value for {}: {}", name, value)),
+                    };
+                    self.current_buffer_mut().set_line_ending(format);
+                    self.set_message(format!("fileformat={}", format.format_name()));
+                    Ok(())
+                }
+                "fenc" | "fileencoding" => {
+                    let encoding = Encoding::parse(value)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.current_buffer_mut().set_encoding(encoding);
+                    self.set_message(format!("fileencoding={}", encoding.name()));
+                    Ok(())
+                }
+                "aidelay" => {
+                    let delay = value
+                        .parse::<usize>()
+                        .map_err(|_| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.options.aidelay = delay;
+                    self.set_message(format!("aidelay={}", delay));
+                    Ok(())
+                }
+                "aiprovider" => {
+                    let provider = ProviderKind::parse(value)
+                        .ok_or_else(|| tr!(self.locale, "Invalid value for {}: {}", name, value))?;
+                    self.ai_config = self.ai_settings.config(provider);
+                    self.set_message(format!("aiprovider={} ({})", provider.name(), self.ai_config.model));
+                    Ok(())
+                }
+                "aiendpoint" | "aimodel" | "aikeyenv" => {
+                    if value.trim().is_empty() {
+                        return Err(tr!(self.locale, "Invalid value for {}: {}", name, value));
+                    }
+                    let field = match name {
+                        "aiendpoint" => &mut self.ai_config.endpoint,
+                        "aimodel" => &mut self.ai_config.model,
+                        _ => &mut self.ai_config.api_key_env,
+                    };
+                    *field = value.to_string();
+                    self.set_message(format!("{}={}", name, value));
+                    Ok(())
+                }
+                "mp" | "makeprg" => {
+                    if value.trim().is_empty() {
+                        return Err(tr!(self.locale, "Invalid value for {}: {}", name, value));
+                    }
+                    self.make_program = value.to_string();
+                    self.set_message(format!("makeprg={}", value));
+                    Ok(())
+                }
+                _ => Err(tr!(self.locale, "Unknown option: {}", name)),
+            };
+        }
+
+        match option {
+            "nu" | "number" => {
+                self.options.number = true;
+                self.set_message(tr!(self.locale, "Enabled line numbers"));
+            }
+            "nonu" | "nonumber" => {
+                self.options.number = false;
+                self.set_message(tr!(self.locale, "Disabled line numbers"));
+            }
+            "rnu" | "relativenumber" => {
+                self.options.relativenumber = true;
+                self.set_message(tr!(self.locale, "Enabled relative line numbers"));
+            }
+            "nornu" | "norelativenumber" => {
+                self.options.relativenumber = false;
+                self.set_message(tr!(self.locale, "Disabled relative line numbers"));
+            }
+            "cursorline" => {
+                self.options.cursorline = true;
+                self.set_message(tr!(self.locale, "Enabled cursor line highlighting"));
+            }
+            "nocursorline" => {
+                self.options.cursorline = false;
+                self.set_message(tr!(self.locale, "Disabled cursor line highlighting"));
+            }
+            "cuc" | "cursorcolumn" => {
+                self.options.cursorcolumn = true;
+                self.set_message(tr!(self.locale, "Enabled cursor column highlighting"));
+            }
+            "nocuc" | "nocursorcolumn" => {
+                self.options.cursorcolumn = false;
+                self.set_message(tr!(self.locale, "Disabled cursor column highlighting"));
+            }
+            "ai" | "autoindent" => {
+                self.options.autoindent = true;
+                self.set_message(tr!(self.locale, "Enabled auto-indent"));
+            }
+            "noai" | "noautoindent" => {
+                self.options.autoindent = false;
+                self.set_message(tr!(self.locale, "Disabled auto-indent"));
+            }
+            "paste" => {
+                self.options.paste = true;
+                self.auto_paste = false;
+                self.set_message(tr!(self.locale, "Paste mode on"));
+            }
+            "nopaste" => {
+                self.options.paste = false;
+                self.auto_paste = false;
+                self.set_message(tr!(self.locale, "Paste mode off"));
+            }
+            "pastedetect" => {
+                self.options.pastedetect = true;
+                self.set_message(tr!(self.locale, "Enabled paste detection"));
+            }
+            "nopastedetect" => {
+                self.options.pastedetect = false;
+                self.paste_detector.reset();
+                self.stop_auto_paste();
+                self.set_message(tr!(self.locale, "Disabled paste detection"));
+            }
+            "autopairs" => {
+                self.options.autopairs = true;
+                self.set_message("autopairs");
+            }
+            "noautopairs" => {
+                self.options.autopairs = false;
+                self.set_message("noautopairs");
+            }
+            "asciiglyphs" => {
+                self.set_ascii_glyphs(true);
+                self.set_message(tr!(self.locale, "Using ASCII glyphs"));
+            }
+            "noasciiglyphs" => {
+                self.set_ascii_glyphs(false);
+                self.set_message(tr!(self.locale, "Using Unicode glyphs"));
+            }
+            "ar" | "autoread" => {
+                self.start_watching()?;
+                self.options.autoread = true;
+                self.set_message(tr!(self.locale, "Files changed on disk will be reloaded automatically"));
+            }
+            "noar" | "noautoread" => {
+                self.options.autoread = false;
+                self.watcher = None;
+                self.set_message(tr!(self.locale, "Files changed on disk will not be reloaded"));
+            }
+            "udf" | "undofile" => {
+                self.options.undofile = true;
+                // 已经打开、还没有修改历史的缓冲区立即恢复
+                let ids: Vec<BufferId> = self
+                    .buffers
+                    .keys()
+                    .filter(|id| self.undo_trees.get(id).is_none_or(UndoTree::is_empty))
+                    .copied()
+                    .collect();
+                for id in ids {
+                    self.read_undo_history(id);
+                }
+                self.set_message(tr!(self.locale, "Undo history will be saved to {}", self.undo_dir.display()));
+            }
+            "noudf" | "noundofile" => {
+                self.options.undofile = false;
+                self.set_message(tr!(self.locale, "Undo history will not be saved"));
+            }
+            "wrap" => {
+                self.options.wrap = true;
+                self.set_message("wrap");
+            }
+            "nowrap" => {
+                self.options.wrap = false;
+                self.set_message("nowrap");
+            }
+            "et" | "expandtab" => {
+                self.options.expandtab = true;
+                self.set_message("expandtab");
+            }
+            "noet" | "noexpandtab" => {
+                self.options.expandtab = false;
+                self.set_message("noexpandtab");
+            }
+            "trash" => {
+                self.options.trash = true;
+                self.set_message(tr!(self.locale, "Deleted files will be moved to trash"));
+            }
+            "notrash" => {
+                self.options.trash = false;
+                self.set_message(tr!(self.locale, "Deleted files will be removed permanently"));
+            }
+            "aicomplete" => {
+                self.options.aicomplete = true;
+                self.set_message(tr!(self.locale, "AI completion enabled ({})", self.ai_config.model));
+            }
+            "noaicomplete" => {
+                self.options.aicomplete = false;
+                self.set_message(tr!(self.locale, "AI completion disabled"));
+            }
+            "lsp" => {
+                self.options.lsp = true;
+                self.set_message(tr!(self.locale, "Language servers enabled"));
+            }
+            "nolsp" => {
+                self.options.lsp = false;
+                self.set_message(tr!(self.locale, "Language servers stopped"));
+            }
+            "gitsigns" => {
+                self.options.gitsigns = true;
+                self.set_message("gitsigns");
+            }
+            "nogitsigns" => {
+                self.options.gitsigns = false;
+                self.set_message("nogitsigns");
+            }
+            // 不带值的非布尔选项显示它的值（:set ts）
+            _ => match self.option_value(option) {
+                Some(value) => self.set_message(value),
+                None => return Err(tr!(self.locale, "Unknown option: {}", option)),
+            },
+        }
+        Ok(())
+    }
+
+    /// 选项当前的值：布尔选项为 `name` / `noname`，其他选项为 `name=value`；
+    /// 局部选项为当前窗口和缓冲区中生效的值。`name` 可以是缩写，不是选项时返回 None
+    pub fn option_value(&self, name: &str) -> Option<String> {
+        let options = self.current_options();
+        let flag = |name: &str, on: bool| format!("{}{}", if on { "" } else { "no" }, name);
+        let value = match name {
+            "nu" | "number" => flag("number", options.number),
+            "rnu" | "relativenumber" => flag("relativenumber", options.relativenumber),
+            "cursorline" => flag("cursorline", options.cursorline),
+            "cuc" | "cursorcolumn" => flag("cursorcolumn", options.cursorcolumn),
+            "wrap" => flag("wrap", options.wrap),
+            "et" | "expandtab" => flag("expandtab", options.expandtab),
+            "ai" | "autoindent" => flag("autoindent", options.autoindent),
+            "paste" => flag("paste", options.paste),
+            "pastedetect" => flag("pastedetect", options.pastedetect),
+            "autopairs" => flag("autopairs", options.autopairs),
+            "asciiglyphs" => flag("asciiglyphs", options.asciiglyphs),
+            "ar" | "autoread" => flag("autoread", options.autoread),
+            "udf" | "undofile" => flag("undofile", options.undofile),
+            "trash" => flag("trash", options.trash),
+            "aicomplete" => flag("aicomplete", options.aicomplete),
+            "lsp" => flag("lsp", options.lsp),
+            "gitsigns" => flag("gitsigns", options.gitsigns),
+            "sw" | "shiftwidth" => format!("shiftwidth={}", options.shiftwidth),
+            "ts" | "tabstop" => format!("tabstop={}", options.tabstop),
+            "tw" | "textwidth" => format!("textwidth={}", options.textwidth),
+            "tm" | "timeoutlen" => format!("timeoutlen={}", options.timeoutlen),
+            "ut" | "updatetime" => format!("updatetime={}", options.updatetime),
+            "ul" | "undolevels" => format!("undolevels={}", options.undolevels),
+            "undomaxmem" => format!("undomaxmem={}", options.undomaxmem),
+            "aidelay" => format!("aidelay={}", options.aidelay),
+            "lang" | "language" => format!("language={}", self.locale.code()),
+            "fcs" | "fillchars" => format!("fillchars={}", options.fillchars.format()),
+            "fdm" | "foldmethod" => format!("foldmethod={}", options.foldmethod.name()),
+            "udir" | "undodir" => format!("undodir={}", self.undo_dir.display()),
+            "protectedpaths" => format!("protectedpaths={}", self.protected_paths.join(",")),
+            "ff" | "fileformat" => format!("fileformat={}", self.current_buffer().line_ending().format_name()),
+            "fenc" | "fileencoding" => format!("fileencoding={}", self.current_buffer().encoding().name()),
+            "aiprovider" => format!("aiprovider={}", self.ai_config.provider.name()),
+            "aiendpoint" => format!("aiendpoint={}", self.ai_config.endpoint),
+            "aimodel" => format!("aimodel={}", self.ai_config.model),
+            "aikeyenv" => format!("aikeyenv={}", self.ai_config.api_key_env),
+            "mp" | "makeprg" => format!("makeprg={}", self.make_program),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// 所有选项当前的值（:set all），按选项名排序
+    pub fn option_values(&self) -> Vec<String> {
+        ALL_OPTIONS.iter().filter_map(|name| self.option_value(name)).collect()
+    }
+
     pub fn get_line_text(&self, line_idx: usize) -> Option<String> {
-        self.current_buffer().line(line_idx).map(|l| {
-            let text = l.to_string();
-            text.strip_suffix('\n').map(String::from).unwrap_or(text)
+        self.current_buffer().line_text(line_idx)
+    }
+
+    /// 从第 `scroll_offset` 行开始最多 `viewport_height` 个屏幕行，`width` 为文本区域的宽度
+    ///
+    /// wrap 时长行折成多个屏幕行；nowrap 时每行一个屏幕行，水平滚动到光标可见
+    pub fn visible_lines(&self, viewport_height: usize, width: usize, scroll_offset: usize) -> Vec<ScreenRow> {
+        let options = self.current_options();
+        self.buffer_rows(self.current_buffer(), &options, self.cursor, viewport_height, width, scroll_offset)
+    }
+
+    /// 一行占据的屏幕行数
+    pub fn screen_rows(&self, line: usize, width: usize) -> usize {
+        self.buffer_screen_rows(self.current_buffer(), &self.current_options(), line, width)
+    }
+
+    /// 光标在文本区域中的位置（列, 屏幕行），考虑折行、Tab 和宽字符
+    ///
+    /// 光标所在的行在 `scroll_offset` 之前时返回 None
+    pub fn cursor_screen_position(&self, width: usize, scroll_offset: usize) -> Option<(usize, usize)> {
+        self.buffer_cursor_position(self.current_buffer(), &self.current_options(), self.cursor, width, scroll_offset)
+    }
+
+    /// 第 `column` 列（显示宽度）在折行后的屏幕行序号和行内的列
+    fn wrapped_position(&self, line: usize, column: usize, width: usize) -> (usize, usize) {
+        self.buffer_wrapped_position(self.current_buffer(), &self.current_options(), line, column, width)
+    }
+
+    /// 按窗口中生效的选项 `options`（wrap、tabstop）排列屏幕行，以下几个函数相同
+    fn buffer_rows(
+        &self,
+        buffer: &Buffer,
+        options: &EditorOptions,
+        cursor: Cursor,
+        viewport_height: usize,
+        width: usize,
+        scroll_offset: usize,
+    ) -> Vec<ScreenRow> {
+        let tabstop = options.tabstop;
+        let left = self.buffer_left_column(buffer, options, cursor, width);
+        let mut rows = Vec::new();
+        let mut line = scroll_offset;
+        while line < buffer.len_lines() && rows.len() < viewport_height {
+            // 关闭的折叠显示为一行摘要
+            if let Some((start, end)) = buffer.folds().closed_range(line) {
+                let text = self.fold_summary(buffer, tabstop, start, end, width);
+                rows.push(ScreenRow { line: start, first: true, column: 0, text, fold_end: Some(end) });
+                line = end + 1;
+                continue;
+            }
+            let Some(text) = buffer.line_text(line) else {
+                break;
+            };
+            if options.wrap {
+                for (index, (column, text)) in display::wrap_line(&text, width, tabstop).into_iter().enumerate() {
+                    rows.push(ScreenRow { line, first: index == 0, column, text, fold_end: None });
+                }
+            } else {
+                let text = display::clip_line(&text, left, width, tabstop);
+                rows.push(ScreenRow { line, first: true, column: left, text, fold_end: None });
+            }
+            line += 1;
+        }
+        rows.truncate(viewport_height);
+        rows
+    }
+
+    /// 关闭的折叠的摘要：`+--  5 lines: 第一行的文本`，用 fillchars 的 fold 字符填满文本区域的宽度
+    fn fold_summary(&self, buffer: &Buffer, tabstop: usize, start: usize, end: usize, width: usize) -> String {
+        let fillchars = self.options.fillchars;
+        let dashes = fillchars.fold.to_string().repeat(buffer.folds().level(start, end) + 1);
+        let text = buffer.line_text(start).unwrap_or_default();
+        let text = text.trim().replace('\t', " ");
+        let summary = format!("{}{}{:>3} {}", fillchars.foldclose, dashes, end - start + 1, tr!(self.locale, "lines: {}", text));
+        let summary = display::clip_line(&summary, 0, width, tabstop);
+        let fill = width.saturating_sub(display::display_column(&summary, summary.chars().count(), tabstop));
+        summary + &fillchars.fold.to_string().repeat(fill)
+    }
+
+    fn buffer_screen_rows(&self, buffer: &Buffer, options: &EditorOptions, line: usize, width: usize) -> usize {
+        if !options.wrap {
+            return 1;
+        }
+        let text = buffer.line_text(line).unwrap_or_default();
+        display::wrap_line(&text, width, options.tabstop).len()
+    }
+
+    /// 从第 `from` 行显示到第 `to` 行之前占据的屏幕行数（关闭的折叠占一行）
+    fn buffer_rows_between(
+        &self,
+        buffer: &Buffer,
+        options: &EditorOptions,
+        from: usize,
+        to: usize,
+        width: usize,
+    ) -> usize {
+        let mut rows = 0;
+        let mut line = from;
+        while line < to {
+            match buffer.folds().closed_range(line) {
+                Some((_, end)) => {
+                    rows += 1;
+                    line = end + 1;
+                }
+                None => {
+                    rows += self.buffer_screen_rows(buffer, options, line, width);
+                    line += 1;
+                }
+            }
+        }
+        rows
+    }
+
+    /// nowrap 时水平滚动的列数：光标超出文本区域右边时让光标位于最右一列
+    fn buffer_left_column(&self, buffer: &Buffer, options: &EditorOptions, cursor: Cursor, width: usize) -> usize {
+        if options.wrap {
+            return 0;
+        }
+        let column = buffer_display_column(buffer, cursor, options.tabstop);
+        (column + 1).saturating_sub(width.max(1))
+    }
+
+    fn buffer_cursor_position(
+        &self,
+        buffer: &Buffer,
+        options: &EditorOptions,
+        cursor: Cursor,
+        width: usize,
+        scroll_offset: usize,
+    ) -> Option<(usize, usize)> {
+        let line = cursor.line;
+        if line < scroll_offset {
+            return None;
+        }
+        // 光标所在的行被折叠时，光标在折叠的摘要行的开头
+        if let Some((start, _)) = buffer.folds().closed_range(line) {
+            return Some((0, self.buffer_rows_between(buffer, options, scroll_offset, start.max(scroll_offset), width)));
+        }
+        let above = self.buffer_rows_between(buffer, options, scroll_offset, line, width);
+        let column = buffer_display_column(buffer, cursor, options.tabstop);
+        if !options.wrap {
+            return Some((column - self.buffer_left_column(buffer, options, cursor, width), above));
+        }
+        let (row, x) = self.buffer_wrapped_position(buffer, options, line, column, width);
+        // 行尾恰好填满一个屏幕行时，Insert 模式的光标停在最后一列
+        Some((x.min(width.saturating_sub(1)), above + row))
+    }
+
+    fn buffer_wrapped_position(
+        &self,
+        buffer: &Buffer,
+        options: &EditorOptions,
+        line: usize,
+        column: usize,
+        width: usize,
+    ) -> (usize, usize) {
+        let text = buffer.line_text(line).unwrap_or_default();
+        let rows = display::wrap_line(&text, width, options.tabstop);
+        let row = rows.iter().rposition(|(start, _)| *start <= column).unwrap_or(0);
+        (row, column - rows[row].0)
+    }
+
+    /// 按屏幕行上下移动光标 (gj / gk)，保持光标在屏幕行中的列
+    ///
+    /// nowrap 时与 j / k 相同
+    pub fn move_screen_line(&mut self, down: bool, count: usize, width: usize) {
+        let options = self.current_options();
+        if !options.wrap {
+            let motion = if down { Motion::Down } else { Motion::Up };
+            for _ in 0..count {
+                self.execute_motion(motion);
+            }
+            return;
+        }
+        let tabstop = options.tabstop;
+        let mut line = self.cursor.line;
+        let column = self.display_column(line, self.cursor.column);
+        let (mut row, x) = self.wrapped_position(line, column, width);
+        let last = self.current_buffer().last_content_line();
+        for _ in 0..count {
+            if down {
+                if row + 1 < self.screen_rows(line, width) {
+                    row += 1;
+                } else if line < last {
+                    line += 1;
+                    row = 0;
+                } else {
+                    break;
+                }
+            } else if row > 0 {
+                row -= 1;
+            } else if line > 0 {
+                line -= 1;
+                row = self.screen_rows(line, width) - 1;
+            } else {
+                break;
+            }
+        }
+
+        let text = self.get_line_text(line).unwrap_or_default();
+        let rows = display::wrap_line(&text, width, tabstop);
+        let (start, row_text) = &rows[row];
+        let row_width: usize = row_text.chars().map(|c| display::char_width(c, 0, tabstop)).sum();
+        let target = start + x.min(row_width.saturating_sub(1));
+        let mut char_column = display::char_at_column(&text, target, tabstop);
+        if !self.mode.is_insert() {
+            char_column = char_column.min(display::last_grapheme(&text));
+        }
+        self.cursor = Cursor::new(line, char_column);
+    }
+
+    // ==================== 滚动 ====================
+
+    /// 把光标所在行滚动到窗口的顶部、中间或底部 (zt / zz / zb)，光标不动
+    ///
+    /// `width` 和 `height` 为当前窗口文本区域的大小，折行和关闭的折叠按屏幕行计算
+    pub fn scroll_cursor_to(&mut self, anchor: ScrollAnchor, width: usize, height: usize) {
+        let (start, end) = self.closed_fold(self.cursor.line).unwrap_or((self.cursor.line, self.cursor.line));
+        let options = self.current_options();
+        let cursor_rows = self.buffer_rows_between(self.current_buffer(), &options, start, end + 1, width);
+        let offset = match anchor {
+            ScrollAnchor::Top => start,
+            ScrollAnchor::Center => self.top_line_within(start, height.saturating_sub(cursor_rows) / 2, width),
+            ScrollAnchor::Bottom => self.top_line_within(start, height.saturating_sub(cursor_rows), width),
+        };
+        self.set_scroll_offset(offset);
+    }
+
+    /// 窗口向下 (`lines` 为正，Ctrl+E) 或向上 (Ctrl+Y) 滚动 `lines` 行，关闭的折叠算一行
+    ///
+    /// 光标所在行滚出窗口时移到窗口中的第一行或最后一个完整显示的行
+    pub fn scroll_window(&mut self, lines: isize, width: usize, height: usize) {
+        let last = self.current_buffer().last_content_line();
+        let mut offset = self.scroll_offset().min(last);
+        for _ in 0..lines.unsigned_abs() {
+            if lines > 0 {
+                let next = self.closed_fold(offset).map_or(offset, |(_, end)| end) + 1;
+                if next > last {
+                    break;
+                }
+                offset = next;
+            } else if offset > 0 {
+                offset = self.closed_fold(offset - 1).map_or(offset - 1, |(start, _)| start);
+            }
+        }
+        self.set_scroll_offset(offset);
+
+        let options = self.current_options();
+        let mut line = self.cursor.line.max(offset);
+        while line > offset {
+            // 光标所在行（或折叠）的最后一个屏幕行在窗口内时不再移动
+            let next = self.closed_fold(line).map_or(line, |(_, end)| end) + 1;
+            if self.buffer_rows_between(self.current_buffer(), &options, offset, next, width) <= height {
+                break;
+            }
+            line = self.closed_fold(line - 1).map_or(line - 1, |(start, _)| start);
+        }
+        if line != self.cursor.line {
+            let mut cursor = self.cursor;
+            cursor.line = line;
+            cursor.restore_preferred_column(self.current_buffer());
+            self.cursor = cursor;
+        }
+    }
+
+    /// 从第 `line` 行向上，在 `rows` 个屏幕行之内能显示到的最上面一行（关闭的折叠占一行）
+    fn top_line_within(&self, line: usize, rows: usize, width: usize) -> usize {
+        let options = self.current_options();
+        let buffer = self.current_buffer();
+        let mut top = line;
+        let mut used = 0;
+        while top > 0 {
+            let above = buffer.folds().closed_range(top - 1).map_or(top - 1, |(start, _)| start);
+            let above_rows = self.buffer_rows_between(buffer, &options, above, top, width);
+            if used + above_rows > rows {
+                break;
+            }
+            used += above_rows;
+            top = above;
+        }
+        top
+    }
+
+    // ==================== 折叠 ====================
+
+    /// foldmethod 为 indent / syntax 时，重新计算内容变化过的缓冲区的折叠；manual 时保留现有的折叠
+    pub fn update_folds(&mut self) {
+        let method = self.options.foldmethod;
+        for buffer in self.buffers.values_mut() {
+            if method == FoldMethod::Manual {
+                buffer.folds_mut().set_manual();
+                continue;
+            }
+            let tick = buffer.changedtick();
+            if buffer.folds().computed() == Some((method, tick)) {
+                continue;
+            }
+            let local = self.buffer_options.get(&buffer.id()).copied().unwrap_or_default();
+            let options = local_options::effective(&self.options, &WindowOptions::default(), &local);
+            let ranges = match method {
+                FoldMethod::Indent => fold::indent_folds(buffer, options.shiftwidth, options.tabstop),
+                _ => crate::syntax::fold_ranges(buffer),
+            };
+            buffer.folds_mut().set_computed(method, tick, ranges);
+        }
+    }
+
+    /// 当前缓冲区中第 `line` 行所在的关闭的折叠的行范围
+    pub fn closed_fold(&self, line: usize) -> Option<(usize, usize)> {
+        self.current_buffer().folds().closed_range(line)
+    }
+
+    /// 创建关闭的折叠 (zf / zF / :fold)，只能在 foldmethod=manual 时使用；光标移到折叠的第一行
+    pub fn create_fold(&mut self, first: usize, last: usize) -> Result<(), String> {
+        if self.options.foldmethod != FoldMethod::Manual {
+            return Err(tr!(self.locale, "Cannot create fold with foldmethod={}", self.options.foldmethod.name()));
+        }
+        self.current_buffer_mut().folds_mut().create(first, last);
+        if (first..=last).contains(&self.cursor.line) {
+            self.cursor.line = first;
+            let buffer = &self.buffers[&self.current_buffer];
+            self.cursor.restore_preferred_column(buffer);
+        }
+        Ok(())
+    }
+
+    /// z 开头的折叠命令：zo / zO 打开，zc / zC 关闭，za / zA 切换，zR / zM 打开 / 关闭所有折叠，
+    /// zd / zD 删除光标处的折叠，zE 删除所有折叠。其他按键忽略
+    pub fn fold_command(&mut self, key: char) -> Result<(), String> {
+        self.update_folds();
+        if matches!(key, 'd' | 'D' | 'E') && self.options.foldmethod != FoldMethod::Manual {
+            return Err(tr!(self.locale, "Cannot delete fold with foldmethod={}", self.options.foldmethod.name()));
+        }
+        let line = self.cursor.line;
+        let folds = self.current_buffer_mut().folds_mut();
+        let found = match key {
+            'o' | 'O' => folds.open(line, key == 'O'),
+            'c' | 'C' => folds.close(line, key == 'C'),
+            'a' | 'A' => folds.toggle(line, key == 'A'),
+            'd' | 'D' => folds.delete(line, key == 'D'),
+            'R' => {
+                folds.open_all();
+                true
+            }
+            'M' => {
+                folds.close_all();
+                true
+            }
+            'E' => {
+                folds.clear();
+                true
+            }
+            _ => true,
+        };
+        if !found {
+            return Err(tr!(self.locale, "No fold found"));
+        }
+        Ok(())
+    }
+
+    /// 打开或关闭范围内的折叠（:foldopen / :foldclose），`all` 时包括所有层
+    fn fold_range(&mut self, range: LineRange, open: bool, all: bool) {
+        self.update_folds();
+        let folds = self.current_buffer_mut().folds_mut();
+        if open {
+            folds.open_range(range.start, range.end, all);
+        } else {
+            folds.close_range(range.start, range.end, all);
+        }
+    }
+
+    /// 把行范围扩展到包含两端所在的关闭的折叠（如 dd 删除整个折叠）
+    fn fold_line_span(&self, first_line: usize, last_line: usize) -> (usize, usize) {
+        let folds = self.current_buffer().folds();
+        let first = folds.closed_range(first_line).map_or(first_line, |(start, _)| start);
+        let last = folds.closed_range(last_line).map_or(last_line, |(_, end)| end);
+        (first, last)
+    }
+
+    // ==================== 包围 ====================
+
+    /// 用 `ch` 对应的一对字符包围 start..end (ys)，光标移到左边的字符上
+    ///
+    /// 去掉范围末尾的空白（如 ysaw 不包围单词后的空格）；整行时包围第一个非空字符到最后一行的行尾
+    fn surround_range(&mut self, start: usize, end: usize, linewise: bool, ch: char) -> Result<(), String> {
+        let (open, close) =
+            pairs::surround_delimiters(ch).ok_or_else(|| tr!(self.locale, "Invalid surround character: {}", ch))?;
+        let buffer = self.current_buffer();
+        let text = buffer.slice(start..end);
+        let start = if linewise {
+            start + text.chars().take_while(|c| c.is_whitespace()).count()
+        } else {
+            start
+        };
+        let end = end - text.chars_at(text.len_chars()).reversed().take_while(|c| c.is_whitespace()).count();
+        if start >= end {
+            return Ok(());
+        }
+
+        with_save_state!(self, {
+            let buffer = self.current_buffer_mut();
+            buffer.insert(end, &close);
+            buffer.insert(start, &open);
+            self.cursor = Cursor::from_char_idx(self.current_buffer(), start);
+            self.update_preferred_column();
+        });
+        Ok(())
+    }
+
+    /// 删除包围光标的一对字符 (ds{char})
+    pub fn delete_surround(&mut self, target: char) -> Result<(), String> {
+        let surrounding = self.find_surrounding(target)?;
+        self.replace_surrounding(surrounding, "", "");
+        Ok(())
+    }
+
+    /// 把包围光标的一对字符换成 `replacement` 对应的一对 (cs{old}{new})
+    pub fn change_surround(&mut self, target: char, replacement: char) -> Result<(), String> {
+        let (open, close) = pairs::surround_delimiters(replacement)
+            .ok_or_else(|| tr!(self.locale, "Invalid surround character: {}", replacement))?;
+        let surrounding = self.find_surrounding(target)?;
+        self.replace_surrounding(surrounding, &open, &close);
+        Ok(())
+    }
+
+    fn find_surrounding(&self, target: char) -> Result<pairs::Surrounding, String> {
+        pairs::find_surrounding(self.current_buffer(), &self.cursor, target)
+            .ok_or_else(|| tr!(self.locale, "No surrounding {} found", target))
+    }
+
+    /// 把一对字符替换为 `open` 和 `close`，作为一次撤销单元；光标移到左边的字符上
+    fn replace_surrounding(&mut self, surrounding: pairs::Surrounding, open: &str, close: &str) {
+        let pairs::Surrounding { open: (open_start, open_end), close: (close_start, close_end) } = surrounding;
+        with_save_state!(self, {
+            let buffer = self.current_buffer_mut();
+            buffer.remove(close_start, close_end - close_start);
+            buffer.insert(close_start, close);
+            buffer.remove(open_start, open_end - open_start);
+            buffer.insert(open_start, open);
+            self.cursor = Cursor::from_char_idx(self.current_buffer(), open_start);
+            self.update_preferred_column();
+        });
+    }
+
+    // ==================== 窗口 ====================
+
+    pub fn windows(&self) -> &WindowManager {
+        &self.windows
+    }
+
+    /// 设置窗口区域在屏幕上的大小（Ctrl+W 的方向切换和调整大小按这个区域计算）
+    pub fn set_window_area(&mut self, area: WindowRect) {
+        self.windows.set_area(area);
+    }
+
+    /// 当前窗口顶部显示的缓冲区行
+    pub fn scroll_offset(&self) -> usize {
+        self.windows.current().scroll_offset
+    }
+
+    pub fn set_scroll_offset(&mut self, scroll_offset: usize) {
+        self.windows.current_mut().scroll_offset = scroll_offset;
+    }
+
+    /// 当前窗口文本区域的（宽, 高）
+    pub fn text_size(&self) -> (usize, usize) {
+        self.text_size
+    }
+
+    /// 设置当前窗口文本区域的（宽, 高），不包括行号和状态栏
+    pub fn set_text_size(&mut self, width: usize, height: usize) {
+        self.text_size = (width.max(1), height.max(1));
+    }
+
+    /// 当前窗口显示的范围：顶部的行和最后一个完整显示的行（关闭的折叠算一行），以及文本区域的大小和折行选项
+    pub fn viewport(&self) -> Viewport {
+        let (width, height) = self.text_size;
+        let last = self.current_buffer().last_content_line();
+        let top = self.scroll_offset().min(last);
+        let rows = self.visible_lines(height, width, top);
+        let mut bottom = rows.last().map_or(top, |row| row.line).min(last);
+        // 最后一行折行后没有完全显示时不算
+        if let Some(row) = rows.last().filter(|row| row.fold_end.is_none() && bottom > top) {
+            let shown = rows.iter().filter(|r| r.line == row.line).count();
+            if shown < self.screen_rows(row.line, width) {
+                bottom = self.closed_fold(bottom - 1).map_or(bottom - 1, |(start, _)| start);
+            }
+        }
+        let options = self.current_options();
+        Viewport { top, bottom, height, width, wrap: options.wrap, tabstop: options.tabstop }
+    }
+
+    /// 窗口显示的缓冲区和光标，当前窗口使用编辑器的光标
+    ///
+    /// 其他窗口的光标可能因为缓冲区在别处被修改而越界，这里限制在缓冲区内
+    pub fn window_view(&self, id: WindowId) -> Option<(&Buffer, Cursor)> {
+        if id == self.windows.current_id() {
+            return Some((self.current_buffer(), self.cursor));
+        }
+        let window = self.windows.get(id)?;
+        let buffer = self.buffers.get(&window.buffer)?;
+        Some((buffer, clamp_cursor(buffer, window.cursor)))
+    }
+
+    /// 窗口中可见的屏幕行，见 visible_lines
+    pub fn window_visible_lines(&self, id: WindowId, viewport_height: usize, width: usize) -> Vec<ScreenRow> {
+        let Some((buffer, cursor)) = self.window_view(id) else {
+            return Vec::new();
+        };
+        let scroll_offset = self.windows.get(id).map_or(0, |window| window.scroll_offset);
+        self.buffer_rows(buffer, &self.window_options(id), cursor, viewport_height, width, scroll_offset)
+    }
+
+    /// 窗口的光标在窗口文本区域中的位置，见 cursor_screen_position
+    pub fn window_cursor_position(&self, id: WindowId, width: usize) -> Option<(usize, usize)> {
+        let (buffer, cursor) = self.window_view(id)?;
+        let scroll_offset = self.windows.get(id)?.scroll_offset;
+        self.buffer_cursor_position(buffer, &self.window_options(id), cursor, width, scroll_offset)
+    }
+
+    /// 把编辑器的光标和当前缓冲区存回当前窗口
+    fn save_window_state(&mut self) {
+        let window = self.windows.current_mut();
+        window.buffer = self.current_buffer;
+        window.cursor = self.cursor;
+    }
+
+    /// 切换到窗口 `id`，恢复它的缓冲区和光标
+    pub fn focus_window(&mut self, id: WindowId) {
+        if id == self.windows.current_id() {
+            return;
+        }
+        self.save_window_state();
+        if self.windows.set_current(id) {
+            self.restore_window_state();
+        }
+    }
+
+    /// 切换到新的当前窗口后，恢复它的缓冲区和光标
+    fn restore_window_state(&mut self) {
+        let window = self.windows.current();
+        let (buffer, cursor) = (window.buffer, window.cursor);
+        self.buffer_cursors.insert(self.current_buffer, self.cursor);
+        self.current_buffer = buffer;
+        self.cursor = clamp_cursor(self.current_buffer(), cursor);
+        self.completion = None;
+    }
+
+    /// 分割当前窗口 (:split / :vsplit)，指定文件时在新窗口中打开
+    pub fn split_window(&mut self, direction: SplitDirection, file: Option<&str>) -> Result<(), String> {
+        self.save_window_state();
+        self.windows.split(direction);
+        if let Some(file) = file {
+            if let Err(e) = self.open_file(Path::new(file)) {
+                // 打开失败时不保留新窗口
+                let id = self.windows.current_id();
+                self.close_window(id)?;
+                return Err(tr!(self.locale, "Failed to open {}: {}", file, e));
+            }
+        }
+        Ok(())
+    }
+
+    /// 关闭窗口，最后一个窗口不能关闭
+    pub fn close_window(&mut self, id: WindowId) -> Result<(), String> {
+        if self.windows.len() <= 1 {
+            return Err(tr!(self.locale, "Cannot close last window"));
+        }
+        let current = self.windows.current_id();
+        self.windows.close(id);
+        if id == current {
+            // 关闭的窗口的状态不再保存，直接恢复新的当前窗口
+            self.restore_window_state();
+        }
+        Ok(())
+    }
+
+    /// 关闭当前窗口以外的所有窗口 (:only)
+    pub fn only_window(&mut self) {
+        self.windows.only();
+    }
+
+    /// 执行 Ctrl+W 之后的按键，`count` 为 Ctrl+W 之前输入的计数
+    ///
+    /// - `s` / `v`：上下 / 左右分割当前窗口
+    /// - `w` / `W`：按布局顺序切换到下一个 / 上一个窗口
+    /// - `h` / `j` / `k` / `l`：切换到左 / 下 / 上 / 右侧的窗口
+    /// - `+` / `-`：增加 / 减少窗口高度，`>` / `<`：增加 / 减少窗口宽度，`=`：所有窗口平分空间
+    /// - `c`：关闭当前窗口，`o`：只保留当前窗口
+    /// - `q`：关闭当前窗口，只剩一个窗口时与 :q 相同
+    pub fn window_command(&mut self, key: char, count: usize) -> Result<(), String> {
+        let count = count.max(1);
+        match key {
+            's' | 'S' => self.split_window(SplitDirection::Horizontal, None)?,
+            'v' => self.split_window(SplitDirection::Vertical, None)?,
+            'w' => self.focus_window(self.windows.cycle(1)),
+            'W' => self.focus_window(self.windows.cycle(-1)),
+            'h' | 'j' | 'k' | 'l' => {
+                let side = match key {
+                    'h' => Side::Left,
+                    'j' => Side::Down,
+                    'k' => Side::Up,
+                    _ => Side::Right,
+                };
+                for _ in 0..count {
+                    match self.windows.neighbor(side) {
+                        Some(id) => self.focus_window(id),
+                        None => break,
+                    }
+                }
+            }
+            '+' => {
+                self.windows.resize(SplitDirection::Horizontal, count as isize);
+            }
+            '-' => {
+                self.windows.resize(SplitDirection::Horizontal, -(count as isize));
+            }
+            '>' => {
+                self.windows.resize(SplitDirection::Vertical, count as isize);
+            }
+            '<' => {
+                self.windows.resize(SplitDirection::Vertical, -(count as isize));
+            }
+            '=' => self.windows.equalize(),
+            'c' => self.close_window(self.windows.current_id())?,
+            'o' => self.only_window(),
+            'q' => self.execute_command("quit")?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 当前窗口中可见的行
+    pub fn visible_range(&self) -> LineRange {
+        let id = self.windows.current_id();
+        let height = self
+            .windows
+            .layout_rects(self.windows.area())
+            .into_iter()
+            .find(|(window, _)| *window == id)
+            .map_or(1, |(_, rect)| rect.height.saturating_sub(1).max(1));
+        let last = self.current_buffer().last_content_line();
+        let start = self.scroll_offset().min(last);
+        LineRange::new(start, (start + height - 1).min(last))
+    }
+
+    /// 当前标签页中显示缓冲区的窗口
+    pub fn window_showing(&self, buffer: BufferId) -> Option<WindowId> {
+        if self.current_buffer == buffer {
+            return Some(self.windows.current_id());
+        }
+        self.windows
+            .ids()
+            .into_iter()
+            .find(|id| self.windows.get(*id).is_some_and(|window| window.buffer == buffer))
+    }
+
+    /// 在当前窗口之后分割出新窗口显示缓冲区 `buffer`，没有这个缓冲区时用 `make` 创建新的缓冲区；
+    /// 光标留在原来的窗口，返回新窗口和其中的缓冲区
+    pub fn split_window_showing(
+        &mut self,
+        direction: SplitDirection,
+        buffer: Option<BufferId>,
+        make: impl FnOnce(BufferId) -> Buffer,
+    ) -> (WindowId, BufferId) {
+        let previous = self.windows.current_id();
+        self.save_window_state();
+        self.windows.split_after(direction);
+        match buffer.filter(|id| self.buffers.contains_key(id)) {
+            Some(buffer) => {
+                let _ = self.switch_buffer(buffer);
+            }
+            None => self.open_new_buffer(make),
+        }
+        let window = self.windows.current_id();
+        let buffer = self.current_buffer;
+        self.focus_window(previous);
+        (window, buffer)
+    }
+
+    /// 把各缓冲区的位置列表（如语言服务器的诊断）放入当前标签页中显示它的窗口，标题为 `title`；
+    /// 窗口已有其他标题的位置列表时不覆盖
+    ///
+    /// 保留列表中选中的位置，`list` 返回 None 时删除窗口的这个位置列表
+    pub fn update_location_lists(&mut self, title: &str, list: impl Fn(&Buffer) -> Option<QuickfixList>) {
+        let current = self.windows.current_id();
+        for id in self.windows.ids() {
+            let Some(window) = self.windows.get(id) else {
+                continue;
+            };
+            if window.location_list.as_ref().is_some_and(|list| list.title() != title) {
+                continue;
+            }
+            let buffer = if id == current { self.current_buffer } else { window.buffer };
+            let selected = window.location_list.as_ref().map_or(0, QuickfixList::current_index);
+            let mut list = self.buffers.get(&buffer).and_then(&list);
+            if let Some(list) = list.as_mut() {
+                list.select(selected);
+            }
+            if let Some(window) = self.windows.get_mut(id) {
+                window.location_list = list;
+            }
+        }
+    }
+
+    /// 在缓冲区末尾追加文本（如 AI 对话的回复），光标在最后一行的窗口跟随到末尾
+    pub fn append_and_follow(&mut self, id: BufferId, text: &str) {
+        let Some(buffer) = self.buffers.get_mut(&id) else {
+            return;
+        };
+        let last = buffer.last_content_line();
+        buffer.append_external(text);
+        let end = Cursor::new(buffer.last_content_line(), 0);
+
+        let current = self.windows.current_id();
+        let rects = self.windows.layout_rects(self.windows.area());
+        for window_id in self.windows.ids() {
+            if window_id == current {
+                if self.current_buffer == id && self.cursor.line >= last {
+                    self.cursor = end;
+                }
+                continue;
+            }
+            let Some(window) = self.windows.get(window_id) else {
+                continue;
+            };
+            if window.buffer != id || window.cursor.line < last {
+                continue;
+            }
+            // 从最后一行向上数满窗口的高度，最后一行显示在窗口底部
+            let (width, height) = rects
+                .iter()
+                .find(|(rect_id, _)| *rect_id == window_id)
+                .map_or((80, 1), |(_, rect)| (rect.width.max(1), rect.height.saturating_sub(1).max(1)));
+            let buffer = &self.buffers[&id];
+            let options = self.window_options(window_id);
+            let mut top = end.line;
+            let mut rows = self.buffer_screen_rows(buffer, &options, top, width);
+            while top > 0 {
+                let above = self.buffer_screen_rows(buffer, &options, top - 1, width);
+                if rows + above > height {
+                    break;
+                }
+                rows += above;
+                top -= 1;
+            }
+            if let Some(window) = self.windows.get_mut(window_id) {
+                window.cursor = end;
+                window.scroll_offset = window.scroll_offset.max(top);
+            }
+        }
+    }
+
+    // ==================== 标签页 ====================
+
+    /// 标签页数
+    pub fn tab_count(&self) -> usize {
+        self.tabs.len()
+    }
+
+    /// 当前标签页的位置（从 0 开始）
+    pub fn tab_index(&self) -> usize {
+        self.tabs.current_index()
+    }
+
+    /// 各标签页在标签栏中的显示信息
+    pub fn tab_labels(&self) -> Vec<TabLabel> {
+        let current_tab = self.tab_index();
+        self.tabs
+            .all(&self.windows)
+            .into_iter()
+            .enumerate()
+            .map(|(index, windows)| {
+                // 当前窗口的缓冲区保存在 current_buffer 中
+                let buffer_of = |id: WindowId| {
+                    if index == current_tab && id == windows.current_id() {
+                        self.buffers.get(&self.current_buffer)
+                    } else {
+                        windows.get(id).and_then(|window| self.buffers.get(&window.buffer))
+                    }
+                };
+                TabLabel {
+                    title: buffer_of(windows.current_id())
+                        .and_then(|buffer| buffer.display_name())
+                        .map(str::to_string),
+                    modified: windows.ids().into_iter().filter_map(buffer_of).any(Buffer::is_modified),
+                    windows: windows.len(),
+                }
+            })
+            .collect()
+    }
+
+    /// 在当前标签页之后新建标签页 (:tabnew)，指定文件时打开文件，否则显示新的空缓冲区
+    pub fn new_tab(&mut self, file: Option<&str>) -> Result<(), String> {
+        self.save_window_state();
+        let previous = self.tab_index();
+        let mut windows = WindowManager::new(self.current_buffer);
+        windows.set_area(self.windows.area());
+        self.tabs.insert(&mut self.windows, windows);
+        match file {
+            Some(file) => {
+                if let Err(e) = self.open_file(Path::new(file)) {
+                    // 打开失败时不保留新标签页
+                    self.close_tab()?;
+                    self.goto_tab(previous);
+                    return Err(tr!(self.locale, "Failed to open {}: {}", file, e));
+                }
+            }
+            None => self.create_new_buffer(),
+        }
+        Ok(())
+    }
+
+    /// 切换到第 `index` 个标签页（从 0 开始）
+    pub fn goto_tab(&mut self, index: usize) {
+        self.save_window_state();
+        if self.tabs.switch(&mut self.windows, index) {
+            self.restore_window_state();
+        }
+    }
+
+    /// 切换到后一个标签页，最后一个之后回到第一个 (gt)；指定 `count` 时切换到第 count 个标签页
+    pub fn next_tab(&mut self, count: Option<usize>) {
+        let index = match count {
+            Some(count) => count.saturating_sub(1),
+            None => (self.tab_index() + 1) % self.tab_count(),
+        };
+        self.goto_tab(index);
+    }
+
+    /// 向前切换 `count` 个标签页，第一个之前回到最后一个 (gT)
+    pub fn previous_tab(&mut self, count: usize) {
+        let len = self.tab_count() as isize;
+        let index = (self.tab_index() as isize - count as isize).rem_euclid(len);
+        self.goto_tab(index as usize);
+    }
+
+    /// 关闭当前标签页 (:tabclose)，最后一个标签页不能关闭
+    pub fn close_tab(&mut self) -> Result<(), String> {
+        if !self.tabs.close(&mut self.windows) {
+            return Err(tr!(self.locale, "Cannot close last tab page"));
+        }
+        self.restore_window_state();
+        Ok(())
+    }
+
+    /// 删除缓冲区后，显示它的窗口改为显示当前缓冲区
+    fn retarget_windows(&mut self, removed: BufferId) {
+        let current = self.current_buffer;
+        let other_tabs = self.tabs.others_mut().flat_map(WindowManager::windows_mut);
+        for window in self.windows.windows_mut().chain(other_tabs) {
+            if window.buffer == removed {
+                window.buffer = current;
+                window.cursor = Cursor::at_origin();
+                window.scroll_offset = 0;
+            }
+        }
+    }
+
+    // ==================== 补全菜单 ====================
+
+    /// 获取当前补全菜单
+    pub fn completion(&self) -> Option<&CompletionMenu> {
+        self.completion.as_ref()
+    }
+
+    /// 打开（或按当前前缀刷新）缓冲区单词补全菜单 (Ctrl+N / Ctrl+P)
+    ///
+    /// 候选项从光标所在行向后扫描，到文件末尾后回到开头。
+    /// 没有候选项时关闭菜单并返回 false。
+    pub fn start_word_completion(&mut self) -> bool {
+        if !self.mode.is_insert() {
+            return false;
+        }
+
+        let line = self.cursor.line;
+        let line_text = self.get_line_text(line).unwrap_or_default();
+        let start_column = word_prefix_start(&line_text, self.cursor.column);
+        let prefix: String = line_text
+            .chars()
+            .skip(start_column)
+            .take(self.cursor.column - start_column)
+            .collect();
+
+        let total_lines = self.current_buffer().len_lines();
+        let lines: Vec<(usize, String)> = (line..total_lines)
+            .chain(0..line)
+            .filter_map(|idx| self.get_line_text(idx).map(|text| (idx, text)))
+            .collect();
+        let items = collect_word_candidates(
+            lines.iter().map(|(idx, text)| (*idx, text.as_str())),
+            &prefix,
+        );
+
+        if items.is_empty() {
+            self.completion = None;
+            self.set_message(tr!(self.locale, "Pattern not found"));
+            return false;
+        }
+
+        self.completion = Some(CompletionMenu::new(items, line, start_column));
+        true
+    }
+
+    /// 选择下一个候选项
+    pub fn completion_next(&mut self) {
+        if let Some(menu) = self.completion.as_mut() {
+            menu.select_next();
+        }
+    }
+
+    /// 选择上一个候选项
+    pub fn completion_prev(&mut self) {
+        if let Some(menu) = self.completion.as_mut() {
+            menu.select_prev();
+        }
+    }
+
+    /// 候选列表向下翻页
+    pub fn completion_page_down(&mut self) {
+        if let Some(menu) = self.completion.as_mut() {
+            menu.page_down();
+        }
+    }
+
+    /// 候选列表向上翻页
+    pub fn completion_page_up(&mut self) {
+        if let Some(menu) = self.completion.as_mut() {
+            menu.page_up();
+        }
+    }
+
+    /// 输入字符后按新的前缀刷新补全菜单：插件的补全重新过滤插件的候选项，否则重新收集单词
+    pub fn refresh_completion(&mut self) -> bool {
+        if self.plugin_completion.is_some() {
+            self.filter_plugin_completion();
+            return self.completion.is_some();
+        }
+        self.start_word_completion()
+    }
+
+    /// 向插件请求补全 (Ctrl+Space)，没有插件提供补全时使用单词补全
+    pub fn request_completion(&mut self) -> bool {
+        if !self.mode.is_insert() {
+            return false;
+        }
+        self.cancel_completion();
+        self.start_plugin_completion(None) || self.start_word_completion()
+    }
+
+    /// Insert 模式中输入字符 `c` 之后调用：插件可以把 `c` 作为触发字符开始补全（如语言服务器的 `.`），
+    /// 输入其他非单词字符时放弃还在等待的补全
+    pub fn completion_trigger(&mut self, c: char) -> bool {
+        if !self.mode.is_insert() {
+            return false;
+        }
+        if self.start_plugin_completion(Some(c)) {
+            return true;
+        }
+        if !(c.is_alphanumeric() || c == '_') && self.plugin_completion.is_some() {
+            self.cancel_completion();
+        }
+        false
+    }
+
+    /// 依次询问插件是否提供补全，有插件接受时关闭当前的菜单，等待它的候选项
+    fn start_plugin_completion(&mut self, trigger: Option<char>) -> bool {
+        let previous = self.plugin_completion.replace(PluginCompletion {
+            buffer: self.current_buffer,
+            line: self.cursor.line,
+            items: None,
+            manual: trigger.is_none(),
+        });
+        let started = self.call_plugins(|plugin, editor| plugin.complete(editor, trigger).then_some(()));
+        if started.is_none() {
+            self.plugin_completion = previous;
+            return false;
+        }
+        self.completion = None;
+        true
+    }
+
+    /// 插件提供补全的候选项（见 `Plugin::complete`），按光标前的单词过滤后打开补全菜单
+    ///
+    /// 补全已被取消时忽略
+    pub fn provide_completion(&mut self, items: Vec<aivim_lsp::CompletionItem>) {
+        if let Some(state) = self.plugin_completion.as_mut() {
+            state.items = Some(items);
+            self.filter_plugin_completion();
+        }
+    }
+
+    /// 按光标前的单词过滤插件的候选项，打开或更新补全菜单
+    ///
+    /// 已经离开 Insert 模式或换了缓冲区、行时放弃这次补全
+    fn filter_plugin_completion(&mut self) {
+        let Some(state) = &self.plugin_completion else {
+            return;
+        };
+        if !self.mode.is_insert() || state.buffer != self.current_buffer || state.line != self.cursor.line {
+            self.cancel_completion();
+            return;
+        }
+        let Some(all_items) = &state.items else {
+            return;
+        };
+        let line = self.cursor.line;
+        let line_text = self.get_line_text(line).unwrap_or_default();
+        let start_column = word_prefix_start(&line_text, self.cursor.column);
+        let prefix: String = line_text
+            .chars()
+            .skip(start_column)
+            .take(self.cursor.column - start_column)
+            .collect();
+        let items = filter_lsp_items(all_items, &prefix);
+        if items.is_empty() {
+            if state.manual {
+                self.set_message(tr!(self.locale, "Pattern not found"));
+            }
+            self.cancel_completion();
+            return;
+        }
+        self.completion = Some(CompletionMenu::new(items, line, start_column));
+    }
+
+    /// 接受插件（语言服务器）的候选项：替换候选项的范围（没有时为光标前的单词）并进行附加的修改，
+    /// 光标放在插入的文本中代码片段的结束位置
+    fn accept_lsp_completion(&mut self, menu: &CompletionMenu, item: &aivim_lsp::CompletionItem) {
+        let line = menu.line();
+        let line_text = self.get_line_text(line).unwrap_or_default();
+        let start_column = match item.range {
+            Some((start, _)) if start.line == line => {
+                aivim_lsp::diagnostic::utf16_to_char(&line_text, start.character).min(self.cursor.column)
+            }
+            _ => menu.start_column(),
+        };
+        let buffer = self.current_buffer();
+        let line_start = buffer.line_to_char(line);
+        let (start, end) = (line_start + start_column, line_start + self.cursor.column);
+        // (起始, 结束, 文本)，按起始位置从后向前修改，前面的位置不受影响
+        let mut edits = vec![(start, end, item.insert_text.clone())];
+        let mut cursor = start + item.cursor;
+        for edit in &item.additional_edits {
+            let to_char = |position: aivim_lsp::Position| {
+                let line = position.line.min(buffer.len_lines().saturating_sub(1));
+                let text = buffer.line_text(line).unwrap_or_default();
+                buffer.line_to_char(line) + aivim_lsp::diagnostic::utf16_to_char(&text, position.character)
+            };
+            let (edit_start, edit_end) = (to_char(edit.start), to_char(edit.end).max(to_char(edit.start)));
+            // 与主要修改重叠的修改无效，忽略
+            if edit_end > start && edit_start < end {
+                continue;
+            }
+            if edit_end <= start {
+                cursor = cursor + edit.new_text.chars().count() - (edit_end - edit_start);
+            }
+            edits.push((edit_start, edit_end, edit.new_text.clone()));
+        }
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.0));
+        let buffer = self.current_buffer_mut();
+        for (start, end, text) in edits {
+            buffer.remove(start, end - start);
+            buffer.insert(start, &text);
+        }
+        let line = self.current_buffer().char_to_line(cursor);
+        let column = cursor - self.current_buffer().line_to_char(line);
+        self.cursor = Cursor::new(line, column);
+        self.update_preferred_column();
+    }
+
+    /// 接受当前候选项 (Ctrl+Y)，用候选文本替换光标前的前缀
+    pub fn accept_completion(&mut self) -> bool {
+        self.plugin_completion = None;
+        let Some(menu) = self.completion.take() else {
+            return false;
+        };
+        let Some(item) = menu.selected_item() else {
+            return false;
+        };
+        if menu.line() != self.cursor.line || self.cursor.column < menu.start_column() {
+            return false;
+        }
+        if let Some(lsp_item) = &item.lsp_item {
+            self.accept_lsp_completion(&menu, lsp_item);
+            return true;
+        }
+
+        let label = item.label.clone();
+        let start = self.current_buffer().line_to_char(menu.line()) + menu.start_column();
+        let prefix_len = self.cursor.column - menu.start_column();
+
+        let buffer = self.current_buffer_mut();
+        buffer.remove(start, prefix_len);
+        buffer.insert(start, &label);
+
+        self.cursor.column = menu.start_column() + label.chars().count();
+        self.update_preferred_column();
+        true
+    }
+
+    /// 取消补全 (Ctrl+E)，不再等待插件的候选项
+    pub fn cancel_completion(&mut self) {
+        self.completion = None;
+        if self.plugin_completion.take().is_some() {
+            self.call_plugins(|plugin, editor| {
+                plugin.cancel_completion(editor);
+                None::<()>
+            });
+        }
+    }
+
+    /// 当前候选项的预览内容：优先显示文档，否则显示来源行
+    pub fn completion_preview(&self) -> Option<String> {
+        let item = self.completion.as_ref()?.selected_item()?;
+        if let Some(doc) = &item.documentation {
+            return Some(doc.clone());
+        }
+        let line = item.source_line?;
+        let text = self.get_line_text(line)?;
+        Some(format!("{}: {}", line + 1, text.trim()))
+    }
+
+    // ==================== 行尾符 ====================
+
+    /// 统计当前缓冲区写入文件时的行尾符
+    ///
+    /// dos 格式的缓冲区中的 `\n` 写入时为 CRLF
+    pub fn line_ending_stats(&self) -> LineEndingStats {
+        let buffer = self.current_buffer();
+        let stats = LineEndingStats::scan(buffer.rope());
+        match buffer.line_ending() {
+            LineEnding::Lf => stats,
+            LineEnding::Crlf => LineEndingStats { lf: 0, crlf: stats.lf + stats.crlf },
+        }
+    }
+
+    /// 当前缓冲区的文件是否以 BOM 开头
+    pub fn has_bom(&self) -> bool {
+        self.current_buffer().has_bom()
+    }
+
+    /// 将当前缓冲区的行尾符统一为 `target`，返回修改的行数
+    pub fn fix_line_endings(&mut self, target: LineEnding) -> usize {
+        let lines = self.line_ending_stats().lines_to_convert(target);
+        if lines == 0 {
+            return 0;
+        }
+
+        with_save_state!(self, {
+            // dos 格式的缓冲区中只保存 `\n`，去掉多余的 `\r` 之后改为转换文件格式
+            let format = self.current_buffer().line_ending();
+            let strip = format == LineEnding::Crlf || target == LineEnding::Lf;
+            let buffer = self.current_buffer_mut();
+            // 从最后一行往前逐行修改行尾，前面各行的位置不变
+            for line in (1..buffer.len_lines()).rev() {
+                let newline = buffer.line_to_char(line) - 1;
+                if buffer.char(newline) != '\n' {
+                    continue;
+                }
+                let has_cr = newline > 0 && buffer.char(newline - 1) == '\r';
+                if strip && has_cr {
+                    buffer.remove(newline - 1, 1);
+                } else if !strip && !has_cr {
+                    buffer.insert_char(newline, '\r');
+                }
+            }
+            if format == LineEnding::Crlf {
+                buffer.set_line_ending(target);
+            }
+            let buffer = self.buffers.get(&self.current_buffer).unwrap();
+            self.cursor.ensure_valid(buffer);
+        });
+        self.set_message(tr!(self.locale, "Converted {} line(s) to {}", lines, target.name()));
+        lines
+    }
+
+    // ==================== 按键映射 ====================
+
+    pub fn mappings(&self) -> &KeyMappings {
+        &self.mappings
+    }
+
+    pub fn mappings_mut(&mut self) -> &mut KeyMappings {
+        &mut self.mappings
+    }
+
+    /// 执行 :map / :noremap / :unmap / :mapclear 及其模式变体
+    ///
+    /// 参数格式：`[<buffer>] {lhs} {rhs}`，`<buffer>` 表示只在当前缓冲区生效
+    fn map_command(&mut self, name: &str, args: &str) -> Result<(), String> {
+        let (kind, modes) = mapping::parse_map_command(name)
+            .ok_or_else(|| tr!(self.locale, "Not an editor command: {}", name))?;
+
+        let mut scope = MapScope::Global;
+        let mut args = args;
+        loop {
+            if let Some(rest) = strip_map_modifier(args, "<buffer>") {
+                scope = MapScope::Buffer(self.current_buffer);
+                args = rest;
+            } else if let Some(rest) = strip_map_modifier(args, "<silent>") {
+                // 命令行不会回显映射，<silent> 直接忽略
+                args = rest;
+            } else {
+                break;
+            }
+        }
+
+        let (lhs, rhs) = match args.split_once(char::is_whitespace) {
+            Some((lhs, rhs)) => (lhs, rhs.trim_start()),
+            None => (args, ""),
+        };
+
+        match kind {
+            MapCommandKind::Clear => {
+                for mode in modes {
+                    self.mappings.clear(scope, mode);
+                }
+            }
+            MapCommandKind::Unmap => {
+                if lhs.is_empty() {
+                    return Err(tr!(self.locale, "Argument required"));
+                }
+                let keys = mapping::parse_keys_with_leader(lhs, &self.map_leader);
+                let mut found = false;
+                for mode in modes {
+                    found |= self.mappings.unmap(scope, mode, &keys);
+                }
+                if !found {
+                    return Err(tr!(self.locale, "No such mapping: {}", lhs));
+                }
+            }
+            MapCommandKind::Map | MapCommandKind::Noremap if rhs.is_empty() => {
+                // 没有右侧时列出映射
+                self.list_mappings(&modes, scope, mapping::parse_keys_with_leader(lhs, &self.map_leader));
+            }
+            MapCommandKind::Map | MapCommandKind::Noremap => {
+                let noremap = kind == MapCommandKind::Noremap;
+                let lhs = mapping::parse_keys_with_leader(lhs, &self.map_leader);
+                let rhs = mapping::parse_keys_with_leader(rhs, &self.map_leader);
+                for mode in modes {
+                    self.mappings.map(scope, mode, lhs.clone(), rhs.clone(), noremap);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 映射中 `<Leader>` 代表的按键
+    pub fn map_leader(&self) -> &[MapKey] {
+        &self.map_leader
+    }
+
+    /// 执行 :let，目前只支持设置 leader 键：`:let mapleader = ","`
+    ///
+    /// 值可以用引号包含，并使用按键记法（`"<Space>"`、`"\<Space>"`）
+    fn let_command(&mut self, args: &str) -> Result<(), String> {
+        let (name, value) = args
+            .split_once('=')
+            .ok_or_else(|| tr!(self.locale, "Argument required"))?;
+        let name = name.trim();
+        if name != "mapleader" {
+            return Err(tr!(self.locale, "Unknown variable: {}", name));
+        }
+        let value = value.trim();
+        let unquoted = ['"', '\'']
+            .into_iter()
+            .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+            .filter(|_| value.len() >= 2);
+        let notation = match unquoted {
+            Some(inner) if value.starts_with('"') => inner.replace("\\<", "<"),
+            Some(inner) => inner.to_string(),
+            None => value.to_string(),
+        };
+        let leader = mapping::parse_keys(&notation);
+        if leader.is_empty() {
+            return Err(tr!(self.locale, "Invalid value for {}: {}", name, value));
+        }
+        self.map_leader = leader;
+        Ok(())
+    }
+
+    /// 当前的配色方案
+    pub fn colorscheme(&self) -> &'static str {
+        self.colorscheme
+    }
+
+    /// :highlight 对高亮组的修改
+    pub fn highlights(&self) -> &[(&'static str, Highlight)] {
+        &self.highlights
+    }
+
+    /// 执行 :highlight {group} fg={color} bg={color} attr={attrs}，`:highlight clear` 清除所有修改
+    fn highlight_command(&mut self, args: &str) -> Result<(), String> {
+        let args = self.arg_list(args)?;
+        if args.len() == 1 && args[0] == "clear" {
+            self.highlights.clear();
+            return Ok(());
+        }
+        let (group, highlight) = colorscheme::parse_highlight(&args).map_err(|e| match e {
+            HighlightError::UnknownGroup(group) => tr!(self.locale, "Unknown highlight group: {}", group),
+            HighlightError::InvalidArgument(arg) if arg.is_empty() => tr!(self.locale, "Argument required"),
+            HighlightError::InvalidArgument(arg) => tr!(self.locale, "Invalid argument: {}", arg),
+        })?;
+        // 同一个组的多次修改合并，后指定的部分覆盖之前的
+        match self.highlights.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, existing)) => {
+                existing.fg = highlight.fg.or(existing.fg);
+                existing.bg = highlight.bg.or(existing.bg);
+                existing.attrs = highlight.attrs.or(existing.attrs);
+            }
+            None => self.highlights.push((group, highlight)),
+        }
+        Ok(())
+    }
+
+    /// 逐行执行文件中的 Ex 命令 (:source {file})
+    ///
+    /// 出错的命令不影响后面的命令，全部执行后报告第一个错误
+    /// 执行 `:call {function}({args})`，目前只支持 `cursor({lnum}, {col})`：
+    /// 把光标移到第 lnum 行第 col 个字符（都从 1 开始，超出范围时移到最后一行 / 行尾）
+    fn call_function(&mut self, call: &str) -> Result<(), String> {
+        let invalid = || tr!(self.locale, "Invalid argument: {}", call);
+        let (name, args) = call.trim().strip_suffix(')').and_then(|call| call.split_once('(')).ok_or_else(invalid)?;
+        if name.trim() != "cursor" {
+            return Err(tr!(self.locale, "Unknown function: {}", name.trim()));
+        }
+        let args: Vec<usize> = args
+            .split(',')
+            .map(|arg| arg.trim().parse::<usize>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        let [line, column] = args[..] else {
+            return Err(invalid());
+        };
+        let buffer = &self.buffers[&self.current_buffer];
+        self.cursor.line = line.saturating_sub(1).min(buffer.last_content_line());
+        let text = buffer.line_text(self.cursor.line).unwrap_or_default();
+        self.cursor.column = column.saturating_sub(1).min(display::last_grapheme(&text));
+        self.cursor.ensure_valid(buffer);
+        self.update_preferred_column();
+        Ok(())
+    }
+
+    /// 打开文件 `path` 的缓冲区
+    fn find_file_buffer(&self, path: &Path) -> Option<BufferId> {
+        let path = watch::absolute(path);
+        let mut ids: Vec<BufferId> = self
+            .buffers
+            .values()
+            .filter(|buffer| buffer.file_path().is_some_and(|p| watch::absolute(p) == path))
+            .map(Buffer::id)
+            .collect();
+        ids.sort_by_key(BufferId::as_usize);
+        ids.first().copied()
+    }
+
+    /// 恢复当前会话的命令 (:mksession)，见 session 模块
+    pub fn session_script(&self) -> io::Result<String> {
+        let cwd = std::env::current_dir()?;
+        let mut lines = vec![
+            "\" aivim session: :source this file or run aivim -S to restore".to_string(),
+            format!("cd {}", session::quote(&cwd.to_string_lossy())),
+        ];
+        lines.extend(session::option_commands(&self.options));
+
+        let mut buffers: Vec<&Buffer> = self.buffers.values().collect();
+        buffers.sort_by_key(|b| b.id().as_usize());
+        for path in buffers.iter().filter_map(|b| b.file_path()) {
+            let edit = format!("edit {}", session::session_path(path, &cwd));
+            if !lines.contains(&edit) {
+                lines.push(edit);
+            }
+        }
+
+        let current_tab = self.tab_index();
+        // 折叠属于缓冲区，在第一个显示缓冲区的窗口中恢复
+        let mut folded = std::collections::HashSet::new();
+        for (index, windows) in self.tabs.all(&self.windows).into_iter().enumerate() {
+            if index > 0 {
+                lines.push("tabnew".to_string());
+            }
+            let mut window_commands = |id: WindowId| {
+                let (buffer, cursor) = if index == current_tab && id == windows.current_id() {
+                    (self.current_buffer, self.cursor)
+                } else {
+                    let window = windows.get(id).expect("window in layout");
+                    (window.buffer, window.cursor)
+                };
+                let mut commands = Vec::new();
+                match self.buffers.get(&buffer).and_then(Buffer::file_path) {
+                    Some(path) => commands.push(format!("buffer {}", session::session_path(path, &cwd))),
+                    None => commands.push("new".to_string()),
+                }
+                if let Some(folds) = self.buffers.get(&buffer).filter(|b| b.file_path().is_some()).map(Buffer::folds) {
+                    if folded.insert(buffer) {
+                        commands.extend(session::fold_commands(folds.folds(), self.options.foldmethod));
+                    }
+                }
+                commands.push(format!("call cursor({}, {})", cursor.line + 1, cursor.column + 1));
+                commands
+            };
+            lines.extend(session::layout_commands(windows.layout(), &mut window_commands));
+        }
+
+        // 回到当前标签页的当前窗口：重建后每个标签页的当前窗口是最后一个窗口
+        if self.tab_count() > 1 {
+            lines.push(format!("tabnext {}", current_tab + 1));
+        }
+        let ids = self.windows.ids();
+        let position = ids.iter().position(|id| *id == self.windows.current_id()).unwrap_or(0);
+        let moves = (position + 1) % ids.len();
+        lines.extend(std::iter::repeat_n("wincmd w".to_string(), moves));
+
+        let mut script = lines.join("\n");
+        script.push('\n');
+        Ok(script)
+    }
+
+    /// 不需要终端，依次执行 Ex 命令（`:` 开头）和 Normal 模式的按键序列，返回每一步的结果（见 script 模块）
+    pub fn run_script(&mut self, script: &[&str]) -> ScriptResult {
+        let mut input = KeyInput::new();
+        let mut result = ScriptResult::default();
+        for step in script {
+            self.clear_message();
+            let mut error = match ScriptInput::parse(step) {
+                ScriptInput::Command(command) => {
+                    let error = self.execute_command(&command).err();
+                    if let Some(error) = &error {
+                        self.set_message(error.clone());
+                    }
+                    if let Some(request) = self.take_normal_request() {
+                        input.execute_normal(self, request);
+                    }
+                    error
+                }
+                ScriptInput::Keys(keys) => {
+                    input.feed_keys(self, &keys, true);
+                    None
+                }
+            };
+            // 等待这一步启动的外部命令（:%!sort、!!、:r !cmd）把输出写入缓冲区
+            if let Err(failure) = self.wait_for_shell_commands(shell::SHELL_WAIT_TIMEOUT) {
+                error.get_or_insert(failure);
+            }
+            result.steps.push(ScriptStep {
+                input: step.to_string(),
+                error,
+                message: self.message.clone(),
+                mode: self.mode,
+                cursor: self.cursor,
+            });
+            if self.quit_requested {
+                result.quit = true;
+                break;
+            }
+        }
+        result
+    }
+
+    pub fn source_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| tr!(self.locale, "Failed to open {}: {}", path.display(), e))?;
+        let mut first_error = None;
+        for (line, command) in crate::config::config_commands(&text) {
+            if let Err(error) = self.execute_command(command) {
+                first_error.get_or_insert((line, error));
+            }
+        }
+        match first_error {
+            Some((line, error)) => Err(tr!(self.locale, "Error in {} line {}: {}", path.display(), line, error)),
+            None => Ok(()),
+        }
+    }
+
+    /// 启动时执行默认的配置文件，配置文件不存在时什么也不做
+    ///
+    /// 配置命令产生的消息（如 `:set` 的回显）不显示，只显示错误
+    pub fn load_config(&mut self) {
+        let ai_error = crate::config::default_ai_config_path()
+            .filter(|path| path.is_file())
+            .and_then(|path| {
+                self.load_ai_settings(&path)
+                    .and_then(|_| self.load_lsp_settings(&path))
+                    .and_then(|_| self.load_statusline_settings(&path))
+                    .and_then(|_| self.load_protected_paths(&path))
+                    .err()
+            });
+        let Some(path) = crate::config::default_config_path().filter(|path| path.is_file()) else {
+            if let Some(error) = ai_error {
+                self.set_message(error);
+            }
+            return;
+        };
+        match self.source_file(&path) {
+            Ok(()) => match ai_error {
+                Some(error) => self.set_message(error),
+                None => self.clear_message(),
+            },
+            Err(error) => self.set_message(error),
+        }
+    }
+
+    /// 读取 config.toml 中的 AI 服务设置，使用其中选择的服务
+    pub fn load_ai_settings(&mut self, path: &Path) -> Result<(), String> {
+        let settings =
+            AiSettings::load(path).map_err(|e| tr!(self.locale, "Error in {}: {}", path.display(), e))?;
+        self.ai_config = settings.active();
+        self.ai_settings = settings;
+        Ok(())
+    }
+
+    /// 读取 config.toml 中的语言服务器设置，正在运行的服务器被关闭，之后按新的设置启动
+    pub fn load_lsp_settings(&mut self, path: &Path) -> Result<(), String> {
+        let settings =
+            LspSettings::load(path).map_err(|e| tr!(self.locale, "Error in {}: {}", path.display(), e))?;
+        self.lsp_settings = settings;
+        self.notify_option_set();
+        Ok(())
+    }
+
+    /// 读取 config.toml 中的状态栏设置
+    pub fn load_statusline_settings(&mut self, path: &Path) -> Result<(), String> {
+        self.statusline =
+            StatuslineSettings::load(path).map_err(|e| tr!(self.locale, "Error in {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// 读取 config.toml 中的受保护路径模式，没有设置时保持不变
+    pub fn load_protected_paths(&mut self, path: &Path) -> Result<(), String> {
+        let patterns = crate::file_ops::load_protected_paths(path)
+            .map_err(|e| tr!(self.locale, "Error in {}: {}", path.display(), e))?;
+        if let Some(patterns) = patterns {
+            self.protected_paths = patterns;
+        }
+        Ok(())
+    }
+
+    /// 状态栏的片段
+    pub fn statusline(&self) -> &StatuslineSettings {
+        &self.statusline
+    }
+
+    /// 在消息栏列出以 `prefix` 开头的映射
+    fn list_mappings(&mut self, modes: &[MapMode], scope: MapScope, prefix: Vec<mapping::MapKey>) {
+        let mut entries = Vec::new();
+        for mode in modes {
+            for (mapping_scope, mapping) in self.mappings.list(*mode, self.current_buffer) {
+                let local_only = scope != MapScope::Global;
+                if !mapping.lhs.starts_with(&prefix) || (local_only && mapping_scope == MapScope::Global) {
+                    continue;
+                }
+                let flags = match (mapping.noremap, mapping_scope) {
+                    (true, MapScope::Buffer(_)) => "*@",
+                    (true, MapScope::Global) => "*",
+                    (false, MapScope::Buffer(_)) => "@",
+                    (false, MapScope::Global) => "",
+                };
+                entries.push(format!(
+                    "{} {} {}{}",
+                    mode.letter(),
+                    mapping::format_keys(&mapping.lhs),
+                    flags,
+                    mapping::format_keys(&mapping.rhs)
+                ));
+            }
+        }
+        if entries.is_empty() {
+            self.set_message(tr!(self.locale, "No mapping found"));
+        } else {
+            self.set_message(entries.join(" | "));
+        }
+    }
+
+    // ==================== 自动命令 ====================
+
+    pub fn autocmds(&self) -> &Autocmds {
+        &self.autocmds
+    }
+
+    /// :autocmd 注册、列出或删除（带 !）自动命令，见 autocmd 模块
+    fn autocmd_command(&mut self, bang: bool, args: &str) -> Result<(), String> {
+        let AutocmdArgs { events, pattern, command } =
+            autocmd::parse_args(args).map_err(|name| tr!(self.locale, "No such event: {}", name))?;
+        let events: Vec<Option<Event>> = match events {
+            Some(events) => events.into_iter().map(Some).collect(),
+            None => vec![None],
+        };
+        if let (Some(pattern), Some(command)) = (pattern, command) {
+            // :autocmd! {event} {pattern} {command} 替换之前注册的命令
+            for event in events.into_iter().flatten() {
+                if bang {
+                    self.autocmds.remove(Some(event), Some(pattern));
+                }
+                self.autocmds.add(event, pattern, command);
+            }
+            return Ok(());
+        }
+        if bang {
+            for event in events {
+                self.autocmds.remove(event, pattern);
+            }
+            return Ok(());
+        }
+        let entries: Vec<String> = events
+            .into_iter()
+            .flat_map(|event| self.autocmds.list(event, pattern))
+            .map(|autocmd| format!("{} {} {}", autocmd.event.name(), autocmd.pattern, autocmd.command))
+            .collect();
+        if entries.is_empty() {
+            self.set_message(tr!(self.locale, "No autocommands found"));
+        } else {
+            self.set_message(entries.join(" | "));
+        }
+        Ok(())
+    }
+
+    /// 触发事件：先按注册顺序通知插件，再执行匹配的自动命令
+    ///
+    /// `buffer` 不是当前缓冲区时（如退出前保存其他缓冲区）临时切换到它执行自动命令，命令的错误显示在消息栏
+    pub fn emit_event(&mut self, event: Event, buffer: BufferId) {
+        for index in 0..self.plugins.len() {
+            // 正在执行的插件不再收到它自己触发的事件
+            if let Some(mut plugin) = self.plugins.take(index) {
+                plugin.on_event(self, event, buffer);
+                self.plugins.restore(index, plugin);
+            }
+        }
+
+        let Some(path) = self.buffers.get(&buffer).map(|buffer| buffer.file_path().map(Path::to_path_buf)) else {
+            return;
+        };
+        let commands = self.autocmds.commands_for(event, path.as_deref());
+        if self.autocmd_running || commands.is_empty() {
+            return;
+        }
+        // 其他缓冲区的事件：执行自动命令期间临时切换到这个缓冲区
+        let previous = self.current_buffer;
+        if buffer != previous {
+            self.buffer_cursors.insert(previous, self.cursor);
+            self.current_buffer = buffer;
+            self.cursor = self.buffer_cursors.get(&buffer).copied().unwrap_or_else(Cursor::at_origin);
+        }
+        self.autocmd_running = true;
+        for command in commands {
+            if let Err(e) = self.execute_command(&command) {
+                self.set_message(tr!(self.locale, "Error in {} autocommand: {}", event.name(), e));
+            }
+        }
+        self.autocmd_running = false;
+        if buffer != previous && self.buffers.contains_key(&previous) {
+            self.buffer_cursors.insert(buffer, self.cursor);
+            self.current_buffer = previous;
+            self.cursor = self.buffer_cursors.get(&previous).copied().unwrap_or_else(Cursor::at_origin);
+        }
+    }
+
+    /// 终端重新获得焦点（或从 shell 回到编辑器）时调用：检查文件是否在外部被修改，并触发 FocusGained
+    pub fn focus_gained(&mut self) {
+        self.check_file_changed();
+        self.emit_event(Event::FocusGained, self.current_buffer);
+    }
+
+    /// Normal 模式下光标停留 updatetime 毫秒后触发 CursorHold，返回是否触发了（需要重绘）
+    ///
+    /// 由事件循环定期调用
+    pub fn poll_cursor_hold(&mut self, now: Instant) -> bool {
+        if self.mode != Mode::Normal {
+            self.cursor_hold.reset();
+            return false;
+        }
+        let position = (self.current_buffer.as_usize(), self.cursor.line, self.cursor.column);
+        let delay = Duration::from_millis(self.options.updatetime as u64);
+        if !self.cursor_hold.update(position, now, delay) {
+            return false;
+        }
+        self.emit_event(Event::CursorHold, self.current_buffer);
+        true
+    }
+
+    /// 删除范围内各行行尾的空格和 Tab (:StripWhitespace)，整个操作作为一次撤销单元
+    fn strip_trailing_whitespace(&mut self, range: LineRange) {
+        let buffer = self.current_buffer();
+        let blanks: Vec<(usize, usize)> = (range.start..=range.end)
+            .rev()
+            .filter_map(|line| {
+                let text = buffer.line_text(line)?;
+                let len = text.chars().count();
+                let kept = text.trim_end_matches([' ', '\t']).chars().count();
+                (kept < len).then(|| (buffer.line_to_char(line) + kept, len - kept))
+            })
+            .collect();
+        if blanks.is_empty() {
+            return;
+        }
+        with_save_state!(self, {
+            for (start, len) in blanks {
+                self.current_buffer_mut().remove(start, len);
+            }
+            self.cursor = clamp_cursor(self.current_buffer(), self.cursor);
+        });
+    }
+
+    // ==================== 插件 ====================
+
+    pub fn plugins(&self) -> &Plugins {
+        &self.plugins
+    }
+
+    /// 注册插件：检查并注册它的命令和按键映射，再调用它的 setup，见 plugin 模块
+    pub fn register_plugin(&mut self, mut plugin: Box<dyn Plugin>) -> Result<(), String> {
+        let commands = plugin.commands();
+        for (i, spec) in commands.iter().enumerate() {
+            if !spec.name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                return Err(tr!(self.locale, "Plugin commands must start with an uppercase letter: {}", spec.name));
+            }
+            let duplicate = commands[..i].iter().any(|other| other.name == spec.name);
+            if duplicate || ex_command::is_builtin(spec.name) || self.plugins.command_owner(spec.name).is_some() {
+                return Err(tr!(self.locale, "Command already exists: {}", spec.name));
+            }
+        }
+        plugin.setup(self)?;
+        for keymap in plugin.keymaps() {
+            let lhs = mapping::parse_keys_with_leader(keymap.lhs, &self.map_leader);
+            let rhs = mapping::parse_keys_with_leader(keymap.rhs, &self.map_leader);
+            self.mappings.map(MapScope::Global, keymap.mode, lhs, rhs, true);
+        }
+        self.plugins.add(plugin, commands);
+        Ok(())
+    }
+
+    /// 执行插件提供的命令
+    fn execute_plugin_command(&mut self, command: &ExCommand) -> Result<(), String> {
+        let Some(index) = self.plugins.command_owner(command.name) else {
+            return Err(tr!(self.locale, "Not an editor command: {}", command.name));
+        };
+        let mut plugin = self
+            .plugins
+            .take(index)
+            .ok_or_else(|| tr!(self.locale, "Plugin is already running: {}", command.name))?;
+        let result = plugin.execute(self, command);
+        self.plugins.restore(index, plugin);
+        result
+    }
+
+    /// 类型为 `T` 的插件（如 `GitPlugin`），没有注册或正在执行时为 None
+    pub fn plugin<T: Plugin>(&self) -> Option<&T> {
+        self.plugins.get::<T>()
+    }
+
+    /// 取出类型为 `T` 的插件调用 `f`，没有注册或正在执行时为 None
+    pub fn with_plugin<T: Plugin, R>(&mut self, f: impl FnOnce(&mut T, &mut Editor) -> R) -> Option<R> {
+        let index = self.plugins.position(TypeId::of::<T>())?;
+        let mut plugin = self.plugins.take(index)?;
+        let result = (plugin.as_mut() as &mut dyn Any).downcast_mut::<T>().map(|plugin| f(plugin, self));
+        self.plugins.restore(index, plugin);
+        result
+    }
+
+    /// 按注册顺序调用插件，直到 `f` 返回 Some；正在执行的插件被跳过
+    fn call_plugins<R>(&mut self, mut f: impl FnMut(&mut dyn Plugin, &mut Editor) -> Option<R>) -> Option<R> {
+        for index in 0..self.plugins.len() {
+            let Some(mut plugin) = self.plugins.take(index) else {
+                continue;
+            };
+            let result = f(plugin.as_mut(), self);
+            self.plugins.restore(index, plugin);
+            if result.is_some() {
+                return result;
+            }
+        }
+        None
+    }
+
+    /// 取出插件后台任务的结果（见 `Plugin::poll`），返回界面是否需要重绘
+    ///
+    /// 由事件循环定期调用
+    pub fn poll_plugins(&mut self, now: Instant) -> bool {
+        let mut changed = false;
+        self.call_plugins(|plugin, editor| {
+            changed |= plugin.poll(editor, now);
+            None::<()>
+        });
+        changed
+    }
+
+    /// 插件先处理 Normal / Insert 模式的按键，返回按键是否已被处理
+    pub fn plugin_key(&mut self, key: MapKey) -> bool {
+        self.call_plugins(|plugin, editor| plugin.on_key(editor, key).then_some(())).is_some()
+    }
+
+    /// 由插件处理编辑器不认识的 `]{key}` / `[{key}`
+    pub fn plugin_bracket(&mut self, key: char, forward: bool, count: usize) -> Result<bool, String> {
+        self.call_plugins(|plugin, editor| match plugin.bracket(editor, key, forward, count) {
+            Ok(false) => None,
+            result => Some(result),
+        })
+        .unwrap_or(Ok(false))
+    }
+
+    /// `:set` 修改选项之后通知插件
+    fn notify_option_set(&mut self) {
+        self.call_plugins(|plugin, editor| {
+            plugin.on_option_set(editor);
+            None::<()>
+        });
+    }
+
+    /// 显示插件 `P` 的 y/n 提示，回答交给它的 `on_confirm`
+    pub fn request_confirmation<P: Plugin>(&mut self, prompt: String) {
+        self.set_message(prompt.clone());
+        self.pending_confirmation = Some(PendingConfirmation::Plugin { plugin: TypeId::of::<P>(), prompt });
+    }
+
+    /// 把 y/n 提示的回答交给请求它的插件
+    fn answer_plugin_confirmation(&mut self, plugin: TypeId, answer: ConfirmAnswer) -> Result<(), String> {
+        let Some(index) = self.plugins.position(plugin) else {
+            return Ok(());
+        };
+        let Some(mut plugin) = self.plugins.take(index) else {
+            return Ok(());
+        };
+        let result = plugin.on_confirm(self, answer);
+        self.plugins.restore(index, plugin);
+        result
+    }
+
+    // ==================== 粘贴检测 ====================
+
+    /// 记录 Insert 模式下按键到达的时间，快速连续输入时自动开启粘贴模式
+    pub fn record_key_timing(&mut self, now: Instant) {
+        if !self.options.pastedetect || !self.mode.is_insert() {
+            return;
+        }
+        if self.paste_detector.record_key(now) && !self.options.paste {
+            self.options.paste = true;
+            self.auto_paste = true;
+            self.set_message(tr!(self.locale, "Paste detected, auto-indent paused"));
+        }
+    }
+
+    /// 粘贴停止后关闭自动开启的粘贴模式，返回粘贴模式是否被关闭
+    ///
+    /// 由事件循环定期调用
+    pub fn poll_paste_detection(&mut self, now: Instant) -> bool {
+        if self.paste_detector.poll(now) && self.auto_paste {
+            self.stop_auto_paste();
+            return true;
+        }
+        false
+    }
+
+    /// 关闭自动开启的粘贴模式（手动 :set paste 的不受影响）
+    fn stop_auto_paste(&mut self) {
+        if self.auto_paste {
+            self.auto_paste = false;
+            self.options.paste = false;
+        }
+    }
+
+    // ==================== AI ====================
+
+    pub fn ai_config(&self) -> &AiConfig {
+        &self.ai_config
+    }
+
+    pub fn set_ai_config(&mut self, config: AiConfig) {
+        self.ai_config = config;
+    }
+
+    /// config.toml 中各语言的服务器设置
+    pub fn lsp_settings(&self) -> &LspSettings {
+        &self.lsp_settings
+    }
+
+    // ==================== 终端缓冲区 ====================
+
+    /// :terminal [cmd] - 在上下分割的新窗口中打开终端缓冲区，运行交互式 shell（或用 shell 运行命令），
+    /// 并进入 Terminal 模式
+    pub fn open_terminal(&mut self, command: Option<&str>) -> Result<(), String> {
+        let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+        let shell = shell::interactive_shell();
+        self.split_window(SplitDirection::Horizontal, None)?;
+        let size = self.window_text_size(self.windows.current_id());
+        let buffer_id = BufferId::new(self.next_buffer_id);
+        let job = match TerminalJob::spawn(buffer_id, &shell, command, &cwd, size, self.waker.clone()) {
+            Ok(job) => job,
+            Err(e) => {
+                // 启动失败时不保留新窗口
+                self.close_window(self.windows.current_id())?;
+                return Err(tr!(self.locale, "Failed to run {}: {}", command.unwrap_or(&shell), e));
+            }
+        };
+        let name = format!("!{}", job.command());
+        self.open_new_buffer(|id| Buffer::scratch(id, &name, ""));
+        self.terminals.insert(buffer_id, job);
+        self.set_mode(Mode::Terminal);
+        Ok(())
+    }
+
+    /// 缓冲区中运行的终端程序，不是终端缓冲区时为 None
+    pub fn terminal(&self, buffer: BufferId) -> Option<&TerminalJob> {
+        self.terminals.get(&buffer)
+    }
+
+    /// 窗口文本区域的大小（行, 列），不包括状态栏；还不知道窗口大小时为 24x80
+    fn window_text_size(&self, id: WindowId) -> (u16, u16) {
+        self.windows
+            .layout_rects(self.windows.area())
+            .into_iter()
+            .find(|(window, _)| *window == id)
+            .map(|(_, rect)| (rect.height.saturating_sub(1), rect.width))
+            .filter(|(rows, cols)| *rows > 0 && *cols > 0)
+            .map_or((24, 80), |(rows, cols)| {
+                (u16::try_from(rows).unwrap_or(u16::MAX), u16::try_from(cols).unwrap_or(u16::MAX))
+            })
+    }
+
+    /// 在运行中的终端缓冲区里进入 Terminal 模式（Normal 模式的 `i` / `a`），当前缓冲区不是终端缓冲区时返回 false
+    pub fn enter_terminal_mode(&mut self) -> bool {
+        let Some(job) = self.terminals.get(&self.current_buffer) else {
+            return false;
+        };
+        if job.is_running() {
+            self.cursor = terminal_cursor(job, self.current_buffer());
+            self.set_mode(Mode::Terminal);
+        } else {
+            self.set_message(tr!(self.locale, "Terminal process has exited"));
+        }
+        true
+    }
+
+    /// 把 Terminal 模式的按键发送给当前终端缓冲区中的程序
+    pub fn send_terminal_key(&mut self, key: MapKey) {
+        let result = self.terminals.get_mut(&self.current_buffer).map(|job| job.send_key(key));
+        if let Some(Err(e)) = result {
+            self.set_message(tr!(self.locale, "Terminal error: {}", e));
+        }
+    }
+
+    /// 取出终端程序的新输出写入缓冲区，并使 PTY 的大小跟随显示它的窗口；返回是否有缓冲区变化
+    ///
+    /// Terminal 模式下光标跟随终端的光标，程序退出后回到 Normal 模式。由事件循环定期调用
+    pub fn poll_terminals(&mut self) -> bool {
+        if self.terminals.is_empty() {
+            return false;
+        }
+        let sizes: Vec<(BufferId, (u16, u16))> = self
+            .windows
+            .ids()
+            .into_iter()
+            .filter_map(|id| Some((self.window_view(id)?.0.id(), self.window_text_size(id))))
+            .collect();
+        let mut changed = false;
+        for job in self.terminals.values_mut() {
+            // 显示在多个窗口中时按第一个窗口的大小
+            if let Some((_, (rows, cols))) = sizes.iter().find(|(buffer, _)| *buffer == job.buffer()) {
+                if let Err(e) = job.resize(*rows, *cols) {
+                    tracing::warn!("Failed to resize terminal: {}", e);
+                }
+            }
+            if !job.poll() {
+                continue;
+            }
+            changed = true;
+            let mut text = job.lines().join("\n");
+            if let Some(code) = job.exit_code() {
+                text.push('\n');
+                text.push_str(&tr!(self.locale, "[Process exited {}]", code));
+            }
+            if let Some(buffer) = self.buffers.get_mut(&job.buffer()) {
+                buffer.replace_external(&text);
+            }
+        }
+        if self.mode == Mode::Terminal {
+            match self.terminals.get(&self.current_buffer).filter(|job| job.is_running()) {
+                Some(job) => self.cursor = terminal_cursor(job, self.current_buffer()),
+                None => self.set_mode(Mode::Normal),
+            }
+        }
+        if changed && self.mode != Mode::Terminal {
+            self.cursor = clamp_cursor(self.current_buffer(), self.cursor);
+        }
+        changed
+    }
+
+    // ==================== 后台任务 ====================
+
+    /// 设置唤醒回调：外部命令的输出、:grep 和文件查找的结果到达时调用，
+    /// 界面收到后调用相应的 `poll_*` 方法
+    /// 唤醒事件循环的回调，后台线程有结果时调用
+    pub fn waker(&self) -> &Waker {
+        &self.waker
+    }
+
+    pub fn set_waker(&mut self, waker: Waker) {
+        self.jobs.set_waker(waker.clone());
+        self.waker = waker;
+    }
+
+    pub fn jobs(&self) -> &JobManager {
+        &self.jobs
+    }
+
+    pub fn jobs_mut(&mut self) -> &mut JobManager {
+        &mut self.jobs
+    }
+
+    /// 处理后台任务的输出和退出事件，返回是否有事件（需要重绘）
+    ///
+    /// 由事件循环定期调用
+    pub fn poll_jobs(&mut self) -> bool {
+        self.poll_job_events().0
+    }
+
+    /// 处理后台任务的事件，返回是否有事件和失败的外部命令的消息
+    fn poll_job_events(&mut self) -> (bool, Vec<String>) {
+        let events = self.jobs.poll();
+        let mut failures = Vec::new();
+        for event in &events {
+            if let JobEvent::Output { id, stream, line } = event {
+                if let Some(shell) = self.shell_commands.get_mut(id) {
+                    shell.push_line(*stream, line.clone());
+                }
+            }
+            if let JobEvent::Exited { id, status } = event {
+                if let Some(shell) = self.shell_commands.remove(id) {
+                    if let Err(failure) = self.finish_shell_command(shell, *status) {
+                        failures.push(failure);
+                    }
+                    continue;
+                }
+                let command = self.jobs.get(*id).map(|job| job.command().to_string());
+                let status = self.describe_job_status(*status);
+                self.set_message(tr!(self.locale, "Job {} ({}) {}", id, command.unwrap_or_default(), status));
+            }
+        }
+        (!events.is_empty(), failures)
+    }
+
+    /// 等待正在运行的外部命令结束并处理输出（无界面模式和脚本接口在每一步之后调用）
+    ///
+    /// 有命令失败时返回第一个失败的消息；超过 `timeout` 还没有结束时返回错误，命令留在后台继续运行
+    pub fn wait_for_shell_commands(&mut self, timeout: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        let mut failure = None;
+        loop {
+            let (_, failures) = self.poll_job_events();
+            if failure.is_none() {
+                failure = failures.into_iter().next();
+            }
+            if self.shell_commands.is_empty() {
+                break;
+            }
+            if Instant::now() >= deadline {
+                let commands: Vec<&str> = self.shell_commands.values().map(|shell| shell.command.as_str()).collect();
+                return Err(tr!(self.locale, "Shell command still running: {}", commands.join(", ")));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// 在后台运行外部命令，结束后由 `poll_jobs` 按 `action` 处理输出
+    fn start_shell_command(&mut self, command: &str, action: ShellAction, input: Option<String>) -> Result<(), String> {
+        let mut spec = JobSpec::shell(command);
+        if let Some(input) = input {
+            spec = spec.stdin(input);
+        }
+        let id = self
+            .jobs
+            .spawn(spec)
+            .map_err(|e| tr!(self.locale, "Failed to run {}: {}", command, e))?;
+        self.shell_commands.insert(id, ShellCommand::new(command, action));
+        self.set_message(tr!(self.locale, "Running {}...", command));
+        Ok(())
+    }
+
+    /// 正在运行的外部命令数量
+    pub fn pending_shell_commands(&self) -> usize {
+        self.shell_commands.len()
+    }
+
+    /// 外部命令结束：显示输出，或把输出写入缓冲区
+    ///
+    /// 命令失败时不修改缓冲区；写入前缓冲区已不是当前缓冲区时丢弃输出。这两种情况返回显示的消息
+    fn finish_shell_command(&mut self, shell: ShellCommand, status: JobStatus) -> Result<(), String> {
+        // :make 不论退出状态如何都解析输出，编译失败正是有错误的时候
+        if let ShellAction::Make { jump } = shell.action {
+            self.finish_make(&shell, status, jump);
+            return Ok(());
+        }
+        if status != JobStatus::Exited(Some(0)) {
+            let mut lines = match shell.action {
+                ShellAction::Show => [shell.stdout(), shell.stderr()].concat(),
+                _ => shell.stderr().to_vec(),
+            };
+            let status = self.describe_job_status(status);
+            lines.push(tr!(self.locale, "Shell command failed ({}): {}", status, shell.command));
+            let message = lines.join("\n");
+            self.set_message(message.clone());
+            return Err(message);
+        }
+
+        let target = match shell.action {
+            ShellAction::Show | ShellAction::Make { .. } => None,
+            ShellAction::Read { buffer, .. } | ShellAction::Filter { buffer, .. } => Some(buffer),
+        };
+        if target.is_some_and(|buffer| buffer != self.current_buffer) {
+            let message = tr!(self.locale, "Buffer changed, output of {} discarded", shell.command);
+            self.set_message(message.clone());
+            return Err(message);
+        }
+
+        let message = match shell.action {
+            ShellAction::Show | ShellAction::Make { .. } => {
+                let lines = [shell.stdout(), shell.stderr()].concat();
+                if lines.is_empty() {
+                    tr!(self.locale, "Shell command finished: {}", shell.command)
+                } else {
+                    lines.join("\n")
+                }
+            }
+            ShellAction::Read { at, .. } => {
+                self.insert_lines_at(at, &shell.stdout_text());
+                tr!(self.locale, "{} line(s) read", shell.stdout().len())
+            }
+            ShellAction::Filter { range, .. } => {
+                self.replace_lines(range, &shell.stdout_text());
+                tr!(self.locale, "{} line(s) filtered", range.line_count())
+            }
+        };
+        // 成功时标准错误的内容仍然显示出来
+        if !matches!(shell.action, ShellAction::Show) && !shell.stderr().is_empty() {
+            self.set_message(shell.stderr().join("\n"));
+        } else {
+            self.set_message(message);
+        }
+        Ok(())
+    }
+
+    /// 格式化任务列表（用于 :jobs 面板）
+    pub fn format_jobs(&self) -> String {
+        if self.jobs.jobs().next().is_none() {
+            return tr!(self.locale, "No jobs");
+        }
+
+        let mut output = String::new();
+        output.push_str(&tr!(self.locale, "Jobs:"));
+        output.push_str("\n-----\n");
+        for job in self.jobs.jobs() {
+            output.push_str(&format!(
+                "[{}] {:<10} {}\n",
+                job.id(),
+                self.describe_job_status(job.status()),
+                job.command()
+            ));
+            // 显示最后一行输出
+            if let Some(last) = job.output().last() {
+                output.push_str(&format!("     {}\n", last));
+            }
+        }
+        output
+    }
+
+    /// 任务状态的本地化描述
+    fn describe_job_status(&self, status: JobStatus) -> String {
+        match status {
+            JobStatus::Running => tr!(self.locale, "running"),
+            JobStatus::Exited(Some(code)) => tr!(self.locale, "exit {}", code),
+            JobStatus::Exited(None) => tr!(self.locale, "terminated"),
+            JobStatus::Killed => tr!(self.locale, "killed"),
+        }
+    }
+
+    // ==================== 文件跟踪 (:tail) ====================
+
+    /// 当前缓冲区是否处于跟踪模式
+    pub fn is_tailing(&self) -> bool {
+        self.tail.as_ref().is_some_and(|tail| tail.buffer == self.current_buffer)
+    }
+
+    /// 开始跟踪当前缓冲区对应的文件
+    ///
+    /// 缓冲区会从磁盘重新加载并设为只读，光标移到末尾。
+    /// 同一时间只跟踪一个文件，对新文件执行 :tail 会停止之前的跟踪。
+    pub fn start_tail(&mut self) -> Result<(), String> {
+        let path = self
+            .current_buffer()
+            .file_path()
+            .map(|p| p.to_path_buf())
+            .ok_or_else(|| tr!(self.locale, "No file name"))?;
+        if self.current_buffer().is_modified() {
+            return Err(tr!(self.locale, "No write since last change"));
+        }
+        if self.tail.is_some() {
+            self.stop_tail();
+        }
+
+        let buffer = self.current_buffer_mut();
+        buffer.reload().map_err(|e| e.to_string())?;
+        let was_read_only = buffer.is_read_only();
+        buffer.set_read_only(true);
+        let offset = path.metadata().map(|m| m.len()).unwrap_or(0);
+
+        self.tail = Some(TailState::new(self.current_buffer, offset, was_read_only));
+        self.execute_motion(Motion::DocumentEnd);
+        self.set_message(tr!(self.locale, "Following {} (:tail to stop)", path.display()));
+        Ok(())
+    }
+
+    /// 停止跟踪，恢复缓冲区原来的只读状态
+    pub fn stop_tail(&mut self) {
+        let Some(tail) = self.tail.take() else {
+            return;
+        };
+        if let Some(buffer) = self.buffers.get_mut(&tail.buffer) {
+            buffer.set_read_only(tail.was_read_only());
+        }
+        self.set_message(tr!(self.locale, "Stopped following"));
+    }
+
+    /// 读取被跟踪文件新追加的内容，内容有变化时返回 true
+    ///
+    /// 光标在最后一行时自动跟随到新的末尾；用户把光标移开后停止跟随，
+    /// 按 G 回到末尾即恢复跟随。
+    pub fn poll_tail(&mut self) -> bool {
+        let Some(tail) = self.tail.as_mut() else {
+            return false;
+        };
+        let Some(buffer) = self.buffers.get_mut(&tail.buffer) else {
+            // 缓冲区已被删除
+            self.tail = None;
+            return false;
+        };
+        let Some(path) = buffer.file_path().map(|p| p.to_path_buf()) else {
+            self.tail = None;
+            return false;
+        };
+
+        let is_current = tail.buffer == self.current_buffer;
+        let follow = is_current && self.cursor.line >= buffer.last_content_line();
+
+        match tail.poll(&path) {
+            Ok(TailUpdate::Unchanged) => return false,
+            Ok(TailUpdate::Appended(text)) => buffer.append_external(&text),
+            Ok(TailUpdate::Truncated) => {
+                if buffer.reload().is_err() {
+                    return false;
+                }
+                tail.reset(path.metadata().map(|m| m.len()).unwrap_or(0));
+                if is_current {
+                    self.cursor.ensure_valid(buffer);
+                }
+            }
+            // 文件暂时不可读（如正在轮转），下次再试
+            Err(_) => return false,
+        }
+
+        if follow {
+            self.execute_motion(Motion::DocumentEnd);
+        }
+        true
+    }
+
+    // ==================== 模糊选择器 ====================
+
+    /// 获取当前打开的选择器
+    pub fn picker(&self) -> Option<&Picker> {
+        self.picker.as_ref()
+    }
+
+    pub fn picker_mut(&mut self) -> Option<&mut Picker> {
+        self.picker.as_mut()
+    }
+
+    /// 打开选择器（替换已打开的选择器）
+    pub fn open_picker(&mut self, picker: Picker) {
+        self.picker = Some(picker);
+        self.finder = None;
+    }
+
+    /// 打开模糊文件选择器 (Ctrl+P / :Files)，列出 `dir`（默认为当前目录）下的文件
+    ///
+    /// 文件在后台查找，由 `poll_finder` 陆续加入选择器
+    pub fn open_file_finder(&mut self, dir: Option<&Path>) -> Result<(), String> {
+        let root = match dir {
+            Some(dir) => dir.to_path_buf(),
+            None => std::env::current_dir().map_err(|e| e.to_string())?,
+        };
+        if !root.is_dir() {
+            return Err(tr!(self.locale, "Not a directory: {}", root.display()));
+        }
+        let mut picker = Picker::new(tr!(self.locale, "Files"), Vec::new());
+        picker.set_loading(true);
+        self.open_picker(picker);
+        self.finder = Some(FileFinder::start_with_waker(root, self.waker.clone()));
+        Ok(())
+    }
+
+    /// 把后台找到的文件加入文件选择器，有新文件或查找结束时返回 true
+    pub fn poll_finder(&mut self) -> bool {
+        let Some(finder) = self.finder.as_mut() else {
+            return false;
+        };
+        let root = finder.root().to_path_buf();
+        let files = finder.poll();
+        let done = finder.is_done();
+        if done {
+            self.finder = None;
+        }
+        let Some(picker) = self.picker.as_mut() else {
+            return false;
+        };
+        let changed = !files.is_empty() || done;
+        picker.extend(files.into_iter().map(|relative| {
+            PickerItem::new(relative.display().to_string(), 0, 0).with_path(root.join(relative))
+        }));
+        picker.set_loading(!done);
+        changed
+    }
+
+    /// 打开当前缓冲区的符号选择器 (:symbols)
+    pub fn open_document_symbols(&mut self) -> Result<(), String> {
+        let lines: Vec<_> = self.current_buffer().text_lines().collect();
+        let items: Vec<_> = crate::symbols::document_symbols(lines.iter().map(AsRef::as_ref))
+            .iter()
+            .map(|symbol| symbol.to_picker_item())
+            .collect();
+        if items.is_empty() {
+            return Err(tr!(self.locale, "No symbols found"));
+        }
+        self.open_picker(Picker::new(tr!(self.locale, "Symbols"), items));
+        Ok(())
+    }
+
+    /// 打开工作区（当前目录）的符号选择器 (:symbols workspace)
+    pub fn open_workspace_symbols(&mut self) -> Result<(), String> {
+        let root = std::env::current_dir().map_err(|e| e.to_string())?;
+        let items = crate::symbols::workspace_symbols(&root);
+        if items.is_empty() {
+            return Err(tr!(self.locale, "No symbols found"));
+        }
+        self.open_picker(Picker::new(tr!(self.locale, "Workspace Symbols"), items));
+        Ok(())
+    }
+
+    /// 在新窗口中打开选中项 (选择器中的 Ctrl+X / Ctrl+V)
+    pub fn accept_picker_in_split(&mut self, direction: SplitDirection) -> Result<(), String> {
+        if self.picker.as_ref().and_then(Picker::selected_item).is_none() {
+            self.cancel_picker();
+            return Ok(());
+        }
+        self.split_window(direction, None)?;
+        if let Err(e) = self.accept_picker() {
+            // 打开失败时不保留新窗口
+            self.close_window(self.windows.current_id())?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// 关闭选择器并跳转到选中项
+    pub fn accept_picker(&mut self) -> Result<(), String> {
+        self.finder = None;
+        let Some(picker) = self.picker.take() else {
+            return Ok(());
+        };
+        let Some(item) = picker.selected_item().cloned() else {
+            return Ok(());
+        };
+
+        self.jump_to_location(item.path.as_deref(), item.line, item.column)
+    }
+
+    /// 跳转到文件中的位置，`path` 为 None 时在当前缓冲区中跳转
+    fn jump_to_location(&mut self, path: Option<&Path>, line: usize, column: usize) -> Result<(), String> {
+        if let Some(path) = path {
+            self.switch_to_file(path).map_err(|e| tr!(self.locale, "Failed to open {}: {}", path.display(), e))?;
+        }
+
+        let mut cursor = Cursor::new(line, column);
+        cursor.ensure_valid(self.current_buffer());
+        cursor.update_preferred_column(self.current_buffer());
+        self.cursor = cursor;
+        Ok(())
+    }
+
+    /// 关闭选择器
+    pub fn cancel_picker(&mut self) {
+        self.picker = None;
+        self.finder = None;
+    }
+
+    // ==================== Quickfix ====================
+
+    pub fn quickfix(&self) -> &QuickfixList {
+        &self.quickfix
+    }
+
+    /// 替换 Quickfix 列表，停止正在进行的 :grep
+    pub fn set_quickfix(&mut self, quickfix: QuickfixList) {
+        self.quickfix = quickfix;
+        self.grep = None;
+    }
+
+    /// 当前窗口的位置列表
+    pub fn location_list(&self) -> Option<&QuickfixList> {
+        self.windows.current().location_list.as_ref()
+    }
+
+    /// 设置当前窗口的位置列表（如诊断信息），None 表示删除
+    pub fn set_location_list(&mut self, list: Option<QuickfixList>) {
+        self.windows.current_mut().location_list = list;
+    }
+
+    /// Quickfix 列表或当前窗口的位置列表
+    pub fn list(&self, kind: ListKind) -> Option<&QuickfixList> {
+        match kind {
+            ListKind::Quickfix => Some(&self.quickfix),
+            ListKind::Location => self.location_list(),
+        }
+    }
+
+    fn list_mut(&mut self, kind: ListKind) -> Option<&mut QuickfixList> {
+        match kind {
+            ListKind::Quickfix => Some(&mut self.quickfix),
+            ListKind::Location => self.windows.current_mut().location_list.as_mut(),
+        }
+    }
+
+    /// 是否正在后台进行 :grep
+    pub fn is_grepping(&self) -> bool {
+        self.grep.is_some()
+    }
+
+    /// 列表面板显示的列表；面板显示位置列表而当前窗口没有位置列表时返回 None
+    pub fn quickfix_panel(&self) -> Option<(ListKind, &QuickfixList)> {
+        let kind = self.quickfix_panel?;
+        self.list(kind).map(|list| (kind, list))
+    }
+
+    pub fn show_quickfix(&self) -> bool {
+        self.quickfix_panel().is_some()
+    }
+
+    pub fn quickfix_focused(&self) -> bool {
+        self.show_quickfix() && self.quickfix_focused
+    }
+
+    /// 打开 Quickfix 面板并使它获得焦点 (:copen)
+    pub fn open_quickfix(&mut self) {
+        self.quickfix_panel = Some(ListKind::Quickfix);
+        self.quickfix_focused = true;
+    }
+
+    /// 在面板中打开当前窗口的位置列表并使它获得焦点 (:lopen)
+    pub fn open_location_list(&mut self) -> Result<(), String> {
+        if self.location_list().is_none() {
+            return Err(tr!(self.locale, "No location list"));
+        }
+        self.quickfix_panel = Some(ListKind::Location);
+        self.quickfix_focused = true;
+        Ok(())
+    }
+
+    /// 关闭列表面板
+    pub fn close_quickfix(&mut self) {
+        self.quickfix_panel = None;
+        self.quickfix_focused = false;
+    }
+
+    /// 面板显示的是 `kind` 列表时关闭面板 (:cclose / :lclose)
+    pub fn close_list(&mut self, kind: ListKind) {
+        if self.quickfix_panel == Some(kind) {
+            self.close_quickfix();
+        }
+    }
+
+    /// 焦点回到编辑窗口，面板保持打开
+    pub fn unfocus_quickfix(&mut self) {
+        self.quickfix_focused = false;
+    }
+
+    /// 在列表面板中选择第 `index` 项，不跳转
+    pub fn select_quickfix(&mut self, index: usize) {
+        if let Some(list) = self.quickfix_panel.and_then(|kind| self.list_mut(kind)) {
+            list.select(index);
+        }
+    }
+
+    /// 在 `dir`（默认为当前目录）下的所有文件中搜索 `pattern` (:grep)
+    ///
+    /// 搜索在后台进行，由 `poll_grep` 把结果陆续加入 Quickfix 列表，并打开 Quickfix 面板
+    pub fn start_grep(&mut self, pattern: &str, dir: Option<&Path>) -> Result<(), String> {
+        let root = dir.unwrap_or(Path::new(".")).to_path_buf();
+        if !root.is_dir() {
+            return Err(tr!(self.locale, "Not a directory: {}", root.display()));
+        }
+        self.quickfix = QuickfixList::new(format!(":grep {}", pattern));
+        self.grep = Some(GrepSearch::start_with_waker(root, pattern, self.waker.clone()));
+        self.open_quickfix();
+        self.set_message(tr!(self.locale, "Searching for {}...", pattern));
+        Ok(())
+    }
+
+    /// 把后台 :grep 找到的匹配加入 Quickfix 列表，有新结果或搜索结束时返回 true
+    pub fn poll_grep(&mut self) -> bool {
+        let Some(grep) = self.grep.as_mut() else {
+            return false;
+        };
+        let entries = grep.poll();
+        let changed = !entries.is_empty();
+        self.quickfix.extend(entries);
+        if !grep.is_done() {
+            return changed;
+        }
+        let pattern = grep.pattern().to_string();
+        self.grep = None;
+        if self.quickfix.is_empty() {
+            self.set_message(tr!(self.locale, "No matches for {}", pattern));
+        } else {
+            self.set_message(tr!(self.locale, "{} matches for {}", self.quickfix.len(), pattern));
+        }
+        true
+    }
+
+    pub fn make_program(&self) -> &str {
+        &self.make_program
+    }
+
+    /// 运行 makeprg，`args` 附加在命令之后 (:make)
+    ///
+    /// 命令结束后从输出中提取错误位置作为 Quickfix 列表，`jump` 为 true 时跳转到第一个错误
+    pub fn start_make(&mut self, args: &str, jump: bool) -> Result<(), String> {
+        let command = if args.is_empty() {
+            self.make_program.clone()
+        } else {
+            format!("{} {}", self.make_program, args)
+        };
+        self.start_shell_command(&command, ShellAction::Make { jump }, None)
+    }
+
+    /// 用 :make 的输出替换 Quickfix 列表
+    fn finish_make(&mut self, shell: &ShellCommand, status: JobStatus, jump: bool) {
+        let output = [shell.stdout(), shell.stderr()].concat().join("\n");
+        let mut quickfix = QuickfixList::new(format!(":{}", shell.command));
+        quickfix.extend(parse_errors(&output));
+        self.set_quickfix(quickfix);
+        if jump && !self.quickfix.is_empty() {
+            if let Err(e) = self.list_jump(ListKind::Quickfix, 0) {
+                self.set_message(e);
+            }
+            return;
+        }
+        let status = self.describe_job_status(status);
+        self.set_message(tr!(self.locale, "{} finished ({}): {} errors", shell.command, status, self.quickfix.len()));
+    }
+
+    /// 列表为空或不存在时的错误
+    fn empty_list_error(&self, kind: ListKind) -> String {
+        match (kind, self.list(kind)) {
+            (ListKind::Location, None) => tr!(self.locale, "No location list"),
+            (ListKind::Location, Some(_)) => tr!(self.locale, "Location list is empty"),
+            (ListKind::Quickfix, _) => tr!(self.locale, "Quickfix list is empty"),
+        }
+    }
+
+    /// 跳转到列表的第 `index` 项 (:cc / :ll、面板中的回车)，焦点回到编辑窗口
+    pub fn list_jump(&mut self, kind: ListKind, index: usize) -> Result<(), String> {
+        match self.list_mut(kind) {
+            Some(list) if !list.is_empty() => list.select(index),
+            _ => return Err(self.empty_list_error(kind)),
+        };
+        self.jump_to_list_entry(kind)
+    }
+
+    /// 跳转到后面第 `count` 项 (:cnext / :lnext)
+    pub fn list_next(&mut self, kind: ListKind, count: usize) -> Result<(), String> {
+        match self.list_mut(kind) {
+            Some(list) if !list.is_empty() => {
+                if list.next(count).is_none() {
+                    return Err(tr!(self.locale, "No more items"));
+                }
+            }
+            _ => return Err(self.empty_list_error(kind)),
+        }
+        self.jump_to_list_entry(kind)
+    }
+
+    /// 跳转到前面第 `count` 项 (:cprev / :lprev)
+    pub fn list_previous(&mut self, kind: ListKind, count: usize) -> Result<(), String> {
+        match self.list_mut(kind) {
+            Some(list) if !list.is_empty() => {
+                if list.previous(count).is_none() {
+                    return Err(tr!(self.locale, "No more items"));
+                }
+            }
+            _ => return Err(self.empty_list_error(kind)),
+        }
+        self.jump_to_list_entry(kind)
+    }
+
+    /// 跳转到当前项，并在命令行显示 `(n of total): 说明`
+    fn jump_to_list_entry(&mut self, kind: ListKind) -> Result<(), String> {
+        let Some(list) = self.list(kind) else {
+            return Ok(());
+        };
+        let Some(entry) = list.current().cloned() else {
+            return Ok(());
+        };
+        let (index, total) = (list.current_index() + 1, list.len());
+        self.quickfix_focused = false;
+        self.jump_to_location(Some(&entry.path), entry.line, entry.column)?;
+        let text = match entry.kind {
+            Some(kind) => format!("{}: {}", kind.name(), entry.text),
+            None => entry.text,
+        };
+        self.set_message(tr!(self.locale, "({} of {}): {}", index, total, text));
+        Ok(())
+    }
+
+    /// 切换到打开该文件的缓冲区，没有则打开文件
+    pub fn switch_to_file(&mut self, path: &Path) -> io::Result<()> {
+        let existing = self
+            .buffers
+            .iter()
+            .find(|(_, buffer)| buffer.file_path() == Some(path))
+            .map(|(id, _)| *id);
+        match existing {
+            Some(id) if id == self.current_buffer => Ok(()),
+            Some(id) => self.switch_buffer(id).map_err(io::Error::other),
+            None => self.open_file(path),
+        }
+    }
+
+    // ==================== 寄存器操作 ====================
+
+    pub fn register_manager(&self) -> &RegisterManager {
+        &self.register_manager
+    }
+
+    pub fn register_manager_mut(&mut self) -> &mut RegisterManager {
+        &mut self.register_manager
+    }
+
+    /// 复制当前行到寄存器 (yy)
+    pub fn yank_line(&mut self, register: Option<char>) {
+        let line_idx = self.cursor.line;
+        if let Some(line_text) = self.get_line_text(line_idx) {
+            let content = format!("{}\n", line_text);
+            let reg = register.unwrap_or('"');
+            self.register_manager.set(reg, content, true);
+        }
+    }
+
+    /// 复制选中区域到寄存器 (y)
+    pub fn yank(&mut self, start: usize, end: usize, register: Option<char>, linewise: bool) {
+        let buffer = self.current_buffer();
+        let content = buffer.slice(start..end).to_string();
+        let reg = register.unwrap_or('"');
+        self.register_manager.set(reg, content, linewise);
+    }
+
+    /// 粘贴寄存器内容 (p/P)
+    pub fn paste(&mut self, register: Option<char>, before_cursor: bool) {
+        let reg = register.unwrap_or('"');
+
+        // 先获取寄存器内容，避免借用冲突
+        let (content, linewise) = {
+            if let Some(register) = self.register_manager.get(reg) {
+                if register.is_empty() {
+                    return;
+                }
+                (register.content.clone(), register.linewise)
+            } else {
+                return;
+            }
+        };
+
+        with_save_state!(self, {
+            if linewise {
+                // 整行粘贴
+                let insert_line = if before_cursor {
+                    self.cursor.line
+                } else {
+                    self.cursor.line + 1
+                };
+
+                let char_idx = self.current_buffer().line_to_char(insert_line);
+                let buffer = self.current_buffer_mut();
+                buffer.insert(char_idx, &content);
+
+                // 移动光标到新粘贴的第一行
+                self.cursor.line = insert_line;
+                self.cursor.column = 0;
+            } else {
+                // 字符粘贴
+                let cursor_line = self.cursor.line;
+                let cursor_col = self.cursor.column;
+
+                let insert_idx = {
+                    let buffer = self.current_buffer();
+                    let line_start = buffer.line_to_char(cursor_line);
+                    let line_len = buffer.line_len(cursor_line);
+                    // line_len 包括换行符，实际文本长度是 line_len - 1
+                    let text_len = line_len.saturating_sub(1);
+                    
+                    if before_cursor {
+                        // P - 在光标位置之前粘贴
+                        // cursor_col 应该限制在文本范围内
+                        line_start + cursor_col.min(text_len)
+                    } else {
+                        // p - 在光标位置之后粘贴
+                        // 如果 cursor_col >= text_len，说明在行尾，在文本末尾粘贴
+                        // 否则在 cursor_col + 1 位置粘贴
+                        if cursor_col >= text_len {
+                            // 在行尾，在文本末尾（换行符前）粘贴
+                            line_start + text_len
+                        } else {
+                            // 在行中间，在当前字符后粘贴
+                            line_start + cursor_col + 1
+                        }
+                    }
+                };
+
+                let buffer = self.current_buffer_mut();
+                buffer.insert(insert_idx, &content);
+
+                // 移动光标到粘贴内容之后
+                let content_len = content.chars().count();
+                let new_line_len = buffer.line_len(cursor_line);
+                self.cursor.column = (cursor_col + content_len).min(new_line_len.saturating_sub(1));
+            }
+        });
+    }
+
+    /// 删除当前行并放入寄存器 (dd)
+    pub fn delete_line(&mut self, register: Option<char>) -> Option<String> {
+        let line_idx = self.cursor.line;
+        let line_text = self.get_line_text(line_idx)?;
+        let content = format!("{}\n", line_text);
+
+        with_save_state!(self, {
+            // 保存到寄存器
+            let reg = register.unwrap_or('"');
+            self.register_manager.set(reg, &content, true);
+
+            // 删除行
+            let line_start = self.current_buffer().line_to_char(line_idx);
+            let line_len = self.current_buffer().line_len(line_idx);
+            let buffer = self.current_buffer_mut();
+            buffer.remove(line_start, line_len);
+
+            // 确保至少有一行
+            if buffer.len_lines() == 0 {
+                buffer.insert(0, "\n");
+            }
+
+            // 调整光标位置
+            let max_line = buffer.len_lines().saturating_sub(1);
+            self.cursor.line = self.cursor.line.min(max_line);
+            self.cursor.column = 0;
+
+            Some(content)
+        })
+    }
+
+    /// 删除光标处的字形簇并放入寄存器 (x/d)，返回删除的文本
+    pub fn delete_char_to_register(&mut self, register: Option<char>) -> Option<String> {
+        with_save_state!(self, {
+            let deleted = self.remove_grapheme_at_cursor()?;
+            let reg = register.unwrap_or('"');
+            self.register_manager.set(reg, deleted.clone(), false);
+            Some(deleted)
+        })
+    }
+
+    // ==================== 搜索功能 ====================
+
+    pub fn search_state(&self) -> &SearchState {
+        &self.search_state
+    }
+
+    /// 开始搜索（/ 或 ?），`input` 为命令行中输入的文本，可以在分隔符之后带偏移（`foo/e`）；
+    /// 模式为空时使用上一次搜索的模式（`//e`）
+    pub fn start_search(&mut self, direction: SearchDirection, input: impl Into<String>) {
+        if !self.set_search_input(direction, &input.into()) {
+            return;
+        }
+
+        // 跳转到第一个匹配（从光标位置开始）
+        let buffer = self.buffers.get(&self.current_buffer).unwrap();
+        let Some(idx) = self.search_state.calc_first_match(&self.cursor, buffer) else {
+            return;
+        };
+        if let Some(landing) = self.search_state.landing(idx, buffer) {
+            self.search_state.set_current_match(idx);
+            self.cursor = landing;
+        }
+    }
+
+    /// 带操作符的搜索（`d/foo`、`c?bar`）：设置搜索模式后对光标到匹配的范围执行操作符，
+    /// 匹配的落点不包括在内（`/foo/e` 时包括，行偏移时按整行）
+    pub fn search_with_operator(
+        &mut self,
+        direction: SearchDirection,
+        input: &str,
+        command: OperatorCommand,
+    ) -> Option<String> {
+        if !self.set_search_input(direction, input) {
+            return None;
+        }
+        self.execute_operator(command)
+    }
+
+    /// 设置搜索模式（`/` 或 `?` 之后输入的内容，可以带偏移）并保存到搜索寄存器和历史，光标不动；出错时显示消息并返回 false
+    fn set_search_input(&mut self, direction: SearchDirection, input: &str) -> bool {
+        let (pattern, offset) = crate::search::split_search_input(input, direction.delimiter());
+        let Some(offset) = SearchOffset::parse(offset) else {
+            self.set_message(tr!(self.locale, "Invalid search offset: {}", offset));
+            return false;
+        };
+        let pattern = if pattern.is_empty() {
+            self.register_manager.get('/').map(|register| register.content).unwrap_or_default()
+        } else {
+            pattern
+        };
+        if pattern.is_empty() {
+            self.set_message(tr!(self.locale, "No previous search pattern"));
+            return false;
+        }
+        let buffer = self.buffers.get(&self.current_buffer).unwrap();
+        self.search_state.set_pattern(&pattern, direction, buffer);
+        self.search_state.offset = offset;
+
+        // 保存到搜索寄存器和搜索历史（历史中包括偏移）
+        self.register_manager.set_search(&pattern);
+        self.search_history.push(input);
+        true
+    }
+
+    /// 搜索光标下的单词（`*` 向前、`#` 向后），光标不在单词上时使用光标后的第一个单词；
+    /// `whole_word` 为 false 时（`g*` / `g#`）不加单词边界，跳到第 `count` 个匹配
+    pub fn search_word_under_cursor(&mut self, direction: SearchDirection, whole_word: bool, count: usize) {
+        let text = self.current_buffer().line_text(self.cursor.line).unwrap_or_default();
+        let Some((start, word)) = crate::search::word_under_cursor(&text, self.cursor.column) else {
+            self.set_message(tr!(self.locale, "No string under cursor"));
+            return;
+        };
+        let pattern = if whole_word { format!("\\<{}\\>", word) } else { word };
+        // 从单词开头找下一个，不停在光标所在的单词上
+        self.cursor.column = start;
+        let buffer = self.buffers.get(&self.current_buffer).unwrap();
+        self.search_state.set_pattern(&pattern, direction, buffer);
+        self.register_manager.set_search(&pattern);
+        self.search_history.push(&pattern);
+        for _ in 0..count.max(1) {
+            self.search_next();
+        }
+    }
+
+    /// 搜索下一个（n），沿搜索方向
+    pub fn search_next(&mut self) {
+        self.search_step(false);
+    }
+
+    /// 搜索上一个（N），与搜索方向相反
+    pub fn search_prev(&mut self) {
+        self.search_step(true);
+    }
+
+    fn search_step(&mut self, reverse: bool) {
+        let buffer = self.buffers.get(&self.current_buffer).unwrap();
+        self.search_state.refresh(buffer);
+        let Some(idx) = self.search_state.step(&self.cursor, buffer, reverse) else {
+            return;
+        };
+        if let Some(landing) = self.search_state.landing(idx, buffer) {
+            self.search_state.set_current_match(idx);
+            self.cursor = landing;
+        }
+    }
+
+    /// 从光标开始按 `count` 次 n（`reverse` 时为 N）到达的位置，光标不动；用于带操作符的搜索
+    fn search_target(&self, count: usize, reverse: bool) -> Option<Cursor> {
+        let buffer = self.current_buffer();
+        let mut target = self.cursor;
+        for _ in 0..count {
+            let idx = self.search_state.step(&target, buffer, reverse)?;
+            target = self.search_state.landing(idx, buffer)?;
+        }
+        Some(target)
+    }
+
+    /// 光标所在的匹配是第几个（从 1 开始）和匹配总数，用于状态栏的 "match 3 of 17"；光标离开当前匹配的落点后为 None
+    pub fn search_count(&self) -> Option<(usize, usize)> {
+        let buffer = self.current_buffer();
+        let idx = self.search_state.current_match?;
+        let landing = self.search_state.landing(idx, buffer)?;
+        let at_match = self.cursor.to_char_idx(buffer) == landing.to_char_idx(buffer);
+        at_match.then_some((idx + 1, self.search_state.matches.len()))
+    }
+
+    /// 清除搜索高亮
+    pub fn clear_search(&mut self) {
+        self.search_state.clear();
+    }
+
+    // ==================== 范围操作 ====================
+
+    /// 删除从当前位置到目标位置的文本
+    pub fn delete_to_motion(&mut self, motion: Motion) -> Option<String> {
+        self.delete_to_motion_with_register(motion, None)
+    }
+
+    /// 删除从当前位置到目标位置的文本（支持指定寄存器）
+    pub fn delete_to_motion_with_register(&mut self, motion: Motion, register: Option<char>) -> Option<String> {
+        with_save_state!(self, {
+            let start_cursor = self.cursor;
+            let start_idx = {
+                let buffer = self.current_buffer();
+                self.cursor.to_char_idx(buffer)
+            };
+
+            // 临时执行移动来计算终点（不改变实际光标）
+            let mut temp_cursor = self.cursor;
+            motion.execute_in(&mut temp_cursor, self.current_buffer(), &self.viewport());
+
+            let mut end_idx = {
+                let buffer = self.current_buffer();
+                temp_cursor.to_char_idx(buffer)
+            };
+
+            if start_idx == end_idx {
+                return None;
+            }
+
+            // 对于单词移动，检查是否跨越了换行符
+            // 如果是，截断到当前行的末尾，不删除换行符
+            if matches!(motion, Motion::WordForward | Motion::WordBackward) {
+                let buffer = self.current_buffer();
+                
+                // 确保 start_idx < end_idx
+                let (start, end) = if start_idx < end_idx {
+                    (start_idx, end_idx)
+                } else {
+                    (end_idx, start_idx)
+                };
+                
+                // 检查删除范围内是否有换行符
+                if let Some(newline_pos) = buffer.slice(start..end).chars().position(|ch| ch == '\n') {
+                    // 有换行符，截断到换行符之前
+                    end_idx = start + newline_pos;
+                }
+            }
+
+            if start_idx == end_idx {
+                return None;
+            }
+
+            let (start, end, is_forward) = if start_idx < end_idx {
+                (start_idx, end_idx, true)
+            } else {
+                (end_idx, start_idx, false)
+            };
+
+            let deleted = self.current_buffer().slice(start..end).to_string();
+
+            // 删除文本
+            {
+                let buffer = self.current_buffer_mut();
+                buffer.remove(start, end - start);
+            }
+
+            // 存入指定的寄存器和无名寄存器（删除操作）
+            self.store_operator_text(&deleted, false, register, true);
+
+            // 设置光标位置：
+            // - 向前删除（dw, dl）：保持在原位置
+            // - 向后删除（db, dh）：移动到删除区域的开头
+            if is_forward {
+                self.cursor = start_cursor;
+            } else {
+                // 向后删除，光标已经在正确的位置（start）
+                self.cursor = Cursor::from_char_idx(self.current_buffer(), start);
+            }
+
+            Some(deleted)
         })
     }
 
-    pub fn visible_lines(&self, viewport_height: usize, scroll_offset: usize) -> Vec<(usize, String)> {
-        let buffer = self.current_buffer();
-        let start_line = scroll_offset;
-        let end_line = (scroll_offset + viewport_height).min(buffer.len_lines());
-
-        (start_line..end_line)
-            .filter_map(|line_idx| {
-                self.get_line_text(line_idx).map(|text| (line_idx, text))
-            })
-            .collect()
+    /// 复制从当前位置到目标位置的文本
+    pub fn yank_to_motion(&mut self, motion: Motion) {
+        self.yank_to_motion_with_register(motion, None);
     }
 
-    // ==================== 寄存器操作 ====================
+    /// 复制从当前位置到目标位置的文本（支持指定寄存器）
+    pub fn yank_to_motion_with_register(&mut self, motion: Motion, register: Option<char>) {
+        let start_idx = {
+            let buffer = self.current_buffer();
+            self.cursor.to_char_idx(buffer)
+        };
 
-    pub fn register_manager(&self) -> &RegisterManager {
-        &self.register_manager
-    }
+        // 临时执行移动来计算终点
+        let mut temp_cursor = self.cursor;
+        motion.execute_in(&mut temp_cursor, self.current_buffer(), &self.viewport());
 
-    pub fn register_manager_mut(&mut self) -> &mut RegisterManager {
-        &mut self.register_manager
+        let end_idx = {
+            let buffer = self.current_buffer();
+            temp_cursor.to_char_idx(buffer)
+        };
+
+        if start_idx == end_idx {
+            return;
+        }
+
+        let (start, end) = if start_idx < end_idx {
+            (start_idx, end_idx)
+        } else {
+            (end_idx, start_idx)
+        };
+
+        let yanked = self.current_buffer().slice(start..end).to_string();
+
+        // 存入指定的寄存器和无名寄存器（复制操作）
+        self.store_operator_text(&yanked, false, register, false);
     }
 
-    /// 复制当前行到寄存器 (yy)
-    pub fn yank_line(&mut self, register: Option<char>) {
-        let line_idx = self.cursor.line;
-        if let Some(line_text) = self.get_line_text(line_idx) {
-            let content = format!("{}\n", line_text);
-            let reg = register.unwrap_or('"');
-            self.register_manager.set(reg, content, true);
+    // ==================== 操作符 + 动作 ====================
+
+    /// 执行操作符命令（如 d3w、"ayy、c$、>ap）
+    ///
+    /// 返回被删除或复制的文本，范围为空时返回 None
+    pub fn execute_operator(&mut self, command: OperatorCommand) -> Option<String> {
+        if matches!(command.target, OperatorTarget::Motion(Motion::SearchNext | Motion::SearchPrev)) {
+            let buffer = self.buffers.get(&self.current_buffer).unwrap();
+            self.search_state.refresh(buffer);
+            if self.search_state.matches.is_empty() {
+                self.set_message(tr!(self.locale, "Pattern not found"));
+                return None;
+            }
+        }
+        let (start, end, linewise) = self.operator_range(command)?;
+
+        match command.operator {
+            Operator::Delete => self.delete_range(start, end, linewise, command.register),
+            Operator::Yank => self.yank_range(start, end, linewise, command.register),
+            Operator::Change => self.change_range(start, end, linewise, command.register),
+            Operator::Indent | Operator::Outdent => {
+                let first_line = self.current_buffer().char_to_line(start);
+                let last_line = self.current_buffer().char_to_line(end.saturating_sub(1).max(start));
+                self.shift_lines(first_line, last_line, command.operator == Operator::Indent);
+                None
+            }
+            Operator::Fold => {
+                let (first_line, last_line) = self.operator_lines(start, end, linewise);
+                if let Err(e) = self.create_fold(first_line, last_line) {
+                    self.set_message(e);
+                }
+                None
+            }
+            Operator::Surround(ch) => {
+                if let Err(e) = self.surround_range(start, end, linewise, ch) {
+                    self.set_message(e);
+                }
+                None
+            }
+            Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => {
+                self.change_case_range(start, end, command.operator);
+                None
+            }
+            Operator::Format => {
+                let (first_line, last_line) = self.operator_lines(start, end, linewise);
+                self.format_lines(first_line, last_line);
+                None
+            }
         }
     }
 
-    /// 复制选中区域到寄存器 (y)
-    pub fn yank(&mut self, start: usize, end: usize, register: Option<char>, linewise: bool) {
+    /// 操作符范围覆盖的第一行和最后一行
+    fn operator_lines(&self, start: usize, end: usize, linewise: bool) -> (usize, usize) {
         let buffer = self.current_buffer();
-        let content = buffer.slice(start..end).to_string();
-        let reg = register.unwrap_or('"');
-        self.register_manager.set(reg, content, linewise);
+        let mut first_line = buffer.char_to_line(start);
+        let last_line = buffer.char_to_line(end.saturating_sub(1).max(start));
+        // 最后一行没有换行符时，整行范围从前一行的换行符开始（见 line_range）
+        if linewise && first_line < last_line && buffer.char(start) == '\n' && end == buffer.len_chars()
+            && !buffer.slice(start + 1..end).chars().any(|c| c == '\n')
+        {
+            first_line += 1;
+        }
+        (first_line, last_line)
     }
 
-    /// 粘贴寄存器内容 (p/P)
-    pub fn paste(&mut self, register: Option<char>, before_cursor: bool) {
-        let reg = register.unwrap_or('"');
+    /// 计算操作符作用的范围：(起始字符索引, 结束字符索引, 是否整行)
+    fn operator_range(&self, command: OperatorCommand) -> Option<(usize, usize, bool)> {
+        let buffer = self.current_buffer();
+        let count = command.count.max(1);
 
-        // 先获取寄存器内容，避免借用冲突
-        let (content, linewise) = {
-            if let Some(register) = self.register_manager.get(reg) {
-                if register.is_empty() {
-                    return;
+        match command.target {
+            OperatorTarget::Line => {
+                let last_line = (self.cursor.line + count - 1).min(buffer.len_lines().saturating_sub(1));
+                let (first_line, last_line) = self.fold_line_span(self.cursor.line, last_line);
+                Some(self.line_range(first_line, last_line))
+            }
+            OperatorTarget::TextObject(obj) => {
+                let (start, end) = obj.get_range(&self.cursor, buffer)?;
+                // 按语法树查找的文本对象覆盖整行时按行操作（如 daf 删除整个函数）
+                if obj.is_syntax() && crate::syntax::covers_lines(buffer, start, end) {
+                    return Some(self.line_range(buffer.char_to_line(start), buffer.char_to_line(end - 1)));
                 }
-                (register.content.clone(), register.linewise)
-            } else {
-                return;
+                Some((start, end, obj.is_linewise()))
             }
-        };
-
-        with_save_state!(self, {
-            if linewise {
-                // 整行粘贴
-                let insert_line = if before_cursor {
-                    self.cursor.line
+            OperatorTarget::Motion(motion) => {
+                // cw 与 ce 相同：光标在单词上时不包含单词后的空白
+                let motion = if command.operator == Operator::Change
+                    && matches!(motion, Motion::WordForward | Motion::WORDForward)
+                    && self.cursor_on_word_char()
+                {
+                    Motion::WordEnd
                 } else {
-                    self.cursor.line + 1
+                    motion
                 };
 
-                let char_idx = self.current_buffer().line_to_char(insert_line);
-                let buffer = self.current_buffer_mut();
-                buffer.insert(char_idx, &content);
+                let search = matches!(motion, Motion::SearchNext | Motion::SearchPrev);
+                let target = if search {
+                    self.search_target(count, motion == Motion::SearchPrev)?
+                } else {
+                    let viewport = self.viewport();
+                    let mut target = self.cursor;
+                    for _ in 0..count {
+                        motion.execute_in(&mut target, buffer, &viewport);
+                    }
+                    target
+                };
+                let offset = self.search_state.offset;
+                let linewise = motion.is_linewise() || (search && matches!(offset, SearchOffset::Line(_)));
+                let inclusive = motion.is_inclusive() || (search && matches!(offset, SearchOffset::End(_)));
+
+                if linewise {
+                    let first_line = self.cursor.line.min(target.line);
+                    let last_line = self.cursor.line.max(target.line);
+                    let (first_line, last_line) = self.fold_line_span(first_line, last_line);
+                    return Some(self.line_range(first_line, last_line));
+                }
 
-                // 移动光标到新粘贴的第一行
-                self.cursor.line = insert_line;
-                self.cursor.column = 0;
-            } else {
-                // 字符粘贴
-                let cursor_line = self.cursor.line;
-                let cursor_col = self.cursor.column;
+                let cursor_idx = self.cursor.to_char_idx(buffer);
+                let target_idx = target.to_char_idx(buffer);
+                let (start, mut end) = if cursor_idx <= target_idx {
+                    (cursor_idx, target_idx)
+                } else {
+                    (target_idx, cursor_idx)
+                };
 
-                let insert_idx = {
-                    let buffer = self.current_buffer();
-                    let line_start = buffer.line_to_char(cursor_line);
-                    let line_len = buffer.line_len(cursor_line);
-                    // line_len 包括换行符，实际文本长度是 line_len - 1
-                    let text_len = line_len.saturating_sub(1);
-                    
-                    if before_cursor {
-                        // P - 在光标位置之前粘贴
-                        // cursor_col 应该限制在文本范围内
-                        line_start + cursor_col.min(text_len)
-                    } else {
-                        // p - 在光标位置之后粘贴
-                        // 如果 cursor_col >= text_len，说明在行尾，在文本末尾粘贴
-                        // 否则在 cursor_col + 1 位置粘贴
-                        if cursor_col >= text_len {
-                            // 在行尾，在文本末尾（换行符前）粘贴
-                            line_start + text_len
-                        } else {
-                            // 在行中间，在当前字符后粘贴
-                            line_start + cursor_col + 1
-                        }
+                if inclusive {
+                    // 包含终点字符，但不包含行尾换行符
+                    let end_line = buffer.char_to_line(end);
+                    let line_content_end = buffer.line_to_char(end_line)
+                        + buffer.line(end_line).map(line_content_len).unwrap_or(0);
+                    end = (end + 1).min(line_content_end.max(end));
+                } else if matches!(motion, Motion::WordForward | Motion::WORDForward) {
+                    // dw 在行尾的最后一个单词上时不删除换行符
+                    if let Some(newline) = buffer.slice(start..end).chars().position(|c| c == '\n') {
+                        end = start + newline;
                     }
-                };
+                }
 
-                let buffer = self.current_buffer_mut();
-                buffer.insert(insert_idx, &content);
+                if start == end {
+                    return None;
+                }
+                Some((start, end, false))
+            }
+        }
+    }
 
-                // 移动光标到粘贴内容之后
-                let content_len = content.chars().count();
-                let new_line_len = buffer.line_len(cursor_line);
-                self.cursor.column = (cursor_col + content_len).min(new_line_len.saturating_sub(1));
+    /// 整行范围（包含最后一行的换行符）
+    fn line_range(&self, first_line: usize, last_line: usize) -> (usize, usize, bool) {
+        let buffer = self.current_buffer();
+        let mut start = buffer.line_to_char(first_line);
+        let end = if last_line + 1 < buffer.len_lines() {
+            buffer.line_to_char(last_line + 1)
+        } else {
+            buffer.len_chars()
+        };
+        // 最后一行没有换行符时，连同前一行的换行符一起删除
+        if end == buffer.len_chars() && start > 0 && !buffer.slice(start..end).chars().any(|c| c == '\n') {
+            start -= 1;
+        }
+        (start, end, true)
+    }
+
+    fn cursor_on_word_char(&self) -> bool {
+        let buffer = self.current_buffer();
+        let idx = self.cursor.to_char_idx(buffer);
+        idx < buffer.len_chars() && {
+            let c = buffer.char(idx);
+            c.is_alphanumeric() || c == '_'
+        }
+    }
+
+    /// 将操作符结果写入寄存器（"_ 为黑洞寄存器，不保存任何内容）
+    ///
+    /// 大写寄存器名表示追加，追加后无名寄存器指向整个寄存器的内容
+    fn store_operator_text(&mut self, text: &str, linewise: bool, register: Option<char>, is_delete: bool) {
+        if register == Some('_') {
+            return;
+        }
+        if let Some(reg) = register {
+            self.register_manager.set(reg, text, linewise);
+            if reg.is_ascii_uppercase() {
+                if let Some(appended) = self.register_manager.get(reg) {
+                    self.register_manager.set_unnamed(appended.content, appended.linewise, is_delete);
+                    return;
+                }
             }
-        });
+        }
+        self.register_manager.set_unnamed(text, linewise, is_delete);
     }
 
-    /// 删除当前行并放入寄存器 (dd)
-    pub fn delete_line(&mut self, register: Option<char>) -> Option<String> {
-        let line_idx = self.cursor.line;
-        let line_text = self.get_line_text(line_idx)?;
-        let content = format!("{}\n", line_text);
+    /// 整行操作的寄存器内容总是以换行符结尾
+    fn register_text(&self, start: usize, end: usize, linewise: bool) -> String {
+        let mut text = self.current_buffer().slice(start..end).to_string();
+        if linewise {
+            if text.starts_with('\n') && !text.ends_with('\n') {
+                text.remove(0);
+            }
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        text
+    }
 
+    fn delete_range(&mut self, start: usize, end: usize, linewise: bool, register: Option<char>) -> Option<String> {
+        let text = self.register_text(start, end, linewise);
         with_save_state!(self, {
-            // 保存到寄存器
-            let reg = register.unwrap_or('"');
-            self.register_manager.set(reg, &content, true);
-
-            // 删除行
-            let line_start = self.current_buffer().line_to_char(line_idx);
-            let line_len = self.current_buffer().line_len(line_idx);
-            let buffer = self.current_buffer_mut();
-            buffer.remove(line_start, line_len);
+            self.current_buffer_mut().remove(start, end - start);
+            self.store_operator_text(&text, linewise, register, true);
 
-            // 确保至少有一行
-            if buffer.len_lines() == 0 {
-                buffer.insert(0, "\n");
+            let buffer = self.buffers.get(&self.current_buffer).unwrap();
+            let idx = start.min(buffer.len_chars());
+            let mut cursor = Cursor::from_char_idx(buffer, idx);
+            if linewise {
+                cursor.line = buffer.char_to_line(idx);
+                cursor.move_to_first_non_blank(buffer);
             }
+            self.cursor = cursor;
+            Some(text)
+        })
+    }
 
-            // 调整光标位置
-            let max_line = buffer.len_lines().saturating_sub(1);
-            self.cursor.line = self.cursor.line.min(max_line);
-            self.cursor.column = 0;
+    fn yank_range(&mut self, start: usize, end: usize, linewise: bool, register: Option<char>) -> Option<String> {
+        let text = self.register_text(start, end, linewise);
+        self.store_operator_text(&text, linewise, register, false);
 
-            Some(content)
+        // 复制后光标移到范围开头（整行复制时保持列不变）
+        let buffer = self.current_buffer();
+        if linewise {
+            self.cursor.line = self.cursor.line.min(buffer.char_to_line(start));
+        } else {
+            self.cursor = Cursor::from_char_idx(buffer, start);
+        }
+        Some(text)
+    }
+
+    fn change_range(&mut self, start: usize, mut end: usize, linewise: bool, register: Option<char>) -> Option<String> {
+        let text = self.register_text(start, end, linewise);
+        let mut start = start;
+        if linewise {
+            // cc 保留一个空行用于输入
+            let buffer = self.current_buffer();
+            if buffer.char(start) == '\n' && start + 1 < end {
+                start += 1;
+            }
+            if end > start && buffer.char(end - 1) == '\n' {
+                end -= 1;
+            }
+        }
+        with_save_state!(self, {
+            self.current_buffer_mut().remove(start, end - start);
+            self.store_operator_text(&text, linewise, register, true);
+            self.cursor = Cursor::from_char_idx(self.current_buffer(), start);
+            // 直接切换模式：删除前已保存状态，一次撤销即可恢复
+            self.mode = Mode::Insert;
+            self.emit_event(Event::InsertEnter, self.current_buffer);
+            Some(text)
         })
     }
 
-    /// 删除字符并放入寄存器 (x/d)
-    pub fn delete_char_to_register(&mut self, register: Option<char>) -> Option<char> {
+    /// 增加或减少多行的缩进（> 和 <），每次移动 shiftwidth 个空格
+    ///
+    /// 整个操作作为一次撤销单元；减少缩进时行首的一个 Tab 视为一级缩进。
+    /// noexpandtab 时增加缩进后按 tabstop 把行首的空白重写为制表符加空格
+    pub fn shift_lines(&mut self, first_line: usize, last_line: usize, indent: bool) {
+        let options = self.current_options();
+        let shiftwidth = options.shiftwidth.max(1);
+        let tabstop = options.tabstop.max(1);
+        let expandtab = options.expandtab;
+        let indent_text = " ".repeat(shiftwidth);
+
+        with_save_state!(self, {
+            for line_idx in first_line..=last_line {
+                let Some(line) = self.current_buffer().line(line_idx) else {
+                    break;
+                };
+                let line_start = self.current_buffer().line_to_char(line_idx);
+                if indent {
+                    // 空行不缩进
+                    if line_content_len(line) == 0 {
+                        continue;
+                    }
+                    if expandtab {
+                        self.current_buffer_mut().insert(line_start, &indent_text);
+                    } else {
+                        let blanks: String = line.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+                        let width = display::display_column(&blanks, blanks.len(), tabstop) + shiftwidth;
+                        let new_indent = format!("{}{}", "\t".repeat(width / tabstop), " ".repeat(width % tabstop));
+                        let buffer = self.current_buffer_mut();
+                        buffer.remove(line_start, blanks.len());
+                        buffer.insert(line_start, &new_indent);
+                    }
+                } else {
+                    let remove = if line.chars().next() == Some('\t') {
+                        1
+                    } else {
+                        line.chars().take(shiftwidth).take_while(|c| *c == ' ').count()
+                    };
+                    if remove > 0 {
+                        self.current_buffer_mut().remove(line_start, remove);
+                    }
+                }
+            }
+
+            self.cursor.line = first_line;
+            let buffer = self.buffers.get(&self.current_buffer).unwrap();
+            self.cursor.move_to_first_non_blank(buffer);
+        });
+    }
+
+    /// 转换范围内文本的大小写（gu、gU、g~），光标移到范围的开头
+    fn change_case_range(&mut self, start: usize, end: usize, operator: Operator) {
+        let text = self.current_buffer().slice(start..end).to_string();
+        let changed: String = text
+            .chars()
+            .flat_map(|c| {
+                let upper = match operator {
+                    Operator::Uppercase => true,
+                    Operator::ToggleCase => c.is_lowercase(),
+                    _ => false,
+                };
+                let converted: Vec<char> =
+                    if upper { c.to_uppercase().collect() } else { c.to_lowercase().collect() };
+                converted
+            })
+            .collect();
+        if changed == text {
+            self.cursor = Cursor::from_char_idx(self.current_buffer(), start);
+            self.update_preferred_column();
+            return;
+        }
+        with_save_state!(self, {
+            let buffer = self.current_buffer_mut();
+            buffer.remove(start, end - start);
+            buffer.insert(start, &changed);
+            self.cursor = Cursor::from_char_idx(self.current_buffer(), start);
+            self.update_preferred_column();
+        });
+    }
+
+    /// 按宽度重排多行文本 (gq)
+    ///
+    /// 空行分隔段落，每个段落的单词重新填入不超过 textwidth（为 0 时 79）列的行，保留段落第一行的缩进；
+    /// 整个操作作为一次撤销单元，光标移到重排后最后一行的第一个非空白字符
+    pub fn format_lines(&mut self, first_line: usize, last_line: usize) {
+        let tabstop = self.current_options().tabstop.max(1);
+        let text_width = match self.options.textwidth {
+            0 => FORMAT_WIDTH,
+            width => width,
+        };
+        let buffer = self.current_buffer();
+        let last_line = last_line.min(buffer.len_lines().saturating_sub(1));
+        let lines: Vec<String> = (first_line..=last_line).filter_map(|idx| buffer.line_text(idx)).collect();
+
+        let mut formatted: Vec<String> = Vec::new();
+        let mut paragraph: Vec<&str> = Vec::new();
+        let mut indent = "";
+        for line in lines.iter().map(String::as_str).chain(std::iter::once("")) {
+            if !line.trim().is_empty() {
+                if paragraph.is_empty() {
+                    indent = &line[..line.len() - line.trim_start().len()];
+                }
+                paragraph.extend(line.split_whitespace());
+                continue;
+            }
+            let indent_width = display::display_column(indent, indent.chars().count(), tabstop);
+            let mut current = String::new();
+            let mut width = 0;
+            for word in paragraph.drain(..) {
+                let word_width = display::display_column(word, word.chars().count(), tabstop);
+                if !current.is_empty() && indent_width + width + 1 + word_width > text_width {
+                    formatted.push(format!("{}{}", indent, current));
+                    current.clear();
+                    width = 0;
+                }
+                if !current.is_empty() {
+                    current.push(' ');
+                    width += 1;
+                }
+                current.push_str(word);
+                width += word_width;
+            }
+            if !current.is_empty() {
+                formatted.push(format!("{}{}", indent, current));
+            }
+            formatted.push(line.to_string());
+        }
+        // 去掉末尾为结束最后一个段落加入的空行
+        formatted.pop();
+        if formatted == lines {
+            self.cursor.line = first_line + formatted.len().saturating_sub(1);
+            self.cursor.move_to_first_non_blank(self.buffers.get(&self.current_buffer).unwrap());
+            return;
+        }
+
         with_save_state!(self, {
-            // 先获取光标位置信息
-            let char_idx = {
-                let buffer = self.current_buffer();
-                self.cursor.to_char_idx(buffer)
-            };
-            let cursor_line = self.cursor.line;
-            let cursor_col = self.cursor.column;
-
             let buffer = self.current_buffer_mut();
-            let ch = buffer.remove_char(char_idx)?;
-
-            // 确保光标有效
-            let max_line = buffer.len_lines().saturating_sub(1);
-            let new_line = cursor_line.min(max_line);
-            let line_len = buffer.line_len(new_line);
-            let max_col = line_len.saturating_sub(1);
-            let new_col = cursor_col.min(max_col);
+            let start = buffer.line_to_char(first_line);
+            let end = start + lines.iter().map(|line| line.chars().count() + 1).sum::<usize>() - 1;
+            buffer.remove(start, end - start);
+            buffer.insert(start, &formatted.join("\n"));
 
-            self.cursor.line = new_line;
-            self.cursor.column = new_col;
+            self.cursor.line = first_line + formatted.len().saturating_sub(1);
+            let buffer = self.buffers.get(&self.current_buffer).unwrap();
+            self.cursor.move_to_first_non_blank(buffer);
+        });
+    }
 
-            // 最后设置寄存器
-            let reg = register.unwrap_or('"');
-            self.register_manager.set(reg, ch.to_string(), false);
+    // ==================== Visual 模式 ====================
 
-            Some(ch)
-        })
+    /// 当前的选择区域，不在 Visual 模式时为 None
+    pub fn visual_selection(&self) -> Option<VisualSelection> {
+        let (anchor, linewise) = self.visual_start.filter(|_| self.mode == Mode::Visual)?;
+        Some(VisualSelection::new(anchor, self.cursor, linewise))
     }
 
-    // ==================== 搜索功能 ====================
-
-    pub fn search_state(&self) -> &SearchState {
-        &self.search_state
+    /// 当前缓冲区最后的选择区域 (gv)
+    pub fn last_visual_selection(&self) -> Option<VisualSelection> {
+        self.last_visual.get(&self.current_buffer).copied()
     }
 
-    /// 开始搜索（/ 或 ?）
-    pub fn start_search(&mut self, direction: SearchDirection, pattern: impl Into<String>) {
-        let pattern = pattern.into();
-        if !pattern.is_empty() {
-            // 先设置搜索模式
-            let buffer_clone = {
-                let buffer = self.current_buffer();
-                buffer.clone()
-            };
-            self.search_state.set_pattern(&pattern, direction, &buffer_clone);
-            
-            // 保存到搜索寄存器
-            self.register_manager.set_search(&pattern);
-            
-            // 跳转到第一个匹配（从光标位置开始）
-            let (idx, pos) = {
-                let buffer = self.current_buffer();
-                // 使用 calc_first_match 找当前位置或之后的第一个匹配
-                let idx = self.search_state.calc_first_match(&self.cursor, buffer);
-                let pos = idx.and_then(|i| self.search_state.get_match_pos(i));
-                (idx, pos)
-            };
-            if let (Some(i), Some(p)) = (idx, pos) {
-                self.search_state.set_current_match(i);
-                let buffer = self.current_buffer();
-                self.cursor = Cursor::from_char_idx(buffer, p);
+    /// v / V：Normal 模式下从光标处开始选择；Visual 模式下按另一个键切换按字符 / 按行选择，
+    /// 按相同的键回到 Normal 模式
+    pub fn toggle_visual(&mut self, linewise: bool) {
+        match self.visual_selection() {
+            Some(selection) if selection.linewise == linewise => self.exit_visual(),
+            Some(selection) => self.visual_start = Some((selection.anchor, linewise)),
+            None => {
+                self.multi_cursor = None;
+                self.visual_start = Some((self.cursor, linewise));
+                self.set_mode(Mode::Visual);
             }
         }
     }
 
-    /// 搜索下一个（n）
-    pub fn search_next(&mut self) {
-        let (idx, pos) = {
-            let buffer = self.current_buffer();
-            let idx = self.search_state.calc_next_match(&self.cursor, buffer);
-            let pos = idx.and_then(|i| self.search_state.get_match_pos(i));
-            (idx, pos)
-        };
-        if let (Some(i), Some(p)) = (idx, pos) {
-            self.search_state.set_current_match(i);
-            let buffer = self.current_buffer();
-            self.cursor = Cursor::from_char_idx(buffer, p);
+    /// 回到 Normal 模式，记住选择区域供 gv 使用
+    pub fn exit_visual(&mut self) {
+        if let Some(selection) = self.visual_selection() {
+            self.last_visual.insert(self.current_buffer, selection);
         }
-    }
-
-    /// 搜索上一个（N）
-    pub fn search_prev(&mut self) {
-        let (idx, pos) = {
-            let buffer = self.current_buffer();
-            let idx = self.search_state.calc_prev_match(&self.cursor, buffer);
-            let pos = idx.and_then(|i| self.search_state.get_match_pos(i));
-            (idx, pos)
-        };
-        if let (Some(i), Some(p)) = (idx, pos) {
-            self.search_state.set_current_match(i);
-            let buffer = self.current_buffer();
-            self.cursor = Cursor::from_char_idx(buffer, p);
+        self.visual_start = None;
+        if self.mode == Mode::Visual {
+            self.set_mode(Mode::Normal);
         }
+        self.cursor = clamp_cursor(self.current_buffer(), self.cursor);
     }
 
-    /// 清除搜索高亮
-    pub fn clear_search(&mut self) {
-        self.search_state.clear();
+    /// 交换选择区域的锚点和光标 (Visual 模式下的 o / O)，选中的范围不变
+    pub fn swap_visual_ends(&mut self) {
+        if let Some(selection) = self.visual_selection() {
+            self.visual_start = Some((selection.cursor, selection.linewise));
+            self.cursor = selection.anchor;
+            self.update_preferred_column();
+        }
     }
 
-    // ==================== 范围操作 ====================
-
-    /// 删除从当前位置到目标位置的文本
-    pub fn delete_to_motion(&mut self, motion: Motion) -> Option<String> {
-        self.delete_to_motion_with_register(motion, None)
+    /// 重新选中当前缓冲区最后的选择区域 (gv)，Visual 模式下与当前的选择区域交换
+    pub fn reselect_visual(&mut self) -> Result<(), String> {
+        let previous = self
+            .last_visual_selection()
+            .ok_or_else(|| tr!(self.locale, "No previous Visual selection"))?;
+        if let Some(current) = self.visual_selection() {
+            self.last_visual.insert(self.current_buffer, current);
+        }
+        let selection = previous.clamp(self.current_buffer());
+        self.visual_start = Some((selection.anchor, selection.linewise));
+        self.cursor = selection.cursor;
+        self.update_preferred_column();
+        if self.mode != Mode::Visual {
+            self.set_mode(Mode::Visual);
+        }
+        Ok(())
     }
 
-    /// 删除从当前位置到目标位置的文本（支持指定寄存器）
-    pub fn delete_to_motion_with_register(&mut self, motion: Motion, register: Option<char>) -> Option<String> {
-        with_save_state!(self, {
-            let start_cursor = self.cursor;
-            let start_idx = {
-                let buffer = self.current_buffer();
-                self.cursor.to_char_idx(buffer)
-            };
-
-            // 临时执行移动来计算终点（不改变实际光标）
-            let mut temp_cursor = self.cursor;
-            motion.execute(&mut temp_cursor, self.current_buffer());
-
-            let mut end_idx = {
-                let buffer = self.current_buffer();
-                temp_cursor.to_char_idx(buffer)
-            };
-
-            if start_idx == end_idx {
-                return None;
-            }
-
-            // 对于单词移动，检查是否跨越了换行符
-            // 如果是，截断到当前行的末尾，不删除换行符
-            if matches!(motion, Motion::WordForward | Motion::WordBackward) {
-                let buffer = self.current_buffer();
-                let text = buffer.rope().to_string();
-                
-                // 确保 start_idx < end_idx
-                let (start, end) = if start_idx < end_idx {
-                    (start_idx, end_idx)
+    /// 对选择区域执行操作符（d y c > < u U ~ gq S{char}）并回到 Normal 模式，整个操作作为一次撤销单元
+    ///
+    /// `>` / `<` 和 `gq` 作用于选中的行，大小写和包围作用于选中的字符（按行选择时为各行的文本）；
+    /// 按行选择时 `d` / `y` / `c` 与 `dd` / `yy` / `cc` 一样按整行操作寄存器 `register`
+    pub fn visual_operator(&mut self, operator: Operator, register: Option<char>) {
+        let Some(selection) = self.visual_selection() else {
+            return;
+        };
+        self.exit_visual();
+        let (first_line, last_line) = selection.lines();
+        match operator {
+            Operator::Delete | Operator::Yank | Operator::Change => {
+                let (start, end, linewise) = if selection.linewise {
+                    self.line_range(first_line, last_line)
                 } else {
-                    (end_idx, start_idx)
+                    let (start, end) = selection.char_range(self.current_buffer());
+                    (start, end, false)
                 };
-                
-                // 检查删除范围内是否有换行符
-                if let Some(newline_pos) = text[start..end].find('\n') {
-                    // 有换行符，截断到换行符之前
-                    end_idx = start + newline_pos;
+                if start == end && !linewise {
+                    return;
+                }
+                match operator {
+                    Operator::Delete => {
+                        self.delete_range(start, end, linewise, register);
+                    }
+                    Operator::Yank => {
+                        self.yank_range(start, end, linewise, register);
+                        // 复制后光标回到选择区域的开头
+                        self.cursor = selection.start();
+                    }
+                    _ => {
+                        self.change_range(start, end, linewise, register);
+                    }
                 }
             }
-
-            if start_idx == end_idx {
-                return None;
+            Operator::Indent | Operator::Outdent => {
+                self.shift_lines(first_line, last_line, operator == Operator::Indent);
             }
-
-            let (start, end, is_forward) = if start_idx < end_idx {
-                (start_idx, end_idx, true)
-            } else {
-                (end_idx, start_idx, false)
-            };
-
-            let deleted = {
-                let buffer = self.current_buffer();
-                let text = buffer.rope().to_string();
-                text[start..end].to_string()
-            };
-
-            // 删除文本
-            {
-                let buffer = self.current_buffer_mut();
-                buffer.remove(start, end - start);
+            Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => {
+                let (start, end) = selection.char_range(self.current_buffer());
+                self.change_case_range(start, end, operator);
             }
-
-            // 如果指定了寄存器，存入命名寄存器
-            if let Some(reg) = register {
-                self.register_manager.set(reg, &deleted, false);
+            Operator::Format => self.format_lines(first_line, last_line),
+            Operator::Surround(ch) => {
+                let (start, end) = selection.char_range(self.current_buffer());
+                if let Err(e) = self.surround_range(start, end, selection.linewise, ch) {
+                    self.set_message(e);
+                }
             }
+            _ => {}
+        }
+    }
 
-            // 同时存入无名寄存器（删除操作）
-            self.register_manager.set_unnamed_delete(&deleted, false);
+    /// 用寄存器的内容替换选择区域 (Visual 模式下的 p / P) 并回到 Normal 模式，整个操作作为一次撤销单元
+    ///
+    /// 被替换的文本放入无名寄存器；整行内容替换按字符的选择时单独成行，
+    /// 按字符的内容替换选中的行时作为一行
+    pub fn visual_put(&mut self, register: Option<char>) {
+        let Some(selection) = self.visual_selection() else {
+            return;
+        };
+        self.exit_visual();
+        let Some((content, content_linewise)) = self
+            .register_manager
+            .get(register.unwrap_or('"'))
+            .filter(|register| !register.is_empty())
+            .map(|register| (register.content.clone(), register.linewise))
+        else {
+            return;
+        };
 
-            // 设置光标位置：
-            // - 向前删除（dw, dl）：保持在原位置
-            // - 向后删除（db, dh）：移动到删除区域的开头
-            if is_forward {
-                self.cursor = start_cursor;
+        let buffer = self.current_buffer();
+        let (first_line, last_line) = selection.lines();
+        let (start, end) = if selection.linewise {
+            let end = if last_line + 1 < buffer.len_lines() {
+                buffer.line_to_char(last_line + 1)
             } else {
-                // 向后删除，光标已经在正确的位置（start）
-                self.cursor = Cursor::from_char_idx(self.current_buffer(), start);
+                buffer.len_chars()
+            };
+            (buffer.line_to_char(first_line), end)
+        } else {
+            selection.char_range(buffer)
+        };
+        let replaced = self.register_text(start, end, selection.linewise);
+        let ends_with_newline = end > start && buffer.char(end - 1) == '\n';
+
+        let text = match (selection.linewise, content_linewise) {
+            (false, false) => content,
+            (false, true) => format!("\n{}", content),
+            (true, linewise) => {
+                let mut text = content;
+                if !linewise {
+                    text.push('\n');
+                }
+                // 选中的最后一行没有换行符时替换的文本也不以换行符结尾
+                if !ends_with_newline {
+                    text.pop();
+                }
+                text
             }
+        };
 
-            Some(deleted)
-        })
+        with_save_state!(self, {
+            let buffer = self.current_buffer_mut();
+            buffer.remove(start, end - start);
+            buffer.insert(start, &text);
+            self.store_operator_text(&replaced, selection.linewise, None, true);
+
+            let buffer = self.buffers.get(&self.current_buffer).unwrap();
+            if selection.linewise || content_linewise {
+                let line = buffer.char_to_line(start) + usize::from(!selection.linewise);
+                self.cursor = Cursor::new(line.min(buffer.last_content_line()), 0);
+                self.cursor.move_to_first_non_blank(buffer);
+            } else {
+                let idx = (start + text.chars().count()).saturating_sub(1).max(start);
+                self.cursor = Cursor::from_char_idx(buffer, idx.min(buffer.len_chars()));
+            }
+        });
     }
 
-    /// 复制从当前位置到目标位置的文本
-    pub fn yank_to_motion(&mut self, motion: Motion) {
-        self.yank_to_motion_with_register(motion, None);
+    /// 合并选中的行 (Visual 模式下的 J) 并回到 Normal 模式，只选中一行时与下一行合并
+    pub fn visual_join(&mut self) {
+        let Some(selection) = self.visual_selection() else {
+            return;
+        };
+        self.exit_visual();
+        let (first_line, last_line) = selection.lines();
+        self.join_line_range(first_line, last_line, true);
     }
 
-    /// 复制从当前位置到目标位置的文本（支持指定寄存器）
-    pub fn yank_to_motion_with_register(&mut self, motion: Motion, register: Option<char>) {
-        let start_idx = {
-            let buffer = self.current_buffer();
-            self.cursor.to_char_idx(buffer)
+    /// 选择区域在当前窗口文本区域中占据的单元格（列, 行），界面用于高亮选择区域
+    ///
+    /// 空行显示为一个单元格；关闭的折叠中的行和不在窗口中的部分不包括在内
+    pub fn visual_cells(&self, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let Some(selection) = self.visual_selection() else {
+            return Vec::new();
         };
+        let buffer = self.current_buffer();
+        let options = self.current_options();
+        let tabstop = options.tabstop;
+        let left = self.buffer_left_column(buffer, &options, self.cursor, width);
+        let (first_line, last_line) = selection.lines();
+        let mut cells = Vec::new();
+        for line in first_line.max(self.scroll_offset())..=last_line {
+            if buffer.folds().closed_range(line).is_some() {
+                continue;
+            }
+            let chars: Vec<char> = buffer.line_text(line).unwrap_or_default().chars().collect();
+            let Some((start, end)) = selection.columns(line, chars.len()) else {
+                continue;
+            };
+            let columns = if chars.is_empty() { 0..1 } else { start..end };
+            for column in columns {
+                let cursor = Cursor::new(line, column);
+                let Some((x, y)) = self.buffer_cursor_position(buffer, &options, cursor, width, self.scroll_offset())
+                else {
+                    continue;
+                };
+                if y >= height {
+                    return cells;
+                }
+                let display_column = buffer_display_column(buffer, cursor, tabstop);
+                let x = if options.wrap {
+                    x
+                } else {
+                    let Some(x) = display_column.checked_sub(left) else {
+                        continue;
+                    };
+                    x
+                };
+                let cell_width = chars.get(column).map_or(1, |c| display::char_width(*c, display_column, tabstop).max(1));
+                cells.extend((x..x + cell_width).filter(|x| *x < width).map(|x| (x, y)));
+            }
+        }
+        cells
+    }
 
-        // 临时执行移动来计算终点
-        let mut temp_cursor = self.cursor;
-        motion.execute(&mut temp_cursor, self.current_buffer());
+    // ==================== 多光标 ====================
 
-        let end_idx = {
-            let buffer = self.current_buffer();
-            temp_cursor.to_char_idx(buffer)
-        };
+    /// 主光标之外的其他光标，切换到其他缓冲区后为空
+    pub fn extra_cursors(&self) -> &[Cursor] {
+        match &self.multi_cursor {
+            Some(multi) if multi.buffer() == self.current_buffer => multi.cursors(),
+            _ => &[],
+        }
+    }
 
-        if start_idx == end_idx {
-            return;
+    /// 在光标下单词的下一个出现位置添加光标 (Normal 模式 Ctrl+N)，新光标成为主光标
+    ///
+    /// 第一次添加时主光标先移到单词的开头；所有出现位置都已经有光标时提示没有更多匹配
+    pub fn add_cursor_at_next_match(&mut self) -> Result<(), String> {
+        let text = self.current_buffer().line_text(self.cursor.line).unwrap_or_default();
+        let (start, word) = crate::search::word_under_cursor(&text, self.cursor.column)
+            .ok_or_else(|| tr!(self.locale, "No string under cursor"))?;
+        let mut cursors = self.extra_cursors().to_vec();
+        if cursors.is_empty() {
+            self.cursor.column = start;
+            self.update_preferred_column();
         }
 
-        let (start, end) = if start_idx < end_idx {
-            (start_idx, end_idx)
-        } else {
-            (end_idx, start_idx)
-        };
+        let buffer = self.current_buffer();
+        let primary = self.cursor.to_char_idx(buffer);
+        let mut taken: Vec<usize> = cursors.iter().map(|cursor| cursor.to_char_idx(buffer)).collect();
+        taken.push(primary);
+        let next = multi_cursor::next_word_match(buffer.rope(), &word, primary, &taken)
+            .ok_or_else(|| tr!(self.locale, "No more matches: {}", word))?;
+
+        cursors.push(self.cursor);
+        self.cursor = Cursor::from_char_idx(self.current_buffer(), next);
+        self.update_preferred_column();
+        self.multi_cursor = Some(MultiCursor::new(self.current_buffer, cursors));
+        Ok(())
+    }
 
-        let yanked = {
+    /// 回到单个光标（Normal 模式下的 Esc），没有其他光标时返回 false
+    pub fn clear_extra_cursors(&mut self) -> bool {
+        let cleared = !self.extra_cursors().is_empty();
+        self.multi_cursor = None;
+        cleared
+    }
+
+    /// 把每个光标依次作为主光标执行 `action`，没有其他光标时只执行一次
+    ///
+    /// 光标从缓冲区的末尾往前执行，修改使已经执行过的光标移动相同的字符数；
+    /// 所有光标的修改只保存一次撤销状态，执行之后位置相同的光标合并为一个
+    pub fn for_each_cursor(&mut self, mut action: impl FnMut(&mut Self)) {
+        if self.extra_cursors().is_empty() {
+            action(self);
+            return;
+        }
+        let buffer_id = self.current_buffer;
+        let extra = self.multi_cursor.take().map(MultiCursor::into_cursors).unwrap_or_default();
+        let buffer = self.current_buffer();
+        // （字符下标, 光标, 是否为主光标）
+        let mut cursors: Vec<(usize, Cursor, bool)> = extra
+            .into_iter()
+            .map(|cursor| (cursor, false))
+            .chain(std::iter::once((self.cursor, true)))
+            .map(|(cursor, primary)| (cursor.to_char_idx(buffer), cursor, primary))
+            .collect();
+        cursors.sort_by_key(|(position, _, _)| std::cmp::Reverse(*position));
+
+        self.save_state();
+        self.undo_grouped = true;
+        for idx in 0..cursors.len() {
+            let (position, cursor, primary) = cursors[idx];
+            self.cursor = cursor;
+            let len = self.current_buffer().len_chars();
+            // . 寄存器只记录主光标输入的文本
+            let inserted = (!primary).then(|| self.inserted_text.clone());
+            action(self);
+            if let Some(inserted) = inserted {
+                self.inserted_text = inserted;
+            }
+            // 命令切换了缓冲区时只保留主光标
+            if self.current_buffer != buffer_id {
+                self.undo_grouped = false;
+                return;
+            }
             let buffer = self.current_buffer();
-            let text = buffer.rope().to_string();
-            text[start..end].to_string()
-        };
+            let moved = self.cursor.to_char_idx(buffer);
+            cursors[idx] = (moved, self.cursor, primary);
+            let delta = buffer.len_chars() as isize - len as isize;
+            let edit = position.min(moved);
+            for done in cursors[..idx].iter_mut().filter(|done| delta != 0 && done.0 > edit) {
+                done.0 = done.0.saturating_add_signed(delta).max(edit);
+            }
+        }
+        self.undo_grouped = false;
 
-        // 如果指定了寄存器，存入命名寄存器
-        if let Some(reg) = register {
-            self.register_manager.set(reg, &yanked, false);
+        let buffer = self.buffers.get(&self.current_buffer).unwrap();
+        let mut extra: Vec<Cursor> = Vec::new();
+        let mut positions = Vec::new();
+        cursors.sort_by_key(|(_, _, primary)| !primary);
+        for (position, cursor, primary) in cursors {
+            let position = position.min(buffer.len_chars());
+            // 被前面的修改移动过的光标按新的位置重新计算行列
+            let mut moved = Cursor::from_char_idx(buffer, position);
+            if (moved.line, moved.column) == (cursor.line, cursor.column) {
+                moved = cursor;
+            }
+            if positions.contains(&position) {
+                continue;
+            }
+            positions.push(position);
+            if primary {
+                self.cursor = moved;
+            } else {
+                extra.push(moved);
+            }
         }
+        if !extra.is_empty() {
+            self.multi_cursor = Some(MultiCursor::new(buffer_id, extra));
+        }
+    }
 
-        // 同时存入无名寄存器（复制操作）
-        self.register_manager.set_unnamed_yank(&yanked, false);
+    /// 其他光标在当前窗口文本区域中的位置（列, 行），不在窗口中的光标不包括在内
+    pub fn extra_cursor_positions(&self, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let buffer = self.current_buffer();
+        let options = self.current_options();
+        let left = self.buffer_left_column(buffer, &options, self.cursor, width);
+        self.extra_cursors()
+            .iter()
+            .filter(|cursor| buffer.folds().closed_range(cursor.line).is_none())
+            .filter_map(|cursor| {
+                let (x, y) = self.buffer_cursor_position(buffer, &options, *cursor, width, self.scroll_offset())?;
+                // nowrap 时按主光标的水平滚动计算列
+                let x = if options.wrap {
+                    x
+                } else {
+                    buffer_display_column(buffer, *cursor, options.tabstop).checked_sub(left)?
+                };
+                (x < width && y < height).then_some((x, y))
+            })
+            .collect()
     }
 
     // ==================== 文本对象操作 ====================
@@ -1016,11 +7070,7 @@ impl Editor {
             let buffer = self.current_buffer();
             let (start, end) = obj.get_range(&self.cursor, buffer)?;
 
-            let deleted = {
-                let buffer = self.current_buffer();
-                let text = buffer.rope().to_string();
-                text[start..end].to_string()
-            };
+            let deleted = self.current_buffer().slice(start..end).to_string();
 
             // 删除文本
             let buffer = self.current_buffer_mut();
@@ -1042,11 +7092,7 @@ impl Editor {
         let buffer = self.current_buffer();
         let (start, end) = obj.get_range(&self.cursor, buffer)?;
 
-        let yanked = {
-            let buffer = self.current_buffer();
-            let text = buffer.rope().to_string();
-            text[start..end].to_string()
-        };
+        let yanked = self.current_buffer().slice(start..end).to_string();
 
         // 将复制的内容放入无名寄存器（复制操作）
         let is_linewise = false;
@@ -1055,6 +7101,54 @@ impl Editor {
         Some(yanked)
     }
 
+    // ==================== 宏 ====================
+
+    /// 正在录制宏的寄存器
+    pub fn recording_register(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// q{register} - 开始把输入的按键录制到寄存器（大写寄存器追加到原来的内容之后）
+    pub fn start_recording(&mut self, register: char) -> Result<(), String> {
+        if !register.is_ascii_alphanumeric() && register != '"' {
+            return Err(tr!(self.locale, "Invalid register: {}", register));
+        }
+        self.recording = Some((register, Vec::new()));
+        Ok(())
+    }
+
+    /// 录制一个按键（由按键处理在处理用户输入的按键之前调用）
+    pub fn record_key(&mut self, key: MapKey) {
+        if let Some((_, keys)) = self.recording.as_mut() {
+            keys.push(key);
+        }
+    }
+
+    /// 结束录制的 q：按键写入寄存器（不包括结束录制的 q 本身）
+    pub fn stop_recording(&mut self) {
+        let Some((register, mut keys)) = self.recording.take() else {
+            return;
+        };
+        if keys.last() == Some(&MapKey::Char('q')) {
+            keys.pop();
+        }
+        self.register_manager.set(register, mapping::format_keys(&keys), false);
+    }
+
+    /// @{register} - 寄存器中要执行的按键，`@@` 为上一次执行的宏
+    pub fn macro_keys(&mut self, register: char) -> Result<Vec<MapKey>, String> {
+        let register = match register {
+            '@' => self.last_macro.ok_or_else(|| tr!(self.locale, "No previously used register"))?,
+            register => register,
+        };
+        if !is_register_name(register) {
+            return Err(tr!(self.locale, "Invalid register: {}", register));
+        }
+        self.last_macro = Some(register);
+        let content = self.register_manager.get(register).map(|register| register.content).unwrap_or_default();
+        Ok(mapping::parse_keys(&content))
+    }
+
     // ==================== 寄存器显示 ====================
 
     /// 格式化所有寄存器内容用于显示
@@ -1066,17 +7160,17 @@ impl Editor {
         let registers = self.register_manager.get_all_registers();
 
         if registers.is_empty() {
-            return "No registers".to_string();
+            return tr!(self.locale, "No registers");
         }
 
         let mut output = String::new();
-        output.push_str("Registers:\n");
-        output.push_str("----------\n");
+        output.push_str(&tr!(self.locale, "Registers:"));
+        output.push_str("\n----------\n");
 
         for reg in registers {
             let name = reg.name;
             let content = &reg.content;
-            let linewise = if reg.linewise { " (linewise)" } else { "" };
+            let linewise = if reg.linewise { tr!(self.locale, " (linewise)") } else { String::new() };
 
             // 截断过长的内容
             let max_len = 80;
@@ -1103,11 +7197,9 @@ impl Editor {
         let mut result = Vec::new();
         
         for (id, buffer) in &self.buffers {
-            let name = buffer.file_path()
-                .and_then(|p| p.file_name())
-                .and_then(|n| n.to_str())
+            let name = buffer.display_name()
                 .map(|s| s.to_string())
-                .unwrap_or_else(|| format!("[缓冲区 {}]", id.as_usize()));
+                .unwrap_or_else(|| tr!(self.locale, "[Buffer {}]", id.as_usize()));
             
             let is_current = *id == self.current_buffer;
             result.push((*id, name, is_current));
@@ -1122,10 +7214,11 @@ impl Editor {
     pub fn format_buffer_list(&self) -> String {
         let buffers = self.list_buffers();
         if buffers.is_empty() {
-            return "没有缓冲区".to_string();
+            return tr!(self.locale, "No buffers");
         }
 
-        let mut output = String::from("缓冲区列表:\n");
+        let mut output = tr!(self.locale, "Buffer list:");
+        output.push('\n');
         output.push_str(&"-".repeat(40));
         output.push('\n');
 
@@ -1151,11 +7244,11 @@ impl Editor {
     /// 切换到指定缓冲区
     pub fn switch_buffer(&mut self, buffer_id: BufferId) -> Result<(), String> {
         if !self.buffers.contains_key(&buffer_id) {
-            return Err(format!("缓冲区 {} 不存在", buffer_id.as_usize()));
+            return Err(tr!(self.locale, "Buffer {} does not exist", buffer_id.as_usize()));
         }
 
         // 保存当前缓冲区的光标位置
-        self.buffer_cursors.insert(self.current_buffer, self.cursor.clone());
+        self.buffer_cursors.insert(self.current_buffer, self.cursor);
 
         // 切换缓冲区
         self.current_buffer = buffer_id;
@@ -1170,7 +7263,7 @@ impl Editor {
     pub fn next_buffer(&mut self) -> Result<(), String> {
         let mut buffer_ids: Vec<BufferId> = self.buffers.keys().cloned().collect();
         if buffer_ids.len() <= 1 {
-            return Err("没有其他缓冲区".to_string());
+            return Err(tr!(self.locale, "No other buffers"));
         }
 
         // 按ID排序
@@ -1188,14 +7281,19 @@ impl Editor {
 
     /// 创建新的空缓冲区
     pub fn create_new_buffer(&mut self) {
+        self.open_new_buffer(Buffer::new);
+    }
+
+    /// 创建缓冲区并切换过去
+    pub fn open_new_buffer(&mut self, make: impl FnOnce(BufferId) -> Buffer) {
         // 保存当前缓冲区的光标位置
-        self.buffer_cursors.insert(self.current_buffer, self.cursor.clone());
+        self.buffer_cursors.insert(self.current_buffer, self.cursor);
 
         // 创建新缓冲区
         let buffer_id = BufferId::new(self.next_buffer_id);
         self.next_buffer_id += 1;
 
-        let buffer = Buffer::new(buffer_id);
+        let buffer = make(buffer_id);
         self.buffers.insert(buffer_id, buffer);
         self.buffer_cursors.insert(buffer_id, Cursor::at_origin());
 
@@ -1205,11 +7303,50 @@ impl Editor {
         self.mode = Mode::Normal;
     }
 
+    /// :DiffOrig - 在只读的临时缓冲区中显示磁盘上的文件与当前缓冲区的差异
+    ///
+    /// 没有未保存的修改时只显示消息；关闭临时缓冲区（:bd）即可回到原来的缓冲区
+    pub fn diff_orig(&mut self) -> Result<(), String> {
+        let path = self
+            .current_buffer()
+            .file_path()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| tr!(self.locale, "No file name"))?;
+        let bytes = std::fs::read(&path)
+            .map_err(|e| tr!(self.locale, "Failed to open {}: {}", path.display(), e))?;
+        // 与读入文件时相同地解码、转换行尾，只比较内容
+        let original = crate::encoding::decode(&bytes).text;
+        let (_, original) = line_ending::detect(&original);
+        let original: Vec<&str> = original.lines().collect();
+        let current: Vec<_> = self.current_buffer().text_lines().collect();
+        let current: Vec<&str> = current.iter().map(AsRef::as_ref).collect();
+
+        let name = path.display().to_string();
+        let Some(diff) = crate::diff::unified_diff_lines(
+            &format!("{} (on disk)", name),
+            &format!("{} (buffer)", name),
+            &original,
+            &current,
+            3,
+        ) else {
+            self.set_message(tr!(self.locale, "No changes"));
+            return Ok(());
+        };
+
+        let title = format!("[DiffOrig] {}", self.current_buffer().display_name().unwrap_or(&name));
+        self.open_new_buffer(|id| {
+            let mut buffer = Buffer::scratch(id, &title, &diff);
+            buffer.set_filetype("diff");
+            buffer
+        });
+        Ok(())
+    }
+
     /// 切换到上一个缓冲区
     pub fn prev_buffer(&mut self) -> Result<(), String> {
         let mut buffer_ids: Vec<BufferId> = self.buffers.keys().cloned().collect();
         if buffer_ids.len() <= 1 {
-            return Err("没有其他缓冲区".to_string());
+            return Err(tr!(self.locale, "No other buffers"));
         }
 
         // 按ID排序
@@ -1232,15 +7369,20 @@ impl Editor {
     /// 删除缓冲区
     pub fn delete_buffer(&mut self, buffer_id: BufferId) -> Result<(), String> {
         if !self.buffers.contains_key(&buffer_id) {
-            return Err(format!("缓冲区 {} 不存在", buffer_id.as_usize()));
+            return Err(tr!(self.locale, "Buffer {} does not exist", buffer_id.as_usize()));
         }
 
         // 检查是否有未保存的修改
         if let Some(buffer) = self.buffers.get(&buffer_id) {
             if buffer.is_modified() {
-                return Err(format!("缓冲区 {} 有未保存的修改，请使用 :bd! 强制删除", buffer_id.as_usize()));
+                return Err(tr!(self.locale, "Buffer {} has unsaved changes (use :bd! to force)", buffer_id.as_usize()));
             }
         }
+        if self.terminals.get(&buffer_id).is_some_and(TerminalJob::is_running) {
+            return Err(tr!(self.locale, "Job still running in buffer {} (use :bd! to end it)", buffer_id.as_usize()));
+        }
+        self.write_undo_history(buffer_id);
+        self.save_positions(&[buffer_id]);
 
         // 如果删除的是当前缓冲区，需要先切换到其他缓冲区
         if buffer_id == self.current_buffer {
@@ -1262,9 +7404,15 @@ impl Editor {
             }
         }
 
-        // 删除缓冲区及其光标记录
+        // 删除缓冲区及其光标记录和局部映射
         self.buffers.remove(&buffer_id);
         self.buffer_cursors.remove(&buffer_id);
+        self.undo_trees.remove(&buffer_id);
+        self.buffer_options.remove(&buffer_id);
+        self.mappings.clear_buffer(buffer_id);
+        // 结束终端缓冲区中的程序
+        self.terminals.remove(&buffer_id);
+        self.retarget_windows(buffer_id);
 
         Ok(())
     }
@@ -1272,8 +7420,9 @@ impl Editor {
     /// 强制删除缓冲区（忽略未保存的修改）
     pub fn delete_buffer_force(&mut self, buffer_id: BufferId) -> Result<(), String> {
         if !self.buffers.contains_key(&buffer_id) {
-            return Err(format!("缓冲区 {} 不存在", buffer_id.as_usize()));
+            return Err(tr!(self.locale, "Buffer {} does not exist", buffer_id.as_usize()));
         }
+        self.save_positions(&[buffer_id]);
 
         // 如果删除的是当前缓冲区，需要先切换到其他缓冲区
         if buffer_id == self.current_buffer {
@@ -1295,9 +7444,15 @@ impl Editor {
             }
         }
 
-        // 删除缓冲区及其光标记录
+        // 删除缓冲区及其光标记录和局部映射
         self.buffers.remove(&buffer_id);
         self.buffer_cursors.remove(&buffer_id);
+        self.undo_trees.remove(&buffer_id);
+        self.buffer_options.remove(&buffer_id);
+        self.mappings.clear_buffer(buffer_id);
+        // 结束终端缓冲区中的程序
+        self.terminals.remove(&buffer_id);
+        self.retarget_windows(buffer_id);
 
         Ok(())
     }
@@ -1307,6 +7462,11 @@ impl Editor {
         self.current_buffer
     }
 
+    /// 按 ID 获取缓冲区，缓冲区已被删除时返回 None
+    pub fn buffer(&self, id: BufferId) -> Option<&Buffer> {
+        self.buffers.get(&id)
+    }
+
     /// 获取缓冲区数量
     pub fn buffer_count(&self) -> usize {
         self.buffers.len()
@@ -1318,3 +7478,54 @@ impl Default for Editor {
         Self::new()
     }
 }
+
+/// 把光标限制在缓冲区的范围内
+/// 终端的光标在缓冲区中的位置：屏幕在滚动历史之后，是缓冲区的最后几行
+fn terminal_cursor(job: &TerminalJob, buffer: &Buffer) -> Cursor {
+    let (rows, _) = job.size();
+    let (row, column) = job.cursor();
+    let first_row = buffer.len_lines().saturating_sub(usize::from(rows));
+    Cursor::new(first_row + row, column)
+}
+
+fn clamp_cursor(buffer: &Buffer, cursor: Cursor) -> Cursor {
+    let line = cursor.line.min(buffer.last_content_line());
+    let len = buffer.line(line).map_or(0, line_content_len);
+    let column = cursor.column.min(len.saturating_sub(1));
+    Cursor::new(line, column)
+}
+
+/// 缓冲区中 `cursor` 处的字符在屏幕上的列（Tab 按 tabstop 展开）
+fn buffer_display_column(buffer: &Buffer, cursor: Cursor, tabstop: usize) -> usize {
+    let text = buffer.line_text(cursor.line).unwrap_or_default();
+    display::display_column(&text, cursor.column, tabstop)
+}
+
+/// 去掉映射命令参数开头的修饰符（如 `<buffer>`）
+fn strip_map_modifier<'a>(args: &'a str, modifier: &str) -> Option<&'a str> {
+    let head = args.get(..modifier.len())?;
+    head.eq_ignore_ascii_case(modifier)
+        .then(|| args[modifier.len()..].trim_start())
+}
+
+/// 连续执行 `count` 次撤销或重做，返回最后到达的状态；一次都不能执行时返回 None
+fn repeat_undo_step(
+    tree: &mut UndoTree,
+    text: &ropey::Rope,
+    cursor: Cursor,
+    count: usize,
+    step: fn(&mut UndoTree, &ropey::Rope, Cursor) -> Option<UndoState>,
+) -> Option<UndoState> {
+    let mut state: Option<UndoState> = None;
+    for _ in 0..count.max(1) {
+        let next = match &state {
+            Some(state) => step(tree, &state.text, state.cursor),
+            None => step(tree, text, cursor),
+        };
+        match next {
+            Some(next) => state = Some(next),
+            None => break,
+        }
+    }
+    state
+}
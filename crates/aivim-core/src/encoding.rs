@@ -0,0 +1,154 @@
+//! 文件编码模块
+//!
+//! 读入文件时检测编码，缓冲区中总是保存解码后的文本，写入时再编码回原来的编码：
+//! - 以 UTF-16 BOM（`FF FE` / `FE FF`）开头的文件按 UTF-16 解码
+//! - 合法的 UTF-8 按 UTF-8 读入（包括以 UTF-8 BOM 开头的文件）
+//! - 其他文件按 latin1 读入：每个字节对应一个字符，不会丢失内容
+//!
+//! BOM 不放入解码后的文本（不占列、不会被删除），缓冲区记录文件有 BOM，写入时再写回。
+//! 文本中有编码无法表示的字符时（如在 latin1 文件中输入中文），需要确认后替换为 `?` 才能写入。
+
+/// 文件编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1，无法按 UTF-8 解码的文件的后备编码
+    Latin1,
+}
+
+/// 写入时代替无法编码的字符
+const REPLACEMENT: char = '?';
+
+/// BOM 解码后的字符
+pub const BOM: char = '\u{feff}';
+
+impl Encoding {
+    /// 名称（:set fileencoding）
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin1",
+        }
+    }
+
+    /// 从名称解析，不区分大小写，也接受 utf8、utf-16（大端）、iso-8859-1 等写法
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Some(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" | "utf-16" | "utf16" => Some(Encoding::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Some(Encoding::Latin1),
+            _ => None,
+        }
+    }
+
+    /// 字符能否用这种编码表示
+    pub fn can_encode(&self, c: char) -> bool {
+        match self {
+            Encoding::Latin1 => (c as u32) <= 0xff,
+            _ => true,
+        }
+    }
+
+    /// 文本中无法用这种编码表示的字符数
+    pub fn unencodable_chars(&self, text: &str) -> usize {
+        text.chars().filter(|&c| !self.can_encode(c)).count()
+    }
+
+    /// 这种编码的 BOM，latin1 没有 BOM
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xef, 0xbb, 0xbf],
+            Encoding::Utf16Le => &[0xff, 0xfe],
+            Encoding::Utf16Be => &[0xfe, 0xff],
+            Encoding::Latin1 => &[],
+        }
+    }
+}
+
+/// 解码后的文件内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub encoding: Encoding,
+    /// 解码后的文本，不包含 BOM
+    pub text: String,
+    /// 无法解码而替换为 U+FFFD 的字符数（UTF-16 中不成对的代理项）
+    pub replaced: usize,
+    /// 文件是否以 BOM 开头
+    pub bom: bool,
+}
+
+/// 是否以 UTF-16 BOM 开头
+pub fn has_utf16_bom(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xff, 0xfe]) || bytes.starts_with(&[0xfe, 0xff])
+}
+
+/// 检测编码并解码文件内容，去掉开头的 BOM
+pub fn decode(bytes: &[u8]) -> Decoded {
+    let mut decoded = if bytes.starts_with(Encoding::Utf16Le.bom()) {
+        decode_utf16(bytes, Encoding::Utf16Le)
+    } else if bytes.starts_with(Encoding::Utf16Be.bom()) {
+        decode_utf16(bytes, Encoding::Utf16Be)
+    } else {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Decoded { encoding: Encoding::Utf8, text: text.to_string(), replaced: 0, bom: false },
+            Err(_) => Decoded {
+                encoding: Encoding::Latin1,
+                text: bytes.iter().map(|&b| b as char).collect(),
+                replaced: 0,
+                bom: false,
+            },
+        }
+    };
+    if decoded.encoding != Encoding::Latin1 && decoded.text.starts_with(BOM) {
+        decoded.text.remove(0);
+        decoded.bom = true;
+    }
+    decoded
+}
+
+fn decode_utf16(bytes: &[u8], encoding: Encoding) -> Decoded {
+    let units = bytes.chunks(2).map(|pair| {
+        let pair = [pair[0], pair.get(1).copied().unwrap_or(0)];
+        if encoding == Encoding::Utf16Le {
+            u16::from_le_bytes(pair)
+        } else {
+            u16::from_be_bytes(pair)
+        }
+    });
+    let mut replaced = 0;
+    let text = char::decode_utf16(units)
+        .map(|c| {
+            c.unwrap_or_else(|_| {
+                replaced += 1;
+                char::REPLACEMENT_CHARACTER
+            })
+        })
+        .collect();
+    Decoded { encoding, text, replaced, bom: false }
+}
+
+/// 把文本编码为 `encoding`，有无法表示的字符时返回它们的个数
+pub fn encode(text: &str, encoding: Encoding) -> Result<Vec<u8>, usize> {
+    match encoding.unencodable_chars(text) {
+        0 => Ok(encode_lossy(text, encoding)),
+        count => Err(count),
+    }
+}
+
+/// 把文本编码为 `encoding`，无法表示的字符替换为 `?`
+pub fn encode_lossy(text: &str, encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Utf8 => text.as_bytes().to_vec(),
+        Encoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        Encoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        Encoding::Latin1 => text
+            .chars()
+            .map(|c| if encoding.can_encode(c) { c as u8 } else { REPLACEMENT as u8 })
+            .collect(),
+    }
+}
@@ -0,0 +1,254 @@
+//! Ex 命令解析模块
+//!
+//! 把 `:` 命令行解析为范围、命令名、`!` 和参数，如 `:1,5d a`、`:w! out.txt`、`:e "my file.txt"`：
+//! - 范围见 range 模块，只有允许范围的命令可以带范围
+//! - 命令名可以缩写为任意唯一的前缀（`:undol`、`:fixl`），
+//!   常用命令有与 Vim 相同的短缩写（`:w`、`:q`、`:b`、`:s`），缩写有歧义时报错
+//! - 命令名后紧跟的 `!` 对所有命令统一解析，不支持 `!` 的命令报错
+//! - 参数按空白分隔，可以用单引号或双引号包含空白，`\` 转义下一个字符
+//! - 映射命令（`:nmap` 等）不能缩写，参数保留原样
+//! - `:!cmd` 解析为名为 `!` 的命令，参数为 shell 命令行
+//! - 插件注册的命令（见 plugin 模块）与命令表中的命令一起解析和缩写
+
+use crate::mapping;
+use crate::range::{self, LineRange, RangeError};
+
+/// 命令表中的一项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    /// 完整的命令名
+    pub name: &'static str,
+    /// 最短的缩写长度
+    pub abbrev: usize,
+    /// 是否接受 `!`
+    pub bang: bool,
+    /// 是否接受范围
+    pub range: bool,
+}
+
+pub const fn spec(name: &'static str, abbrev: usize, bang: bool, range: bool) -> CommandSpec {
+    CommandSpec { name, abbrev, bang, range }
+}
+
+/// 支持的命令，缩写长度参照 Vim
+const COMMANDS: &[CommandSpec] = &[
+    spec("write", 1, true, true),
+    spec("wq", 2, true, false),
+    spec("quit", 1, true, false),
+    spec("qall", 2, true, false),
+    spec("set", 2, false, false),
+    spec("setlocal", 4, false, false),
+    spec("setglobal", 4, false, false),
+    spec("DiffOrig", 1, false, false),
+    spec("undolist", 5, false, false),
+    spec("registers", 3, false, false),
+    spec("jobs", 4, false, false),
+    spec("job", 3, false, false),
+    spec("ls", 2, false, false),
+    spec("buffers", 7, false, false),
+    spec("buffer", 1, false, false),
+    spec("bnext", 2, false, false),
+    spec("next", 1, false, false),
+    spec("bprevious", 2, false, false),
+    spec("previous", 4, false, false),
+    spec("bdelete", 2, true, false),
+    spec("new", 3, false, false),
+    spec("edit", 1, true, false),
+    spec("checktime", 6, false, false),
+    spec("Rename", 1, true, false),
+    spec("Delete", 1, true, false),
+    spec("fixlineendings", 1, true, false),
+    spec("tail", 1, false, false),
+    spec("symbols", 2, false, false),
+    spec("Files", 1, false, false),
+    spec("grep", 2, false, false),
+    spec("cnext", 2, false, false),
+    spec("cprevious", 2, false, false),
+    spec("cc", 2, false, false),
+    spec("copen", 4, false, false),
+    spec("cclose", 3, false, false),
+    spec("cfirst", 4, false, false),
+    spec("clast", 3, false, false),
+    spec("make", 3, true, false),
+    spec("lnext", 3, false, false),
+    spec("lprevious", 2, false, false),
+    spec("ll", 2, false, false),
+    spec("lfirst", 4, false, false),
+    spec("llast", 3, false, false),
+    spec("lopen", 4, false, false),
+    spec("lclose", 3, false, false),
+    spec("substitute", 1, false, true),
+    spec("yank", 1, false, true),
+    spec("delete", 1, false, true),
+    spec("move", 1, false, true),
+    spec("copy", 2, false, true),
+    spec("t", 1, false, true),
+    spec("normal", 4, true, true),
+    spec("fold", 2, false, true),
+    spec("foldopen", 5, true, true),
+    spec("foldclose", 5, true, true),
+    spec("read", 1, false, true),
+    spec("shell", 2, false, false),
+    spec("terminal", 3, false, false),
+    spec("suspend", 3, true, false),
+    spec("stop", 2, true, false),
+    spec("let", 3, false, false),
+    spec("source", 2, false, false),
+    spec("mksession", 3, true, false),
+    spec("cd", 2, false, false),
+    spec("wincmd", 4, false, false),
+    spec("call", 3, false, false),
+    spec("autocmd", 2, true, false),
+    spec("StripWhitespace", 1, false, true),
+    spec("colorscheme", 4, false, false),
+    spec("highlight", 2, false, false),
+    spec("split", 2, false, false),
+    spec("vsplit", 2, false, false),
+    spec("close", 3, true, false),
+    spec("only", 2, true, false),
+    spec("tabnew", 6, false, false),
+    spec("tabedit", 4, false, false),
+    spec("tabclose", 4, true, false),
+    spec("tabonly", 4, true, false),
+    spec("tabnext", 4, false, false),
+    spec("tabprevious", 4, false, false),
+];
+
+/// 解析后的 Ex 命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExCommand<'a> {
+    pub range: Option<LineRange>,
+    /// 完整的命令名；只有范围时（`:10`）为空，外部命令为 `!`
+    pub name: &'a str,
+    pub bang: bool,
+    /// 命令名和 `!` 之后的原始参数，去掉了开头的空白
+    pub args: &'a str,
+}
+
+/// 命令解析错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExError {
+    Range(RangeError),
+    /// 未知的命令
+    Unknown(String),
+    /// 缩写对应多个命令
+    Ambiguous(String),
+    /// 命令不接受范围
+    NoRange(String),
+    /// 命令不接受 `!`
+    NoBang(String),
+    /// 引号没有闭合
+    UnterminatedQuote,
+}
+
+/// 解析命令行，空命令返回 None
+///
+/// `current` 为光标所在行，`last` 为最后一行
+pub fn parse(command: &str, current: usize, last: usize) -> Result<Option<ExCommand<'_>>, ExError> {
+    parse_with(command, current, last, &[])
+}
+
+/// 与 parse 相同，`extra` 为插件注册的命令
+pub fn parse_with<'a>(
+    command: &'a str,
+    current: usize,
+    last: usize,
+    extra: &[CommandSpec],
+) -> Result<Option<ExCommand<'a>>, ExError> {
+    let (range, rest) = range::parse_range(command, current, last).map_err(ExError::Range)?;
+    let rest = rest.trim_start();
+
+    let name_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+    let (word, rest) = rest.split_at(name_len);
+    let (bang, args) = match rest.strip_prefix('!') {
+        Some(args) => (true, args.trim_start()),
+        None => (false, rest.trim_start()),
+    };
+
+    if word.is_empty() {
+        // :!cmd 和 :{range}!cmd，命令名为 "!"
+        if let Some(shell) = rest.strip_prefix('!') {
+            return Ok(Some(ExCommand { range, name: "!", bang: false, args: shell.trim_start() }));
+        }
+        return match (range, rest.is_empty()) {
+            (None, true) => Ok(None),
+            (Some(_), true) => Ok(Some(ExCommand { range, name: "", bang: false, args: "" })),
+            _ => Err(ExError::Unknown(rest.to_string())),
+        };
+    }
+
+    // 映射命令不缩写，是否接受 ! 由映射命令自己决定
+    let with_bang = if bang { format!("{}!", word) } else { word.to_string() };
+    if mapping::parse_map_command(&with_bang).is_some() {
+        if range.is_some() {
+            return Err(ExError::NoRange(word.to_string()));
+        }
+        return Ok(Some(ExCommand { range, name: word, bang, args }));
+    }
+
+    let spec = resolve(word, extra)?;
+    if range.is_some() && !spec.range {
+        return Err(ExError::NoRange(spec.name.to_string()));
+    }
+    if bang && !spec.bang {
+        return Err(ExError::NoBang(spec.name.to_string()));
+    }
+    Ok(Some(ExCommand { range, name: spec.name, bang, args }))
+}
+
+/// 把命令名或缩写解析为命令表或 `extra` 中的命令
+fn resolve<'a>(word: &str, extra: &'a [CommandSpec]) -> Result<&'a CommandSpec, ExError> {
+    let specs = COMMANDS.iter().chain(extra);
+    if let Some(spec) = specs.clone().find(|spec| spec.name == word) {
+        return Ok(spec);
+    }
+    let mut matches = specs
+        .filter(|spec| spec.name.starts_with(word) && word.len() >= spec.abbrev);
+    match (matches.next(), matches.next()) {
+        (Some(spec), None) => Ok(spec),
+        (Some(_), Some(_)) => Err(ExError::Ambiguous(word.to_string())),
+        (None, _) => Err(ExError::Unknown(word.to_string())),
+    }
+}
+
+/// `name` 是否是命令表中的命令（完整的命令名）
+pub fn is_builtin(name: &str) -> bool {
+    COMMANDS.iter().any(|spec| spec.name == name)
+}
+
+/// 按空白拆分参数：引号内的空白不分隔，`\` 转义下一个字符（单引号内除外）
+pub fn split_args(args: &str) -> Result<Vec<String>, ExError> {
+    let mut result = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = args.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(arg) = current.take() {
+                    result.push(arg);
+                }
+            }
+            '\\' => {
+                let arg = current.get_or_insert_with(String::new);
+                arg.push(chars.next().unwrap_or('\\'));
+            }
+            '"' | '\'' => {
+                let quote = c;
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        None => return Err(ExError::UnterminatedQuote),
+                        Some(c) if c == quote => break,
+                        Some('\\') if quote == '"' => {
+                            arg.push(chars.next().ok_or(ExError::UnterminatedQuote)?);
+                        }
+                        Some(c) => arg.push(c),
+                    }
+                }
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    result.extend(current);
+    Ok(result)
+}
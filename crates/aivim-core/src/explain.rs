@@ -0,0 +1,105 @@
+//! AI 解释模块 (:AiExplain / K)
+//!
+//! 把光标所在的函数或指定范围的行发给模型，解释在光标附近的浮动窗口中流式显示：
+//! - `:{range}AiExplain` 解释范围内的行；没有范围时解释包含光标的函数（见 `symbols::function_range`），
+//!   光标不在函数中时解释光标下的单词，附带所在的行
+//! - Normal 模式的 `K` 等同于 `:AiExplain`
+//! - 请求在后台进行，等待期间可以继续编辑；Esc 关闭浮动窗口并取消请求
+//! - 浮动窗口只在请求时的缓冲区中显示，新的请求替换之前的解释
+
+use aivim_ai::chat::{self, ChatMessage};
+use aivim_ai::{AiConfig, AiEvent, AiStream};
+use serde_json::Value;
+
+use crate::buffer::BufferId;
+use crate::chat::ChatContext;
+
+const SYSTEM_PROMPT: &str = "You explain code to a programmer inside a text editor. \
+Be concise: start with a one-sentence summary, then the key points. \
+Answer in plain text without Markdown headings; the answer is shown in a small popup.";
+
+/// 光标下的单词（字母、数字和下划线），`column` 为字符索引
+pub fn word_at(line: &str, column: usize) -> Option<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    if !chars.get(column).is_some_and(is_word) {
+        return None;
+    }
+    let start = column - chars[..column].iter().rev().take_while(|c| is_word(c)).count();
+    let end = column + chars[column..].iter().take_while(|c| is_word(c)).count();
+    Some(chars[start..end].iter().collect())
+}
+
+/// 解释请求的 JSON：`word` 为 None 时解释 `context` 中的全部代码
+pub fn request_body(config: &AiConfig, context: &ChatContext, word: Option<&str>) -> Value {
+    let question = match word {
+        Some(word) => format!("Explain `{}` as used in this code.", word),
+        None => "Explain this code.".to_string(),
+    };
+    let messages = [ChatMessage::system(SYSTEM_PROMPT), ChatMessage::user(context.prompt(&question))];
+    chat::request_body(config, &messages, config.chat_max_tokens, 0.2)
+}
+
+/// 浮动窗口中显示的解释
+#[derive(Debug)]
+pub struct Explanation {
+    buffer: BufferId,
+    /// 浮动窗口的标题，如 `main.rs:10-20` 或 `foo`
+    title: String,
+    text: String,
+    stream: Option<AiStream>,
+}
+
+impl Explanation {
+    pub fn new(buffer: BufferId, title: String, stream: AiStream) -> Self {
+        Self {
+            buffer,
+            title,
+            text: String::new(),
+            stream: Some(stream),
+        }
+    }
+
+    /// 请求解释时的缓冲区
+    pub fn buffer(&self) -> BufferId {
+        self.buffer
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// 已经收到的解释
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// 是否正在接收回复
+    pub fn is_responding(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// 取出回复，返回（是否有新内容, 错误）
+    pub fn poll(&mut self) -> (bool, Option<String>) {
+        let Some(stream) = self.stream.as_mut() else {
+            return (false, None);
+        };
+        let mut changed = false;
+        let mut error = None;
+        for event in stream.poll() {
+            match event {
+                AiEvent::Delta(text) => {
+                    self.text.push_str(&text);
+                    changed = true;
+                }
+                AiEvent::Done => {}
+                AiEvent::Error(message) => error = Some(message),
+            }
+        }
+        if stream.is_finished() {
+            self.stream = None;
+            changed = true;
+        }
+        (changed, error)
+    }
+}
@@ -0,0 +1,143 @@
+//! 表达式寄存器求值模块
+//!
+//! Insert 模式下 Ctrl+R = 输入的表达式在这里求值，结果插入到光标处。
+//! 目前只支持简单的算术：
+//! - 整数和小数，`+ - * / %`，括号和一元负号
+//! - 都是整数时按整数计算（`7 / 2` 为 3），有小数参与时按浮点数计算
+
+/// 表达式求值错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprError {
+    /// 无法解析的表达式
+    Invalid,
+    /// 整数除以零
+    DivisionByZero,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Int(i64),
+    Float(f64),
+}
+
+impl Value {
+    fn as_float(self) -> f64 {
+        match self {
+            Value::Int(n) => n as f64,
+            Value::Float(f) => f,
+        }
+    }
+
+    fn format(self) -> String {
+        match self {
+            Value::Int(n) => n.to_string(),
+            // 与 Vim 一样，整数值的浮点数也显示小数点
+            Value::Float(f) if f.is_finite() && f.fract() == 0.0 => format!("{:.1}", f),
+            Value::Float(f) => f.to_string(),
+        }
+    }
+}
+
+/// 求值表达式，返回插入的文本
+pub fn evaluate(expr: &str) -> Result<String, ExprError> {
+    let mut parser = Parser {
+        chars: expr.chars().filter(|c| !c.is_whitespace()).collect(),
+        pos: 0,
+    };
+    let value = parser.expression()?;
+    if parser.pos != parser.chars.len() {
+        return Err(ExprError::Invalid);
+    }
+    Ok(value.format())
+}
+
+/// 递归下降解析：expression = term (('+' | '-') term)*，term = factor (('*' | '/' | '%') factor)*
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expression(&mut self) -> Result<Value, ExprError> {
+        let mut value = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            value = apply(op, value, self.term()?)?;
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<Value, ExprError> {
+        let mut value = self.factor()?;
+        while let Some(op @ ('*' | '/' | '%')) = self.peek() {
+            self.pos += 1;
+            value = apply(op, value, self.factor()?)?;
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<Value, ExprError> {
+        match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                apply('-', Value::Int(0), self.factor()?)
+            }
+            Some('+') => {
+                self.pos += 1;
+                self.factor()
+            }
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expression()?;
+                if self.peek() != Some(')') {
+                    return Err(ExprError::Invalid);
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ExprError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        if text.contains('.') {
+            text.parse().map(Value::Float).map_err(|_| ExprError::Invalid)
+        } else {
+            text.parse().map(Value::Int).map_err(|_| ExprError::Invalid)
+        }
+    }
+}
+
+fn apply(op: char, left: Value, right: Value) -> Result<Value, ExprError> {
+    if let (Value::Int(a), Value::Int(b)) = (left, right) {
+        let result = match op {
+            '+' => a.checked_add(b),
+            '-' => a.checked_sub(b),
+            '*' => a.checked_mul(b),
+            '/' | '%' if b == 0 => return Err(ExprError::DivisionByZero),
+            '/' => a.checked_div(b),
+            _ => a.checked_rem(b),
+        };
+        // 整数溢出时改用浮点数
+        if let Some(result) = result {
+            return Ok(Value::Int(result));
+        }
+    }
+    let (a, b) = (left.as_float(), right.as_float());
+    Ok(Value::Float(match op {
+        '+' => a + b,
+        '-' => a - b,
+        '*' => a * b,
+        '/' => a / b,
+        _ => a % b,
+    }))
+}
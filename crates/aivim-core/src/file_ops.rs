@@ -0,0 +1,154 @@
+//! 文件管理模块
+//!
+//! 实现直接操作磁盘文件的命令：
+//! - :Rename {file} - 重命名当前缓冲区对应的文件
+//! - :Delete - 删除当前缓冲区对应的文件并关闭缓冲区
+//! - :10,20w {file} / :w >> {file} - 把部分行写入或追加到其他文件
+//!
+//! 以及破坏性操作的安全保护：
+//! - 开启 `trash` 选项后，删除的文件被移到系统回收站而不是直接删除
+//! - 写入匹配受保护路径模式（如 `/etc/*`）的文件前需要确认。模式用 `:set protectedpaths=...`
+//!   （逗号分隔，为空时不保护任何路径）或 `config.toml` 设置：
+//!
+//! ```toml
+//! [files]
+//! protected_paths = ["/etc/*", "/usr/*", "/srv/*"]
+//! ```
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// 默认受保护的路径模式
+pub const DEFAULT_PROTECTED_PATHS: &[&str] = &["/etc/*", "/usr/*", "/bin/*", "/sbin/*", "/boot/*"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilesSection {
+    protected_paths: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    files: FilesSection,
+}
+
+/// 解析配置文件中 `[files]` 表的 `protected_paths`，没有设置时返回 None，错误信息包括出错的位置
+pub fn parse_protected_paths(text: &str) -> Result<Option<Vec<String>>, String> {
+    let file: ConfigFile = toml::from_str(text).map_err(|e| {
+        let location = e.to_string().lines().next().unwrap_or_default().to_string();
+        format!("{}: {}", location, e.message())
+    })?;
+    Ok(file.files.protected_paths)
+}
+
+/// 读取配置文件中的受保护路径模式
+pub fn load_protected_paths(path: &Path) -> Result<Option<Vec<String>>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_protected_paths(&text)
+}
+
+/// 拆分 `:set protectedpaths=` 的值：逗号分隔，忽略空的项
+pub fn split_patterns(value: &str) -> Vec<String> {
+    value.split(',').filter(|pattern| !pattern.is_empty()).map(str::to_string).collect()
+}
+
+/// 把（已编码的）文本写入文件，`append` 为 true 时追加到文件末尾（文件不存在时创建）
+pub fn write_text(path: &Path, text: &[u8], append: bool) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    file.write_all(text)
+}
+
+/// 重命名磁盘上的文件
+///
+/// # 参数
+/// - `from`: 原文件路径
+/// - `to`: 新文件路径
+/// - `force`: 目标文件已存在时是否覆盖（对应 `!` 修饰符）
+///
+/// # 说明
+/// - 原文件不存在时（缓冲区尚未保存过）视为成功，只需更新缓冲区路径
+/// - 目标文件已存在且未指定 `force` 时返回 `AlreadyExists` 错误
+pub fn rename_file(from: &Path, to: &Path, force: bool) -> io::Result<()> {
+    if from == to {
+        return Ok(());
+    }
+
+    if to.exists() && !force {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("File exists: {} (add ! to override)", to.display()),
+        ));
+    }
+
+    if !from.exists() {
+        return Ok(());
+    }
+
+    // 自动创建目标目录
+    if let Some(parent) = to.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    fs::rename(from, to)
+}
+
+/// 删除磁盘上的文件
+///
+/// 文件不存在时返回 `NotFound` 错误，目录不会被删除
+pub fn delete_file(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is a directory", path.display()),
+        ));
+    }
+    fs::remove_file(path)
+}
+
+/// 将文件移到系统回收站
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found", path.display()),
+        ));
+    }
+    trash::delete(path).map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// 检查路径是否匹配任一受保护的模式
+///
+/// 相对路径会先转换为绝对路径再匹配，无效的模式会被忽略
+pub fn is_protected(path: &Path, patterns: &[String]) -> bool {
+    let absolute = absolute_path(path);
+    patterns.iter().any(|pattern| {
+        glob::Pattern::new(pattern)
+            .map(|p| p.matches_path(path) || p.matches_path(&absolute))
+            .unwrap_or(false)
+    })
+}
+
+/// 获取绝对路径（文件不存在时基于当前目录拼接）
+fn absolute_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
@@ -0,0 +1,144 @@
+//! 界面填充字符模块 (:set fillchars)
+//!
+//! 控制界面中非文本内容使用的字符，格式与 Vim 的 fillchars 相同：
+//! `vert:|,eob:~`，未指定的项使用默认值。
+//! - vert / horiz: 窗口和面板的竖直、水平分隔线
+//! - fold / foldopen / foldclose / foldsep: 折叠行的填充字符和折叠栏标记
+//! - eob: 缓冲区末尾之后的空行（Vim 中的 `~`）
+//!
+//! 终端字体不支持框线字符时（如 Linux 控制台、非 UTF-8 的 locale），
+//! 默认字符退回到纯 ASCII 版本（`:set asciiglyphs`）。
+
+use std::env;
+
+/// 界面填充字符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillChars {
+    pub vert: char,
+    pub horiz: char,
+    pub fold: char,
+    pub foldopen: char,
+    pub foldclose: char,
+    pub foldsep: char,
+    pub eob: char,
+}
+
+impl FillChars {
+    /// 默认的 Unicode 字符
+    pub const UNICODE: FillChars = FillChars {
+        vert: '│',
+        horiz: '─',
+        fold: '·',
+        foldopen: '▾',
+        foldclose: '▸',
+        foldsep: '│',
+        eob: '~',
+    };
+
+    /// 终端不支持 Unicode 框线字符时使用的 ASCII 字符
+    pub const ASCII: FillChars = FillChars {
+        vert: '|',
+        horiz: '-',
+        fold: '-',
+        foldopen: '-',
+        foldclose: '+',
+        foldsep: '|',
+        eob: '~',
+    };
+
+    /// 默认字符
+    pub fn defaults(ascii: bool) -> Self {
+        if ascii {
+            Self::ASCII
+        } else {
+            Self::UNICODE
+        }
+    }
+
+    /// 解析 `name:c,name:c` 格式的设置，未指定的项使用默认字符
+    ///
+    /// 每项的值必须正好是一个可见字符，格式错误时返回 None
+    pub fn parse(spec: &str, ascii: bool) -> Option<Self> {
+        let mut fillchars = Self::defaults(ascii);
+        if spec.is_empty() {
+            return Some(fillchars);
+        }
+        for item in spec.split(',') {
+            let (name, value) = item.split_once(':')?;
+            let mut chars = value.chars();
+            let c = match (chars.next(), chars.next()) {
+                (Some(c), None) if !c.is_control() => c,
+                _ => return None,
+            };
+            *fillchars.item_mut(name)? = c;
+        }
+        Some(fillchars)
+    }
+
+    fn item_mut(&mut self, name: &str) -> Option<&mut char> {
+        let item = match name {
+            "vert" => &mut self.vert,
+            "horiz" => &mut self.horiz,
+            "fold" => &mut self.fold,
+            "foldopen" => &mut self.foldopen,
+            "foldclose" => &mut self.foldclose,
+            "foldsep" => &mut self.foldsep,
+            "eob" => &mut self.eob,
+            _ => return None,
+        };
+        Some(item)
+    }
+
+    /// 切换默认字符集：仍是旧默认值的项换成新的默认值，用户设置过的项保持不变
+    pub fn with_defaults(self, ascii: bool) -> Self {
+        let (from, to) = if ascii {
+            (Self::UNICODE, Self::ASCII)
+        } else {
+            (Self::ASCII, Self::UNICODE)
+        };
+        let pick = |current: char, old: char, new: char| if current == old { new } else { current };
+        FillChars {
+            vert: pick(self.vert, from.vert, to.vert),
+            horiz: pick(self.horiz, from.horiz, to.horiz),
+            fold: pick(self.fold, from.fold, to.fold),
+            foldopen: pick(self.foldopen, from.foldopen, to.foldopen),
+            foldclose: pick(self.foldclose, from.foldclose, to.foldclose),
+            foldsep: pick(self.foldsep, from.foldsep, to.foldsep),
+            eob: pick(self.eob, from.eob, to.eob),
+        }
+    }
+
+    /// `:set fillchars=...` 格式的表示
+    pub fn format(&self) -> String {
+        format!(
+            "vert:{},horiz:{},fold:{},foldopen:{},foldclose:{},foldsep:{},eob:{}",
+            self.vert, self.horiz, self.fold, self.foldopen, self.foldclose, self.foldsep, self.eob
+        )
+    }
+}
+
+impl Default for FillChars {
+    fn default() -> Self {
+        Self::UNICODE
+    }
+}
+
+/// 根据环境变量推断终端能否显示 Unicode 框线字符
+///
+/// Linux 虚拟控制台 (TERM=linux) 的字体没有这些字符；
+/// locale 不是 UTF-8 时终端也不会按 UTF-8 解码输出
+pub fn terminal_supports_unicode() -> bool {
+    if env::var("TERM").is_ok_and(|term| term == "linux") {
+        return false;
+    }
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+        // 没有设置 locale 时大多数现代终端仍然支持 UTF-8
+        .unwrap_or(true)
+}
@@ -0,0 +1,271 @@
+//! 文件查找模块
+//!
+//! 为模糊文件选择器（Ctrl+P、:Files）列出目录下的文件：
+//! - 后台线程遍历目录树，按批通过通道发送相对路径并唤醒事件循环，主线程调用 `FileFinder::poll` 取出
+//! - 遵守各级目录中的 `.gitignore`（以及 `.git/info/exclude`），跳过 `.git` 和隐藏文件
+//! - 最多列出 `MAX_FINDER_FILES` 个文件
+//!
+//! `.gitignore` 支持 Git 的常用写法：`#` 注释、`!` 取反、结尾的 `/` 只匹配目录、
+//! 包含 `/` 的模式相对于 `.gitignore` 所在目录、`*` / `?` / `[...]` / `**` 通配符
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use glob::{MatchOptions, Pattern};
+
+use crate::waker::Waker;
+
+/// 最多列出的文件数
+pub const MAX_FINDER_FILES: usize = 100_000;
+
+/// 每批发送的文件数
+const BATCH_SIZE: usize = 512;
+
+/// `*` 和 `?` 不匹配 `/`，与 Git 相同
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// `.gitignore` 中的一条规则
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    pattern: Pattern,
+    /// `!` 开头：重新包含之前排除的路径
+    negated: bool,
+    /// 以 `/` 结尾：只匹配目录
+    dir_only: bool,
+    /// 包含 `/`：匹配相对于 `.gitignore` 所在目录的完整路径，否则只匹配文件名
+    anchored: bool,
+}
+
+/// 一个 `.gitignore` 文件中的规则
+#[derive(Debug, Clone, Default)]
+pub struct Gitignore {
+    rules: Vec<IgnoreRule>,
+}
+
+impl Gitignore {
+    /// 解析 `.gitignore` 的内容，忽略无效的模式
+    pub fn parse(text: &str) -> Self {
+        let rules = text.lines().filter_map(parse_rule).collect();
+        Self { rules }
+    }
+
+    /// 读取目录中的 `.gitignore`，没有时返回 None
+    fn load(dir: &Path) -> Option<Self> {
+        let text = fs::read_to_string(dir.join(".gitignore")).ok()?;
+        Some(Self::parse(&text))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// `path`（相对于 `.gitignore` 所在目录，用 `/` 分隔）是否被忽略；没有规则匹配时返回 None
+    ///
+    /// 后面的规则优先
+    pub fn matched(&self, path: &str, is_dir: bool) -> Option<bool> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && rule.pattern.matches_with(if rule.anchored { path } else { name }, MATCH_OPTIONS)
+            })
+            .map(|rule| !rule.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<IgnoreRule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    // `\#`、`\!` 表示字面的 # 和 !
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').filter(|rest| rest.starts_with(['#', '!'])).unwrap_or(line)),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    if line.is_empty() {
+        return None;
+    }
+    let pattern = Pattern::new(line).ok()?;
+    Some(IgnoreRule { pattern, negated, dir_only, anchored })
+}
+
+/// 遍历中某一级目录的忽略规则
+struct IgnoreLevel {
+    /// `.gitignore` 所在目录相对于根目录的路径（根目录为空）
+    base: String,
+    rules: Gitignore,
+}
+
+/// 按各级 `.gitignore` 判断 `path`（相对于根目录）是否被忽略，深层目录的规则优先
+fn is_ignored(levels: &[IgnoreLevel], path: &str, is_dir: bool) -> bool {
+    for level in levels.iter().rev() {
+        let relative = if level.base.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(&level.base).and_then(|rest| rest.strip_prefix('/')) {
+                Some(rest) => rest,
+                None => continue,
+            }
+        };
+        if let Some(ignored) = level.rules.matched(relative, is_dir) {
+            return ignored;
+        }
+    }
+    false
+}
+
+/// 遍历 `root` 下的文件，对每个文件（相对路径）调用 `visit`，`visit` 返回 false 时停止
+///
+/// 同一目录中的条目按名称排序
+pub fn walk_files(root: &Path, visit: &mut dyn FnMut(PathBuf) -> bool) {
+    let mut levels = Vec::new();
+    if let Ok(text) = fs::read_to_string(root.join(".git/info/exclude")) {
+        levels.push(IgnoreLevel { base: String::new(), rules: Gitignore::parse(&text) });
+    }
+    walk_dir(root, "", &mut levels, visit);
+}
+
+/// 遍历目录 `dir`（相对于根目录为 `relative`），返回 false 表示已停止
+fn walk_dir(dir: &Path, relative: &str, levels: &mut Vec<IgnoreLevel>, visit: &mut dyn FnMut(PathBuf) -> bool) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return true;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let pushed = match Gitignore::load(dir) {
+        Some(rules) if !rules.is_empty() => {
+            levels.push(IgnoreLevel { base: relative.to_string(), rules });
+            true
+        }
+        _ => false,
+    };
+
+    let mut keep_going = true;
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        // 不进入指向目录的符号链接，避免循环
+        if file_type.is_symlink() && entry.path().is_dir() {
+            continue;
+        }
+        let is_dir = file_type.is_dir();
+        let path = if relative.is_empty() { name.to_string() } else { format!("{}/{}", relative, name) };
+        if is_ignored(levels, &path, is_dir) {
+            continue;
+        }
+        keep_going = if is_dir {
+            walk_dir(&entry.path(), &path, levels, visit)
+        } else {
+            visit(PathBuf::from(&path))
+        };
+        if !keep_going {
+            break;
+        }
+    }
+
+    if pushed {
+        levels.pop();
+    }
+    keep_going
+}
+
+/// 同步列出 `root` 下的文件（相对路径），最多 `MAX_FINDER_FILES` 个
+pub fn list_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    walk_files(root, &mut |path| {
+        files.push(path);
+        files.len() < MAX_FINDER_FILES
+    });
+    files
+}
+
+/// 在后台线程中遍历目录的文件查找任务
+///
+/// 丢弃 FileFinder 后通道断开，后台线程在发送下一批时停止
+#[derive(Debug)]
+pub struct FileFinder {
+    root: PathBuf,
+    receiver: Receiver<Vec<PathBuf>>,
+    done: bool,
+}
+
+impl FileFinder {
+    /// 开始在后台遍历 `root`
+    pub fn start(root: PathBuf) -> Self {
+        Self::start_with_waker(root, Waker::default())
+    }
+
+    /// 开始在后台遍历，每发送一批文件和遍历结束时调用 `waker`
+    pub fn start_with_waker(root: PathBuf, waker: Waker) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let walk_root = root.clone();
+        thread::spawn(move || {
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            let mut count = 0;
+            walk_files(&walk_root, &mut |path| {
+                batch.push(path);
+                count += 1;
+                if batch.len() >= BATCH_SIZE {
+                    if sender.send(std::mem::take(&mut batch)).is_err() {
+                        return false;
+                    }
+                    waker.wake();
+                }
+                count < MAX_FINDER_FILES
+            });
+            if !batch.is_empty() {
+                let _ = sender.send(batch);
+            }
+            drop(sender);
+            waker.wake();
+        });
+        Self { root, receiver, done: false }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 遍历是否已经结束
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// 取出目前已找到的文件（相对于根目录）
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(batch) => files.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        files
+    }
+}
@@ -0,0 +1,399 @@
+//! 折叠模块 (zf / zo / zc / za)
+//!
+//! 每个缓冲区保存一组折叠，折叠是可以嵌套的行范围，关闭的折叠在窗口中显示为一行摘要：
+//! - `foldmethod=manual`（默认）时用 zf{motion}、zF 或 `:fold` 创建折叠，编辑时随文本移动
+//! - `foldmethod=indent` 按缩进层级计算折叠，`foldmethod=syntax` 按语法树中跨越多行的函数和类型定义计算
+//!   （见 syntax 模块），缓冲区修改后重新计算，保留起始行不变的折叠的开关状态；切换折叠方式时所有折叠关闭
+//! - 上下移动光标时关闭的折叠只算一行，光标停在折叠的第一行
+//! - `:mksession` 保存手动创建的折叠和各折叠的开关状态
+
+use std::collections::HashSet;
+
+use crate::buffer::Buffer;
+use crate::display;
+
+/// 折叠方式（:set foldmethod=）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FoldMethod {
+    /// 手动创建
+    #[default]
+    Manual,
+    /// 按缩进
+    Indent,
+    /// 按语法树
+    Syntax,
+}
+
+impl FoldMethod {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "manual" => Some(FoldMethod::Manual),
+            "indent" => Some(FoldMethod::Indent),
+            "syntax" => Some(FoldMethod::Syntax),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FoldMethod::Manual => "manual",
+            FoldMethod::Indent => "indent",
+            FoldMethod::Syntax => "syntax",
+        }
+    }
+}
+
+/// 一个折叠：第 `start` 到 `end` 行（包含）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fold {
+    pub start: usize,
+    pub end: usize,
+    pub closed: bool,
+}
+
+impl Fold {
+    fn contains(&self, line: usize) -> bool {
+        self.start <= line && line <= self.end
+    }
+
+    /// 是否包含另一个折叠（范围相同时也算包含）
+    fn encloses(&self, other: &Fold) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// 缓冲区中的折叠
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FoldSet {
+    /// 按起始行排序，起始行相同时外层（更长）的在前
+    folds: Vec<Fold>,
+    /// 计算折叠时的折叠方式和缓冲区的 changedtick，手动折叠时为 None
+    computed: Option<(FoldMethod, u64)>,
+}
+
+impl FoldSet {
+    pub fn folds(&self) -> &[Fold] {
+        &self.folds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    /// 计算折叠时的折叠方式和 changedtick
+    pub fn computed(&self) -> Option<(FoldMethod, u64)> {
+        self.computed
+    }
+
+    fn sort(&mut self) {
+        self.folds.sort_by_key(|fold| (fold.start, std::cmp::Reverse(fold.end)));
+    }
+
+    /// 创建关闭的折叠，已经有范围相同的折叠时关闭它
+    pub fn create(&mut self, start: usize, end: usize) {
+        let (start, end) = (start.min(end), start.max(end));
+        match self.folds.iter_mut().find(|fold| fold.start == start && fold.end == end) {
+            Some(fold) => fold.closed = true,
+            None => {
+                self.folds.push(Fold { start, end, closed: true });
+                self.sort();
+            }
+        }
+    }
+
+    /// 用计算出的折叠替换现有的折叠
+    ///
+    /// 折叠方式不变时保留起始行相同的折叠的开关状态，新出现的折叠打开；折叠方式改变时所有折叠关闭
+    pub fn set_computed(&mut self, method: FoldMethod, tick: u64, ranges: Vec<(usize, usize)>) {
+        let reset = self.computed.map(|(previous, _)| previous) != Some(method);
+        let closed: HashSet<usize> = self.folds.iter().filter(|fold| fold.closed).map(|fold| fold.start).collect();
+        self.folds = ranges
+            .into_iter()
+            .map(|(start, end)| Fold { start, end, closed: reset || closed.contains(&start) })
+            .collect();
+        self.sort();
+        self.computed = Some((method, tick));
+    }
+
+    /// 切换为手动折叠：保留现有的折叠，之后不再重新计算
+    pub fn set_manual(&mut self) {
+        self.computed = None;
+    }
+
+    /// 包含第 `line` 行的最外层关闭的折叠的行范围（第 `line` 行被折叠时显示为这个范围的摘要）
+    pub fn closed_range(&self, line: usize) -> Option<(usize, usize)> {
+        self.folds
+            .iter()
+            .find(|fold| fold.closed && fold.contains(line))
+            .map(|fold| (fold.start, fold.end))
+    }
+
+    /// 折叠的层级：包含它的折叠数（包括自己）
+    pub fn level(&self, start: usize, end: usize) -> usize {
+        let target = Fold { start, end, closed: false };
+        self.folds.iter().filter(|fold| fold.encloses(&target)).count()
+    }
+
+    /// 包含第 `line` 行的最外层关闭的折叠的下标
+    fn outer_closed(&self, line: usize) -> Option<usize> {
+        self.folds.iter().position(|fold| fold.closed && fold.contains(line))
+    }
+
+    /// 打开第 `line` 行所在的关闭的折叠 (zo)，`all` 时打开包含这一行的所有折叠 (zO)
+    ///
+    /// 没有可以打开的折叠时返回 false
+    pub fn open(&mut self, line: usize, all: bool) -> bool {
+        if all {
+            let mut opened = false;
+            for fold in self.folds.iter_mut().filter(|fold| fold.closed && fold.contains(line)) {
+                fold.closed = false;
+                opened = true;
+            }
+            return opened;
+        }
+        match self.outer_closed(line) {
+            Some(index) => {
+                self.folds[index].closed = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 关闭第 `line` 行所在的一层折叠 (zc)，`all` 时关闭包含这一行的所有折叠 (zC)
+    ///
+    /// 这一行已经被折叠时关闭外面一层的折叠；没有可以关闭的折叠时返回 false
+    pub fn close(&mut self, line: usize, all: bool) -> bool {
+        if all {
+            let mut found = false;
+            for fold in self.folds.iter_mut().filter(|fold| fold.contains(line)) {
+                fold.closed = true;
+                found = true;
+            }
+            return found;
+        }
+        let visible = self.outer_closed(line).map(|index| self.folds[index]);
+        let candidate = self
+            .folds
+            .iter()
+            .enumerate()
+            .filter(|(_, fold)| !fold.closed && fold.contains(line))
+            .filter(|(_, fold)| visible.is_none_or(|visible| fold.encloses(&visible)))
+            .min_by_key(|(_, fold)| fold.len())
+            .map(|(index, _)| index);
+        match candidate {
+            Some(index) => {
+                self.folds[index].closed = true;
+                true
+            }
+            // 最外层的折叠已经关闭
+            None => visible.is_some(),
+        }
+    }
+
+    /// 第 `line` 行被折叠时打开 (zo)，否则关闭 (zc)；`all` 时打开或关闭包含这一行的所有折叠 (zA)
+    pub fn toggle(&mut self, line: usize, all: bool) -> bool {
+        if self.closed_range(line).is_some() {
+            self.open(line, all)
+        } else {
+            self.close(line, all)
+        }
+    }
+
+    /// 打开所有折叠 (zR)
+    pub fn open_all(&mut self) {
+        self.folds.iter_mut().for_each(|fold| fold.closed = false);
+    }
+
+    /// 关闭所有折叠 (zM)
+    pub fn close_all(&mut self) {
+        self.folds.iter_mut().for_each(|fold| fold.closed = true);
+    }
+
+    /// 打开范围内各行所在的折叠 (:foldopen)：一层，即包含这些行的最外层关闭的折叠；`all` 时打开所有层
+    pub fn open_range(&mut self, first: usize, last: usize, all: bool) {
+        let overlaps = |fold: &Fold| fold.start <= last && first <= fold.end;
+        if all {
+            self.folds.iter_mut().filter(|fold| overlaps(fold)).for_each(|fold| fold.closed = false);
+            return;
+        }
+        let outer: Vec<usize> = (0..self.folds.len())
+            .filter(|&index| {
+                let fold = self.folds[index];
+                fold.closed
+                    && overlaps(&fold)
+                    && !self.folds.iter().enumerate().any(|(other, outer)| {
+                        other != index && outer.closed && outer.encloses(&fold) && outer.len() > fold.len()
+                    })
+            })
+            .collect();
+        for index in outer {
+            self.folds[index].closed = false;
+        }
+    }
+
+    /// 关闭范围内各行所在的折叠 (:foldclose)：一层，即不包含其他打开的折叠的最内层折叠；`all` 时关闭所有层
+    pub fn close_range(&mut self, first: usize, last: usize, all: bool) {
+        let overlaps = |fold: &Fold| fold.start <= last && first <= fold.end;
+        let inner: Vec<usize> = (0..self.folds.len())
+            .filter(|&index| {
+                let fold = self.folds[index];
+                overlaps(&fold)
+                    && (all
+                        || !fold.closed
+                            && !self.folds.iter().enumerate().any(|(other, inner)| {
+                                other != index
+                                    && !inner.closed
+                                    && overlaps(inner)
+                                    && fold.encloses(inner)
+                                    && inner.len() < fold.len()
+                            }))
+            })
+            .collect();
+        for index in inner {
+            self.folds[index].closed = true;
+        }
+    }
+
+    /// 删除第 `line` 行所在的一个折叠 (zd)：这一行被折叠时删除显示的折叠，否则删除最内层的折叠；
+    /// `all` 时删除包含这一行的所有折叠 (zD)。嵌套的折叠保留。没有折叠时返回 false
+    pub fn delete(&mut self, line: usize, all: bool) -> bool {
+        if all {
+            let before = self.folds.len();
+            self.folds.retain(|fold| !fold.contains(line));
+            return self.folds.len() < before;
+        }
+        let index = self.outer_closed(line).or_else(|| {
+            self.folds
+                .iter()
+                .enumerate()
+                .filter(|(_, fold)| fold.contains(line))
+                .min_by_key(|(_, fold)| fold.len())
+                .map(|(index, _)| index)
+        });
+        match index {
+            Some(index) => {
+                self.folds.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 删除所有折叠 (zE)
+    pub fn clear(&mut self) {
+        self.folds.clear();
+    }
+
+    /// 在第 `line` 行插入了 `count` 个换行符：之后的折叠下移，包含这一行的折叠变长
+    ///
+    /// `at_line_start` 表示在行首插入，这时从这一行开始的折叠整体下移
+    pub(crate) fn insert_lines(&mut self, line: usize, count: usize, at_line_start: bool) {
+        for fold in &mut self.folds {
+            if fold.start > line || (at_line_start && fold.start == line) {
+                fold.start += count;
+                fold.end += count;
+            } else if fold.end >= line {
+                fold.end += count;
+            }
+        }
+    }
+
+    /// 删除了第 `first` 行到第 `last` 行之间的换行符
+    ///
+    /// `whole_lines` 表示删除的是第 `first` 到 `last - 1` 整行；否则这些行合并到第 `first` 行。
+    /// 全部被删除或合并为一行的折叠被删除
+    pub(crate) fn remove_lines(&mut self, first: usize, last: usize, whole_lines: bool) {
+        let count = last - first;
+        let map = |line: usize| -> Option<usize> {
+            if whole_lines {
+                match line {
+                    _ if line < first => Some(line),
+                    _ if line >= last => Some(line - count),
+                    _ => None,
+                }
+            } else if line <= first {
+                Some(line)
+            } else if line > last {
+                Some(line - count)
+            } else {
+                Some(first)
+            }
+        };
+        self.folds = self
+            .folds
+            .iter()
+            .filter_map(|fold| {
+                let (start, end) = match (map(fold.start), map(fold.end)) {
+                    (Some(start), Some(end)) => (start, end),
+                    (None, Some(end)) => (first, end),
+                    (Some(start), None) => (start, first.saturating_sub(1).max(start)),
+                    (None, None) => return None,
+                };
+                let collapsed = start == end && fold.start != fold.end;
+                (!collapsed).then_some(Fold { start, end, closed: fold.closed })
+            })
+            .collect();
+        self.sort();
+        self.folds.dedup_by(|a, b| a.start == b.start && a.end == b.end);
+    }
+
+    /// 缓冲区内容被整体替换（如撤销）之后，删除超出最后一行的折叠
+    pub(crate) fn clamp(&mut self, last_line: usize) {
+        self.folds.retain(|fold| fold.start <= last_line);
+        for fold in &mut self.folds {
+            fold.end = fold.end.min(last_line);
+        }
+    }
+}
+
+/// 按缩进计算折叠的行范围：缩进层级（缩进宽度除以 `shiftwidth`）不小于 n 的连续行是第 n 层折叠
+///
+/// 空行的层级取前后非空行中较小的一个，只有一行的折叠不创建
+pub fn indent_folds(buffer: &Buffer, shiftwidth: usize, tabstop: usize) -> Vec<(usize, usize)> {
+    let last = buffer.last_content_line();
+    if buffer.len_chars() == 0 {
+        return Vec::new();
+    }
+    let levels: Vec<Option<usize>> = (0..=last)
+        .map(|line| {
+            let text = buffer.line_text(line).unwrap_or_default();
+            let indent: String = text.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+            if indent.len() == text.len() {
+                return None;
+            }
+            Some(display::display_column(&text, indent.chars().count(), tabstop) / shiftwidth.max(1))
+        })
+        .collect();
+    // 每行之后（包括这一行）第一个非空行的层级
+    let mut next = vec![0; levels.len()];
+    let mut following = 0;
+    for (line, level) in levels.iter().enumerate().rev() {
+        following = level.unwrap_or(following);
+        next[line] = following;
+    }
+    let mut resolved: Vec<usize> = Vec::with_capacity(levels.len());
+    for (line, level) in levels.iter().enumerate() {
+        let previous = resolved.last().copied().unwrap_or(0);
+        resolved.push(level.unwrap_or(previous.min(next[line])));
+    }
+
+    let mut ranges = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for (line, level) in resolved.iter().copied().chain(std::iter::once(0)).enumerate() {
+        while open.len() > level {
+            let start = open.pop().unwrap_or(line);
+            if line - 1 > start {
+                ranges.push((start, line - 1));
+            }
+        }
+        while open.len() < level {
+            open.push(line);
+        }
+    }
+    ranges
+}
@@ -0,0 +1,111 @@
+//! Git 模块
+//!
+//! 通过 `git` 命令读取仓库的信息，命令在指定的目录中运行（通常为当前文件所在的目录）：
+//! - `git` 不存在、目录不在仓库中或命令失败时返回错误，错误信息为 git 的标准错误输出
+
+use ropey::Rope;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const GIT: &str = "git";
+
+/// 在 `dir` 中运行 git 命令，返回标准输出
+fn run(dir: &Path, args: &[&str]) -> io::Result<String> {
+    let output = Command::new(GIT).args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("git failed");
+        return Err(io::Error::other(message.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 在 `dir` 中运行 git 命令，`input` 写入标准输入，返回标准输出
+///
+/// 先写完标准输入再读取输出，只能用于读完输入才开始输出的命令（如 `apply`、`blame --contents -`）
+fn run_with_input<'a>(dir: &Path, args: &[&str], input: impl IntoIterator<Item = &'a str>) -> io::Result<String> {
+    let mut child = Command::new(GIT)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for chunk in input {
+            stdin.write_all(chunk.as_bytes())?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("git failed");
+        return Err(io::Error::other(message.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 仓库的工作区根目录
+pub fn work_tree(dir: &Path) -> io::Result<PathBuf> {
+    let root = run(dir, &["rev-parse", "--show-toplevel"])?;
+    Ok(PathBuf::from(root.trim_end_matches('\n')))
+}
+
+/// `dir` 所在仓库的当前分支，分离的 HEAD 为提交号的缩写
+pub fn current_branch(dir: &Path) -> io::Result<String> {
+    // symbolic-ref 在还没有提交的分支上也可以使用
+    let branch = run(dir, &["symbolic-ref", "--short", "-q", "HEAD"])
+        .or_else(|_| run(dir, &["rev-parse", "--short", "HEAD"]))?;
+    Ok(branch.trim_end_matches('\n').to_string())
+}
+
+/// 仓库的 git 目录中的文件，如 `COMMIT_EDITMSG`
+pub fn git_path(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let path = run(dir, &["rev-parse", "--git-path", name])?;
+    Ok(dir.join(path.trim_end_matches('\n')))
+}
+
+/// 工作区的修改：`staged` 为 true 时为暂存区与 HEAD 的差异，否则为工作区与暂存区的差异
+pub fn diff(dir: &Path, staged: bool) -> io::Result<String> {
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    if staged {
+        args.push("--cached");
+    }
+    run(dir, &args)
+}
+
+/// 文件所在的目录和文件名
+fn split_path(path: &Path) -> io::Result<(&Path, String)> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name().ok_or_else(|| io::Error::other("not a file"))?;
+    Ok((dir, name.to_string_lossy().into_owned()))
+}
+
+/// 文件在 HEAD 中的内容，在文件所在的目录中运行 `git show HEAD:./{file}`
+pub fn show_head(path: &Path) -> io::Result<String> {
+    let (dir, name) = split_path(path)?;
+    run(dir, &["show", &format!("HEAD:./{}", name)])
+}
+
+/// 把补丁应用到暂存区，在文件所在的目录中运行 `git apply --cached`
+///
+/// 补丁中的文件名相对于这个目录，没有上下文行（见 `git_signs::Hunk::patch`）
+pub fn apply_cached(path: &Path, patch: &str) -> io::Result<()> {
+    let (dir, _) = split_path(path)?;
+    run_with_input(dir, &["apply", "--cached", "--unidiff-zero", "-"], [patch]).map(|_| ())
+}
+
+/// 按 `contents`（缓冲区的内容）逐行 blame，返回 `git blame --porcelain` 的输出（见 blame 模块）
+///
+/// 内容按 rope 的块依次写入标准输入，不复制整个缓冲区
+pub fn blame(path: &Path, contents: &Rope) -> io::Result<String> {
+    let (dir, name) = split_path(path)?;
+    run_with_input(dir, &["blame", "--porcelain", "--contents", "-", "--", &name], contents.chunks())
+}
+
+/// 提交的详细信息和修改，在文件所在的目录中运行 `git show`
+pub fn show_commit(path: &Path, commit: &str) -> io::Result<String> {
+    let (dir, _) = split_path(path)?;
+    run(dir, &["show", "--no-color", "--format=fuller", "--stat", "--patch", commit])
+}
@@ -0,0 +1,309 @@
+//! Git 插件（内置插件，见 plugin 模块）
+//!
+//! - 差异标记 (:set gitsigns)：在后台把缓冲区与 HEAD 比较（见 git_signs 模块），`]c` / `[c` 跳到修改
+//! - `:GitPreviewHunk`、`:GitStageHunk`、`:GitRevertHunk` 预览、暂存和恢复光标所在的修改
+//! - `:GitBlame` 在行尾显示 blame 信息（见 blame 模块），显示时 Normal 模式的 Enter 打开光标所在行的提交
+//! - 状态栏的 branch 片段显示缓冲区所在仓库的当前分支
+//!
+//! 写入文件后重新与 HEAD 比较，终端重新获得焦点时重新读取 HEAD 和分支（可能在外部提交或切换过分支）。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::autocmd::Event;
+use crate::blame::{self, Blame};
+use crate::buffer::{Buffer, BufferId};
+use crate::cursor::Cursor;
+use crate::editor::Editor;
+use crate::ex_command::{spec, CommandSpec, ExCommand};
+use crate::git_signs::{self, GitSigns, Hunk, HunkPreview};
+use crate::mapping::MapKey;
+use crate::mode::Mode;
+use crate::motion::Motion;
+use crate::plugin::Plugin;
+use crate::range::LineRange;
+use crate::{tr, with_save_state};
+
+/// Git 差异标记、blame 和分支
+#[derive(Default)]
+pub struct GitPlugin {
+    /// 缓冲区与 HEAD 的差异标记
+    signs: GitSigns,
+    /// 浮动窗口中预览的修改 (:GitPreviewHunk)
+    hunk_preview: Option<HunkPreview>,
+    /// 行尾显示的 blame 信息 (:GitBlame)
+    blame: Option<Blame>,
+    /// 各目录所在仓库的当前分支，不在仓库中时为 None
+    branches: HashMap<PathBuf, Option<String>>,
+}
+
+impl GitPlugin {
+    /// 缓冲区与 HEAD 之间的修改，没有开启 gitsigns 或还没有比较完时为空
+    pub fn hunks(&self, editor: &Editor, buffer: &Buffer) -> &[Hunk] {
+        if !editor.options().gitsigns {
+            return &[];
+        }
+        self.signs.hunks(buffer.id())
+    }
+
+    /// 浮动窗口中预览的修改，光标离开打开预览时所在的行后不再显示
+    pub fn hunk_preview(&self, editor: &Editor) -> Option<&HunkPreview> {
+        self.hunk_preview.as_ref().filter(|preview| previewing(editor, preview))
+    }
+
+    /// 缓冲区的 blame 信息，缓冲区在查询之后修改过时为 None（保存后重新查询）
+    pub fn blame(&self, buffer: &Buffer) -> Option<&Blame> {
+        self.blame
+            .as_ref()
+            .filter(|blame| blame.buffer == buffer.id() && blame.tick == buffer.changedtick())
+    }
+
+    /// 缓冲区所在仓库的当前分支，文件不在仓库中或还没有查询过时为 None
+    pub fn branch(&self, buffer: &Buffer) -> Option<&str> {
+        let dir = buffer.file_path().and_then(Path::parent)?;
+        self.branches.get(dir)?.as_deref()
+    }
+
+    /// 查询当前缓冲区所在仓库的分支，每个目录只查询一次；查询到分支时返回 true（状态栏需要重绘）
+    fn update_branch(&mut self, editor: &Editor) -> bool {
+        let Some(dir) = editor.current_buffer().file_path().and_then(Path::parent).map(Path::to_path_buf) else {
+            return false;
+        };
+        if self.branches.contains_key(&dir) {
+            return false;
+        }
+        let query_dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir.as_path() };
+        let branch = crate::git::current_branch(query_dir).ok();
+        let found = branch.is_some();
+        self.branches.insert(dir, branch);
+        found
+    }
+
+    /// 当前缓冲区与 HEAD 的修改：按缓冲区现在的内容重新比较，不使用可能过时的后台结果
+    fn current_hunks(&self, editor: &Editor) -> Result<Vec<Hunk>, String> {
+        if !editor.options().gitsigns {
+            return Err(tr!(editor.locale(), "Git signs are off (:set gitsigns)"));
+        }
+        let buffer = editor.current_buffer();
+        Ok(self
+            .signs
+            .head(buffer.id())
+            .map(|head| git_signs::diff_hunks(head, buffer.rope()))
+            .unwrap_or_default())
+    }
+
+    /// 光标所在行的修改
+    fn hunk_at_cursor(&self, editor: &Editor) -> Result<Hunk, String> {
+        let hunks = self.current_hunks(editor)?;
+        git_signs::hunk_at(&hunks, editor.cursor().line)
+            .cloned()
+            .ok_or_else(|| tr!(editor.locale(), "No hunk at cursor"))
+    }
+
+    /// ]c / [c - 跳到后面 / 前面第 count 处修改的第一行
+    fn jump_to_hunk(&self, editor: &mut Editor, forward: bool, count: usize) -> Result<(), String> {
+        let hunks = self.current_hunks(editor)?;
+        let line = git_signs::hunk_line(&hunks, editor.cursor().line, forward, count)
+            .ok_or_else(|| tr!(editor.locale(), "No more hunks"))?;
+        *editor.cursor_mut() = Cursor::new(line.min(editor.current_buffer().last_content_line()), 0);
+        editor.execute_motion(Motion::FirstNonBlank);
+        Ok(())
+    }
+
+    /// :GitPreviewHunk - 在光标附近的浮动窗口中显示光标所在的修改
+    fn preview_hunk(&mut self, editor: &Editor) -> Result<(), String> {
+        let hunk = self.hunk_at_cursor(editor)?;
+        self.hunk_preview = Some(HunkPreview {
+            buffer: editor.current_buffer_id(),
+            line: editor.cursor().line,
+            hunk,
+        });
+        Ok(())
+    }
+
+    /// :GitStageHunk - 把光标所在的修改加入暂存区（缓冲区中未保存的内容也一起暂存）
+    fn stage_hunk(&self, editor: &mut Editor) -> Result<(), String> {
+        let hunk = self.hunk_at_cursor(editor)?;
+        let path = editor
+            .current_buffer()
+            .file_path()
+            .ok_or_else(|| tr!(editor.locale(), "No file name"))?
+            .to_path_buf();
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        crate::git::apply_cached(&path, &hunk.patch(&name)).map_err(|e| tr!(editor.locale(), "git failed: {}", e))?;
+        editor.set_message(tr!(editor.locale(), "Staged hunk"));
+        Ok(())
+    }
+
+    /// :GitRevertHunk - 把缓冲区中光标所在的修改恢复为 HEAD 中的内容，可以撤销
+    fn revert_hunk(&mut self, editor: &mut Editor) -> Result<(), String> {
+        let hunk = self.hunk_at_cursor(editor)?;
+        let text: String = hunk.old_lines.iter().map(|line| format!("{}\n", line)).collect();
+        with_save_state!(editor, {
+            let buffer = editor.current_buffer_mut();
+            if hunk.new_lines.is_empty() {
+                buffer.insert_lines(hunk.new_start, &text);
+            } else {
+                let range = LineRange::new(hunk.new_start, hunk.new_start + hunk.new_lines.len() - 1);
+                buffer.replace_lines(range, &text);
+            }
+            let last = editor.current_buffer().last_content_line();
+            *editor.cursor_mut() = Cursor::new(hunk.new_start.min(last), 0);
+            editor.execute_motion(Motion::FirstNonBlank);
+        });
+        self.hunk_preview = None;
+        editor.set_message(tr!(editor.locale(), "Reverted hunk"));
+        Ok(())
+    }
+
+    /// 按当前缓冲区的内容查询 blame
+    fn blame_current_buffer(editor: &Editor) -> Result<Blame, String> {
+        let buffer = editor.current_buffer();
+        let path = buffer.file_path().ok_or_else(|| tr!(editor.locale(), "No file name"))?;
+        let output =
+            crate::git::blame(path, buffer.rope()).map_err(|e| tr!(editor.locale(), "git failed: {}", e))?;
+        Ok(Blame {
+            buffer: buffer.id(),
+            tick: buffer.changedtick(),
+            lines: blame::parse_porcelain(&output),
+        })
+    }
+
+    /// :GitBlame - 在当前缓冲区的每行末尾显示 blame 信息，已经显示时关闭
+    fn toggle_blame(&mut self, editor: &Editor) -> Result<(), String> {
+        if self.blame.as_ref().is_some_and(|blame| blame.buffer == editor.current_buffer_id()) {
+            self.blame = None;
+            return Ok(());
+        }
+        self.blame = Some(Self::blame_current_buffer(editor)?);
+        Ok(())
+    }
+
+    /// 保存文件后重新查询当前缓冲区的 blame（正在显示时）
+    fn refresh_blame(&mut self, editor: &Editor) {
+        if self.blame.as_ref().is_some_and(|blame| blame.buffer == editor.current_buffer_id()) {
+            self.blame = Self::blame_current_buffer(editor).ok();
+        }
+    }
+
+    /// 显示 blame 时在新的缓冲区中打开光标所在行的提交，返回是否显示 blame
+    fn open_blame_commit(&self, editor: &mut Editor) -> Result<bool, String> {
+        let Some(line) = self
+            .blame(editor.current_buffer())
+            .and_then(|blame| blame.line(editor.cursor().line))
+            .cloned()
+        else {
+            return Ok(false);
+        };
+        if !line.is_committed() {
+            return Err(tr!(editor.locale(), "Not committed yet"));
+        }
+        let path = editor
+            .current_buffer()
+            .file_path()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| tr!(editor.locale(), "No file name"))?;
+        let details =
+            crate::git::show_commit(&path, &line.commit).map_err(|e| tr!(editor.locale(), "git failed: {}", e))?;
+        let title = format!("[GitShow] {}", line.short_commit());
+        editor.open_new_buffer(|id| {
+            let mut buffer = Buffer::scratch(id, &title, &details);
+            buffer.set_filetype("diff");
+            buffer
+        });
+        Ok(true)
+    }
+}
+
+/// 光标是否还在打开预览时所在的行
+fn previewing(editor: &Editor, preview: &HunkPreview) -> bool {
+    preview.buffer == editor.current_buffer_id() && preview.line == editor.cursor().line
+}
+
+impl Plugin for GitPlugin {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn commands(&self) -> Vec<CommandSpec> {
+        vec![
+            spec("GitPreviewHunk", 4, false, false),
+            spec("GitStageHunk", 4, false, false),
+            spec("GitRevertHunk", 4, false, false),
+            spec("GitBlame", 4, false, false),
+        ]
+    }
+
+    fn execute(&mut self, editor: &mut Editor, command: &ExCommand) -> Result<(), String> {
+        match command.name {
+            "GitPreviewHunk" => self.preview_hunk(editor),
+            "GitStageHunk" => self.stage_hunk(editor),
+            "GitRevertHunk" => self.revert_hunk(editor),
+            "GitBlame" => self.toggle_blame(editor),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_event(&mut self, editor: &mut Editor, event: Event, buffer: BufferId) {
+        match event {
+            // 写入缓冲区之后重新与 HEAD 比较，正在显示当前缓冲区的 blame 时重新查询
+            Event::BufWritePost => {
+                self.signs.reload(buffer);
+                if buffer == editor.current_buffer_id() {
+                    self.refresh_blame(editor);
+                }
+            }
+            // 可能在外部提交或切换过分支
+            Event::FocusGained => {
+                self.signs.reload_all();
+                self.branches.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /// 查询当前缓冲区的分支；开启 gitsigns 时取出后台比较的结果，
+    /// 并为新打开、刚保存或修改后停顿的缓冲区启动比较
+    fn poll(&mut self, editor: &mut Editor, now: Instant) -> bool {
+        let branch = self.update_branch(editor);
+        if !editor.options().gitsigns {
+            return branch;
+        }
+        let changed = self.signs.poll();
+        self.signs.sync(editor.buffers(), now, editor.waker());
+        branch || changed
+    }
+
+    fn on_option_set(&mut self, editor: &mut Editor) {
+        if editor.options().gitsigns {
+            self.signs.sync(editor.buffers(), Instant::now(), editor.waker());
+        } else {
+            self.signs = GitSigns::new();
+        }
+    }
+
+    fn on_key(&mut self, editor: &mut Editor, key: MapKey) -> bool {
+        if editor.mode() != Mode::Normal {
+            return false;
+        }
+        match key {
+            // Esc 关闭修改的预览
+            MapKey::Esc => self.hunk_preview.take().is_some_and(|preview| previewing(editor, &preview)),
+            // 显示 blame 时 Enter 打开光标所在行的提交
+            MapKey::Enter => self.open_blame_commit(editor).unwrap_or_else(|e| {
+                editor.set_message(e);
+                true
+            }),
+            _ => false,
+        }
+    }
+
+    fn bracket(&mut self, editor: &mut Editor, key: char, forward: bool, count: usize) -> Result<bool, String> {
+        if key != 'c' {
+            return Ok(false);
+        }
+        self.jump_to_hunk(editor, forward, count)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,336 @@
+//! Git 差异标记模块 (:set gitsigns)
+//!
+//! 比较缓冲区和文件在 HEAD 中的内容，在行号左边的标记列中显示每一行的修改：
+//! - `+` 新增的行，`~` 修改的行，`_` 下面有被删除的行，`‾` 第一行之前有被删除的行
+//! - HEAD 中的内容用 `git show HEAD:./{file}` 读取，文件不在仓库中或没有被跟踪时没有标记
+//! - 比较在后台线程中进行：打开文件和保存文件时重新读取 HEAD，修改缓冲区之后停顿一段时间时重新比较，
+//!   结果通过通道发送并唤醒事件循环，主线程调用 `GitSigns::poll` 取出
+//! - `]c` / `[c` 跳到下一处 / 上一处修改，`:GitPreviewHunk` 在浮动窗口中显示光标所在的修改，
+//!   `:GitStageHunk` 把它加入暂存区（`git apply --cached`），`:GitRevertHunk` 把缓冲区中的这处修改恢复为 HEAD 中的内容
+
+use ropey::Rope;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::buffer::{rope_lines, Buffer, BufferId};
+use crate::diff::{diff_lines, DiffOp};
+use crate::git;
+use crate::waker::Waker;
+
+/// 修改缓冲区之后停顿多久重新比较
+pub const GIT_SIGNS_DELAY: Duration = Duration::from_millis(500);
+
+/// 一行的标记
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sign {
+    /// 新增的行
+    Added,
+    /// 修改的行
+    Modified,
+    /// 这一行下面有被删除的行
+    Removed,
+    /// 第一行之前有被删除的行
+    RemovedAbove,
+}
+
+impl Sign {
+    /// 标记列中显示的字符
+    pub fn symbol(&self) -> char {
+        match self {
+            Sign::Added => '+',
+            Sign::Modified => '~',
+            Sign::Removed => '_',
+            Sign::RemovedAbove => '‾',
+        }
+    }
+}
+
+/// 缓冲区与 HEAD 之间的一处连续修改
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 在 HEAD 中的起始行（从 0 开始）
+    pub old_start: usize,
+    /// HEAD 中被删除或修改的行
+    pub old_lines: Vec<String>,
+    /// 在缓冲区中的起始行（从 0 开始），只有删除时为删除位置之后的一行
+    pub new_start: usize,
+    /// 缓冲区中新增或修改后的行
+    pub new_lines: Vec<String>,
+}
+
+impl Hunk {
+    /// 修改在缓冲区中显示标记的行范围（包含两端）
+    pub fn line_range(&self) -> (usize, usize) {
+        if self.new_lines.is_empty() {
+            let line = self.new_start.saturating_sub(1);
+            (line, line)
+        } else {
+            (self.new_start, self.new_start + self.new_lines.len() - 1)
+        }
+    }
+
+    /// 缓冲区第 `line` 行的标记：前面的行与删除的行一一对应时为修改，多出的行为新增
+    pub fn sign(&self, line: usize) -> Option<Sign> {
+        let (first, last) = self.line_range();
+        if line < first || line > last {
+            return None;
+        }
+        if self.new_lines.is_empty() {
+            return Some(if self.new_start == 0 { Sign::RemovedAbove } else { Sign::Removed });
+        }
+        if line - self.new_start < self.old_lines.len() {
+            Some(Sign::Modified)
+        } else {
+            Some(Sign::Added)
+        }
+    }
+
+    /// 预览中显示的行：被删除的行以 `-` 开头，新增的行以 `+` 开头
+    pub fn preview_lines(&self) -> Vec<String> {
+        let removed = self.old_lines.iter().map(|line| format!("-{}", line));
+        let added = self.new_lines.iter().map(|line| format!("+{}", line));
+        removed.chain(added).collect()
+    }
+
+    /// 只包含这处修改、没有上下文行的补丁，`name` 为补丁中的文件名
+    pub fn patch(&self, name: &str) -> String {
+        // 范围为空时行号是范围之前的一行
+        let range = |start: usize, len: usize| if len == 0 { format!("{},0", start) } else { format!("{},{}", start + 1, len) };
+        let mut patch = format!(
+            "--- a/{name}\n+++ b/{name}\n@@ -{} +{} @@\n",
+            range(self.old_start, self.old_lines.len()),
+            range(self.new_start, self.new_lines.len()),
+        );
+        for line in self.preview_lines() {
+            patch.push_str(&line);
+            patch.push('\n');
+        }
+        patch
+    }
+}
+
+/// 比较 HEAD 中的内容和缓冲区的文本，返回按位置排列的修改
+pub fn diff_hunks(head: &str, text: &Rope) -> Vec<Hunk> {
+    let old: Vec<&str> = head.lines().collect();
+    let new: Vec<_> = rope_lines(text).collect();
+    let new: Vec<&str> = new.iter().map(AsRef::as_ref).collect();
+    let mut hunks: Vec<Hunk> = Vec::new();
+    // 上一行是否属于正在收集的修改
+    let mut open = false;
+    let (mut old_line, mut new_line) = (0, 0);
+    for line in diff_lines(&old, &new) {
+        if line.op == DiffOp::Equal {
+            open = false;
+            old_line += 1;
+            new_line += 1;
+            continue;
+        }
+        if !open {
+            hunks.push(Hunk {
+                old_start: old_line,
+                old_lines: Vec::new(),
+                new_start: new_line,
+                new_lines: Vec::new(),
+            });
+            open = true;
+        }
+        let hunk = hunks.last_mut().unwrap();
+        match line.op {
+            DiffOp::Delete => {
+                hunk.old_lines.push(line.text.to_string());
+                old_line += 1;
+            }
+            _ => {
+                hunk.new_lines.push(line.text.to_string());
+                new_line += 1;
+            }
+        }
+    }
+    hunks
+}
+
+/// 第 `line` 行的标记
+pub fn line_sign(hunks: &[Hunk], line: usize) -> Option<Sign> {
+    hunks.iter().find_map(|hunk| hunk.sign(line))
+}
+
+/// 包含第 `line` 行的修改
+pub fn hunk_at(hunks: &[Hunk], line: usize) -> Option<&Hunk> {
+    hunks.iter().find(|hunk| hunk.sign(line).is_some())
+}
+
+/// 从第 `line` 行向后（`forward`）或向前数第 `count` 处修改的第一行，没有那么多修改时为 None
+pub fn hunk_line(hunks: &[Hunk], line: usize, forward: bool, count: usize) -> Option<usize> {
+    let starts = hunks.iter().map(|hunk| hunk.line_range().0);
+    if forward {
+        starts.filter(|&start| start > line).nth(count.saturating_sub(1))
+    } else {
+        starts.rev().filter(|&start| start < line).nth(count.saturating_sub(1))
+    }
+}
+
+/// 修改的预览 (:GitPreviewHunk)，光标离开打开预览时所在的行后不再显示
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkPreview {
+    pub buffer: BufferId,
+    pub line: usize,
+    pub hunk: Hunk,
+}
+
+/// 一个缓冲区的比较状态
+#[derive(Debug)]
+struct BufferState {
+    /// 文件在 HEAD 中的内容，文件没有被跟踪时为 None
+    head: Option<Arc<str>>,
+    hunks: Vec<Hunk>,
+    /// 结果对应的 changedtick
+    tick: Option<u64>,
+    /// 最近一次看到的 changedtick 和看到它的时间
+    seen: (u64, Instant),
+    /// 后台线程正在比较
+    pending: bool,
+    /// 下一次比较前重新读取 HEAD
+    reload: bool,
+}
+
+/// 后台线程的比较结果
+struct Update {
+    buffer: BufferId,
+    tick: u64,
+    head: Option<Arc<str>>,
+    hunks: Vec<Hunk>,
+}
+
+/// 所有缓冲区的差异标记
+pub struct GitSigns {
+    buffers: HashMap<BufferId, BufferState>,
+    sender: Sender<Update>,
+    receiver: Receiver<Update>,
+}
+
+impl Default for GitSigns {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            buffers: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl std::fmt::Debug for GitSigns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitSigns").field("buffers", &self.buffers).finish()
+    }
+}
+
+impl GitSigns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 缓冲区的修改（还没有比较过时为空）
+    pub fn hunks(&self, buffer: BufferId) -> &[Hunk] {
+        self.buffers.get(&buffer).map(|state| state.hunks.as_slice()).unwrap_or_default()
+    }
+
+    /// 文件在 HEAD 中的内容，还没有读取或文件没有被跟踪时为 None
+    pub fn head(&self, buffer: BufferId) -> Option<&str> {
+        self.buffers.get(&buffer).and_then(|state| state.head.as_deref())
+    }
+
+    /// 保存文件后重新读取 HEAD 并比较
+    pub fn reload(&mut self, buffer: BufferId) {
+        if let Some(state) = self.buffers.get_mut(&buffer) {
+            state.reload = true;
+        }
+    }
+
+    /// 重新读取所有缓冲区的 HEAD（终端重新获得焦点时，可能在外部提交过）
+    pub fn reload_all(&mut self) {
+        for state in self.buffers.values_mut() {
+            state.reload = true;
+        }
+    }
+
+    /// 为需要比较的文件缓冲区启动后台比较：新打开的、需要重新读取 HEAD 的，
+    /// 以及修改之后已经停顿了 `GIT_SIGNS_DELAY` 的缓冲区；同时丢弃已经关闭的缓冲区的状态
+    pub fn sync<'a>(&mut self, buffers: impl Iterator<Item = &'a Buffer>, now: Instant, waker: &Waker) {
+        let mut open = Vec::new();
+        for buffer in buffers {
+            let Some(path) = buffer.file_path() else {
+                continue;
+            };
+            open.push(buffer.id());
+            let tick = buffer.changedtick();
+            let state = self.buffers.entry(buffer.id()).or_insert_with(|| BufferState {
+                head: None,
+                hunks: Vec::new(),
+                tick: None,
+                seen: (tick, now),
+                pending: false,
+                reload: true,
+            });
+            if state.seen.0 != tick {
+                state.seen = (tick, now);
+            }
+            if state.pending {
+                continue;
+            }
+            let idle = state.tick != Some(tick) && now.duration_since(state.seen.1) >= GIT_SIGNS_DELAY;
+            if !state.reload && !idle {
+                continue;
+            }
+            let head = if state.reload { None } else { state.head.clone() };
+            state.reload = false;
+            state.pending = true;
+            spawn_diff(buffer.id(), tick, path.to_path_buf(), head, buffer.rope().clone(), self.sender.clone(), waker);
+        }
+        self.buffers.retain(|id, _| open.contains(id));
+    }
+
+    /// 取出后台比较的结果，有缓冲区的标记更新时返回 true
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(update) = self.receiver.try_recv() {
+            let Some(state) = self.buffers.get_mut(&update.buffer) else {
+                continue;
+            };
+            state.pending = false;
+            state.tick = Some(update.tick);
+            state.head = update.head;
+            if state.hunks != update.hunks {
+                state.hunks = update.hunks;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// 在后台线程中比较：`head` 为 None 时先读取文件在 HEAD 中的内容
+///
+/// `text` 是缓冲区的 rope 的副本，与缓冲区共享节点，不复制文本
+fn spawn_diff(
+    buffer: BufferId,
+    tick: u64,
+    path: PathBuf,
+    head: Option<Arc<str>>,
+    text: Rope,
+    sender: Sender<Update>,
+    waker: &Waker,
+) {
+    let waker = waker.clone();
+    thread::spawn(move || {
+        let head = head.or_else(|| git::show_head(&path).ok().map(Arc::from));
+        let hunks = head.as_deref().map(|head| diff_hunks(head, &text)).unwrap_or_default();
+        if sender.send(Update { buffer, tick, head, hunks }).is_ok() {
+            waker.wake();
+        }
+    });
+}
@@ -0,0 +1,153 @@
+//! 工作区搜索模块 (:grep)
+//!
+//! 在目录下的所有文件中搜索字符串，结果作为 Quickfix 列表的项：
+//! - 文件列表与模糊文件选择器相同（遵守 `.gitignore`，跳过隐藏文件，见 finder 模块）
+//! - 与 / 搜索相同，按字面字符串匹配，区分大小写
+//! - 跳过二进制文件（开头包含 NUL 字节）和非 UTF-8 文件
+//! - 后台线程逐个文件搜索，按批通过通道发送结果并唤醒事件循环，主线程调用 `GrepSearch::poll` 取出
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::finder::{walk_files, MAX_FINDER_FILES};
+use crate::hex;
+use crate::quickfix::QuickfixEntry;
+use crate::waker::Waker;
+
+/// 最多保留的匹配数
+pub const MAX_GREP_MATCHES: usize = 10_000;
+
+/// 在一个文件的内容中搜索，每个匹配的行一项，列为该行第一个匹配的位置
+pub fn grep_text(path: &Path, text: &str, pattern: &str) -> Vec<QuickfixEntry> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, content)| {
+            let byte = content.find(pattern)?;
+            let column = content[..byte].chars().count();
+            Some(QuickfixEntry::new(path, line, column, content.trim()))
+        })
+        .collect()
+}
+
+/// 搜索一个文件，无法读取或为二进制文件时没有结果
+pub fn grep_file(path: &Path, pattern: &str) -> Vec<QuickfixEntry> {
+    let Ok(bytes) = fs::read(path) else {
+        return Vec::new();
+    };
+    if hex::is_binary(&bytes) {
+        return Vec::new();
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => grep_text(path, &text, pattern),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 遍历 `root` 下的文件搜索 `pattern`，对每个有匹配的文件调用 `visit`，`visit` 返回 false 时停止
+///
+/// 结果中的路径为 `root` 与文件相对路径的拼接
+fn grep_walk(root: &Path, pattern: &str, visit: &mut dyn FnMut(Vec<QuickfixEntry>) -> bool) {
+    let mut files = 0;
+    let mut matches = 0;
+    walk_files(root, &mut |relative| {
+        files += 1;
+        let mut entries = grep_file(&root.join(&relative), pattern);
+        // 显示为相对于搜索目录的路径
+        for entry in &mut entries {
+            entry.path = join_display(root, &relative);
+        }
+        entries.truncate(MAX_GREP_MATCHES - matches);
+        matches += entries.len();
+        if !entries.is_empty() && !visit(entries) {
+            return false;
+        }
+        matches < MAX_GREP_MATCHES && files < MAX_FINDER_FILES
+    });
+}
+
+/// `root` 为 `.` 时省略，使路径显示为 `src/main.rs` 而不是 `./src/main.rs`
+fn join_display(root: &Path, relative: &Path) -> PathBuf {
+    if root == Path::new(".") {
+        relative.to_path_buf()
+    } else {
+        root.join(relative)
+    }
+}
+
+/// 同步搜索 `root` 下的所有文件
+pub fn grep_files(root: &Path, pattern: &str) -> Vec<QuickfixEntry> {
+    let mut result = Vec::new();
+    grep_walk(root, pattern, &mut |entries| {
+        result.extend(entries);
+        true
+    });
+    result
+}
+
+/// 在后台线程中进行的搜索
+///
+/// 丢弃 GrepSearch 后通道断开，后台线程在发送下一批结果时停止
+#[derive(Debug)]
+pub struct GrepSearch {
+    pattern: String,
+    receiver: Receiver<Vec<QuickfixEntry>>,
+    done: bool,
+}
+
+impl GrepSearch {
+    /// 开始在后台搜索 `root` 下的文件
+    pub fn start(root: PathBuf, pattern: &str) -> Self {
+        Self::start_with_waker(root, pattern, Waker::default())
+    }
+
+    /// 开始在后台搜索，每发送一批结果和搜索结束时调用 `waker`
+    pub fn start_with_waker(root: PathBuf, pattern: &str, waker: Waker) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let walk_pattern = pattern.to_string();
+        thread::spawn(move || {
+            grep_walk(&root, &walk_pattern, &mut |entries| {
+                let sent = sender.send(entries).is_ok();
+                waker.wake();
+                sent
+            });
+            // 通道在这里断开，唤醒事件循环以便发现搜索已经结束
+            drop(sender);
+            waker.wake();
+        });
+        Self {
+            pattern: pattern.to_string(),
+            receiver,
+            done: false,
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// 搜索是否已经结束
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// 取出目前已找到的匹配
+    pub fn poll(&mut self) -> Vec<QuickfixEntry> {
+        let mut entries = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(batch) => entries.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        entries
+    }
+}
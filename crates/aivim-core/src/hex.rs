@@ -0,0 +1,90 @@
+//! 二进制文件的十六进制视图
+//!
+//! 包含 NUL 字节的文件不按文本读入，缓冲区中是类似 xxd 的十六进制转储，写入时再转换回字节：
+//! `00000010: 4865 6c6c 6f00 0102 0304 0506 0708 090a  Hello...........`
+//! - 每行 16 个字节：偏移、十六进制列、ASCII 列，偏移和 ASCII 列只用于显示，写入时只读取十六进制列
+//! - 修改十六进制数字（如 `r`、`R`）即修改字节，在十六进制列中删除或插入成对的数字即删除或插入字节
+//! - 写入后重新生成转储，更新偏移和 ASCII 列
+
+use std::fmt::Write;
+
+use crate::encoding;
+
+/// 每行显示的字节数
+pub const BYTES_PER_LINE: usize = 16;
+
+/// 检查是否为二进制文件时读取的字节数
+const BINARY_CHECK_LEN: usize = 8192;
+
+/// 十六进制列的宽度：每 2 个字节一组，组之间一个空格
+const HEX_WIDTH: usize = BYTES_PER_LINE / 2 * 5 - 1;
+
+/// 文件开头是否包含 NUL 字节，带 UTF-16 BOM 的文本文件除外
+pub fn is_binary(bytes: &[u8]) -> bool {
+    !encoding::has_utf16_bom(bytes) && bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0)
+}
+
+/// 生成十六进制转储，每行以换行符结尾
+pub fn dump(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for (index, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let mut hex = String::new();
+        for (i, byte) in chunk.iter().enumerate() {
+            if i > 0 && i % 2 == 0 {
+                hex.push(' ');
+            }
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        let _ = writeln!(text, "{:08x}: {:<width$}  {}", index * BYTES_PER_LINE, hex, ascii, width = HEX_WIDTH);
+    }
+    text
+}
+
+/// 一行转储的十六进制列：偏移之后到第一个连续两个空格之前
+fn hex_column(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once(": ")?;
+    Some(rest.split("  ").next().unwrap_or(""))
+}
+
+/// 从转储的十六进制列读回字节，空行被忽略
+///
+/// 某行没有偏移、十六进制数字不成对或含有其他字符时返回该行的行号（从 0 开始）
+pub fn parse(text: &str) -> Result<Vec<u8>, usize> {
+    let mut bytes = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let hex = hex_column(line).ok_or(line_idx)?;
+        let digits: Vec<u32> = hex
+            .chars()
+            .filter(|c| *c != ' ')
+            .map(|c| c.to_digit(16).ok_or(line_idx))
+            .collect::<Result<_, _>>()?;
+        if !digits.len().is_multiple_of(2) {
+            return Err(line_idx);
+        }
+        bytes.extend(digits.chunks(2).map(|pair| (pair[0] * 16 + pair[1]) as u8));
+    }
+    Ok(bytes)
+}
+
+/// 光标所在字节在文件中的偏移：按行首的偏移加上该行十六进制列中光标之前的数字个数计算
+///
+/// 光标不在十六进制数字上时返回 None
+pub fn byte_offset(line: &str, column: usize) -> Option<usize> {
+    let (prefix, _) = line.split_once(": ")?;
+    let offset = usize::from_str_radix(prefix, 16).ok()?;
+    let hex = hex_column(line)?;
+    let column = column.checked_sub(prefix.chars().count() + 2)?;
+    let under_cursor = hex.chars().nth(column)?;
+    if !under_cursor.is_ascii_hexdigit() {
+        return None;
+    }
+    let digits = hex.chars().take(column).filter(char::is_ascii_hexdigit).count();
+    Some(offset + digits / 2)
+}
@@ -0,0 +1,119 @@
+//! 命令行历史模块
+//!
+//! 记录执行过的 `:` 命令和 `/` `?` 搜索，Command / 搜索模式下用上下方向键浏览：
+//! - 重复的条目只保留最新的一次
+//! - 浏览时只显示以开始浏览时已输入的文本为前缀的条目（输入 `:b` 再按上方向键只显示缓冲区命令）
+//! - 向下越过最新的条目时恢复开始浏览前输入的文本
+
+/// 每种历史保留的条目数
+pub const DEFAULT_HISTORY_SIZE: usize = 100;
+
+/// 一种命令行历史（`:` 命令或搜索）
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    /// 从旧到新排列的条目
+    entries: Vec<String>,
+    capacity: usize,
+    browse: Option<Browse>,
+}
+
+/// 正在浏览的状态
+#[derive(Debug, Clone, PartialEq)]
+struct Browse {
+    /// 当前显示的条目，等于 entries.len() 表示显示开始浏览前输入的文本
+    index: usize,
+    /// 开始浏览前输入的文本，也是过滤条目的前缀
+    typed: String,
+    /// 最后一次显示在命令行的文本，命令行被修改后重新开始浏览
+    shown: String,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity,
+            browse: None,
+        }
+    }
+
+    /// 从旧到新排列的条目
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// 添加一条历史，空白的条目被忽略，并结束浏览
+    pub fn push(&mut self, entry: &str) {
+        self.browse = None;
+        if entry.trim().is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e != entry);
+        self.entries.push(entry.to_string());
+        if self.entries.len() > self.capacity {
+            let excess = self.entries.len() - self.capacity;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// 结束浏览，下次浏览以当时的命令行为前缀
+    pub fn reset(&mut self) {
+        self.browse = None;
+    }
+
+    /// 上一条匹配前缀的历史，`current` 为命令行当前的文本
+    ///
+    /// 没有更早的匹配条目时返回 None，命令行保持不变
+    pub fn prev(&mut self, current: &str) -> Option<String> {
+        let browse = self.browse_from(current);
+        match self.entries[..browse.index].iter().rposition(|e| e.starts_with(&browse.typed)) {
+            Some(index) => Some(self.show(browse, index)),
+            None => {
+                self.browse = Some(browse);
+                None
+            }
+        }
+    }
+
+    /// 下一条匹配前缀的历史，越过最新的条目时返回开始浏览前输入的文本
+    ///
+    /// 没有在浏览时返回 None
+    pub fn next(&mut self, current: &str) -> Option<String> {
+        let browse = self.browse_from(current);
+        if browse.index >= self.entries.len() {
+            self.browse = Some(browse);
+            return None;
+        }
+        let index = self.entries[browse.index + 1..]
+            .iter()
+            .position(|e| e.starts_with(&browse.typed))
+            .map_or(self.entries.len(), |offset| browse.index + 1 + offset);
+        Some(self.show(browse, index))
+    }
+
+    /// 继续当前的浏览；命令行在浏览之后被修改过时以新的文本重新开始
+    fn browse_from(&mut self, current: &str) -> Browse {
+        match self.browse.take() {
+            Some(browse) if browse.shown == current => browse,
+            _ => Browse {
+                index: self.entries.len(),
+                typed: current.to_string(),
+                shown: current.to_string(),
+            },
+        }
+    }
+
+    fn show(&mut self, mut browse: Browse, index: usize) -> String {
+        browse.index = index;
+        browse.shown = self.entries.get(index).unwrap_or(&browse.typed).clone();
+        let text = browse.shown.clone();
+        self.browse = Some(browse);
+        text
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_SIZE)
+    }
+}
@@ -0,0 +1,375 @@
+//! 界面文本本地化模块
+//!
+//! 所有用户可见的消息都通过消息目录翻译（见 `tr!` 宏）：
+//! - 英文原文作为消息键，目录中没有的消息原样显示英文
+//! - 语言可以通过 `:set lang=zh` 设置，默认从 LC_ALL / LC_MESSAGES / LANG 环境变量读取
+//! - 模板中的 `{}` 依次替换为参数，`{0}`、`{1}` 按位置替换（译文语序不同时使用）
+
+use std::env;
+use std::fmt::Display;
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Locale {
+    /// 解析语言名，接受 `en`、`zh` 以及 `zh_CN.UTF-8` 这样的 locale 字符串
+    pub fn parse(name: &str) -> Option<Self> {
+        let language = name
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "zh" => Some(Locale::Zh),
+            _ => None,
+        }
+    }
+
+    /// 从环境变量推断语言，按 LC_ALL、LC_MESSAGES、LANG 的顺序取第一个非空值
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    /// 语言代码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Zh => "zh",
+        }
+    }
+}
+
+/// 翻译消息，目录中没有译文时返回原文
+pub fn translate(locale: Locale, msg: &'static str) -> &'static str {
+    match locale {
+        Locale::En => msg,
+        Locale::Zh => zh(msg).unwrap_or(msg),
+    }
+}
+
+/// 用参数替换模板中的 `{}`（依次）和 `{N}`（按位置），其他花括号原样保留
+pub fn format_message(template: &str, args: &[&dyn Display]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let index = after.find('}').and_then(|close| {
+            let inner = &after[..close];
+            let index = if inner.is_empty() {
+                let index = next;
+                next += 1;
+                index
+            } else {
+                inner.parse::<usize>().ok()?
+            };
+            Some((index, close))
+        });
+        match index {
+            Some((index, close)) if index < args.len() => {
+                output.push_str(&args[index].to_string());
+                rest = &after[close + 1..];
+            }
+            _ => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 中文消息目录
+fn zh(msg: &str) -> Option<&'static str> {
+    let text = match msg {
+        // 文件
+        "Saved" => "已保存",
+        "No file name" => "没有文件名",
+        "Filename required" => "需要文件名",
+        "Opened {}" => "已打开 {}",
+        "Failed to open {}: {}" => "无法打开 {}: {}",
+        "Loading {}..." => "正在读入 {}...",
+        "Loading {} {}%" => "正在读入 {} {}%",
+        "Renamed to {}" => "已重命名为 {}",
+        "Failed to rename {}: {}" => "无法重命名 {}: {}",
+        "Deleted {}" => "已删除 {}",
+        "Moved {} to trash" => "已将 {} 移到回收站",
+        "Failed to delete {}: {}" => "无法删除 {}: {}",
+        "Delete file {}? (y/n)" => "删除文件 {}？(y/n)",
+        "No write since last change" => "有未保存的修改",
+        "No changes" => "没有修改",
+        "No write since last change (add ! to override)" => "有未保存的修改（加 ! 强制执行）",
+        "{} is a protected path, write anyway? (y/n)" => "{} 是受保护的路径，仍然写入？(y/n)",
+        "{} is a protected path (use :w! to write)" => "{} 是受保护的路径（使用 :w! 写入）",
+        "Cancelled" => "已取消",
+        "{} is read-only, write anyway? (y/n)" => "{} 是只读的，仍然写入？(y/n)",
+        "Save changes to {}? (y/n/a/q)" => "保存对 {} 的修改？(y/n/a/q)",
+        "Cannot save {}: {}" => "无法保存 {}: {}",
+        "Yes" => "是",
+        "No" => "否",
+        "All" => "全部",
+        "Quit" => "取消",
+        "Last" => "替换后停止",
+        "Replace with {}? (y/n/a/q/l)" => "替换为 {}？(y/n/a/q/l)",
+        "\"{}\" {}L written" => "已写入 \"{}\" {} 行",
+        "\"{}\" {}L appended" => "已追加到 \"{}\" {} 行",
+        "\"{}\" {}L read" => "已读入 \"{}\" {} 行",
+        "Use ! to write partial buffer" => "写入部分缓冲区需要加 !",
+        "File exists (add ! to override)" => "文件已存在（加 ! 覆盖）",
+
+        // 缓冲区
+        "[Buffer {}]" => "[缓冲区 {}]",
+        "No buffers" => "没有缓冲区",
+        "Buffer list:" => "缓冲区列表:",
+        "Buffer {} does not exist" => "缓冲区 {} 不存在",
+        "No other buffers" => "没有其他缓冲区",
+        "Buffer {} has unsaved changes (use :bd! to force)" => "缓冲区 {} 有未保存的修改，请使用 :bd! 强制删除",
+        "Buffer ID required" => "需要缓冲区 ID",
+        "Invalid buffer ID" => "无效的缓冲区 ID",
+        "Switched to buffer {}" => "已切换到缓冲区 {}",
+        "Deleted buffer {}" => "已删除缓冲区 {}",
+        "Created new buffer {}" => "已创建缓冲区 {}",
+
+        // 命令与选项
+        "Unknown command: {}" => "未知命令: {}",
+        "Not an editor command: {}" => "不是编辑器命令: {}",
+        "Argument required" => "需要参数",
+        "Unknown option: {}" => "未知选项: {}",
+        "Invalid value for {}: {}" => "{} 的值无效: {}",
+        "Unknown variable: {}" => "未知的变量: {}",
+        "Error in {} line {}: {}" => "{} 第 {} 行出错: {}",
+        "Error in {}: {}" => "{} 出错: {}",
+        "Searching for {}..." => "正在搜索 {}...",
+        "No matches for {}" => "没有找到 {}",
+        "{} matches for {}" => "找到 {} 处 {}",
+        "Quickfix list is empty" => "Quickfix 列表为空",
+        "No more items" => "没有更多的项",
+        "({} of {}): {}" => "({}/{}): {}",
+        "Quickfix" => "Quickfix",
+        "Location List" => "位置列表",
+        "No location list" => "没有位置列表",
+        "Location list is empty" => "位置列表为空",
+        "{} finished ({}): {} errors" => "{} 已结束 ({}): {} 个错误",
+        "Files" => "文件",
+        "Not a directory: {}" => "不是目录: {}",
+        "Cannot close last tab page" => "不能关闭最后一个标签页",
+        "Cannot close last window" => "不能关闭最后一个窗口",
+        "Cannot find color scheme: {}" => "找不到配色方案: {}",
+        "Unknown highlight group: {}" => "未知的高亮组: {}",
+        "Invalid argument: {}" => "无效的参数: {}",
+        "Enabled line numbers" => "已显示行号",
+        "Disabled line numbers" => "已隐藏行号",
+        "Enabled relative line numbers" => "已显示相对行号",
+        "Disabled relative line numbers" => "已隐藏相对行号",
+        "Enabled cursor line highlighting" => "已高亮当前行",
+        "Disabled cursor line highlighting" => "已取消高亮当前行",
+        "Enabled cursor column highlighting" => "已高亮光标所在列",
+        "Disabled cursor column highlighting" => "已取消高亮光标所在列",
+        "Enabled auto-indent" => "已开启自动缩进",
+        "Disabled auto-indent" => "已关闭自动缩进",
+        "Paste mode on" => "粘贴模式已开启",
+        "Paste mode off" => "粘贴模式已关闭",
+        "Enabled paste detection" => "已开启粘贴检测",
+        "Disabled paste detection" => "已关闭粘贴检测",
+        "Paste detected, auto-indent paused" => "检测到粘贴，已暂停自动缩进",
+        "Deleted files will be moved to trash" => "删除的文件将移到回收站",
+        "Deleted files will be removed permanently" => "删除的文件将被永久删除",
+        "Undo history will be saved to {}" => "撤销历史将保存到 {}",
+        "Undo history will not be saved" => "撤销历史将不再保存",
+        "Language set to {}" => "界面语言已设置为 {}",
+        "Invalid range" => "无效的范围",
+        "Backwards range given" => "范围的起始行在结束行之后",
+        "No range allowed: {}" => "命令不接受范围: {}",
+        "No ! allowed: {}" => "命令不接受 !: {}",
+        "Ambiguous command: {}" => "有歧义的命令缩写: {}",
+        "Unterminated quote" => "引号没有闭合",
+        "Too many arguments" => "参数过多",
+        "Using ASCII glyphs" => "界面使用 ASCII 字符",
+        "Using Unicode glyphs" => "界面使用 Unicode 字符",
+
+        // 搜索与替换
+        "Pattern not found" => "找不到匹配",
+        "Replaced {} occurrence(s)" => "已替换 {} 处",
+        "Invalid substitute command" => "无效的替换命令",
+        "Pattern not found: {}" => "找不到匹配: {}",
+        "No previous search pattern" => "没有上一次搜索的模式",
+        "Invalid search offset: {}" => "无效的搜索偏移: {}",
+        "No string under cursor" => "光标下没有字符串",
+        "No more matches: {}" => "没有更多匹配: {}",
+        "No previous Visual selection" => "没有上一次的选择区域",
+        "{} match(es) on {} line(s)" => "{} 处匹配，共 {} 行",
+        "match {} of {}" => "第 {} 个匹配，共 {} 个",
+
+        // 撤销
+        "Already at oldest change" => "已经是最早的修改",
+        "Already at newest change" => "已经是最新的修改",
+        "Nothing to undo" => "没有可撤销的修改",
+        "number changes when" => "编号 修改次数 时间",
+        "{}s ago" => "{} 秒前",
+
+        // 寄存器
+        "{} line(s) yanked" => "已复制 {} 行",
+        "{} line(s) deleted" => "已删除 {} 行",
+        "{} line(s) moved" => "已移动 {} 行",
+        "Cannot move a range of lines into itself" => "不能把行移动到自身范围内",
+        "Invalid address" => "无效的地址",
+        "Invalid register: {}" => "无效的寄存器: {}",
+        "No registers" => "没有寄存器",
+        "Invalid expression: {}" => "无效的表达式: {}",
+        "Division by zero" => "除数为零",
+        "Registers:" => "寄存器:",
+        " (linewise)" => "（整行）",
+
+        // 行尾符
+        "All lines already use {}" => "所有行已使用 {}",
+        "Invalid line ending: {} (use lf or crlf)" => "无效的行尾符: {}（使用 lf 或 crlf）",
+        "Convert {} line(s) to {}? (y/n)" => "将 {} 行转换为 {}？(y/n)",
+        "{} character(s) cannot be encoded as {}, replace them with '?'? (y/n)" => "{} 个字符无法以 {} 编码，替换为 '?'？(y/n)",
+        "Cannot encode {} character(s) as {} (use :w to replace them)" => "{} 个字符无法以 {} 编码（使用 :w 替换它们）",
+        "{} character(s) could not be decoded" => "{} 个字符无法解码",
+        "Binary file: editing as hex dump" => "二进制文件：以十六进制转储编辑",
+        "{} has changed on disk since reading it, overwrite it? (y/n)" => "{} 在读入后已被外部修改，是否覆盖？(y/n)",
+        "{} has changed on disk (:e! to reload, :w! to overwrite)" => "{} 已被外部修改（:e! 重新读入，:w! 覆盖）",
+        "Reloaded {}" => "已重新读入 {}",
+        "{} changed on disk, reloaded" => "{} 已被外部修改，已重新读入",
+        "Files changed on disk will be reloaded automatically" => "被外部修改的文件将自动重新读入",
+        "Files changed on disk will not be reloaded" => "被外部修改的文件不再自动重新读入",
+        "No matching buffer for {}" => "没有匹配 {} 的缓冲区",
+        "Session saved to {}" => "会话已保存到 {}",
+        "Unknown function: {}" => "未知的函数: {}",
+        "Failed to write {}: {}" => "无法写入 {}: {}",
+        "Failed to change directory to {}: {}" => "无法切换到目录 {}: {}",
+        "Buffer is read-only (add ! to override)" => "缓冲区是只读的（加 ! 强制写入）",
+        "AI completion enabled ({})" => "已开启 AI 补全（{}）",
+        "AI completion disabled" => "已关闭 AI 补全",
+        "AI completion failed: {}" => "AI 补全失败: {}",
+        "AI chat failed: {}" => "AI 对话失败: {}",
+        "AI is still responding" => "AI 仍在回复",
+        "AI edit failed: {}" => "AI 编辑失败: {}",
+        "AI explain failed: {}" => "AI 解释失败: {}",
+        "git failed: {}" => "git 失败: {}",
+        "No changes to commit" => "没有要提交的修改",
+        "AI commit message failed: {}" => "AI 生成提交信息失败: {}",
+        "Waiting for AI commit message..." => "正在等待 AI 生成提交信息...",
+        "AI returned an empty commit message" => "AI 返回了空的提交信息",
+        "Buffer changed, AI commit message discarded" => "缓冲区已切换，丢弃 AI 生成的提交信息",
+        "Inserted AI commit message" => "已插入 AI 生成的提交信息",
+        "Nothing to explain under the cursor" => "光标下没有可以解释的内容",
+        "Waiting for AI edit of {} line(s)..." => "正在等待 AI 修改 {} 行...",
+        "AI suggested no changes" => "AI 没有修改",
+        "Apply AI edit to lines {}-{}? (y/n)" => "把 AI 的修改应用到第 {}-{} 行? (y/n)",
+        "Applied AI edit to {} line(s)" => "已把 AI 的修改应用到 {} 行",
+        "Buffer changed, AI edit discarded" => "缓冲区已修改，丢弃 AI 的修改",
+        "Converted {} line(s) to {}" => "已将 {} 行转换为 {}",
+
+        // 语言服务器
+        "Language servers enabled" => "已开启语言服务器",
+        "Language servers stopped" => "已关闭语言服务器",
+        "Failed to start language server {}" => "无法启动语言服务器 {}",
+        "Language server {} exited" => "语言服务器 {} 已退出",
+        "Completion failed: {}" => "补全失败：{}",
+
+        // 折叠
+        "No fold found" => "没有找到折叠",
+        "Cannot create fold with foldmethod={}" => "foldmethod={} 时不能创建折叠",
+        "Cannot delete fold with foldmethod={}" => "foldmethod={} 时不能删除折叠",
+        "lines: {}" => "行: {}",
+
+        // 包围
+        "Invalid surround character: {}" => "无效的包围字符：{}",
+        "No surrounding {} found" => "没有找到包围光标的 {}",
+
+        // Git 差异标记
+        "Git signs are off (:set gitsigns)" => "没有开启 Git 差异标记（:set gitsigns）",
+        "No more hunks" => "没有更多修改",
+        "No hunk at cursor" => "光标所在的行没有修改",
+        "Staged hunk" => "已暂存修改",
+        "Reverted hunk" => "已撤销修改",
+        "Hunk" => "修改",
+        "Not committed yet" => "还没有提交",
+
+        // 文件跟踪
+        "Following {} (:tail to stop)" => "正在跟踪 {}（:tail 停止）",
+        "Stopped following" => "已停止跟踪",
+
+        // 符号
+        "Symbols" => "符号",
+        "Workspace Symbols" => "工作区符号",
+        "No symbols found" => "没有找到符号",
+        "Invalid symbols scope: {}" => "无效的符号范围: {}",
+        "No matches" => "没有匹配项",
+
+        // 按键映射
+        "No mapping found" => "没有找到映射",
+        "No autocommands found" => "没有找到自动命令",
+        "No such event: {}" => "没有这个事件: {}",
+        "Error in {} autocommand: {}" => "{} 自动命令出错: {}",
+        "Plugin commands must start with an uppercase letter: {}" => "插件的命令必须以大写字母开头: {}",
+        "Command already exists: {}" => "命令已存在: {}",
+        "Plugin is already running: {}" => "插件正在执行: {}",
+        "No such mapping: {}" => "没有这个映射: {}",
+        "Recursive mapping" => "映射递归过深",
+        "Recursive use of :normal too deep" => ":normal 嵌套过深",
+
+        // 宏
+        "No previously used register" => "没有上一次执行的宏",
+        "Recursive macro too deep" => "宏嵌套过深",
+
+        // 后台任务
+        "No jobs" => "没有任务",
+        "Jobs:" => "任务:",
+        "No job {}" => "没有任务 {}",
+        "Job {} is not running" => "任务 {} 没有在运行",
+        "Job ID required" => "需要任务 ID",
+        "Invalid job ID: {}" => "无效的任务 ID: {}",
+        "Killed job {}" => "已终止任务 {}",
+        "Cleared {} finished job(s)" => "已清除 {} 个已结束的任务",
+        "Usage: :job kill {id} | :job clear" => "用法: :job kill {id} | :job clear",
+        "Job {} ({}) {}" => "任务 {} ({}) {}",
+        "Running {}..." => "正在运行 {}...",
+        "Failed to run {}: {}" => "无法运行 {}: {}",
+        "Shell command finished: {}" => "外部命令已结束: {}",
+        "Shell command failed ({}): {}" => "外部命令失败 ({}): {}",
+        "Shell command still running: {}" => "外部命令还在运行: {}",
+        "Shell returned {}" => "shell 返回 {}",
+        "Cannot suspend: {}" => "无法挂起: {}",
+        "Terminal error: {}" => "终端错误: {}",
+        "Terminal process has exited" => "终端中的程序已退出",
+        "[Process exited {}]" => "[程序已退出 {}]",
+        "Job still running in buffer {} (use :bd! to end it)" => "缓冲区 {} 中的程序还在运行（使用 :bd! 结束）",
+        "Buffer changed, output of {} discarded" => "缓冲区已切换，丢弃 {} 的输出",
+        "{} line(s) read" => "已读入 {} 行",
+        "{} line(s) filtered" => "已过滤 {} 行",
+        "running" => "运行中",
+        "exit {}" => "退出码 {}",
+        "terminated" => "已终止",
+        "killed" => "已杀死",
+
+        // 界面
+        "[No Name]" => "[未命名]",
+        "Preview" => "预览",
+        "Registers (press q or Esc to close)" => "寄存器（按 q 或 Esc 关闭）",
+        "Jobs (press q or Esc to close)" => "任务（按 q 或 Esc 关闭）",
+        "Options (press q or Esc to close)" => "选项（按 q 或 Esc 关闭）",
+        "Buffer List" => "缓冲区列表",
+        "File name" => "文件名",
+        "Hint: " => "提示: ",
+        "press q or Esc to close, " => "按 q 或 Esc 关闭, ",
+        "switch buffer" => "切换缓冲区",
+        _ => return None,
+    };
+    Some(text)
+}
@@ -0,0 +1,1072 @@
+//! 按键处理模块
+//!
+//! 把按键按编辑器当前的模式交给编辑器执行，不依赖终端，界面和脚本（`Editor::run_script`）共用：
+//! - 先匹配用户映射：匹配到映射前缀时等待后续按键，`timeoutlen` 超时后由界面调用 `KeyInput::timeout`
+//! - Normal 模式的计数、寄存器、操作符和动作由 `KeyParser` 解析
+//! - 确认提示、模糊选择器、各种列表面板打开时按键优先交给它们
+//! - `:normal` 的按键也在这里回放
+//! - 有多个光标时，动作、操作符和插入的文本交给 `Editor::for_each_cursor` 对每个光标执行
+//! - Visual 模式的动作同样由 `KeyParser` 解析，作用于选择区域的按键见 `visual` 模块
+//!
+//! 滚动窗口、退出等与屏幕有关的处理由调用方在每个按键之后进行。
+
+use std::time::Instant;
+
+use crate::cursor::Cursor;
+use crate::editor::{ConfirmAnswer, Editor, NormalRequest, ScrollAnchor};
+use crate::keymap::{KeyParser, NormalCommand, Operator, OperatorCommand, ParseResult};
+use crate::mapping::{MapKey, MapLookup, MapMode, Mapping, MAX_MAP_DEPTH};
+use crate::mode::Mode;
+use crate::motion::Motion;
+use crate::search::SearchDirection;
+use crate::window::SplitDirection;
+
+/// 按键处理的状态：未完成的按键序列、等待映射的按键等
+#[derive(Debug)]
+pub struct KeyInput {
+    key_parser: KeyParser,
+    /// 匹配到映射前缀、等待后续按键的输入
+    pending_keys: Vec<MapKey>,
+    pending_since: Option<Instant>,
+    /// Insert / Command / 搜索模式下按了 Ctrl+R，等待寄存器名
+    insert_register_pending: bool,
+    /// Normal 模式下按了 Ctrl+W，等待窗口命令，记录 Ctrl+W 之前输入的计数
+    window_pending: Option<usize>,
+    /// Terminal 模式下按了 Ctrl+\，下一个键为 Ctrl+N 时回到 Normal 模式
+    terminal_escape_pending: bool,
+    /// 正在执行的 :normal 嵌套层数（执行宏时也计入）
+    normal_depth: usize,
+    /// 操作符之后按了 / 或 ?（如 `d/foo`），等待搜索模式输入完成后执行的操作符
+    search_operator: Option<OperatorCommand>,
+    /// Visual 模式下按了 S，等待包围选择区域的字符
+    visual_surround_pending: bool,
+}
+
+impl Default for KeyInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyInput {
+    pub fn new() -> Self {
+        Self {
+            key_parser: KeyParser::new(),
+            pending_keys: Vec::new(),
+            pending_since: None,
+            insert_register_pending: false,
+            window_pending: None,
+            terminal_escape_pending: false,
+            normal_depth: 0,
+            search_operator: None,
+            visual_surround_pending: false,
+        }
+    }
+
+    /// Normal 模式的按键解析器（界面用于显示操作符等待状态）
+    pub fn key_parser(&self) -> &KeyParser {
+        &self.key_parser
+    }
+
+    /// 开始等待映射后续按键的时间，没有等待中的按键时为 None
+    pub fn pending_since(&self) -> Option<Instant> {
+        self.pending_since
+    }
+
+    /// 处理用户输入的按键：先匹配用户映射，再交给各模式处理
+    pub fn handle_key(&mut self, editor: &mut Editor, key: MapKey) {
+        // 录制宏时记录的是用户输入的按键，不是映射展开后的按键
+        editor.record_key(key);
+        if self.mapping_mode(editor).is_some() {
+            self.pending_keys.push(key);
+            self.resolve_pending_keys(editor);
+        } else {
+            self.flush_pending_keys(editor);
+            self.dispatch_key(editor, key);
+        }
+    }
+
+    /// 依次处理按键，`remap` 为 false 时不展开映射；结束时还在等待的按键按超时处理
+    pub fn feed_keys(&mut self, editor: &mut Editor, keys: &[MapKey], remap: bool) {
+        if remap {
+            for key in keys {
+                self.handle_key(editor, *key);
+            }
+            if !self.pending_keys.is_empty() {
+                self.timeout(editor);
+            }
+        } else {
+            for key in keys {
+                self.dispatch_key(editor, *key);
+            }
+        }
+    }
+
+    /// 处理括号粘贴：选择器打开时加入查询文本，其余交给编辑器整段插入
+    ///
+    /// 粘贴的内容不经过映射，也不作为命令执行
+    pub fn paste(&mut self, editor: &mut Editor, text: &str) {
+        self.flush_pending_keys(editor);
+        self.insert_register_pending = false;
+        if editor.pending_confirmation().is_some() || editor.quickfix_focused() {
+            return;
+        }
+        if let Some(picker) = editor.picker_mut() {
+            text.chars().filter(|c| !c.is_control()).for_each(|c| picker.push_char(c));
+            return;
+        }
+        self.key_parser.reset();
+        editor.paste_text(text);
+    }
+
+    /// 当前可以使用映射的模式；有弹出面板或未完成的操作符时不使用映射
+    fn mapping_mode(&self, editor: &Editor) -> Option<MapMode> {
+        let overlay = editor.pending_confirmation().is_some()
+            || editor.picker().is_some()
+            || editor.show_registers_panel()
+            || editor.show_jobs_panel()
+            || editor.show_options_panel()
+            || editor.show_buffer_list()
+            || editor.quickfix_focused();
+        if overlay || self.key_parser.is_pending() {
+            return None;
+        }
+        // 粘贴模式下不使用 Insert 模式映射
+        MapMode::for_mode(editor.mode()).filter(|mode| !(*mode == MapMode::Insert && editor.options().paste))
+    }
+
+    /// 根据映射表处理等待中的按键
+    fn resolve_pending_keys(&mut self, editor: &mut Editor) {
+        while !self.pending_keys.is_empty() {
+            let mapping = match self.lookup_pending_keys(editor) {
+                MapLookup::Prefix { .. } => {
+                    self.pending_since = Some(Instant::now());
+                    return;
+                }
+                MapLookup::Match(mapping) => Some(mapping.clone()),
+                MapLookup::None => None,
+            };
+            match mapping {
+                Some(mapping) => {
+                    self.pending_keys.clear();
+                    self.execute_mapping(editor, &mapping, 0);
+                }
+                None => {
+                    // 第一个按键按原样处理，其余按键重新匹配
+                    let key = self.pending_keys.remove(0);
+                    self.dispatch_key(editor, key);
+                }
+            }
+        }
+        self.pending_since = None;
+    }
+
+    fn lookup_pending_keys<'a>(&self, editor: &'a Editor) -> MapLookup<'a> {
+        match self.mapping_mode(editor) {
+            Some(mode) => editor.mappings().lookup(mode, editor.current_buffer_id(), &self.pending_keys),
+            None => MapLookup::None,
+        }
+    }
+
+    /// 等待超时：执行完全匹配的映射，否则按原样处理第一个按键
+    pub fn timeout(&mut self, editor: &mut Editor) {
+        let exact = match self.lookup_pending_keys(editor) {
+            MapLookup::Prefix { exact } => exact.cloned(),
+            MapLookup::Match(mapping) => Some(mapping.clone()),
+            MapLookup::None => None,
+        };
+        match exact {
+            Some(mapping) => {
+                self.pending_keys.clear();
+                self.execute_mapping(editor, &mapping, 0);
+            }
+            None if !self.pending_keys.is_empty() => {
+                let key = self.pending_keys.remove(0);
+                self.dispatch_key(editor, key);
+            }
+            None => {}
+        }
+        self.pending_since = None;
+        self.resolve_pending_keys(editor);
+    }
+
+    /// 不再等待映射，按原样处理所有等待中的按键
+    fn flush_pending_keys(&mut self, editor: &mut Editor) {
+        self.pending_since = None;
+        for key in std::mem::take(&mut self.pending_keys) {
+            self.dispatch_key(editor, key);
+        }
+    }
+
+    /// 展开并执行映射的右侧
+    fn execute_mapping(&mut self, editor: &mut Editor, mapping: &Mapping, depth: usize) {
+        if depth >= MAX_MAP_DEPTH {
+            let message = editor.tr("Recursive mapping");
+            editor.set_message(message);
+            return;
+        }
+
+        let mut keys = &mapping.rhs[..];
+        if mapping.noremap {
+            for key in keys {
+                self.dispatch_key(editor, *key);
+            }
+            return;
+        }
+        // 右侧以左侧开头时（如 :nmap j gj），开头部分不再展开，避免无限递归
+        if keys.starts_with(&mapping.lhs) {
+            for key in &mapping.lhs {
+                self.dispatch_key(editor, *key);
+            }
+            keys = &keys[mapping.lhs.len()..];
+        }
+
+        let mut i = 0;
+        while i < keys.len() {
+            let nested = self.mapping_mode(editor).and_then(|mode| {
+                editor
+                    .mappings()
+                    .longest_match(mode, editor.current_buffer_id(), &keys[i..])
+                    .cloned()
+            });
+            match nested {
+                Some(nested) => {
+                    i += nested.lhs.len();
+                    self.execute_mapping(editor, &nested, depth + 1);
+                }
+                None => {
+                    self.dispatch_key(editor, keys[i]);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// 执行 :normal：在范围内每一行的行首（没有范围时在光标处）按 Normal 模式回放按键
+    pub fn execute_normal(&mut self, editor: &mut Editor, request: NormalRequest) {
+        if self.normal_depth >= MAX_MAP_DEPTH {
+            let message = editor.tr("Recursive use of :normal too deep");
+            editor.set_message(message);
+            return;
+        }
+        self.normal_depth += 1;
+        match request.range {
+            None => self.replay_normal_keys(editor, &request.keys, request.remap),
+            Some(range) => {
+                for line in range.start..=range.end {
+                    // 按键可能删除了后面的行
+                    if line >= editor.current_buffer().len_lines() {
+                        break;
+                    }
+                    *editor.cursor_mut() = Cursor::new(line, 0);
+                    self.replay_normal_keys(editor, &request.keys, request.remap);
+                }
+            }
+        }
+        self.normal_depth -= 1;
+    }
+
+    fn replay_normal_keys(&mut self, editor: &mut Editor, keys: &[MapKey], remap: bool) {
+        self.key_parser.reset();
+        let replay = Mapping {
+            lhs: Vec::new(),
+            rhs: keys.to_vec(),
+            noremap: !remap,
+        };
+        self.execute_mapping(editor, &replay, 0);
+        // 未完成的命令被放弃，结束时回到 Normal 模式
+        self.key_parser.reset();
+        self.insert_register_pending = false;
+        self.window_pending = None;
+        if editor.mode() != Mode::Normal {
+            self.dispatch_key(editor, MapKey::Esc);
+        }
+    }
+
+    /// 按当前模式处理一个按键（不再匹配映射）
+    pub fn dispatch_key(&mut self, editor: &mut Editor, key: MapKey) {
+        // 如果有等待确认的操作（如 :Delete），按回答的按键处理：只有 y / n 时其他按键取消，
+        // 有更多回答时 Esc 取消，其他按键忽略
+        if let Some(confirmation) = editor.pending_confirmation() {
+            let choices = confirmation.choices();
+            let answer = match key {
+                MapKey::Char(c) => ConfirmAnswer::from_key(c).filter(|answer| choices.contains(answer)),
+                MapKey::Esc | MapKey::Ctrl('c') => Some(ConfirmAnswer::Quit),
+                _ => None,
+            };
+            let answer = match answer {
+                Some(answer) => answer,
+                None if choices.len() > 2 => return,
+                None => ConfirmAnswer::Quit,
+            };
+            if let Err(e) = editor.answer_confirmation(answer) {
+                editor.set_message(e);
+            }
+            return;
+        }
+
+        // 模糊选择器打开时所有按键都交给选择器
+        if editor.picker().is_some() {
+            handle_picker_key(editor, key);
+            return;
+        }
+
+        // 寄存器、任务、选项、缓冲区列表面板显示时，q / Esc 只关闭面板，其他按键关闭面板后继续处理
+        let closes_panel = matches!(key, MapKey::Char('q') | MapKey::Char('Q') | MapKey::Esc);
+        if editor.show_registers_panel() {
+            editor.set_show_registers_panel(false);
+            if closes_panel {
+                return;
+            }
+        }
+        if editor.show_jobs_panel() {
+            editor.set_show_jobs_panel(false);
+            if closes_panel {
+                return;
+            }
+        }
+        if editor.show_options_panel() {
+            editor.set_show_options_panel(false);
+            if closes_panel {
+                return;
+            }
+        }
+        if editor.show_buffer_list() {
+            editor.set_show_buffer_list(false);
+            if closes_panel {
+                return;
+            }
+        }
+
+        // Quickfix 面板有焦点时，Normal 模式的按键用于在列表中选择（: 仍然进入命令行）
+        if editor.quickfix_focused() && editor.mode() == Mode::Normal && key != MapKey::Char(':') {
+            handle_quickfix_key(editor, key);
+            return;
+        }
+
+        match editor.mode() {
+            Mode::Normal => self.handle_normal_mode(editor, key),
+            Mode::Visual => self.handle_visual_mode(editor, key),
+            Mode::Insert | Mode::Replace => self.handle_insert_mode(editor, key),
+            Mode::Command => self.handle_command_mode(editor, key),
+            Mode::SearchForward | Mode::SearchBackward => self.handle_search_mode(editor, key),
+            Mode::Terminal => self.handle_terminal_mode(editor, key),
+        }
+    }
+
+    /// Visual 模式：动作扩展选择区域，`>` `<` `u` `U` `~` `J` `gq` `S{char}` 作用于选择区域，
+    /// `v` / `V` 切换或结束选择，`o` / `O` 跳到选择区域的另一端，`gv` 与上一次的选择区域交换，Esc 回到 Normal 模式
+    fn handle_visual_mode(&mut self, editor: &mut Editor, key: MapKey) {
+        if std::mem::take(&mut self.visual_surround_pending) {
+            if let MapKey::Char(c) = key {
+                editor.visual_operator(Operator::Surround(c), None);
+            }
+            return;
+        }
+
+        let ch = match key {
+            MapKey::Char(c) => c,
+            MapKey::Left => 'h',
+            MapKey::Down => 'j',
+            MapKey::Up => 'k',
+            MapKey::Right => 'l',
+            MapKey::Esc | MapKey::Ctrl('c') => {
+                self.key_parser.reset();
+                editor.exit_visual();
+                return;
+            }
+            _ => {
+                self.key_parser.reset();
+                return;
+            }
+        };
+
+        if self.key_parser.awaiting_g() {
+            match ch {
+                'q' => {
+                    self.key_parser.reset();
+                    editor.visual_operator(Operator::Format, None);
+                    return;
+                }
+                'v' => {
+                    self.key_parser.reset();
+                    if let Err(e) = editor.reselect_visual() {
+                        editor.set_message(e);
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        } else if self.key_parser.awaiting_command() {
+            // "{register} 之后的 d / y / c / p 使用指定的寄存器
+            let register = self.key_parser.register();
+            let operator = match ch {
+                'd' | 'x' => Some(Operator::Delete),
+                'y' => Some(Operator::Yank),
+                'c' => Some(Operator::Change),
+                '>' => Some(Operator::Indent),
+                '<' => Some(Operator::Outdent),
+                'u' => Some(Operator::Lowercase),
+                'U' => Some(Operator::Uppercase),
+                '~' => Some(Operator::ToggleCase),
+                _ => None,
+            };
+            if let Some(operator) = operator {
+                self.key_parser.reset();
+                editor.visual_operator(operator, register);
+                return;
+            }
+            match ch {
+                'p' | 'P' => editor.visual_put(register),
+                'J' => editor.visual_join(),
+                'S' => self.visual_surround_pending = true,
+                'v' => editor.toggle_visual(false),
+                'V' => editor.toggle_visual(true),
+                'o' | 'O' => editor.swap_visual_ends(),
+                _ => {
+                    self.feed_visual_motion(editor, ch);
+                    return;
+                }
+            }
+            self.key_parser.reset();
+            return;
+        }
+        self.feed_visual_motion(editor, ch);
+    }
+
+    /// Visual 模式下只执行解析出的动作，其他命令（包括等待动作的操作符）被丢弃
+    fn feed_visual_motion(&mut self, editor: &mut Editor, ch: char) {
+        if let ParseResult::Complete(NormalCommand::Motion { motion, count }) = self.key_parser.feed(ch) {
+            for _ in 0..count {
+                editor.execute_motion(motion);
+            }
+        }
+        if self.key_parser.operator().is_some() {
+            self.key_parser.reset();
+        }
+    }
+
+    /// Terminal 模式：按键发送给终端中的程序，`Ctrl+\ Ctrl+N` 回到 Normal 模式（`Ctrl+\` 按两次发送一次）
+    fn handle_terminal_mode(&mut self, editor: &mut Editor, key: MapKey) {
+        if std::mem::take(&mut self.terminal_escape_pending) {
+            if key == MapKey::Ctrl('n') {
+                editor.set_mode(Mode::Normal);
+                return;
+            }
+            editor.send_terminal_key(MapKey::Ctrl('\\'));
+            if key == MapKey::Ctrl('\\') {
+                return;
+            }
+        } else if key == MapKey::Ctrl('\\') {
+            self.terminal_escape_pending = true;
+            return;
+        }
+        editor.send_terminal_key(key);
+    }
+
+    fn handle_normal_mode(&mut self, editor: &mut Editor, key: MapKey) {
+        // Ctrl+W 之后的按键是窗口命令，Ctrl+W Ctrl+W 等同于 Ctrl+W w
+        if let Some(count) = self.window_pending.take() {
+            if let MapKey::Char(c) | MapKey::Ctrl(c) = key {
+                if let Err(e) = editor.window_command(c, count) {
+                    editor.set_message(e);
+                }
+            }
+            return;
+        }
+
+        // 没有未完成的序列时插件先处理按键（如 Esc 关闭插件的浮动窗口）
+        if !self.key_parser.is_pending() && editor.plugin_key(key) {
+            return;
+        }
+
+        // 终端缓冲区中 i / a / I / A 进入 Terminal 模式
+        if matches!(key, MapKey::Char('i' | 'a' | 'I' | 'A'))
+            && !self.key_parser.is_pending()
+            && editor.enter_terminal_mode()
+        {
+            return;
+        }
+
+        // 录制宏时没有未完成的序列时按 q 结束录制
+        if key == MapKey::Char('q') && editor.recording_register().is_some() && !self.key_parser.is_pending() {
+            editor.stop_recording();
+            return;
+        }
+
+        // Ctrl 组合键不参与按键序列解析，并取消未完成的序列
+        if let MapKey::Ctrl(c) = key {
+            let count = self.key_parser.count();
+            self.key_parser.reset();
+            match c {
+                'a' => {
+                    editor.increment_number(count as i64);
+                }
+                'x' => {
+                    editor.increment_number(-(count as i64));
+                }
+                'd' => editor.execute_motion(Motion::PageDown),
+                'e' | 'y' => {
+                    let (width, height) = editor.text_size();
+                    let lines = if c == 'e' { count as isize } else { -(count as isize) };
+                    editor.scroll_window(lines, width, height);
+                }
+                'u' => editor.execute_motion(Motion::PageUp),
+                'f' | 'b' => {
+                    let motion = if c == 'f' { Motion::PageForward } else { Motion::PageBackward };
+                    for _ in 0..count {
+                        editor.execute_motion(motion);
+                    }
+                }
+                'r' => editor.redo_steps(count),
+                'n' => {
+                    for _ in 0..count {
+                        if let Err(e) = editor.add_cursor_at_next_match() {
+                            editor.set_message(e);
+                            break;
+                        }
+                    }
+                }
+                'w' => self.window_pending = Some(count),
+                'z' => editor.suspend(),
+                'p' => {
+                    if let Err(e) = editor.open_file_finder(None) {
+                        editor.set_message(e);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // 方向键等价于 hjkl，可以与操作符组合
+        let ch = match key {
+            MapKey::Char(c) => c,
+            MapKey::Left => 'h',
+            MapKey::Down => 'j',
+            MapKey::Up => 'k',
+            MapKey::Right => 'l',
+            // r<CR> 用换行替换字符
+            MapKey::Enter if self.key_parser.awaiting_replace_char() => '\n',
+            // 有多个光标时 Esc 回到单个光标
+            MapKey::Esc if !self.key_parser.is_pending() && editor.clear_extra_cursors() => return,
+            _ => {
+                // Esc 及其他按键取消未完成的序列
+                self.key_parser.reset();
+                return;
+            }
+        };
+
+        let has_count = self.key_parser.has_count();
+        match self.key_parser.feed(ch) {
+            // gt 切换到下一个标签页，{count}gt 切换到第 count 个
+            ParseResult::Complete(NormalCommand::G { key: 't', count }) => {
+                editor.next_tab(has_count.then_some(count));
+            }
+            ParseResult::Complete(command) => self.execute_normal_command(editor, command),
+            _ => {}
+        }
+    }
+
+    /// 执行解析完成的 Normal 模式命令
+    fn execute_normal_command(&mut self, editor: &mut Editor, command: NormalCommand) {
+        match command {
+            NormalCommand::Motion { motion, count } => {
+                editor.for_each_cursor(|editor| {
+                    for _ in 0..count {
+                        editor.execute_motion(motion);
+                    }
+                });
+            }
+            NormalCommand::Operator(operator) => {
+                editor.for_each_cursor(|editor| {
+                    editor.execute_operator(operator);
+                });
+            }
+            NormalCommand::SearchOperator { command, backward } => {
+                self.search_operator = Some(command);
+                editor.set_mode(if backward { Mode::SearchBackward } else { Mode::SearchForward });
+                editor.command_line_mut().clear();
+            }
+            // 修改文本和进入 Insert 模式的按键对每个光标执行
+            NormalCommand::Key {
+                key: key @ ('i' | 'a' | 'A' | 'I' | 'o' | 'O' | 'x' | 'p' | 'P' | 'J'),
+                count,
+                register,
+            } => {
+                editor.for_each_cursor(|editor| handle_normal_key(editor, key, count, register));
+            }
+            NormalCommand::Key { key, count, register } => {
+                handle_normal_key(editor, key, count, register);
+            }
+            NormalCommand::G { key: 'J', count } => {
+                editor.join_lines(count, false);
+            }
+            NormalCommand::G { key: 'v', .. } => {
+                if let Err(e) = editor.reselect_visual() {
+                    editor.set_message(e);
+                }
+            }
+            NormalCommand::G { key: '-', count } => {
+                editor.undo_travel(-(count as isize));
+            }
+            NormalCommand::G { key: '+', count } => {
+                editor.undo_travel(count as isize);
+            }
+            NormalCommand::G { key: 'j', count } => {
+                editor.move_screen_line(true, count, editor.text_size().0);
+            }
+            NormalCommand::G { key: 'k', count } => {
+                editor.move_screen_line(false, count, editor.text_size().0);
+            }
+            NormalCommand::G { key: 'T', count } => {
+                editor.previous_tab(count);
+            }
+            NormalCommand::G { key: key @ ('*' | '#'), count } => {
+                let direction = if key == '*' { SearchDirection::Forward } else { SearchDirection::Backward };
+                editor.search_word_under_cursor(direction, false, count);
+            }
+            NormalCommand::G { .. } => {}
+            // zt / zz / zb 滚动窗口，z. / z- 同时把光标移到第一个非空白字符
+            NormalCommand::Z { key: key @ ('t' | 'z' | 'b' | '.' | '-'), .. } => {
+                let anchor = match key {
+                    't' => ScrollAnchor::Top,
+                    'b' | '-' => ScrollAnchor::Bottom,
+                    _ => ScrollAnchor::Center,
+                };
+                if key == '.' || key == '-' {
+                    editor.execute_motion(Motion::FirstNonBlank);
+                }
+                let (width, height) = editor.text_size();
+                editor.scroll_cursor_to(anchor, width, height);
+            }
+            NormalCommand::Z { key, .. } => {
+                if let Err(e) = editor.fold_command(key) {
+                    editor.set_message(e);
+                }
+            }
+            // 编辑器不处理的 ]x / [x 交给插件（如 Git 插件的 ]c / [c）
+            NormalCommand::Bracket { forward, key, count } => {
+                if let Err(e) = editor.plugin_bracket(key, forward, count) {
+                    editor.set_message(e);
+                }
+            }
+            NormalCommand::ReplaceChar { ch, count } => {
+                editor.for_each_cursor(|editor| {
+                    editor.replace_chars(ch, count);
+                });
+            }
+            NormalCommand::DeleteSurround { target } => {
+                if let Err(e) = editor.delete_surround(target) {
+                    editor.set_message(e);
+                }
+            }
+            NormalCommand::ChangeSurround { target, replacement } => {
+                if let Err(e) = editor.change_surround(target, replacement) {
+                    editor.set_message(e);
+                }
+            }
+            NormalCommand::Record { register } => {
+                if let Err(e) = editor.start_recording(register) {
+                    editor.set_message(e);
+                }
+            }
+            NormalCommand::Replay { register, count } => {
+                self.replay_macro(editor, register, count);
+            }
+        }
+    }
+
+    /// @{register}：把寄存器的内容当作按键执行 count 次
+    fn replay_macro(&mut self, editor: &mut Editor, register: char, count: usize) {
+        let keys = match editor.macro_keys(register) {
+            Ok(keys) => keys,
+            Err(e) => {
+                editor.set_message(e);
+                return;
+            }
+        };
+        // 宏中可以执行宏（包括自己），与 :normal 共用嵌套层数的限制
+        if self.normal_depth >= MAX_MAP_DEPTH {
+            let message = editor.tr("Recursive macro too deep");
+            editor.set_message(message);
+            return;
+        }
+        self.normal_depth += 1;
+        let replay = Mapping {
+            lhs: Vec::new(),
+            rhs: keys,
+            noremap: false,
+        };
+        for _ in 0..count {
+            self.execute_mapping(editor, &replay, 0);
+        }
+        self.normal_depth -= 1;
+    }
+
+    fn handle_insert_mode(&mut self, editor: &mut Editor, key: MapKey) {
+        // 补全菜单打开时优先处理菜单按键
+        if editor.completion().is_some() && handle_completion_key(editor, key) {
+            return;
+        }
+
+        if editor.expression_prompt_active() {
+            handle_expression_prompt_key(editor, key);
+            return;
+        }
+        if self.insert_register_pending {
+            // Ctrl+R {reg} - 插入寄存器内容，Ctrl+R = 输入表达式
+            self.insert_register_pending = false;
+            match key {
+                MapKey::Char('=') => editor.start_expression_prompt(),
+                MapKey::Char(c) => {
+                    if let Err(e) = editor.insert_register(c) {
+                        editor.set_message(e);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // 插件先处理按键（如 AI 插件用 Tab / Esc 接受或丢弃补全建议）
+        if editor.plugin_key(key) {
+            return;
+        }
+        match key {
+            MapKey::Esc => {
+                editor.set_mode(Mode::Normal);
+            }
+            MapKey::Ctrl('r') => {
+                self.insert_register_pending = true;
+            }
+            MapKey::Ctrl('n') => {
+                // Ctrl+N - 打开补全菜单
+                editor.start_word_completion();
+            }
+            MapKey::Ctrl('p') => {
+                // Ctrl+P - 打开补全菜单并选中最后一个候选项
+                editor.start_word_completion();
+                editor.completion_prev();
+            }
+            MapKey::Ctrl(' ') => {
+                // Ctrl+Space - 向插件（语言服务器）请求补全
+                editor.request_completion();
+            }
+            MapKey::Char(c) => {
+                editor.for_each_cursor(|editor| editor.insert_char(c));
+                editor.completion_trigger(c);
+            }
+            MapKey::Enter => {
+                editor.for_each_cursor(Editor::insert_newline);
+            }
+            MapKey::Backspace => {
+                editor.for_each_cursor(Editor::backspace);
+            }
+            MapKey::Tab => {
+                editor.for_each_cursor(Editor::insert_tab);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_command_mode(&mut self, editor: &mut Editor, key: MapKey) {
+        if self.handle_command_line_register(editor, key) {
+            return;
+        }
+        match key {
+            MapKey::Esc => {
+                editor.set_mode(Mode::Normal);
+                editor.command_line_mut().clear();
+                editor.clear_message();
+            }
+            MapKey::Enter => {
+                let command = editor.command_line().to_string();
+                editor.add_command_history(&command);
+                editor.command_line_mut().clear();
+                editor.set_mode(Mode::Normal);
+
+                // 执行命令
+                if let Err(e) = editor.execute_command(&command) {
+                    editor.set_message(e);
+                }
+                if let Some(request) = editor.take_normal_request() {
+                    self.execute_normal(editor, request);
+                }
+            }
+            MapKey::Char(c) => {
+                editor.command_line_mut().push(c);
+            }
+            MapKey::Backspace => {
+                editor.command_line_mut().pop();
+            }
+            MapKey::Up => editor.history_prev(),
+            MapKey::Down => editor.history_next(),
+            _ => {}
+        }
+    }
+
+    /// 处理命令行中的 Ctrl+R {reg}：把寄存器内容追加到命令行，返回 true 表示按键已被消费
+    fn handle_command_line_register(&mut self, editor: &mut Editor, key: MapKey) -> bool {
+        if self.insert_register_pending {
+            self.insert_register_pending = false;
+            if let MapKey::Char(c) = key {
+                if let Err(e) = editor.insert_register_in_command_line(c) {
+                    editor.set_message(e);
+                }
+            }
+            return true;
+        }
+        if key == MapKey::Ctrl('r') {
+            self.insert_register_pending = true;
+            return true;
+        }
+        false
+    }
+
+    fn handle_search_mode(&mut self, editor: &mut Editor, key: MapKey) {
+        if self.handle_command_line_register(editor, key) {
+            return;
+        }
+        match key {
+            MapKey::Esc => {
+                editor.set_mode(Mode::Normal);
+                editor.command_line_mut().clear();
+                self.search_operator = None;
+            }
+            MapKey::Enter => {
+                // 先保存方向，再改变模式
+                let direction = match editor.mode() {
+                    Mode::SearchBackward => SearchDirection::Backward,
+                    _ => SearchDirection::Forward,
+                };
+
+                let pattern = editor.command_line().to_string();
+                editor.command_line_mut().clear();
+                editor.set_mode(Mode::Normal);
+
+                let operator = self.search_operator.take();
+                if pattern.is_empty() {
+                    return;
+                }
+                match operator {
+                    Some(command) => {
+                        editor.search_with_operator(direction, &pattern, command);
+                    }
+                    None => editor.start_search(direction, &pattern),
+                }
+            }
+            MapKey::Char(c) => {
+                editor.command_line_mut().push(c);
+            }
+            MapKey::Backspace => {
+                editor.command_line_mut().pop();
+            }
+            MapKey::Up => editor.history_prev(),
+            MapKey::Down => editor.history_next(),
+            _ => {}
+        }
+    }
+}
+
+/// 处理不带操作符的 Normal 模式按键（计数和寄存器已由解析器提取）
+fn handle_normal_key(editor: &mut Editor, key: char, count: usize, register: Option<char>) {
+    match key {
+        'i' => {
+            editor.set_mode(Mode::Insert);
+        }
+        'a' => {
+            editor.enter_append_mode();
+        }
+        'A' => {
+            // 在行尾追加
+            editor.execute_motion(Motion::LineEnd);
+            editor.enter_append_mode();
+        }
+        'I' => {
+            // 在第一个非空白字符前插入
+            editor.execute_motion(Motion::FirstNonBlank);
+            editor.set_mode(Mode::Insert);
+        }
+        'R' => {
+            editor.set_mode(Mode::Replace);
+        }
+        'o' => {
+            editor.open_line_below();
+        }
+        'O' => {
+            editor.open_line_above();
+        }
+        'x' => {
+            for _ in 0..count {
+                editor.delete_char_to_register(register);
+            }
+        }
+        'p' => {
+            // p - 在光标后粘贴
+            for _ in 0..count {
+                editor.paste(register, false);
+            }
+        }
+        'P' => {
+            // P - 在光标前粘贴
+            for _ in 0..count {
+                editor.paste(register, true);
+            }
+        }
+        'u' => {
+            editor.undo_steps(count);
+        }
+        'J' => {
+            editor.join_lines(count, true);
+        }
+        'v' | 'V' => {
+            editor.toggle_visual(key == 'V');
+        }
+        ':' => {
+            editor.set_mode(Mode::Command);
+            editor.command_line_mut().clear();
+        }
+        '/' => {
+            editor.set_mode(Mode::SearchForward);
+            editor.command_line_mut().clear();
+        }
+        '?' => {
+            editor.set_mode(Mode::SearchBackward);
+            editor.command_line_mut().clear();
+        }
+        'n' => {
+            for _ in 0..count {
+                editor.search_next();
+            }
+        }
+        'N' => {
+            for _ in 0..count {
+                editor.search_prev();
+            }
+        }
+        '*' => {
+            editor.search_word_under_cursor(SearchDirection::Forward, true, count);
+        }
+        '#' => {
+            editor.search_word_under_cursor(SearchDirection::Backward, true, count);
+        }
+        _ => {}
+    }
+}
+
+/// 处理表达式输入按键：回车求值并插入结果，Esc 取消
+fn handle_expression_prompt_key(editor: &mut Editor, key: MapKey) {
+    match key {
+        MapKey::Esc => editor.cancel_expression_prompt(),
+        MapKey::Enter => {
+            if let Err(e) = editor.finish_expression_prompt() {
+                editor.set_message(e);
+            }
+        }
+        MapKey::Backspace => {
+            editor.command_line_mut().pop();
+        }
+        MapKey::Char(c) => editor.command_line_mut().push(c),
+        _ => {}
+    }
+}
+
+/// 处理补全菜单按键，返回 true 表示按键已被菜单消费
+fn handle_completion_key(editor: &mut Editor, key: MapKey) -> bool {
+    match key {
+        MapKey::Ctrl('y') => {
+            editor.accept_completion();
+        }
+        MapKey::Ctrl('e') => {
+            editor.cancel_completion();
+        }
+        MapKey::Ctrl('n') | MapKey::Down => editor.completion_next(),
+        MapKey::Ctrl('p') | MapKey::Up => editor.completion_prev(),
+        MapKey::PageDown => editor.completion_page_down(),
+        MapKey::PageUp => editor.completion_page_up(),
+        MapKey::Char(c) => {
+            // 继续输入单词字符时按新前缀刷新候选项，否则关闭菜单（触发字符重新请求插件的补全）
+            editor.insert_char(c);
+            if c.is_alphanumeric() || c == '_' {
+                editor.refresh_completion();
+            } else {
+                editor.cancel_completion();
+                editor.completion_trigger(c);
+            }
+        }
+        MapKey::Backspace => {
+            editor.backspace();
+            editor.refresh_completion();
+        }
+        _ => {
+            // 其他按键关闭菜单，并继续按普通 Insert 模式处理
+            editor.cancel_completion();
+            return false;
+        }
+    }
+    true
+}
+
+/// 处理模糊选择器按键：输入过滤，上下选择，回车跳转，Ctrl+X / Ctrl+V 在上下 / 左右分割的新窗口中打开，Esc 关闭
+fn handle_picker_key(editor: &mut Editor, key: MapKey) {
+    match key {
+        MapKey::Esc | MapKey::Ctrl('c') => editor.cancel_picker(),
+        MapKey::Enter => {
+            if let Err(e) = editor.accept_picker() {
+                editor.set_message(e);
+            }
+        }
+        MapKey::Ctrl(c @ ('x' | 'v')) => {
+            let direction = if c == 'x' { SplitDirection::Horizontal } else { SplitDirection::Vertical };
+            if let Err(e) = editor.accept_picker_in_split(direction) {
+                editor.set_message(e);
+            }
+        }
+        MapKey::Down | MapKey::Tab | MapKey::Ctrl('n') => picker_select(editor, true),
+        MapKey::Up | MapKey::BackTab | MapKey::Ctrl('p') => picker_select(editor, false),
+        MapKey::Backspace => {
+            if let Some(picker) = editor.picker_mut() {
+                picker.pop_char();
+            }
+        }
+        MapKey::Char(c) => {
+            if let Some(picker) = editor.picker_mut() {
+                picker.push_char(c);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn picker_select(editor: &mut Editor, next: bool) {
+    if let Some(picker) = editor.picker_mut() {
+        if next {
+            picker.select_next();
+        } else {
+            picker.select_prev();
+        }
+    }
+}
+
+/// 处理列表面板的按键：j/k 选择，回车跳转，q 关闭面板，Esc 回到编辑窗口
+fn handle_quickfix_key(editor: &mut Editor, key: MapKey) {
+    let Some((kind, list)) = editor.quickfix_panel() else {
+        return;
+    };
+    let current = list.current_index();
+    match key {
+        MapKey::Char('j') | MapKey::Down => editor.select_quickfix(current + 1),
+        MapKey::Char('k') | MapKey::Up => editor.select_quickfix(current.saturating_sub(1)),
+        MapKey::Char('g') | MapKey::Home => editor.select_quickfix(0),
+        MapKey::Char('G') | MapKey::End => editor.select_quickfix(usize::MAX),
+        MapKey::Enter => {
+            if let Err(e) = editor.list_jump(kind, current) {
+                editor.set_message(e);
+            }
+        }
+        MapKey::Char('q') => editor.close_quickfix(),
+        MapKey::Esc => editor.unfocus_quickfix(),
+        _ => {}
+    }
+}
@@ -0,0 +1,439 @@
+//! 异步任务（job）管理模块
+//!
+//! 统一管理编辑器启动的外部进程，:!cmd、:make、格式化程序、LSP 等都通过这里启动。
+//! 每个任务的 stdout/stderr 由后台线程按行读取并通过通道发送（同时唤醒事件循环，见 waker 模块），
+//! 主线程在事件循环中调用 `JobManager::poll` 取出输出事件并检查进程是否已退出。
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::waker::Waker;
+
+/// 每个任务最多保留的输出行数，超出后丢弃最早的行
+pub const MAX_JOB_OUTPUT_LINES: usize = 10_000;
+
+/// 任务 ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(usize);
+
+impl JobId {
+    pub fn new(id: usize) -> Self {
+        Self(id)
+    }
+
+    pub fn as_usize(&self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// 输出来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStream {
+    Stdout,
+    Stderr,
+}
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// 正在运行
+    Running,
+    /// 进程已退出；被信号终止时没有退出码
+    Exited(Option<i32>),
+    /// 被 :job kill 终止
+    Killed,
+}
+
+impl JobStatus {
+    pub fn is_running(&self) -> bool {
+        matches!(self, JobStatus::Running)
+    }
+
+    /// 用于 :jobs 列表和消息的简短描述
+    pub fn describe(&self) -> String {
+        match self {
+            JobStatus::Running => "running".to_string(),
+            JobStatus::Exited(Some(code)) => format!("exit {}", code),
+            JobStatus::Exited(None) => "terminated".to_string(),
+            JobStatus::Killed => "killed".to_string(),
+        }
+    }
+}
+
+/// 任务事件，由 `JobManager::poll` 返回
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobEvent {
+    /// 一行输出（不含行尾换行符）
+    Output {
+        id: JobId,
+        stream: JobStream,
+        line: String,
+    },
+    /// 任务结束，输出已全部读取
+    Exited { id: JobId, status: JobStatus },
+}
+
+impl JobEvent {
+    pub fn id(&self) -> JobId {
+        match self {
+            JobEvent::Output { id, .. } | JobEvent::Exited { id, .. } => *id,
+        }
+    }
+}
+
+/// 任务事件回调，在主线程的 `poll` 中调用
+pub type JobCallback = Box<dyn FnMut(&JobEvent)>;
+
+/// 要启动的进程描述
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobSpec {
+    program: String,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+    stdin: Option<String>,
+}
+
+impl JobSpec {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            cwd: None,
+            stdin: None,
+        }
+    }
+
+    /// 通过系统 shell 执行一条命令行（Unix 为 `sh -c`，Windows 为 `cmd /C`）
+    pub fn shell(command: impl Into<String>) -> Self {
+        if cfg!(windows) {
+            Self::new("cmd").arg("/C").arg(command)
+        } else {
+            Self::new("sh").arg("-c").arg(command)
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// 设置工作目录
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// 写入进程标准输入的内容，写完后关闭 stdin
+    pub fn stdin(mut self, input: impl Into<String>) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    /// 用于显示的命令行
+    pub fn display(&self) -> String {
+        // shell 命令只显示命令行本身
+        if self.args.len() == 2 && (self.args[0] == "-c" || self.args[0] == "/C") {
+            return self.args[1].clone();
+        }
+        let mut display = self.program.clone();
+        for arg in &self.args {
+            display.push(' ');
+            display.push_str(arg);
+        }
+        display
+    }
+}
+
+/// 一个正在运行或已结束的任务
+pub struct Job {
+    id: JobId,
+    command: String,
+    status: JobStatus,
+    output: VecDeque<String>,
+    child: Child,
+    /// 尚未读完的输出流数量
+    open_streams: usize,
+    /// 已请求终止
+    killed: bool,
+}
+
+impl Job {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.status
+    }
+
+    /// 进程 ID
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// 已收到的输出行（stdout 和 stderr 按到达顺序合并）
+    pub fn output(&self) -> impl Iterator<Item = &str> {
+        self.output.iter().map(String::as_str)
+    }
+
+    fn push_output(&mut self, line: String) {
+        if self.output.len() == MAX_JOB_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+        self.output.push_back(line);
+    }
+}
+
+/// 后台读取线程发往主线程的消息
+enum Message {
+    Line(JobId, JobStream, String),
+    Closed(JobId),
+}
+
+/// 任务管理器
+pub struct JobManager {
+    jobs: BTreeMap<JobId, Job>,
+    callbacks: HashMap<JobId, JobCallback>,
+    next_id: usize,
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    /// 读取线程收到输出时唤醒事件循环
+    waker: Waker,
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            jobs: BTreeMap::new(),
+            callbacks: HashMap::new(),
+            next_id: 1,
+            sender,
+            receiver,
+            waker: Waker::default(),
+        }
+    }
+
+    /// 设置之后启动的任务产生输出时调用的唤醒回调
+    pub fn set_waker(&mut self, waker: Waker) {
+        self.waker = waker;
+    }
+
+    /// 启动任务，输出通过 `poll` 返回的事件获取
+    pub fn spawn(&mut self, spec: JobSpec) -> io::Result<JobId> {
+        let mut command = Command::new(&spec.program);
+        command
+            .args(&spec.args)
+            .stdin(if spec.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(cwd) = &spec.cwd {
+            command.current_dir(cwd);
+        }
+        let mut child = command.spawn()?;
+
+        let id = JobId::new(self.next_id);
+        self.next_id += 1;
+
+        // stdin 在单独的线程中写入，避免进程输出填满管道时互相阻塞
+        if let (Some(input), Some(mut stdin)) = (spec.stdin.clone(), child.stdin.take()) {
+            thread::spawn(move || {
+                let _ = stdin.write_all(input.as_bytes());
+            });
+        }
+
+        let mut open_streams = 0;
+        if let Some(stdout) = child.stdout.take() {
+            self.spawn_reader(id, JobStream::Stdout, stdout);
+            open_streams += 1;
+        }
+        if let Some(stderr) = child.stderr.take() {
+            self.spawn_reader(id, JobStream::Stderr, stderr);
+            open_streams += 1;
+        }
+
+        self.jobs.insert(
+            id,
+            Job {
+                id,
+                command: spec.display(),
+                status: JobStatus::Running,
+                output: VecDeque::new(),
+                child,
+                open_streams,
+                killed: false,
+            },
+        );
+        Ok(id)
+    }
+
+    /// 启动任务，并在 `poll` 时把该任务的事件交给回调
+    pub fn spawn_with(&mut self, spec: JobSpec, callback: JobCallback) -> io::Result<JobId> {
+        let id = self.spawn(spec)?;
+        self.callbacks.insert(id, callback);
+        Ok(id)
+    }
+
+    fn spawn_reader(&self, id: JobId, stream: JobStream, source: impl Read + Send + 'static) {
+        let sender = self.sender.clone();
+        let waker = self.waker.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(source);
+            let mut bytes = Vec::new();
+            loop {
+                bytes.clear();
+                match reader.read_until(b'\n', &mut bytes) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        if bytes.ends_with(b"\n") {
+                            bytes.pop();
+                            if bytes.ends_with(b"\r") {
+                                bytes.pop();
+                            }
+                        }
+                        let line = String::from_utf8_lossy(&bytes).into_owned();
+                        if sender.send(Message::Line(id, stream, line)).is_err() {
+                            return;
+                        }
+                        waker.wake();
+                    }
+                }
+            }
+            let _ = sender.send(Message::Closed(id));
+            waker.wake();
+        });
+    }
+
+    /// 终止正在运行的任务
+    pub fn kill(&mut self, id: JobId) -> io::Result<()> {
+        let job = self
+            .jobs
+            .get_mut(&id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No job {}", id)))?;
+        if !job.status.is_running() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Job {} is not running", id),
+            ));
+        }
+        job.child.kill()?;
+        job.killed = true;
+        Ok(())
+    }
+
+    /// 取出自上次调用以来的输出，并检查已退出的任务
+    ///
+    /// 任务的 `Exited` 事件总在它的全部输出之后返回
+    pub fn poll(&mut self) -> Vec<JobEvent> {
+        let mut events = Vec::new();
+
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                Message::Line(id, stream, line) => {
+                    if let Some(job) = self.jobs.get_mut(&id) {
+                        job.push_output(line.clone());
+                    }
+                    events.push(JobEvent::Output { id, stream, line });
+                }
+                Message::Closed(id) => {
+                    if let Some(job) = self.jobs.get_mut(&id) {
+                        job.open_streams = job.open_streams.saturating_sub(1);
+                    }
+                }
+            }
+        }
+
+        for job in self.jobs.values_mut() {
+            if !job.status.is_running() {
+                continue;
+            }
+            // 被终止的任务不等待输出流关闭（子进程可能仍持有管道）
+            if job.open_streams > 0 && !job.killed {
+                continue;
+            }
+            if let Ok(Some(exit)) = job.child.try_wait() {
+                job.status = if job.killed {
+                    JobStatus::Killed
+                } else {
+                    JobStatus::Exited(exit.code())
+                };
+                events.push(JobEvent::Exited {
+                    id: job.id,
+                    status: job.status,
+                });
+            }
+        }
+
+        for event in &events {
+            if let Some(callback) = self.callbacks.get_mut(&event.id()) {
+                callback(event);
+            }
+            if let JobEvent::Exited { id, .. } = event {
+                self.callbacks.remove(id);
+            }
+        }
+
+        events
+    }
+
+    pub fn get(&self, id: JobId) -> Option<&Job> {
+        self.jobs.get(&id)
+    }
+
+    /// 所有任务，按 ID 排序
+    pub fn jobs(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.values()
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.jobs.values().filter(|job| job.status.is_running()).count()
+    }
+
+    /// 移除已结束的任务，返回移除的数量
+    pub fn clear_finished(&mut self) -> usize {
+        let before = self.jobs.len();
+        self.jobs.retain(|_, job| job.status.is_running());
+        before - self.jobs.len()
+    }
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for JobManager {
+    /// 编辑器退出时终止仍在运行的任务，避免留下孤儿进程
+    fn drop(&mut self) {
+        for job in self.jobs.values_mut() {
+            if job.status.is_running() && job.child.kill().is_ok() {
+                let _ = job.child.wait();
+            }
+        }
+    }
+}
@@ -0,0 +1,595 @@
+//! 按键序列解析模块
+//!
+//! 将 Normal 模式下的按键序列解析为完整命令，语法为：
+//!
+//! ```text
+//! [count] ["x] [count] operator [count] (motion | text-object | operator)
+//! ```
+//!
+//! 例如 `3w`、`d2w`、`"a3yy`、`2d3j`、`ciw`、`>ap`、`dgg`、`gUiw`。
+//! 任何动作都可以与任意操作符（d/y/c/>/</gu/gU/g~/gq）组合，无需为每个按键单独处理。
+//! 解析器不认识的按键（如 i、p、x）连同计数和寄存器一起交给调用方处理。
+//!
+//! 包围操作（vim-surround）：`ys{motion}{char}` 解析为包围操作符，`yss` 包围整行；
+//! `ds{char}` 和 `cs{old}{new}` 解析为独立的命令。
+//!
+//! `[` / `]` 前缀的命令（如跳到上一处 / 下一处修改的 `[c` / `]c`）交给调用方执行。
+
+use crate::motion::Motion;
+use crate::text_object::TextObject;
+
+/// 操作符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// 删除 (d)
+    Delete,
+    /// 复制 (y)
+    Yank,
+    /// 修改 (c)
+    Change,
+    /// 增加缩进 (>)
+    Indent,
+    /// 减少缩进 (<)
+    Outdent,
+    /// 创建折叠 (zf)
+    Fold,
+    /// 用字符对应的一对字符包围 (ys)
+    Surround(char),
+    /// 转为小写 (gu)
+    Lowercase,
+    /// 转为大写 (gU)
+    Uppercase,
+    /// 切换大小写 (g~)
+    ToggleCase,
+    /// 按宽度重排文本 (gq)
+    Format,
+}
+
+impl Operator {
+    /// 从按键解析操作符
+    pub fn from_key(key: char) -> Option<Self> {
+        match key {
+            'd' => Some(Operator::Delete),
+            'y' => Some(Operator::Yank),
+            'c' => Some(Operator::Change),
+            '>' => Some(Operator::Indent),
+            '<' => Some(Operator::Outdent),
+            _ => None,
+        }
+    }
+
+    /// g 前缀的操作符（gu、gU、g~、gq）
+    pub fn from_g_key(key: char) -> Option<Self> {
+        match key {
+            'u' => Some(Operator::Lowercase),
+            'U' => Some(Operator::Uppercase),
+            '~' => Some(Operator::ToggleCase),
+            'q' => Some(Operator::Format),
+            _ => None,
+        }
+    }
+
+    /// 操作符对应的按键（zf 为 F，zF 创建 count 行的折叠；g 前缀的操作符为第二个键，如 guu、gUU）
+    pub fn key(&self) -> char {
+        match self {
+            Operator::Delete => 'd',
+            Operator::Yank => 'y',
+            Operator::Change => 'c',
+            Operator::Indent => '>',
+            Operator::Outdent => '<',
+            Operator::Fold => 'F',
+            Operator::Surround(_) => 's',
+            Operator::Lowercase => 'u',
+            Operator::Uppercase => 'U',
+            Operator::ToggleCase => '~',
+            Operator::Format => 'q',
+        }
+    }
+}
+
+/// 操作符作用的对象
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OperatorTarget {
+    /// 从光标到动作终点
+    Motion(Motion),
+    /// 文本对象（如 iw、ap）
+    TextObject(TextObject),
+    /// 整行（重复操作符，如 dd、yy、>>）
+    Line,
+}
+
+/// 完整的操作符命令
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatorCommand {
+    pub operator: Operator,
+    pub target: OperatorTarget,
+    /// 重复次数（操作符前后的计数相乘）
+    pub count: usize,
+    pub register: Option<char>,
+}
+
+/// 解析完成的 Normal 模式命令
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalCommand {
+    /// 光标移动
+    Motion { motion: Motion, count: usize },
+    /// 操作符 + 动作/文本对象
+    Operator(OperatorCommand),
+    /// 解析器不处理的按键，由调用方执行
+    Key {
+        key: char,
+        count: usize,
+        register: Option<char>,
+    },
+    /// g 前缀的非移动命令（如 gJ），由调用方执行
+    G { key: char, count: usize },
+    /// z 前缀的命令（如 zo、zc），由调用方执行；zf 和 zF 解析为折叠操作符
+    Z { key: char, count: usize },
+    /// ] 或 [ 前缀的命令（如 ]c、[c），`forward` 表示 ] 前缀，由调用方执行
+    Bracket { forward: bool, key: char, count: usize },
+    /// r{char}：用指定字符替换光标处的 count 个字符
+    ReplaceChar { ch: char, count: usize },
+    /// ds{char}：删除包围光标的一对字符
+    DeleteSurround { target: char },
+    /// cs{old}{new}：把包围光标的一对字符换成另一对
+    ChangeSurround { target: char, replacement: char },
+    /// q{register}：开始录制宏（录制中按 q 结束录制由调用方在解析之前处理）
+    Record { register: char },
+    /// @{register}：执行寄存器中的宏 count 次，`@@` 的寄存器为 '@'
+    Replay { register: char, count: usize },
+    /// 操作符之后按 / 或 ?（如 `d/foo<CR>`）：调用方进入搜索模式，输入搜索模式后对光标到匹配的范围执行 `command`
+    SearchOperator { command: OperatorCommand, backward: bool },
+}
+
+/// 输入一个按键后的解析结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseResult {
+    /// 序列尚未完成，等待更多按键
+    Pending,
+    /// 解析出完整命令
+    Complete(NormalCommand),
+    /// 无效序列，已被丢弃
+    Invalid,
+}
+
+/// 当前等待的输入类型
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Stage {
+    #[default]
+    Start,
+    /// 按下 " 后等待寄存器名
+    Register,
+    /// 按下 g 后等待第二个键
+    G,
+    /// 按下 z 后等待第二个键
+    Z,
+    /// 按下 ] 或 [ 后等待第二个键
+    Bracket { forward: bool },
+    /// 按下 a/i 后等待文本对象
+    TextObject { around: bool },
+    /// 按下 r 后等待替换字符
+    ReplaceChar,
+    /// ys 的动作或文本对象之后等待包围字符
+    SurroundChar { target: OperatorTarget },
+    /// 按下 ds 后等待要删除的包围字符
+    DeleteSurround,
+    /// 按下 cs 后等待原来的包围字符和新的包围字符
+    ChangeSurround { target: Option<char> },
+    /// 按下 q 后等待录制宏的寄存器
+    Record,
+    /// 按下 @ 后等待宏所在的寄存器
+    Replay,
+}
+
+/// 按键序列解析器
+#[derive(Debug, Clone, Default)]
+pub struct KeyParser {
+    count: Option<usize>,
+    register: Option<char>,
+    operator: Option<Operator>,
+    motion_count: Option<usize>,
+    stage: Stage,
+}
+
+impl KeyParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否有未完成的按键序列
+    pub fn is_pending(&self) -> bool {
+        self.count.is_some()
+            || self.register.is_some()
+            || self.operator.is_some()
+            || self.stage != Stage::Start
+    }
+
+    /// 没有等待中的操作符或多键命令（可能已输入寄存器和计数），下一个键开始一个新命令
+    pub fn awaiting_command(&self) -> bool {
+        self.operator.is_none() && self.stage == Stage::Start
+    }
+
+    /// 已选择的寄存器
+    pub fn register(&self) -> Option<char> {
+        self.register
+    }
+
+    /// 正在等待寄存器名（刚按下 "）
+    pub fn awaiting_register(&self) -> bool {
+        self.stage == Stage::Register
+    }
+
+    /// 正在等待 g 前缀的第二个键
+    pub fn awaiting_g(&self) -> bool {
+        self.stage == Stage::G
+    }
+
+    /// 正在等待 z 前缀的第二个键
+    pub fn awaiting_z(&self) -> bool {
+        self.stage == Stage::Z
+    }
+
+    /// 正在等待 ] 或 [ 前缀的第二个键
+    pub fn awaiting_bracket(&self) -> bool {
+        matches!(self.stage, Stage::Bracket { .. })
+    }
+
+    /// 是否在 r 之后等待替换字符
+    pub fn awaiting_replace_char(&self) -> bool {
+        self.stage == Stage::ReplaceChar
+    }
+
+    /// 已输入的计数（没有时为 1），供解析器之外处理的按键使用（如 Ctrl+A）
+    pub fn count(&self) -> usize {
+        self.total_count()
+    }
+
+    /// 是否输入了计数（`{count}gt` 与 `gt` 的含义不同）
+    pub fn has_count(&self) -> bool {
+        self.count.is_some() || self.motion_count.is_some()
+    }
+
+    /// 等待中的操作符
+    pub fn operator(&self) -> Option<Operator> {
+        self.operator
+    }
+
+    /// 正在等待文本对象时返回是否为 around（a）
+    pub fn text_object_around(&self) -> Option<bool> {
+        match self.stage {
+            Stage::TextObject { around } => Some(around),
+            _ => None,
+        }
+    }
+
+    /// 丢弃未完成的按键序列
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 输入一个按键
+    ///
+    /// 返回 `Complete` 或 `Invalid` 时解析器会自动重置
+    pub fn feed(&mut self, key: char) -> ParseResult {
+        let result = match self.stage {
+            Stage::Register => self.feed_register(key),
+            Stage::G => self.feed_g(key),
+            Stage::Z => self.feed_z(key),
+            Stage::Bracket { forward } => ParseResult::Complete(NormalCommand::Bracket {
+                forward,
+                key,
+                count: self.total_count(),
+            }),
+            Stage::TextObject { around } => self.feed_text_object(key, around),
+            Stage::ReplaceChar => ParseResult::Complete(NormalCommand::ReplaceChar {
+                ch: key,
+                count: self.total_count(),
+            }),
+            Stage::SurroundChar { target } => ParseResult::Complete(NormalCommand::Operator(OperatorCommand {
+                operator: Operator::Surround(key),
+                target,
+                count: self.total_count(),
+                register: self.register,
+            })),
+            Stage::DeleteSurround => ParseResult::Complete(NormalCommand::DeleteSurround { target: key }),
+            Stage::ChangeSurround { target: None } => {
+                self.stage = Stage::ChangeSurround { target: Some(key) };
+                ParseResult::Pending
+            }
+            Stage::ChangeSurround { target: Some(target) } => {
+                ParseResult::Complete(NormalCommand::ChangeSurround { target, replacement: key })
+            }
+            Stage::Record => ParseResult::Complete(NormalCommand::Record { register: key }),
+            Stage::Replay => ParseResult::Complete(NormalCommand::Replay {
+                register: key,
+                count: self.total_count(),
+            }),
+            Stage::Start => self.feed_start(key),
+        };
+        if result != ParseResult::Pending {
+            self.reset();
+        }
+        result
+    }
+
+    fn feed_start(&mut self, key: char) -> ParseResult {
+        // 计数：0 只有在已经开始输入计数时才算数字，否则是移到行首
+        if let Some(digit) = key.to_digit(10) {
+            let slot = if self.operator.is_some() {
+                &mut self.motion_count
+            } else {
+                &mut self.count
+            };
+            if digit != 0 || slot.is_some() {
+                *slot = Some(slot.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+                return ParseResult::Pending;
+            }
+        }
+
+        match self.operator {
+            Some(operator) => {
+                if key == operator.key() {
+                    return self.complete_operator(OperatorTarget::Line);
+                }
+                if key == 's' {
+                    match operator {
+                        // 包围字符在动作之后输入，先用 '\0' 占位
+                        Operator::Yank => self.operator = Some(Operator::Surround('\0')),
+                        Operator::Delete => self.stage = Stage::DeleteSurround,
+                        Operator::Change => self.stage = Stage::ChangeSurround { target: None },
+                        _ => return ParseResult::Invalid,
+                    }
+                    return ParseResult::Pending;
+                }
+                match key {
+                    'a' => self.stage = Stage::TextObject { around: true },
+                    'i' => self.stage = Stage::TextObject { around: false },
+                    'g' => self.stage = Stage::G,
+                    '|' => return self.complete_column(),
+                    'G' if self.has_count() => return self.complete_line(),
+                    '/' | '?' if !matches!(operator, Operator::Surround(_)) => {
+                        let command = OperatorCommand {
+                            operator,
+                            target: OperatorTarget::Motion(Motion::SearchNext),
+                            count: self.total_count(),
+                            register: self.register,
+                        };
+                        return ParseResult::Complete(NormalCommand::SearchOperator { command, backward: key == '?' });
+                    }
+                    'n' => return self.complete_operator(OperatorTarget::Motion(Motion::SearchNext)),
+                    'N' => return self.complete_operator(OperatorTarget::Motion(Motion::SearchPrev)),
+                    _ => {
+                        return match motion_for_key(key) {
+                            Some(motion) => self.complete_operator(OperatorTarget::Motion(motion)),
+                            None => ParseResult::Invalid,
+                        };
+                    }
+                }
+                ParseResult::Pending
+            }
+            None => {
+                if key == '"' {
+                    self.stage = Stage::Register;
+                    return ParseResult::Pending;
+                }
+                if let Some(operator) = Operator::from_key(key) {
+                    self.operator = Some(operator);
+                    return ParseResult::Pending;
+                }
+                if key == 'g' {
+                    self.stage = Stage::G;
+                    return ParseResult::Pending;
+                }
+                if key == 'r' {
+                    self.stage = Stage::ReplaceChar;
+                    return ParseResult::Pending;
+                }
+                if key == 'z' {
+                    self.stage = Stage::Z;
+                    return ParseResult::Pending;
+                }
+                if key == ']' || key == '[' {
+                    self.stage = Stage::Bracket { forward: key == ']' };
+                    return ParseResult::Pending;
+                }
+                if key == 'q' || key == '@' {
+                    self.stage = if key == 'q' { Stage::Record } else { Stage::Replay };
+                    return ParseResult::Pending;
+                }
+                if key == '|' {
+                    return self.complete_column();
+                }
+                if key == 'G' && self.has_count() {
+                    return self.complete_line();
+                }
+                match motion_for_key(key) {
+                    Some(motion) => ParseResult::Complete(NormalCommand::Motion {
+                        motion,
+                        count: self.total_count(),
+                    }),
+                    None => ParseResult::Complete(NormalCommand::Key {
+                        key,
+                        count: self.total_count(),
+                        register: self.register,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn feed_register(&mut self, key: char) -> ParseResult {
+        if is_register_name(key) {
+            self.register = Some(key);
+            self.stage = Stage::Start;
+            ParseResult::Pending
+        } else {
+            ParseResult::Invalid
+        }
+    }
+
+    fn feed_g(&mut self, key: char) -> ParseResult {
+        // gu / gU / g~ / gq 等待动作；操作符之后再按一次（gugu、gqgq）作用于整行
+        if let Some(operator) = Operator::from_g_key(key) {
+            return match self.operator {
+                None => {
+                    self.operator = Some(operator);
+                    self.stage = Stage::Start;
+                    ParseResult::Pending
+                }
+                Some(pending) if pending == operator => self.complete_operator(OperatorTarget::Line),
+                Some(_) => ParseResult::Invalid,
+            };
+        }
+        let motion = match key {
+            'g' if self.has_count() => return self.complete_line(),
+            'g' => Motion::DocumentStart,
+            'e' => Motion::WordEndBackward,
+            'E' => Motion::WORDEndBackward,
+            '_' => Motion::LastNonBlank,
+            '0' => Motion::ScreenLineStart,
+            '$' => Motion::ScreenLineEnd,
+            _ if self.operator.is_none() => {
+                return ParseResult::Complete(NormalCommand::G {
+                    key,
+                    count: self.total_count(),
+                });
+            }
+            _ => return ParseResult::Invalid,
+        };
+        match self.operator {
+            Some(_) => self.complete_operator(OperatorTarget::Motion(motion)),
+            None => ParseResult::Complete(NormalCommand::Motion {
+                motion,
+                count: self.total_count(),
+            }),
+        }
+    }
+
+    /// zf 之后等待动作或文本对象，zF 折叠 count 行，其他按键交给调用方
+    fn feed_z(&mut self, key: char) -> ParseResult {
+        match key {
+            'f' => {
+                self.operator = Some(Operator::Fold);
+                self.stage = Stage::Start;
+                ParseResult::Pending
+            }
+            'F' => {
+                self.operator = Some(Operator::Fold);
+                self.complete_operator(OperatorTarget::Line)
+            }
+            _ => ParseResult::Complete(NormalCommand::Z {
+                key,
+                count: self.total_count(),
+            }),
+        }
+    }
+
+    fn feed_text_object(&mut self, key: char, around: bool) -> ParseResult {
+        match text_object_for_key(key, around) {
+            Some(obj) => self.complete_operator(OperatorTarget::TextObject(obj)),
+            None => ParseResult::Invalid,
+        }
+    }
+
+    fn complete_operator(&mut self, target: OperatorTarget) -> ParseResult {
+        match self.operator {
+            Some(Operator::Surround(_)) => {
+                self.stage = Stage::SurroundChar { target };
+                ParseResult::Pending
+            }
+            Some(operator) => ParseResult::Complete(NormalCommand::Operator(OperatorCommand {
+                operator,
+                target,
+                count: self.total_count(),
+                register: self.register,
+            })),
+            None => ParseResult::Invalid,
+        }
+    }
+
+    /// `|` 的计数是目标列，移动只执行一次（如 `10|`、`d5|`）
+    fn complete_column(&mut self) -> ParseResult {
+        let motion = Motion::ToColumn(self.total_count());
+        self.count = None;
+        self.motion_count = None;
+        match self.operator {
+            Some(_) => self.complete_operator(OperatorTarget::Motion(motion)),
+            None => ParseResult::Complete(NormalCommand::Motion { motion, count: 1 }),
+        }
+    }
+
+    /// `{count}G` / `{count}gg` 的计数是目标行，移动只执行一次（如 `5G`、`d3G`）
+    fn complete_line(&mut self) -> ParseResult {
+        let motion = Motion::ToLine(self.total_count());
+        self.count = None;
+        self.motion_count = None;
+        match self.operator {
+            Some(_) => self.complete_operator(OperatorTarget::Motion(motion)),
+            None => ParseResult::Complete(NormalCommand::Motion { motion, count: 1 }),
+        }
+    }
+
+    /// 操作符前后的计数相乘（如 2d3w 删除 6 个单词）
+    fn total_count(&self) -> usize {
+        self.count.unwrap_or(1) * self.motion_count.unwrap_or(1)
+    }
+}
+
+/// 按键对应的光标移动
+pub fn motion_for_key(key: char) -> Option<Motion> {
+    match key {
+        'h' => Some(Motion::Left),
+        'l' => Some(Motion::Right),
+        'k' => Some(Motion::Up),
+        'j' => Some(Motion::Down),
+        '0' => Some(Motion::LineStart),
+        '$' => Some(Motion::LineEnd),
+        '^' => Some(Motion::FirstNonBlank),
+        'w' => Some(Motion::WordForward),
+        'b' => Some(Motion::WordBackward),
+        'e' => Some(Motion::WordEnd),
+        'W' => Some(Motion::WORDForward),
+        'B' => Some(Motion::WORDBackward),
+        'E' => Some(Motion::WORDEnd),
+        'G' => Some(Motion::DocumentEnd),
+        'H' => Some(Motion::WindowTop),
+        'M' => Some(Motion::WindowMiddle),
+        'L' => Some(Motion::WindowBottom),
+        _ => None,
+    }
+}
+
+/// 按键对应的文本对象（`around` 表示 a 前缀，否则为 i 前缀）
+pub fn text_object_for_key(key: char, around: bool) -> Option<TextObject> {
+    let obj = match (key, around) {
+        ('w' | 'W', true) => TextObject::AroundWord,
+        ('w' | 'W', false) => TextObject::InnerWord,
+        ('s', true) => TextObject::AroundSentence,
+        ('s', false) => TextObject::InnerSentence,
+        ('p', true) => TextObject::AroundParagraph,
+        ('p', false) => TextObject::InnerParagraph,
+        ('t', true) => TextObject::AroundTag,
+        ('t', false) => TextObject::InnerTag,
+        ('f', true) => TextObject::AroundFunction,
+        ('f', false) => TextObject::InnerFunction,
+        ('c', true) => TextObject::AroundClass,
+        ('c', false) => TextObject::InnerClass,
+        ('(' | ')' | 'b', true) => TextObject::AroundBracket('('),
+        ('(' | ')' | 'b', false) => TextObject::InnerBracket('('),
+        ('[' | ']', true) => TextObject::AroundBracket('['),
+        ('[' | ']', false) => TextObject::InnerBracket('['),
+        ('{' | '}' | 'B', true) => TextObject::AroundBracket('{'),
+        ('{' | '}' | 'B', false) => TextObject::InnerBracket('{'),
+        ('<' | '>', true) => TextObject::AroundBracket('<'),
+        ('<' | '>', false) => TextObject::InnerBracket('<'),
+        ('"' | '\'' | '`', true) => TextObject::AroundQuote(key),
+        ('"' | '\'' | '`', false) => TextObject::InnerQuote(key),
+        _ => return None,
+    };
+    Some(obj)
+}
+
+/// 是否是合法的寄存器名（只读寄存器 . % # : / 只能用于粘贴）
+pub(crate) fn is_register_name(key: char) -> bool {
+    key.is_ascii_alphanumeric() || matches!(key, '"' | '-' | '+' | '*' | '_' | '.' | '%' | '#' | ':' | '/')
+}
@@ -1,22 +1,93 @@
+pub mod ai_edit;
+pub mod ai_plugin;
+pub mod autocmd;
+pub mod blame;
 pub mod buffer;
 pub mod buffer_snapshot;
+pub mod chat;
+pub mod colorscheme;
+pub mod commit_msg;
+pub mod completion;
+pub mod config;
 pub mod cursor;
+pub mod diff;
+pub mod display;
 pub mod editor;
+pub mod explain;
+pub mod encoding;
+pub mod file_ops;
+pub mod fillchars;
+pub mod fold;
+pub mod finder;
+pub mod git;
+pub mod git_plugin;
+pub mod git_signs;
+pub mod grep;
+pub mod hex;
+pub mod history;
+pub mod i18n;
+pub mod input;
+pub mod job;
+pub mod keymap;
+pub mod line_ending;
+pub mod local_options;
+pub mod loader;
+pub mod lsp;
+pub mod lsp_plugin;
 pub mod macros;
+pub mod mapping;
+pub mod match_index;
 pub mod mode;
+pub mod multi_cursor;
 pub mod motion;
+pub mod number;
+pub mod pairs;
+pub mod paste;
+pub mod picker;
+pub mod plugin;
+pub mod positions;
+pub mod quickfix;
+pub mod range;
 pub mod edit;
+pub mod edit_log;
+pub mod ex_command;
+pub mod expr;
 pub mod register;
 pub mod search;
+pub mod session;
+pub mod script;
+pub mod shell;
+pub mod statusline;
+pub mod suggestion;
 pub mod replace;
+pub mod symbols;
+pub mod syntax;
+pub mod tabpage;
+pub mod terminal;
+pub mod tail;
 pub mod text_object;
+pub mod undo_file;
+pub mod undo_tree;
+pub mod visual;
+pub mod waker;
+pub mod watch;
+pub mod window;
 
 pub use buffer::Buffer;
 pub use buffer_snapshot::{BufferSnapshot, SnapshotManager};
+pub use completion::{CompletionItem, CompletionMenu};
 pub use cursor::Cursor;
 pub use editor::Editor;
+pub use fillchars::FillChars;
+pub use i18n::Locale;
+pub use input::KeyInput;
+pub use job::{JobEvent, JobId, JobManager, JobSpec, JobStatus};
+pub use keymap::{KeyParser, NormalCommand, Operator, OperatorCommand, OperatorTarget, ParseResult};
+pub use mapping::{KeyMappings, MapKey, MapLookup, MapMode, MapScope, Mapping};
 pub use mode::Mode;
+pub use picker::{Picker, PickerItem};
 pub use register::{Register, RegisterManager};
+pub use script::{ScriptResult, ScriptStep};
 pub use search::{SearchState, SearchDirection};
 pub use replace::{ReplaceResult, replace_in_buffer, parse_substitute_command};
 pub use text_object::{TextObject, parse_text_object};
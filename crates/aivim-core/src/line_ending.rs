@@ -0,0 +1,125 @@
+//! 行尾符检测模块
+//!
+//! 统计缓冲区中 LF 和 CRLF 行尾的数量，用于：
+//! - 状态栏显示混合行尾警告
+//! - :fixlineendings 将混合行尾统一为一种
+//!
+//! 读入文件时检测文件格式（fileformat）：所有行都以 CRLF 结尾时为 dos 格式，
+//! 缓冲区中只保存 `\n`，写入时再转换回 CRLF；混合行尾的文件按 unix 格式原样读入。
+
+use std::borrow::Cow;
+
+use ropey::Rope;
+
+/// 行尾符类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix 风格 `\n`
+    Lf,
+    /// Windows 风格 `\r\n`
+    Crlf,
+}
+
+impl LineEnding {
+    /// 从名称解析（lf / crlf，也接受 unix / dos）
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "lf" | "unix" => Some(LineEnding::Lf),
+            "crlf" | "dos" => Some(LineEnding::Crlf),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
+
+    /// 文件格式的名称（:set fileformat）
+    pub fn format_name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "unix",
+            LineEnding::Crlf => "dos",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// 行尾符统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineEndingStats {
+    /// 以 `\n` 结尾的行数
+    pub lf: usize,
+    /// 以 `\r\n` 结尾的行数
+    pub crlf: usize,
+}
+
+impl LineEndingStats {
+    /// 统计文本中的行尾符
+    pub fn scan(rope: &Rope) -> Self {
+        let mut stats = Self::default();
+        let mut prev = None;
+        for c in rope.chars() {
+            if c == '\n' {
+                if prev == Some('\r') {
+                    stats.crlf += 1;
+                } else {
+                    stats.lf += 1;
+                }
+            }
+            prev = Some(c);
+        }
+        stats
+    }
+
+    /// 是否同时存在 LF 和 CRLF
+    pub fn is_mixed(&self) -> bool {
+        self.lf > 0 && self.crlf > 0
+    }
+
+    /// 占多数的行尾符（数量相同时取 LF）
+    pub fn dominant(&self) -> LineEnding {
+        if self.crlf > self.lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// 转换为指定行尾符时需要修改的行数
+    pub fn lines_to_convert(&self, target: LineEnding) -> usize {
+        match target {
+            LineEnding::Lf => self.crlf,
+            LineEnding::Crlf => self.lf,
+        }
+    }
+}
+
+/// 将文本中的所有行尾符统一为 `target`
+pub fn normalize(text: &str, target: LineEnding) -> String {
+    let lf = text.replace("\r\n", "\n");
+    match target {
+        LineEnding::Lf => lf,
+        LineEnding::Crlf => lf.replace('\n', "\r\n"),
+    }
+}
+
+/// 检测读入的文件的格式，返回格式和缓冲区中保存的文本
+///
+/// 至少有一行、且所有行都以 `\r\n` 结尾时为 CRLF，文本中的 `\r\n` 转换为 `\n`；否则为 LF，文本不变
+pub fn detect(text: &str) -> (LineEnding, Cow<'_, str>) {
+    let lines = text.matches('\n').count();
+    if lines > 0 && text.matches("\r\n").count() == lines {
+        (LineEnding::Crlf, Cow::Owned(text.replace("\r\n", "\n")))
+    } else {
+        (LineEnding::Lf, Cow::Borrowed(text))
+    }
+}
@@ -0,0 +1,128 @@
+//! 后台读入大文件 (:e)
+//!
+//! 大于 `ASYNC_LOAD_SIZE` 的文件在后台线程中读入和解码，读入期间原来的缓冲区可以继续编辑：
+//! - 后台线程按块读取文件，每读完一块报告进度并唤醒事件循环，状态栏的 `loading` 片段显示转圈的符号和百分比
+//! - 读完之后在后台线程中创建缓冲区（解码、建立 rope），主线程取出后切换到它
+//! - 同一时间只读入一个文件，再次 `:e` 另一个大文件时放弃之前的读入；丢弃 `FileLoad` 后后台线程在读下一块时停止
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::buffer::{Buffer, BufferId};
+use crate::waker::Waker;
+
+/// 超过这个大小（字节）的文件在后台读入
+pub const ASYNC_LOAD_SIZE: u64 = 16 * 1024 * 1024;
+
+/// 每次读取的字节数
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// 转圈符号每一帧的时间
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_ASCII: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// 转圈符号的第 `frame` 帧
+pub fn spinner(frame: usize, ascii: bool) -> &'static str {
+    if ascii {
+        SPINNER_ASCII[frame % SPINNER_ASCII.len()]
+    } else {
+        SPINNER[frame % SPINNER.len()]
+    }
+}
+
+/// 后台线程发回的消息
+enum LoadEvent {
+    /// 已经读取的字节数
+    Progress(u64),
+    Done(io::Result<Box<Buffer>>),
+}
+
+/// 一个正在后台读入的文件
+#[derive(Debug)]
+pub struct FileLoad {
+    path: PathBuf,
+    /// 读入完成后缓冲区的编号
+    buffer: BufferId,
+    total: u64,
+    read: u64,
+    started: Instant,
+    /// 转圈符号当前的帧
+    frame: usize,
+    receiver: Receiver<LoadEvent>,
+}
+
+impl FileLoad {
+    /// 开始在后台读入 `path`（大小为 `total` 字节），读完后的缓冲区编号为 `buffer`
+    pub fn start(path: &Path, buffer: BufferId, total: u64, now: Instant, waker: Waker) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let thread_path = path.to_path_buf();
+        thread::spawn(move || {
+            let result = read_file(&thread_path, total, &sender, &waker)
+                .map(|bytes| Box::new(Buffer::from_file_bytes(buffer, &thread_path, &bytes)));
+            if sender.send(LoadEvent::Done(result)).is_ok() {
+                waker.wake();
+            }
+        });
+        Self { path: path.to_path_buf(), buffer, total, read: 0, started: now, frame: 0, receiver }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 已经读取的百分比
+    pub fn percent(&self) -> u64 {
+        (self.read.min(self.total) * 100).checked_div(self.total).unwrap_or(100)
+    }
+
+    /// 转圈符号当前的帧
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// 取出后台线程的进度，读入结束时返回缓冲区或错误；`changed` 为进度或转圈符号是否变化（需要重绘）
+    pub fn poll(&mut self, now: Instant) -> (bool, Option<io::Result<Buffer>>) {
+        let frame = (now.saturating_duration_since(self.started).as_millis() / SPINNER_INTERVAL.as_millis()) as usize;
+        let mut changed = frame != self.frame;
+        self.frame = frame;
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                LoadEvent::Progress(read) => {
+                    changed |= read != self.read;
+                    self.read = read;
+                }
+                LoadEvent::Done(result) => return (true, Some(result.map(|buffer| *buffer))),
+            }
+        }
+        (changed, None)
+    }
+
+    /// 读入完成后缓冲区的编号
+    pub fn buffer(&self) -> BufferId {
+        self.buffer
+    }
+}
+
+/// 按块读取文件，每读完一块报告进度；接收方已经丢弃时停止
+fn read_file(path: &Path, total: u64, sender: &Sender<LoadEvent>, waker: &Waker) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut bytes = Vec::with_capacity(total as usize);
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let len = file.read(&mut chunk)?;
+        if len == 0 {
+            return Ok(bytes);
+        }
+        bytes.extend_from_slice(&chunk[..len]);
+        if sender.send(LoadEvent::Progress(bytes.len() as u64)).is_err() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Loading cancelled"));
+        }
+        waker.wake();
+    }
+}
@@ -0,0 +1,125 @@
+//! 窗口和缓冲区的局部选项 (:setlocal)
+//!
+//! 一部分选项可以只对一个窗口或一个缓冲区设置，其余的选项只有全局值：
+//! - 窗口局部：number、relativenumber、cursorline、cursorcolumn、wrap，分割窗口时新窗口复制局部值
+//! - 缓冲区局部：shiftwidth、tabstop、expandtab、autoindent
+//! - `:setlocal` 只设置当前窗口或缓冲区的局部值，没有局部值时使用全局值
+//! - `:set` 设置全局值并清除当前窗口或缓冲区的局部值，其他窗口和缓冲区的局部值保持不变
+//! - `:setglobal` 只设置全局值
+
+use crate::editor::EditorOptions;
+
+/// 窗口的局部值，None 时使用全局值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowOptions {
+    pub number: Option<bool>,
+    pub relativenumber: Option<bool>,
+    pub cursorline: Option<bool>,
+    pub cursorcolumn: Option<bool>,
+    pub wrap: Option<bool>,
+}
+
+/// 缓冲区的局部值，None 时使用全局值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferOptions {
+    pub shiftwidth: Option<usize>,
+    pub tabstop: Option<usize>,
+    pub expandtab: Option<bool>,
+    pub autoindent: Option<bool>,
+}
+
+/// 可以设置局部值的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalOption {
+    Number,
+    RelativeNumber,
+    CursorLine,
+    CursorColumn,
+    Wrap,
+    ShiftWidth,
+    TabStop,
+    ExpandTab,
+    AutoIndent,
+}
+
+impl LocalOption {
+    /// `:set` 的参数（`nu`、`nonu`、`ts=4`）设置的选项，不能设置局部值时返回 None
+    pub fn parse(option: &str) -> Option<Self> {
+        let name = option.split_once('=').map_or(option, |(name, _)| name);
+        Self::from_name(name).or_else(|| Self::from_name(name.strip_prefix("no")?))
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let option = match name {
+            "nu" | "number" => LocalOption::Number,
+            "rnu" | "relativenumber" => LocalOption::RelativeNumber,
+            "cursorline" => LocalOption::CursorLine,
+            "cuc" | "cursorcolumn" => LocalOption::CursorColumn,
+            "wrap" => LocalOption::Wrap,
+            "sw" | "shiftwidth" => LocalOption::ShiftWidth,
+            "ts" | "tabstop" => LocalOption::TabStop,
+            "et" | "expandtab" => LocalOption::ExpandTab,
+            "ai" | "autoindent" => LocalOption::AutoIndent,
+            _ => return None,
+        };
+        Some(option)
+    }
+
+    /// 窗口局部选项，否则为缓冲区局部选项
+    pub fn is_window_local(self) -> bool {
+        matches!(
+            self,
+            LocalOption::Number
+                | LocalOption::RelativeNumber
+                | LocalOption::CursorLine
+                | LocalOption::CursorColumn
+                | LocalOption::Wrap
+        )
+    }
+
+    /// 把 `options` 中这个选项的值存为局部值
+    pub fn store(self, options: &EditorOptions, window: &mut WindowOptions, buffer: &mut BufferOptions) {
+        match self {
+            LocalOption::Number => window.number = Some(options.number),
+            LocalOption::RelativeNumber => window.relativenumber = Some(options.relativenumber),
+            LocalOption::CursorLine => window.cursorline = Some(options.cursorline),
+            LocalOption::CursorColumn => window.cursorcolumn = Some(options.cursorcolumn),
+            LocalOption::Wrap => window.wrap = Some(options.wrap),
+            LocalOption::ShiftWidth => buffer.shiftwidth = Some(options.shiftwidth),
+            LocalOption::TabStop => buffer.tabstop = Some(options.tabstop),
+            LocalOption::ExpandTab => buffer.expandtab = Some(options.expandtab),
+            LocalOption::AutoIndent => buffer.autoindent = Some(options.autoindent),
+        }
+    }
+
+    /// 清除局部值，之后使用全局值
+    pub fn clear(self, window: &mut WindowOptions, buffer: &mut BufferOptions) {
+        match self {
+            LocalOption::Number => window.number = None,
+            LocalOption::RelativeNumber => window.relativenumber = None,
+            LocalOption::CursorLine => window.cursorline = None,
+            LocalOption::CursorColumn => window.cursorcolumn = None,
+            LocalOption::Wrap => window.wrap = None,
+            LocalOption::ShiftWidth => buffer.shiftwidth = None,
+            LocalOption::TabStop => buffer.tabstop = None,
+            LocalOption::ExpandTab => buffer.expandtab = None,
+            LocalOption::AutoIndent => buffer.autoindent = None,
+        }
+    }
+}
+
+/// 用窗口和缓冲区的局部值覆盖全局值之后的选项
+pub fn effective(global: &EditorOptions, window: &WindowOptions, buffer: &BufferOptions) -> EditorOptions {
+    EditorOptions {
+        number: window.number.unwrap_or(global.number),
+        relativenumber: window.relativenumber.unwrap_or(global.relativenumber),
+        cursorline: window.cursorline.unwrap_or(global.cursorline),
+        cursorcolumn: window.cursorcolumn.unwrap_or(global.cursorcolumn),
+        wrap: window.wrap.unwrap_or(global.wrap),
+        shiftwidth: buffer.shiftwidth.unwrap_or(global.shiftwidth),
+        tabstop: buffer.tabstop.unwrap_or(global.tabstop),
+        expandtab: buffer.expandtab.unwrap_or(global.expandtab),
+        autoindent: buffer.autoindent.unwrap_or(global.autoindent),
+        ..*global
+    }
+}
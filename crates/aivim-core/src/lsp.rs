@@ -0,0 +1,401 @@
+//! 语言服务器管理模块 (:set lsp)
+//!
+//! 开启 lsp 选项后，为有对应服务器的文件（见 aivim_lsp::config）启动语言服务器：
+//! - 同一种语言、同一个项目根目录的文件共用一个服务器
+//! - 缓冲区的 changedtick 变化时发送 `textDocument/didChange`：服务器支持增量同步时按缓冲区的修改记录
+//!   （见 edit_log 模块）只发送修改的区域，否则发送全部文本；新打开的缓冲区发送 `didOpen`，
+//!   被删除的缓冲区发送 `didClose`
+//! - 服务器发布的诊断按文件保存，界面在行号左侧显示标记、给范围加下划线，并在状态栏中统计；
+//!   诊断同时放入显示这个文件的窗口的位置列表，用 :lopen、:lnext 查看和跳转
+//! - 编辑器通过 `request` 发送请求（如补全），回复到达后用 `take_response` 取出
+//! - 无法启动或意外退出的服务器不再重新启动，直到重新开启 lsp 选项
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use aivim_lsp::config::language_id;
+use aivim_lsp::{completion, uri, LspClient, LspEvent, LspSettings, Position};
+use ropey::Rope;
+use serde_json::Value;
+
+pub use aivim_lsp::{Diagnostic, Severity};
+
+use crate::buffer::{Buffer, BufferId};
+use crate::quickfix::{QuickfixEntry, QuickfixKind, QuickfixList};
+use crate::waker::Waker;
+
+/// 诊断组成的位置列表的标题，窗口已有其他位置列表时不覆盖
+pub const DIAGNOSTICS_TITLE: &str = "Diagnostics";
+
+/// 一个正在运行的服务器
+#[derive(Debug)]
+struct Server {
+    /// 语言名称
+    name: String,
+    root: PathBuf,
+    client: LspClient,
+    /// 服务器的能力，初始化完成之前为 Null
+    capabilities: Value,
+}
+
+/// 发送给服务器的请求，用于取出回复
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LspRequest {
+    server: usize,
+    id: u64,
+}
+
+/// 发送给服务器的文档
+#[derive(Debug)]
+struct Document {
+    uri: String,
+    /// 服务器在 `servers` 中的下标
+    server: usize,
+    version: i64,
+    /// 上次发送时缓冲区的 changedtick
+    tick: u64,
+    /// 上次发送时缓冲区的内容（与缓冲区共享节点），用于计算增量修改在服务器的文档中的位置
+    text: Rope,
+}
+
+/// 管理所有语言服务器和发送给它们的文档
+#[derive(Debug, Default)]
+pub struct LspManager {
+    settings: LspSettings,
+    servers: Vec<Option<Server>>,
+    documents: HashMap<BufferId, Document>,
+    /// 各文件的诊断，按 URI 保存
+    diagnostics: HashMap<String, Vec<Diagnostic>>,
+    /// 诊断有变化，还没有更新窗口的位置列表
+    diagnostics_changed: bool,
+    /// 无法启动或已经退出的服务器（语言名称），不再重新启动
+    failed: HashSet<String>,
+    /// 等待回复的请求
+    pending: HashSet<LspRequest>,
+    /// 已经到达、还没有取出的回复
+    responses: HashMap<LspRequest, Result<Value, String>>,
+}
+
+/// 字符位置在文档中的行和 UTF-16 列
+fn lsp_position(text: &Rope, char_idx: usize) -> Position {
+    let line = text.char_to_line(char_idx);
+    let column = text.slice(text.line_to_char(line)..char_idx).chars().map(char::len_utf16).sum();
+    Position::new(line, column)
+}
+
+impl LspManager {
+    pub fn new(settings: LspSettings) -> Self {
+        Self {
+            settings,
+            ..Self::default()
+        }
+    }
+
+    pub fn settings(&self) -> &LspSettings {
+        &self.settings
+    }
+
+    /// 使用新的服务器设置，关闭所有正在运行的服务器
+    pub fn set_settings(&mut self, settings: LspSettings) {
+        *self = Self::new(settings);
+    }
+
+    /// 关闭所有服务器，清除诊断
+    pub fn stop(&mut self) {
+        *self = Self::new(self.settings.clone());
+        self.diagnostics_changed = true;
+    }
+
+    /// 正在运行的服务器的语言名称
+    pub fn running_servers(&self) -> Vec<&str> {
+        self.servers.iter().flatten().map(|server| server.name.as_str()).collect()
+    }
+
+    /// 缓冲区是否已经发送给服务器
+    pub fn is_attached(&self, buffer: BufferId) -> bool {
+        self.documents.contains_key(&buffer)
+    }
+
+    /// 发送给服务器的缓冲区的 URI
+    pub fn document_uri(&self, buffer: BufferId) -> Option<&str> {
+        self.documents.get(&buffer).map(|document| document.uri.as_str())
+    }
+
+    /// 缓冲区的服务器中触发补全的字符，服务器还没有完成初始化时为空
+    pub fn trigger_characters(&self, buffer: BufferId) -> Vec<char> {
+        self.documents
+            .get(&buffer)
+            .and_then(|document| self.servers[document.server].as_ref())
+            .map(|server| completion::trigger_characters(&server.capabilities))
+            .unwrap_or_default()
+    }
+
+    /// 向缓冲区的服务器发送请求，缓冲区没有发送给服务器时返回 None
+    pub fn request(&mut self, buffer: BufferId, method: &str, params: Value) -> Option<LspRequest> {
+        let index = self.documents.get(&buffer)?.server;
+        let server = self.servers[index].as_mut()?;
+        let request = LspRequest {
+            server: index,
+            id: server.client.request(method, params),
+        };
+        self.pending.insert(request);
+        Some(request)
+    }
+
+    /// 取出请求的回复，还没有到达时返回 None
+    pub fn take_response(&mut self, request: LspRequest) -> Option<Result<Value, String>> {
+        let response = self.responses.remove(&request)?;
+        self.pending.remove(&request);
+        Some(response)
+    }
+
+    /// 不再需要请求的回复，之后到达的回复被丢弃
+    pub fn cancel(&mut self, request: LspRequest) {
+        self.pending.remove(&request);
+        self.responses.remove(&request);
+    }
+
+    /// 缓冲区的诊断，按位置排序
+    pub fn diagnostics(&self, buffer: &Buffer) -> &[Diagnostic] {
+        let uri = match self.documents.get(&buffer.id()) {
+            Some(document) => document.uri.clone(),
+            None => match buffer.file_path() {
+                Some(path) => uri::from_path(path),
+                None => return &[],
+            },
+        };
+        self.diagnostics.get(&uri).map_or(&[], Vec::as_slice)
+    }
+
+    /// 缓冲区的诊断组成的位置列表（:lopen、:lnext），没有诊断时返回 None
+    pub fn location_list(&self, buffer: &Buffer) -> Option<QuickfixList> {
+        let path = buffer.file_path()?;
+        let diagnostics = self.diagnostics(buffer);
+        if diagnostics.is_empty() {
+            return None;
+        }
+        let mut list = QuickfixList::new(DIAGNOSTICS_TITLE);
+        list.extend(diagnostics.iter().map(|diagnostic| {
+            let line = diagnostic.start.line;
+            let text = buffer.line_text(line).unwrap_or_default();
+            let column = aivim_lsp::diagnostic::utf16_to_char(&text, diagnostic.start.character);
+            let message = match &diagnostic.source {
+                Some(source) => format!("{}: {}", source, diagnostic.message),
+                None => diagnostic.message.clone(),
+            };
+            let kind = match diagnostic.severity {
+                Severity::Error => QuickfixKind::Error,
+                Severity::Warning => QuickfixKind::Warning,
+                Severity::Information => QuickfixKind::Info,
+                Severity::Hint => QuickfixKind::Note,
+            };
+            QuickfixEntry::new(path, line, column, message).with_kind(kind)
+        }));
+        Some(list)
+    }
+
+    /// 取出诊断是否有变化（发布了新的诊断、关闭了文档或服务器）
+    pub fn take_diagnostics_changed(&mut self) -> bool {
+        std::mem::take(&mut self.diagnostics_changed)
+    }
+
+    /// 缓冲区中各严重程度的诊断数，顺序同 `Severity::ALL`
+    pub fn diagnostic_counts(&self, buffer: &Buffer) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for diagnostic in self.diagnostics(buffer) {
+            counts[diagnostic.severity as usize] += 1;
+        }
+        counts
+    }
+
+    /// 把缓冲区的变化发送给服务器，返回无法启动的服务器的错误
+    ///
+    /// 没有文件名的缓冲区（如临时缓冲区）和二进制文件不发送
+    pub fn sync<'a>(&mut self, buffers: impl Iterator<Item = &'a Buffer>, waker: &Waker) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut open = HashSet::new();
+        for buffer in buffers {
+            let Some(path) = buffer.file_path().filter(|_| !buffer.is_binary()) else {
+                continue;
+            };
+            open.insert(buffer.id());
+            let uri = uri::from_path(path);
+            if let Some(document) = self.documents.get_mut(&buffer.id()) {
+                if document.uri == uri {
+                    if document.tick != buffer.changedtick() {
+                        document.version += 1;
+                        if let Some(server) = &self.servers[document.server] {
+                            let edit = buffer
+                                .edits_since(document.tick)
+                                .flatten()
+                                .filter(|_| aivim_lsp::client::incremental_sync(&server.capabilities));
+                            match edit {
+                                Some(edit) => {
+                                    let start = lsp_position(&document.text, edit.start);
+                                    let end = lsp_position(&document.text, edit.old_end());
+                                    let text = buffer.slice(edit.start..edit.new_end()).to_string();
+                                    server.client.did_change_range(&document.uri, document.version, start, end, &text);
+                                }
+                                None => server.client.did_change(&document.uri, document.version, &buffer.rope().to_string()),
+                            }
+                        }
+                        document.tick = buffer.changedtick();
+                        document.text = buffer.rope().clone();
+                    }
+                    continue;
+                }
+                // 缓冲区换了文件名（:saveas），按新文件重新打开
+                self.close(buffer.id());
+            }
+            if let Err(error) = self.open(buffer, path, uri, waker) {
+                errors.push(error);
+            }
+        }
+        let closed: Vec<BufferId> = self.documents.keys().filter(|id| !open.contains(id)).copied().collect();
+        for id in closed {
+            self.close(id);
+        }
+        errors
+    }
+
+    /// 向文件的服务器发送 `didOpen`，服务器还没有运行时启动它
+    fn open(&mut self, buffer: &Buffer, path: &Path, uri: String, waker: &Waker) -> Result<(), String> {
+        let Some(config) = self.settings.server_for(path) else {
+            return Ok(());
+        };
+        if self.failed.contains(&config.name) {
+            return Ok(());
+        }
+        let root = config.root_dir(path);
+        let existing = self.servers.iter().position(|server| {
+            server
+                .as_ref()
+                .is_some_and(|server| server.name == config.name && server.root == root)
+        });
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                let waker = waker.clone();
+                let client = LspClient::start(config, &root, move || waker.wake()).map_err(|e| {
+                    self.failed.insert(config.name.clone());
+                    format!("{}: {}", config.command, e)
+                })?;
+                self.servers.push(Some(Server {
+                    name: config.name.clone(),
+                    root,
+                    client,
+                    capabilities: Value::Null,
+                }));
+                self.servers.len() - 1
+            }
+        };
+        let language = language_id(path, config);
+        if let Some(server) = &self.servers[index] {
+            server.client.did_open(&uri, &language, 1, &buffer.rope().to_string());
+        }
+        self.documents.insert(
+            buffer.id(),
+            Document {
+                uri,
+                server: index,
+                version: 1,
+                tick: buffer.changedtick(),
+                text: buffer.rope().clone(),
+            },
+        );
+        Ok(())
+    }
+
+    fn close(&mut self, buffer: BufferId) {
+        let Some(document) = self.documents.remove(&buffer) else {
+            return;
+        };
+        if let Some(server) = &self.servers[document.server] {
+            server.client.did_close(&document.uri);
+        }
+        self.diagnostics.remove(&document.uri);
+        self.diagnostics_changed = true;
+    }
+
+    /// 取出服务器的消息，返回意外退出的服务器的语言名称
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut exited = Vec::new();
+        for index in 0..self.servers.len() {
+            let Some(server) = &mut self.servers[index] else {
+                continue;
+            };
+            for event in server.client.poll() {
+                match event {
+                    LspEvent::Diagnostics { uri, diagnostics } => {
+                        self.diagnostics.insert(uri, diagnostics);
+                        self.diagnostics_changed = true;
+                    }
+                    LspEvent::Initialized(capabilities) => server.capabilities = capabilities,
+                    LspEvent::Response { id, result } => {
+                        let request = LspRequest { server: index, id };
+                        if self.pending.contains(&request) {
+                            self.responses.insert(request, result);
+                        }
+                    }
+                    LspEvent::Exited => exited.push(index),
+                }
+            }
+        }
+        let mut names = Vec::new();
+        for index in exited {
+            let Some(server) = self.servers[index].take() else {
+                continue;
+            };
+            self.failed.insert(server.name.clone());
+            // 退出的服务器的请求不会再有回复
+            for request in self.pending.iter().filter(|request| request.server == index) {
+                self.responses
+                    .entry(*request)
+                    .or_insert_with(|| Err(format!("{} exited", server.name)));
+            }
+            let uris: Vec<String> = self
+                .documents
+                .values()
+                .filter(|document| document.server == index)
+                .map(|document| document.uri.clone())
+                .collect();
+            self.documents.retain(|_, document| document.server != index);
+            for uri in uris {
+                self.diagnostics.remove(&uri);
+            }
+            self.diagnostics_changed = true;
+            names.push(server.name);
+        }
+        names
+    }
+}
+
+/// 行中诊断范围对应的字符范围 (起始, 结束)，`line` 不在诊断范围内时返回 None
+///
+/// 诊断跨行时中间的行整行都在范围内；空范围至少覆盖一个字符
+pub fn diagnostic_columns(diagnostic: &Diagnostic, line: usize, text: &str) -> Option<(usize, usize)> {
+    if line < diagnostic.start.line || line > diagnostic.end.line {
+        return None;
+    }
+    let len = text.chars().count();
+    let start = if line == diagnostic.start.line {
+        aivim_lsp::diagnostic::utf16_to_char(text, diagnostic.start.character)
+    } else {
+        0
+    };
+    let end = if line == diagnostic.end.line {
+        aivim_lsp::diagnostic::utf16_to_char(text, diagnostic.end.character)
+    } else {
+        len
+    };
+    Some((start, end.max(start + 1)))
+}
+
+/// 一行最严重的诊断
+pub fn line_severity(diagnostics: &[Diagnostic], line: usize) -> Option<Severity> {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.start.line == line)
+        .map(|diagnostic| diagnostic.severity)
+        .min()
+}
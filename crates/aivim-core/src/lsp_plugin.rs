@@ -0,0 +1,138 @@
+//! 语言服务器插件（内置插件，见 plugin 模块）
+//!
+//! 开启 lsp 选项时把缓冲区的变化发送给语言服务器（见 lsp 模块），诊断放入窗口的位置列表；
+//! 读入文件之后立即打开文档，不等下一次定时检查。Insert 模式的 Ctrl+Space 和服务器的触发字符
+//! （如 `.`、`::` 的 `:`）向服务器请求补全，候选项交给编辑器的补全菜单。
+
+use std::time::Instant;
+
+use crate::autocmd::Event;
+use crate::buffer::BufferId;
+use crate::editor::Editor;
+use crate::lsp::{LspManager, LspRequest, DIAGNOSTICS_TITLE};
+use crate::plugin::Plugin;
+use crate::tr;
+
+/// 语言服务器和诊断、补全
+#[derive(Default)]
+pub struct LspPlugin {
+    lsp: LspManager,
+    /// 开启了 lsp 选项，关闭时停止服务器
+    enabled: bool,
+    /// 等待回复的补全请求
+    completion: Option<LspRequest>,
+}
+
+impl LspPlugin {
+    /// 语言服务器和它们发布的诊断
+    pub fn manager(&self) -> &LspManager {
+        &self.lsp
+    }
+
+    /// 开启 lsp 时把缓冲区的变化发送给语言服务器，并取出服务器发布的诊断
+    ///
+    /// config.toml 的服务器设置变化时先关闭正在运行的服务器，之后按新的设置启动
+    fn update(&mut self, editor: &mut Editor) {
+        if self.lsp.settings() != editor.lsp_settings() {
+            self.lsp.set_settings(editor.lsp_settings().clone());
+        }
+        if !editor.options().lsp {
+            return;
+        }
+        self.enabled = true;
+        let errors = self.lsp.sync(editor.buffers(), editor.waker());
+        if let Some(error) = errors.first() {
+            editor.set_message(tr!(editor.locale(), "Failed to start language server {}", error));
+        }
+        if let Some(name) = self.lsp.poll().first() {
+            editor.set_message(tr!(editor.locale(), "Language server {} exited", name));
+        }
+        if self.lsp.take_diagnostics_changed() {
+            self.update_diagnostic_lists(editor);
+        }
+        self.poll_completion(editor);
+    }
+
+    /// 把诊断放入当前标签页中各窗口的位置列表，窗口已有其他来源的位置列表时不覆盖
+    fn update_diagnostic_lists(&self, editor: &mut Editor) {
+        editor.update_location_lists(DIAGNOSTICS_TITLE, |buffer| self.lsp.location_list(buffer));
+    }
+
+    /// 处理补全请求的回复
+    fn poll_completion(&mut self, editor: &mut Editor) {
+        let Some(request) = self.completion else {
+            return;
+        };
+        let Some(result) = self.lsp.take_response(request) else {
+            return;
+        };
+        self.completion = None;
+        match result {
+            Ok(result) => editor.provide_completion(aivim_lsp::completion::parse_response(&result)),
+            Err(error) => {
+                editor.cancel_completion();
+                editor.set_message(tr!(editor.locale(), "Completion failed: {}", error));
+            }
+        }
+    }
+}
+
+impl Plugin for LspPlugin {
+    fn name(&self) -> &'static str {
+        "lsp"
+    }
+
+    fn on_event(&mut self, editor: &mut Editor, event: Event, _buffer: BufferId) {
+        if matches!(event, Event::BufReadPost | Event::BufNewFile) {
+            self.update(editor);
+        }
+    }
+
+    /// 连续输入时只在停顿之后发送（见 `LspManager::sync`）；诊断到达时服务器线程会唤醒事件循环
+    fn poll(&mut self, editor: &mut Editor, _now: Instant) -> bool {
+        self.update(editor);
+        false
+    }
+
+    fn on_option_set(&mut self, editor: &mut Editor) {
+        if editor.options().lsp {
+            // 无法启动服务器时显示错误
+            self.update(editor);
+        } else if std::mem::take(&mut self.enabled) {
+            self.lsp.stop();
+            self.update_diagnostic_lists(editor);
+        }
+    }
+
+    /// 手动请求时总是发送，否则只在 `trigger` 是当前缓冲区的服务器的触发字符时发送
+    fn complete(&mut self, editor: &mut Editor, trigger: Option<char>) -> bool {
+        if !editor.options().lsp {
+            return false;
+        }
+        let buffer = editor.current_buffer_id();
+        if trigger.is_some_and(|c| !self.lsp.trigger_characters(buffer).contains(&c)) {
+            return false;
+        }
+        self.cancel_completion(editor);
+        // 先发送最新的文本，服务器按它计算候选项
+        self.lsp.sync(editor.buffers(), editor.waker());
+        let Some(uri) = self.lsp.document_uri(buffer) else {
+            return false;
+        };
+        let cursor = *editor.cursor();
+        let line_text = editor.get_line_text(cursor.line).unwrap_or_default();
+        let position = aivim_lsp::Position::new(
+            cursor.line,
+            aivim_lsp::diagnostic::char_to_utf16(&line_text, cursor.column),
+        );
+        let params = aivim_lsp::completion::request_params(uri, position, trigger);
+        self.completion = self.lsp.request(buffer, "textDocument/completion", params);
+        self.completion.is_some()
+    }
+
+    fn cancel_completion(&mut self, _editor: &mut Editor) {
+        if let Some(request) = self.completion.take() {
+            self.lsp.cancel(request);
+        }
+    }
+}
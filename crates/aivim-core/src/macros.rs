@@ -4,7 +4,7 @@
 /// 这是防止 "No file path set" 错误的简单解决方案。
 ///
 /// 使用方式：
-/// ```
+/// ```ignore
 /// // 在 Editor 方法中使用
 /// pub fn my_operation(&mut self) {
 ///     with_save_state!(self, {
@@ -64,6 +64,25 @@ macro_rules! with_save_state_if {
     }};
 }
 
+/// 翻译并格式化用户可见的消息
+///
+/// 英文原文作为消息键，`{}` 依次替换为参数：
+/// ```ignore
+/// self.set_message(tr!(self.locale, "Switched to buffer {}", id));
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($locale:expr, $msg:literal) => {
+        $crate::i18n::translate($locale, $msg).to_string()
+    };
+    ($locale:expr, $msg:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format_message(
+            $crate::i18n::translate($locale, $msg),
+            &[$(&$arg as &dyn std::fmt::Display),+],
+        )
+    };
+}
+
 /// 文档和示例
 /// 
 /// # 为什么需要这个宏？
@@ -77,7 +96,7 @@ macro_rules! with_save_state_if {
 /// 
 /// # 使用示例
 /// 
-/// ```rust
+/// ```ignore
 /// impl Editor {
 ///     // 好的例子：使用宏包装修改操作
 ///     pub fn delete_line_fixed(&mut self) {
@@ -0,0 +1,342 @@
+//! 用户按键映射模块
+//!
+//! 维护 :map、:nmap、:inoremap 等命令定义的映射表：
+//! - 每个映射只在指定的模式下生效（Normal / Visual / Insert / Command）
+//! - 带 `<buffer>` 的映射只在定义它的缓冲区生效，供文件类型插件使用，
+//!   避免污染全局映射；缓冲区映射优先于全局映射
+//! - 映射中的 `<Leader>` 在定义时替换为当前的 leader 键（`:let mapleader`，默认为 `\`）
+//!
+//! 映射的展开（按键等待、递归展开）见 input 模块，这里只提供查询。
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::buffer::BufferId;
+use crate::mode::Mode;
+
+/// 递归映射的最大展开深度
+pub const MAX_MAP_DEPTH: usize = 100;
+
+/// 映射中的一个按键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MapKey {
+    Char(char),
+    /// Ctrl 组合键，字母统一为小写
+    Ctrl(char),
+    Enter,
+    Esc,
+    Tab,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    /// Shift+Tab
+    BackTab,
+}
+
+impl MapKey {
+    /// 按键的 `<...>` 表示法
+    pub fn notation(&self) -> String {
+        match self {
+            MapKey::Char(' ') => "<Space>".to_string(),
+            MapKey::Char('<') => "<lt>".to_string(),
+            MapKey::Char(c) => c.to_string(),
+            MapKey::Ctrl(' ') => "<C-Space>".to_string(),
+            MapKey::Ctrl(c) => format!("<C-{}>", c),
+            MapKey::Enter => "<CR>".to_string(),
+            MapKey::Esc => "<Esc>".to_string(),
+            MapKey::Tab => "<Tab>".to_string(),
+            MapKey::Backspace => "<BS>".to_string(),
+            MapKey::Up => "<Up>".to_string(),
+            MapKey::Down => "<Down>".to_string(),
+            MapKey::Left => "<Left>".to_string(),
+            MapKey::Right => "<Right>".to_string(),
+            MapKey::PageUp => "<PageUp>".to_string(),
+            MapKey::PageDown => "<PageDown>".to_string(),
+            MapKey::Home => "<Home>".to_string(),
+            MapKey::End => "<End>".to_string(),
+            MapKey::BackTab => "<S-Tab>".to_string(),
+        }
+    }
+}
+
+/// 默认的 leader 键
+pub const DEFAULT_LEADER: MapKey = MapKey::Char('\\');
+
+/// 解析按键表示法，如 `<C-w>j`、`:w<CR>`，`<Leader>` 为默认的 leader 键
+///
+/// 无法识别的 `<...>` 按字面字符处理（与 Vim 一致）
+pub fn parse_keys(notation: &str) -> Vec<MapKey> {
+    parse_keys_with_leader(notation, &[DEFAULT_LEADER])
+}
+
+/// 解析按键表示法，`<Leader>` 替换为 `leader`
+pub fn parse_keys_with_leader(notation: &str, leader: &[MapKey]) -> Vec<MapKey> {
+    let mut keys = Vec::new();
+    let mut rest = notation;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                if rest[1..end].eq_ignore_ascii_case("leader") {
+                    keys.extend_from_slice(leader);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+                if let Some(key) = parse_special_key(&rest[1..end]) {
+                    keys.push(key);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        keys.push(MapKey::Char(c));
+        rest = &rest[c.len_utf8()..];
+    }
+    keys
+}
+
+fn parse_special_key(name: &str) -> Option<MapKey> {
+    let lower = name.to_ascii_lowercase();
+    let key = match lower.as_str() {
+        "cr" | "enter" | "return" => MapKey::Enter,
+        "esc" => MapKey::Esc,
+        "tab" => MapKey::Tab,
+        "bs" | "backspace" => MapKey::Backspace,
+        "space" => MapKey::Char(' '),
+        "lt" => MapKey::Char('<'),
+        "bar" => MapKey::Char('|'),
+        "bslash" => MapKey::Char('\\'),
+        "up" => MapKey::Up,
+        "down" => MapKey::Down,
+        "left" => MapKey::Left,
+        "right" => MapKey::Right,
+        "pageup" => MapKey::PageUp,
+        "pagedown" => MapKey::PageDown,
+        "home" => MapKey::Home,
+        "end" => MapKey::End,
+        "s-tab" => MapKey::BackTab,
+        _ => {
+            let ctrl = lower.strip_prefix("c-")?;
+            if ctrl == "space" {
+                return Some(MapKey::Ctrl(' '));
+            }
+            let mut chars = ctrl.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => MapKey::Ctrl(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+/// 按键序列的表示法
+pub fn format_keys(keys: &[MapKey]) -> String {
+    keys.iter().map(MapKey::notation).collect()
+}
+
+/// 映射生效的模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapMode {
+    Normal,
+    Visual,
+    Insert,
+    Command,
+}
+
+impl MapMode {
+    /// 编辑器模式对应的映射模式，搜索等模式不使用映射
+    pub fn for_mode(mode: Mode) -> Option<Self> {
+        match mode {
+            Mode::Normal => Some(MapMode::Normal),
+            Mode::Visual => Some(MapMode::Visual),
+            Mode::Insert | Mode::Replace => Some(MapMode::Insert),
+            Mode::Command => Some(MapMode::Command),
+            Mode::SearchForward | Mode::SearchBackward | Mode::Terminal => None,
+        }
+    }
+
+    /// :map 列表中显示的模式字母
+    pub fn letter(&self) -> char {
+        match self {
+            MapMode::Normal => 'n',
+            MapMode::Visual => 'v',
+            MapMode::Insert => 'i',
+            MapMode::Command => 'c',
+        }
+    }
+}
+
+/// 映射的作用范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapScope {
+    Global,
+    Buffer(BufferId),
+}
+
+/// 映射命令的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapCommandKind {
+    /// :map / :nmap 等，递归展开右侧
+    Map,
+    /// :noremap / :nnoremap 等，右侧不再展开
+    Noremap,
+    /// :unmap / :nunmap 等
+    Unmap,
+    /// :mapclear / :nmapclear 等
+    Clear,
+}
+
+/// 解析映射命令名，返回命令类型和生效的模式
+///
+/// 前缀 n/v/x/i/c 限定模式；不带前缀时为 Normal + Visual，`!` 结尾时为 Insert + Command
+pub fn parse_map_command(name: &str) -> Option<(MapCommandKind, Vec<MapMode>)> {
+    let (name, bang) = match name.strip_suffix('!') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let (modes, base) = match name.chars().next()? {
+        'n' if name != "noremap" => (vec![MapMode::Normal], &name[1..]),
+        'v' | 'x' => (vec![MapMode::Visual], &name[1..]),
+        'i' => (vec![MapMode::Insert], &name[1..]),
+        'c' => (vec![MapMode::Command], &name[1..]),
+        _ if bang => (vec![MapMode::Insert, MapMode::Command], name),
+        _ => (vec![MapMode::Normal, MapMode::Visual], name),
+    };
+    // 只有不带模式前缀的命令可以用 ! 结尾
+    if bang && modes.len() == 1 {
+        return None;
+    }
+    let kind = match base {
+        "map" => MapCommandKind::Map,
+        "noremap" => MapCommandKind::Noremap,
+        "unmap" => MapCommandKind::Unmap,
+        "mapclear" => MapCommandKind::Clear,
+        _ => return None,
+    };
+    Some((kind, modes))
+}
+
+/// 一条映射
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapping {
+    pub lhs: Vec<MapKey>,
+    pub rhs: Vec<MapKey>,
+    /// 右侧不再展开映射
+    pub noremap: bool,
+}
+
+/// 查询按键序列的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MapLookup<'a> {
+    /// 没有以此序列开头的映射
+    None,
+    /// 是更长映射的前缀，需要等待更多按键；`exact` 为完全匹配的映射（超时后执行）
+    Prefix { exact: Option<&'a Mapping> },
+    /// 完全匹配且没有更长的映射
+    Match(&'a Mapping),
+}
+
+type MapTable = BTreeMap<Vec<MapKey>, Mapping>;
+
+/// 映射表
+#[derive(Debug, Clone, Default)]
+pub struct KeyMappings {
+    global: HashMap<MapMode, MapTable>,
+    buffer_local: HashMap<(BufferId, MapMode), MapTable>,
+}
+
+impl KeyMappings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn table(&self, scope: MapScope, mode: MapMode) -> Option<&MapTable> {
+        match scope {
+            MapScope::Global => self.global.get(&mode),
+            MapScope::Buffer(id) => self.buffer_local.get(&(id, mode)),
+        }
+    }
+
+    fn table_mut(&mut self, scope: MapScope, mode: MapMode) -> &mut MapTable {
+        match scope {
+            MapScope::Global => self.global.entry(mode).or_default(),
+            MapScope::Buffer(id) => self.buffer_local.entry((id, mode)).or_default(),
+        }
+    }
+
+    /// 添加映射，已存在的同名映射被替换
+    pub fn map(&mut self, scope: MapScope, mode: MapMode, lhs: Vec<MapKey>, rhs: Vec<MapKey>, noremap: bool) {
+        let mapping = Mapping {
+            lhs: lhs.clone(),
+            rhs,
+            noremap,
+        };
+        self.table_mut(scope, mode).insert(lhs, mapping);
+    }
+
+    /// 删除映射，返回是否存在
+    pub fn unmap(&mut self, scope: MapScope, mode: MapMode, lhs: &[MapKey]) -> bool {
+        self.table_mut(scope, mode).remove(lhs).is_some()
+    }
+
+    /// 清除某个范围内指定模式的所有映射
+    pub fn clear(&mut self, scope: MapScope, mode: MapMode) {
+        self.table_mut(scope, mode).clear();
+    }
+
+    /// 删除缓冲区时清除它的所有局部映射
+    pub fn clear_buffer(&mut self, buffer: BufferId) {
+        self.buffer_local.retain(|(id, _), _| *id != buffer);
+    }
+
+    pub fn get(&self, scope: MapScope, mode: MapMode, lhs: &[MapKey]) -> Option<&Mapping> {
+        self.table(scope, mode)?.get(lhs)
+    }
+
+    /// 在当前缓冲区的可见映射中查询按键序列
+    pub fn lookup(&self, mode: MapMode, buffer: BufferId, keys: &[MapKey]) -> MapLookup<'_> {
+        let mut exact = None;
+        let mut longer = false;
+        for scope in [MapScope::Buffer(buffer), MapScope::Global] {
+            let Some(table) = self.table(scope, mode) else {
+                continue;
+            };
+            if exact.is_none() {
+                exact = table.get(keys);
+            }
+            longer |= table
+                .range(keys.to_vec()..)
+                .take_while(|(lhs, _)| lhs.starts_with(keys))
+                .any(|(lhs, _)| lhs.len() > keys.len());
+        }
+        match (exact, longer) {
+            (_, true) => MapLookup::Prefix { exact },
+            (Some(mapping), false) => MapLookup::Match(mapping),
+            (None, false) => MapLookup::None,
+        }
+    }
+
+    /// 左侧是 `keys` 前缀的映射中最长的一个（用于展开映射右侧）
+    pub fn longest_match(&self, mode: MapMode, buffer: BufferId, keys: &[MapKey]) -> Option<&Mapping> {
+        (1..=keys.len()).rev().find_map(|len| {
+            self.get(MapScope::Buffer(buffer), mode, &keys[..len])
+                .or_else(|| self.get(MapScope::Global, mode, &keys[..len]))
+        })
+    }
+
+    /// 当前缓冲区可见的映射，缓冲区映射在前
+    pub fn list(&self, mode: MapMode, buffer: BufferId) -> Vec<(MapScope, &Mapping)> {
+        let mut result = Vec::new();
+        for scope in [MapScope::Buffer(buffer), MapScope::Global] {
+            if let Some(table) = self.table(scope, mode) {
+                result.extend(table.values().map(|mapping| (scope, mapping)));
+            }
+        }
+        result
+    }
+}
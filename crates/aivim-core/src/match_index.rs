@@ -0,0 +1,238 @@
+//! 搜索匹配的位置索引
+//!
+//! 匹配按位置保存在平衡树（treap）中，每个节点只记录相对父节点的位置：
+//! - 修改之后的匹配整体移动时只改动子树根节点的相对位置，不逐个移动
+//! - 按位置拆分、合并子树，替换修改区域内的匹配只涉及该区域，期望 O(log n + 新匹配数)
+//! - 按序号取位置、统计某个位置之前的匹配数都沿树向下查找，期望 O(log n)
+
+use std::fmt;
+use std::ops::Range;
+
+type Link = Option<Box<Node>>;
+
+#[derive(Clone)]
+struct Node {
+    /// 相对父节点的位置；子树的根节点为绝对位置
+    offset: isize,
+    /// 随机优先级，父节点的优先级不低于子节点，使树的期望深度为 O(log n)
+    priority: u64,
+    /// 子树中的匹配数
+    size: usize,
+    left: Link,
+    right: Link,
+}
+
+impl Node {
+    fn new(pos: usize, priority: u64) -> Box<Self> {
+        Box::new(Self { offset: pos as isize, priority, size: 1, left: None, right: None })
+    }
+
+    fn update_size(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn size(link: &Link) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// 把子树整体移动 `delta`：只修改根节点的位置
+fn shift(link: Link, delta: isize) -> Link {
+    link.map(|mut node| {
+        node.offset += delta;
+        node
+    })
+}
+
+/// 按绝对位置拆分：位置小于 `key` 的在左边，返回的两棵树的根节点为绝对位置
+fn split(link: Link, base: isize, key: usize) -> (Link, Link) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    let pos = base + node.offset;
+    node.offset = pos;
+    if pos < key as isize {
+        let (left, right) = split(node.right.take(), pos, key);
+        node.right = shift(left, -pos);
+        node.update_size();
+        (Some(node), right)
+    } else {
+        let (left, right) = split(node.left.take(), pos, key);
+        node.left = shift(right, -pos);
+        node.update_size();
+        (left, Some(node))
+    }
+}
+
+/// 合并两棵根节点为绝对位置的树，`left` 的位置都在 `right` 之前
+fn merge(left: Link, right: Link) -> Link {
+    match (left, right) {
+        (None, link) | (link, None) => link,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority >= right.priority {
+                let child = shift(left.right.take(), left.offset);
+                left.right = shift(merge(child, Some(right)), -left.offset);
+                left.update_size();
+                Some(left)
+            } else {
+                let child = shift(right.left.take(), right.offset);
+                right.left = shift(merge(Some(left), child), -right.offset);
+                right.update_size();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// 按位置排列的匹配
+#[derive(Clone, Default)]
+pub struct MatchIndex {
+    root: Link,
+    /// 生成优先级的 xorshift 状态
+    seed: u64,
+}
+
+impl MatchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    /// 第 `idx` 个匹配的位置
+    pub fn get(&self, idx: usize) -> Option<usize> {
+        let mut idx = idx;
+        let mut base = 0;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let pos = base + node.offset;
+            let left = size(&node.left);
+            if idx < left {
+                link = &node.left;
+            } else if idx == left {
+                return Some(pos as usize);
+            } else {
+                idx -= left + 1;
+                link = &node.right;
+            }
+            base = pos;
+        }
+        None
+    }
+
+    /// 位置小于 `pos` 的匹配数，也就是第一个不在 `pos` 之前的匹配的序号
+    pub fn count_before(&self, pos: usize) -> usize {
+        let mut count = 0;
+        let mut base = 0;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let node_pos = base + node.offset;
+            if node_pos < pos as isize {
+                count += size(&node.left) + 1;
+                link = &node.right;
+            } else {
+                link = &node.left;
+            }
+            base = node_pos;
+        }
+        count
+    }
+
+    /// 按位置顺序取出所有匹配
+    pub fn to_vec(&self) -> Vec<usize> {
+        let mut positions = Vec::with_capacity(self.len());
+        let mut stack = Vec::new();
+        let mut link = &self.root;
+        let mut base = 0;
+        loop {
+            while let Some(node) = link {
+                let pos = base + node.offset;
+                stack.push((node, pos));
+                link = &node.left;
+                base = pos;
+            }
+            let Some((node, pos)) = stack.pop() else {
+                return positions;
+            };
+            positions.push(pos as usize);
+            link = &node.right;
+            base = pos;
+        }
+    }
+
+    /// 按一处修改更新：去掉位于 `removed` 中的匹配，之后的匹配移动 `delta`，再加入修改后找到的匹配
+    ///
+    /// `found` 按位置排列，都位于修改后的区域中（在移动之前的匹配之后、移动之后的匹配之前）
+    pub fn replace(&mut self, removed: Range<usize>, delta: isize, found: Vec<usize>) {
+        let (before, rest) = split(self.root.take(), 0, removed.start);
+        let (_, after) = split(rest, 0, removed.end);
+        let middle = self.build(found);
+        self.root = merge(merge(before, middle), shift(after, delta));
+    }
+
+    /// 由按位置排列的匹配建树（笛卡尔树），O(n)
+    fn build(&mut self, positions: Vec<usize>) -> Link {
+        // 栈中每个节点的右子树是栈中它上面的节点，出栈时连接
+        let mut stack: Vec<Box<Node>> = Vec::new();
+        let link = |mut parent: Box<Node>, right: Link| {
+            parent.right = shift(right, -parent.offset);
+            parent.update_size();
+            Some(parent)
+        };
+        for pos in positions {
+            let mut node = Node::new(pos, self.next_priority());
+            let mut last = None;
+            while let Some(top) = stack.pop_if(|top| top.priority < node.priority) {
+                last = link(top, last);
+            }
+            node.left = shift(last, -node.offset);
+            stack.push(node);
+        }
+        let mut root = None;
+        while let Some(top) = stack.pop() {
+            root = link(top, root);
+        }
+        root
+    }
+
+    fn next_priority(&mut self) -> u64 {
+        // xorshift64，种子不能为 0
+        let mut x = if self.seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { self.seed };
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed = x;
+        x
+    }
+}
+
+impl FromIterator<usize> for MatchIndex {
+    /// `iter` 按位置排列
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut index = Self::new();
+        index.root = index.build(iter.into_iter().collect());
+        index
+    }
+}
+
+impl PartialEq for MatchIndex {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.to_vec() == other.to_vec()
+    }
+}
+
+impl fmt::Debug for MatchIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.to_vec()).finish()
+    }
+}
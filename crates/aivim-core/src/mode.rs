@@ -1,5 +1,7 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default)]
 pub enum Mode {
+    #[default]
     Normal,
     Insert,
     Visual,
@@ -7,6 +9,8 @@ pub enum Mode {
     Replace,
     SearchForward,
     SearchBackward,
+    /// 终端缓冲区中按键发送给程序 (:terminal)
+    Terminal,
 }
 
 impl Mode {
@@ -19,6 +23,7 @@ impl Mode {
             Mode::Replace => "REPLACE",
             Mode::SearchForward => "SEARCH",
             Mode::SearchBackward => "SEARCH",
+            Mode::Terminal => "TERMINAL",
         }
     }
 
@@ -39,8 +44,3 @@ impl Mode {
     }
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Mode::Normal
-    }
-}
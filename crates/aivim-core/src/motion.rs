@@ -1,9 +1,15 @@
-/// 光标移动（Motion）模块
-///
-/// 实现 Vim 风格的光标移动命令，如 w, b, e, $, 0 等
+//! 光标移动（Motion）模块
+//!
+//! 实现 Vim 风格的光标移动命令，如 w, b, e, $, 0 等
+//!
+//! 列是字符下标，光标按字形簇移动，不会停在 emoji 序列或组合符号的中间；
+//! 上下移动时保持光标在屏幕上的列（显示宽度），见 `Cursor::preferred_column`
+//!
+//! 翻页、H / M / L 和 g0 / g$ 按当前窗口显示的范围（`Viewport`）移动，由编辑器根据窗口的大小、滚动位置和选项提供
 
 use crate::buffer::Buffer;
 use crate::cursor::Cursor;
+use crate::display::{self, DEFAULT_TABSTOP};
 
 /// 光标移动命令
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -22,31 +28,104 @@ pub enum Motion {
     LineEnd,
     /// 移到第一个非空字符 (^)
     FirstNonBlank,
+    /// 移到最后一个非空字符 (g_)
+    LastNonBlank,
+    /// 移到第 n 个屏幕列，从 1 开始 (n|)
+    ToColumn(usize),
+    /// 移到光标所在屏幕行（折行后）的第一个字符 (g0)
+    ScreenLineStart,
+    /// 移到光标所在屏幕行的最后一个字符 (g$)
+    ScreenLineEnd,
     /// 向前移动一个单词 (w)
     WordForward,
     /// 向后移动一个单词 (b)
     WordBackward,
     /// 向前移动到单词结尾 (e)
     WordEnd,
-    /// 向前移动一个 WORD（大写，以空白分隔）(W)
+    /// 向后移动到上一个单词结尾 (ge)
+    WordEndBackward,
+    /// 向前移动一个 WORD（连续的非空白字符）(W)
     WORDForward,
-    /// 向后移动一个 WORD（大写）(B)
+    /// 向后移动一个 WORD (B)
     WORDBackward,
     /// 向前移动到 WORD 结尾 (E)
     WORDEnd,
+    /// 向后移动到上一个 WORD 结尾 (gE)
+    WORDEndBackward,
     /// 移到文件开头 (gg)
     DocumentStart,
     /// 移到文件结尾 (G)
     DocumentEnd,
-    /// 向下移动半页 (Ctrl+D)
+    /// 移到第 n 行的第一个非空字符，从 1 开始 ({count}G、{count}gg)
+    ToLine(usize),
+    /// 向下移动半页 (Ctrl+D)，窗口同时向下滚动相同的行数
     PageDown,
-    /// 向上移动半页 (Ctrl+U)
+    /// 向上移动半页 (Ctrl+U)，窗口同时向上滚动相同的行数
     PageUp,
+    /// 向下翻一页 (Ctrl+F)：移到窗口倒数第二行，编辑器把这一行滚动到窗口顶部
+    PageForward,
+    /// 向上翻一页 (Ctrl+B)：移到窗口第二行，编辑器把这一行滚动到窗口底部
+    PageBackward,
+    /// 移到窗口顶部的行 (H)
+    WindowTop,
+    /// 移到窗口中间的行 (M)
+    WindowMiddle,
+    /// 移到窗口底部的行 (L)
+    WindowBottom,
+    /// 沿搜索方向的下一个匹配，作为操作符的目标（`d/foo`、`dn`）；终点由编辑器按当前的搜索模式计算，这里不移动
+    SearchNext,
+    /// 与搜索方向相反的下一个匹配 (`dN`)
+    SearchPrev,
+}
+
+/// 没有窗口信息时按这个大小的窗口计算
+const DEFAULT_VIEWPORT_HEIGHT: usize = 20;
+const DEFAULT_VIEWPORT_WIDTH: usize = 80;
+
+/// 窗口显示的范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// 窗口顶部显示的缓冲区行
+    pub top: usize,
+    /// 窗口底部最后一个完整显示的缓冲区行
+    pub bottom: usize,
+    /// 窗口文本区域的高度（屏幕行）
+    pub height: usize,
+    /// 窗口文本区域的宽度（列）
+    pub width: usize,
+    /// 窗口中生效的 wrap 和 tabstop 选项
+    pub wrap: bool,
+    pub tabstop: usize,
+}
+
+impl Viewport {
+    /// Ctrl+D / Ctrl+U 移动的行数：窗口高度的一半
+    pub fn half_page(&self) -> usize {
+        (self.height / 2).max(1)
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            top: 0,
+            bottom: DEFAULT_VIEWPORT_HEIGHT - 1,
+            height: DEFAULT_VIEWPORT_HEIGHT,
+            width: DEFAULT_VIEWPORT_WIDTH,
+            wrap: true,
+            tabstop: DEFAULT_TABSTOP,
+        }
+    }
 }
 
 impl Motion {
-    /// 执行光标移动
+    /// 执行光标移动，翻页和 H / M / L 按默认大小的窗口计算
     pub fn execute(&self, cursor: &mut Cursor, buffer: &Buffer) {
+        self.execute_in(cursor, buffer, &Viewport::default());
+    }
+
+    /// 在显示范围为 `viewport` 的窗口中执行光标移动
+    pub fn execute_in(&self, cursor: &mut Cursor, buffer: &Buffer, viewport: &Viewport) {
         match self {
             Motion::Left => move_left(cursor, buffer),
             Motion::Right => move_right(cursor, buffer),
@@ -55,82 +134,334 @@ impl Motion {
             Motion::LineStart => move_line_start(cursor),
             Motion::LineEnd => move_line_end(cursor, buffer),
             Motion::FirstNonBlank => move_first_non_blank(cursor, buffer),
+            Motion::LastNonBlank => move_last_non_blank(cursor, buffer),
+            Motion::ToColumn(column) => move_to_column(cursor, buffer, *column, viewport.tabstop),
+            Motion::ScreenLineStart => move_screen_line_edge(cursor, buffer, viewport, false),
+            Motion::ScreenLineEnd => move_screen_line_edge(cursor, buffer, viewport, true),
             Motion::WordForward => move_word_forward(cursor, buffer),
             Motion::WordBackward => move_word_backward(cursor, buffer),
             Motion::WordEnd => move_word_end(cursor, buffer),
-            Motion::WORDForward => move_word_forward(cursor, buffer), // 简化实现
-            Motion::WORDBackward => move_word_backward(cursor, buffer), // 简化实现
-            Motion::WORDEnd => move_word_end(cursor, buffer), // 简化实现
+            Motion::WordEndBackward => move_word_end_backward(cursor, buffer, false),
+            Motion::WORDForward => move_big_word_forward(cursor, buffer),
+            Motion::WORDBackward => move_big_word_backward(cursor, buffer),
+            Motion::WORDEnd => move_big_word_end(cursor, buffer),
+            Motion::WORDEndBackward => move_word_end_backward(cursor, buffer, true),
             Motion::DocumentStart => move_document_start(cursor),
             Motion::DocumentEnd => move_document_end(cursor, buffer),
-            Motion::PageDown => page_down(cursor, buffer),
-            Motion::PageUp => page_up(cursor),
+            Motion::ToLine(line) => move_to_line(cursor, buffer, line.saturating_sub(1)),
+            Motion::PageDown => page_down(cursor, buffer, viewport.half_page()),
+            Motion::PageUp => page_up(cursor, buffer, viewport.half_page()),
+            Motion::PageForward => {
+                // 窗口已经显示到最后一行时移到最后一行
+                let line = if viewport.bottom >= buffer.last_content_line() {
+                    viewport.bottom
+                } else {
+                    viewport.bottom.saturating_sub(1).max(viewport.top + 1)
+                };
+                move_to_line(cursor, buffer, line);
+            }
+            Motion::PageBackward => {
+                // 已经在文件开头时不动
+                if viewport.top > 0 {
+                    move_to_line(cursor, buffer, (viewport.top + 1).min(viewport.bottom));
+                }
+            }
+            Motion::WindowTop => move_to_line(cursor, buffer, viewport.top),
+            Motion::WindowMiddle => move_to_line(cursor, buffer, (viewport.top + viewport.bottom) / 2),
+            Motion::WindowBottom => move_to_line(cursor, buffer, viewport.bottom),
+            Motion::SearchNext | Motion::SearchPrev => {}
         }
     }
+
+    /// 按窗口显示的范围移动，执行后编辑器需要检查是否落在关闭的折叠中
+    pub fn uses_viewport(&self) -> bool {
+        matches!(
+            self,
+            Motion::PageForward | Motion::PageBackward | Motion::WindowTop | Motion::WindowMiddle | Motion::WindowBottom
+        )
+    }
+
+    /// 与操作符组合时是否按整行处理（如 dj、dG）
+    pub fn is_linewise(&self) -> bool {
+        matches!(
+            self,
+            Motion::Up
+                | Motion::Down
+                | Motion::DocumentStart
+                | Motion::DocumentEnd
+                | Motion::ToLine(_)
+                | Motion::PageDown
+                | Motion::PageUp
+                | Motion::PageForward
+                | Motion::PageBackward
+                | Motion::WindowTop
+                | Motion::WindowMiddle
+                | Motion::WindowBottom
+        )
+    }
+
+    /// 与操作符组合时是否包含终点字符（如 de、d$）
+    pub fn is_inclusive(&self) -> bool {
+        matches!(
+            self,
+            Motion::WordEnd
+                | Motion::WORDEnd
+                | Motion::WordEndBackward
+                | Motion::WORDEndBackward
+                | Motion::LineEnd
+                | Motion::LastNonBlank
+                | Motion::ScreenLineEnd
+        )
+    }
 }
 
-fn move_left(cursor: &mut Cursor, _buffer: &Buffer) {
+/// 一行的文本，不包括换行符
+fn line_text(buffer: &Buffer, line: usize) -> String {
+    buffer.line_text(line).unwrap_or_default()
+}
+
+/// 一行中的字形簇，光标只停在字形簇的起始列（字符下标）
+struct LineGraphemes {
+    /// 每个字形簇的起始列和第一个字符，单词的分类按第一个字符判断
+    graphemes: Vec<(usize, char)>,
+    /// 行的字符数
+    len: usize,
+}
+
+impl LineGraphemes {
+    fn new(text: &str) -> Self {
+        Self {
+            graphemes: display::graphemes(text)
+                .map(|(start, grapheme)| (start, grapheme.chars().next().unwrap_or(' ')))
+                .collect(),
+            len: text.chars().count(),
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.graphemes.len()
+    }
+
+    /// 第 `column` 列所在字形簇的序号，超过行尾时返回字形簇的个数
+    fn index(&self, column: usize) -> usize {
+        if column >= self.len {
+            return self.count();
+        }
+        self.graphemes.partition_point(|&(start, _)| start <= column).saturating_sub(1)
+    }
+
+    /// 第 `index` 个字形簇的起始列，超过行尾时返回行的字符数
+    fn column(&self, index: usize) -> usize {
+        self.graphemes.get(index).map_or(self.len, |&(start, _)| start)
+    }
+
+    fn char(&self, index: usize) -> Option<char> {
+        self.graphemes.get(index).map(|&(_, ch)| ch)
+    }
+
+    /// 从第 `index` 个字形簇开始跳过满足 `pred` 的字形簇，返回第一个不满足的序号
+    fn skip_forward(&self, mut index: usize, pred: impl Fn(char) -> bool) -> usize {
+        while self.char(index).is_some_and(&pred) {
+            index += 1;
+        }
+        index
+    }
+
+    /// 从第 `index` 个字形簇之前向前跳过满足 `pred` 的字形簇，返回跳过的第一个的序号
+    fn skip_backward(&self, mut index: usize, pred: impl Fn(char) -> bool) -> usize {
+        while index > 0 && self.char(index - 1).is_some_and(&pred) {
+            index -= 1;
+        }
+        index
+    }
+}
+
+/// 单词字符：字母、数字（包括 CJK 等 Unicode 字符）和下划线
+pub fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+fn is_punctuation(ch: char) -> bool {
+    !is_word_char(ch) && !ch.is_whitespace()
+}
+
+/// 跨行移动单词时一个位置的分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    /// 空白字符和非空行的行尾
+    Blank,
+    /// 空行，w / b / ge 会停在空行上
+    EmptyLine,
+    Punctuation,
+    /// 单词字符；WORD 中所有非空白字符都算这一类
+    Word,
+}
+
+fn char_class(ch: char, big: bool) -> CharClass {
+    if ch.is_whitespace() {
+        CharClass::Blank
+    } else if big || is_word_char(ch) {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// 跨行按字形簇移动的位置：每行的字形簇之后还有一个代表行尾的位置，空行只有这一个位置
+struct TextPosition<'a> {
+    buffer: &'a Buffer,
+    line: usize,
+    graphemes: LineGraphemes,
+    index: usize,
+    /// WORD（只按空白分隔）
+    big: bool,
+}
+
+impl<'a> TextPosition<'a> {
+    fn new(cursor: &Cursor, buffer: &'a Buffer, big: bool) -> Self {
+        let graphemes = LineGraphemes::new(&line_text(buffer, cursor.line));
+        let index = graphemes.index(cursor.column);
+        Self { buffer, line: cursor.line, graphemes, index, big }
+    }
+
+    fn class(&self) -> CharClass {
+        match self.graphemes.char(self.index) {
+            Some(ch) => char_class(ch, self.big),
+            None if self.graphemes.count() == 0 => CharClass::EmptyLine,
+            None => CharClass::Blank,
+        }
+    }
+
+    /// 移到下一个位置，已经在最后一行的行尾时返回 false
+    fn next(&mut self) -> bool {
+        if self.index < self.graphemes.count() {
+            self.index += 1;
+        } else if self.line < self.buffer.last_content_line() {
+            self.line += 1;
+            self.graphemes = LineGraphemes::new(&line_text(self.buffer, self.line));
+            self.index = 0;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// 移到上一个位置，已经在第一行的开头时返回 false
+    fn prev(&mut self) -> bool {
+        if self.index > 0 {
+            self.index -= 1;
+        } else if self.line > 0 {
+            self.line -= 1;
+            self.graphemes = LineGraphemes::new(&line_text(self.buffer, self.line));
+            self.index = self.graphemes.count();
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// 移到当前单词（同一类的连续字形簇）的第一个字形簇
+    fn run_start(&mut self) {
+        let class = self.class();
+        self.index = self.graphemes.skip_backward(self.index, |ch| char_class(ch, self.big) == class);
+    }
+
+    /// 移到当前单词的最后一个字形簇
+    fn run_end(&mut self) {
+        let class = self.class();
+        let end = self.graphemes.skip_forward(self.index, |ch| char_class(ch, self.big) == class);
+        self.index = end.saturating_sub(1).max(self.index);
+    }
+
+    /// 把光标移到这个位置，行尾的位置按行的最后一个字形簇
+    fn apply(&self, cursor: &mut Cursor) {
+        cursor.line = self.line;
+        cursor.column = self.graphemes.column(self.index.min(self.graphemes.count().saturating_sub(1)));
+        cursor.update_preferred_column(self.buffer);
+    }
+}
+
+fn move_left(cursor: &mut Cursor, buffer: &Buffer) {
     if cursor.column > 0 {
-        cursor.column -= 1;
-        cursor.update_preferred_column();
+        let text = line_text(buffer, cursor.line);
+        cursor.column = display::prev_grapheme(&text, cursor.column);
+        cursor.update_preferred_column(buffer);
     }
 }
 
 fn move_right(cursor: &mut Cursor, buffer: &Buffer) {
-    let line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-    if cursor.column < line_text.len().saturating_sub(1) {
-        cursor.column += 1;
-        cursor.update_preferred_column();
+    let text = line_text(buffer, cursor.line);
+    if cursor.column < display::last_grapheme(&text) {
+        cursor.column = display::next_grapheme(&text, cursor.column);
+        cursor.update_preferred_column(buffer);
     }
 }
 
 fn move_up(cursor: &mut Cursor, buffer: &Buffer) {
     if cursor.line > 0 {
         cursor.line -= 1;
-        // 确保列位置不超过新行的长度
-        let line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-        let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-        cursor.column = cursor.column.min(line_text.len().saturating_sub(1));
+        cursor.restore_preferred_column(buffer);
     }
 }
 
 fn move_down(cursor: &mut Cursor, buffer: &Buffer) {
     if cursor.line + 1 < buffer.len_lines() {
         cursor.line += 1;
-        // 确保列位置不超过新行的长度
-        let line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-        let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-        cursor.column = cursor.column.min(line_text.len().saturating_sub(1));
+        cursor.restore_preferred_column(buffer);
     }
 }
 
 fn move_line_start(cursor: &mut Cursor) {
     cursor.column = 0;
-    cursor.update_preferred_column();
+    cursor.preferred_column = Some(0);
 }
 
 fn move_line_end(cursor: &mut Cursor, buffer: &Buffer) {
-    let line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-    // 移除行尾换行符
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-    // 行尾是 line_text.len()，但光标应该停在最后一个字符
-    cursor.column = line_text.len().saturating_sub(1);
-    cursor.update_preferred_column();
+    // 光标停在最后一个字形簇上，而不是行尾之后
+    cursor.column = display::last_grapheme(&line_text(buffer, cursor.line));
+    cursor.update_preferred_column(buffer);
 }
 
 fn move_first_non_blank(cursor: &mut Cursor, buffer: &Buffer) {
-    let line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-    
-    // 找到第一个非空白字符
-    let first_non_blank = line_text
-        .chars()
-        .position(|ch| !ch.is_whitespace())
-        .unwrap_or(0);
-    
-    cursor.column = first_non_blank;
-    cursor.update_preferred_column();
+    let line = LineGraphemes::new(&line_text(buffer, cursor.line));
+    let first_non_blank = line.skip_forward(0, char::is_whitespace);
+    cursor.column = if first_non_blank < line.count() { line.column(first_non_blank) } else { 0 };
+    cursor.update_preferred_column(buffer);
+}
+
+fn move_last_non_blank(cursor: &mut Cursor, buffer: &Buffer) {
+    let line = LineGraphemes::new(&line_text(buffer, cursor.line));
+    let end = line.skip_backward(line.count(), char::is_whitespace);
+    cursor.column = line.column(end.saturating_sub(1));
+    cursor.update_preferred_column(buffer);
+}
+
+/// 移到第 `column` 个屏幕列（从 1 开始）所在的字符，超过行尾时停在最后一个字符
+fn move_to_column(cursor: &mut Cursor, buffer: &Buffer, column: usize, tabstop: usize) {
+    let text = line_text(buffer, cursor.line);
+    cursor.column = display::char_at_column(&text, column.saturating_sub(1), tabstop).min(display::last_grapheme(&text));
+    cursor.update_preferred_column(buffer);
+}
+
+/// 移到光标所在屏幕行的第一个 (`end` 为 false) 或最后一个字符；
+/// 不折行时屏幕行是窗口中显示的那一段（水平滚动到光标可见）
+fn move_screen_line_edge(cursor: &mut Cursor, buffer: &Buffer, viewport: &Viewport, end: bool) {
+    let text = line_text(buffer, cursor.line);
+    let tabstop = viewport.tabstop;
+    let width = viewport.width.max(1);
+    let column = display::display_column(&text, cursor.column, tabstop);
+    let (start, row_width) = if viewport.wrap {
+        display::wrap_line(&text, width, tabstop)
+            .into_iter()
+            .rev()
+            .find(|(start, _)| *start <= column)
+            .map_or((0, 0), |(start, row)| (start, row.chars().map(|c| display::char_width(c, 0, tabstop)).sum()))
+    } else {
+        ((column + 1).saturating_sub(width), width)
+    };
+    let target = if end { start + row_width.saturating_sub(1) } else { start };
+    cursor.column = display::char_at_column(&text, target, tabstop).min(display::last_grapheme(&text));
+    cursor.update_preferred_column(buffer);
 }
 
 fn move_word_forward(cursor: &mut Cursor, buffer: &Buffer) {
@@ -139,14 +470,11 @@ fn move_word_forward(cursor: &mut Cursor, buffer: &Buffer) {
 
 fn move_word_forward_internal(cursor: &mut Cursor, buffer: &Buffer, cross_line: bool) {
     let current_line = cursor.line;
-    let current_col = cursor.column;
-    let line_text = buffer.line(current_line).map(|l| l.to_string()).unwrap_or_default();
-
-    // 移除行尾换行符
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
+    let line = LineGraphemes::new(&line_text(buffer, current_line));
+    let mut index = line.index(cursor.column);
 
     // 如果已经在行尾或超出，移动到下一行的第一个单词
-    if current_col >= line_text.len() {
+    if index >= line.count() {
         if current_line + 1 < buffer.len_lines() {
             cursor.line = current_line + 1;
             cursor.column = 0;
@@ -156,239 +484,173 @@ fn move_word_forward_internal(cursor: &mut Cursor, buffer: &Buffer, cross_line:
         return;
     }
 
-    let remaining = &line_text[current_col..];
-    let mut chars = remaining.chars().peekable();
-
     // 如果是跨行后的第一次调用，先跳过前导空白，然后停在第一个单词开头
     if cross_line {
-        // 跳过空白字符
-        while let Some(&ch) = chars.peek() {
-            if ch.is_whitespace() {
-                chars.next();
-            } else {
-                break;
-            }
-        }
-        // 停在当前位置（第一个单词的开头）
-        if chars.peek().is_some() {
-            let consumed = remaining.len() - chars.collect::<String>().len();
-            cursor.column = current_col + consumed;
-            cursor.update_preferred_column();
+        index = line.skip_forward(index, char::is_whitespace);
+        if index < line.count() {
+            cursor.column = line.column(index);
+            cursor.update_preferred_column(buffer);
             return;
         }
     }
 
-    // 检查当前位置是否是单词字符
-    let at_word = chars.peek().map(|&ch| ch.is_alphanumeric() || ch == '_').unwrap_or(false);
-
-    if at_word {
-        // 当前位置是单词，跳过这个单词的剩余部分
-        while let Some(&ch) = chars.peek() {
-            if ch.is_alphanumeric() || ch == '_' {
-                chars.next();
-            } else {
-                break;
-            }
-        }
-
-        // 跳过标点符号
-        while let Some(&ch) = chars.peek() {
-            if !ch.is_alphanumeric() && ch != '_' && !ch.is_whitespace() {
-                chars.next();
-            } else {
-                break;
-            }
-        }
-
-        // 跳过空白字符（包括行尾，允许跨行）
-        while let Some(&ch) = chars.peek() {
-            if ch.is_whitespace() {
-                chars.next();
-            } else {
-                break;
-            }
-        }
+    if line.char(index).is_some_and(is_word_char) {
+        // 当前位置是单词，跳过这个单词的剩余部分和之后的标点
+        index = line.skip_forward(index, is_word_char);
+        index = line.skip_forward(index, is_punctuation);
     } else {
-        // 当前位置不是单词（是空白或标点），跳过空白，停在下一个单词开头
-        // 跳过标点符号
-        while let Some(&ch) = chars.peek() {
-            if !ch.is_alphanumeric() && ch != '_' && !ch.is_whitespace() {
-                chars.next();
-            } else {
-                break;
-            }
-        }
-
-        // 跳过空白字符
-        while let Some(&ch) = chars.peek() {
-            if ch.is_whitespace() {
-                chars.next();
-            } else {
-                break;
-            }
-        }
-        // 停在这里（下一个单词的开头）
+        // 当前位置不是单词（是空白或标点），跳过标点
+        index = line.skip_forward(index, is_punctuation);
     }
+    // 跳过空白字符，停在下一个单词的开头
+    index = line.skip_forward(index, char::is_whitespace);
 
-    let consumed = remaining.len() - chars.collect::<String>().len();
-    let new_col = current_col + consumed;
-
-    // 如果已经到达或超过行尾，尝试移动到下一行
-    if new_col >= line_text.len() {
+    // 如果已经到达行尾，尝试移动到下一行
+    if index >= line.count() {
         if current_line + 1 < buffer.len_lines() {
             cursor.line = current_line + 1;
             cursor.column = 0;
             // 递归调用以跳过新行的前导空白
             move_word_forward_internal(cursor, buffer, true);
-        } else {
-            // 在最后一行，移动到行尾
-            cursor.column = line_text.len().saturating_sub(1);
+            return;
         }
+        // 在最后一行，移动到最后一个字形簇
+        cursor.column = line.column(line.count().saturating_sub(1));
     } else {
-        cursor.column = new_col;
+        cursor.column = line.column(index);
     }
-    cursor.update_preferred_column();
+    cursor.update_preferred_column(buffer);
 }
 
 fn move_word_backward(cursor: &mut Cursor, buffer: &Buffer) {
     let current_line = cursor.line;
-    let current_col = cursor.column;
-    
+
     // 如果已经在行首，尝试移动到上一行的最后一个单词
-    if current_col == 0 {
+    if cursor.column == 0 {
         if current_line > 0 {
             cursor.line = current_line - 1;
-            // 获取上一行的文本
-            let prev_line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-            let prev_line_text = prev_line_text.strip_suffix('\n').unwrap_or(&prev_line_text);
             // 移动到上一行的行尾，然后递归调用以找到最后一个单词
-            cursor.column = prev_line_text.len();
+            cursor.column = buffer.line_text(cursor.line).map_or(0, |text| text.chars().count());
             move_word_backward(cursor, buffer);
         }
         return;
     }
-    
-    let line_text = buffer.line(current_line).map(|l| l.to_string()).unwrap_or_default();
-    
-    // 移除行尾换行符
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-    
-    // 获取当前位置之前的文本
-    let preceding = &line_text[..current_col.min(line_text.len())];
-    let mut chars: Vec<char> = preceding.chars().collect();
-    
-    // 跳过空白字符
-    while let Some(&ch) = chars.last() {
-        if ch.is_whitespace() {
-            chars.pop();
-        } else {
-            break;
-        }
+
+    let line = LineGraphemes::new(&line_text(buffer, current_line));
+    let mut index = line.index(cursor.column);
+    index = line.skip_backward(index, char::is_whitespace);
+    index = line.skip_backward(index, is_punctuation);
+    index = line.skip_backward(index, is_word_char);
+
+    cursor.column = line.column(index);
+    cursor.update_preferred_column(buffer);
+}
+
+fn move_word_end(cursor: &mut Cursor, buffer: &Buffer) {
+    let line = LineGraphemes::new(&line_text(buffer, cursor.line));
+    let start = line.index(cursor.column);
+    if start >= line.count() {
+        return;
     }
-    
-    // 跳过标点符号
-    while let Some(&ch) = chars.last() {
-        if !ch.is_alphanumeric() && ch != '_' && !ch.is_whitespace() {
-            chars.pop();
-        } else {
-            break;
+
+    let mut index = line.skip_forward(start, char::is_whitespace);
+    index = line.skip_forward(index, is_word_char);
+    index = line.skip_forward(index, is_punctuation);
+
+    // 停在跳过的最后一个字形簇上，且不超出本行范围
+    let end = (start + (index - start).saturating_sub(1)).min(line.count() - 1);
+    cursor.column = line.column(end);
+    cursor.update_preferred_column(buffer);
+}
+
+/// W：跳过当前的 WORD 和之后的空白（包括换行），停在下一个 WORD 的开头或空行上；没有下一个 WORD 时停在最后一个字符
+fn move_big_word_forward(cursor: &mut Cursor, buffer: &Buffer) {
+    let mut pos = TextPosition::new(cursor, buffer, true);
+    let moved = match pos.class() {
+        CharClass::Word => {
+            pos.run_end();
+            pos.next()
         }
+        CharClass::EmptyLine => pos.next(),
+        _ => true,
+    };
+    if moved {
+        while pos.class() == CharClass::Blank && pos.next() {}
     }
-    
-    // 跳过单词
-    while let Some(&ch) = chars.last() {
-        if ch.is_alphanumeric() || ch == '_' {
-            chars.pop();
-        } else {
-            break;
-        }
+    pos.apply(cursor);
+}
+
+/// B：向前跳过空白（包括换行），停在上一个 WORD 的开头或空行上
+fn move_big_word_backward(cursor: &mut Cursor, buffer: &Buffer) {
+    let mut pos = TextPosition::new(cursor, buffer, true);
+    if !pos.prev() {
+        return;
     }
-    
-    let new_col = chars.len();
-    cursor.column = new_col;
-    cursor.update_preferred_column();
+    while pos.class() == CharClass::Blank && pos.prev() {}
+    if pos.class() == CharClass::Word {
+        pos.run_start();
+    }
+    pos.apply(cursor);
 }
 
-fn move_word_end(cursor: &mut Cursor, buffer: &Buffer) {
-    let current_line = cursor.line;
-    let current_col = cursor.column;
-    let line_text = buffer.line(current_line).map(|l| l.to_string()).unwrap_or_default();
-    
-    // 移除行尾换行符
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-    
-    if current_col >= line_text.len() {
+/// E：向后移动至少一个位置，跳过空白和空行，停在 WORD 的最后一个字符
+fn move_big_word_end(cursor: &mut Cursor, buffer: &Buffer) {
+    let mut pos = TextPosition::new(cursor, buffer, true);
+    if !pos.next() {
         return;
     }
-    
-    let remaining = &line_text[current_col..];
-    let mut chars = remaining.chars().peekable();
-    
-    // 跳过空白字符
-    if let Some(&ch) = chars.peek() {
-        if ch.is_whitespace() {
-            while let Some(&ch) = chars.peek() {
-                if ch.is_whitespace() && ch != '\n' {
-                    chars.next();
-                } else {
-                    break;
-                }
-            }
+    while matches!(pos.class(), CharClass::Blank | CharClass::EmptyLine) {
+        if !pos.next() {
+            // 之后没有 WORD，停在最后一个字符
+            pos.apply(cursor);
+            return;
         }
     }
-    
-    // 跳过单词
-    while let Some(&ch) = chars.peek() {
-        if ch.is_alphanumeric() || ch == '_' {
-            chars.next();
-        } else {
-            break;
-        }
+    pos.run_end();
+    pos.apply(cursor);
+}
+
+/// ge / gE：跳过当前单词和之前的空白（包括换行），停在上一个单词的最后一个字符或空行上；
+/// 之前没有单词时停在文件开头
+fn move_word_end_backward(cursor: &mut Cursor, buffer: &Buffer, big: bool) {
+    let mut pos = TextPosition::new(cursor, buffer, big);
+    if matches!(pos.class(), CharClass::Word | CharClass::Punctuation) {
+        pos.run_start();
     }
-    
-    // 跳过标点
-    while let Some(&ch) = chars.peek() {
-        if !ch.is_alphanumeric() && ch != '_' && !ch.is_whitespace() {
-            chars.next();
-        } else {
-            break;
-        }
+    let mut found = pos.prev();
+    while found && pos.class() == CharClass::Blank {
+        found = pos.prev();
     }
-    
-    let consumed = remaining.len() - chars.collect::<String>().len();
-    let new_col = current_col + consumed.saturating_sub(1);
-    
-    // 确保不超出本行范围
-    let final_col = new_col.min(line_text.len().saturating_sub(1));
-    cursor.column = final_col;
-    cursor.update_preferred_column();
+    if found {
+        pos.apply(cursor);
+    } else {
+        move_document_start(cursor);
+    }
+}
+
+/// 移到第 `line` 行（不超过最后一行）的第一个非空字符
+fn move_to_line(cursor: &mut Cursor, buffer: &Buffer, line: usize) {
+    cursor.line = line.min(buffer.last_content_line());
+    move_first_non_blank(cursor, buffer);
 }
 
 fn move_document_start(cursor: &mut Cursor) {
     cursor.line = 0;
     cursor.column = 0;
-    cursor.update_preferred_column();
+    cursor.preferred_column = Some(0);
 }
 
 fn move_document_end(cursor: &mut Cursor, buffer: &Buffer) {
-    cursor.line = buffer.len_lines().saturating_sub(1);
-    let line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-    cursor.column = line_text.len().saturating_sub(1);
-    cursor.update_preferred_column();
+    cursor.line = buffer.last_content_line();
+    move_line_end(cursor, buffer);
 }
 
-fn page_down(cursor: &mut Cursor, buffer: &Buffer) {
-    // 向下移动半页（假设半页为 10 行）
-    let half_page = 10;
-    cursor.line = (cursor.line + half_page).min(buffer.len_lines().saturating_sub(1));
-    let line_text = buffer.line(cursor.line).map(|l| l.to_string()).unwrap_or_default();
-    let line_text = line_text.strip_suffix('\n').unwrap_or(&line_text);
-    cursor.column = cursor.column.min(line_text.len().saturating_sub(1));
+fn page_down(cursor: &mut Cursor, buffer: &Buffer, half_page: usize) {
+    cursor.line = (cursor.line + half_page).min(buffer.last_content_line());
+    cursor.restore_preferred_column(buffer);
 }
 
-fn page_up(cursor: &mut Cursor) {
-    // 向上移动半页
-    let half_page = 10;
+fn page_up(cursor: &mut Cursor, buffer: &Buffer, half_page: usize) {
     cursor.line = cursor.line.saturating_sub(half_page);
+    cursor.restore_preferred_column(buffer);
 }
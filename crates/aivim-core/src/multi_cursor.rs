@@ -0,0 +1,66 @@
+//! 多光标编辑
+//!
+//! Normal 模式下 Ctrl+N 在光标下单词的下一个出现位置（整词匹配，到文件末尾后从头开始）加一个光标，
+//! 新加的光标成为主光标，窗口跟随它滚动：
+//! - Normal 模式的动作、操作符、`r`、`x`、`p` / `P`、`J` 和进入 Insert 模式的 `i` `a` `I` `A` `o` `O`
+//!   对每个光标执行一次
+//! - Insert 模式下输入的字符、回车、退格和 Tab 插入到每个光标处，`.` 寄存器只记录一份
+//! - 所有光标的修改作为一次撤销单元；撤销、重做之后只保留主光标，切换缓冲区之后其他光标不再生效
+//! - Normal 模式下按 Esc 回到单个光标
+//!
+//! 光标按在缓冲区中的位置从后往前执行，前面光标的修改使后面已经执行过的光标移动相同的字符数；
+//! 执行之后位置相同的光标合并为一个。
+
+use ropey::Rope;
+
+use crate::buffer::BufferId;
+use crate::cursor::Cursor;
+use crate::motion::is_word_char;
+
+/// 主光标之外的其他光标，只在创建它们的缓冲区中有效
+#[derive(Debug, Clone)]
+pub struct MultiCursor {
+    buffer: BufferId,
+    cursors: Vec<Cursor>,
+}
+
+impl MultiCursor {
+    pub fn new(buffer: BufferId, cursors: Vec<Cursor>) -> Self {
+        Self { buffer, cursors }
+    }
+
+    pub fn buffer(&self) -> BufferId {
+        self.buffer
+    }
+
+    pub fn cursors(&self) -> &[Cursor] {
+        &self.cursors
+    }
+
+    pub fn into_cursors(self) -> Vec<Cursor> {
+        self.cursors
+    }
+}
+
+/// `text` 中 `after`（字符下标）之后 `word` 的下一个整词出现位置，到末尾后从头开始，
+/// 跳过 `taken` 中已经有光标的位置；没有时返回 None
+///
+/// 直接在 rope 上逐个位置比较，不把整个缓冲区复制成字符串
+pub fn next_word_match(text: &Rope, word: &str, after: usize, taken: &[usize]) -> Option<usize> {
+    let word: Vec<char> = word.chars().collect();
+    let len = text.len_chars();
+    if word.is_empty() || word.len() > len {
+        return None;
+    }
+    let is_match = |start: usize| {
+        let end = start + word.len();
+        text.char(start) == word[0]
+            && text.slice(start..end).chars().eq(word.iter().copied())
+            && (start == 0 || !is_word_char(text.char(start - 1)))
+            && (end == len || !is_word_char(text.char(end)))
+            && !taken.contains(&start)
+    };
+    let last = len - word.len();
+    let wrap = after.min(last);
+    (after.saturating_add(1)..=last).chain(0..=wrap).find(|&start| is_match(start))
+}
@@ -0,0 +1,108 @@
+//! 数字加减模块 (Ctrl+A / Ctrl+X)
+//!
+//! 在一行文本中查找光标处或光标之后的第一个数字，支持：
+//! - 十进制整数，前面的 `-` 视为负号（紧跟在字母数字之后的 `-` 除外，如 `a-1`）
+//! - 十六进制 `0x` / `0X`，按无符号数回绕，保持原有的位数和大小写；前面的 `-` 不是负号
+
+/// 数字的进制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    Decimal,
+    Hex,
+}
+
+/// 在行中找到的数字
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberMatch {
+    /// 起始列（包含负号或 0x 前缀）
+    pub start: usize,
+    /// 结束列（不包含）
+    pub end: usize,
+    pub format: NumberFormat,
+}
+
+/// 查找光标所在或之后的第一个数字
+pub fn find_number(line: &[char], column: usize) -> Option<NumberMatch> {
+    let mut i = 0;
+    while i < line.len() {
+        let found = match_number_at(line, i);
+        match found {
+            Some(number) if number.end > column => return Some(number),
+            Some(number) => i = number.end,
+            None => i += 1,
+        }
+    }
+    None
+}
+
+/// 从第 `i` 列开始匹配一个数字
+fn match_number_at(line: &[char], i: usize) -> Option<NumberMatch> {
+    if is_hex_prefix(line, i) {
+        let end = scan_while(line, i + 2, |c| c.is_ascii_hexdigit());
+        return Some(NumberMatch {
+            start: i,
+            end,
+            format: NumberFormat::Hex,
+        });
+    }
+
+    let negative = line[i] == '-'
+        && line.get(i + 1).is_some_and(|c| c.is_ascii_digit())
+        && !(i > 0 && line[i - 1].is_alphanumeric());
+    let digits_start = if negative { i + 1 } else { i };
+    // 十六进制数没有符号：`-0x0f` 中的 `-` 不属于数字，从下一列开始匹配十六进制数
+    if !line.get(digits_start).is_some_and(|c| c.is_ascii_digit()) || (negative && is_hex_prefix(line, digits_start)) {
+        return None;
+    }
+    Some(NumberMatch {
+        start: i,
+        end: scan_while(line, digits_start, |c| c.is_ascii_digit()),
+        format: NumberFormat::Decimal,
+    })
+}
+
+/// 第 `i` 列是否是后面跟着十六进制数字的 `0x` / `0X`
+fn is_hex_prefix(line: &[char], i: usize) -> bool {
+    line.get(i) == Some(&'0')
+        && matches!(line.get(i + 1), Some('x') | Some('X'))
+        && line.get(i + 2).is_some_and(|c| c.is_ascii_hexdigit())
+}
+
+fn scan_while(line: &[char], start: usize, pred: impl Fn(char) -> bool) -> usize {
+    let mut end = start;
+    while end < line.len() && pred(line[end]) {
+        end += 1;
+    }
+    end
+}
+
+/// 计算数字加上 `delta` 后的文本，数字超出范围时返回 None
+pub fn add_to_number(text: &str, format: NumberFormat, delta: i64) -> Option<String> {
+    match format {
+        NumberFormat::Hex => {
+            let digits = &text[2..];
+            let value = u64::from_str_radix(digits, 16).ok()?;
+            let result = value.wrapping_add(delta as u64);
+            // 保持原有的位数，有大写字母时输出大写
+            let width = digits.len();
+            let formatted = if digits.chars().any(|c| c.is_ascii_uppercase()) {
+                format!("{:0width$X}", result)
+            } else {
+                format!("{:0width$x}", result)
+            };
+            Some(format!("{}{}", &text[..2], formatted))
+        }
+        NumberFormat::Decimal => {
+            let value: i64 = text.parse().ok()?;
+            let result = value.checked_add(delta)?;
+            // 带前导零的数字保持原有的位数（如 007 -> 008）
+            let digits = text.trim_start_matches('-');
+            if digits.len() > 1 && digits.starts_with('0') {
+                let sign = if result < 0 { "-" } else { "" };
+                Some(format!("{}{:0width$}", sign, result.unsigned_abs(), width = digits.len()))
+            } else {
+                Some(result.to_string())
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! 括号和引号对
+//!
+//! - autopairs：Insert 模式下输入左括号或引号时自动插入对应的右字符，光标后正好是要输入的右字符时跳过它；
+//!   在空的一对字符之间退格时一起删除
+//! - 包围操作（vim-surround）：`ys` 添加、`ds` 删除、`cs` 修改包围文本的一对字符。
+//!   `(` `[` `{` 在内侧加一个空格（删除时连同内侧的空白一起删除），`)` `]` `}` `>` 和 `b` `r` `B` `a` 不加空格，
+//!   其他标点用同一个字符包围；`ds` / `cs` 的原字符还可以是 `t`（HTML 标签）
+
+use crate::buffer::Buffer;
+use crate::cursor::Cursor;
+use crate::motion::is_word_char;
+use crate::text_object::{bracket_pair, closing_bracket, quote_pair, TextObject};
+
+/// autopairs 自动补全的字符对
+const AUTO_PAIRS: [(char, char); 6] = [('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\''), ('`', '`')];
+
+/// 输入 `ch` 时自动插入的右字符，`prev` / `next` 为光标前后的字符
+///
+/// 光标后是单词字符时不补全；引号前是单词字符时也不补全（如 `don't`、Rust 的生命周期 `'a`）
+pub fn auto_close(ch: char, prev: Option<char>, next: Option<char>) -> Option<char> {
+    let &(_, close) = AUTO_PAIRS.iter().find(|(open, _)| *open == ch)?;
+    if next.is_some_and(is_word_char) {
+        return None;
+    }
+    if ch == close && prev.is_some_and(|c| is_word_char(c) || c == ch) {
+        return None;
+    }
+    Some(close)
+}
+
+/// 输入右括号或引号时光标后正好是它：跳过而不是插入
+pub fn skips_over(ch: char, next: Option<char>) -> bool {
+    next == Some(ch) && AUTO_PAIRS.iter().any(|(_, close)| *close == ch)
+}
+
+/// 光标在空的一对字符之间（如 `(|)`），退格时一起删除
+pub fn deletes_pair(prev: Option<char>, next: Option<char>) -> bool {
+    matches!((prev, next), (Some(open), Some(close)) if AUTO_PAIRS.contains(&(open, close)))
+}
+
+/// `ys` / `cs` 的新包围字符对应的（左, 右）文本
+pub fn surround_delimiters(ch: char) -> Option<(String, String)> {
+    let (open, close, padded) = match ch {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        '{' => ('{', '}', true),
+        ')' | 'b' => ('(', ')', false),
+        ']' | 'r' => ('[', ']', false),
+        '}' | 'B' => ('{', '}', false),
+        '<' | '>' | 'a' => ('<', '>', false),
+        _ if ch.is_ascii_punctuation() => (ch, ch, false),
+        _ => return None,
+    };
+    Some(if padded {
+        (format!("{} ", open), format!(" {}", close))
+    } else {
+        (open.to_string(), close.to_string())
+    })
+}
+
+/// 包围光标的一对字符的位置（字符索引范围 [start, end)）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Surrounding {
+    pub open: (usize, usize),
+    pub close: (usize, usize),
+}
+
+/// 查找 `ds` / `cs` 的原字符 `target` 对应的包围光标的一对字符
+pub fn find_surrounding(buffer: &Buffer, cursor: &Cursor, target: char) -> Option<Surrounding> {
+    let open = match target {
+        ')' | 'b' => '(',
+        ']' | 'r' => '[',
+        '}' | 'B' => '{',
+        '>' | 'a' => '<',
+        't' => {
+            let (start, end) = TextObject::AroundTag.get_range(cursor, buffer)?;
+            let (inner_start, inner_end) = TextObject::InnerTag.get_range(cursor, buffer)?;
+            return Some(Surrounding { open: (start, inner_start), close: (inner_end, end) });
+        }
+        _ => target,
+    };
+
+    if closing_bracket(open).is_some() {
+        let (open_idx, close_idx) = bracket_pair(buffer, cursor.to_char_idx(buffer), open)?;
+        let mut surrounding = Surrounding { open: (open_idx, open_idx + 1), close: (close_idx, close_idx + 1) };
+        // ds( 连同括号内侧的空白一起删除
+        if target == open {
+            let is_blank = |c: &char| *c == ' ' || *c == '\t';
+            let inner = buffer.slice(open_idx + 1..close_idx);
+            let leading = inner.chars().take_while(is_blank).count();
+            let trailing = inner.chars_at(inner.len_chars()).reversed().take_while(is_blank).count();
+            let trailing = trailing.min(inner.len_chars() - leading);
+            surrounding.open.1 += leading;
+            surrounding.close.0 -= trailing;
+        }
+        return Some(surrounding);
+    }
+
+    if !target.is_ascii_punctuation() {
+        return None;
+    }
+    let (open_idx, close_idx) = quote_pair(buffer, cursor, target)?;
+    Some(Surrounding { open: (open_idx, open_idx + 1), close: (close_idx, close_idx + 1) })
+}
@@ -0,0 +1,71 @@
+//! 粘贴检测模块
+//!
+//! 不支持 bracketed paste 的终端会把粘贴内容当作一串普通按键发送，
+//! 自动缩进等功能会把粘贴的代码缩进弄乱。这里根据按键间隔判断是否在粘贴：
+//! 连续多个按键的间隔都远小于人手输入的速度时认为开始粘贴，
+//! 之后停顿超过一段时间认为粘贴结束。
+
+use std::time::{Duration, Instant};
+
+/// 两个按键间隔小于该值时视为快速输入
+pub const PASTE_KEY_INTERVAL: Duration = Duration::from_millis(8);
+
+/// 连续快速输入的按键数达到该值时认为在粘贴
+pub const PASTE_BURST_KEYS: usize = 6;
+
+/// 粘贴过程中停顿超过该值时认为粘贴结束
+pub const PASTE_IDLE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// 按键间隔检测器
+#[derive(Debug, Clone, Default)]
+pub struct PasteDetector {
+    /// 上一个按键的时间
+    last_key: Option<Instant>,
+    /// 当前连续快速输入的按键数
+    burst: usize,
+    /// 是否处于检测到的粘贴中
+    active: bool,
+}
+
+impl PasteDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否处于检测到的粘贴中
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// 记录一个按键，返回本次是否刚检测到粘贴开始
+    pub fn record_key(&mut self, now: Instant) -> bool {
+        let fast = self
+            .last_key
+            .is_some_and(|last| now.saturating_duration_since(last) < PASTE_KEY_INTERVAL);
+        self.last_key = Some(now);
+        self.burst = if fast { self.burst + 1 } else { 1 };
+
+        if !self.active && self.burst >= PASTE_BURST_KEYS {
+            self.active = true;
+            return true;
+        }
+        false
+    }
+
+    /// 检查粘贴是否结束，返回本次是否刚结束
+    pub fn poll(&mut self, now: Instant) -> bool {
+        let idle = self
+            .last_key
+            .is_none_or(|last| now.saturating_duration_since(last) >= PASTE_IDLE_TIMEOUT);
+        if self.active && idle {
+            self.reset();
+            return true;
+        }
+        false
+    }
+
+    /// 清除检测状态
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,263 @@
+//! 模糊选择器模块
+//!
+//! 通用的模糊过滤列表，用于符号选择（:symbols）、文件查找（Ctrl+P）等需要从大量候选中
+//! 快速定位一项的场景：
+//! - 输入查询字符串，按子序列匹配过滤候选项并按得分排序
+//! - 上下移动选择，回车跳转到选中项的位置
+//! - 候选项可以在打开后陆续加入（后台查找文件时），加入时保持当前的选择
+
+use std::path::PathBuf;
+
+/// 默认最多同时显示的候选项数量
+pub const DEFAULT_MAX_VISIBLE: usize = 12;
+
+/// 选择器候选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickerItem {
+    /// 用于匹配和显示的文本
+    pub label: String,
+    /// 附加说明（如符号类型、文件名）
+    pub detail: Option<String>,
+    /// 所在文件，None 表示当前缓冲区
+    pub path: Option<PathBuf>,
+    /// 跳转位置（行、列，从 0 开始）
+    pub line: usize,
+    pub column: usize,
+}
+
+impl PickerItem {
+    pub fn new(label: impl Into<String>, line: usize, column: usize) -> Self {
+        Self {
+            label: label.into(),
+            detail: None,
+            path: None,
+            line,
+            column,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+/// 模糊选择器状态
+#[derive(Debug, Clone)]
+pub struct Picker {
+    title: String,
+    items: Vec<PickerItem>,
+    query: String,
+    /// 匹配的候选项索引，按得分从高到低排序
+    matches: Vec<usize>,
+    selected: usize,
+    scroll_offset: usize,
+    max_visible: usize,
+    /// 是否还在加载候选项
+    loading: bool,
+}
+
+impl Picker {
+    pub fn new(title: impl Into<String>, items: Vec<PickerItem>) -> Self {
+        let mut picker = Self {
+            title: title.into(),
+            items,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            scroll_offset: 0,
+            max_visible: DEFAULT_MAX_VISIBLE,
+            loading: false,
+        };
+        picker.refilter();
+        picker
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// 候选项总数
+    pub fn total(&self) -> usize {
+        self.items.len()
+    }
+
+    /// 匹配当前查询的候选项数量
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_item(&self) -> Option<&PickerItem> {
+        self.matches.get(self.selected).map(|&idx| &self.items[idx])
+    }
+
+    pub fn max_visible(&self) -> usize {
+        self.max_visible
+    }
+
+    pub fn set_max_visible(&mut self, max_visible: usize) {
+        self.max_visible = max_visible.max(1);
+        self.ensure_selected_visible();
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading
+    }
+
+    pub fn set_loading(&mut self, loading: bool) {
+        self.loading = loading;
+    }
+
+    /// 加入新的候选项，重新过滤后保持选中的一项
+    pub fn extend(&mut self, items: impl IntoIterator<Item = PickerItem>) {
+        let selected = self.matches.get(self.selected).copied();
+        let scroll_offset = self.scroll_offset;
+        self.items.extend(items);
+        self.refilter();
+        if let Some(position) = selected.and_then(|idx| self.matches.iter().position(|&m| m == idx)) {
+            self.selected = position;
+            self.scroll_offset = scroll_offset;
+            self.ensure_selected_visible();
+        }
+    }
+
+    /// 当前可见的匹配项（带在匹配列表中的索引）
+    pub fn visible_items(&self) -> impl Iterator<Item = (usize, &PickerItem)> {
+        self.matches
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(self.max_visible)
+            .map(|(pos, &idx)| (pos, &self.items[idx]))
+    }
+
+    /// 在查询末尾追加字符
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    /// 删除查询的最后一个字符
+    pub fn pop_char(&mut self) {
+        self.query.pop();
+        self.refilter();
+    }
+
+    pub fn set_query(&mut self, query: impl Into<String>) {
+        self.query = query.into();
+        self.refilter();
+    }
+
+    /// 选择下一项（到末尾后回到开头）
+    pub fn select_next(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        self.selected = (self.selected + 1) % self.matches.len();
+        self.ensure_selected_visible();
+    }
+
+    /// 选择上一项（到开头后回到末尾）
+    pub fn select_prev(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        self.selected = if self.selected == 0 {
+            self.matches.len() - 1
+        } else {
+            self.selected - 1
+        };
+        self.ensure_selected_visible();
+    }
+
+    /// 按查询重新过滤并排序，选择重置到第一项
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| fuzzy_score(&self.query, &item.label).map(|score| (score, idx)))
+            .collect();
+        // 得分相同时保持原有顺序
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.matches = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.selected = 0;
+        self.scroll_offset = 0;
+    }
+
+    fn ensure_selected_visible(&mut self) {
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if self.selected >= self.scroll_offset + self.max_visible {
+            self.scroll_offset = self.selected + 1 - self.max_visible;
+        }
+    }
+}
+
+/// 计算模糊匹配得分，不匹配时返回 None
+///
+/// 查询字符必须按顺序出现在候选文本中（子序列匹配）。
+/// 查询全部为小写时忽略大小写（smartcase）。
+/// 连续匹配、单词开头的匹配以及靠前的匹配得分更高，空查询匹配所有候选项。
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let ignore_case = !query.chars().any(|c| c.is_uppercase());
+    let normalize = |c: char| if ignore_case { c.to_ascii_lowercase() } else { c };
+
+    let mut query_chars = query.chars().map(normalize).peekable();
+    let mut score = 0i64;
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+    let mut first_match: Option<usize> = None;
+
+    for (idx, c) in candidate.chars().enumerate() {
+        let Some(&wanted) = query_chars.peek() else {
+            break;
+        };
+        if normalize(c) == wanted {
+            query_chars.next();
+            score += 1;
+            if prev_matched {
+                score += 5;
+            }
+            // 单词开头：首字符、分隔符之后或驼峰的大写字母
+            let boundary = match prev {
+                None => true,
+                Some(p) => !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase()),
+            };
+            if boundary {
+                score += 8;
+            }
+            first_match.get_or_insert(idx);
+            prev_matched = true;
+        } else {
+            prev_matched = false;
+        }
+        prev = Some(c);
+    }
+
+    if query_chars.peek().is_some() {
+        return None;
+    }
+
+    // 匹配越靠前、候选越短越好
+    score -= first_match.unwrap_or(0).min(20) as i64;
+    score -= (candidate.chars().count() as i64 / 8).min(10);
+    Some(score)
+}
@@ -0,0 +1,227 @@
+//! 插件接口
+//!
+//! 插件向编辑器注册 Ex 命令和按键映射，并接收自动命令的事件（见 autocmd 模块）：
+//! - `setup` 在注册时调用一次，可以设置选项、注册自动命令
+//! - `commands` 声明插件提供的命令，与内置命令一样可以缩写、带 `!` 和范围；
+//!   命令名必须以大写字母开头，不能与已有的命令相同。执行命令时调用插件的 `execute`
+//! - `keymaps` 声明的映射注册为全局的 noremap 映射，用户的映射可以覆盖
+//! - `on_event` 在用户的自动命令之前收到每一个事件
+//! - `poll` 由事件循环定期调用，取出后台任务的结果；`on_option_set` 在 `:set` 之后调用
+//! - `on_key` 在编辑器处理 Normal / Insert 模式的按键之前调用，`bracket` 处理编辑器不认识的 `]x` / `[x`
+//! - `complete` 为 Insert 模式提供补全的候选项（`Editor::provide_completion`）
+//! - `Editor::request_confirmation` 显示 y/n 提示，回答交给插件的 `on_confirm`
+//!
+//! Git 差异标记和 blame（git_plugin 模块）、语言服务器（lsp_plugin 模块）、AI 补全、对话和编辑
+//! （ai_plugin 模块）都是 `Editor::new` 中注册的内置插件，它们的状态保存在插件中，
+//! 界面用 `Editor::plugin` 按类型查询。`Editor::with_plugins` 只注册给定的插件，
+//! 不注册某个内置插件时它的命令、按键和后台检查都不可用。
+//! 第三方插件用 `Editor::register_plugin` 注册：
+//!
+//! ```ignore
+//! struct Hello;
+//!
+//! impl Plugin for Hello {
+//!     fn name(&self) -> &'static str {
+//!         "hello"
+//!     }
+//!
+//!     fn commands(&self) -> Vec<CommandSpec> {
+//!         vec![spec("Hello", 5, false, false)]
+//!     }
+//!
+//!     fn keymaps(&self) -> Vec<PluginKeymap> {
+//!         vec![PluginKeymap::new(MapMode::Normal, "<Leader>h", ":Hello<CR>")]
+//!     }
+//!
+//!     fn execute(&mut self, editor: &mut Editor, command: &ExCommand) -> Result<(), String> {
+//!         editor.set_message(format!("Hello, {}", command.args));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! editor.register_plugin(Box::new(Hello))?;
+//! ```
+//!
+//! 插件在执行命令或处理事件时从注册表中取出，期间再触发的事件不会发给它自己。
+
+use std::any::{Any, TypeId};
+use std::time::Instant;
+
+pub use crate::ai_plugin::AiPlugin;
+pub use crate::git_plugin::GitPlugin;
+pub use crate::lsp_plugin::LspPlugin;
+
+use crate::autocmd::Event;
+use crate::buffer::BufferId;
+use crate::editor::{ConfirmAnswer, Editor};
+use crate::ex_command::{CommandSpec, ExCommand};
+use crate::mapping::{MapKey, MapMode};
+
+/// 编辑器插件
+pub trait Plugin: Any {
+    /// 插件名
+    fn name(&self) -> &'static str;
+
+    /// 注册时调用一次，返回错误时不注册插件
+    fn setup(&mut self, _editor: &mut Editor) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// 插件提供的 Ex 命令
+    fn commands(&self) -> Vec<CommandSpec> {
+        Vec::new()
+    }
+
+    /// 插件提供的按键映射
+    fn keymaps(&self) -> Vec<PluginKeymap> {
+        Vec::new()
+    }
+
+    /// 执行插件提供的命令，`command.name` 为完整的命令名
+    fn execute(&mut self, _editor: &mut Editor, _command: &ExCommand) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// 自动命令的事件，`buffer` 为发生事件的缓冲区
+    fn on_event(&mut self, _editor: &mut Editor, _event: Event, _buffer: BufferId) {}
+
+    /// 取出后台任务的结果，返回界面是否需要重绘；由事件循环定期调用
+    fn poll(&mut self, _editor: &mut Editor, _now: Instant) -> bool {
+        false
+    }
+
+    /// `:set` / `:setlocal` 修改选项或读取 config.toml 的设置之后调用，插件按新的设置开启或关闭功能
+    fn on_option_set(&mut self, _editor: &mut Editor) {}
+
+    /// Normal 模式（没有未完成的按键序列时）和 Insert 模式下编辑器处理按键之前调用，
+    /// 返回 true 时按键已被插件处理（如 Esc 关闭插件的浮动窗口）
+    fn on_key(&mut self, _editor: &mut Editor, _key: MapKey) -> bool {
+        false
+    }
+
+    /// 编辑器不处理的 `]{key}` / `[{key}`，返回 Ok(false) 表示插件也不处理
+    fn bracket(&mut self, _editor: &mut Editor, _key: char, _forward: bool, _count: usize) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    /// Insert 模式下请求补全：`trigger` 为 None 时是手动请求 (Ctrl+Space)，否则为刚输入的字符；
+    /// 返回 true 表示插件稍后用 `Editor::provide_completion` 提供候选项
+    fn complete(&mut self, _editor: &mut Editor, _trigger: Option<char>) -> bool {
+        false
+    }
+
+    /// 补全被取消（关闭了菜单或开始了新的补全），不再需要 `complete` 请求的候选项
+    fn cancel_completion(&mut self, _editor: &mut Editor) {}
+
+    /// 插件用 `Editor::request_confirmation` 显示的提示的回答
+    fn on_confirm(&mut self, _editor: &mut Editor, _answer: ConfirmAnswer) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// 插件提供的按键映射，两侧使用与 `:map` 相同的按键记法（可以使用 `<Leader>`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginKeymap {
+    pub mode: MapMode,
+    pub lhs: &'static str,
+    pub rhs: &'static str,
+}
+
+impl PluginKeymap {
+    pub fn new(mode: MapMode, lhs: &'static str, rhs: &'static str) -> Self {
+        Self { mode, lhs, rhs }
+    }
+}
+
+/// 注册的插件和它们提供的命令
+#[derive(Default)]
+pub struct Plugins {
+    /// 按注册顺序；执行中的插件被取出，位置为 None
+    plugins: Vec<Option<Box<dyn Plugin>>>,
+    /// 插件提供的命令
+    commands: Vec<CommandSpec>,
+    /// commands 中每个命令所属的插件（plugins 中的下标）
+    owners: Vec<usize>,
+    /// 插件名，与 plugins 的下标对应（包括执行中的插件）
+    registered: Vec<&'static str>,
+    /// 插件的类型，与 plugins 的下标对应
+    types: Vec<TypeId>,
+}
+
+impl Plugins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// 注册的插件名，执行中的插件不包含在内
+    pub fn names(&self) -> Vec<&'static str> {
+        self.plugins.iter().flatten().map(|plugin| plugin.name()).collect()
+    }
+
+    /// 是否注册了名为 `name` 的插件（包括执行中的插件）
+    pub fn contains(&self, name: &str) -> bool {
+        self.registered.contains(&name)
+    }
+
+    /// 插件提供的所有命令
+    pub fn commands(&self) -> &[CommandSpec] {
+        &self.commands
+    }
+
+    /// 类型为 `T` 的插件，没有注册或正在执行时为 None
+    pub fn get<T: Plugin>(&self) -> Option<&T> {
+        self.plugins
+            .iter()
+            .flatten()
+            .find_map(|plugin| (plugin.as_ref() as &dyn Any).downcast_ref::<T>())
+    }
+
+    /// 类型为 `type_id` 的插件的下标（包括执行中的插件）
+    pub fn position(&self, type_id: TypeId) -> Option<usize> {
+        self.types.iter().position(|id| *id == type_id)
+    }
+
+    /// 提供命令 `name`（完整的命令名）的插件
+    pub fn command_owner(&self, name: &str) -> Option<usize> {
+        let index = self.commands.iter().position(|spec| spec.name == name)?;
+        Some(self.owners[index])
+    }
+
+    /// 添加插件和它的命令，返回插件的下标
+    pub fn add(&mut self, plugin: Box<dyn Plugin>, commands: Vec<CommandSpec>) -> usize {
+        let index = self.plugins.len();
+        self.owners.extend(std::iter::repeat_n(index, commands.len()));
+        self.commands.extend(commands);
+        self.registered.push(plugin.name());
+        self.types.push((plugin.as_ref() as &dyn Any).type_id());
+        self.plugins.push(Some(plugin));
+        index
+    }
+
+    /// 取出插件以便调用它（调用需要 `&mut Editor`），正在执行时返回 None
+    pub fn take(&mut self, index: usize) -> Option<Box<dyn Plugin>> {
+        self.plugins.get_mut(index)?.take()
+    }
+
+    /// 放回 take 取出的插件
+    pub fn restore(&mut self, index: usize, plugin: Box<dyn Plugin>) {
+        self.plugins[index] = Some(plugin);
+    }
+}
+
+/// `Editor::new` 注册的内置插件：Git、语言服务器、AI
+pub fn builtin_plugins() -> Vec<Box<dyn Plugin>> {
+    vec![
+        Box::new(GitPlugin::default()),
+        Box::new(LspPlugin::default()),
+        Box::new(AiPlugin::default()),
+    ]
+}
@@ -0,0 +1,99 @@
+//! 文件的上次光标位置模块（类似 Vim 的 `'"` 标记）
+//!
+//! 退出和删除缓冲区时把文件的光标位置写入状态文件，再次打开同一个文件时回到上次的位置：
+//! - 状态文件默认为 `$XDG_STATE_HOME/aivim/positions`（未设置时为 `~/.local/state/aivim/positions`）
+//! - 每行一个文件：`行 列 文件绝对路径`，行和列从 0 开始，最近编辑的文件在前
+//! - 最多保留 `MAX_POSITIONS` 个文件，更早的被丢弃
+//! - 写入前先读取状态文件，合并同时运行的其他编辑器写入的位置
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 最多记住位置的文件数
+pub const MAX_POSITIONS: usize = 100;
+
+/// 默认的状态文件
+pub fn default_positions_path() -> PathBuf {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+        .unwrap_or_else(std::env::temp_dir);
+    state_home.join("aivim").join("positions")
+}
+
+/// 状态文件中记录的文件位置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilePositions {
+    /// (文件绝对路径, 行, 列)，最近的在前
+    entries: Vec<(PathBuf, usize, usize)>,
+}
+
+/// 状态文件中使用的路径
+fn key(file: &Path) -> PathBuf {
+    fs::canonicalize(file).unwrap_or_else(|_| crate::watch::absolute(file))
+}
+
+impl FilePositions {
+    /// 解析状态文件的内容，忽略格式错误的行
+    pub fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ' ');
+                let line = parts.next()?.parse().ok()?;
+                let column = parts.next()?.parse().ok()?;
+                let path = parts.next().filter(|path| !path.is_empty())?;
+                Some((PathBuf::from(path), line, column))
+            })
+            .take(MAX_POSITIONS)
+            .collect();
+        Self { entries }
+    }
+
+    /// 读取状态文件，文件不存在或无法读取时为空
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path).map(|text| Self::parse(&text)).unwrap_or_default()
+    }
+
+    pub fn format(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(path, line, column)| format!("{} {} {}\n", line, column, path.display()))
+            .collect()
+    }
+
+    /// 写入状态文件，目录不存在时创建
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.format())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 文件上次的光标位置 (行, 列)
+    pub fn get(&self, file: &Path) -> Option<(usize, usize)> {
+        let file = key(file);
+        self.entries
+            .iter()
+            .find(|(path, _, _)| *path == file)
+            .map(|(_, line, column)| (*line, *column))
+    }
+
+    /// 记录文件的光标位置，文件移到最前面
+    pub fn set(&mut self, file: &Path, line: usize, column: usize) {
+        let file = key(file);
+        self.entries.retain(|(path, _, _)| *path != file);
+        self.entries.insert(0, (file, line, column));
+        self.entries.truncate(MAX_POSITIONS);
+    }
+}
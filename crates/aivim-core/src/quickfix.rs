@@ -0,0 +1,238 @@
+//! Quickfix 列表模块
+//!
+//! 保存一组文件位置（文件、行、列、说明），如 :grep 的搜索结果、:make 的编译错误：
+//! - :cnext / :cprev 按顺序跳转，到头后报错而不是循环
+//! - :copen 在屏幕底部打开列表面板，回车跳转到选中的位置，:cclose 关闭
+//! - 每个窗口还可以有自己的位置列表（location list），如诊断信息，用 :lnext / :lopen 等命令操作
+//!
+//! `parse_errors` 从编译器输出中提取位置，支持的格式：
+//! - `file:line:col: error: msg`、`file:line: msg`（gcc、clang、grep -n 等）
+//! - rustc 的 `error[E0425]: msg` 加下一行的 `--> file:line:col`
+
+use std::path::PathBuf;
+
+/// Quickfix 列表中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickfixEntry {
+    pub path: PathBuf,
+    /// 行、列（从 0 开始，列为字符索引）
+    pub line: usize,
+    pub column: usize,
+    /// 说明文字，如匹配行的内容
+    pub text: String,
+    /// 类型（错误、警告等），没有时为 None
+    pub kind: Option<QuickfixKind>,
+}
+
+impl QuickfixEntry {
+    pub fn new(path: impl Into<PathBuf>, line: usize, column: usize, text: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            line,
+            column,
+            text: text.into(),
+            kind: None,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: QuickfixKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+}
+
+/// 项的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickfixKind {
+    Error,
+    Warning,
+    Info,
+    Note,
+}
+
+impl QuickfixKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuickfixKind::Error => "error",
+            QuickfixKind::Warning => "warning",
+            QuickfixKind::Info => "info",
+            QuickfixKind::Note => "note",
+        }
+    }
+
+    /// 按编译器输出中的写法解析
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "error" | "fatal error" => Some(QuickfixKind::Error),
+            "warning" => Some(QuickfixKind::Warning),
+            "info" => Some(QuickfixKind::Info),
+            "note" | "help" => Some(QuickfixKind::Note),
+            _ => None,
+        }
+    }
+}
+
+/// 列表的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    /// 全局的 Quickfix 列表
+    Quickfix,
+    /// 当前窗口的位置列表
+    Location,
+}
+
+/// Quickfix 列表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuickfixList {
+    /// 产生列表的命令，如 `:grep foo`
+    title: String,
+    entries: Vec<QuickfixEntry>,
+    /// 当前项的索引
+    current: usize,
+}
+
+impl QuickfixList {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            entries: Vec::new(),
+            current: 0,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn entries(&self) -> &[QuickfixEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = QuickfixEntry>) {
+        self.entries.extend(entries);
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    pub fn current(&self) -> Option<&QuickfixEntry> {
+        self.entries.get(self.current)
+    }
+
+    /// 选择第 `index` 项，超出范围时选择最后一项
+    pub fn select(&mut self, index: usize) -> Option<&QuickfixEntry> {
+        self.current = index.min(self.entries.len().saturating_sub(1));
+        self.current()
+    }
+
+    /// 向后移动 `count` 项，已经是最后一项时返回 None
+    pub fn next(&mut self, count: usize) -> Option<&QuickfixEntry> {
+        if self.current + 1 >= self.entries.len() {
+            return None;
+        }
+        self.select(self.current.saturating_add(count))
+    }
+
+    /// 向前移动 `count` 项，已经是第一项时返回 None
+    pub fn previous(&mut self, count: usize) -> Option<&QuickfixEntry> {
+        if self.current == 0 || self.entries.is_empty() {
+            return None;
+        }
+        self.select(self.current.saturating_sub(count))
+    }
+}
+
+/// 从编译器等工具的输出中提取位置，不能识别的行被忽略
+///
+/// 输出中的行号和列号从 1 开始，没有列号时为行首
+pub fn parse_errors(output: &str) -> Vec<QuickfixEntry> {
+    let mut entries = Vec::new();
+    // rustc 格式中 `-->` 之前的说明
+    let mut pending: Option<(Option<QuickfixKind>, String)> = None;
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(location) = trimmed.strip_prefix("--> ") {
+            if let (Some((kind, text)), Some((path, line, column, _))) = (pending.take(), parse_location(location)) {
+                entries.push(make_entry(path, line, column, kind, text));
+            }
+            continue;
+        }
+        if let Some((path, line, column, rest)) = parse_location(line) {
+            let (kind, text) = split_kind(rest);
+            entries.push(make_entry(path, line, column, kind, text.to_string()));
+            pending = None;
+            continue;
+        }
+        pending = match split_kind(trimmed) {
+            (Some(kind), text) => Some((Some(kind), text.to_string())),
+            (None, _) => None,
+        };
+    }
+    entries
+}
+
+fn make_entry(path: &str, line: usize, column: Option<usize>, kind: Option<QuickfixKind>, text: String) -> QuickfixEntry {
+    let column = column.unwrap_or(1);
+    QuickfixEntry {
+        path: PathBuf::from(path),
+        line: line.saturating_sub(1),
+        column: column.saturating_sub(1),
+        text,
+        kind,
+    }
+}
+
+/// 解析 `path:line[:col][:rest]`，路径中不能有空白
+fn parse_location(text: &str) -> Option<(&str, usize, Option<usize>, &str)> {
+    // 路径至少有一个字符，冒号后不是行号时（如 Windows 盘符 `C:`）继续找下一个冒号
+    for (index, _) in text.match_indices(':').filter(|(index, _)| *index > 0) {
+        let path = &text[..index];
+        if path.contains(char::is_whitespace) {
+            return None;
+        }
+        let Some((line, rest)) = split_number(&text[index + 1..]) else {
+            continue;
+        };
+        let rest = match rest.strip_prefix(':') {
+            Some(rest) => rest,
+            None if rest.is_empty() => rest,
+            None => continue,
+        };
+        return Some(match split_number(rest) {
+            Some((column, tail)) if tail.is_empty() || tail.starts_with(':') => {
+                (path, line, Some(column), tail.strip_prefix(':').unwrap_or(tail).trim())
+            }
+            _ => (path, line, None, rest.trim()),
+        });
+    }
+    None
+}
+
+/// 分出开头的十进制数，没有数字时返回 None
+fn split_number(text: &str) -> Option<(usize, &str)> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let number = text[..digits].parse().ok()?;
+    Some((number, &text[digits..]))
+}
+
+/// 分出说明开头的 `error:`、`warning[W01]:` 等类型
+fn split_kind(text: &str) -> (Option<QuickfixKind>, &str) {
+    let Some((head, rest)) = text.split_once(':') else {
+        return (None, text);
+    };
+    // 去掉错误代码，如 `error[E0425]`
+    let name = head.split_once('[').map_or(head, |(name, _)| name).trim();
+    match QuickfixKind::parse(name) {
+        Some(kind) => (Some(kind), rest.trim()),
+        None => (None, text),
+    }
+}
@@ -0,0 +1,131 @@
+//! 命令行范围解析模块
+//!
+//! 解析 Ex 命令前的行范围，如 `:10,20w`、`:%y a`、`:.,$-1s/a/b/`：
+//! - `N` 为第 N 行，`.` 为当前行，`$` 为最后一行，`%` 为整个文件
+//! - 地址后可以跟 `+N` / `-N` 偏移，单独的 `+` / `-` 表示 1
+//! - 省略的地址表示当前行（如 `,5`、`+3`）
+//! - :m / :t 的目标地址可以为 `0`，表示第一行之前
+//!
+//! 返回的行号从 0 开始。
+
+/// 闭区间行范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn single(line: usize) -> Self {
+        Self::new(line, line)
+    }
+
+    /// 范围内的行数
+    pub fn line_count(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+/// 范围解析错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    /// 行号超出文件或格式错误
+    Invalid,
+    /// 起始行在结束行之后
+    Backwards,
+}
+
+/// 解析命令开头的范围，返回范围和剩余的命令文本
+///
+/// `current` 为光标所在行，`last` 为最后一行；没有范围时返回 None
+pub fn parse_range(command: &str, current: usize, last: usize) -> Result<(Option<LineRange>, &str), RangeError> {
+    let command = command.trim_start();
+    if let Some(rest) = command.strip_prefix('%') {
+        return Ok((Some(LineRange::new(0, last)), rest.trim_start()));
+    }
+
+    let (first, rest) = parse_address(command, current, last)?;
+    let (second, rest) = match rest.strip_prefix(',') {
+        Some(rest) => {
+            let (second, rest) = parse_address(rest, current, last)?;
+            (Some(second.unwrap_or(current)), rest)
+        }
+        None => (None, rest),
+    };
+
+    let range = match (first, second) {
+        (None, None) => return Ok((None, command)),
+        (first, Some(second)) => LineRange::new(first.unwrap_or(current), second),
+        (Some(first), None) => LineRange::single(first),
+    };
+    if range.start > range.end {
+        return Err(RangeError::Backwards);
+    }
+    Ok((Some(range), rest.trim_start()))
+}
+
+/// 解析 :m / :t 的目标地址，返回插入位置：目标行的下一行（从 0 开始）
+///
+/// 目标为 `0` 时插入到第一行之前，返回 0
+pub fn parse_destination(text: &str, current: usize, last: usize) -> Result<usize, RangeError> {
+    let text = text.trim();
+    if !text.is_empty() && text.chars().all(|c| c == '0') {
+        return Ok(0);
+    }
+    match parse_address(text, current, last)? {
+        (Some(line), rest) if rest.trim().is_empty() => Ok(line + 1),
+        _ => Err(RangeError::Invalid),
+    }
+}
+
+/// 命令的范围是否只有地址 `0`：范围按第一行解析，`:0r` 等命令需要区分，插入到第一行之前
+pub fn is_zero_address(command: &str) -> bool {
+    let command = command.trim_start();
+    let digits = command.len() - command.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    digits > 0 && command[..digits].chars().all(|c| c == '0') && !command[digits..].starts_with([',', '+', '-'])
+}
+
+/// 解析一个地址（基准 + 偏移），没有地址时返回 None
+fn parse_address(text: &str, current: usize, last: usize) -> Result<(Option<usize>, &str), RangeError> {
+    let (base, mut rest) = if let Some(rest) = text.strip_prefix('.') {
+        (Some(current as i64), rest)
+    } else if let Some(rest) = text.strip_prefix('$') {
+        (Some(last as i64), rest)
+    } else {
+        match take_number(text) {
+            // 行号 0 按第一行处理
+            (Some(n), rest) => (Some(n.max(1) - 1), rest),
+            (None, rest) => (None, rest),
+        }
+    };
+
+    let mut line = base;
+    loop {
+        let sign = match rest.chars().next() {
+            Some('+') => 1,
+            Some('-') => -1,
+            _ => break,
+        };
+        let (n, after) = take_number(&rest[1..]);
+        line = Some(line.unwrap_or(current as i64) + sign * n.unwrap_or(1));
+        rest = after;
+    }
+
+    match line {
+        Some(line) if line < 0 || line > last as i64 => Err(RangeError::Invalid),
+        Some(line) => Ok((Some(line as usize), rest)),
+        None => Ok((None, rest)),
+    }
+}
+
+fn take_number(text: &str) -> (Option<i64>, &str) {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match text[..digits].parse() {
+        Ok(n) => (Some(n), &text[digits..]),
+        Err(_) => (None, text),
+    }
+}
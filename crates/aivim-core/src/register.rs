@@ -68,6 +68,8 @@ impl RegisterManager {
         readonly.insert(':', Register::empty(':'));
         // . 寄存器: 上次插入的文本
         readonly.insert('.', Register::empty('.'));
+        // = 寄存器: 上次求值的表达式
+        readonly.insert('=', Register::empty('='));
 
         Self {
             unnamed: Register::empty('"'),
@@ -90,6 +92,12 @@ impl RegisterManager {
             '0'..='9' => self.numbered.get(name.to_digit(10).unwrap() as usize).cloned(),
             'a'..='z' | 'A'..='Z' => self.named.get(&name.to_ascii_lowercase()).cloned(),
             '%' | '#' | ':' | '.' => self.readonly.get(&name).cloned(),
+            // 表达式寄存器：内容为上次表达式的求值结果
+            '=' => {
+                let expr = &self.readonly.get(&'=')?.content;
+                let value = crate::expr::evaluate(expr).ok()?;
+                Some(Register::new('=', value, false))
+            }
             '/' => Some(self.search.clone()),
             '*' | '+' => {
                 // 系统剪贴板寄存器
@@ -123,9 +131,16 @@ impl RegisterManager {
             'A'..='Z' => {
                 // 大写表示追加
                 let lower = name.to_ascii_lowercase();
-                if let Some(existing) = self.named.get_mut(&lower) {
+                if let Some(existing) = self.named.get_mut(&lower).filter(|r| !r.is_empty()) {
+                    // 整行与字符内容混合时用换行分隔，结果按整行处理
+                    if (linewise || existing.linewise) && !existing.content.ends_with('\n') {
+                        existing.content.push('\n');
+                    }
                     existing.content.push_str(&content);
-                    existing.linewise = linewise;
+                    if existing.linewise && !existing.content.ends_with('\n') {
+                        existing.content.push('\n');
+                    }
+                    existing.linewise |= linewise;
                 } else {
                     self.named.insert(lower, Register::new(lower, content, linewise));
                 }
@@ -1,28 +1,35 @@
-/// 替换功能模块
-///
-/// 实现Vim风格的替换命令：
-/// - :s/old/new - 替换当前行第一个匹配
-/// - :s/old/new/g - 替换当前行所有匹配
-/// - :%s/old/new/g - 替换整个文件所有匹配
+//! 替换功能模块
+//!
+//! 实现Vim风格的替换命令：
+//! - :s/old/new - 替换当前行第一个匹配
+//! - :s/old/new/g - 替换当前行所有匹配
+//! - :%s/old/new/g - 替换整个文件所有匹配
+//! - :%s/old//gn - 只统计匹配数，不修改缓冲区
+//! - 模式为空时使用上一次搜索的模式（如 `:%s///gn` 统计搜索的匹配数）
+//! - :s/old/new/gc - 逐个确认每个匹配：y 替换、n 跳过、a 替换这个和剩下的所有匹配、q 停止、l 替换这个之后停止
+
+use std::borrow::Cow;
 
-use crate::buffer::Buffer;
+use crate::buffer::{line_content_len, Buffer};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ReplaceResult {
     /// 替换次数
     pub count: usize,
-    /// 替换后的文本
-    pub new_text: String,
+    /// 最后一个发生替换的行，没有替换时为 None
+    pub last_line: Option<usize>,
 }
 
 /// 执行替换操作
 ///
+/// 逐行查找，只修改有匹配的行，不复制整个缓冲区
+///
 /// # 参数
 /// - `buffer`: 缓冲区
 /// - `pattern`: 要替换的模式
 /// - `replacement`: 替换内容
 /// - `global`: 是否替换所有匹配（g标志）
-/// - `line_range`: 行范围（None表示当前行，Some((start, end))表示范围）
+/// - `line_range`: 行范围（None表示整个文件，Some((start, end))表示 [start, end) 的行）
 pub fn replace_in_buffer(
     buffer: &mut Buffer,
     pattern: &str,
@@ -30,70 +37,77 @@ pub fn replace_in_buffer(
     global: bool,
     line_range: Option<(usize, usize)>,
 ) -> ReplaceResult {
+    let mut result = ReplaceResult::default();
     if pattern.is_empty() {
-        return ReplaceResult {
-            count: 0,
-            new_text: buffer.to_string(),
-        };
+        return result;
     }
 
-    let text = buffer.to_string();
-    let lines: Vec<&str> = text.lines().collect();
-    
     // 确定替换范围
-    let (start_line, end_line) = match line_range {
-        Some((start, end)) => (start, end),
-        None => (0, lines.len()), // None 表示整个文件
-    };
-
-    let mut new_lines = Vec::new();
-    let mut total_replacements = 0;
+    let (start_line, end_line) = line_range.unwrap_or((0, buffer.len_lines()));
 
-    for (line_idx, line) in lines.iter().enumerate() {
-        let new_line = if line_idx >= start_line && line_idx < end_line {
-            if global {
-                // 替换所有匹配
-                let count = line.matches(pattern).count();
-                total_replacements += count;
-                line.replace(pattern, replacement)
-            } else {
-                // 只替换第一个匹配
-                if let Some(pos) = line.find(pattern) {
-                    total_replacements += 1;
-                    let mut result = String::new();
-                    result.push_str(&line[..pos]);
-                    result.push_str(replacement);
-                    result.push_str(&line[pos + pattern.len()..]);
-                    result
-                } else {
-                    line.to_string()
-                }
-            }
+    for line_idx in start_line..end_line.min(buffer.len_lines()) {
+        let Some(line) = buffer.line(line_idx) else {
+            break;
+        };
+        let content_len = line_content_len(line);
+        let text: Cow<str> = line.slice(..content_len).into();
+        let (new_line, count) = if global {
+            // 替换所有匹配
+            (text.replace(pattern, replacement), text.matches(pattern).count())
         } else {
-            line.to_string()
+            // 只替换第一个匹配
+            match text.find(pattern) {
+                Some(pos) => (format!("{}{}{}", &text[..pos], replacement, &text[pos + pattern.len()..]), 1),
+                None => continue,
+            }
         };
-        new_lines.push(new_line);
-    }
-
-    let new_text = new_lines.join("\n") + "\n";
+        if count == 0 {
+            continue;
+        }
 
-    // 保存文件路径
-    let file_path = buffer.file_path().map(|p| p.to_path_buf());
-    
-    // 更新缓冲区
-    let buffer_id = buffer.id();
-    *buffer = crate::buffer::Buffer::new(buffer_id);
-    buffer.insert(0, &new_text);
-    
-    // 恢复文件路径
-    if let Some(path) = file_path {
-        buffer.set_file_path(path);
+        let line_start = buffer.line_to_char(line_idx);
+        buffer.remove(line_start, content_len);
+        buffer.insert(line_start, &new_line);
+        result.count += count;
+        result.last_line = Some(line_idx);
     }
+    result
+}
 
-    ReplaceResult {
-        count: total_replacements,
-        new_text,
+/// 统计范围内的匹配（n 标志），返回（匹配数, 有匹配的行数）；`global` 为 false 时每行只算第一个匹配
+///
+/// `line_range` 与 `replace_in_buffer` 相同，为 [start, end) 的行
+pub fn count_matches(buffer: &Buffer, pattern: &str, global: bool, line_range: (usize, usize)) -> (usize, usize) {
+    if pattern.is_empty() {
+        return (0, 0);
+    }
+    let (mut count, mut lines) = (0, 0);
+    for line_idx in line_range.0..line_range.1.min(buffer.len_lines()) {
+        let Some(text) = buffer.line_text(line_idx) else {
+            break;
+        };
+        let matches = if global { text.matches(pattern).count() } else { usize::from(text.contains(pattern)) };
+        if matches > 0 {
+            count += matches;
+            lines += 1;
+        }
     }
+    (count, lines)
+}
+
+/// 解析后的替换命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubstituteCommand {
+    pub pattern: String,
+    pub replacement: String,
+    /// g 标志：替换行内所有匹配
+    pub global: bool,
+    /// c 标志：替换前逐个确认
+    pub confirm: bool,
+    /// n 标志：只统计匹配数，不替换
+    pub count_only: bool,
+    /// 命令以 `%` 开头（整个文件）
+    pub full_file: bool,
 }
 
 /// 解析替换命令
@@ -102,7 +116,9 @@ pub fn replace_in_buffer(
 /// - :s/old/new
 /// - :s/old/new/g
 /// - :%s/old/new/g
-pub fn parse_substitute_command(command: &str) -> Option<(String, String, bool, bool)> {
+/// - :s/old/new/gc
+/// - :%s/old//gn
+pub fn parse_substitute_command(command: &str) -> Option<SubstituteCommand> {
     // 移除开头的 ':'（如果存在）和 's/'
     let content = command.strip_prefix(':').unwrap_or(command);
     
@@ -114,23 +130,112 @@ pub fn parse_substitute_command(command: &str) -> Option<(String, String, bool,
         content.strip_prefix("s/")?
     };
 
-    let global = content.ends_with("/g");
-    let content = if global {
-        &content[..content.len() - 2]
-    } else {
-        content
-    };
-
-    // 找到分隔符位置
-    let parts: Vec<&str> = content.splitn(2, '/').collect();
-    if parts.len() != 2 {
+    // 找到分隔符位置：模式 / 替换文本 [/ 标志]
+    let parts: Vec<&str> = content.splitn(3, '/').collect();
+    if parts.len() < 2 {
+        return None;
+    }
+    let flags = parts.get(2).copied().unwrap_or_default();
+    if !flags.chars().all(|flag| matches!(flag, 'g' | 'c' | 'n')) {
         return None;
     }
 
-    let pattern = parts[0].to_string();
-    let replacement = parts[1].to_string();
+    Some(SubstituteCommand {
+        pattern: parts[0].to_string(),
+        replacement: parts[1].to_string(),
+        global: flags.contains('g'),
+        confirm: flags.contains('c'),
+        count_only: flags.contains('n'),
+        full_file,
+    })
+}
+
+/// 逐个确认的替换（c 标志）：当前候选匹配的位置和已经替换的次数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmSubstitute {
+    pub pattern: String,
+    pub replacement: String,
+    pub global: bool,
+    /// 替换范围的最后一行
+    pub last_line: usize,
+    /// 当前候选匹配（行, 行内字符列）
+    pub line: usize,
+    pub column: usize,
+    /// 已经替换的次数
+    pub count: usize,
+}
+
+impl ConfirmSubstitute {
+    /// 在 `[first_line, last_line]` 中查找第一个匹配，没有匹配时为 None
+    pub fn start(command: SubstituteCommand, buffer: &Buffer, first_line: usize, last_line: usize) -> Option<Self> {
+        let mut state = Self {
+            pattern: command.pattern,
+            replacement: command.replacement,
+            global: command.global,
+            last_line,
+            line: first_line,
+            column: 0,
+            count: 0,
+        };
+        let (line, column) = state.find_from(buffer, first_line, 0)?;
+        state.line = line;
+        state.column = column;
+        Some(state)
+    }
+
+    /// 当前候选匹配的列范围 [start, end)（字符）
+    pub fn columns(&self) -> (usize, usize) {
+        (self.column, self.column + self.pattern.chars().count())
+    }
+
+    /// 从 `line` 行的 `column` 列开始查找下一个匹配
+    fn find_from(&self, buffer: &Buffer, line: usize, column: usize) -> Option<(usize, usize)> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        for line_idx in line..=self.last_line.min(buffer.len_lines().saturating_sub(1)) {
+            let text = buffer.line_text(line_idx)?;
+            let start = if line_idx == line {
+                text.char_indices().nth(column).map_or(text.len(), |(index, _)| index)
+            } else {
+                0
+            };
+            if let Some(pos) = text[start..].find(&self.pattern) {
+                return Some((line_idx, text[..start + pos].chars().count()));
+            }
+        }
+        None
+    }
+
+    /// 替换当前候选匹配
+    pub fn replace(&mut self, buffer: &mut Buffer) {
+        let start = buffer.line_to_char(self.line) + self.column;
+        buffer.remove(start, self.pattern.chars().count());
+        buffer.insert(start, &self.replacement);
+        self.count += 1;
+    }
 
-    Some((pattern, replacement, global, full_file))
+    /// 移到下一个候选匹配：没有 g 标志时跳到下一行，`replaced` 时从替换后的文本之后查找；没有更多匹配时返回 false
+    pub fn advance(&mut self, buffer: &Buffer, replaced: bool) -> bool {
+        let (line, column) = if !self.global {
+            (self.line + 1, 0)
+        } else if replaced {
+            (self.line, self.column + self.replacement.chars().count())
+        } else {
+            (self.line, self.column + self.pattern.chars().count())
+        };
+        if line > self.last_line {
+            return false;
+        }
+        match self.find_from(buffer, line, column) {
+            Some((line, column)) => {
+                self.line = line;
+                self.column = column;
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -146,7 +251,7 @@ mod tests {
         let result = replace_in_buffer(&mut buffer, "hello", "hi", false, None);
 
         assert_eq!(result.count, 1);
-        assert!(result.new_text.contains("hi world hello"));
+        assert_eq!(buffer.to_string(), "hi world hello\n");
     }
 
     #[test]
@@ -157,18 +262,19 @@ mod tests {
         let result = replace_in_buffer(&mut buffer, "hello", "hi", true, None);
 
         assert_eq!(result.count, 2);
-        assert!(result.new_text.contains("hi world hi"));
+        assert_eq!(buffer.to_string(), "hi world hi\n");
     }
 
     #[test]
     fn test_parse_substitute() {
-        let result = parse_substitute_command(":s/old/new");
-        assert_eq!(result, Some(("old".to_string(), "new".to_string(), false, false)));
+        let result = parse_substitute_command(":s/old/new").unwrap();
+        assert_eq!((result.pattern.as_str(), result.replacement.as_str()), ("old", "new"));
+        assert!(!result.global && !result.full_file);
 
-        let result = parse_substitute_command(":s/old/new/g");
-        assert_eq!(result, Some(("old".to_string(), "new".to_string(), true, false)));
+        let result = parse_substitute_command(":s/old/new/g").unwrap();
+        assert!(result.global && !result.confirm);
 
-        let result = parse_substitute_command(":%s/old/new/g");
-        assert_eq!(result, Some(("old".to_string(), "new".to_string(), true, true)));
+        let result = parse_substitute_command(":%s/old/new/gc").unwrap();
+        assert!(result.global && result.confirm && result.full_file);
     }
 }
@@ -0,0 +1,75 @@
+//! 脚本接口 (Editor::run_script)
+//!
+//! 不需要终端，依次执行一组 Ex 命令和 Normal 模式按键，供工具和测试以程序方式驱动编辑器：
+//! - 以 `:` 开头的一步是 Ex 命令（不需要结尾的 `<CR>`），如 `":%s/a/b/g"`
+//! - 其他的一步是 Normal 模式的按键序列，按映射的表示法书写，如 `"ggdd"`、`"ihello<Esc>"`，
+//!   按键会展开用户映射，可以进入其他模式；一步结束时还在等待映射后续按键的按键按超时处理
+//! - 每一步记录错误、消息栏、模式和光标位置；命令要求退出（`:q`、`:wq`）后不再执行后面的步骤
+//! - 一步启动的外部命令（`:%!sort`、`:r !cmd`）在这一步结束前等待完成，下一步可以看到它的输出；
+//!   命令失败或超时没有结束时作为这一步的错误
+//!
+//! 需要确认的命令（如 `:w` 覆盖外部修改过的文件）留在等待确认的状态，下一步的按键 `y` 确认，其他按键取消。
+
+use crate::cursor::Cursor;
+use crate::mapping::{self, MapKey};
+use crate::mode::Mode;
+
+/// 脚本中的一步
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptInput {
+    /// Ex 命令（不含开头的 `:`）
+    Command(String),
+    /// Normal 模式的按键
+    Keys(Vec<MapKey>),
+}
+
+impl ScriptInput {
+    /// 解析脚本中的一步：`:` 开头的是 Ex 命令，其余按按键表示法解析
+    pub fn parse(step: &str) -> Self {
+        match step.strip_prefix(':') {
+            Some(command) => ScriptInput::Command(command.to_string()),
+            None => ScriptInput::Keys(mapping::parse_keys(step)),
+        }
+    }
+}
+
+/// 一步的执行结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptStep {
+    /// 脚本中的原文
+    pub input: String,
+    /// Ex 命令返回的错误；按键没有返回值，按键引起的错误显示在 `message` 中
+    pub error: Option<String>,
+    /// 执行后消息栏的内容（这一步没有产生消息时为 None）
+    pub message: Option<String>,
+    /// 执行后的模式
+    pub mode: Mode,
+    /// 执行后的光标位置
+    pub cursor: Cursor,
+}
+
+/// 脚本的执行结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptResult {
+    /// 已执行的步骤，命令要求退出时后面的步骤不执行，也不在这里
+    pub steps: Vec<ScriptStep>,
+    /// 是否有命令要求退出编辑器
+    pub quit: bool,
+}
+
+impl ScriptResult {
+    /// 所有 Ex 命令都没有出错
+    pub fn is_ok(&self) -> bool {
+        self.steps.iter().all(|step| step.error.is_none())
+    }
+
+    /// 第一个出错的步骤
+    pub fn first_error(&self) -> Option<&ScriptStep> {
+        self.steps.iter().find(|step| step.error.is_some())
+    }
+
+    /// 最后一步执行后消息栏的内容
+    pub fn last_message(&self) -> Option<&str> {
+        self.steps.last().and_then(|step| step.message.as_deref())
+    }
+}
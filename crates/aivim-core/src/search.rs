@@ -1,13 +1,25 @@
-/// 搜索功能模块
-/// 
-/// 实现Vim风格的搜索功能：
-/// - /pattern - 向前搜索
-/// - ?pattern - 向后搜索
-/// - n - 下一个匹配
-/// - N - 上一个匹配
-
-use crate::buffer::Buffer;
+//! 搜索功能模块
+//! 
+//! 实现Vim风格的搜索功能：
+//! - /pattern - 向前搜索
+//! - ?pattern - 向后搜索
+//! - n - 下一个匹配
+//! - N - 上一个匹配
+//! - /pattern/e、/pattern/+1 等 - 搜索偏移（见 `SearchOffset`），n / N 沿用同样的偏移
+//! - n 沿搜索方向跳到下一个匹配，N 反方向；光标在当前匹配的落点上时按匹配的顺序前后移动
+//!
+//! 匹配按位置保存在平衡树中（见 match_index 模块），n / N 二分查找；缓冲区修改之后按缓冲区的修改记录
+//! （见 edit_log 模块）只重新查找修改过的行，之后的匹配整体移动，不重新扫描整个缓冲区
+//! - * / # - 向前 / 向后搜索光标下的单词，模式为 `\<word\>`（`\<` 和 `\>` 表示单词边界）
+//! - g* / g# - 同上，但不要求单词边界
+
+use std::borrow::Cow;
+
+use crate::buffer::{Buffer, BufferId};
 use crate::cursor::Cursor;
+use crate::motion::is_word_char;
+use crate::edit_log::TextEdit;
+use crate::match_index::MatchIndex;
 
 #[derive(Debug, Clone)]
 pub struct SearchState {
@@ -16,9 +28,13 @@ pub struct SearchState {
     /// 搜索方向
     pub direction: SearchDirection,
     /// 所有匹配位置（字符索引）
-    pub matches: Vec<usize>,
+    pub matches: MatchIndex,
     /// 当前匹配的索引
     pub current_match: Option<usize>,
+    /// 跳到匹配后光标相对匹配的位置
+    pub offset: SearchOffset,
+    /// 匹配对应的缓冲区和它的 changedtick
+    indexed: Option<(BufferId, u64)>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -27,13 +43,79 @@ pub enum SearchDirection {
     Backward, // ?
 }
 
+impl SearchDirection {
+    /// 命令行中分隔模式和偏移的字符
+    pub fn delimiter(&self) -> char {
+        match self {
+            SearchDirection::Forward => '/',
+            SearchDirection::Backward => '?',
+        }
+    }
+}
+
+/// 搜索偏移：模式之后用分隔符隔开，如 `/foo/e`、`?foo?-1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchOffset {
+    /// 匹配的开头
+    #[default]
+    None,
+    /// 匹配所在行向下（负数向上）第 n 行的行首：`+1`、`-2`、`3`
+    Line(isize),
+    /// 匹配开头向右（负数向左）第 n 个字符：`s+1`、`b-1`
+    Start(isize),
+    /// 匹配最后一个字符向右（负数向左）第 n 个字符：`e`、`e-1`
+    End(isize),
+}
+
+impl SearchOffset {
+    /// 解析分隔符之后的偏移，格式不对时为 None
+    pub fn parse(text: &str) -> Option<Self> {
+        // `+` / `-` 之后没有数字时为 1
+        let number = |text: &str| -> Option<isize> {
+            match text {
+                "" => Some(0),
+                "+" => Some(1),
+                "-" => Some(-1),
+                _ => text.strip_prefix('+').unwrap_or(text).parse().ok(),
+            }
+        };
+        match text.chars().next() {
+            None => Some(SearchOffset::None),
+            Some('e') => number(&text[1..]).map(SearchOffset::End),
+            Some('s' | 'b') => number(&text[1..]).map(SearchOffset::Start),
+            Some(_) => number(text).map(SearchOffset::Line),
+        }
+    }
+}
+
+/// 把 `/` 或 `?` 之后输入的文本分成模式和偏移：模式到第一个没有转义的分隔符为止，
+/// 模式中的 `\/`（`?` 搜索中的 `\?`）表示分隔符本身
+pub fn split_search_input(input: &str, delimiter: char) -> (String, &str) {
+    let mut pattern = String::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((idx, ch)) = chars.next() {
+        if ch == delimiter {
+            return (pattern, &input[idx + ch.len_utf8()..]);
+        }
+        if ch == '\\' && chars.peek().is_some_and(|&(_, next)| next == delimiter) {
+            pattern.push(delimiter);
+            chars.next();
+            continue;
+        }
+        pattern.push(ch);
+    }
+    (pattern, "")
+}
+
 impl SearchState {
     pub fn new() -> Self {
         Self {
             pattern: String::new(),
             direction: SearchDirection::Forward,
-            matches: Vec::new(),
+            matches: MatchIndex::new(),
             current_match: None,
+            offset: SearchOffset::None,
+            indexed: None,
         }
     }
 
@@ -45,28 +127,116 @@ impl SearchState {
     pub fn set_pattern(&mut self, pattern: impl Into<String>, direction: SearchDirection, buffer: &Buffer) {
         self.pattern = pattern.into();
         self.direction = direction;
+        self.offset = SearchOffset::None;
         self.find_all_matches(buffer);
     }
 
+    /// 缓冲区修改后更新匹配（n / N 之前调用，状态栏的匹配计数不会过时），保留当前匹配的索引
+    ///
+    /// 缓冲区没有变化时什么也不做；不跨行的模式只重新查找修改过的行，修改记录不完整时重新查找整个缓冲区
+    pub fn refresh(&mut self, buffer: &Buffer) {
+        let state = (buffer.id(), buffer.changedtick());
+        if self.pattern.is_empty() || self.indexed == Some(state) {
+            return;
+        }
+        let current = self.current_match;
+        let edit = match self.indexed {
+            Some((id, tick)) if id == buffer.id() => buffer.edits_since(tick),
+            _ => None,
+        };
+        match edit {
+            Some(None) => {}
+            Some(Some(edit)) if !self.pattern.contains('\n') => self.update_edited_lines(buffer, edit),
+            _ => self.find_all_matches(buffer),
+        }
+        self.indexed = Some(state);
+        self.current_match = current.filter(|&idx| idx < self.matches.len());
+    }
+
+    /// 按一处修改更新匹配：丢掉修改所在各行原来的匹配，整体移动之后的匹配，再在修改后的这些行中查找
+    fn update_edited_lines(&mut self, buffer: &Buffer, edit: TextEdit) {
+        let first_line = buffer.char_to_line(edit.start.min(buffer.len_chars()));
+        let last_line = buffer.char_to_line(edit.new_end().min(buffer.len_chars()));
+        let start = buffer.line_to_char(first_line);
+        let end = if last_line + 1 < buffer.len_lines() {
+            buffer.line_to_char(last_line + 1)
+        } else {
+            buffer.len_chars()
+        };
+        // 这些行之后的文本没有变化，修改前的位置为 end - inserted + removed
+        let old_end = end - edit.inserted + edit.removed;
+        let (literal, word_start, word_end) = split_word_boundaries(&self.pattern);
+        let found = find_in_lines(buffer, first_line..last_line + 1, literal, word_start, word_end);
+        let delta = edit.inserted as isize - edit.removed as isize;
+        self.matches.replace(start..old_end, delta, found);
+    }
+
+    /// 跳到第 `idx` 个匹配时光标的位置（应用偏移）
+    pub fn landing(&self, idx: usize, buffer: &Buffer) -> Option<Cursor> {
+        let start = self.matches.get(idx)?;
+        let last_char = buffer.len_chars().saturating_sub(1);
+        let char_at = |pos: isize| Cursor::from_char_idx(buffer, pos.clamp(0, last_char as isize) as usize);
+        Some(match self.offset {
+            SearchOffset::None => Cursor::from_char_idx(buffer, start),
+            SearchOffset::Start(n) => char_at(start as isize + n),
+            SearchOffset::End(n) => char_at((start + self.pattern_len()) as isize - 1 + n),
+            SearchOffset::Line(n) => {
+                let line = buffer.char_to_line(start) as isize + n;
+                Cursor::new(line.clamp(0, buffer.len_lines().saturating_sub(1) as isize) as usize, 0)
+            }
+        })
+    }
+
+    /// n（`reverse` 为 false）或 N 跳到的匹配：n 沿搜索方向，N 反方向。
+    /// 光标在当前匹配的落点上时按匹配的顺序移到相邻的匹配，否则找落点在光标之后（之前）的第一个匹配，到头时回绕
+    pub fn step(&self, cursor: &Cursor, buffer: &Buffer, reverse: bool) -> Option<usize> {
+        let len = self.matches.len();
+        if len == 0 {
+            return None;
+        }
+        let forward = (self.direction == SearchDirection::Forward) != reverse;
+        let pos = cursor.to_char_idx(buffer);
+        let landing = |idx: usize| self.landing(idx, buffer).map_or(0, |cursor| cursor.to_char_idx(buffer));
+        if let Some(current) = self.current_match.filter(|&idx| landing(idx) == pos) {
+            return Some(if forward { (current + 1) % len } else { (current + len - 1) % len });
+        }
+        // 落点随匹配的位置单调不减，二分查找；没有偏移时落点就是匹配的位置，直接在树中查找
+        let no_offset = self.offset == SearchOffset::None;
+        if forward {
+            let after = if no_offset {
+                self.matches.count_before(pos + 1)
+            } else {
+                partition_point(len, |idx| landing(idx) <= pos)
+            };
+            Some(if after < len { after } else { 0 })
+        } else {
+            let before = if no_offset {
+                self.matches.count_before(pos)
+            } else {
+                partition_point(len, |idx| landing(idx) < pos)
+            };
+            Some(before.checked_sub(1).unwrap_or(len - 1))
+        }
+    }
+
     /// 查找所有匹配位置
     fn find_all_matches(&mut self, buffer: &Buffer) {
         self.matches.clear();
         self.current_match = None;
+        self.indexed = Some((buffer.id(), buffer.changedtick()));
 
         if self.pattern.is_empty() {
             return;
         }
 
-        let text = buffer.to_string();
-        let pattern = &self.pattern;
-
-        // 简单的字符串匹配（后续可以升级为正则表达式）
-        let mut start = 0;
-        while let Some(pos) = text[start..].find(pattern) {
-            let absolute_pos = start + pos;
-            self.matches.push(absolute_pos);
-            start = absolute_pos + 1;
-        }
+        // 简单的字符串匹配（后续可以升级为正则表达式），逐行查找，不复制整个缓冲区
+        let (literal, word_start, word_end) = split_word_boundaries(&self.pattern);
+        let matches = if literal.contains('\n') {
+            find_multiline(buffer, literal)
+        } else {
+            find_in_lines(buffer, 0..buffer.len_lines(), literal, word_start, word_end)
+        };
+        self.matches = matches.into_iter().collect();
     }
 
     /// 计算下一个匹配的索引（用于 n 命令）
@@ -81,14 +251,14 @@ impl SearchState {
         match self.direction {
             SearchDirection::Forward => {
                 // 正向搜索：找当前位置之后的第一个匹配
-                self.matches.iter()
-                    .position(|&m| m > current_char_idx)
-                    .or_else(|| if !self.matches.is_empty() { Some(0) } else { None })
+                Some(self.matches.count_before(current_char_idx + 1))
+                    .filter(|&idx| idx < self.matches.len())
+                    .or(if !self.matches.is_empty() { Some(0) } else { None })
             }
             SearchDirection::Backward => {
                 // 反向搜索：找当前位置之前的第一个匹配
-                self.matches.iter()
-                    .rposition(|&m| m < current_char_idx)
+                self.matches.count_before(current_char_idx)
+                    .checked_sub(1)
                     .or_else(|| if !self.matches.is_empty() { Some(self.matches.len() - 1) } else { None })
             }
         }
@@ -105,14 +275,14 @@ impl SearchState {
         match self.direction {
             SearchDirection::Forward => {
                 // 正向搜索：找当前位置或之后的第一个匹配
-                self.matches.iter()
-                    .position(|&m| m >= current_char_idx)
-                    .or_else(|| if !self.matches.is_empty() { Some(0) } else { None })
+                Some(self.matches.count_before(current_char_idx))
+                    .filter(|&idx| idx < self.matches.len())
+                    .or(if !self.matches.is_empty() { Some(0) } else { None })
             }
             SearchDirection::Backward => {
                 // 反向搜索：找当前位置或之前的第一个匹配
-                self.matches.iter()
-                    .rposition(|&m| m <= current_char_idx)
+                self.matches.count_before(current_char_idx + 1)
+                    .checked_sub(1)
                     .or_else(|| if !self.matches.is_empty() { Some(self.matches.len() - 1) } else { None })
             }
         }
@@ -129,21 +299,21 @@ impl SearchState {
         // 与 calc_next_match 相反
         match self.direction {
             SearchDirection::Forward => {
-                self.matches.iter()
-                    .rposition(|&m| m < current_char_idx)
+                self.matches.count_before(current_char_idx)
+                    .checked_sub(1)
                     .or_else(|| if self.matches.len() > 1 { Some(self.matches.len() - 1) } else { None })
             }
             SearchDirection::Backward => {
-                self.matches.iter()
-                    .position(|&m| m > current_char_idx)
-                    .or_else(|| if self.matches.len() > 1 { Some(0) } else { None })
+                Some(self.matches.count_before(current_char_idx + 1))
+                    .filter(|&idx| idx < self.matches.len())
+                    .or(if self.matches.len() > 1 { Some(0) } else { None })
             }
         }
     }
 
     /// 获取指定索引匹配的位置
     pub fn get_match_pos(&self, idx: usize) -> Option<usize> {
-        self.matches.get(idx).copied()
+        self.matches.get(idx)
     }
 
     /// 设置当前匹配索引
@@ -151,27 +321,130 @@ impl SearchState {
         self.current_match = Some(idx);
     }
 
-    /// 获取当前高亮的匹配范围（用于UI显示）
+    /// 获取当前高亮的匹配范围（用于UI显示），范围为字符索引
     pub fn current_match_range(&self) -> Option<(usize, usize)> {
-        self.current_match.map(|idx| {
-            let start = self.matches[idx];
-            let end = start + self.pattern.len();
-            (start, end)
-        })
+        let start = self.matches.get(self.current_match?)?;
+        Some((start, start + self.pattern_len()))
     }
 
-    /// 获取所有匹配范围（用于UI高亮）
+    /// 获取所有匹配范围（用于UI高亮），范围为字符索引
     pub fn all_match_ranges(&self) -> Vec<(usize, usize)> {
-        self.matches.iter()
-            .map(|&start| (start, start + self.pattern.len()))
+        let len = self.pattern_len();
+        self.matches.to_vec().into_iter()
+            .map(|start| (start, start + len))
             .collect()
     }
 
+    /// 模式的字符数（匹配的长度）
+    fn pattern_len(&self) -> usize {
+        split_word_boundaries(&self.pattern).0.chars().count()
+    }
+
     pub fn clear(&mut self) {
         self.pattern.clear();
+        self.offset = SearchOffset::None;
         self.matches.clear();
         self.current_match = None;
+        self.indexed = None;
+    }
+}
+
+/// `0..len` 中第一个使 `pred` 不成立的索引，`pred` 对前面的索引成立、对后面的索引不成立
+fn partition_point(len: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// 光标下的单词，光标不在单词上时为光标之后这一行的第一个单词（`*` / `#` 搜索的内容），
+/// 返回（单词开头的列, 单词），列为字符索引
+pub fn word_under_cursor(line: &str, column: usize) -> Option<(usize, String)> {
+    let chars: Vec<char> = line.chars().collect();
+    let column = column.min(chars.len());
+    let first = column + chars[column..].iter().position(|c| is_word_char(*c))?;
+    let start = if first == column {
+        column - chars[..column].iter().rev().take_while(|c| is_word_char(**c)).count()
+    } else {
+        first
+    };
+    let end = first + chars[first..].iter().take_while(|c| is_word_char(**c)).count();
+    Some((start, chars[start..end].iter().collect()))
+}
+
+/// 拆出模式开头的 `\<` 和结尾的 `\>`，返回（字面文本, 开头是否要求单词边界, 结尾是否要求单词边界）
+fn split_word_boundaries(pattern: &str) -> (&str, bool, bool) {
+    let (pattern, word_start) = match pattern.strip_prefix("\\<") {
+        Some(rest) => (rest, true),
+        None => (pattern, false),
+    };
+    let (pattern, word_end) = match pattern.strip_suffix("\\>") {
+        Some(rest) => (rest, true),
+        None => (pattern, false),
+    };
+    (pattern, word_start, word_end)
+}
+
+/// 在 `lines` 中的每一行查找不包含换行符的模式，匹配可以重叠；`word_start` / `word_end` 要求匹配前 / 后不是单词字符
+fn find_in_lines(
+    buffer: &Buffer,
+    lines: std::ops::Range<usize>,
+    pattern: &str,
+    word_start: bool,
+    word_end: bool,
+) -> Vec<usize> {
+    if pattern.is_empty() || lines.start >= buffer.len_lines() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    let mut line_start = buffer.line_to_char(lines.start);
+    for line in buffer.rope().lines_at(lines.start).take(lines.len()) {
+        let text: Cow<str> = line.into();
+        // 从上一个匹配的第二个字符开始继续查找
+        let mut start = 0;
+        let mut chars_before = 0;
+        while let Some(pos) = text[start..].find(pattern) {
+            let byte = start + pos;
+            chars_before += text[start..byte].chars().count();
+            let before_ok = !word_start || !text[..byte].chars().next_back().is_some_and(is_word_char);
+            let after_ok = !word_end || !text[byte + pattern.len()..].chars().next().is_some_and(is_word_char);
+            if before_ok && after_ok {
+                matches.push(line_start + chars_before);
+            }
+            start = byte + text[byte..].chars().next().map_or(1, char::len_utf8);
+            chars_before += 1;
+        }
+        line_start += line.len_chars();
+    }
+    matches
+}
+
+/// 查找跨行的模式：匹配一定从某一行的 `模式第一段 + 换行符` 结尾处开始
+fn find_multiline(buffer: &Buffer, pattern: &str) -> Vec<usize> {
+    let Some((first, _)) = pattern.split_once('\n') else {
+        return Vec::new();
+    };
+    let head = format!("{}\n", first);
+    let head_len = head.chars().count();
+    let pattern_len = pattern.chars().count();
+    let mut matches = Vec::new();
+    for (line_idx, line) in buffer.rope().lines().enumerate() {
+        let text: Cow<str> = line.into();
+        if !text.ends_with(&head) {
+            continue;
+        }
+        let start = buffer.line_to_char(line_idx) + line.len_chars() - head_len;
+        if start + pattern_len <= buffer.len_chars() && buffer.slice(start..start + pattern_len) == pattern {
+            matches.push(start);
+        }
     }
+    matches
 }
 
 impl Default for SearchState {
@@ -194,8 +467,8 @@ mod tests {
         search.set_pattern("hello", SearchDirection::Forward, &buffer);
 
         assert_eq!(search.matches.len(), 2);
-        assert_eq!(search.matches[0], 0);
-        assert_eq!(search.matches[1], 12);
+        assert_eq!(search.matches.get(0), Some(0));
+        assert_eq!(search.matches.get(1), Some(12));
     }
 
     #[test]
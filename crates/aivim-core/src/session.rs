@@ -0,0 +1,146 @@
+//! 会话模块 (:mksession)
+//!
+//! 把工作目录、选项、打开的文件、标签页和窗口布局、光标位置写成由 Ex 命令组成的会话文件，
+//! 用 `:source {file}` 或 `aivim -S {file}` 执行即可恢复：
+//! - 工作目录中的文件保存相对路径，其他文件保存绝对路径
+//! - 只保存与默认值不同的全局选项
+//! - 窗口布局用 :split / :vsplit 重建，`:wincmd w` 在窗口之间移动，窗口大小恢复为平分
+//! - 光标位置用 `:call cursor({lnum}, {col})` 恢复
+//! - 手动创建的折叠用 `:{start},{end}fold` 重建，折叠的开关状态用 `:{lnum}foldopen` / `:{lnum}foldclose` 恢复
+//! - 没有文件名的缓冲区在窗口中显示为新的空缓冲区，内容不保存
+
+use std::path::Path;
+
+use crate::editor::EditorOptions;
+use crate::fold::{Fold, FoldMethod};
+use crate::window::{Layout, SplitDirection, WindowId};
+
+/// 不指定文件名时的会话文件
+pub const DEFAULT_SESSION_FILE: &str = "Session.vim";
+
+/// 把参数写成 Ex 命令可以解析的形式：包含空白、引号或 `\` 时用双引号包含并转义
+pub fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// 会话中保存的文件路径：在工作目录 `cwd` 中时为相对路径
+pub fn session_path(path: &Path, cwd: &Path) -> String {
+    let absolute = if path.is_absolute() { path.to_path_buf() } else { cwd.join(path) };
+    let path = absolute.strip_prefix(cwd).unwrap_or(&absolute);
+    quote(&path.to_string_lossy())
+}
+
+/// 恢复与默认值不同的选项的 `:set` 命令
+pub fn option_commands(options: &EditorOptions) -> Vec<String> {
+    let defaults = EditorOptions::default();
+    let flags = [
+        ("number", options.number, defaults.number),
+        ("relativenumber", options.relativenumber, defaults.relativenumber),
+        ("cursorline", options.cursorline, defaults.cursorline),
+        ("cursorcolumn", options.cursorcolumn, defaults.cursorcolumn),
+        ("trash", options.trash, defaults.trash),
+        ("expandtab", options.expandtab, defaults.expandtab),
+        ("wrap", options.wrap, defaults.wrap),
+        ("autoindent", options.autoindent, defaults.autoindent),
+        ("pastedetect", options.pastedetect, defaults.pastedetect),
+        ("autopairs", options.autopairs, defaults.autopairs),
+        ("undofile", options.undofile, defaults.undofile),
+        ("autoread", options.autoread, defaults.autoread),
+        ("aicomplete", options.aicomplete, defaults.aicomplete),
+        ("lsp", options.lsp, defaults.lsp),
+        ("gitsigns", options.gitsigns, defaults.gitsigns),
+    ];
+    let values = [
+        ("shiftwidth", options.shiftwidth, defaults.shiftwidth),
+        ("tabstop", options.tabstop, defaults.tabstop),
+        ("textwidth", options.textwidth, defaults.textwidth),
+        ("timeoutlen", options.timeoutlen, defaults.timeoutlen),
+        ("updatetime", options.updatetime, defaults.updatetime),
+        ("undolevels", options.undolevels, defaults.undolevels),
+        ("undomaxmem", options.undomaxmem, defaults.undomaxmem),
+        ("aidelay", options.aidelay, defaults.aidelay),
+    ];
+
+    let mut commands: Vec<String> = flags
+        .into_iter()
+        .filter(|(_, value, default)| value != default)
+        .map(|(name, value, _)| format!("set {}{}", if value { "" } else { "no" }, name))
+        .collect();
+    commands.extend(
+        values
+            .into_iter()
+            .filter(|(_, value, default)| value != default)
+            .map(|(name, value, _)| format!("set {}={}", name, value)),
+    );
+    if options.fillchars != defaults.fillchars && !options.asciiglyphs {
+        commands.push(format!("set fillchars={}", options.fillchars.format()));
+    }
+    if options.foldmethod != defaults.foldmethod {
+        commands.push(format!("set foldmethod={}", options.foldmethod.name()));
+    }
+    commands
+}
+
+/// 恢复缓冲区折叠的命令，`manual` 时先重建折叠
+///
+/// 重建或重新计算出的折叠都是关闭的：先从外到内打开本身打开或包含打开的折叠的折叠，
+/// 再从内到外关闭其中本身关闭的折叠。`:{lnum}foldopen` 打开包含这一行的最外层关闭的折叠，
+/// `:{lnum}foldclose` 关闭包含这一行的最内层打开的折叠，所以用折叠的第一行指定折叠
+pub fn fold_commands(folds: &[Fold], method: FoldMethod) -> Vec<String> {
+    let mut commands = Vec::new();
+    if method == FoldMethod::Manual {
+        commands.extend(folds.iter().map(|fold| format!("{},{}fold", fold.start + 1, fold.end + 1)));
+    }
+    let encloses = |outer: &Fold, inner: &Fold| {
+        outer.start <= inner.start && inner.end <= outer.end && (outer.start, outer.end) != (inner.start, inner.end)
+    };
+    let has_open = |fold: &Fold| !fold.closed || folds.iter().any(|inner| !inner.closed && encloses(fold, inner));
+    // folds 按起始行排序，外层的在前
+    let opened: Vec<&Fold> = folds.iter().filter(|fold| has_open(fold)).collect();
+    commands.extend(opened.iter().map(|fold| format!("{}foldopen", fold.start + 1)));
+    commands.extend(
+        opened
+            .iter()
+            .rev()
+            .filter(|fold| fold.closed)
+            .map(|fold| format!("{}foldclose", fold.start + 1)),
+    );
+    commands
+}
+
+/// 在当前窗口中重建布局 `layout` 的命令，结束时当前窗口为布局中的最后一个窗口
+///
+/// 同方向分割 n-1 次后当前窗口是第一个子布局，之后依次重建每个子布局，
+/// 每个子布局结束在它的最后一个窗口，`:wincmd w` 即到达下一个子布局的窗口。
+/// `window` 生成在窗口中显示缓冲区、恢复光标的命令
+pub fn layout_commands(layout: &Layout, window: &mut dyn FnMut(WindowId) -> Vec<String>) -> Vec<String> {
+    let mut commands = Vec::new();
+    match layout {
+        Layout::Window(id) => commands.extend(window(*id)),
+        Layout::Split { direction, children, .. } => {
+            let split = match direction {
+                SplitDirection::Horizontal => "split",
+                SplitDirection::Vertical => "vsplit",
+            };
+            commands.extend(std::iter::repeat_n(split.to_string(), children.len() - 1));
+            for (index, child) in children.iter().enumerate() {
+                if index > 0 {
+                    commands.push("wincmd w".to_string());
+                }
+                commands.extend(layout_commands(child, window));
+            }
+        }
+    }
+    commands
+}
@@ -0,0 +1,86 @@
+//! 外部命令模块
+//!
+//! 实现 :!cmd、:r !cmd、:{range}!cmd 和 :make：命令通过 JobManager 在后台运行，
+//! 编辑器在任务结束后根据 `ShellAction` 处理输出：
+//! - 标准输出和标准错误分开收集，只有标准输出会写入缓冲区
+//! - 命令以非零状态退出时不修改缓冲区，显示标准错误和退出状态
+//!
+//! :shell 启动的交互式 shell 和 Ctrl+Z 挂起需要使用终端，由界面处理（见 `TerminalRequest`）
+
+use std::time::Duration;
+
+use crate::buffer::BufferId;
+use crate::job::JobStream;
+use crate::range::LineRange;
+
+/// 无界面模式和脚本接口等待外部命令结束的最长时间
+pub const SHELL_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 命令结束后对输出的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellAction {
+    /// :!cmd 显示输出
+    Show,
+    /// :r !cmd 把输出插入到缓冲区第 `at` 行之前（`:0r` 为 0，否则为范围的最后一行的下一行）
+    Read { buffer: BufferId, at: usize },
+    /// :{range}!cmd 把范围内的行作为输入，用输出替换这些行
+    Filter { buffer: BufferId, range: LineRange },
+    /// :make 从输出中提取错误位置作为 Quickfix 列表，`jump` 为 true 时跳转到第一个错误
+    Make { jump: bool },
+}
+
+/// 需要界面暂时交出终端的请求：界面恢复终端的正常模式，结束之后重新进入全屏界面并完整重绘
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalRequest {
+    /// Ctrl+Z / :suspend 挂起编辑器回到启动它的 shell，`fg` 之后继续
+    Suspend,
+    /// :shell 启动交互式 shell，退出 shell 之后回到编辑器
+    Shell(String),
+}
+
+/// :shell 启动的 shell：环境变量 `SHELL`，没有时为 `sh`（Windows 上为 `COMSPEC` 或 `cmd`）
+pub fn interactive_shell() -> String {
+    let (var, fallback) = if cfg!(windows) { ("COMSPEC", "cmd") } else { ("SHELL", "sh") };
+    std::env::var(var).ok().filter(|shell| !shell.is_empty()).unwrap_or_else(|| fallback.to_string())
+}
+
+/// 正在运行的外部命令
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellCommand {
+    pub command: String,
+    pub action: ShellAction,
+    stdout: Vec<String>,
+    stderr: Vec<String>,
+}
+
+impl ShellCommand {
+    pub fn new(command: impl Into<String>, action: ShellAction) -> Self {
+        Self {
+            command: command.into(),
+            action,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+
+    /// 记录一行输出
+    pub fn push_line(&mut self, stream: JobStream, line: String) {
+        match stream {
+            JobStream::Stdout => self.stdout.push(line),
+            JobStream::Stderr => self.stderr.push(line),
+        }
+    }
+
+    pub fn stdout(&self) -> &[String] {
+        &self.stdout
+    }
+
+    pub fn stderr(&self) -> &[String] {
+        &self.stderr
+    }
+
+    /// 标准输出的文本，每行以换行符结尾
+    pub fn stdout_text(&self) -> String {
+        self.stdout.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
@@ -0,0 +1,193 @@
+//! 状态栏模块
+//!
+//! 状态栏由左右两组片段组成，片段和顺序在 `config.toml` 的 `[statusline]` 表中设置，省略的一组使用默认值：
+//!
+//! ```toml
+//! [statusline]
+//! left = ["mode", "register", "filename", "flags", "diagnostics"]
+//! right = ["loading", "recording", "searchcount", "branch", "filetype", "position", "percent"]
+//! ```
+//!
+//! - 没有内容的片段（如不在仓库中时的 `branch`）不占位置
+//! - 终端太窄放不下所有片段时按优先级从低到高省略片段，只剩文件名还放不下时由界面截短文件名
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// 状态栏中的一个片段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// 模式，有未完成的操作符时加 `-OPERATOR`
+    Mode,
+    /// 按 `"x` 选择的寄存器
+    Register,
+    /// 文件名
+    FileName,
+    /// `[+]`、`[RO]`、文件编码和格式等标志
+    Flags,
+    /// 语言服务器的诊断数
+    Diagnostics,
+    /// 正在后台读入的文件和进度（`⠹ Loading big.log 42%`）
+    Loading,
+    /// 正在录制的宏寄存器（`recording @q`）
+    Recording,
+    /// 搜索之后光标所在的匹配是第几个（`match 3 of 17`）
+    SearchCount,
+    /// 文件所在仓库的当前分支
+    Branch,
+    /// 文件类型
+    FileType,
+    /// 光标位置 `行:列`
+    Position,
+    /// 光标所在行在文件中的百分比
+    Percent,
+}
+
+impl Segment {
+    pub const ALL: [Segment; 12] = [
+        Segment::Mode,
+        Segment::Register,
+        Segment::FileName,
+        Segment::Flags,
+        Segment::Diagnostics,
+        Segment::Loading,
+        Segment::Recording,
+        Segment::SearchCount,
+        Segment::Branch,
+        Segment::FileType,
+        Segment::Position,
+        Segment::Percent,
+    ];
+
+    /// config.toml 中的名字
+    pub fn name(&self) -> &'static str {
+        match self {
+            Segment::Mode => "mode",
+            Segment::Register => "register",
+            Segment::FileName => "filename",
+            Segment::Flags => "flags",
+            Segment::Diagnostics => "diagnostics",
+            Segment::Loading => "loading",
+            Segment::Recording => "recording",
+            Segment::SearchCount => "searchcount",
+            Segment::Branch => "branch",
+            Segment::FileType => "filetype",
+            Segment::Position => "position",
+            Segment::Percent => "percent",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|segment| segment.name() == name)
+    }
+
+    /// 终端太窄时先省略优先级低的片段
+    pub fn priority(&self) -> u8 {
+        match self {
+            Segment::FileName => 9,
+            Segment::Mode => 8,
+            Segment::Position => 7,
+            Segment::Register | Segment::Loading | Segment::Recording => 6,
+            Segment::Flags | Segment::SearchCount => 5,
+            Segment::Diagnostics => 4,
+            Segment::Branch => 3,
+            Segment::FileType => 2,
+            Segment::Percent => 1,
+        }
+    }
+}
+
+/// 状态栏左右两组片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatuslineSettings {
+    pub left: Vec<Segment>,
+    pub right: Vec<Segment>,
+}
+
+impl Default for StatuslineSettings {
+    fn default() -> Self {
+        Self {
+            left: vec![
+                Segment::Mode,
+                Segment::Register,
+                Segment::FileName,
+                Segment::Flags,
+                Segment::Diagnostics,
+            ],
+            right: vec![
+                Segment::Loading,
+                Segment::Recording,
+                Segment::SearchCount,
+                Segment::Branch,
+                Segment::FileType,
+                Segment::Position,
+                Segment::Percent,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StatuslineSection {
+    left: Option<Vec<String>>,
+    right: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    statusline: StatuslineSection,
+}
+
+impl StatuslineSettings {
+    /// 解析配置文件的内容，只读取 `[statusline]` 表，错误信息包括出错的位置
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| {
+            let location = e.to_string().lines().next().unwrap_or_default().to_string();
+            format!("{}: {}", location, e.message())
+        })?;
+        let segments = |names: Vec<String>| {
+            names
+                .iter()
+                .map(|name| Segment::from_name(name).ok_or_else(|| format!("statusline: unknown segment: {}", name)))
+                .collect::<Result<Vec<_>, String>>()
+        };
+        let mut settings = Self::default();
+        if let Some(left) = file.statusline.left {
+            settings.left = segments(left)?;
+        }
+        if let Some(right) = file.statusline.right {
+            settings.right = segments(right)?;
+        }
+        Ok(settings)
+    }
+
+    /// 读取配置文件
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&text)
+    }
+}
+
+/// 在 `width` 列中放得下的片段：`segments` 为各片段和它占的宽度（包括分隔的空格），
+/// 放不下时按优先级从低到高省略，文件名不省略
+pub fn fit(segments: &[(Segment, usize)], width: usize) -> Vec<bool> {
+    let mut visible: Vec<bool> = segments.iter().map(|(_, len)| *len > 0).collect();
+    loop {
+        let total: usize = segments.iter().zip(&visible).filter(|(_, shown)| **shown).map(|((_, len), _)| len).sum();
+        if total <= width {
+            return visible;
+        }
+        let lowest = segments
+            .iter()
+            .enumerate()
+            .filter(|(idx, (segment, _))| visible[*idx] && *segment != Segment::FileName)
+            .min_by_key(|(idx, (segment, _))| (segment.priority(), std::cmp::Reverse(*idx)));
+        match lowest {
+            Some((idx, _)) => visible[idx] = false,
+            None => return visible,
+        }
+    }
+}
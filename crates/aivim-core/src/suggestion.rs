@@ -0,0 +1,141 @@
+//! AI 补全建议模块（Insert 模式的 ghost text）
+//!
+//! 开启 aicomplete 后，Insert 模式下光标停留 aidelay 毫秒时把光标前后的文本发给模型（见 aivim-ai），
+//! 流式返回的文本作为建议以暗色显示在光标之后：
+//! - 建议属于请求时的位置（缓冲区、光标和光标所在行的内容），输入或移动光标后丢弃，并取消未完成的请求
+//! - Tab 接受建议，把文本插入光标处；Esc 丢弃建议，同一位置不再请求
+//! - 每个位置只请求一次，请求失败时由编辑器在消息行显示错误
+
+use std::time::{Duration, Instant};
+
+use aivim_ai::{AiEvent, AiStream};
+
+use crate::buffer::BufferId;
+
+/// 建议所属的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestionAnchor {
+    pub buffer: BufferId,
+    pub line: usize,
+    pub column: usize,
+    /// 光标所在行的内容，同一位置的行被修改后建议不再适用
+    pub line_text: String,
+}
+
+/// 一个位置的建议
+#[derive(Debug)]
+struct Suggestion {
+    anchor: SuggestionAnchor,
+    text: String,
+    /// 未完成的请求
+    stream: Option<AiStream>,
+}
+
+/// 建议的状态
+#[derive(Debug, Default)]
+pub struct Suggestions {
+    /// 光标停留的位置和开始停留的时间
+    idle: Option<(SuggestionAnchor, Instant)>,
+    /// 已经请求过（或被丢弃）的位置的建议
+    current: Option<Suggestion>,
+}
+
+impl Suggestions {
+    /// 丢弃建议和停留的位置，返回是否有显示中的建议被清除
+    pub fn clear(&mut self) -> bool {
+        self.idle = None;
+        self.current.take().is_some_and(|suggestion| !suggestion.text.is_empty())
+    }
+
+    /// 记录光标所在的位置：位置改变时丢弃旧位置的建议并重新计时，
+    /// 返回 `(是否有显示中的建议被清除, 是否应该为这个位置发送请求)`
+    pub fn update(&mut self, anchor: &SuggestionAnchor, now: Instant, delay: Duration) -> (bool, bool) {
+        let mut cleared = false;
+        if self.current.as_ref().is_some_and(|suggestion| suggestion.anchor != *anchor) {
+            cleared = self.current.take().is_some_and(|suggestion| !suggestion.text.is_empty());
+        }
+        match &self.idle {
+            Some((idle, since)) if idle == anchor => {
+                let due = self.current.is_none() && now.duration_since(*since) >= delay;
+                (cleared, due)
+            }
+            _ => {
+                self.idle = Some((anchor.clone(), now));
+                (cleared, false)
+            }
+        }
+    }
+
+    /// 记录为位置发送的请求，请求无法发送时 `stream` 为 None（不再重试）
+    pub fn start(&mut self, anchor: SuggestionAnchor, stream: Option<AiStream>) {
+        self.current = Some(Suggestion {
+            anchor,
+            text: String::new(),
+            stream,
+        });
+    }
+
+    /// 不经过请求直接显示建议
+    pub fn show(&mut self, anchor: SuggestionAnchor, text: &str) {
+        self.current = Some(Suggestion {
+            anchor,
+            text: text.to_string(),
+            stream: None,
+        });
+    }
+
+    /// 取出请求返回的文本，返回 `(建议是否变化, 请求的错误)`
+    pub fn poll(&mut self) -> (bool, Option<String>) {
+        let Some(suggestion) = self.current.as_mut() else {
+            return (false, None);
+        };
+        let Some(stream) = suggestion.stream.as_mut() else {
+            return (false, None);
+        };
+        let mut changed = false;
+        let mut error = None;
+        for event in stream.poll() {
+            match event {
+                AiEvent::Delta(text) => {
+                    suggestion.text.push_str(&text);
+                    changed = true;
+                }
+                AiEvent::Done => {}
+                AiEvent::Error(message) => error = Some(message),
+            }
+        }
+        if stream.is_finished() {
+            suggestion.stream = None;
+        }
+        (changed, error)
+    }
+
+    /// 是否有未完成的请求
+    pub fn is_requesting(&self) -> bool {
+        self.current.as_ref().is_some_and(|suggestion| suggestion.stream.is_some())
+    }
+
+    /// 位置上显示的建议
+    pub fn text(&self, anchor: &SuggestionAnchor) -> Option<&str> {
+        self.current
+            .as_ref()
+            .filter(|suggestion| suggestion.anchor == *anchor && !suggestion.text.is_empty())
+            .map(|suggestion| suggestion.text.as_str())
+    }
+
+    /// 取出位置上的建议用于插入，并取消未完成的请求
+    pub fn accept(&mut self, anchor: &SuggestionAnchor) -> Option<String> {
+        let text = self.text(anchor)?.to_string();
+        self.current = None;
+        Some(text)
+    }
+
+    /// 丢弃位置上的建议，同一位置不再请求；返回是否有显示中的建议
+    pub fn dismiss(&mut self, anchor: &SuggestionAnchor) -> bool {
+        if self.text(anchor).is_none() {
+            return false;
+        }
+        self.start(anchor.clone(), None);
+        true
+    }
+}
@@ -0,0 +1,264 @@
+//! 符号提取模块
+//!
+//! 为符号选择器（:symbols）提供文档符号和工作区符号。
+//! 在 LSP 客户端可用之前，通过逐行扫描 Rust 源码中的条目声明
+//! （fn、struct、enum、trait、impl、mod 等）来近似 rust-analyzer 的符号列表。
+
+use crate::picker::PickerItem;
+use crate::range::LineRange;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 工作区扫描最多读取的文件数
+const MAX_WORKSPACE_FILES: usize = 2000;
+
+/// 符号类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Module,
+    Constant,
+    Static,
+    TypeAlias,
+    Macro,
+}
+
+impl SymbolKind {
+    /// 从声明关键字解析符号类型
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "fn" => Some(SymbolKind::Function),
+            "struct" => Some(SymbolKind::Struct),
+            "enum" => Some(SymbolKind::Enum),
+            "trait" => Some(SymbolKind::Trait),
+            "impl" => Some(SymbolKind::Impl),
+            "mod" => Some(SymbolKind::Module),
+            "const" => Some(SymbolKind::Constant),
+            "static" => Some(SymbolKind::Static),
+            "type" => Some(SymbolKind::TypeAlias),
+            "macro_rules!" => Some(SymbolKind::Macro),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Impl => "impl",
+            SymbolKind::Module => "module",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Static => "static",
+            SymbolKind::TypeAlias => "type",
+            SymbolKind::Macro => "macro",
+        }
+    }
+}
+
+/// 源码中的一个符号
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 声明所在行和名称起始列（从 0 开始）
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Symbol {
+    /// 转换为选择器候选项
+    pub fn to_picker_item(&self) -> PickerItem {
+        PickerItem::new(&self.name, self.line, self.column).with_detail(self.kind.name())
+    }
+}
+
+/// 声明前可能出现的修饰符
+const MODIFIERS: &[&str] = &["pub", "async", "unsafe", "extern", "default", "const"];
+
+/// 提取一段源码中的符号（文档符号）
+pub fn document_symbols<'a, I>(lines: I) -> Vec<Symbol>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    lines
+        .into_iter()
+        .enumerate()
+        .filter_map(|(line_idx, text)| parse_declaration(text, line_idx))
+        .collect()
+}
+
+/// 包含第 `line` 行的函数的行范围（从声明行到匹配的 `}`），最内层的函数优先
+///
+/// 按花括号计数确定函数的结束行，忽略 `//` 注释，不识别字符串中的花括号
+pub fn function_range(lines: &[&str], line: usize) -> Option<LineRange> {
+    let mut functions: Vec<Symbol> = document_symbols(lines.iter().copied().take(line + 1))
+        .into_iter()
+        .filter(|symbol| symbol.kind == SymbolKind::Function)
+        .collect();
+    functions.reverse();
+    functions
+        .into_iter()
+        .map(|symbol| LineRange::new(symbol.line, block_end(lines, symbol.line)))
+        .find(|range| range.end >= line)
+}
+
+/// 从第 `start` 行开始的代码块的结束行：第一个 `{` 匹配的 `}` 所在行，
+/// 在 `{` 之前遇到 `;` 时（没有函数体的声明）为该行
+fn block_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut opened = false;
+    for (index, text) in lines.iter().enumerate().skip(start) {
+        let code = text.split("//").next().unwrap_or("");
+        for c in code.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' if opened => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return index;
+                    }
+                }
+                ';' if !opened => return index,
+                _ => {}
+            }
+        }
+    }
+    lines.len().saturating_sub(1).max(start)
+}
+
+/// 递归扫描目录下的 Rust 源文件，返回带路径的符号候选项（工作区符号）
+///
+/// 跳过隐藏目录和 `target` 目录
+pub fn workspace_symbols(root: &Path) -> Vec<PickerItem> {
+    let mut files = Vec::new();
+    collect_source_files(root, &mut files);
+    files.sort();
+
+    let mut items = Vec::new();
+    for path in files {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let relative = path.strip_prefix(root).unwrap_or(&path).display().to_string();
+        for symbol in document_symbols(content.lines()) {
+            items.push(
+                PickerItem::new(&symbol.name, symbol.line, symbol.column)
+                    .with_detail(format!("{} {}:{}", symbol.kind.name(), relative, symbol.line + 1))
+                    .with_path(path.clone()),
+            );
+        }
+    }
+    items
+}
+
+fn collect_source_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if files.len() >= MAX_WORKSPACE_FILES {
+            return;
+        }
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_source_files(&path, files);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+/// 解析一行中的条目声明
+fn parse_declaration(text: &str, line_idx: usize) -> Option<Symbol> {
+    let indent = text.len() - text.trim_start().len();
+    let mut rest = &text[indent..];
+
+    // 跳过可见性和修饰符（pub(crate)、async、extern "C" 等）
+    loop {
+        let word_end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let word = &rest[..word_end];
+        if !MODIFIERS.contains(&word) {
+            break;
+        }
+        // `const` 后面不是 fn 时是常量声明
+        if word == "const" && !rest[word_end..].trim_start().starts_with("fn ") {
+            break;
+        }
+        rest = &rest[word_end..];
+        if rest.starts_with('(') {
+            rest = &rest[rest.find(')')? + 1..];
+        }
+        rest = rest.trim_start();
+        if rest.starts_with('"') {
+            rest = &rest[1..];
+            rest = rest[rest.find('"')? + 1..].trim_start();
+        }
+    }
+
+    let keyword_end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '!'))?;
+    let kind = SymbolKind::from_keyword(&rest[..keyword_end])?;
+    let name_text = rest[keyword_end..].trim_start();
+
+    let name = if kind == SymbolKind::Impl {
+        // impl 取到 { 或 where 之前的完整签名，如 `Display for Buffer`
+        let name_text = if name_text.starts_with('<') {
+            skip_generics(name_text)
+        } else {
+            name_text
+        };
+        let end = name_text
+            .find(" where")
+            .or_else(|| name_text.find('{'))
+            .unwrap_or(name_text.len());
+        name_text[..end].trim().to_string()
+    } else {
+        name_text
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect()
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let byte_column = text.len() - name_text.len();
+
+    Some(Symbol {
+        name,
+        kind,
+        line: line_idx,
+        column: text[..byte_column].chars().count(),
+    })
+}
+
+/// 跳过开头的泛型参数列表 `<...>`
+fn skip_generics(text: &str) -> &str {
+    let mut depth = 0;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return text[idx + 1..].trim_start();
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
@@ -0,0 +1,327 @@
+//! 语法树模块 (tree-sitter)
+//!
+//! 按文件扩展名选择 tree-sitter 语法，为缓冲区解析语法树，用于结构化的文本对象：
+//! - `af` / `if` - 光标所在的函数（包括方法、闭包）/ 函数体
+//! - `ac` / `ic` - 光标所在的类型定义（类、结构体、枚举、impl 等）/ 定义体
+//! - `foldmethod=syntax` 的折叠：跨越多行的函数和类型定义（见 fold 模块）
+//! - 支持 Rust、Python、C、Go、JavaScript 和 TypeScript，其他文件没有语法树，这些文本对象无效
+//! - 语法树缓存在缓冲区中，内容变化（changedtick）后下次使用时重新解析整个文件
+//!
+//! tree-sitter 使用字节偏移，与缓冲区的字符索引之间通过 rope 转换。
+
+use std::sync::Mutex;
+
+use ropey::Rope;
+use tree_sitter::{Language, Node, Parser, Tree};
+
+use crate::buffer::Buffer;
+
+/// 支持的语法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxLanguage {
+    Rust,
+    Python,
+    C,
+    Go,
+    JavaScript,
+    TypeScript,
+}
+
+impl SyntaxLanguage {
+    /// 文件扩展名对应的语法
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let language = match extension {
+            "rs" => SyntaxLanguage::Rust,
+            "py" | "pyi" => SyntaxLanguage::Python,
+            "c" | "h" => SyntaxLanguage::C,
+            "go" => SyntaxLanguage::Go,
+            "js" | "mjs" | "cjs" | "jsx" => SyntaxLanguage::JavaScript,
+            "ts" | "mts" | "cts" => SyntaxLanguage::TypeScript,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// 缓冲区的语法：按文件类型（如 :DiffOrig 设置的），没有时按文件扩展名
+    pub fn for_buffer(buffer: &Buffer) -> Option<Self> {
+        let filetype = buffer.filetype().map(|filetype| match filetype {
+            "rust" => "rs",
+            "python" => "py",
+            "javascript" => "js",
+            "typescript" => "ts",
+            other => other,
+        });
+        let extension = buffer.file_path().and_then(|path| path.extension()).and_then(|ext| ext.to_str());
+        filetype.or(extension).and_then(Self::from_extension)
+    }
+
+    fn language(&self) -> Language {
+        match self {
+            SyntaxLanguage::Rust => tree_sitter_rust::LANGUAGE.into(),
+            SyntaxLanguage::Python => tree_sitter_python::LANGUAGE.into(),
+            SyntaxLanguage::C => tree_sitter_c::LANGUAGE.into(),
+            SyntaxLanguage::Go => tree_sitter_go::LANGUAGE.into(),
+            SyntaxLanguage::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            SyntaxLanguage::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+        }
+    }
+
+    /// 函数的节点类型
+    fn function_kinds(&self) -> &'static [&'static str] {
+        match self {
+            SyntaxLanguage::Rust => &["function_item", "function_signature_item", "closure_expression"],
+            SyntaxLanguage::Python => &["function_definition", "lambda"],
+            SyntaxLanguage::C => &["function_definition"],
+            SyntaxLanguage::Go => &["function_declaration", "method_declaration", "func_literal"],
+            SyntaxLanguage::JavaScript | SyntaxLanguage::TypeScript => &[
+                "function_declaration",
+                "function_expression",
+                "generator_function_declaration",
+                "generator_function",
+                "arrow_function",
+                "method_definition",
+            ],
+        }
+    }
+
+    /// 类型定义的节点类型
+    fn class_kinds(&self) -> &'static [&'static str] {
+        match self {
+            SyntaxLanguage::Rust => &["struct_item", "enum_item", "union_item", "trait_item", "impl_item"],
+            SyntaxLanguage::Python => &["class_definition"],
+            SyntaxLanguage::C => &["struct_specifier", "union_specifier", "enum_specifier"],
+            SyntaxLanguage::Go => &["type_declaration"],
+            SyntaxLanguage::JavaScript => &["class_declaration", "class"],
+            SyntaxLanguage::TypeScript => &[
+                "class_declaration",
+                "class",
+                "abstract_class_declaration",
+                "interface_declaration",
+                "enum_declaration",
+            ],
+        }
+    }
+
+    /// 与定义写在一起、属于定义的前导节点（如 Rust 的 `#[...]`、Python 的装饰器）
+    fn is_attached(&self, node: &Node) -> bool {
+        match self {
+            SyntaxLanguage::Rust => node.kind() == "attribute_item",
+            SyntaxLanguage::Python => node.kind() == "decorator",
+            _ => false,
+        }
+    }
+}
+
+/// 缓冲区中缓存的语法树，复制缓冲区时不复制
+#[derive(Default)]
+pub struct SyntaxCache(Mutex<Option<(u64, Tree)>>);
+
+impl Clone for SyntaxCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for SyntaxCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SyntaxCache")
+    }
+}
+
+/// 解析 rope 中的文本，直接读取 rope 的分块，不复制全文
+fn parse(language: SyntaxLanguage, rope: &Rope) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(&language.language()).ok()?;
+    let len = rope.len_bytes();
+    let mut read = |byte: usize, _| -> &[u8] {
+        if byte >= len {
+            return &[];
+        }
+        let (chunk, start, _, _) = rope.chunk_at_byte(byte);
+        &chunk.as_bytes()[byte - start..]
+    };
+    parser.parse_with_options(&mut read, None, None)
+}
+
+/// 缓冲区的语法树和语法，没有对应的语法时返回 None
+pub fn syntax_tree(buffer: &Buffer) -> Option<(SyntaxLanguage, Tree)> {
+    let language = SyntaxLanguage::for_buffer(buffer)?;
+    let mut cache = buffer.syntax_cache().0.lock().ok()?;
+    if let Some((tick, tree)) = cache.as_ref() {
+        if *tick == buffer.changedtick() {
+            return Some((language, tree.clone()));
+        }
+    }
+    let tree = parse(language, buffer.rope())?;
+    *cache = Some((buffer.changedtick(), tree.clone()));
+    Some((language, tree))
+}
+
+/// 结构化文本对象的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxObject {
+    Function,
+    Class,
+}
+
+/// 光标处（字符索引 `char_idx`）最内层的函数或类型定义的字符范围 (起始, 结束)
+///
+/// `around` 时为整个定义（连同前导的属性、装饰器），否则为定义体花括号之间的内容（Python 为缩进的代码块）；
+/// 范围两边只有空白时扩展为整行。光标在行首的缩进中时，按这一行的第一个非空白字符查找
+pub fn object_range(buffer: &Buffer, char_idx: usize, object: SyntaxObject, around: bool) -> Option<(usize, usize)> {
+    let (language, tree) = syntax_tree(buffer)?;
+    let rope = buffer.rope();
+    if rope.len_chars() == 0 {
+        return None;
+    }
+    let char_idx = first_non_blank_if_indent(rope, char_idx.min(rope.len_chars() - 1));
+    let byte = rope.char_to_byte(char_idx);
+    let kinds = match object {
+        SyntaxObject::Function => language.function_kinds(),
+        SyntaxObject::Class => language.class_kinds(),
+    };
+    let mut node = enclosing(language, tree.root_node().descendant_for_byte_range(byte, byte)?, kinds)?;
+
+    let (start, end) = if around {
+        // Python 的装饰器在外层的 decorated_definition 中，Rust 的属性是前面的兄弟节点
+        let mut first = node;
+        if let Some(parent) = node.parent().filter(|parent| parent.kind() == "decorated_definition") {
+            node = parent;
+            first = parent;
+        }
+        while let Some(previous) = first.prev_sibling().filter(|previous| language.is_attached(previous)) {
+            first = previous;
+        }
+        (first.start_byte(), node.end_byte())
+    } else {
+        body_range(&node)?
+    };
+    let (start, end) = trim(rope, rope.byte_to_char(start), rope.byte_to_char(end))?;
+    Some(expand_to_lines(rope, start, end))
+}
+
+/// 语法折叠的行范围 (起始行, 结束行)：跨越多行的函数和类型定义，按起始行排序，没有语法树时为空
+pub fn fold_ranges(buffer: &Buffer) -> Vec<(usize, usize)> {
+    let Some((language, tree)) = syntax_tree(buffer) else {
+        return Vec::new();
+    };
+    let mut ranges = Vec::new();
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        let kind = node.kind();
+        if language.function_kinds().contains(&kind) || language.class_kinds().contains(&kind) {
+            let start = node.start_position().row;
+            let end = node.end_position();
+            // 以换行符结束的节点不包括下一行
+            let end = if end.column == 0 && end.row > start { end.row - 1 } else { end.row };
+            if end > start {
+                ranges.push((start, end));
+            }
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                ranges.sort_by_key(|&(start, end)| (start, std::cmp::Reverse(end)));
+                ranges.dedup();
+                return ranges;
+            }
+        }
+    }
+}
+
+/// 包含 `node` 的最内层的 `kinds` 节点；`node` 在属性或装饰器中时为它们所属的定义
+fn enclosing<'a>(language: SyntaxLanguage, node: Node<'a>, kinds: &[&str]) -> Option<Node<'a>> {
+    let mut node = Some(node);
+    while let Some(current) = node {
+        if kinds.contains(&current.kind()) {
+            return Some(current);
+        }
+        let definition = if current.kind() == "decorated_definition" {
+            current.child_by_field_name("definition")
+        } else if language.is_attached(&current) {
+            let mut next = current.next_sibling();
+            while let Some(sibling) = next.filter(|sibling| language.is_attached(sibling)) {
+                next = sibling.next_sibling();
+            }
+            next
+        } else {
+            None
+        };
+        if let Some(definition) = definition.filter(|definition| kinds.contains(&definition.kind())) {
+            return Some(definition);
+        }
+        node = current.parent();
+    }
+    None
+}
+
+/// 定义体的字节范围：花括号之间的内容，没有花括号时为整个代码块
+fn body_range(node: &Node) -> Option<(usize, usize)> {
+    let body = node.child_by_field_name("body").or_else(|| braced_descendant(node))?;
+    let count = body.child_count();
+    let (open, close) = (body.child(0)?, body.child(count.saturating_sub(1))?);
+    if count >= 2 && open.kind() == "{" && close.kind() == "}" {
+        Some((open.end_byte(), close.start_byte()))
+    } else {
+        Some((body.start_byte(), body.end_byte()))
+    }
+}
+
+/// 第一个（先序遍历）直接包含 `{` 和 `}` 的节点，如 Go 的 `type T struct { ... }`
+fn braced_descendant<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    let mut cursor = node.walk();
+    let children: Vec<Node<'a>> = node.children(&mut cursor).collect();
+    if children.iter().any(|child| child.kind() == "{") && children.iter().any(|child| child.kind() == "}") {
+        return Some(*node);
+    }
+    children.iter().find_map(braced_descendant)
+}
+
+/// 去掉范围两端的空白，只有空白时返回 None
+fn trim(rope: &Rope, start: usize, end: usize) -> Option<(usize, usize)> {
+    let start = start + rope.chars_at(start).take(end - start).take_while(|c| c.is_whitespace()).count();
+    if start >= end {
+        return None;
+    }
+    let end = end - rope.chars_at(end).reversed().take(end - start).take_while(|c| c.is_whitespace()).count();
+    Some((start, end))
+}
+
+/// 范围前面到行首、后面到行尾都只有空白时，扩展为整行（包括最后一行的换行符）
+fn expand_to_lines(rope: &Rope, start: usize, end: usize) -> (usize, usize) {
+    let start_line = rope.char_to_line(start);
+    let line_start = rope.line_to_char(start_line);
+    let before_blank = rope.slice(line_start..start).chars().all(|c| c == ' ' || c == '\t');
+    let end_line = rope.char_to_line(end);
+    let next_line = if end_line + 1 < rope.len_lines() {
+        rope.line_to_char(end_line + 1)
+    } else {
+        rope.len_chars()
+    };
+    let after_blank = rope.slice(end..next_line).chars().all(char::is_whitespace);
+    if before_blank && after_blank {
+        (line_start, next_line)
+    } else {
+        (start, end)
+    }
+}
+
+/// 光标在行首的缩进中时，移到这一行的第一个非空白字符
+fn first_non_blank_if_indent(rope: &Rope, char_idx: usize) -> usize {
+    let line_start = rope.line_to_char(rope.char_to_line(char_idx));
+    if !rope.slice(line_start..char_idx).chars().all(|c| c == ' ' || c == '\t') {
+        return char_idx;
+    }
+    let indent = rope.chars_at(char_idx).take_while(|c| *c == ' ' || *c == '\t').count();
+    char_idx + indent
+}
+
+/// 范围是否由整行组成（从行首开始，到行首或文件末尾结束）
+pub fn covers_lines(buffer: &Buffer, start: usize, end: usize) -> bool {
+    let rope = buffer.rope();
+    let at_line_start = |idx: usize| idx == rope.line_to_char(rope.char_to_line(idx));
+    start < end && at_line_start(start) && (end == rope.len_chars() || at_line_start(end))
+}
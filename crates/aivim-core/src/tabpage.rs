@@ -0,0 +1,103 @@
+//! 标签页模块
+//!
+//! 每个标签页有自己的窗口布局（一个 WindowManager），标签页之间互不影响：
+//! - :tabnew 在当前标签页之后新建标签页并切换过去，:tabclose 关闭当前标签页
+//! - gt / gT 切换到后一个 / 前一个标签页（首尾相接），`{count}gt` 切换到第 count 个
+//! - 有多个标签页时屏幕顶部显示标签栏
+//!
+//! 编辑器的窗口就是当前标签页的窗口，这里只保存其他标签页，
+//! 切换标签页时把两者交换（见 Editor::goto_tab）
+
+use crate::window::WindowManager;
+
+/// 标签栏中一个标签页的显示信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabLabel {
+    /// 标签页当前窗口的缓冲区名，没有名称时为 None
+    pub title: Option<String>,
+    /// 标签页中是否有窗口显示已修改的缓冲区
+    pub modified: bool,
+    /// 标签页中的窗口数
+    pub windows: usize,
+}
+
+/// 当前标签页以外的所有标签页
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TabPages {
+    /// 按顺序排列的其他标签页，不包括当前标签页
+    pages: Vec<WindowManager>,
+    /// 当前标签页的位置（从 0 开始），即当前标签页之前的标签页数
+    current: usize,
+}
+
+impl TabPages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 标签页总数（包括当前标签页）
+    pub fn len(&self) -> usize {
+        self.pages.len() + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// 当前标签页的位置（从 0 开始）
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// 按顺序排列的所有标签页，`current` 为当前标签页的窗口
+    pub fn all<'a>(&'a self, current: &'a WindowManager) -> Vec<&'a WindowManager> {
+        let mut result: Vec<&WindowManager> = self.pages.iter().collect();
+        result.insert(self.current, current);
+        result
+    }
+
+    /// 其他标签页的窗口
+    pub fn others_mut(&mut self) -> impl Iterator<Item = &mut WindowManager> {
+        self.pages.iter_mut()
+    }
+
+    /// 在当前标签页之后插入新标签页并切换过去，`current` 为当前标签页的窗口，换成 `new`
+    pub fn insert(&mut self, current: &mut WindowManager, new: WindowManager) {
+        let old = std::mem::replace(current, new);
+        self.pages.insert(self.current, old);
+        self.current += 1;
+    }
+
+    /// 切换到第 `index` 个标签页，`current` 与它交换；位置无效时返回 false
+    pub fn switch(&mut self, current: &mut WindowManager, index: usize) -> bool {
+        if index >= self.len() || index == self.current {
+            return false;
+        }
+        // 目标标签页在 pages 中的位置
+        let position = if index < self.current { index } else { index - 1 };
+        std::mem::swap(current, &mut self.pages[position]);
+        let old = self.pages.remove(position);
+        // 原来的当前标签页放回它在 pages（此时不包括目标标签页）中的位置
+        let old_position = if self.current < index { self.current } else { self.current - 1 };
+        self.pages.insert(old_position, old);
+        self.current = index;
+        true
+    }
+
+    /// 关闭当前标签页，切换到后一个（没有时为前一个）标签页；只有一个标签页时返回 false
+    pub fn close(&mut self, current: &mut WindowManager) -> bool {
+        if self.pages.is_empty() {
+            return false;
+        }
+        let position = self.current.min(self.pages.len() - 1);
+        *current = self.pages.remove(position);
+        self.current = position;
+        true
+    }
+
+    /// 关闭其他所有标签页
+    pub fn only(&mut self) {
+        self.pages.clear();
+        self.current = 0;
+    }
+}
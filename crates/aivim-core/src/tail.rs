@@ -0,0 +1,88 @@
+//! 文件跟踪（tail/follow）模块
+//!
+//! 实现 :tail 命令：类似 `less +F`，持续读取文件末尾新追加的内容。
+//! 记录已读取的字节偏移量，每次轮询只读取新增部分；
+//! 文件被截断或轮转（长度变小）时需要整体重新加载。
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::buffer::BufferId;
+
+/// 一次轮询的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum TailUpdate {
+    /// 文件没有变化
+    Unchanged,
+    /// 文件末尾追加了新内容
+    Appended(String),
+    /// 文件被截断或替换，需要重新加载
+    Truncated,
+}
+
+/// 被跟踪文件的读取状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct TailState {
+    /// 跟踪的缓冲区
+    pub buffer: BufferId,
+    /// 已读取的字节数
+    offset: u64,
+    /// 开始跟踪前缓冲区是否只读（停止跟踪时恢复）
+    was_read_only: bool,
+}
+
+impl TailState {
+    pub fn new(buffer: BufferId, offset: u64, was_read_only: bool) -> Self {
+        Self {
+            buffer,
+            offset,
+            was_read_only,
+        }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn was_read_only(&self) -> bool {
+        self.was_read_only
+    }
+
+    /// 重置偏移量（重新加载整个文件之后调用）
+    pub fn reset(&mut self, offset: u64) {
+        self.offset = offset;
+    }
+
+    /// 读取上次偏移量之后追加的内容
+    ///
+    /// 末尾不完整的 UTF-8 字符留到下次读取
+    pub fn poll(&mut self, path: &Path) -> io::Result<TailUpdate> {
+        let len = path.metadata()?.len();
+        if len < self.offset {
+            return Ok(TailUpdate::Truncated);
+        }
+        if len == self.offset {
+            return Ok(TailUpdate::Unchanged);
+        }
+
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut bytes)?;
+
+        // 末尾是被截断的多字节字符时只消费完整部分
+        if let Err(e) = std::str::from_utf8(&bytes) {
+            if e.error_len().is_none() {
+                bytes.truncate(e.valid_up_to());
+            }
+        }
+        if bytes.is_empty() {
+            return Ok(TailUpdate::Unchanged);
+        }
+
+        self.offset += bytes.len() as u64;
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        Ok(TailUpdate::Appended(text))
+    }
+}
@@ -0,0 +1,254 @@
+//! 终端缓冲区 (:terminal)
+//!
+//! 在伪终端 (PTY) 中运行 shell 或命令，输出显示在普通的窗口中：
+//! - 后台线程读取 PTY 的输出并唤醒事件循环，主线程用 vt100 解析，
+//!   把滚动历史和屏幕的文本写入缓冲区，窗口、搜索、复制等都按普通的只读缓冲区处理
+//! - Terminal 模式（在终端缓冲区中按 `i` / `a`）下按键发送给程序，`Ctrl+\ Ctrl+N` 回到 Normal 模式，
+//!   Normal 模式下可以在滚动历史中移动、搜索和复制
+//! - PTY 的大小跟随显示它的窗口；程序退出后缓冲区保留，最后一行显示退出码
+//! - 删除缓冲区或退出编辑器时结束程序
+
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+
+use crate::buffer::BufferId;
+use crate::mapping::MapKey;
+use crate::waker::Waker;
+
+/// 滚动历史最多保留的行数
+pub const TERMINAL_SCROLLBACK: usize = 1000;
+
+/// 一个终端缓冲区中运行的程序
+pub struct TerminalJob {
+    buffer: BufferId,
+    /// 启动的命令，交互式 shell 时为 shell 的路径
+    command: String,
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    receiver: Receiver<Vec<u8>>,
+    parser: vt100::Parser,
+    /// 程序的退出码，还在运行时为 None
+    exit_code: Option<u32>,
+}
+
+impl std::fmt::Debug for TerminalJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TerminalJob")
+            .field("buffer", &self.buffer)
+            .field("command", &self.command)
+            .field("exit_code", &self.exit_code)
+            .finish()
+    }
+}
+
+impl TerminalJob {
+    /// 在大小为 `rows` x `cols` 的 PTY 中启动 `shell`；`command` 不为空时用 `shell -c` 运行它
+    pub fn spawn(
+        buffer: BufferId,
+        shell: &str,
+        command: Option<&str>,
+        cwd: &Path,
+        (rows, cols): (u16, u16),
+        waker: Waker,
+    ) -> io::Result<Self> {
+        let size = PtySize { rows: rows.max(1), cols: cols.max(1), pixel_width: 0, pixel_height: 0 };
+        let pair = native_pty_system().openpty(size).map_err(pty_error)?;
+        let mut builder = CommandBuilder::new(shell);
+        if let Some(command) = command {
+            builder.arg(if cfg!(windows) { "/C" } else { "-c" });
+            builder.arg(command);
+        }
+        builder.cwd(cwd);
+        builder.env("TERM", "xterm-256color");
+        let child = pair.slave.spawn_command(builder).map_err(pty_error)?;
+        // 只保留主端，程序退出后读取才会结束
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader().map_err(pty_error)?;
+        let writer = pair.master.take_writer().map_err(pty_error)?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) | Err(_) => break,
+                    Ok(len) => {
+                        if sender.send(chunk[..len].to_vec()).is_err() {
+                            break;
+                        }
+                        waker.wake();
+                    }
+                }
+            }
+            // 读取结束（程序退出），让主线程检查退出码
+            waker.wake();
+        });
+
+        Ok(Self {
+            buffer,
+            command: command.unwrap_or(shell).to_string(),
+            master: pair.master,
+            writer,
+            child,
+            receiver,
+            parser: vt100::Parser::new(size.rows, size.cols, TERMINAL_SCROLLBACK),
+            exit_code: None,
+        })
+    }
+
+    pub fn buffer(&self) -> BufferId {
+        self.buffer
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// 程序还在运行
+    pub fn is_running(&self) -> bool {
+        self.exit_code.is_none()
+    }
+
+    /// 程序的退出码，还在运行时为 None
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    /// PTY 的大小（行, 列）
+    pub fn size(&self) -> (u16, u16) {
+        self.parser.screen().size()
+    }
+
+    /// 调整 PTY 的大小，程序收到 SIGWINCH
+    pub fn resize(&mut self, rows: u16, cols: u16) -> io::Result<()> {
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        if self.size() == (rows, cols) {
+            return Ok(());
+        }
+        self.parser.set_size(rows, cols);
+        self.master
+            .resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+            .map_err(pty_error)
+    }
+
+    /// 把字节写给程序
+    pub fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.writer.flush()
+    }
+
+    /// 把按键发送给程序
+    pub fn send_key(&mut self, key: MapKey) -> io::Result<()> {
+        match key_bytes(key, self.parser.screen().application_cursor()) {
+            Some(bytes) => self.write(&bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// 粘贴文本：程序开启了括号粘贴时用括号包围，换行按回车发送
+    pub fn paste(&mut self, text: &str) -> io::Result<()> {
+        let text = text.replace("\r\n", "\r").replace('\n', "\r");
+        if self.parser.screen().bracketed_paste() {
+            self.write(format!("\x1b[200~{}\x1b[201~", text).as_bytes())
+        } else {
+            self.write(text.as_bytes())
+        }
+    }
+
+    /// 取出程序的新输出并检查是否已退出，返回屏幕内容是否变化
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(bytes) = self.receiver.try_recv() {
+            self.parser.process(&bytes);
+            changed = true;
+        }
+        if self.exit_code.is_none() {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                self.exit_code = Some(status.exit_code());
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// 滚动历史和屏幕的所有行，从最早的历史开始
+    pub fn lines(&mut self) -> Vec<String> {
+        let (rows, cols) = self.size();
+        let rows = usize::from(rows);
+        self.parser.set_scrollback(usize::MAX);
+        let mut offset = self.parser.screen().scrollback();
+        let mut lines = Vec::with_capacity(offset + rows);
+        // 每次向下滚动一屏，取出这一屏中还没有取出的行
+        while offset > 0 {
+            self.parser.set_scrollback(offset);
+            let take = offset.min(rows);
+            lines.extend(self.parser.screen().rows(0, cols).take(take));
+            offset -= take;
+        }
+        self.parser.set_scrollback(0);
+        lines.extend(self.parser.screen().rows(0, cols));
+        lines
+    }
+
+    /// 终端光标在屏幕上的位置（行, 列）
+    pub fn cursor(&self) -> (usize, usize) {
+        let (row, col) = self.parser.screen().cursor_position();
+        (usize::from(row), usize::from(col))
+    }
+}
+
+impl Drop for TerminalJob {
+    fn drop(&mut self) {
+        if self.is_running() {
+            let _ = self.child.kill();
+            let _ = self.child.try_wait();
+        }
+    }
+}
+
+fn pty_error(error: impl std::fmt::Display) -> io::Error {
+    io::Error::other(error.to_string())
+}
+
+/// 按键发送给程序的字节，`application_cursor` 为程序是否开启了应用光标键模式
+pub fn key_bytes(key: MapKey, application_cursor: bool) -> Option<Vec<u8>> {
+    let arrow = |code: char| {
+        let prefix = if application_cursor { "\x1bO" } else { "\x1b[" };
+        format!("{}{}", prefix, code).into_bytes()
+    };
+    let bytes = match key {
+        MapKey::Char(c) => c.to_string().into_bytes(),
+        MapKey::Ctrl(c) => {
+            let byte = match c {
+                'a'..='z' => c as u8 - b'a' + 1,
+                ' ' | '@' => 0,
+                '[' => 0x1b,
+                '\\' => 0x1c,
+                ']' => 0x1d,
+                '^' => 0x1e,
+                '_' => 0x1f,
+                _ => return None,
+            };
+            vec![byte]
+        }
+        MapKey::Enter => b"\r".to_vec(),
+        MapKey::Esc => b"\x1b".to_vec(),
+        MapKey::Tab => b"\t".to_vec(),
+        MapKey::Backspace => b"\x7f".to_vec(),
+        MapKey::Up => arrow('A'),
+        MapKey::Down => arrow('B'),
+        MapKey::Right => arrow('C'),
+        MapKey::Left => arrow('D'),
+        MapKey::Home => arrow('H'),
+        MapKey::End => arrow('F'),
+        MapKey::PageUp => b"\x1b[5~".to_vec(),
+        MapKey::PageDown => b"\x1b[6~".to_vec(),
+        MapKey::BackTab => b"\x1b[Z".to_vec(),
+    };
+    Some(bytes)
+}
@@ -1,12 +1,18 @@
-/// 文本对象（Text Objects）
-///
-/// 实现 Vim 风格的文本对象，如 aw, iw, as, is 等
-///
-/// 文本对象定义了一个文本范围，可以与操作符（d, y, c）配合使用
-
-use crate::buffer::Buffer;
+//! 文本对象（Text Objects）
+//!
+//! 实现 Vim 风格的文本对象，如 aw, iw, as, is 等
+//!
+//! 文本对象定义了一个文本范围，可以与操作符（d, y, c）配合使用。
+//! af/if、ac/ic 按语法树（见 syntax 模块）查找函数和类型定义，没有语法树的文件中无效；
+//! a(/i(、a[/i[、a{/i{、a</i< 查找包含光标的括号对（可以跨行），a"/i"、a'/i'、a`/i` 查找同一行中的引号对
+
+use crate::buffer::{line_content_len, Buffer};
 use crate::cursor::Cursor;
+use crate::motion::is_word_char;
+use crate::syntax::{self, SyntaxObject};
+use ropey::RopeSlice;
 
+/// 判断是否是单词字符
 /// 文本对象类型
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextObject {
@@ -22,6 +28,26 @@ pub enum TextObject {
     AroundParagraph,
     /// Inner Paragraph - 仅段落本身
     InnerParagraph,
+    /// Around Tag - 标签对及其内容（如 `<a>text</a>`）
+    AroundTag,
+    /// Inner Tag - 仅标签对之间的内容
+    InnerTag,
+    /// Around Function - 整个函数（连同属性、装饰器）
+    AroundFunction,
+    /// Inner Function - 函数体
+    InnerFunction,
+    /// Around Class - 整个类型定义
+    AroundClass,
+    /// Inner Class - 类型定义体
+    InnerClass,
+    /// Around Bracket - 括号对及其内容，字符为左括号（`(`、`[`、`{`、`<`）
+    AroundBracket(char),
+    /// Inner Bracket - 仅括号之间的内容
+    InnerBracket(char),
+    /// Around Quote - 引号对及其内容，连同后面（没有时为前面）的空白
+    AroundQuote(char),
+    /// Inner Quote - 仅引号之间的内容
+    InnerQuote(char),
 }
 
 impl TextObject {
@@ -34,25 +60,93 @@ impl TextObject {
             TextObject::InnerSentence => Self::get_inner_sentence_range(cursor, buffer),
             TextObject::AroundParagraph => Self::get_around_paragraph_range(cursor, buffer),
             TextObject::InnerParagraph => Self::get_inner_paragraph_range(cursor, buffer),
+            TextObject::AroundTag => Self::get_tag_range(cursor, buffer, true),
+            TextObject::InnerTag => Self::get_tag_range(cursor, buffer, false),
+            TextObject::AroundFunction => Self::get_syntax_range(cursor, buffer, SyntaxObject::Function, true),
+            TextObject::InnerFunction => Self::get_syntax_range(cursor, buffer, SyntaxObject::Function, false),
+            TextObject::AroundClass => Self::get_syntax_range(cursor, buffer, SyntaxObject::Class, true),
+            TextObject::InnerClass => Self::get_syntax_range(cursor, buffer, SyntaxObject::Class, false),
+            TextObject::AroundBracket(open) => Self::get_bracket_range(cursor, buffer, *open, true),
+            TextObject::InnerBracket(open) => Self::get_bracket_range(cursor, buffer, *open, false),
+            TextObject::AroundQuote(quote) => Self::get_quote_range(cursor, buffer, *quote, true),
+            TextObject::InnerQuote(quote) => Self::get_quote_range(cursor, buffer, *quote, false),
+        }
+    }
+
+    /// 按语法树查找的文本对象（af、if、ac、ic）
+    fn get_syntax_range(
+        cursor: &Cursor,
+        buffer: &Buffer,
+        object: SyntaxObject,
+        around: bool,
+    ) -> Option<(usize, usize)> {
+        syntax::object_range(buffer, cursor.to_char_idx(buffer), object, around)
+    }
+
+    /// 是否按语法树查找
+    pub fn is_syntax(&self) -> bool {
+        matches!(
+            self,
+            TextObject::AroundFunction | TextObject::InnerFunction | TextObject::AroundClass | TextObject::InnerClass
+        )
+    }
+
+    /// 获取 a( / i( 等括号文本对象的范围
+    ///
+    /// i{ 的内容从左括号的下一行开始、右括号单独在一行时到它的上一行结束（按行操作多行的代码块）
+    fn get_bracket_range(cursor: &Cursor, buffer: &Buffer, open: char, around: bool) -> Option<(usize, usize)> {
+        let (open_idx, close_idx) = bracket_pair(buffer, cursor.to_char_idx(buffer), open)?;
+        if around {
+            return Some((open_idx, close_idx + 1));
         }
+        let mut start = open_idx + 1;
+        let mut end = close_idx;
+        if start < end && buffer.char(start) == '\n' {
+            start += 1;
+            let close_line = buffer.char_to_line(close_idx);
+            let close_line_start = buffer.line_to_char(close_line);
+            if close_line_start >= start && buffer.slice(close_line_start..close_idx).chars().all(char::is_whitespace) {
+                end = close_line_start;
+            }
+        }
+        Some((start, end.max(start)))
+    }
+
+    /// 获取 a" / i" 等引号文本对象的范围
+    fn get_quote_range(cursor: &Cursor, buffer: &Buffer, quote: char, around: bool) -> Option<(usize, usize)> {
+        let (open_idx, close_idx) = quote_pair(buffer, cursor, quote)?;
+        if !around {
+            return Some((open_idx + 1, close_idx));
+        }
+        let line = buffer.line(cursor.line)?;
+        let line_start = buffer.line_to_char(cursor.line);
+        let len = line_content_len(line);
+        let is_blank = |c: char| c == ' ' || c == '\t';
+
+        let after = line
+            .chars_at(close_idx + 1 - line_start)
+            .take(len.saturating_sub(close_idx + 1 - line_start))
+            .take_while(|c| is_blank(*c))
+            .count();
+        if after > 0 {
+            return Some((open_idx, close_idx + 1 + after));
+        }
+        let before = line.chars_at(open_idx - line_start).reversed().take_while(|c| is_blank(*c)).count();
+        Some((open_idx - before, close_idx + 1))
     }
 
     /// 获取 aw（around word）的范围
     /// 包括当前单词及其后的一个空格（如果有）
     fn get_around_word_range(cursor: &Cursor, buffer: &Buffer) -> Option<(usize, usize)> {
-        let line_text = buffer.line(cursor.line)?.to_string();
+        let line = buffer.line(cursor.line)?;
         let line_start = buffer.line_to_char(cursor.line);
-        let col = cursor.column.min(line_text.len().saturating_sub(1));
 
         // 找到当前单词的边界
-        let (word_start, word_end) = Self::find_word_boundaries(&line_text, col)?;
+        let (word_start, word_end) = Self::find_word_boundaries(line, cursor.column)?;
 
         // 检查单词后是否有空格
-        let has_trailing_space = word_end < line_text.len()
-            && line_text[word_end..].starts_with(' ');
-
-        let end = if has_trailing_space {
-            word_end + 1  // 包含后面的空格
+        let end = if line.get_char(word_end) == Some(' ') {
+            word_end + 1 // 包含后面的空格
         } else {
             word_end
         };
@@ -63,52 +157,48 @@ impl TextObject {
     /// 获取 iw（inner word）的范围
     /// 仅包括当前单词本身
     fn get_inner_word_range(cursor: &Cursor, buffer: &Buffer) -> Option<(usize, usize)> {
-        let line_text = buffer.line(cursor.line)?.to_string();
+        let line = buffer.line(cursor.line)?;
         let line_start = buffer.line_to_char(cursor.line);
-        let col = cursor.column.min(line_text.len().saturating_sub(1));
 
         // 找到当前单词的边界
-        let (word_start, word_end) = Self::find_word_boundaries(&line_text, col)?;
+        let (word_start, word_end) = Self::find_word_boundaries(line, cursor.column)?;
 
         Some((line_start + word_start, line_start + word_end))
     }
 
-    /// 找到单词的边界（start, end）
-    fn find_word_boundaries(line_text: &str, col: usize) -> Option<(usize, usize)> {
-        let chars: Vec<char> = line_text.chars().collect();
-        let col = col.min(chars.len().saturating_sub(1));
-
-        // 确定当前位置的字符类型
-        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    /// 找到单词的边界（start, end），均为行内字符索引
+    ///
+    /// 直接在 RopeSlice 上迭代字符，不复制行文本。
+    /// 空行或光标之后没有单词时返回 None。
+    fn find_word_boundaries(line: RopeSlice, col: usize) -> Option<(usize, usize)> {
+        let len = line_content_len(line);
+        if len == 0 {
+            return None;
+        }
+        let col = col.min(len - 1);
 
-        // 如果当前位置是空白字符，向前找单词
-        let start_col = if col < chars.len() && !is_word_char(chars[col]) {
-            // 向前查找单词开始
-            chars.iter()
-                .enumerate()
-                .skip(col)
-                .find(|(_, c)| is_word_char(**c))
-                .map(|(i, _)| i)?
-        } else {
+        // 如果当前位置不是单词字符，向后找到下一个单词的开头
+        let start_col = if is_word_char(line.char(col)) {
             col
+        } else {
+            col + line.chars_at(col).take(len - col).position(is_word_char)?
         };
 
         // 找到单词开始位置（向前查找）
-        let word_start = chars[..=start_col]
-            .iter()
-            .enumerate()
-            .rev()
-            .find(|(_, c)| !is_word_char(**c))
-            .map(|(i, _)| i + 1)
-            .unwrap_or(0);
+        let word_start = start_col
+            - line
+                .chars_at(start_col)
+                .reversed()
+                .take_while(|c| is_word_char(*c))
+                .count();
 
         // 找到单词结束位置（向后查找）
-        let word_end = chars[start_col..]
-            .iter()
-            .enumerate()
-            .find(|(_, c)| !is_word_char(**c))
-            .map(|(i, _)| start_col + i)
-            .unwrap_or(chars.len());
+        let word_end = start_col
+            + line
+                .chars_at(start_col)
+                .take(len - start_col)
+                .take_while(|c| is_word_char(*c))
+                .count();
 
         Some((word_start, word_end))
     }
@@ -126,16 +216,294 @@ impl TextObject {
     }
 
     /// 获取 ap（around paragraph）的范围
-    fn get_around_paragraph_range(_cursor: &Cursor, _buffer: &Buffer) -> Option<(usize, usize)> {
-        // TODO: 实现段落文本对象
-        None
+    fn get_around_paragraph_range(cursor: &Cursor, buffer: &Buffer) -> Option<(usize, usize)> {
+        let (first, last) = Self::paragraph_lines(cursor, buffer)?;
+        let total_lines = Self::content_lines(buffer);
+        let blank = Self::is_blank_line(buffer, first);
+
+        // 包含段落后面的空行（或空行块后面的段落）；没有时向前扩展
+        let mut end_line = last;
+        while end_line + 1 < total_lines && Self::is_blank_line(buffer, end_line + 1) != blank {
+            end_line += 1;
+        }
+        let mut start_line = first;
+        if end_line == last {
+            while start_line > 0 && Self::is_blank_line(buffer, start_line - 1) != blank {
+                start_line -= 1;
+            }
+        }
+
+        Some(Self::lines_to_range(buffer, start_line, end_line))
     }
 
     /// 获取 ip（inner paragraph）的范围
-    fn get_inner_paragraph_range(_cursor: &Cursor, _buffer: &Buffer) -> Option<(usize, usize)> {
-        // TODO: 实现段落文本对象
-        None
+    /// 光标所在的连续非空行（光标在空行上时为连续空行）
+    fn get_inner_paragraph_range(cursor: &Cursor, buffer: &Buffer) -> Option<(usize, usize)> {
+        let (first, last) = Self::paragraph_lines(cursor, buffer)?;
+        Some(Self::lines_to_range(buffer, first, last))
+    }
+
+    /// 光标所在段落的首行和末行
+    fn paragraph_lines(cursor: &Cursor, buffer: &Buffer) -> Option<(usize, usize)> {
+        let total_lines = Self::content_lines(buffer);
+        if total_lines == 0 {
+            return None;
+        }
+        let line = cursor.line.min(total_lines - 1);
+        let blank = Self::is_blank_line(buffer, line);
+
+        let mut first = line;
+        while first > 0 && Self::is_blank_line(buffer, first - 1) == blank {
+            first -= 1;
+        }
+        let mut last = line;
+        while last + 1 < total_lines && Self::is_blank_line(buffer, last + 1) == blank {
+            last += 1;
+        }
+        Some((first, last))
+    }
+
+    /// 有内容的行数（不计文件末尾换行符之后的空行）
+    fn content_lines(buffer: &Buffer) -> usize {
+        let lines = buffer.len_lines();
+        if buffer.len_chars() > 0 && buffer.char(buffer.len_chars() - 1) == '\n' {
+            lines - 1
+        } else if buffer.is_empty() {
+            0
+        } else {
+            lines
+        }
+    }
+
+    fn is_blank_line(buffer: &Buffer, line_idx: usize) -> bool {
+        buffer
+            .line(line_idx)
+            .map(|line| line.chars().all(char::is_whitespace))
+            .unwrap_or(true)
+    }
+
+    /// 整行范围（包含最后一行的换行符）
+    fn lines_to_range(buffer: &Buffer, first: usize, last: usize) -> (usize, usize) {
+        let start = buffer.line_to_char(first);
+        let end = if last + 1 < buffer.len_lines() {
+            buffer.line_to_char(last + 1)
+        } else {
+            buffer.len_chars()
+        };
+        (start, end)
+    }
+
+    /// 与操作符组合时是否按整行处理（段落）
+    pub fn is_linewise(&self) -> bool {
+        matches!(self, TextObject::AroundParagraph | TextObject::InnerParagraph)
+    }
+}
+
+/// 标签类型
+#[derive(Debug, Clone, PartialEq)]
+enum TagKind {
+    Open(String),
+    Close(String),
+}
+
+/// 扫描到的标签（字符索引范围为 [start, end)）
+#[derive(Debug, Clone)]
+struct Tag {
+    kind: TagKind,
+    start: usize,
+    end: usize,
+}
+
+impl TextObject {
+    /// 获取 at / it（around/inner tag）的范围
+    ///
+    /// 找到包含光标的最内层 `<tag>...</tag>` 对，支持嵌套和同名标签嵌套
+    fn get_tag_range(cursor: &Cursor, buffer: &Buffer, around: bool) -> Option<(usize, usize)> {
+        if buffer.is_empty() {
+            return None;
+        }
+        let pos = cursor.to_char_idx(buffer).min(buffer.len_chars() - 1);
+
+        let tags = Self::scan_tags(buffer);
+
+        // 用栈匹配开闭标签，得到所有标签对 (open, close)
+        let mut stack: Vec<&Tag> = Vec::new();
+        let mut pairs: Vec<(&Tag, &Tag)> = Vec::new();
+        for tag in &tags {
+            match &tag.kind {
+                TagKind::Open(_) => stack.push(tag),
+                TagKind::Close(name) => {
+                    // 找到最近的同名开标签，中间未闭合的标签被丢弃
+                    let matching = stack.iter().rposition(|open| {
+                        matches!(&open.kind, TagKind::Open(open_name) if open_name == name)
+                    });
+                    if let Some(idx) = matching {
+                        let open = stack[idx];
+                        stack.truncate(idx);
+                        pairs.push((open, tag));
+                    }
+                }
+            }
+        }
+
+        // 选择包含光标的最内层标签对
+        let (open, close) = pairs
+            .into_iter()
+            .filter(|(open, close)| open.start <= pos && pos < close.end)
+            .min_by_key(|(open, close)| close.end - open.start)?;
+
+        if around {
+            Some((open.start, close.end))
+        } else {
+            Some((open.end, close.start))
+        }
+    }
+
+    /// 扫描缓冲区中的所有开/闭标签（忽略自闭合标签、注释和声明）
+    fn scan_tags(buffer: &Buffer) -> Vec<Tag> {
+        let mut tags = Vec::new();
+        let mut chars = buffer.rope().chars().enumerate().peekable();
+
+        while let Some((start, ch)) = chars.next() {
+            if ch != '<' {
+                continue;
+            }
+
+            let mut body = String::new();
+            let mut end = None;
+            while let Some(&(idx, c)) = chars.peek() {
+                if c == '<' {
+                    // 不完整的标签，从新的 '<' 重新开始
+                    break;
+                }
+                chars.next();
+                if c == '>' {
+                    end = Some(idx + 1);
+                    break;
+                }
+                body.push(c);
+            }
+
+            let Some(end) = end else {
+                continue;
+            };
+            if body.starts_with('!') || body.starts_with('?') || body.ends_with('/') {
+                continue;
+            }
+
+            let (is_close, rest) = match body.strip_prefix('/') {
+                Some(rest) => (true, rest),
+                None => (false, body.as_str()),
+            };
+            let name: String = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+                .collect();
+            if name.is_empty() {
+                continue;
+            }
+
+            let kind = if is_close {
+                TagKind::Close(name)
+            } else {
+                TagKind::Open(name)
+            };
+            tags.push(Tag { kind, start, end });
+        }
+
+        tags
+    }
+}
+
+/// 左括号对应的右括号
+pub fn closing_bracket(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '<' => Some('>'),
+        _ => None,
+    }
+}
+
+/// 包含位置 `pos` 的最内层括号对 `open`...`close` 的字符索引（左括号, 右括号）
+///
+/// 光标在括号上时就是这个括号所在的括号对；嵌套的括号按深度匹配
+pub fn bracket_pair(buffer: &Buffer, pos: usize, open: char) -> Option<(usize, usize)> {
+    let close = closing_bracket(open)?;
+    if buffer.is_empty() {
+        return None;
     }
+    let rope = buffer.rope();
+    let pos = pos.min(buffer.len_chars() - 1);
+
+    let open_idx = if rope.char(pos) == open {
+        pos
+    } else {
+        // 向前找到没有匹配的左括号（光标在右括号上时从它之前开始）
+        let mut depth = 0usize;
+        let mut found = None;
+        for (offset, c) in rope.chars_at(pos).reversed().enumerate() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                if depth == 0 {
+                    found = Some(pos - 1 - offset);
+                    break;
+                }
+                depth -= 1;
+            }
+        }
+        found?
+    };
+
+    let mut depth = 0usize;
+    for (offset, c) in rope.chars_at(open_idx + 1).enumerate() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                let close_idx = open_idx + 1 + offset;
+                return (close_idx >= pos).then_some((open_idx, close_idx));
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// 光标所在行中包含光标（或光标之后第一个）的引号对的字符索引（左引号, 右引号）
+///
+/// 行内的引号从行首开始两两配对，`\` 转义的引号不算
+pub fn quote_pair(buffer: &Buffer, cursor: &Cursor, quote: char) -> Option<(usize, usize)> {
+    let line = buffer.line(cursor.line)?;
+    let line_start = buffer.line_to_char(cursor.line);
+    let len = line_content_len(line);
+
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+    for (col, c) in line.chars().take(len).enumerate() {
+        if c == quote && !escaped {
+            quotes.push(col);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+
+    let col = cursor.column;
+    let pair = match quotes.iter().position(|&q| q == col) {
+        // 光标在引号上：按它之前的引号个数决定是左引号还是右引号
+        Some(k) if k % 2 == 0 => (quotes[k], *quotes.get(k + 1)?),
+        Some(k) => (quotes[k - 1], quotes[k]),
+        None => {
+            let before = quotes.iter().take_while(|&&q| q < col).count();
+            if before % 2 == 1 {
+                (quotes[before - 1], *quotes.get(before)?)
+            } else {
+                (*quotes.get(before)?, *quotes.get(before + 1)?)
+            }
+        }
+    };
+    Some((line_start + pair.0, line_start + pair.1))
 }
 
 /// 从字符解析文本对象
@@ -147,6 +515,12 @@ pub fn parse_text_object(ch: char) -> Option<TextObject> {
         'S' => Some(TextObject::InnerSentence),
         'p' => Some(TextObject::AroundParagraph),
         'P' => Some(TextObject::InnerParagraph),
+        't' => Some(TextObject::AroundTag),
+        'T' => Some(TextObject::InnerTag),
+        'f' => Some(TextObject::AroundFunction),
+        'F' => Some(TextObject::InnerFunction),
+        'c' => Some(TextObject::AroundClass),
+        'C' => Some(TextObject::InnerClass),
         _ => None,
     }
 }
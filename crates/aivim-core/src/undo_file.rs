@@ -0,0 +1,59 @@
+//! 持久化撤销历史模块
+//!
+//! 开启 `:set undofile` 后，保存文件和关闭缓冲区时把撤销树写入撤销目录，
+//! 再次打开同一个文件时恢复，可以撤销上一次编辑会话中的修改：
+//! - 撤销目录默认为 `$XDG_STATE_HOME/aivim/undo`（未设置时为 `~/.local/state/aivim/undo`），
+//!   可以用 `:set undodir=` 修改
+//! - 撤销文件名为文件绝对路径的哈希值
+//! - 撤销树当前状态的文本与打开的文件内容不一致时（文件在外部被修改过）不恢复
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::undo_tree::{UndoLimits, UndoTree};
+
+/// 默认的撤销目录
+pub fn default_undo_dir() -> PathBuf {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+        .unwrap_or_else(std::env::temp_dir);
+    state_home.join("aivim").join("undo")
+}
+
+/// 文件对应的撤销文件路径
+pub fn undo_file_path(undo_dir: &Path, file: &Path) -> PathBuf {
+    let absolute = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let hash = fnv1a(absolute.to_string_lossy().as_bytes());
+    undo_dir.join(format!("{:016x}.undo", hash))
+}
+
+/// 把撤销树写入文件对应的撤销文件，撤销目录不存在时创建
+pub fn write_undo_file(undo_dir: &Path, file: &Path, tree: &UndoTree) -> io::Result<()> {
+    fs::create_dir_all(undo_dir)?;
+    fs::write(undo_file_path(undo_dir, file), tree.encode())
+}
+
+/// 读取文件对应的撤销历史
+///
+/// 没有撤销文件、格式错误或当前状态与 `text` 不一致时返回 None
+pub fn read_undo_file(undo_dir: &Path, file: &Path, text: &ropey::Rope, limits: UndoLimits) -> Option<UndoTree> {
+    let data = fs::read(undo_file_path(undo_dir, file)).ok()?;
+    let tree = UndoTree::decode(&data, limits)?;
+    let current = tree.current_text()?;
+    // 保存时会给不以换行符结尾的文本补上换行符
+    let mut saved = current.clone();
+    if saved.len_chars() > 0 && saved.char(saved.len_chars() - 1) != '\n' {
+        saved.insert(saved.len_chars(), "\n");
+    }
+    (current == text || saved == *text).then_some(tree)
+}
+
+/// 64 位 FNV-1a 哈希，结果不随 Rust 版本变化
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,496 @@
+//! 撤销树模块
+//!
+//! 每个缓冲区的修改历史保存为一棵树而不是撤销/重做两个栈：
+//! 撤销之后再做新的修改会开出新的分支，旧的分支仍然可以通过 g- / g+ 回到。
+//! - 节点按创建顺序编号（0 为最初的状态），g- / g+ 按编号即时间顺序移动
+//! - u / Ctrl+R 沿父节点和最近经过的子节点移动
+//! - 节点保存 ropey::Rope 的克隆，Rope 的克隆共享未修改的部分，不会复制整个缓冲区
+//!
+//! 历史的大小受 `undolevels`（修改次数）和 `undomaxmem`（内存）限制，
+//! 超出时从最早的状态开始淘汰，当前状态所在分支之外的旧分支一起丢弃。
+//! 内存按每个状态与父状态不同的部分计算，共享的部分不重复计算，大文件的小修改也可以撤销。
+//!
+//! 开启 `undofile` 时撤销树可以编码保存到撤销文件（见 `undo_file` 模块），
+//! 每个状态只保存与父状态不同的中间部分。
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use ropey::Rope;
+
+use crate::cursor::Cursor;
+
+/// 撤销文件格式的标识和版本
+const UNDO_FILE_MAGIC: &str = "aivim-undo 1";
+
+/// 默认最多保留的修改次数
+pub const DEFAULT_UNDO_LEVELS: usize = 1000;
+
+/// 默认每个缓冲区撤销历史的内存上限（MB）
+pub const DEFAULT_UNDO_MAX_MEM: usize = 256;
+
+/// 撤销历史的大小限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoLimits {
+    /// 最多保留的修改次数（状态数减一）
+    pub levels: usize,
+    /// 所有状态修改部分大小之和的上限（字节）
+    ///
+    /// 每个状态按与父状态不同的部分计算，最早的状态与其他状态共享文本，不计入
+    pub max_bytes: usize,
+}
+
+impl Default for UndoLimits {
+    fn default() -> Self {
+        Self {
+            levels: DEFAULT_UNDO_LEVELS,
+            max_bytes: DEFAULT_UNDO_MAX_MEM * 1024 * 1024,
+        }
+    }
+}
+
+/// 撤销树中的一个文本状态
+#[derive(Debug, Clone)]
+struct UndoNode {
+    text: Rope,
+    cursor: Cursor,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// 重做时进入的子节点（最近一次经过的分支）
+    redo_child: Option<usize>,
+    time: Instant,
+    /// 与父节点不同的字节数，根节点为 0
+    bytes: usize,
+}
+
+/// 移动后需要恢复的文本和光标
+#[derive(Debug, Clone)]
+pub struct UndoState {
+    pub text: Rope,
+    pub cursor: Cursor,
+}
+
+/// :undolist 中的一项（一个分支的末端）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoLeaf {
+    /// 节点编号
+    pub seq: usize,
+    /// 从最早保留的状态到该节点的修改次数
+    pub changes: usize,
+    pub time: Instant,
+}
+
+/// 撤销树
+#[derive(Debug, Clone, Default)]
+pub struct UndoTree {
+    /// 按编号排列的节点，最小的编号是根节点
+    nodes: BTreeMap<usize, UndoNode>,
+    current: usize,
+    next_seq: usize,
+    /// 所有节点与父节点不同的字节数之和
+    bytes: usize,
+    limits: UndoLimits,
+}
+
+impl UndoTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(limits: UndoLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    pub fn limits(&self) -> UndoLimits {
+        self.limits
+    }
+
+    /// 修改限制，立即淘汰超出的历史
+    pub fn set_limits(&mut self, limits: UndoLimits) {
+        self.limits = limits;
+        self.evict();
+    }
+
+    /// 当前状态的编号，还没有记录任何状态时为 None
+    pub fn current(&self) -> Option<usize> {
+        (!self.nodes.is_empty()).then_some(self.current)
+    }
+
+    /// 保留的状态数（包括最早的状态）
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// 保留的状态与父状态不同的字节数之和
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// 修改前记录当前文本
+    ///
+    /// 文本与当前节点相同时只更新光标，撤销后光标回到这次修改开始的位置
+    pub fn record(&mut self, text: &Rope, cursor: Cursor) {
+        if !self.seal(text, cursor) {
+            if let Some(node) = self.nodes.get_mut(&self.current) {
+                node.cursor = cursor;
+            }
+        }
+    }
+
+    /// 把尚未记录的修改结果加入树中，返回是否新增了节点
+    fn seal(&mut self, text: &Rope, cursor: Cursor) -> bool {
+        let parent = self.current();
+        let bytes = parent.map_or(0, |current| changed_bytes(&self.nodes[&current].text, text));
+        if parent.is_some() && bytes == 0 {
+            return false;
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.bytes += bytes;
+        self.nodes.insert(
+            seq,
+            UndoNode {
+                text: text.clone(),
+                cursor,
+                parent,
+                children: Vec::new(),
+                redo_child: None,
+                time: Instant::now(),
+                bytes,
+            },
+        );
+        if let Some(parent) = parent.and_then(|parent| self.nodes.get_mut(&parent)) {
+            parent.children.push(seq);
+            parent.redo_child = Some(seq);
+        }
+        self.current = seq;
+        self.evict();
+        true
+    }
+
+    /// 超出限制时从最早的状态开始淘汰，当前状态总是保留
+    fn evict(&mut self) {
+        while self.over_limits() {
+            let Some((&root, _)) = self.nodes.first_key_value() else {
+                break;
+            };
+            if root == self.current {
+                break;
+            }
+            // 保留通向当前状态的子树，其余分支随根节点一起丢弃
+            let mut keep = self.current;
+            while let Some(parent) = self.nodes[&keep].parent.filter(|&parent| parent != root) {
+                keep = parent;
+            }
+            let root_node = self.remove_node(root);
+            for child in root_node.children.into_iter().filter(|&child| child != keep) {
+                self.remove_subtree(child);
+            }
+            if let Some(node) = self.nodes.get_mut(&keep) {
+                node.parent = None;
+                // 成为根节点，文本不再与父节点共享之外的部分单独计算
+                self.bytes -= std::mem::take(&mut node.bytes);
+            }
+        }
+    }
+
+    fn over_limits(&self) -> bool {
+        self.nodes.len() > self.limits.levels + 1 || (self.nodes.len() > 1 && self.bytes > self.limits.max_bytes)
+    }
+
+    fn remove_node(&mut self, seq: usize) -> UndoNode {
+        let node = self.nodes.remove(&seq).expect("undo node exists");
+        self.bytes -= node.bytes;
+        node
+    }
+
+    fn remove_subtree(&mut self, seq: usize) {
+        let mut pending = vec![seq];
+        while let Some(seq) = pending.pop() {
+            pending.extend(self.remove_node(seq).children);
+        }
+    }
+
+    /// 撤销：回到父节点
+    pub fn undo(&mut self, text: &Rope, cursor: Cursor) -> Option<UndoState> {
+        self.seal(text, cursor);
+        let current = self.current;
+        let parent = self.nodes.get(&current)?.parent?;
+        self.nodes.get_mut(&parent)?.redo_child = Some(current);
+        Some(self.move_to(parent))
+    }
+
+    /// 重做：进入最近经过的子节点
+    pub fn redo(&mut self, text: &Rope, cursor: Cursor) -> Option<UndoState> {
+        self.seal(text, cursor);
+        let node = self.nodes.get(&self.current)?;
+        let child = node.redo_child.or_else(|| node.children.last().copied())?;
+        Some(self.move_to(child))
+    }
+
+    /// 按时间顺序前后移动 `steps` 个状态 (g- / g+)，已经到头时返回 None
+    pub fn travel(&mut self, text: &Rope, cursor: Cursor, steps: isize) -> Option<UndoState> {
+        self.seal(text, cursor);
+        if steps == 0 {
+            return None;
+        }
+        let target = if steps < 0 {
+            let mut earlier = self.nodes.range(..self.current).rev().map(|(&seq, _)| seq);
+            earlier.nth(steps.unsigned_abs() - 1).or_else(|| self.nodes.keys().next().copied())
+        } else {
+            let mut later = self.nodes.range(self.current + 1..).map(|(&seq, _)| seq);
+            later.nth(steps as usize - 1).or_else(|| self.nodes.keys().next_back().copied())
+        }?;
+        if target == self.current {
+            return None;
+        }
+        // 让之后的重做沿着到达目标的分支进行
+        let mut node = target;
+        while let Some(parent) = self.nodes[&node].parent {
+            if let Some(parent_node) = self.nodes.get_mut(&parent) {
+                parent_node.redo_child = Some(node);
+            }
+            node = parent;
+        }
+        Some(self.move_to(target))
+    }
+
+    fn move_to(&mut self, seq: usize) -> UndoState {
+        self.current = seq;
+        let node = &self.nodes[&seq];
+        UndoState {
+            text: node.text.clone(),
+            cursor: node.cursor,
+        }
+    }
+
+    /// 所有分支的末端，按编号排序
+    pub fn leaves(&self) -> Vec<UndoLeaf> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| node.parent.is_some() && node.children.is_empty())
+            .map(|(&seq, node)| UndoLeaf {
+                seq,
+                changes: self.depth(seq),
+                time: node.time,
+            })
+            .collect()
+    }
+
+    fn depth(&self, mut seq: usize) -> usize {
+        let mut depth = 0;
+        while let Some(parent) = self.nodes[&seq].parent {
+            depth += 1;
+            seq = parent;
+        }
+        depth
+    }
+
+    /// 当前状态的文本
+    pub fn current_text(&self) -> Option<&Rope> {
+        self.nodes.get(&self.current).map(|node| &node.text)
+    }
+
+    /// 编码为撤销文件的内容
+    ///
+    /// 每个节点一行头部 `node 编号 父节点 重做子节点 光标行 光标列 秒数 前缀 后缀 长度`，
+    /// 后面是与父节点不同的中间部分文本（前缀和后缀为与父节点相同的字节数）
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = format!("{}\ncurrent {} next {}\n", UNDO_FILE_MAGIC, self.current, self.next_seq).into_bytes();
+        let now = Instant::now();
+        for (&seq, node) in &self.nodes {
+            let text = node.text.to_string();
+            let parent_text = node.parent.map(|parent| self.nodes[&parent].text.to_string());
+            let (prefix, suffix) = parent_text.as_deref().map_or((0, 0), |parent| common_affixes(parent, &text));
+            let middle = &text[prefix..text.len() - suffix];
+            let header = format!(
+                "node {} {} {} {} {} {} {} {} {}\n",
+                seq,
+                optional(node.parent),
+                optional(node.redo_child),
+                node.cursor.line,
+                node.cursor.column,
+                now.saturating_duration_since(node.time).as_secs(),
+                prefix,
+                suffix,
+                middle.len()
+            );
+            data.extend_from_slice(header.as_bytes());
+            data.extend_from_slice(middle.as_bytes());
+            data.push(b'\n');
+        }
+        data
+    }
+
+    /// 从撤销文件的内容恢复，格式错误时返回 None
+    pub fn decode(data: &[u8], limits: UndoLimits) -> Option<Self> {
+        let mut rest = data;
+        if next_line(&mut rest)? != UNDO_FILE_MAGIC {
+            return None;
+        }
+        let fields: Vec<&str> = next_line(&mut rest)?.split(' ').collect();
+        let (current, next_seq) = match fields[..] {
+            ["current", current, "next", next] => (current.parse().ok()?, next.parse().ok()?),
+            _ => return None,
+        };
+
+        let mut tree = Self::with_limits(limits);
+        tree.current = current;
+        tree.next_seq = next_seq;
+        let now = Instant::now();
+        while !rest.is_empty() {
+            let fields: Vec<&str> = next_line(&mut rest)?.split(' ').collect();
+            let ["node", seq, parent, redo_child, line, column, age, prefix, suffix, len] = fields[..] else {
+                return None;
+            };
+            let seq: usize = seq.parse().ok()?;
+            let parent = parse_optional(parent)?;
+            let (prefix, suffix, len): (usize, usize, usize) =
+                (prefix.parse().ok()?, suffix.parse().ok()?, len.parse().ok()?);
+            let middle = std::str::from_utf8(rest.get(..len)?).ok()?;
+            rest = rest.get(len..)?.strip_prefix(b"\n")?;
+
+            // 父节点的编号总是更小，已经先解码
+            let text = match parent {
+                Some(parent) if parent < seq => {
+                    let parent_text = tree.nodes.get(&parent)?.text.to_string();
+                    let suffix_start = parent_text.len().checked_sub(suffix)?;
+                    format!("{}{}{}", parent_text.get(..prefix)?, middle, parent_text.get(suffix_start..)?)
+                }
+                Some(_) => return None,
+                None => middle.to_string(),
+            };
+            let age = Duration::from_secs(age.parse().ok()?);
+            let cursor = Cursor::new(line.parse().ok()?, column.parse().ok()?);
+            let text = Rope::from_str(&text);
+            let bytes = parent.map_or(0, |parent| changed_bytes(&tree.nodes[&parent].text, &text));
+            tree.bytes += bytes;
+            tree.nodes.insert(
+                seq,
+                UndoNode {
+                    text,
+                    cursor,
+                    parent,
+                    children: Vec::new(),
+                    redo_child: parse_optional(redo_child)?,
+                    time: now.checked_sub(age).unwrap_or(now),
+                    bytes,
+                },
+            );
+            if let Some(parent) = parent {
+                tree.nodes.get_mut(&parent)?.children.push(seq);
+            }
+        }
+
+        if !tree.nodes.contains_key(&tree.current) || tree.nodes.keys().any(|&seq| seq >= tree.next_seq) {
+            return None;
+        }
+        tree.evict();
+        Some(tree)
+    }
+}
+
+fn optional(seq: Option<usize>) -> String {
+    seq.map_or_else(|| "-".to_string(), |seq| seq.to_string())
+}
+
+fn parse_optional(field: &str) -> Option<Option<usize>> {
+    match field {
+        "-" => Some(None),
+        _ => field.parse().ok().map(Some),
+    }
+}
+
+/// 取出下一行（不含换行符）
+fn next_line<'a>(data: &mut &'a [u8]) -> Option<&'a str> {
+    let end = data.iter().position(|&b| b == b'\n')?;
+    let line = std::str::from_utf8(&data[..end]).ok()?;
+    *data = &data[end + 1..];
+    Some(line)
+}
+
+/// 两段文本相同的开头和结尾的字节数，都落在字符边界上且不重叠
+fn common_affixes(old: &str, new: &str) -> (usize, usize) {
+    let (old_bytes, new_bytes) = (old.as_bytes(), new.as_bytes());
+    let mut prefix = old_bytes.iter().zip(new_bytes).take_while(|(a, b)| a == b).count();
+    while !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old_bytes
+        .iter()
+        .rev()
+        .zip(new_bytes.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+    (prefix, suffix)
+}
+
+/// 两个文本不同的部分的字节数：去掉相同的开头和结尾之后较长的一边，文本相同时为 0
+fn changed_bytes(old: &Rope, new: &Rope) -> usize {
+    fn chunks(rope: &Rope) -> impl Iterator<Item = &[u8]> {
+        rope.chunks().map(str::as_bytes)
+    }
+    fn reversed(rope: &Rope) -> impl Iterator<Item = &[u8]> {
+        let mut chunks = rope.chunks_at_byte(rope.len_bytes()).0;
+        std::iter::from_fn(move || chunks.prev()).map(str::as_bytes)
+    }
+    let longer = old.len_bytes().max(new.len_bytes());
+    let prefix = common_len(chunks(old), chunks(new), false);
+    let max_suffix = old.len_bytes().min(new.len_bytes()) - prefix;
+    let suffix = common_len(reversed(old), reversed(new), true).min(max_suffix);
+    longer - prefix - suffix
+}
+
+/// 两个块序列相同的开头（`from_end` 时为结尾，块按从后向前的顺序）的字节数
+///
+/// Rope 的克隆共享没有修改的块，同一个块直接跳过，不逐字节比较
+fn common_len<'a>(
+    mut old: impl Iterator<Item = &'a [u8]>,
+    mut new: impl Iterator<Item = &'a [u8]>,
+    from_end: bool,
+) -> usize {
+    let (mut a, mut b): (&[u8], &[u8]) = (&[], &[]);
+    let mut len = 0;
+    loop {
+        while a.is_empty() {
+            let Some(chunk) = old.next() else { return len };
+            a = chunk;
+        }
+        while b.is_empty() {
+            let Some(chunk) = new.next() else { return len };
+            b = chunk;
+        }
+        if std::ptr::eq(a, b) {
+            len += a.len();
+            (a, b) = (&[], &[]);
+            continue;
+        }
+        let n = a.len().min(b.len());
+        let same = if from_end {
+            a.iter().rev().zip(b.iter().rev()).take(n).take_while(|(x, y)| x == y).count()
+        } else {
+            a.iter().zip(b).take(n).take_while(|(x, y)| x == y).count()
+        };
+        len += same;
+        if same < n {
+            return len;
+        }
+        if from_end {
+            (a, b) = (&a[..a.len() - n], &b[..b.len() - n]);
+        } else {
+            (a, b) = (&a[n..], &b[n..]);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Visual 模式的选择区域
+//!
+//! Normal 模式下 `v` 按字符、`V` 按行开始选择，选择区域从进入 Visual 模式时的光标位置（锚点）
+//! 到当前光标，包括两端的字符：
+//! - 动作移动光标扩展选择区域，`v` / `V` 在两种选择之间切换，再按一次相同的键回到 Normal 模式
+//! - `o` / `O` 交换锚点和光标，从选择区域的另一端继续扩展
+//! - `d` / `x`、`y`、`c` 删除、复制、修改选中的文本（按行选择时按整行），`p` / `P` 用寄存器的内容替换选中的文本
+//! - `>` / `<` 增加 / 减少选中行的缩进，`u` / `U` / `~` 转为小写 / 大写 / 切换大小写，
+//!   `J` 合并选中的行，`gq` 重排选中的行，`S{char}` 用一对字符包围选择区域；
+//!   每个操作作为一次撤销单元，之后回到 Normal 模式
+//! - 离开 Visual 模式时记住每个缓冲区最后的选择区域，`gv` 重新选中它（Visual 模式下与当前的选择区域交换）
+
+use crate::buffer::Buffer;
+use crate::cursor::Cursor;
+
+/// 选择区域：锚点和光标两端（包括两端的字符），`linewise` 为 true 时选中整行 (V)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisualSelection {
+    pub anchor: Cursor,
+    pub cursor: Cursor,
+    pub linewise: bool,
+}
+
+impl VisualSelection {
+    pub fn new(anchor: Cursor, cursor: Cursor, linewise: bool) -> Self {
+        Self { anchor, cursor, linewise }
+    }
+
+    /// 在缓冲区中靠前的一端
+    pub fn start(&self) -> Cursor {
+        if (self.anchor.line, self.anchor.column) <= (self.cursor.line, self.cursor.column) {
+            self.anchor
+        } else {
+            self.cursor
+        }
+    }
+
+    /// 在缓冲区中靠后的一端
+    pub fn end(&self) -> Cursor {
+        if (self.anchor.line, self.anchor.column) <= (self.cursor.line, self.cursor.column) {
+            self.cursor
+        } else {
+            self.anchor
+        }
+    }
+
+    /// 选择区域覆盖的第一行和最后一行
+    pub fn lines(&self) -> (usize, usize) {
+        (self.start().line, self.end().line)
+    }
+
+    /// 选中的字符范围 `start..end`：按行选择时为第一行的开头到最后一行的行尾（不包括换行符），
+    /// 按字符选择时包括结尾的字符，结尾在行尾之后时包括换行符
+    pub fn char_range(&self, buffer: &Buffer) -> (usize, usize) {
+        let (first, last) = self.lines();
+        if self.linewise {
+            let start = buffer.line_to_char(first);
+            let end = buffer.line_to_char(last) + buffer.line_text(last).map_or(0, |text| text.chars().count());
+            return (start, end);
+        }
+        let start = self.start().to_char_idx(buffer);
+        let end = (self.end().to_char_idx(buffer) + 1).min(buffer.len_chars());
+        (start, end.max(start))
+    }
+
+    /// 第 `line` 行中选中的字符列 `start..end`（`len` 为这一行的字符数，不包括换行符），
+    /// 不在选择区域中的行返回 None
+    pub fn columns(&self, line: usize, len: usize) -> Option<(usize, usize)> {
+        let (first, last) = self.lines();
+        if line < first || line > last {
+            return None;
+        }
+        if self.linewise {
+            return Some((0, len));
+        }
+        let start = if line == first { self.start().column.min(len) } else { 0 };
+        let end = if line == last { (self.end().column + 1).min(len) } else { len };
+        Some((start, end.max(start)))
+    }
+
+    /// 把两端限制在缓冲区的范围内（gv 重新选中时文本可能已经改变）
+    pub fn clamp(self, buffer: &Buffer) -> Self {
+        let last_line = buffer.last_content_line();
+        let clamp = |cursor: Cursor| {
+            let line = cursor.line.min(last_line);
+            let len = buffer.line_text(line).map_or(0, |text| text.chars().count());
+            Cursor::new(line, cursor.column.min(len.saturating_sub(1)))
+        };
+        Self::new(clamp(self.anchor), clamp(self.cursor), self.linewise)
+    }
+}
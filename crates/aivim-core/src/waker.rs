@@ -0,0 +1,30 @@
+//! 事件循环唤醒模块
+//!
+//! 后台线程（外部命令的输出、:grep、文件查找、读入大文件）产生新结果后调用 `Waker::wake`，
+//! 界面的事件循环收到通知后立即取出结果，而不用等到下一次定时检查。
+//! 编辑器本身不依赖界面，唤醒的方式由界面通过 `Editor::set_waker` 提供
+
+use std::fmt;
+use std::sync::Arc;
+
+/// 唤醒事件循环的回调，可以在任意线程中调用；默认什么也不做
+#[derive(Clone, Default)]
+pub struct Waker(Option<Arc<dyn Fn() + Send + Sync>>);
+
+impl Waker {
+    pub fn new(wake: impl Fn() + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(wake)))
+    }
+
+    pub fn wake(&self) {
+        if let Some(wake) = &self.0 {
+            wake();
+        }
+    }
+}
+
+impl fmt::Debug for Waker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "Waker" } else { "Waker(none)" })
+    }
+}
@@ -0,0 +1,90 @@
+//! 文件监视模块 (autoread)
+//!
+//! 开启 autoread 时监视所有打开的缓冲区的文件，文件在外部被修改时通知编辑器：
+//! - 监视文件所在的目录而不是文件本身，其他程序通过"写入临时文件再重命名"保存时也能发现
+//! - 系统的通知在后台线程中到达，记录变化的文件并唤醒事件循环，主线程调用 `FileWatcher::poll` 取出
+//! - 只报告文件可能被修改，是否真的变化由编辑器比较文件的修改时间决定（编辑器自己写入文件也会产生通知）
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use crate::waker::Waker;
+
+/// 监视打开的文件
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+    /// 被监视的目录及目录中被监视的文件
+    dirs: HashMap<PathBuf, HashSet<PathBuf>>,
+}
+
+impl std::fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileWatcher").field("dirs", &self.dirs).finish()
+    }
+}
+
+/// 文件的绝对路径，通知中的路径总是绝对路径
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl FileWatcher {
+    /// 创建监视器，有文件变化时调用 `waker`
+    pub fn new(waker: Waker) -> notify::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            for path in event.paths {
+                let _ = sender.send(path);
+            }
+            waker.wake();
+        })?;
+        Ok(Self {
+            watcher,
+            receiver,
+            dirs: HashMap::new(),
+        })
+    }
+
+    /// 开始监视文件，文件所在的目录不存在时忽略
+    pub fn watch(&mut self, path: &Path) {
+        let path = absolute(path);
+        let Some(dir) = path.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        if !self.dirs.contains_key(&dir) {
+            if let Err(e) = self.watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                tracing::warn!("Failed to watch {}: {}", dir.display(), e);
+                return;
+            }
+        }
+        self.dirs.entry(dir).or_default().insert(path);
+    }
+
+    /// 是否正在监视文件
+    pub fn is_watching(&self, path: &Path) -> bool {
+        let path = absolute(path);
+        path.parent()
+            .and_then(|dir| self.dirs.get(dir))
+            .is_some_and(|files| files.contains(&path))
+    }
+
+    /// 取出上次调用之后可能被修改的被监视文件（绝对路径，不重复）
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for path in self.receiver.try_iter() {
+            if self.is_watching(&path) && !changed.contains(&path) {
+                changed.push(path);
+            }
+        }
+        changed
+    }
+}
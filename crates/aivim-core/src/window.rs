@@ -0,0 +1,468 @@
+//! 窗口模块
+//!
+//! 一个窗口显示一个缓冲区，有自己的光标和滚动位置，多个窗口按分割方向组成布局树：
+//! - :split 把当前窗口分成上下两个，:vsplit 分成左右两个，新窗口在上方 / 左侧并成为当前窗口
+//! - 在同方向的分割中再分割时，新窗口加入同一层，与被分割的窗口平分它原来的空间
+//! - 同一层的子节点按各自的大小比例分配空间，Ctrl+W + - < > 调整大小，Ctrl+W = 全部平分
+//! - 关闭窗口后空间交给相邻的节点；只剩一个子节点的分割被移除，剩下的节点占据整个分割的位置
+//! - Ctrl+W h/j/k/l 按窗口在屏幕上的位置切换，窗口区域的大小由界面通过 set_area 告知
+//!
+//! 编辑器的光标和当前缓冲区就是当前窗口的状态，这里保存的是其他窗口的状态，
+//! 切换窗口时由编辑器保存和恢复（见 Editor::focus_window）
+
+use std::collections::BTreeMap;
+
+use crate::buffer::BufferId;
+use crate::cursor::Cursor;
+use crate::local_options::WindowOptions;
+use crate::quickfix::QuickfixList;
+
+/// 窗口的最小高度（一行文本加状态栏）
+pub const MIN_WINDOW_HEIGHT: usize = 2;
+
+/// 窗口的最小宽度
+pub const MIN_WINDOW_WIDTH: usize = 1;
+
+/// 窗口 ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(usize);
+
+impl WindowId {
+    pub fn as_usize(&self) -> usize {
+        self.0
+    }
+}
+
+/// 一个窗口的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    pub buffer: BufferId,
+    pub cursor: Cursor,
+    /// 窗口顶部显示的缓冲区行
+    pub scroll_offset: usize,
+    /// 窗口的位置列表 (:lnext 等)，分割时复制到新窗口
+    pub location_list: Option<QuickfixList>,
+    /// 窗口局部选项 (:setlocal)，分割时复制到新窗口
+    pub options: WindowOptions,
+}
+
+/// 分割方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// 上下排列（:split）
+    Horizontal,
+    /// 左右排列（:vsplit），相邻的窗口之间有一列分隔线
+    Vertical,
+}
+
+/// 相邻窗口的方向（Ctrl+W h/j/k/l）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Down,
+    Up,
+    Right,
+}
+
+/// 窗口布局树
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+    Window(WindowId),
+    /// 按方向排列的子布局，至少有两个；`sizes` 为各子布局的相对大小
+    Split {
+        direction: SplitDirection,
+        children: Vec<Layout>,
+        sizes: Vec<usize>,
+    },
+}
+
+impl Layout {
+    /// 按从上到下、从左到右的顺序收集窗口
+    fn collect(&self, ids: &mut Vec<WindowId>) {
+        match self {
+            Layout::Window(id) => ids.push(*id),
+            Layout::Split { children, .. } => children.iter().for_each(|child| child.collect(ids)),
+        }
+    }
+
+    fn contains(&self, id: WindowId) -> bool {
+        match self {
+            Layout::Window(window) => *window == id,
+            Layout::Split { children, .. } => children.iter().any(|child| child.contains(id)),
+        }
+    }
+
+    /// 把窗口 `id` 替换为 `new` 和它组成的分割，`after` 为 false 时 `new` 在前
+    fn split(&mut self, id: WindowId, new: WindowId, direction: SplitDirection, after: bool) -> bool {
+        match self {
+            Layout::Window(window) if *window == id => {
+                let mut children = vec![Layout::Window(new), Layout::Window(id)];
+                if after {
+                    children.reverse();
+                }
+                *self = Layout::Split {
+                    direction,
+                    children,
+                    sizes: vec![1, 1],
+                };
+                true
+            }
+            Layout::Window(_) => false,
+            Layout::Split { direction: split_direction, children, sizes } => {
+                // 同方向的分割直接在这一层插入，两个窗口平分原来的大小
+                if *split_direction == direction {
+                    if let Some(index) = children.iter().position(|child| *child == Layout::Window(id)) {
+                        sizes.iter_mut().for_each(|size| *size *= 2);
+                        sizes[index] /= 2;
+                        let position = if after { index + 1 } else { index };
+                        sizes.insert(position, sizes[index]);
+                        children.insert(position, Layout::Window(new));
+                        return true;
+                    }
+                }
+                children.iter_mut().any(|child| child.split(id, new, direction, after))
+            }
+        }
+    }
+
+    /// 移除窗口 `id`，空间交给相邻的子节点；只剩一个子节点的分割由这个子节点代替
+    fn remove(&mut self, id: WindowId) -> bool {
+        let Layout::Split { direction, children, sizes } = self else {
+            return false;
+        };
+        let removed = match children.iter().position(|child| *child == Layout::Window(id)) {
+            Some(index) => {
+                children.remove(index);
+                let size = sizes.remove(index);
+                sizes[index.min(children.len() - 1)] += size;
+                true
+            }
+            None => children.iter_mut().any(|child| child.remove(id)),
+        };
+        if removed {
+            let direction = *direction;
+            merge_same_direction(direction, children, sizes);
+            if children.len() == 1 {
+                *self = children.remove(0);
+            }
+        }
+        removed
+    }
+
+    /// 所有分割的子节点平分空间
+    fn equalize(&mut self) {
+        if let Layout::Split { children, sizes, .. } = self {
+            sizes.iter_mut().for_each(|size| *size = 1);
+            children.iter_mut().for_each(Layout::equalize);
+        }
+    }
+
+    fn rects(&self, area: WindowRect, result: &mut Vec<(WindowId, WindowRect)>) {
+        match self {
+            Layout::Window(id) => result.push((*id, area)),
+            Layout::Split { direction, children, sizes } => {
+                for (child, child_area) in children.iter().zip(child_areas(area, *direction, sizes)) {
+                    child.rects(child_area, result);
+                }
+            }
+        }
+    }
+
+    /// 在包含窗口 `id` 的最内层 `direction` 方向的分割中，把窗口所在的子节点增大 `delta`，
+    /// 相邻的子节点相应缩小，两者都不小于最小尺寸
+    fn resize(&mut self, area: WindowRect, id: WindowId, direction: SplitDirection, delta: isize) -> bool {
+        let Layout::Split { direction: split_direction, children, sizes } = self else {
+            return false;
+        };
+        let areas = child_areas(area, *split_direction, sizes);
+        let Some(index) = children.iter().position(|child| child.contains(id)) else {
+            return false;
+        };
+        if children[index].resize(areas[index], id, direction, delta) {
+            return true;
+        }
+        if *split_direction != direction {
+            return false;
+        }
+
+        // 按当前的实际大小重新记录，再在两个相邻的子节点之间移动空间
+        let (cells, min): (Vec<usize>, usize) = match direction {
+            SplitDirection::Horizontal => (areas.iter().map(|a| a.height).collect(), MIN_WINDOW_HEIGHT),
+            SplitDirection::Vertical => (areas.iter().map(|a| a.width).collect(), MIN_WINDOW_WIDTH),
+        };
+        let neighbor = if index + 1 < cells.len() { index + 1 } else { index - 1 };
+        let shrink = cells[index].saturating_sub(min) as isize;
+        let grow = cells[neighbor].saturating_sub(min) as isize;
+        let delta = delta.max(-shrink).min(grow);
+        *sizes = cells;
+        sizes[index] = (sizes[index] as isize + delta) as usize;
+        sizes[neighbor] = (sizes[neighbor] as isize - delta) as usize;
+        true
+    }
+}
+
+/// 把与 `direction` 同方向的子分割展开到这一层，大小按比例换算
+fn merge_same_direction(direction: SplitDirection, children: &mut Vec<Layout>, sizes: &mut Vec<usize>) {
+    let mut merged_children = Vec::new();
+    let mut merged_sizes: Vec<usize> = Vec::new();
+    // 已展开的子分割使这一层的大小放大的倍数
+    let mut scale = 1;
+    for (child, size) in std::mem::take(children).into_iter().zip(std::mem::take(sizes)) {
+        match child {
+            Layout::Split { direction: inner, children: inner_children, sizes: inner_sizes } if inner == direction => {
+                let total: usize = inner_sizes.iter().sum::<usize>().max(1);
+                merged_sizes.iter_mut().for_each(|s| *s *= total);
+                merged_sizes.extend(inner_sizes.iter().map(|s| s * size * scale));
+                merged_children.extend(inner_children);
+                scale *= total;
+            }
+            child => {
+                merged_children.push(child);
+                merged_sizes.push(size * scale);
+            }
+        }
+    }
+    let divisor = merged_sizes.iter().fold(0, |a, &b| gcd(a, b)).max(1);
+    *children = merged_children;
+    *sizes = merged_sizes.into_iter().map(|s| s / divisor).collect();
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// 分割中各子节点的区域；左右排列时相邻的子节点之间各留一列分隔线
+fn child_areas(area: WindowRect, direction: SplitDirection, sizes: &[usize]) -> Vec<WindowRect> {
+    let count = sizes.len();
+    let mut result = Vec::with_capacity(count);
+    match direction {
+        SplitDirection::Horizontal => {
+            let mut y = area.y;
+            for height in divide(area.height, sizes) {
+                result.push(WindowRect { y, height, ..area });
+                y += height;
+            }
+        }
+        SplitDirection::Vertical => {
+            let available = area.width.saturating_sub(count - 1);
+            let mut x = area.x;
+            for width in divide(available, sizes) {
+                result.push(WindowRect { x, width, ..area });
+                x += width + 1;
+            }
+        }
+    }
+    result
+}
+
+/// 按比例把 `total` 分给各部分，余数分给前面的几份
+fn divide(total: usize, sizes: &[usize]) -> Vec<usize> {
+    let sum: usize = sizes.iter().sum();
+    if sum == 0 {
+        return divide(total, &vec![1; sizes.len()]);
+    }
+    let mut parts: Vec<usize> = sizes.iter().map(|size| total * size / sum).collect();
+    let remainder = total - parts.iter().sum::<usize>();
+    parts.iter_mut().take(remainder).for_each(|part| *part += 1);
+    parts
+}
+
+/// 窗口在屏幕上占据的区域（包括窗口的状态栏）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WindowRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// 所有窗口和它们的布局
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowManager {
+    windows: BTreeMap<WindowId, Window>,
+    layout: Layout,
+    current: WindowId,
+    next_id: usize,
+    /// 窗口区域在屏幕上的位置和大小
+    area: WindowRect,
+}
+
+impl WindowManager {
+    /// 只有一个显示 `buffer` 的窗口
+    pub fn new(buffer: BufferId) -> Self {
+        let id = WindowId(0);
+        let window = Window {
+            buffer,
+            cursor: Cursor::at_origin(),
+            scroll_offset: 0,
+            location_list: None,
+            options: WindowOptions::default(),
+        };
+        Self {
+            windows: BTreeMap::from([(id, window)]),
+            layout: Layout::Window(id),
+            current: id,
+            next_id: 1,
+            // 界面告知实际大小之前按 80x24 的终端（去掉命令行）计算
+            area: WindowRect { x: 0, y: 0, width: 80, height: 23 },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    pub fn area(&self) -> WindowRect {
+        self.area
+    }
+
+    /// 设置窗口区域的大小，界面在每次绘制前调用
+    pub fn set_area(&mut self, area: WindowRect) {
+        self.area = area;
+    }
+
+    pub fn current_id(&self) -> WindowId {
+        self.current
+    }
+
+    pub fn current(&self) -> &Window {
+        &self.windows[&self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut Window {
+        self.windows.get_mut(&self.current).expect("current window")
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&Window> {
+        self.windows.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut Window> {
+        self.windows.get_mut(&id)
+    }
+
+    /// 按从上到下、从左到右的顺序排列的窗口
+    pub fn ids(&self) -> Vec<WindowId> {
+        let mut ids = Vec::new();
+        self.layout.collect(&mut ids);
+        ids
+    }
+
+    /// 所有窗口
+    pub fn windows_mut(&mut self) -> impl Iterator<Item = &mut Window> {
+        self.windows.values_mut()
+    }
+
+    /// 分割当前窗口，新窗口复制当前窗口的状态并成为当前窗口
+    pub fn split(&mut self, direction: SplitDirection) -> WindowId {
+        self.split_side(direction, false)
+    }
+
+    /// 与 `split` 相同，但新窗口在当前窗口的右侧（下方）
+    pub fn split_after(&mut self, direction: SplitDirection) -> WindowId {
+        self.split_side(direction, true)
+    }
+
+    fn split_side(&mut self, direction: SplitDirection, after: bool) -> WindowId {
+        let id = WindowId(self.next_id);
+        self.next_id += 1;
+        self.windows.insert(id, self.current().clone());
+        self.layout.split(self.current, id, direction, after);
+        self.current = id;
+        id
+    }
+
+    /// 切换当前窗口，窗口不存在时返回 false
+    pub fn set_current(&mut self, id: WindowId) -> bool {
+        if !self.windows.contains_key(&id) {
+            return false;
+        }
+        self.current = id;
+        true
+    }
+
+    /// 按布局顺序向后（`offset` 为正）或向前循环的第 `offset` 个窗口
+    pub fn cycle(&self, offset: isize) -> WindowId {
+        let ids = self.ids();
+        let index = ids.iter().position(|id| *id == self.current).unwrap_or(0) as isize;
+        ids[(index + offset).rem_euclid(ids.len() as isize) as usize]
+    }
+
+    /// 当前窗口在 `side` 方向上相邻的窗口，没有时返回 None
+    ///
+    /// 有多个相邻窗口时选择与当前窗口左上角对齐的那个（左右方向按行、上下方向按列）
+    pub fn neighbor(&self, side: Side) -> Option<WindowId> {
+        let rects = self.layout_rects(self.area);
+        let (_, current) = *rects.iter().find(|(id, _)| *id == self.current)?;
+        let adjacent = |rect: &WindowRect| match side {
+            Side::Left => rect.x + rect.width + 1 == current.x,
+            Side::Right => current.x + current.width + 1 == rect.x,
+            Side::Up => rect.y + rect.height == current.y,
+            Side::Down => current.y + current.height == rect.y,
+        };
+        // 与当前窗口左上角所在的行 / 列重叠的窗口
+        let overlaps = |rect: &WindowRect| match side {
+            Side::Left | Side::Right => rect.y <= current.y && current.y < rect.y + rect.height,
+            Side::Up | Side::Down => rect.x <= current.x && current.x < rect.x + rect.width,
+        };
+        rects
+            .iter()
+            .filter(|(_, rect)| adjacent(rect))
+            .find(|(_, rect)| overlaps(rect))
+            .map(|(id, _)| *id)
+    }
+
+    /// 把当前窗口的高度（Horizontal）或宽度（Vertical）增加 `delta`，负数为减小
+    ///
+    /// 空间从相邻的窗口获得或交给相邻的窗口，窗口不在该方向的分割中时不做任何事
+    pub fn resize(&mut self, direction: SplitDirection, delta: isize) -> bool {
+        self.layout.resize(self.area, self.current, direction, delta)
+    }
+
+    /// 所有窗口平分空间 (Ctrl+W =)
+    pub fn equalize(&mut self) {
+        self.layout.equalize();
+    }
+
+    /// 关闭窗口，不能关闭最后一个窗口
+    ///
+    /// 关闭当前窗口后，布局顺序中的下一个窗口（没有时为上一个）成为当前窗口
+    pub fn close(&mut self, id: WindowId) -> bool {
+        if self.windows.len() <= 1 || !self.windows.contains_key(&id) {
+            return false;
+        }
+        if id == self.current {
+            let ids = self.ids();
+            let index = ids.iter().position(|other| *other == id).unwrap_or(0);
+            self.current = ids.get(index + 1).copied().unwrap_or(ids[index.saturating_sub(1)]);
+        }
+        self.windows.remove(&id);
+        self.layout.remove(id);
+        true
+    }
+
+    /// 关闭当前窗口以外的所有窗口 (Ctrl+W o)
+    pub fn only(&mut self) {
+        self.windows.retain(|id, _| *id == self.current);
+        self.layout = Layout::Window(self.current);
+    }
+
+    /// 各窗口在 `area` 中的区域，按布局顺序排列
+    pub fn layout_rects(&self, area: WindowRect) -> Vec<(WindowId, WindowRect)> {
+        let mut result = Vec::new();
+        self.layout.rects(area, &mut result);
+        result
+    }
+}
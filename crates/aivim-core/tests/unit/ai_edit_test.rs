@@ -0,0 +1,116 @@
+//! AI 编辑单元测试
+//!
+//! 对应源文件: src/ai_edit.rs, src/ai_plugin.rs (:AiEdit、预览和确认)
+
+use aivim_core::ai_edit::strip_code_fence;
+use aivim_core::ai_plugin::AiPlugin;
+use aivim_core::editor::{Editor, PendingConfirmation};
+use aivim_core::range::LineRange;
+use std::time::{Duration, Instant};
+use super::common::{editor_with_reply, has_curl};
+
+fn ai(editor: &Editor) -> &AiPlugin {
+    editor.plugin::<AiPlugin>().unwrap()
+}
+
+/// 等待回复结束
+fn wait_for_reply(editor: &mut Editor) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while ai(editor).edit().is_some_and(|edit| edit.is_responding()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        editor.poll_plugins(Instant::now());
+    }
+}
+
+// ==================== 回复处理 ====================
+
+#[test]
+fn test_strip_code_fence() {
+    assert_eq!(strip_code_fence("let x = 1;"), "let x = 1;\n");
+    assert_eq!(strip_code_fence("```rust\nlet x = 1;\nlet y = 2;\n```\n"), "let x = 1;\nlet y = 2;\n");
+    assert_eq!(strip_code_fence("```\nfoo\n```"), "foo\n");
+    assert_eq!(strip_code_fence("\n\n"), "");
+}
+
+// ==================== :AiEdit ====================
+
+#[test]
+fn test_ai_edit_requires_instruction() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("AiEdit").is_err());
+    assert!(ai(&editor).edit().is_none());
+}
+
+#[test]
+fn test_ai_edit_preview_and_apply() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("apply", "a\nb\nc\nd\n", &["```text\\nB\\n", "C\\n```"]);
+    let windows = editor.windows().len();
+    editor.execute_command("2,3AiEdit uppercase").unwrap();
+    wait_for_reply(&mut editor);
+
+    // diff 预览在新窗口中，光标留在原来的缓冲区
+    assert_eq!(editor.windows().len(), windows + 1);
+    assert!(matches!(editor.pending_confirmation(), Some(PendingConfirmation::Plugin { .. })));
+    assert_eq!(ai(&editor).edit().map(|edit| edit.range), Some(LineRange::new(1, 2)));
+    let preview = ai(&editor).edit().and_then(|edit| edit.preview).unwrap();
+    let diff = editor.buffer(preview).unwrap().to_string();
+    assert!(diff.contains("-b\n-c\n+B\n+C\n"), "{}", diff);
+    assert_eq!(editor.buffer(preview).unwrap().filetype(), Some("diff"));
+    assert_ne!(editor.current_buffer_id(), preview);
+
+    editor.resolve_confirmation(true).unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "a\nB\nC\nd\n");
+    assert_eq!(editor.windows().len(), windows);
+    assert!(editor.buffer(preview).is_none());
+    assert!(ai(&editor).edit().is_none());
+
+    // 一次撤销恢复全部修改
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\nd\n");
+}
+
+#[test]
+fn test_ai_edit_cancel() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("cancel", "a\nb\n", &["A"]);
+    editor.execute_command("AiEdit uppercase").unwrap();
+    wait_for_reply(&mut editor);
+    let preview = ai(&editor).edit().and_then(|edit| edit.preview).unwrap();
+
+    editor.resolve_confirmation(false).unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\n");
+    assert!(editor.buffer(preview).is_none());
+    assert!(ai(&editor).edit().is_none());
+}
+
+#[test]
+fn test_ai_edit_discarded_when_lines_changed() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("changed", "a\nb\n", &["A"]);
+    editor.execute_command("AiEdit uppercase").unwrap();
+    wait_for_reply(&mut editor);
+
+    editor.current_buffer_mut().replace_lines(LineRange::single(0), "x\n");
+    assert!(editor.resolve_confirmation(true).is_err());
+    assert_eq!(editor.current_buffer().to_string(), "x\nb\n");
+}
+
+#[test]
+fn test_ai_edit_no_changes() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("same", "a\nb\n", &["a"]);
+    editor.execute_command("AiEdit keep it").unwrap();
+    wait_for_reply(&mut editor);
+    assert!(editor.pending_confirmation().is_none());
+    assert!(ai(&editor).edit().is_none());
+    assert_eq!(editor.windows().len(), 1);
+}
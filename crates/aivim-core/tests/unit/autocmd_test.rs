@@ -0,0 +1,204 @@
+//! 自动命令单元测试
+//!
+//! 对应源文件: src/autocmd.rs, src/editor.rs (:autocmd、事件触发、:StripWhitespace、updatetime)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use aivim_core::autocmd::{glob_match, parse_args, Autocmds, Event, HoldTimer};
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::Mode;
+
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("aivim_autocmd_{}_{}.rs", name, std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_event_names() {
+    assert_eq!(Event::from_name("bufwritepre"), Some(Event::BufWritePre));
+    assert_eq!(Event::from_name("BufRead"), Some(Event::BufReadPost));
+    assert_eq!(Event::from_name("InsertLeave"), Some(Event::InsertLeave));
+    assert_eq!(Event::from_name("BufFoo"), None);
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*", "main.rs"));
+    assert!(glob_match("*.rs", "main.rs"));
+    assert!(!glob_match("*.rs", "main.py"));
+    assert!(glob_match("ma?n.*", "main.rs"));
+    assert!(glob_match("*/src/*.rs", "/repo/src/main.rs"));
+    assert!(!glob_match("a*b", "acbc"));
+}
+
+#[test]
+fn test_parse_args() {
+    let args = parse_args("BufWritePre,InsertLeave *.rs set nu").unwrap();
+    assert_eq!(args.events, Some(vec![Event::BufWritePre, Event::InsertLeave]));
+    assert_eq!(args.pattern, Some("*.rs"));
+    assert_eq!(args.command, Some("set nu"));
+
+    let args = parse_args("CursorHold").unwrap();
+    assert_eq!((args.pattern, args.command), (None, None));
+    assert_eq!(parse_args("Nope *").unwrap_err(), "Nope");
+}
+
+#[test]
+fn test_commands_for_pattern() {
+    let mut autocmds = Autocmds::new();
+    autocmds.add(Event::BufWritePre, "*.rs,*.py", "first");
+    autocmds.add(Event::BufWritePre, "*", "second");
+    autocmds.add(Event::InsertLeave, "*", "other");
+
+    assert_eq!(autocmds.commands_for(Event::BufWritePre, Some(Path::new("/a/b.py"))), vec!["first", "second"]);
+    assert_eq!(autocmds.commands_for(Event::BufWritePre, Some(Path::new("b.txt"))), vec!["second"]);
+    // 没有文件名的缓冲区只匹配 *
+    assert_eq!(autocmds.commands_for(Event::BufWritePre, None), vec!["second"]);
+
+    autocmds.remove(Some(Event::BufWritePre), None);
+    assert_eq!(autocmds.list(None, None).len(), 1);
+}
+
+#[test]
+fn test_hold_timer_fires_once() {
+    let mut timer = HoldTimer::default();
+    let start = Instant::now();
+    let delay = Duration::from_millis(100);
+    assert!(!timer.update((0, 1, 2), start, delay));
+    assert!(!timer.update((0, 1, 2), start + Duration::from_millis(50), delay));
+    assert!(timer.update((0, 1, 2), start + delay, delay));
+    assert!(!timer.update((0, 1, 2), start + delay * 2, delay));
+    // 光标移动后重新计时
+    assert!(!timer.update((0, 1, 3), start + delay * 2, delay));
+    assert!(timer.update((0, 1, 3), start + delay * 3, delay));
+}
+
+#[test]
+fn test_autocmd_command_register_list_and_remove() {
+    let mut editor = Editor::new();
+    editor.execute_command("autocmd BufWritePre *.rs StripWhitespace").unwrap();
+    editor.execute_command("au InsertLeave * set nu").unwrap();
+    assert_eq!(editor.autocmds().list(None, None).len(), 2);
+
+    editor.execute_command("autocmd BufWritePre").unwrap();
+    assert_eq!(editor.message(), Some("BufWritePre *.rs StripWhitespace"));
+
+    editor.execute_command("autocmd! InsertLeave").unwrap();
+    assert_eq!(editor.autocmds().list(None, None).len(), 1);
+    editor.execute_command("autocmd!").unwrap();
+    assert!(editor.autocmds().list(None, None).is_empty());
+
+    assert!(editor.execute_command("autocmd Nope * set nu").is_err());
+}
+
+#[test]
+fn test_buf_write_pre_strips_whitespace() {
+    let path = write_temp("write", "fn main() {  \n}\t\n");
+    let mut editor = Editor::new();
+    editor.execute_command("autocmd BufWritePre *.rs StripWhitespace").unwrap();
+    editor.open_file(&path).unwrap();
+    editor.execute_command("w").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fn main() {\n}\n");
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_autocmds_run_for_other_buffers() {
+    let first = write_temp("other_1", "a\n");
+    let second = write_temp("other_2", "b\n");
+    let mut editor = Editor::new();
+    editor.execute_command("autocmd BufWritePre *.rs StripWhitespace").unwrap();
+    editor.open_file(&first).unwrap();
+    let first_id = editor.current_buffer_id();
+    assert!(editor.run_script(&["A!  <Esc>"]).is_ok());
+    editor.open_file(&second).unwrap();
+    let second_id = editor.current_buffer_id();
+
+    // 事件的缓冲区不是当前缓冲区时，临时切换到它执行自动命令
+    editor.emit_event(Event::BufWritePre, first_id);
+    assert_eq!(editor.current_buffer_id(), second_id);
+    assert_eq!(editor.buffer(first_id).unwrap().to_string(), "a!\n");
+
+    // 退出前保存其他缓冲区时同样执行 BufWritePre
+    assert!(editor.run_script(&["A  <Esc>"]).is_ok());
+    editor.execute_command("qa").unwrap();
+    assert!(editor.run_script(&["a"]).is_ok());
+    assert!(editor.quit_requested());
+    assert_eq!(fs::read_to_string(&first).unwrap(), "a!\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "b\n");
+    fs::remove_file(first).ok();
+    fs::remove_file(second).ok();
+}
+
+#[test]
+fn test_buf_read_post_runs_for_matching_files() {
+    let path = write_temp("read", "x\n");
+    let mut editor = Editor::new();
+    editor.execute_command("autocmd BufReadPost *.py set nu").unwrap();
+    editor.execute_command("autocmd BufReadPost *.rs set rnu").unwrap();
+    editor.open_file(&path).unwrap();
+
+    assert!(!editor.options().number);
+    assert!(editor.options().relativenumber);
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_insert_enter_and_leave() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "hello\n");
+    editor.execute_command("autocmd InsertEnter * set nu").unwrap();
+    editor.execute_command("autocmd InsertLeave * set nonu").unwrap();
+
+    assert!(editor.run_script(&["i"]).is_ok());
+    assert!(editor.options().number);
+    assert!(editor.run_script(&["<Esc>"]).is_ok());
+    assert!(!editor.options().number);
+    // c 操作符直接进入 Insert 模式
+    assert!(editor.run_script(&["cc"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Insert);
+    assert!(editor.options().number);
+}
+
+#[test]
+fn test_autocmd_error_is_reported() {
+    let mut editor = Editor::new();
+    editor.execute_command("autocmd InsertEnter * nosuchcommand").unwrap();
+    editor.set_mode(Mode::Insert);
+    assert!(editor.message().is_some_and(|message| message.contains("InsertEnter")));
+}
+
+#[test]
+fn test_cursor_hold_after_updatetime() {
+    let mut editor = Editor::new();
+    editor.execute_command("set updatetime=100").unwrap();
+    editor.execute_command("autocmd CursorHold * set cursorline").unwrap();
+    let start = Instant::now();
+    assert!(!editor.poll_cursor_hold(start));
+    assert!(!editor.poll_cursor_hold(start + Duration::from_millis(50)));
+    assert!(editor.poll_cursor_hold(start + Duration::from_millis(100)));
+    assert!(editor.options().cursorline);
+
+    assert!(editor.execute_command("set updatetime=0").is_err());
+}
+
+#[test]
+fn test_strip_whitespace_range_is_single_undo() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "a \nb\t \nc  \n");
+    *editor.cursor_mut() = Cursor::new(2, 2);
+    editor.execute_command("1,2StripWhitespace").unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc  \n");
+
+    editor.execute_command("StripWhitespace").unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 0));
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc  \n");
+}
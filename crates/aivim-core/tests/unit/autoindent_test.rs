@@ -0,0 +1,79 @@
+//! 自动缩进与 o/O 命令单元测试
+//!
+//! 对应源文件: src/editor.rs (insert_newline, open_line_below, open_line_above)
+
+use aivim_core::editor::Editor;
+use aivim_core::Mode;
+use super::common::editor_with;
+
+#[test]
+fn test_newline_without_autoindent() {
+    let mut editor = editor_with("    foo\n", 0, 7);
+    editor.set_mode(Mode::Insert);
+    editor.insert_newline();
+
+    assert_eq!(editor.current_buffer().to_string(), "    foo\n\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 0));
+}
+
+#[test]
+fn test_newline_copies_indent() {
+    let mut editor = editor_with("\t  foo\n", 0, 6);
+    editor.execute_command("set autoindent").unwrap();
+    editor.set_mode(Mode::Insert);
+    editor.insert_newline();
+    editor.insert_char('x');
+
+    assert_eq!(editor.current_buffer().to_string(), "\t  foo\n\t  x\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 4));
+}
+
+#[test]
+fn test_open_line_below() {
+    let mut editor = editor_with("    a\nb\n", 0, 2);
+    editor.execute_command("set ai").unwrap();
+    editor.open_line_below();
+
+    assert_eq!(editor.mode(), Mode::Insert);
+    assert_eq!(editor.current_buffer().to_string(), "    a\n    \nb\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 4));
+}
+
+#[test]
+fn test_open_line_below_last_line_without_newline() {
+    let mut editor = editor_with("a", 0, 0);
+    editor.open_line_below();
+    editor.insert_char('b');
+
+    assert_eq!(editor.current_buffer().to_string(), "a\nb");
+}
+
+#[test]
+fn test_open_line_above() {
+    let mut editor = editor_with("a\n  b\n", 1, 3);
+    editor.execute_command("set autoindent").unwrap();
+    editor.open_line_above();
+    editor.insert_char('x');
+
+    assert_eq!(editor.current_buffer().to_string(), "a\n  x\n  b\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 3));
+}
+
+#[test]
+fn test_open_line_is_undoable() {
+    let mut editor = editor_with("a\n", 0, 0);
+    editor.open_line_above();
+    editor.set_mode(Mode::Normal);
+    editor.undo();
+
+    assert_eq!(editor.current_buffer().to_string(), "a\n");
+}
+
+#[test]
+fn test_noautoindent_option() {
+    let mut editor = Editor::new();
+    editor.execute_command("set ai").unwrap();
+    assert!(editor.options().autoindent);
+    editor.execute_command("set noai").unwrap();
+    assert!(!editor.options().autoindent);
+}
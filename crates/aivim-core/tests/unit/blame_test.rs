@@ -0,0 +1,110 @@
+//! Git blame 单元测试
+//!
+//! 对应源文件: src/blame.rs, src/git.rs (blame、show_commit), src/git_plugin.rs (:GitBlame)
+
+use aivim_core::blame::{parse_porcelain, Blame};
+use aivim_core::editor::Editor;
+use aivim_core::git_plugin::GitPlugin;
+use std::fs;
+use super::common::{has_git, init_repo};
+
+const PORCELAIN: &str = "\
+1111111111111111111111111111111111111111 1 1 2
+author Alice
+author-mail <alice@example.com>
+author-time 1709942400
+author-tz +0800
+summary Add greeting
+filename a.txt
+\thello
+1111111111111111111111111111111111111111 2 2
+\tworld
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-time 1709942400
+author-tz -0500
+summary Version of a.txt from standard input
+filename a.txt
+\tnew
+";
+
+/// git 插件显示的当前缓冲区的 blame
+fn shown_blame(editor: &Editor) -> Option<&Blame> {
+    editor.plugin::<GitPlugin>().unwrap().blame(editor.current_buffer())
+}
+
+// ==================== 解析测试 ====================
+
+#[test]
+fn test_parse_porcelain() {
+    let lines = parse_porcelain(PORCELAIN);
+    assert_eq!(lines.len(), 3);
+    // 同一个提交的后续行没有作者等信息，沿用第一次出现时的
+    assert_eq!(lines[1].author, "Alice");
+    assert_eq!(lines[1].summary, "Add greeting");
+    assert!(lines[0].is_committed());
+    assert_eq!(lines[0].short_commit(), "11111111");
+    assert!(!lines[2].is_committed());
+}
+
+#[test]
+fn test_blame_dates_use_author_timezone() {
+    let lines = parse_porcelain(PORCELAIN);
+    // 2024-03-09 00:00 UTC
+    assert_eq!(lines[0].date(), "2024-03-09");
+    assert_eq!(lines[2].date(), "2024-03-08");
+    assert_eq!(lines[0].annotation(), "Alice, 2024-03-09 · Add greeting");
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_git_blame_toggle_and_edit() {
+    if !has_git() {
+        return;
+    }
+    let dir = init_repo("toggle", "one\ntwo\n");
+    let path = dir.join("a.txt");
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("GitBlame").unwrap();
+    let blame = shown_blame(&editor).unwrap();
+    assert_eq!(blame.lines.len(), 2);
+    assert_eq!(blame.lines[0].author, "Tester");
+    assert_eq!(blame.lines[1].summary, "Initial import");
+
+    // 修改之后不显示，保存时按新的内容重新查询
+    assert!(editor.run_script(&["Onew", "<Esc>"]).is_ok());
+    assert!(shown_blame(&editor).is_none());
+    editor.execute_command("w").unwrap();
+    let blame = shown_blame(&editor).unwrap();
+    assert_eq!(blame.lines.len(), 3);
+    assert!(!blame.lines[0].is_committed());
+
+    editor.execute_command("GitBlame").unwrap();
+    assert!(shown_blame(&editor).is_none());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_enter_opens_blame_commit() {
+    if !has_git() {
+        return;
+    }
+    let dir = init_repo("show", "one\n");
+    let path = dir.join("a.txt");
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("GitBlame").unwrap();
+    let commit = shown_blame(&editor).unwrap().lines[0].short_commit().to_string();
+    assert!(editor.run_script(&["<CR>"]).is_ok());
+    let buffer = editor.current_buffer();
+    assert_eq!(buffer.display_name().map(str::to_string), Some(format!("[GitShow] {}", commit)));
+    assert!(buffer.to_string().contains("Initial import"));
+    assert!(buffer.to_string().contains("+one"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_git_blame_needs_tracked_file() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("GitBlame").is_err());
+}
@@ -5,6 +5,7 @@
 use aivim_core::buffer::{Buffer, BufferId};
 use aivim_core::cursor::Cursor;
 use aivim_core::editor::Editor;
+use aivim_core::Locale;
 
 // ==================== 基础缓冲区操作测试 ====================
 
@@ -25,19 +26,21 @@ fn test_current_buffer_id() {
 
 #[test]
 fn test_list_buffers_single() {
-    let editor = Editor::new();
+    let mut editor = Editor::new();
+    editor.set_locale(Locale::Zh);
     let buffers = editor.list_buffers();
     
     assert_eq!(buffers.len(), 1);
     let (id, name, is_current) = &buffers[0];
     assert_eq!(id.as_usize(), 0);
-    assert!(name.contains("缓冲区") || name == "");
+    assert!(name.contains("缓冲区") || name.is_empty());
     assert!(*is_current);
 }
 
 #[test]
 fn test_format_buffer_list() {
-    let editor = Editor::new();
+    let mut editor = Editor::new();
+    editor.set_locale(Locale::Zh);
     let output = editor.format_buffer_list();
     
     assert!(output.contains("缓冲区列表"));
@@ -66,6 +69,7 @@ fn test_switch_buffer() {
 #[test]
 fn test_switch_buffer_nonexistent() {
     let mut editor = Editor::new();
+    editor.set_locale(Locale::Zh);
     
     let result = editor.switch_buffer(BufferId::new(999));
     assert!(result.is_err());
@@ -92,6 +96,7 @@ fn test_next_buffer() {
 #[test]
 fn test_next_buffer_single() {
     let mut editor = Editor::new();
+    editor.set_locale(Locale::Zh);
     
     let result = editor.next_buffer();
     assert!(result.is_err());
@@ -188,6 +193,7 @@ fn test_delete_current_buffer_switches() {
 #[test]
 fn test_delete_buffer_modified() {
     let mut editor = Editor::new();
+    editor.set_locale(Locale::Zh);
     
     // 创建第二个缓冲区并修改它
     {
@@ -237,6 +243,7 @@ fn test_delete_last_buffer_creates_new() {
 #[test]
 fn test_delete_nonexistent_buffer() {
     let mut editor = Editor::new();
+    editor.set_locale(Locale::Zh);
     
     let result = editor.delete_buffer(BufferId::new(999));
     assert!(result.is_err());
@@ -1,10 +1,10 @@
 //! Buffer 模块单元测试
 //!
 //! 对应源文件: src/buffer.rs
-//! 测试范围: 缓冲区创建、插入、删除、行操作、文件操作
+//! 测试范围: 缓冲区创建、插入、删除、行操作、文件操作、行范围读写
 
 use aivim_core::buffer::{Buffer, BufferId};
-use std::io::Write;
+use aivim_core::range::LineRange;
 use std::path::PathBuf;
 
 // ==================== 基本操作测试 ====================
@@ -260,6 +260,70 @@ fn test_buffer_save_no_path() {
     assert!(result.is_err());
 }
 
+// ==================== 行范围读写测试 ====================
+
+fn buffer_with(content: &str) -> Buffer {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, content);
+    buffer
+}
+
+#[test]
+fn test_buffer_last_content_line() {
+    assert_eq!(buffer_with("").last_content_line(), 0);
+    assert_eq!(buffer_with("a\nb\n").last_content_line(), 1);
+    assert_eq!(buffer_with("a\nb").last_content_line(), 1);
+}
+
+#[test]
+fn test_buffer_lines_text() {
+    let buffer = buffer_with("one\ntwo\nthree");
+    assert_eq!(buffer.lines_text(LineRange::new(0, 1)), "one\ntwo\n");
+    // 最后一行没有换行符时补上
+    assert_eq!(buffer.lines_text(LineRange::new(2, 2)), "three\n");
+}
+
+#[test]
+fn test_buffer_insert_lines() {
+    let mut buffer = buffer_with("a\nb\n");
+    buffer.insert_lines(1, "x\n");
+    assert_eq!(buffer.to_string(), "a\nx\nb\n");
+    buffer.insert_lines(3, "y\n");
+    assert_eq!(buffer.to_string(), "a\nx\nb\ny\n");
+
+    // 最后一行没有换行符
+    let mut buffer = buffer_with("a\nb");
+    buffer.insert_lines(2, "c\n");
+    assert_eq!(buffer.to_string(), "a\nb\nc");
+}
+
+#[test]
+fn test_buffer_replace_lines() {
+    let mut buffer = buffer_with("a\nb\nc\n");
+    assert_eq!(buffer.replace_lines(LineRange::new(0, 1), "x\n"), LineRange::new(0, 1));
+    assert_eq!(buffer.to_string(), "x\nc\n");
+
+    // 范围超出文件时截断，最后一行原本没有换行符时也不加
+    let mut buffer = buffer_with("a\nb");
+    assert_eq!(buffer.replace_lines(LineRange::new(1, 5), "y\nz\n"), LineRange::new(1, 1));
+    assert_eq!(buffer.to_string(), "a\ny\nz");
+}
+
+#[test]
+fn test_buffer_write_lines() {
+    let path = PathBuf::from("/tmp/aivim_test_buffer_write_lines.txt");
+    let buffer = buffer_with("one\ntwo\nthree");
+
+    assert!(buffer.write_lines(LineRange::new(1, 2), &path, false).is_ok());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\nthree\n");
+    assert!(buffer.write_lines(LineRange::new(0, 0), &path, true).is_ok());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\nthree\none\n");
+    // 写入一部分不改变缓冲区的文件路径
+    assert!(buffer.file_path().is_none());
+
+    let _ = std::fs::remove_file(&path);
+}
+
 // ==================== 边界情况测试 ====================
 
 #[test]
@@ -0,0 +1,102 @@
+//! 大小写和重排操作符单元测试
+//!
+//! 对应源文件: src/keymap.rs, src/editor.rs (gu / gU / g~ 大小写、gq 重排文本、textwidth)
+
+use aivim_core::keymap::{KeyParser, NormalCommand, Operator, OperatorCommand, OperatorTarget, ParseResult};
+use aivim_core::motion::Motion;
+use super::common::editor_with;
+
+fn parse(keys: &str) -> ParseResult {
+    let mut parser = KeyParser::new();
+    let mut result = ParseResult::Invalid;
+    for key in keys.chars() {
+        result = parser.feed(key);
+    }
+    result
+}
+
+fn operator(operator: Operator, target: OperatorTarget) -> ParseResult {
+    ParseResult::Complete(NormalCommand::Operator(OperatorCommand { operator, target, count: 1, register: None }))
+}
+
+#[test]
+fn test_parse_case_operators() {
+    assert_eq!(parse("gUw"), operator(Operator::Uppercase, OperatorTarget::Motion(Motion::WordForward)));
+    assert_eq!(parse("guu"), operator(Operator::Lowercase, OperatorTarget::Line));
+    assert_eq!(parse("gugu"), operator(Operator::Lowercase, OperatorTarget::Line));
+    assert_eq!(parse("g~~"), operator(Operator::ToggleCase, OperatorTarget::Line));
+    assert_eq!(parse("gqq"), operator(Operator::Format, OperatorTarget::Line));
+    assert_eq!(parse("gqgq"), operator(Operator::Format, OperatorTarget::Line));
+    // 不同的 g 操作符不能组合
+    assert_eq!(parse("gugU"), ParseResult::Invalid);
+}
+
+#[test]
+fn test_uppercase_word() {
+    let mut editor = editor_with("hello world\n", 0, 0);
+    assert!(editor.run_script(&["gUiw"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "HELLO world\n");
+    assert_eq!(editor.cursor().column, 0);
+}
+
+#[test]
+fn test_lowercase_and_toggle_lines() {
+    let mut editor = editor_with("Hello World\nÄBC\n", 0, 6);
+    assert!(editor.run_script(&["2guu"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "hello world\näbc\n");
+    // 光标移到范围的开头
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 0));
+
+    assert!(editor.run_script(&["g~$"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "HELLO WORLD\näbc\n");
+}
+
+#[test]
+fn test_case_operator_is_single_undo() {
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    assert!(editor.run_script(&["gUj"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "ONE\nTWO\n");
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "one\ntwo\n");
+}
+
+#[test]
+fn test_format_wraps_long_line() {
+    let words = vec!["word"; 20].join(" ");
+    let mut editor = editor_with(&format!("  {}\n", words), 0, 0);
+    assert!(editor.run_script(&["gqq"]).is_ok());
+
+    let text = editor.current_buffer().to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    // 每行不超过 79 列，保留缩进
+    assert!(lines.iter().all(|line| line.len() <= 79 && line.starts_with("  word")));
+    assert_eq!(lines.join(" ").split_whitespace().count(), 20);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 2));
+}
+
+#[test]
+fn test_format_uses_textwidth() {
+    let mut editor = editor_with("aaa bbb ccc ddd\n", 0, 0);
+    assert!(editor.execute_command("set tw=10").is_ok());
+    assert_eq!(editor.options().textwidth, 10);
+    assert!(editor.run_script(&["gqq"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "aaa bbb\nccc ddd\n");
+
+    // 0 时按 79 列重排
+    assert!(editor.execute_command("set textwidth=0").is_ok());
+    assert!(editor.run_script(&["gg", "gqj"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "aaa bbb ccc ddd\n");
+    assert!(editor.execute_command("set tw=x").is_err());
+}
+
+#[test]
+fn test_format_joins_paragraph_lines() {
+    let mut editor = editor_with("a\nb  c\n\nd\ne\n", 0, 0);
+    assert!(editor.run_script(&["gqG"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a b c\n\nd e\n");
+    assert_eq!(editor.cursor().line, 2);
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb  c\n\nd\ne\n");
+}
@@ -0,0 +1,130 @@
+//! AI 对话单元测试
+//!
+//! 对应源文件: src/chat.rs, src/ai_plugin.rs (:AiChat、对话的回复)
+
+use aivim_core::ai_plugin::AiPlugin;
+use aivim_core::chat::{format_user_message, ChatContext, CHAT_BUFFER_NAME};
+use aivim_core::editor::Editor;
+use aivim_core::range::LineRange;
+use aivim_core::window::Layout;
+use std::time::{Duration, Instant};
+use super::common::{editor_with_reply, has_curl};
+
+fn ai(editor: &Editor) -> &AiPlugin {
+    editor.plugin::<AiPlugin>().unwrap()
+}
+
+/// 等待回复结束
+fn wait_for_reply(editor: &mut Editor) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while ai(editor).chat().is_some_and(|chat| chat.is_responding()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        editor.poll_plugins(Instant::now());
+    }
+}
+
+fn chat_text(editor: &Editor) -> String {
+    let id = ai(editor).chat_buffer().unwrap();
+    editor.buffer(id).unwrap().to_string()
+}
+
+// ==================== 格式 ====================
+
+#[test]
+fn test_context_label_and_prompt() {
+    let context = ChatContext {
+        name: "main.rs".to_string(),
+        filetype: Some("rust".to_string()),
+        range: LineRange::new(9, 11),
+        text: "fn main() {\n}\n".to_string(),
+    };
+    assert_eq!(context.label(), "main.rs:10-12");
+    assert_eq!(context.prompt("why?"), "main.rs:10-12:\n```rust\nfn main() {\n}\n```\n\nwhy?");
+    assert_eq!(
+        format_user_message("why?", Some(&context)),
+        "## You\nwhy?\n> main.rs:10-12\n\n## AI\n"
+    );
+}
+
+// ==================== :AiChat ====================
+
+#[test]
+fn test_open_chat_window() {
+    let mut editor = Editor::new();
+    let code = editor.windows().current_id();
+    editor.execute_command("AiChat").unwrap();
+
+    // 对话窗口在右侧，并成为当前窗口
+    let chat = editor.windows().current_id();
+    assert_ne!(chat, code);
+    assert!(matches!(
+        editor.windows().layout(),
+        Layout::Split { children, .. } if children == &vec![Layout::Window(code), Layout::Window(chat)]
+    ));
+    assert_eq!(editor.current_buffer().display_name(), Some(CHAT_BUFFER_NAME));
+    assert!(editor.current_buffer().is_read_only());
+
+    // 再次打开时复用窗口
+    editor.focus_window(code);
+    editor.execute_command("AiChat").unwrap();
+    assert_eq!(editor.windows().len(), 2);
+    assert_eq!(editor.windows().current_id(), chat);
+}
+
+#[test]
+fn test_context_requires_message() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("%AiChat").is_err());
+    assert!(editor.execute_command("AiChat!").is_err());
+    assert_eq!(ai(&editor).chat_buffer(), None);
+}
+
+#[test]
+fn test_send_message_streams_reply() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("stream", "let x = 1;\n", &["Hello", ", world"]);
+    let code = editor.windows().current_id();
+    editor.execute_command("AiChat hi there").unwrap();
+
+    // 发送消息时留在原来的窗口
+    assert_eq!(editor.windows().current_id(), code);
+    assert_eq!(editor.windows().len(), 2);
+    wait_for_reply(&mut editor);
+    assert_eq!(chat_text(&editor), "## You\nhi there\n\n## AI\nHello, world\n\n");
+
+    let messages = ai(&editor).chat().unwrap().messages();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].content, "hi there");
+    assert_eq!(messages[1].content, "Hello, world");
+}
+
+#[test]
+fn test_range_attaches_context() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("range", "one\ntwo\nthree\n", &["ok"]);
+    editor.execute_command("2,3AiChat explain").unwrap();
+    wait_for_reply(&mut editor);
+    assert!(chat_text(&editor).starts_with("## You\nexplain\n> [No Name]:2-3\n\n## AI\nok\n"));
+    let question = &ai(&editor).chat().unwrap().messages()[0].content;
+    assert!(question.contains("```\ntwo\nthree\n```"));
+
+    // 回复结束后可以继续对话，历史保留
+    editor.execute_command("AiChat! and now?").unwrap();
+    wait_for_reply(&mut editor);
+    let messages = ai(&editor).chat().unwrap().messages();
+    assert_eq!(messages.len(), 4);
+    assert!(messages[2].content.contains("one\ntwo\nthree"));
+}
+
+#[test]
+fn test_deleting_buffer_ends_chat() {
+    let mut editor = Editor::new();
+    editor.execute_command("AiChat").unwrap();
+    editor.execute_command("bd").unwrap();
+    editor.poll_plugins(Instant::now());
+    assert_eq!(ai(&editor).chat_buffer(), None);
+}
@@ -0,0 +1,155 @@
+//! 配色方案单元测试
+//!
+//! 对应源文件: src/colorscheme.rs, src/editor.rs (:colorscheme / :highlight 命令)
+
+use aivim_core::colorscheme::{
+    parse_color, parse_highlight, Highlight, HighlightAttrs, HighlightColor, HighlightError, DEFAULT_COLORSCHEME,
+};
+use aivim_core::editor::Editor;
+
+fn args(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_string).collect()
+}
+
+// ==================== 解析测试 ====================
+
+#[test]
+fn test_parse_color() {
+    assert_eq!(parse_color("red"), Some(HighlightColor::Indexed(1)));
+    assert_eq!(parse_color("DarkGrey"), Some(HighlightColor::Indexed(8)));
+    assert_eq!(parse_color("208"), Some(HighlightColor::Indexed(208)));
+    assert_eq!(parse_color("#fabd2F"), Some(HighlightColor::Rgb(0xfa, 0xbd, 0x2f)));
+    assert_eq!(parse_color("NONE"), Some(HighlightColor::Reset));
+    assert_eq!(parse_color("#fff"), None);
+    assert_eq!(parse_color("256"), None);
+    assert_eq!(parse_color("purpleish"), None);
+}
+
+#[test]
+fn test_parse_highlight() {
+    let (group, highlight) = parse_highlight(&args("linenr fg=#928374 gui=bold,italic")).unwrap();
+    assert_eq!(group, "LineNr");
+    assert_eq!(highlight.fg, Some(HighlightColor::Rgb(0x92, 0x83, 0x74)));
+    assert_eq!(highlight.bg, None);
+    assert_eq!(
+        highlight.attrs,
+        Some(HighlightAttrs { bold: true, italic: true, ..HighlightAttrs::default() })
+    );
+
+    let (_, highlight) = parse_highlight(&args("Pmenu ctermbg=236 attr=NONE")).unwrap();
+    assert_eq!(highlight.bg, Some(HighlightColor::Indexed(236)));
+    assert_eq!(highlight.attrs, Some(HighlightAttrs::default()));
+}
+
+#[test]
+fn test_parse_highlight_errors() {
+    assert_eq!(parse_highlight(&[]), Err(HighlightError::InvalidArgument(String::new())));
+    assert_eq!(parse_highlight(&args("LineNr")), Err(HighlightError::InvalidArgument(String::new())));
+    assert_eq!(
+        parse_highlight(&args("NoSuchGroup fg=red")),
+        Err(HighlightError::UnknownGroup("NoSuchGroup".to_string()))
+    );
+    assert_eq!(
+        parse_highlight(&args("LineNr fg=nope")),
+        Err(HighlightError::InvalidArgument("fg=nope".to_string()))
+    );
+    assert_eq!(
+        parse_highlight(&args("LineNr blink")),
+        Err(HighlightError::InvalidArgument("blink".to_string()))
+    );
+    assert_eq!(
+        parse_highlight(&args("LineNr attr=blink")),
+        Err(HighlightError::InvalidArgument("attr=blink".to_string()))
+    );
+}
+
+// ==================== 命令测试 ====================
+
+#[test]
+fn test_colorscheme_command() {
+    let mut editor = Editor::new();
+    assert_eq!(editor.colorscheme(), DEFAULT_COLORSCHEME);
+
+    editor.execute_command("colo gruvbox").unwrap();
+    assert_eq!(editor.colorscheme(), "gruvbox");
+
+    // 不带参数时显示当前的配色方案
+    editor.execute_command("colorscheme").unwrap();
+    assert_eq!(editor.message(), Some("gruvbox"));
+
+    assert_eq!(
+        editor.execute_command("colorscheme solarized"),
+        Err("Cannot find color scheme: solarized".to_string())
+    );
+    assert_eq!(editor.colorscheme(), "gruvbox");
+}
+
+#[test]
+fn test_highlight_command_merges_overrides() {
+    let mut editor = Editor::new();
+    editor.execute_command("hi LineNr fg=red").unwrap();
+    editor.execute_command("highlight linenr bg=#000000").unwrap();
+    editor.execute_command("hi Search attr=reverse").unwrap();
+    assert_eq!(
+        editor.highlights(),
+        &[
+            (
+                "LineNr",
+                Highlight {
+                    fg: Some(HighlightColor::Indexed(1)),
+                    bg: Some(HighlightColor::Rgb(0, 0, 0)),
+                    attrs: None,
+                }
+            ),
+            (
+                "Search",
+                Highlight {
+                    fg: None,
+                    bg: None,
+                    attrs: Some(HighlightAttrs { reverse: true, ..HighlightAttrs::default() }),
+                }
+            ),
+        ]
+    );
+
+    editor.execute_command("hi clear").unwrap();
+    assert!(editor.highlights().is_empty());
+}
+
+#[test]
+fn test_highlight_command_errors() {
+    let mut editor = Editor::new();
+    assert_eq!(editor.execute_command("hi"), Err("Argument required".to_string()));
+    assert_eq!(
+        editor.execute_command("hi Foo fg=red"),
+        Err("Unknown highlight group: Foo".to_string())
+    );
+    assert_eq!(
+        editor.execute_command("hi LineNr fg=nope"),
+        Err("Invalid argument: fg=nope".to_string())
+    );
+    assert!(editor.highlights().is_empty());
+}
+
+#[test]
+fn test_colorscheme_clears_highlights() {
+    let mut editor = Editor::new();
+    editor.execute_command("hi LineNr fg=red").unwrap();
+    editor.execute_command("colorscheme light").unwrap();
+    assert!(editor.highlights().is_empty());
+}
+
+#[test]
+fn test_colorscheme_from_config_file() {
+    let dir = std::env::temp_dir().join(format!("aivim_colorscheme_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("aivimrc");
+    std::fs::write(&path, "\" 配色\ncolorscheme gruvbox\nhighlight CursorLine bg=236\n").unwrap();
+
+    let mut editor = Editor::new();
+    editor.source_file(&path).unwrap();
+    assert_eq!(editor.colorscheme(), "gruvbox");
+    assert_eq!(editor.highlights()[0].0, "CursorLine");
+
+    std::fs::remove_dir_all(&dir).ok();
+}
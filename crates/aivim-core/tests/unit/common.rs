@@ -0,0 +1,91 @@
+//! 测试共用的辅助函数
+
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::window::WindowRect;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 缓冲区内容为 `content`，光标在第 `line` 行第 `column` 列的编辑器
+pub fn editor_with(content: &str, line: usize, column: usize) -> Editor {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, content);
+    *editor.cursor_mut() = Cursor::new(line, column);
+    editor
+}
+
+/// AI 相关的测试通过 curl 读取 `file://` 地址，没有 curl 时跳过
+pub fn has_curl() -> bool {
+    Command::new("curl").arg("--version").output().is_ok()
+}
+
+/// 把 `bytes` 写入临时文件并打开，返回编辑器和文件路径
+///
+/// 所有测试共用同一个目录，`name` 在各测试文件之间不能重复
+pub fn editor_with_bytes(name: &str, bytes: &[u8]) -> (Editor, PathBuf) {
+    let path = std::env::temp_dir().join(format!("aivim_file_{}_{}.txt", name, std::process::id()));
+    fs::write(&path, bytes).unwrap();
+    (Editor::with_file(&path).unwrap(), path)
+}
+
+/// 把文本 `content` 写入临时文件并打开，返回编辑器和文件路径
+pub fn editor_with_file(name: &str, content: &str) -> (Editor, PathBuf) {
+    editor_with_bytes(name, content.as_bytes())
+}
+
+/// git 相关的测试需要 git 命令，没有时跳过
+pub fn has_git() -> bool {
+    Command::new("git").arg("--version").output().is_ok()
+}
+
+/// 在 `dir` 中运行 git，要求成功
+pub fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git").args(args).current_dir(dir).output().unwrap().status;
+    assert!(status.success(), "git {:?}", args);
+}
+
+/// 在临时目录中创建仓库，内容为 `content` 的 `a.txt` 已暂存
+pub fn staged_repo(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aivim_repo_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    git(&dir, &["init", "-q"]);
+    fs::write(dir.join("a.txt"), content).unwrap();
+    git(&dir, &["add", "a.txt"]);
+    dir
+}
+
+/// 在临时目录中创建仓库并提交 `a.txt`，作者为 Tester，提交说明为 Initial import
+pub fn init_repo(name: &str, content: &str) -> PathBuf {
+    let dir = staged_repo(name, content);
+    git(&dir, &["-c", "user.name=Tester", "-c", "user.email=t@example.com", "commit", "-q", "-m", "Initial import"]);
+    dir
+}
+
+/// 打开文本，AI 服务换成返回 `reply` 的 `file://` 地址（`reply` 中的换行写作 `\\n`）
+///
+/// 每次调用写入不同的回复文件，并行运行的测试可以使用相同的 `name`
+pub fn editor_with_reply(name: &str, text: &str, reply: &[&str]) -> Editor {
+    static NEXT_REPLY: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "aivim_reply_{}_{}_{}.txt",
+        name,
+        std::process::id(),
+        NEXT_REPLY.fetch_add(1, Ordering::Relaxed)
+    ));
+    let response: String = reply
+        .iter()
+        .map(|delta| format!("data: {{\"choices\":[{{\"delta\":{{\"content\":\"{}\"}}}}]}}\n\n", delta))
+        .chain(["data: [DONE]\n".to_string()])
+        .collect();
+    fs::write(&path, response).unwrap();
+
+    let mut editor = Editor::new();
+    editor.open_bytes(text.as_bytes());
+    editor.set_window_area(WindowRect { x: 0, y: 0, width: 80, height: 20 });
+    editor.execute_command(&format!("set aiendpoint=file://{}", path.display())).unwrap();
+    editor.execute_command("set aikeyenv=AIVIM_TEST_UNSET_KEY").unwrap();
+    editor
+}
@@ -0,0 +1,202 @@
+//! 补全菜单单元测试
+//!
+//! 对应源文件: src/completion.rs, src/editor.rs (补全菜单操作)
+
+use aivim_core::completion::{
+    collect_word_candidates, filter_lsp_items, word_prefix_start, CompletionItem, CompletionMenu,
+};
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::mode::Mode;
+
+fn items(labels: &[&str]) -> Vec<CompletionItem> {
+    labels.iter().map(|l| CompletionItem::new(*l)).collect()
+}
+
+/// 创建处于 Insert 模式、光标在指定位置的编辑器
+fn insert_editor(content: &str, line: usize, column: usize) -> Editor {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, content);
+    editor.set_mode(Mode::Insert);
+    *editor.cursor_mut() = Cursor::new(line, column);
+    editor
+}
+
+// ==================== 候选项收集测试 ====================
+
+#[test]
+fn test_word_prefix_start() {
+    assert_eq!(word_prefix_start("let foo_ba", 10), 4);
+    assert_eq!(word_prefix_start("let ", 4), 4);
+    assert_eq!(word_prefix_start("abc", 0), 0);
+}
+
+#[test]
+fn test_collect_word_candidates_dedup_and_source_line() {
+    let lines = vec![(0, "hello help"), (1, "hello helmet"), (2, "he")];
+    let result = collect_word_candidates(lines, "hel");
+
+    let labels: Vec<&str> = result.iter().map(|i| i.label.as_str()).collect();
+    assert_eq!(labels, vec!["hello", "help", "helmet"]);
+    assert_eq!(result[2].source_line, Some(1));
+}
+
+#[test]
+fn test_collect_word_candidates_skips_exact_prefix() {
+    let lines = vec![(0, "foo foobar")];
+    let result = collect_word_candidates(lines, "foo");
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].label, "foobar");
+}
+
+// ==================== 菜单导航测试 ====================
+
+#[test]
+fn test_menu_select_wraps() {
+    let mut menu = CompletionMenu::new(items(&["a", "b", "c"]), 0, 0);
+    assert_eq!(menu.selected_index(), 0);
+
+    menu.select_prev();
+    assert_eq!(menu.selected_index(), 2);
+
+    menu.select_next();
+    assert_eq!(menu.selected_index(), 0);
+}
+
+#[test]
+fn test_menu_scrolls_long_list() {
+    let labels: Vec<String> = (0..20).map(|i| format!("item{}", i)).collect();
+    let labels: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+    let mut menu = CompletionMenu::new(items(&labels), 0, 0);
+    menu.set_max_visible(5);
+
+    for _ in 0..6 {
+        menu.select_next();
+    }
+    assert_eq!(menu.selected_index(), 6);
+    assert_eq!(menu.scroll_offset(), 2);
+
+    let visible: Vec<usize> = menu.visible_items().map(|(i, _)| i).collect();
+    assert_eq!(visible, vec![2, 3, 4, 5, 6]);
+
+    // 从开头回绕到末尾时滚动到底部
+    let mut menu = CompletionMenu::new(items(&labels), 0, 0);
+    menu.set_max_visible(5);
+    menu.select_prev();
+    assert_eq!(menu.scroll_offset(), 15);
+}
+
+#[test]
+fn test_menu_page_down_up() {
+    let labels: Vec<String> = (0..12).map(|i| format!("w{}", i)).collect();
+    let labels: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+    let mut menu = CompletionMenu::new(items(&labels), 0, 0);
+    menu.set_max_visible(5);
+
+    menu.page_down();
+    assert_eq!(menu.selected_index(), 5);
+    menu.page_down();
+    menu.page_down();
+    assert_eq!(menu.selected_index(), 11);
+
+    menu.page_up();
+    assert_eq!(menu.selected_index(), 6);
+}
+
+#[test]
+fn test_filter_lsp_items() {
+    let items = aivim_lsp::completion::parse_response(&serde_json::json!([
+        { "label": "Vec", "kind": 22 },
+        { "label": "vec!", "kind": 15, "filterText": "vec" },
+        { "label": "HashMap", "kind": 22 },
+    ]));
+    // 不区分大小写地按 filterText 匹配前缀
+    let filtered = filter_lsp_items(&items, "ve");
+    let labels: Vec<&str> = filtered.iter().map(|item| item.label.as_str()).collect();
+    assert_eq!(labels, vec!["Vec", "vec!"]);
+    assert_eq!(filtered[0].kind, Some("S "));
+    assert!(filtered[1].lsp_item.is_some());
+    assert_eq!(filter_lsp_items(&items, "").len(), 3);
+}
+
+// ==================== Editor 集成测试 ====================
+
+#[test]
+fn test_start_word_completion() {
+    let mut editor = insert_editor("apple apricot\nap", 1, 2);
+
+    assert!(editor.start_word_completion());
+    let menu = editor.completion().unwrap();
+    assert_eq!(menu.len(), 2);
+    assert_eq!(menu.start_column(), 0);
+}
+
+#[test]
+fn test_start_word_completion_no_match() {
+    let mut editor = insert_editor("apple\nzz", 1, 2);
+
+    assert!(!editor.start_word_completion());
+    assert!(editor.completion().is_none());
+}
+
+#[test]
+fn test_completion_requires_insert_mode() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "apple ap");
+    *editor.cursor_mut() = Cursor::new(0, 8);
+
+    assert!(!editor.start_word_completion());
+}
+
+#[test]
+fn test_accept_completion_replaces_prefix() {
+    let mut editor = insert_editor("apple apricot\nx ap", 1, 4);
+
+    editor.start_word_completion();
+    editor.completion_next(); // 选中 "apricot"
+    assert!(editor.accept_completion());
+
+    assert_eq!(editor.get_line_text(1).unwrap(), "x apricot");
+    assert_eq!(editor.cursor().column, 9);
+    assert!(editor.completion().is_none());
+}
+
+#[test]
+fn test_cancel_completion_keeps_text() {
+    let mut editor = insert_editor("apple\nap", 1, 2);
+
+    editor.start_word_completion();
+    editor.cancel_completion();
+
+    assert!(editor.completion().is_none());
+    assert_eq!(editor.get_line_text(1).unwrap(), "ap");
+}
+
+#[test]
+fn test_completion_preview_shows_source_line() {
+    let mut editor = insert_editor("  let counter = 1;\ncou", 1, 3);
+
+    editor.start_word_completion();
+    assert_eq!(editor.completion_preview(), Some("1: let counter = 1;".to_string()));
+}
+
+#[test]
+fn test_leaving_insert_mode_closes_completion() {
+    let mut editor = insert_editor("apple\nap", 1, 2);
+
+    editor.start_word_completion();
+    editor.set_mode(Mode::Normal);
+    assert!(editor.completion().is_none());
+}
+
+#[test]
+fn test_lsp_completion_without_server_uses_words() {
+    let mut editor = insert_editor("apple\nap", 1, 2);
+
+    // 没有语言服务器时 Ctrl+Space 使用单词补全，触发字符不做任何事
+    assert!(editor.request_completion());
+    assert_eq!(editor.completion().unwrap().items()[0].label, "apple");
+    editor.cancel_completion();
+    assert!(!editor.completion_trigger('.'));
+    assert!(editor.completion().is_none());
+}
@@ -0,0 +1,48 @@
+//! 配置文件单元测试
+//!
+//! 对应源文件: src/config.rs, src/editor.rs (:source、:set timeoutlen)
+
+use aivim_core::config::config_commands;
+use aivim_core::editor::{Editor, DEFAULT_TIMEOUTLEN};
+use aivim_core::mapping::{parse_keys, MapMode, MapScope};
+use std::path::Path;
+
+#[test]
+fn test_config_commands() {
+    let text = "\" 注释\n\nset number\n  :nmap x y\n:\n";
+    assert_eq!(config_commands(text), vec![(3, "set number"), (4, "nmap x y")]);
+}
+
+#[test]
+fn test_source_file() {
+    let path = Path::new("/tmp/aivim_test_source.vim");
+    std::fs::write(path, "let mapleader = \" \"\nset nu timeoutlen=300\nnnoremap <leader>w :w<CR>\n").unwrap();
+
+    let mut editor = Editor::new();
+    assert_eq!(editor.options().timeoutlen, DEFAULT_TIMEOUTLEN);
+    assert!(editor.execute_command("so /tmp/aivim_test_source.vim").is_ok());
+    assert!(editor.options().number);
+    assert_eq!(editor.options().timeoutlen, 300);
+    assert!(editor.mappings().get(MapScope::Global, MapMode::Normal, &parse_keys("<Space>w")).is_some());
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_source_file_errors() {
+    let path = Path::new("/tmp/aivim_test_source_errors.vim");
+    std::fs::write(path, "set nu\nfoo\nset bar\nset rnu\n").unwrap();
+
+    // 出错的命令不影响后面的命令，报告第一个错误
+    let mut editor = Editor::new();
+    assert_eq!(
+        editor.source_file(path),
+        Err("Error in /tmp/aivim_test_source_errors.vim line 2: Unknown command: foo".to_string())
+    );
+    assert!(editor.options().number);
+    assert!(editor.options().relativenumber);
+
+    let _ = std::fs::remove_file(path);
+    assert!(editor.source_file(path).is_err());
+    assert_eq!(editor.execute_command("source"), Err("Filename required".to_string()));
+}
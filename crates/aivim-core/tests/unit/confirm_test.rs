@@ -0,0 +1,202 @@
+//! 确认提示单元测试
+//!
+//! 对应源文件: src/editor.rs (PendingConfirmation、ConfirmAnswer、:q / :qa 退出前保存), src/input.rs (回答的按键)
+
+use aivim_core::editor::{ConfirmAnswer, Editor, PendingConfirmation};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use super::common::editor_with;
+
+/// 打开两个临时文件并都修改，当前缓冲区为第二个文件
+fn editor_with_two_modified(name: &str) -> (Editor, PathBuf, PathBuf) {
+    let first = PathBuf::from(format!("/tmp/aivim_confirm_{}_1.txt", name));
+    let second = PathBuf::from(format!("/tmp/aivim_confirm_{}_2.txt", name));
+    fs::write(&first, "one\n").unwrap();
+    fs::write(&second, "two\n").unwrap();
+    let mut editor = Editor::with_file(&first).unwrap();
+    assert!(editor.run_script(&["A!<Esc>"]).is_ok());
+    editor.open_file(&second).unwrap();
+    assert!(editor.run_script(&["A!<Esc>"]).is_ok());
+    (editor, first, second)
+}
+
+#[test]
+fn test_answer_keys() {
+    assert_eq!(ConfirmAnswer::from_key('y'), Some(ConfirmAnswer::Yes));
+    assert_eq!(ConfirmAnswer::from_key('N'), Some(ConfirmAnswer::No));
+    assert_eq!(ConfirmAnswer::from_key('a'), Some(ConfirmAnswer::All));
+    assert_eq!(ConfirmAnswer::from_key('q'), Some(ConfirmAnswer::Quit));
+    assert_eq!(ConfirmAnswer::from_key('x'), None);
+    assert_eq!(ConfirmAnswer::All.key(), 'a');
+}
+
+#[test]
+fn test_quit_asks_for_each_modified_buffer() {
+    let (mut editor, first, second) = editor_with_two_modified("each");
+    editor.execute_command("q").unwrap();
+    // 从当前缓冲区开始询问
+    assert_eq!(editor.message(), Some("Save changes to aivim_confirm_each_2.txt? (y/n/a/q)"));
+    assert_eq!(editor.pending_confirmation().unwrap().choices().len(), 4);
+
+    // 其他按键不取消多选的提示
+    assert!(editor.run_script(&["x"]).is_ok());
+    assert!(editor.pending_confirmation().is_some());
+
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(fs::read_to_string(&second).unwrap(), "two!\n");
+    assert!(!editor.quit_requested());
+    assert_eq!(editor.message(), Some("Save changes to aivim_confirm_each_1.txt? (y/n/a/q)"));
+
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!(fs::read_to_string(&first).unwrap(), "one\n");
+    assert!(editor.quit_requested());
+}
+
+#[test]
+fn test_quit_all_saves_all_or_cancels() {
+    let (mut editor, first, second) = editor_with_two_modified("all");
+    editor.execute_command("qa").unwrap();
+    assert!(editor.run_script(&["<Esc>"]).is_ok());
+    assert_eq!(editor.message(), Some("Cancelled"));
+    assert!(!editor.quit_requested());
+
+    editor.execute_command("qa").unwrap();
+    editor.answer_confirmation(ConfirmAnswer::All).unwrap();
+    assert_eq!(fs::read_to_string(&first).unwrap(), "one!\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "two!\n");
+    assert!(editor.quit_requested());
+
+    // 没有修改时直接退出，:qa! 不询问
+    let (mut editor, _, _) = editor_with_two_modified("force");
+    editor.execute_command("qa!").unwrap();
+    assert!(editor.pending_confirmation().is_none());
+    assert!(editor.quit_requested());
+}
+
+#[test]
+fn test_quit_save_failure_stops() {
+    let mut editor = Editor::new();
+    assert!(editor.run_script(&["ix<Esc>"]).is_ok());
+    editor.execute_command("q").unwrap();
+    assert!(matches!(editor.pending_confirmation(), Some(PendingConfirmation::SaveBeforeQuit { .. })));
+    editor.run_script(&["y"]);
+    assert!(editor.message().unwrap().starts_with("Cannot save [No Name]: "));
+    assert!(editor.pending_confirmation().is_none());
+    assert!(!editor.quit_requested());
+}
+
+#[test]
+fn test_quit_save_checks_protected_paths() {
+    let (mut editor, first, second) = editor_with_two_modified("protected");
+    editor.execute_command("set protectedpaths=/tmp/aivim_confirm_protected_2.txt").unwrap();
+    editor.execute_command("q").unwrap();
+    // 与 :w 一样先确认是否写入受保护的路径，取消时不退出
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(editor.pending_confirmation(), Some(&PendingConfirmation::WriteProtected(second.clone())));
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!(fs::read_to_string(&second).unwrap(), "two\n");
+    assert!(!editor.quit_requested());
+
+    // 确认写入之后继续询问下一个缓冲区
+    editor.execute_command("q").unwrap();
+    assert!(editor.run_script(&["y", "y"]).is_ok());
+    assert_eq!(fs::read_to_string(&second).unwrap(), "two!\n");
+    assert_eq!(editor.message(), Some("Save changes to aivim_confirm_protected_1.txt? (y/n/a/q)"));
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(fs::read_to_string(&first).unwrap(), "one!\n");
+    assert!(editor.quit_requested());
+}
+
+#[test]
+fn test_quit_save_all_checks_changed_on_disk() {
+    let (mut editor, first, second) = editor_with_two_modified("changed");
+    fs::write(&first, "other\n").unwrap();
+    let file = fs::File::options().write(true).open(&first).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+
+    editor.execute_command("qa").unwrap();
+    editor.answer_confirmation(ConfirmAnswer::All).unwrap();
+    // 第二个文件已经保存，在外部被修改过的第一个文件先确认
+    assert_eq!(fs::read_to_string(&second).unwrap(), "two!\n");
+    assert_eq!(editor.pending_confirmation(), Some(&PendingConfirmation::OverwriteChanged(first.clone())));
+    assert!(!editor.quit_requested());
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(fs::read_to_string(&first).unwrap(), "one!\n");
+    assert!(editor.quit_requested());
+}
+
+#[test]
+fn test_yes_no_prompt_cancelled_by_other_keys() {
+    let path = PathBuf::from("/tmp/aivim_confirm_ro.txt");
+    fs::write(&path, "a\n").unwrap();
+    let mut editor = Editor::new();
+    editor.set_read_only_mode(true);
+    editor.open_file(&path).unwrap();
+
+    editor.execute_command("w").unwrap();
+    assert_eq!(editor.message(), Some("/tmp/aivim_confirm_ro.txt is read-only, write anyway? (y/n)"));
+    assert!(editor.run_script(&["a"]).is_ok());
+    assert_eq!(editor.message(), Some("Cancelled"));
+    assert!(editor.pending_confirmation().is_none());
+
+    // 文件在外部被修改过时再确认一次
+    fs::write(&path, "c\n").unwrap();
+    let file = fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+    editor.execute_command("w").unwrap();
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(editor.pending_confirmation(), Some(&PendingConfirmation::OverwriteChanged(path.clone())));
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\n");
+}
+
+// ==================== :s///c 测试 ====================
+
+#[test]
+fn test_substitute_confirm_each_match() {
+    let mut editor = editor_with("a a\nb\na\n", 0, 0);
+    editor.execute_command("%s/a/x/gc").unwrap();
+    assert_eq!(editor.message(), Some("Replace with x? (y/n/a/q/l)"));
+    assert_eq!(editor.pending_confirmation().unwrap().choices().len(), 5);
+    assert_eq!(editor.substitute_candidate().map(|c| (c.1, c.2, c.3)), Some((0, 0, 1)));
+
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(editor.substitute_candidate().map(|c| (c.1, c.2)), Some((0, 2)));
+    assert_eq!(editor.cursor().column, 2);
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!(editor.substitute_candidate().map(|c| (c.1, c.2)), Some((2, 0)));
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert!(editor.pending_confirmation().is_none());
+    assert_eq!(editor.current_buffer().to_string(), "x a\nb\nx\n");
+    assert_eq!(editor.message(), Some("Replaced 2 occurrence(s)"));
+
+    // 所有替换作为一次修改撤销
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a a\nb\na\n");
+}
+
+#[test]
+fn test_substitute_confirm_all_last_and_quit() {
+    let mut editor = editor_with("a a\na\n", 0, 0);
+    editor.execute_command("%s/a/x/gc").unwrap();
+    assert!(editor.run_script(&["n", "a"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a x\nx\n");
+
+    let mut editor = editor_with("a a\na\n", 0, 0);
+    editor.execute_command("%s/a/x/gc").unwrap();
+    assert!(editor.run_script(&["l"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "x a\na\n");
+    assert_eq!(editor.message(), Some("Replaced 1 occurrence(s)"));
+
+    // q 和 Esc 停止，其他按键忽略
+    let mut editor = editor_with("a a\na\n", 0, 0);
+    editor.execute_command("%s/a/x/c").unwrap();
+    assert!(editor.run_script(&["j", "<Esc>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a a\na\n");
+    assert_eq!(editor.message(), Some("Replaced 0 occurrence(s)"));
+
+    // 没有匹配时不询问
+    editor.execute_command("s/zzz/x/c").unwrap();
+    assert!(editor.pending_confirmation().is_none());
+}
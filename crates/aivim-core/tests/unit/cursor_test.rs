@@ -277,7 +277,7 @@ fn test_cursor_preferred_column() {
     buffer.insert(0, "Hello\nHi\nWorld");
     
     let mut cursor = Cursor::new(0, 5);
-    cursor.update_preferred_column();
+    cursor.update_preferred_column(&buffer);
     assert_eq!(cursor.preferred_column, Some(5));
     
     // 移动到短行
@@ -292,7 +292,7 @@ fn test_cursor_move_down_with_preferred_column() {
     buffer.insert(0, "Hello World\nHi\nTest Line");
     
     let mut cursor = Cursor::new(0, 8);
-    cursor.update_preferred_column();
+    cursor.update_preferred_column(&buffer);
     
     // 移动到短行
     cursor.move_down(&buffer, 1);
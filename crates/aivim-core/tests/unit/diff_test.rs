@@ -0,0 +1,119 @@
+//! 行级差异单元测试
+//!
+//! 对应源文件: src/diff.rs, src/editor.rs (:DiffOrig)
+
+use std::fs;
+use std::path::Path;
+
+use aivim_core::diff::{diff_lines, unified_diff, DiffOp};
+use aivim_core::editor::Editor;
+
+fn ops(old: &[&str], new: &[&str]) -> Vec<(DiffOp, String)> {
+    diff_lines(old, new)
+        .into_iter()
+        .map(|line| (line.op, line.text.to_string()))
+        .collect()
+}
+
+// ==================== diff_lines 测试 ====================
+
+#[test]
+fn test_diff_identical() {
+    let lines = diff_lines(&["a", "b"], &["a", "b"]);
+    assert!(lines.iter().all(|line| line.op == DiffOp::Equal));
+    assert_eq!(lines[1].old_line, Some(1));
+    assert_eq!(lines[1].new_line, Some(1));
+}
+
+#[test]
+fn test_diff_insert_and_delete() {
+    assert_eq!(
+        ops(&["a", "b", "c"], &["a", "x", "c", "d"]),
+        vec![
+            (DiffOp::Equal, "a".to_string()),
+            (DiffOp::Delete, "b".to_string()),
+            (DiffOp::Insert, "x".to_string()),
+            (DiffOp::Equal, "c".to_string()),
+            (DiffOp::Insert, "d".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_diff_line_numbers() {
+    let lines = diff_lines(&["a", "b"], &["x", "a", "b"]);
+    assert_eq!(lines[0].op, DiffOp::Insert);
+    assert_eq!((lines[0].old_line, lines[0].new_line), (None, Some(0)));
+    assert_eq!((lines[2].old_line, lines[2].new_line), (Some(1), Some(2)));
+}
+
+#[test]
+fn test_diff_empty_sides() {
+    assert_eq!(ops(&[], &["a"]), vec![(DiffOp::Insert, "a".to_string())]);
+    assert_eq!(ops(&["a"], &[]), vec![(DiffOp::Delete, "a".to_string())]);
+}
+
+// ==================== unified_diff 测试 ====================
+
+#[test]
+fn test_unified_diff_no_changes() {
+    assert_eq!(unified_diff("a", "b", "x\ny\n", "x\ny\n", 3), None);
+}
+
+#[test]
+fn test_unified_diff_hunks() {
+    let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+    let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\nten\n";
+    let diff = unified_diff("old", "new", old, new, 1).unwrap();
+    assert_eq!(
+        diff,
+        "--- old\n+++ new\n@@ -2,3 +2,3 @@\n 2\n-3\n+three\n 4\n@@ -9,1 +9,2 @@\n 9\n+ten\n"
+    );
+}
+
+#[test]
+fn test_unified_diff_pure_insert_without_context() {
+    let diff = unified_diff("old", "new", "a\nb\n", "a\nx\nb\n", 0).unwrap();
+    assert!(diff.ends_with("@@ -1,0 +2,1 @@\n+x\n"));
+}
+
+// ==================== :DiffOrig 测试 ====================
+
+#[test]
+fn test_diff_orig_opens_scratch_buffer() {
+    let path = Path::new("/tmp/aivim_diff_orig.txt");
+    fs::write(path, "a\nb\n").unwrap();
+    let mut editor = Editor::with_file(path).unwrap();
+    let original = editor.current_buffer().id();
+    editor.current_buffer_mut().insert(2, "x\n");
+
+    assert!(editor.execute_command("DiffOrig").is_ok());
+    let buffer = editor.current_buffer();
+    assert_ne!(buffer.id(), original);
+    assert!(buffer.is_read_only());
+    assert!(!buffer.is_modified());
+    assert_eq!(buffer.filetype(), Some("diff"));
+    assert_eq!(buffer.display_name(), Some("[DiffOrig] aivim_diff_orig.txt"));
+    let text = buffer.to_string();
+    assert!(text.contains("@@ -1,2 +1,3 @@\n a\n+x\n b\n"));
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_diff_orig_without_changes() {
+    let path = Path::new("/tmp/aivim_diff_orig_same.txt");
+    fs::write(path, "a\n").unwrap();
+    let mut editor = Editor::with_file(path).unwrap();
+    let original = editor.current_buffer().id();
+
+    assert!(editor.execute_command("DiffOrig").is_ok());
+    assert_eq!(editor.current_buffer().id(), original);
+    assert_eq!(editor.message(), Some("No changes"));
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_diff_orig_without_file() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("DiffOrig").is_err());
+}
@@ -0,0 +1,255 @@
+//! 显示宽度单元测试
+//!
+//! 对应源文件: src/display.rs, src/editor.rs (Tab 键、tabstop / expandtab 选项、wrap 折行、gj / gk、字形簇)
+
+use aivim_core::cursor::Cursor;
+use aivim_core::display::{
+    char_at_column, char_width, clip_line, display_column, expand_tabs, grapheme_start, last_grapheme, next_grapheme,
+    prev_grapheme, wrap_line, ScreenRow, DEFAULT_TABSTOP,
+};
+use aivim_core::editor::Editor;
+use aivim_core::mode::Mode;
+use super::common::editor_with;
+
+// ==================== 显示宽度测试 ====================
+
+#[test]
+fn test_char_width() {
+    assert_eq!(char_width('a', 0, 8), 1);
+    assert_eq!(char_width('中', 0, 8), 2);
+    // Tab 占据到下一个 tabstop 整数倍的列
+    assert_eq!(char_width('\t', 0, 8), 8);
+    assert_eq!(char_width('\t', 3, 8), 5);
+    assert_eq!(char_width('\t', 8, 4), 4);
+}
+
+#[test]
+fn test_display_column() {
+    assert_eq!(display_column("\tab", 0, 8), 0);
+    assert_eq!(display_column("\tab", 1, 8), 8);
+    assert_eq!(display_column("ab\tc", 3, 4), 4);
+    assert_eq!(display_column("中文x", 2, 8), 4);
+}
+
+#[test]
+fn test_expand_tabs() {
+    assert_eq!(expand_tabs("\tfoo", 4), "    foo");
+    assert_eq!(expand_tabs("ab\tc\td", 4), "ab  c   d");
+    assert_eq!(expand_tabs("plain", 4), "plain");
+}
+
+// ==================== 选项测试 ====================
+
+#[test]
+fn test_tab_options() {
+    let mut editor = Editor::new();
+    assert_eq!(editor.options().tabstop, DEFAULT_TABSTOP);
+    assert!(editor.options().expandtab);
+
+    editor.execute_command("set ts=4 noet").unwrap();
+    assert_eq!(editor.options().tabstop, 4);
+    assert!(!editor.options().expandtab);
+    editor.execute_command("set expandtab").unwrap();
+    assert!(editor.options().expandtab);
+
+    assert!(editor.execute_command("set tabstop=0").is_err());
+    assert_eq!(editor.options().tabstop, 4);
+}
+
+#[test]
+fn test_display_column_uses_tabstop() {
+    let mut editor = editor_with("\tx\n", 0, 1);
+    assert_eq!(editor.display_column(0, 1), 8);
+    editor.execute_command("set ts=2").unwrap();
+    assert_eq!(editor.display_column(0, 1), 2);
+}
+
+// ==================== Tab 键测试 ====================
+
+#[test]
+fn test_insert_tab_expandtab() {
+    // 插入空格到下一个 shiftwidth 整数倍的列
+    let mut editor = editor_with("ab\n", 0, 2);
+    editor.set_mode(Mode::Insert);
+    editor.insert_tab();
+    assert_eq!(editor.current_buffer().to_string(), "ab  \n");
+    editor.insert_tab();
+    assert_eq!(editor.current_buffer().to_string(), "ab      \n");
+    assert_eq!(editor.cursor().column, 8);
+}
+
+#[test]
+fn test_insert_tab_noexpandtab() {
+    let mut editor = editor_with("ab\n", 0, 2);
+    editor.execute_command("set noexpandtab").unwrap();
+    editor.set_mode(Mode::Insert);
+    editor.insert_tab();
+    assert_eq!(editor.current_buffer().to_string(), "ab\t\n");
+}
+
+#[test]
+fn test_insert_tab_paste_mode() {
+    let mut editor = editor_with("\n", 0, 0);
+    editor.execute_command("set paste").unwrap();
+    editor.set_mode(Mode::Insert);
+    editor.insert_tab();
+    assert_eq!(editor.current_buffer().to_string(), "\t\n");
+}
+
+// ==================== 折行测试 ====================
+
+#[test]
+fn test_char_at_column() {
+    assert_eq!(char_at_column("\tab", 0, 8), 0);
+    assert_eq!(char_at_column("\tab", 7, 8), 0);
+    assert_eq!(char_at_column("\tab", 8, 8), 1);
+    assert_eq!(char_at_column("中文", 3, 8), 1);
+    assert_eq!(char_at_column("ab", 5, 8), 2);
+}
+
+#[test]
+fn test_wrap_line() {
+    assert_eq!(wrap_line("", 4, 8), vec![(0, String::new())]);
+    assert_eq!(
+        wrap_line("abcdefghij", 4, 8),
+        vec![(0, "abcd".to_string()), (4, "efgh".to_string()), (8, "ij".to_string())]
+    );
+    // 放不下的宽字符移到下一个屏幕行
+    assert_eq!(wrap_line("abc中", 4, 8), vec![(0, "abc".to_string()), (3, "中".to_string())]);
+    // Tab 按整行的列展开后再折行
+    assert_eq!(wrap_line("a\tb", 4, 4), vec![(0, "a   ".to_string()), (4, "b".to_string())]);
+}
+
+#[test]
+fn test_clip_line() {
+    assert_eq!(clip_line("abcdefgh", 2, 3, 8), "cde");
+    assert_eq!(clip_line("\tx", 6, 4, 8), "  x");
+    // 跨过起始列的宽字符不显示
+    assert_eq!(clip_line("中文字", 1, 4, 8), "文");
+}
+
+#[test]
+fn test_visible_lines_wrap() {
+    let mut editor = editor_with("abcdefghij\nxy\n", 0, 0);
+    let rows = editor.visible_lines(10, 4, 0);
+    let texts: Vec<_> = rows.iter().map(|row| (row.line, row.first, row.text.as_str())).collect();
+    assert_eq!(
+        texts,
+        vec![(0, true, "abcd"), (0, false, "efgh"), (0, false, "ij"), (1, true, "xy"), (2, true, "")]
+    );
+    assert_eq!(rows.iter().map(|row| row.column).collect::<Vec<_>>(), vec![0, 4, 8, 0, 0]);
+    // 屏幕行数受视口高度限制
+    assert_eq!(editor.visible_lines(2, 4, 0).len(), 2);
+
+    editor.execute_command("set nowrap").unwrap();
+    assert!(!editor.options().wrap);
+    assert_eq!(
+        editor.visible_lines(2, 4, 0),
+        vec![
+            ScreenRow { line: 0, first: true, column: 0, text: "abcd".to_string(), fold_end: None },
+            ScreenRow { line: 1, first: true, column: 0, text: "xy".to_string(), fold_end: None },
+        ]
+    );
+}
+
+#[test]
+fn test_cursor_screen_position_wrap() {
+    let mut editor = editor_with("abcdefghij\nxy\n", 0, 9);
+    assert_eq!(editor.cursor_screen_position(4, 0), Some((1, 2)));
+    assert_eq!(editor.screen_rows(0, 4), 3);
+
+    *editor.cursor_mut() = Cursor::new(1, 1);
+    assert_eq!(editor.cursor_screen_position(4, 0), Some((1, 3)));
+    assert_eq!(editor.cursor_screen_position(4, 1), Some((1, 0)));
+    assert_eq!(editor.cursor_screen_position(4, 2), None);
+}
+
+#[test]
+fn test_nowrap_scrolls_horizontally() {
+    let mut editor = editor_with("abcdefghij\n", 0, 9);
+    editor.execute_command("set nowrap").unwrap();
+
+    // 光标停在最右一列，左边的文本被滚出
+    assert_eq!(editor.cursor_screen_position(4, 0), Some((3, 0)));
+    assert_eq!(editor.visible_lines(1, 4, 0)[0].text, "ghij");
+}
+
+#[test]
+fn test_move_screen_line() {
+    let mut editor = editor_with("abcdefghij\nxy\n", 0, 1);
+
+    // gj 在同一行的屏幕行之间移动，保持屏幕列
+    editor.move_screen_line(true, 1, 4);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 5));
+    editor.move_screen_line(true, 1, 4);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 9));
+    editor.move_screen_line(true, 1, 4);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 1));
+    // 已经在最后一个屏幕行
+    editor.move_screen_line(true, 1, 4);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 1));
+
+    // gk 进入上一行的最后一个屏幕行；计数
+    editor.move_screen_line(false, 1, 4);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 9));
+    editor.move_screen_line(false, 2, 4);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 1));
+}
+
+#[test]
+fn test_move_screen_line_clamps_to_short_row() {
+    let mut editor = editor_with("abcdefghij\n", 0, 3);
+    editor.move_screen_line(true, 2, 4);
+    // 最后一个屏幕行只有两个字符
+    assert_eq!(editor.cursor().column, 9);
+
+    // nowrap 时与 j / k 相同
+    let mut editor = editor_with("abcdefghij\nxy\n", 0, 1);
+    editor.execute_command("set nowrap").unwrap();
+    editor.move_screen_line(true, 1, 4);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 1));
+}
+
+// ==================== 字形簇测试 ====================
+
+/// 带组合符号的 é（2 个字符）、国旗 emoji（2 个字符）、家庭 emoji（5 个字符）
+const CLUSTERS: &str = "ae\u{301}🇨🇳👨\u{200d}👩\u{200d}👧x";
+
+#[test]
+fn test_grapheme_boundaries() {
+    // 字形簇的起始下标：a=0, é=1, 🇨🇳=3, 👨‍👩‍👧=5, x=10
+    assert_eq!(next_grapheme(CLUSTERS, 0), 1);
+    assert_eq!(next_grapheme(CLUSTERS, 1), 3);
+    assert_eq!(next_grapheme(CLUSTERS, 3), 5);
+    assert_eq!(next_grapheme(CLUSTERS, 5), 10);
+    assert_eq!(next_grapheme(CLUSTERS, 10), 11);
+    assert_eq!(prev_grapheme(CLUSTERS, 10), 5);
+    assert_eq!(prev_grapheme(CLUSTERS, 5), 3);
+    assert_eq!(prev_grapheme(CLUSTERS, 1), 0);
+    assert_eq!(prev_grapheme(CLUSTERS, 0), 0);
+    // 字形簇中间的下标归到字形簇的开头
+    assert_eq!(grapheme_start(CLUSTERS, 2), 1);
+    assert_eq!(grapheme_start(CLUSTERS, 7), 5);
+    assert_eq!(grapheme_start(CLUSTERS, 20), 11);
+    assert_eq!(last_grapheme(CLUSTERS), 10);
+    assert_eq!(last_grapheme(""), 0);
+}
+
+#[test]
+fn test_grapheme_display_width() {
+    // 组合符号不占列，emoji 占两列
+    assert_eq!(display_column(CLUSTERS, 3, 8), 2);
+    assert_eq!(display_column(CLUSTERS, 5, 8), 4);
+    assert_eq!(display_column(CLUSTERS, 10, 8), 6);
+    // 字形簇中间的下标与字形簇开头在同一列
+    assert_eq!(display_column(CLUSTERS, 2, 8), 1);
+    assert_eq!(char_at_column(CLUSTERS, 1, 8), 1);
+    assert_eq!(char_at_column(CLUSTERS, 3, 8), 3);
+    assert_eq!(char_at_column(CLUSTERS, 5, 8), 5);
+}
+
+#[test]
+fn test_wrap_line_keeps_graphemes_together() {
+    let rows = wrap_line("ae\u{301}🇨🇳", 3, 8);
+    assert_eq!(rows, vec![(0, "ae\u{301}".to_string()), (2, "🇨🇳".to_string())]);
+}
@@ -0,0 +1,116 @@
+//! 缓冲区修改记录单元测试
+//!
+//! 对应源文件: src/edit_log.rs, src/buffer.rs (修改记录), src/search.rs (增量更新匹配)
+
+use aivim_core::buffer::{Buffer, BufferId};
+use aivim_core::edit_log::{EditLog, TextEdit};
+use aivim_core::search::{SearchDirection, SearchState};
+use ropey::Rope;
+
+fn edit(start: usize, removed: usize, inserted: usize) -> TextEdit {
+    TextEdit { start, removed, inserted }
+}
+
+/// 用合并后的修改把 `old` 变成 `new`：修改区域之外的文本应该相同
+fn assert_covers(old: &str, new: &str, edit: TextEdit) {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    assert_eq!(old[..edit.start], new[..edit.start]);
+    assert_eq!(old[edit.old_end()..], new[edit.new_end()..]);
+}
+
+// ==================== 合并修改测试 ====================
+
+#[test]
+fn test_then_merges_edits() {
+    // "abcdef" -> 在 2 插入 "XY" -> "abXYcdef" -> 删除 5 处的 "d" -> "abXYcef"
+    assert_eq!(edit(2, 0, 2).then(edit(5, 1, 0)), edit(2, 2, 3));
+    assert_covers("abcdef", "abXYcef", edit(2, 2, 3));
+    // 后一处修改在前面："abcdef" -> "abcdZef" -> "acdZef"
+    let merged = edit(4, 0, 1).then(edit(1, 1, 0));
+    assert_eq!(merged, edit(1, 3, 3));
+    assert_covers("abcdef", "acdZef", merged);
+}
+
+#[test]
+fn test_log_since() {
+    let mut log = EditLog::default();
+    assert_eq!(log.since(0), Some(None));
+    log.record(edit(0, 0, 1));
+    log.record(edit(3, 0, 1));
+    assert_eq!(log.since(0), Some(Some(edit(0, 2, 4))));
+    assert_eq!(log.since(1), Some(Some(edit(3, 0, 1))));
+    assert_eq!(log.since(2), Some(None));
+    assert_eq!(log.since(3), None);
+
+    log.reset(5);
+    assert_eq!(log.since(2), None);
+    assert_eq!(log.since(5), Some(None));
+}
+
+#[test]
+fn test_buffer_records_edits() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "hello world\n");
+    let tick = buffer.changedtick();
+    buffer.remove(0, 6);
+    buffer.insert_char(5, '!');
+    let merged = buffer.edits_since(tick).unwrap().unwrap();
+    assert_covers("hello world\n", &buffer.to_string(), merged);
+
+    // 整体替换内容之后没有修改记录
+    buffer.set_rope(Rope::from_str("other\n"));
+    assert_eq!(buffer.edits_since(tick), None);
+    assert_eq!(buffer.edits_since(buffer.changedtick()), Some(None));
+}
+
+// ==================== 增量更新匹配测试 ====================
+
+/// 每次修改之后增量更新的匹配应与重新查找的相同
+#[test]
+fn test_incremental_matches_equal_full_search() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "foo bar foo\nbaz\nfoo_foo foo\n\nxfoo\n");
+    let edits: [(&str, usize, usize); 7] = [
+        ("insert", 4, 0),
+        ("remove", 3, 5),
+        ("insert", 0, 0),
+        ("newline", 6, 0),
+        ("join", 11, 1),
+        ("remove", 0, 100),
+        ("insert", 0, 0),
+    ];
+    for pattern in ["foo", "\\<foo\\>", "o\nb"] {
+        let mut incremental = SearchState::new();
+        incremental.set_pattern(pattern, SearchDirection::Forward, &buffer);
+        let mut copy = buffer.clone();
+        for (kind, at, len) in edits {
+            let at = at.min(copy.len_chars());
+            match kind {
+                "insert" => copy.insert(at, "foo "),
+                "newline" => copy.insert(at, "o\nbfoo"),
+                "join" => copy.remove(at, len),
+                _ => copy.remove(at, len.min(copy.len_chars() - at)),
+            }
+            incremental.refresh(&copy);
+            let mut full = SearchState::new();
+            full.set_pattern(pattern, SearchDirection::Forward, &copy);
+            assert_eq!(incremental.matches, full.matches, "{:?} after {} at {}", pattern, kind, at);
+        }
+    }
+}
+
+#[test]
+fn test_refresh_keeps_matches_of_unchanged_buffer() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "a a a\n");
+    let mut search = SearchState::new();
+    search.set_pattern("a", SearchDirection::Forward, &buffer);
+    search.set_current_match(1);
+    search.refresh(&buffer);
+    assert_eq!(search.current_match, Some(1));
+    buffer.insert(0, "a ");
+    search.refresh(&buffer);
+    assert_eq!(search.matches.to_vec(), vec![0, 2, 4, 6]);
+    assert_eq!(search.current_match, Some(1));
+}
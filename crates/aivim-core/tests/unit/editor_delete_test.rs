@@ -2,7 +2,6 @@
 //!
 //! 测试 delete_to_motion_with_register 等编辑器删除功能
 
-use aivim_core::buffer::{Buffer, BufferId};
 use aivim_core::cursor::Cursor;
 use aivim_core::editor::Editor;
 use aivim_core::motion::Motion;
@@ -120,3 +119,20 @@ fn test_delete_word_forward_empty_result() {
     // 没有可删除的内容（换行符前没有单词字符）
     assert!(result.is_none());
 }
+
+#[test]
+fn test_delete_char_removes_whole_grapheme() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "ae\u{301}👍🏽b\n");
+
+    // x 删除整个带组合符号的字母，包括组合符号
+    *editor.cursor_mut() = Cursor::new(0, 1);
+    assert_eq!(editor.delete_char_to_register(None).as_deref(), Some("e\u{301}"));
+    assert_eq!(editor.current_buffer().to_string(), "a👍🏽b\n");
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "e\u{301}");
+
+    // emoji 与肤色修饰符一起删除
+    assert_eq!(editor.delete_char_to_register(None).as_deref(), Some("👍🏽"));
+    assert_eq!(editor.current_buffer().to_string(), "ab\n");
+    assert_eq!(editor.cursor().column, 1);
+}
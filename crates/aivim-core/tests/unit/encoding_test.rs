@@ -0,0 +1,185 @@
+//! 文件编码单元测试
+//!
+//! 对应源文件: src/encoding.rs, src/buffer.rs (按原编码读写), src/editor.rs (:set fileencoding, 有损写入的确认)
+
+use aivim_core::editor::PendingConfirmation;
+use aivim_core::encoding::{self, Encoding};
+use std::fs;
+use super::common::editor_with_bytes;
+
+// ==================== 检测与转换测试 ====================
+
+#[test]
+fn test_decode_utf8() {
+    let decoded = encoding::decode("héllo 世界".as_bytes());
+    assert_eq!(decoded.encoding, Encoding::Utf8);
+    assert_eq!(decoded.text, "héllo 世界");
+    assert_eq!(decoded.replaced, 0);
+}
+
+#[test]
+fn test_decode_invalid_utf8_as_latin1() {
+    let decoded = encoding::decode(b"caf\xe9\n");
+    assert_eq!(decoded.encoding, Encoding::Latin1);
+    assert_eq!(decoded.text, "café\n");
+    assert_eq!(encoding::encode(&decoded.text, Encoding::Latin1).unwrap(), b"caf\xe9\n");
+}
+
+#[test]
+fn test_decode_utf16_with_bom() {
+    let le = [0xff, 0xfe, b'h', 0, b'i', 0];
+    let decoded = encoding::decode(&le);
+    assert_eq!(decoded.encoding, Encoding::Utf16Le);
+    assert_eq!(decoded.text, "hi");
+    assert!(decoded.bom);
+    assert_eq!([Encoding::Utf16Le.bom(), &encoding::encode(&decoded.text, Encoding::Utf16Le).unwrap()].concat(), le);
+
+    let be = [0xfe, 0xff, 0, b'h', 0, b'i'];
+    let decoded = encoding::decode(&be);
+    assert_eq!(decoded.encoding, Encoding::Utf16Be);
+    assert_eq!(decoded.text, "hi");
+    assert!(decoded.bom);
+    assert_eq!([Encoding::Utf16Be.bom(), &encoding::encode(&decoded.text, Encoding::Utf16Be).unwrap()].concat(), be);
+}
+
+#[test]
+fn test_decode_utf8_bom() {
+    let decoded = encoding::decode(b"\xef\xbb\xbfab");
+    assert_eq!((decoded.encoding, decoded.text.as_str(), decoded.bom), (Encoding::Utf8, "ab", true));
+    assert!(!encoding::decode(b"ab").bom);
+    // latin1 没有 BOM，U+FEFF 以外的字节原样保留
+    assert!(!encoding::decode(b"\xef\xbb\xbf\xff").bom);
+}
+
+#[test]
+fn test_decode_unpaired_surrogate_is_replaced() {
+    let decoded = encoding::decode(&[0xff, 0xfe, 0x00, 0xd8, b'a', 0]);
+    assert_eq!(decoded.text, "\u{fffd}a");
+    assert_eq!(decoded.replaced, 1);
+}
+
+#[test]
+fn test_encode_unencodable_chars() {
+    assert_eq!(encoding::encode("a世b界", Encoding::Latin1), Err(2));
+    assert_eq!(encoding::encode_lossy("a世b", Encoding::Latin1), b"a?b");
+    assert!(encoding::encode("a世b", Encoding::Utf8).is_ok());
+}
+
+#[test]
+fn test_parse_encoding_names() {
+    assert_eq!(Encoding::parse("UTF8"), Some(Encoding::Utf8));
+    assert_eq!(Encoding::parse("iso-8859-1"), Some(Encoding::Latin1));
+    assert_eq!(Encoding::parse("utf-16le"), Some(Encoding::Utf16Le));
+    assert_eq!(Encoding::parse("gbk"), None);
+}
+
+// ==================== 读写文件测试 ====================
+
+#[test]
+fn test_utf8_bom_is_not_text() {
+    let (mut editor, path) = editor_with_bytes("utf8_bom", b"\xef\xbb\xbfab\n");
+    assert!(editor.has_bom());
+    assert_eq!(editor.current_buffer().to_string(), "ab\n");
+
+    // x 删除第一个字符而不是不可见的 BOM
+    assert!(editor.run_script(&["x"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "b\n");
+    editor.execute_command("w").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"\xef\xbb\xbfb\n");
+    assert!(editor.has_bom());
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_utf16_bom_round_trip() {
+    let (mut editor, path) = editor_with_bytes("utf16_bom", &[0xff, 0xfe, b'a', 0, b'\n', 0]);
+    assert_eq!(editor.current_buffer().to_string(), "a\n");
+    assert!(editor.run_script(&["rb", ":w"]).is_ok());
+    assert_eq!(fs::read(&path).unwrap(), [0xff, 0xfe, b'b', 0, b'\n', 0]);
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_no_bom_without_bom() {
+    let (mut editor, path) = editor_with_bytes("no_bom", b"ab\n");
+    assert!(!editor.has_bom());
+    editor.execute_command("w").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"ab\n");
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_latin1_file_round_trip() {
+    let (mut editor, path) = editor_with_bytes("latin1_round_trip", b"caf\xe9\r\nna\xefve\r\n");
+    assert_eq!(editor.current_buffer().encoding(), Encoding::Latin1);
+    assert_eq!(editor.current_buffer().to_string(), "café\nnaïve\n");
+
+    editor.current_buffer_mut().insert(0, "à ");
+    editor.execute_command("w").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"\xe0 caf\xe9\r\nna\xefve\r\n");
+}
+
+#[test]
+fn test_utf16_file_round_trip() {
+    let bytes: Vec<u8> = [0xff, 0xfe].into_iter().chain("a\n世\n".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+    let (mut editor, path) = editor_with_bytes("utf16_round_trip", &bytes);
+    assert_eq!(editor.current_buffer().encoding(), Encoding::Utf16Le);
+
+    editor.execute_command("w").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+}
+
+#[test]
+fn test_unencodable_chars_prompt_before_lossy_write() {
+    let (mut editor, path) = editor_with_bytes("lossy_write", b"caf\xe9\n");
+    editor.current_buffer_mut().insert(0, "世界");
+    editor.execute_command("w").unwrap();
+
+    assert_eq!(
+        editor.pending_confirmation(),
+        Some(&PendingConfirmation::LossyWrite { path: None, encoding: Encoding::Latin1, chars: 2 })
+    );
+    assert_eq!(editor.message(), Some("2 character(s) cannot be encoded as latin1, replace them with '?'? (y/n)"));
+    assert_eq!(fs::read(&path).unwrap(), b"caf\xe9\n");
+
+    editor.resolve_confirmation(true).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"??caf\xe9\n");
+    assert!(!editor.current_buffer().is_modified());
+}
+
+#[test]
+fn test_declined_lossy_write_keeps_file() {
+    let (mut editor, path) = editor_with_bytes("lossy_declined", b"caf\xe9\n");
+    editor.current_buffer_mut().insert(0, "世");
+    editor.execute_command("w").unwrap();
+    editor.resolve_confirmation(false).unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), b"caf\xe9\n");
+    assert!(editor.current_buffer().is_modified());
+}
+
+#[test]
+fn test_wq_refuses_unencodable_chars() {
+    let (mut editor, path) = editor_with_bytes("lossy_wq", b"caf\xe9\n");
+    editor.current_buffer_mut().insert(0, "世");
+    let err = editor.execute_command("wq").unwrap_err();
+
+    assert_eq!(err, "Cannot encode 1 character(s) as latin1 (use :w to replace them)");
+    assert_eq!(fs::read(&path).unwrap(), b"caf\xe9\n");
+    assert!(!editor.quit_requested());
+}
+
+#[test]
+fn test_set_fileencoding_converts_on_write() {
+    let (mut editor, path) = editor_with_bytes("set_fenc", "café\n".as_bytes());
+    editor.execute_command("set fenc").unwrap();
+    assert_eq!(editor.message(), Some("fileencoding=utf-8"));
+
+    editor.execute_command("set fileencoding=latin1").unwrap();
+    assert_eq!(editor.message(), Some("fileencoding=latin1"));
+    assert!(editor.current_buffer().is_modified());
+    editor.execute_command("w").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"caf\xe9\n");
+
+    assert!(editor.execute_command("set fenc=gbk").is_err());
+}
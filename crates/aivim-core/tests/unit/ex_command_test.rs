@@ -0,0 +1,189 @@
+//! Ex 命令解析单元测试
+//!
+//! 对应源文件: src/ex_command.rs, src/editor.rs (execute_command, :d, :normal)
+
+use std::fs;
+
+use aivim_core::editor::Editor;
+use aivim_core::editor::NormalRequest;
+use aivim_core::ex_command::{parse, split_args, ExCommand, ExError};
+use aivim_core::mapping::MapKey;
+use aivim_core::range::{LineRange, RangeError};
+use super::common::editor_with;
+
+fn parsed(command: &str) -> ExCommand<'_> {
+    parse(command, 0, 9).unwrap().unwrap()
+}
+
+// ==================== 解析测试 ====================
+
+#[test]
+fn test_parse_empty() {
+    assert_eq!(parse("", 0, 9), Ok(None));
+    assert_eq!(parse("   ", 0, 9), Ok(None));
+}
+
+#[test]
+fn test_parse_range_and_args() {
+    let cmd = parsed("1,5d a");
+    assert_eq!(cmd.range, Some(LineRange::new(0, 4)));
+    assert_eq!(cmd.name, "delete");
+    assert!(!cmd.bang);
+    assert_eq!(cmd.args, "a");
+
+    // 只有范围
+    let cmd = parsed("3");
+    assert_eq!((cmd.range, cmd.name), (Some(LineRange::single(2)), ""));
+}
+
+#[test]
+fn test_parse_bang() {
+    let cmd = parsed("w! out.txt");
+    assert_eq!((cmd.name, cmd.bang, cmd.args), ("write", true, "out.txt"));
+    let cmd = parsed("q!");
+    assert_eq!((cmd.name, cmd.bang), ("quit", true));
+    let cmd = parsed("bd!");
+    assert_eq!((cmd.name, cmd.bang), ("bdelete", true));
+
+    assert_eq!(parse("set! nu", 0, 9), Err(ExError::NoBang("set".to_string())));
+}
+
+#[test]
+fn test_parse_abbreviations() {
+    assert_eq!(parsed("w").name, "write");
+    assert_eq!(parsed("wri").name, "write");
+    assert_eq!(parsed("wq").name, "wq");
+    assert_eq!(parsed("q").name, "quit");
+    assert_eq!(parsed("qa").name, "qall");
+    assert_eq!(parsed("b 2").name, "buffer");
+    assert_eq!(parsed("bn").name, "bnext");
+    assert_eq!(parsed("bprev").name, "bprevious");
+    assert_eq!(parsed("undol").name, "undolist");
+    assert_eq!(parsed("reg").name, "registers");
+    assert_eq!(parsed("fixl").name, "fixlineendings");
+    assert_eq!(parsed("Di").name, "DiffOrig");
+    assert_eq!(parsed("se nu").name, "set");
+    assert_eq!(parsed("sym").name, "symbols");
+    // 完整的命令名优先于更长的命令
+    assert_eq!(parsed("job clear").name, "job");
+}
+
+#[test]
+fn test_parse_ambiguous_and_unknown() {
+    assert_eq!(parse("D", 0, 9), Err(ExError::Ambiguous("D".to_string())));
+    assert_eq!(parse("undo", 0, 9), Err(ExError::Unknown("undo".to_string())));
+    assert_eq!(parse("nosuch", 0, 9), Err(ExError::Unknown("nosuch".to_string())));
+}
+
+#[test]
+fn test_parse_substitute() {
+    let cmd = parsed("%s/a b/c/g");
+    assert_eq!(cmd.range, Some(LineRange::new(0, 9)));
+    assert_eq!((cmd.name, cmd.args), ("substitute", "/a b/c/g"));
+}
+
+#[test]
+fn test_parse_range_errors() {
+    assert_eq!(parse("1,3e foo", 0, 9), Err(ExError::NoRange("edit".to_string())));
+    assert_eq!(parse("20d", 0, 9), Err(ExError::Range(RangeError::Invalid)));
+}
+
+#[test]
+fn test_parse_map_commands_keep_raw_args() {
+    let cmd = parsed("inoremap jk <Esc>");
+    assert_eq!((cmd.name, cmd.args), ("inoremap", "jk <Esc>"));
+    let cmd = parsed("map! ab cd");
+    assert_eq!((cmd.name, cmd.bang), ("map", true));
+}
+
+#[test]
+fn test_split_args_quoting() {
+    assert_eq!(split_args("a  b"), Ok(vec!["a".to_string(), "b".to_string()]));
+    assert_eq!(split_args("\"my file.txt\""), Ok(vec!["my file.txt".to_string()]));
+    assert_eq!(split_args("'a\\b' c\\ d"), Ok(vec!["a\\b".to_string(), "c d".to_string()]));
+    assert_eq!(split_args("x\"y z\""), Ok(vec!["xy z".to_string()]));
+    assert_eq!(split_args("\"open"), Err(ExError::UnterminatedQuote));
+    assert_eq!(split_args(""), Ok(vec![]));
+}
+
+// ==================== 执行测试 ====================
+
+#[test]
+fn test_delete_range_command() {
+    let mut editor = editor_with("1\n2\n3\n4\n", 0, 0);
+    assert!(editor.execute_command("2,3d a").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "1\n4\n");
+    assert_eq!(editor.register_manager().get('a').unwrap().content, "2\n3\n");
+    assert_eq!(editor.cursor().line, 1);
+
+    // 不带范围时删除当前行
+    assert!(editor.execute_command("d").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "1\n");
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "4\n");
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "1\n4\n");
+}
+
+#[test]
+fn test_quit_requested() {
+    let mut editor = editor_with("text\n", 0, 0);
+    assert!(!editor.quit_requested());
+    // 有修改时先询问是否保存
+    assert!(editor.execute_command("qui").is_ok());
+    assert!(editor.pending_confirmation().is_some());
+    editor.resolve_confirmation(false).unwrap();
+    assert!(!editor.quit_requested());
+    assert!(editor.execute_command("q!").is_ok());
+    assert!(editor.quit_requested());
+}
+
+#[test]
+fn test_command_errors() {
+    let mut editor = editor_with("text\n", 0, 0);
+    assert_eq!(editor.execute_command("D"), Err("Ambiguous command: D".to_string()));
+    assert_eq!(editor.execute_command("new!"), Err("No ! allowed: new".to_string()));
+    assert_eq!(editor.execute_command("e a b"), Err("Too many arguments".to_string()));
+}
+
+#[test]
+fn test_edit_quoted_file_name() {
+    let path = "/tmp/aivim_ex command.txt";
+    fs::write(path, "quoted\n").unwrap();
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("e \"/tmp/aivim_ex command.txt\"").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "quoted\n");
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_set_multiple_options() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("se nu rnu").is_ok());
+    assert!(editor.options().number);
+    assert!(editor.options().relativenumber);
+}
+
+#[test]
+fn test_normal_request() {
+    let mut editor = editor_with("a\nb\nc\n", 0, 0);
+    assert!(editor.take_normal_request().is_none());
+
+    assert!(editor.execute_command("2,3norm A;<Esc>").is_ok());
+    assert_eq!(
+        editor.take_normal_request(),
+        Some(NormalRequest {
+            range: Some(LineRange::new(1, 2)),
+            keys: vec![MapKey::Char('A'), MapKey::Char(';'), MapKey::Esc],
+            remap: true,
+        })
+    );
+    // 只能取出一次
+    assert!(editor.take_normal_request().is_none());
+
+    assert!(editor.execute_command("normal! dw").is_ok());
+    let request = editor.take_normal_request().unwrap();
+    assert_eq!((request.range, request.remap), (None, false));
+
+    assert!(editor.execute_command("normal").is_err());
+}
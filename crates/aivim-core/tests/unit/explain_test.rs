@@ -0,0 +1,97 @@
+//! AI 解释单元测试
+//!
+//! 对应源文件: src/explain.rs, src/symbols.rs (function_range), src/ai_plugin.rs (:AiExplain、K)
+
+use aivim_core::ai_plugin::AiPlugin;
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::explain::{word_at, Explanation};
+use aivim_core::range::LineRange;
+use aivim_core::symbols::function_range;
+use std::time::{Duration, Instant};
+use super::common::{editor_with_reply, has_curl};
+
+/// ai 插件浮动窗口中的解释
+fn shown_explanation(editor: &Editor) -> Option<&Explanation> {
+    editor.plugin::<AiPlugin>().unwrap().explanation(editor)
+}
+
+/// 等待回复结束
+fn wait_for_reply(editor: &mut Editor) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while shown_explanation(editor).is_some_and(|explanation| explanation.is_responding()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        editor.poll_plugins(Instant::now());
+    }
+}
+
+const CODE: &str = "use std::fs;\n\nfn outer() {\n    let x = 1;\n    fn inner() {\n        // }\n    }\n}\n\nfn decl();\n";
+
+// ==================== 目标 ====================
+
+#[test]
+fn test_word_at() {
+    assert_eq!(word_at("let foo_bar = 1;", 6), Some("foo_bar".to_string()));
+    assert_eq!(word_at("let foo_bar = 1;", 4), Some("foo_bar".to_string()));
+    assert_eq!(word_at("let foo_bar = 1;", 3), None);
+    assert_eq!(word_at("", 0), None);
+}
+
+#[test]
+fn test_function_range() {
+    let lines: Vec<&str> = CODE.lines().collect();
+    assert_eq!(function_range(&lines, 3), Some(LineRange::new(2, 7)));
+    // 最内层的函数优先，注释中的花括号不计数
+    assert_eq!(function_range(&lines, 5), Some(LineRange::new(4, 6)));
+    assert_eq!(function_range(&lines, 7), Some(LineRange::new(2, 7)));
+    assert_eq!(function_range(&lines, 0), None);
+    assert_eq!(function_range(&lines, 8), None);
+    assert_eq!(function_range(&lines, 9), Some(LineRange::single(9)));
+}
+
+// ==================== :AiExplain ====================
+
+#[test]
+fn test_nothing_to_explain() {
+    let mut editor = Editor::new();
+    editor.open_bytes(b"  \n");
+    assert!(editor.execute_command("AiExplain").is_err());
+    assert!(shown_explanation(&editor).is_none());
+}
+
+#[test]
+fn test_explain_function_with_k() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("function", CODE, &["Defines ", "x."]);
+    *editor.cursor_mut() = Cursor::new(3, 4);
+    assert!(editor.run_script(&["K"]).is_ok());
+    assert!(shown_explanation(&editor).is_some_and(|explanation| explanation.is_responding()));
+    wait_for_reply(&mut editor);
+
+    let explanation = shown_explanation(&editor).unwrap();
+    assert_eq!(explanation.text(), "Defines x.");
+    assert!(explanation.title().ends_with(":3-8"), "{}", explanation.title());
+
+    // Esc 关闭浮动窗口，不改变模式和光标
+    assert!(editor.run_script(&["<Esc>"]).is_ok());
+    assert!(shown_explanation(&editor).is_none());
+    assert_eq!(*editor.cursor(), Cursor::new(3, 4));
+}
+
+#[test]
+fn test_explain_word_outside_function() {
+    if !has_curl() {
+        return;
+    }
+    let mut editor = editor_with_reply("word", CODE, &["A module."]);
+    *editor.cursor_mut() = Cursor::new(0, 5);
+    editor.execute_command("AiExplain").unwrap();
+    wait_for_reply(&mut editor);
+    assert_eq!(shown_explanation(&editor).unwrap().title(), "std");
+
+    // 只在请求时的缓冲区中显示
+    editor.create_new_buffer();
+    assert!(shown_explanation(&editor).is_none());
+}
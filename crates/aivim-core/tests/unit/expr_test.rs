@@ -0,0 +1,134 @@
+//! 表达式寄存器和上次插入寄存器单元测试
+//!
+//! 对应源文件: src/expr.rs, src/editor.rs (Insert / Command 模式 Ctrl+R {reg}, Ctrl+R =, ". 寄存器)
+
+use aivim_core::editor::Editor;
+use aivim_core::expr::{evaluate, ExprError};
+use aivim_core::mode::Mode;
+use super::common::editor_with;
+
+fn type_text(editor: &mut Editor, text: &str) {
+    for ch in text.chars() {
+        if ch == '\n' {
+            editor.insert_newline();
+        } else {
+            editor.insert_char(ch);
+        }
+    }
+}
+
+// ==================== 求值测试 ====================
+
+#[test]
+fn test_evaluate_integers() {
+    assert_eq!(evaluate("1 + 2 * 3"), Ok("7".to_string()));
+    assert_eq!(evaluate("(1 + 2) * 3"), Ok("9".to_string()));
+    assert_eq!(evaluate("7 / 2"), Ok("3".to_string()));
+    assert_eq!(evaluate("7 % 4"), Ok("3".to_string()));
+    assert_eq!(evaluate("-3 - -4"), Ok("1".to_string()));
+}
+
+#[test]
+fn test_evaluate_floats() {
+    assert_eq!(evaluate("7.0 / 2"), Ok("3.5".to_string()));
+    assert_eq!(evaluate("1.5 * 2"), Ok("3.0".to_string()));
+}
+
+#[test]
+fn test_evaluate_errors() {
+    assert_eq!(evaluate("1 +"), Err(ExprError::Invalid));
+    assert_eq!(evaluate("(1"), Err(ExprError::Invalid));
+    assert_eq!(evaluate("2 x 3"), Err(ExprError::Invalid));
+    assert_eq!(evaluate("1 / 0"), Err(ExprError::DivisionByZero));
+}
+
+// ==================== . 寄存器测试 ====================
+
+#[test]
+fn test_last_insert_register() {
+    let mut editor = editor_with("\n", 0, 0);
+    editor.set_mode(Mode::Insert);
+    type_text(&mut editor, "helo");
+    editor.backspace();
+    type_text(&mut editor, "lo\nx");
+    editor.set_mode(Mode::Normal);
+
+    assert_eq!(editor.register_manager().get('.').unwrap().content, "hello\nx");
+
+    // 下一次 Insert 会话覆盖上次的内容
+    editor.set_mode(Mode::Insert);
+    type_text(&mut editor, "y");
+    editor.set_mode(Mode::Normal);
+    assert_eq!(editor.register_manager().get('.').unwrap().content, "y");
+
+    // ".p 粘贴上次输入的文本
+    editor.paste(Some('.'), false);
+    assert_eq!(editor.current_buffer().to_string(), "hello\nxyy\n");
+}
+
+// ==================== Ctrl+R 测试 ====================
+
+#[test]
+fn test_insert_register_in_insert_mode() {
+    let mut editor = editor_with("ab\n", 0, 1);
+    editor.register_manager_mut().set('a', "XY\nZ", false);
+    editor.set_mode(Mode::Insert);
+
+    assert!(editor.insert_register('a').is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "aXY\nZb\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 1));
+
+    // 插入的寄存器内容也算作本次输入
+    editor.set_mode(Mode::Normal);
+    assert_eq!(editor.register_manager().get('.').unwrap().content, "XY\nZ");
+}
+
+#[test]
+fn test_insert_invalid_register() {
+    let mut editor = editor_with("\n", 0, 0);
+    editor.set_mode(Mode::Insert);
+    assert!(editor.insert_register('!').is_err());
+}
+
+#[test]
+fn test_insert_register_in_command_line() {
+    let mut editor = editor_with("\n", 0, 0);
+    editor.register_manager_mut().set('a', "foo\nbar\n", true);
+    editor.set_mode(Mode::Command);
+    editor.command_line_mut().push_str("s/");
+
+    assert!(editor.insert_register_in_command_line('a').is_ok());
+    assert_eq!(editor.command_line(), "s/foo bar");
+    // 空寄存器什么也不插入
+    assert!(editor.insert_register_in_command_line('z').is_ok());
+    assert_eq!(editor.command_line(), "s/foo bar");
+    assert!(editor.insert_register_in_command_line('!').is_err());
+}
+
+#[test]
+fn test_expression_register() {
+    let mut editor = editor_with("total: \n", 0, 7);
+    editor.set_mode(Mode::Insert);
+    editor.start_expression_prompt();
+    assert!(editor.expression_prompt_active());
+    editor.command_line_mut().push_str("6 * 7");
+
+    assert!(editor.finish_expression_prompt().is_ok());
+    assert!(!editor.expression_prompt_active());
+    assert_eq!(editor.current_buffer().to_string(), "total: 42\n");
+    assert_eq!(editor.cursor().column, 9);
+
+    // = 寄存器保存表达式，取值时重新求值
+    assert_eq!(editor.register_manager().get('=').unwrap().content, "42");
+}
+
+#[test]
+fn test_expression_register_error() {
+    let mut editor = editor_with("\n", 0, 0);
+    editor.set_mode(Mode::Insert);
+    editor.start_expression_prompt();
+    editor.command_line_mut().push_str("1 / 0");
+
+    assert_eq!(editor.finish_expression_prompt(), Err("Division by zero".to_string()));
+    assert_eq!(editor.current_buffer().to_string(), "\n");
+}
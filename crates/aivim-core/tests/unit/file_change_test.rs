@@ -0,0 +1,195 @@
+//! 外部修改检测单元测试
+//!
+//! 对应源文件: src/buffer.rs (文件修改时间), src/editor.rs (写入前确认、:e!、:checktime、autoread),
+//! src/watch.rs (文件监视)
+
+use aivim_core::editor::{Editor, PendingConfirmation};
+use aivim_core::waker::Waker;
+use aivim_core::watch::FileWatcher;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use super::common::editor_with_file;
+
+/// 模拟其他程序修改文件：写入新内容并把修改时间设为稍后的时间
+fn modify_externally(path: &PathBuf, content: &str) {
+    fs::write(path, content).unwrap();
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+}
+
+#[test]
+fn test_unchanged_file_not_reported() {
+    let (mut editor, _) = editor_with_file("unchanged", "a\n");
+    assert!(!editor.current_buffer().changed_on_disk());
+
+    editor.execute_command("checktime").unwrap();
+    assert_eq!(editor.message(), None);
+}
+
+#[test]
+fn test_new_file_not_reported() {
+    let path = PathBuf::from("/tmp/aivim_file_change_new.txt");
+    let _ = fs::remove_file(&path);
+    let editor = Editor::with_file(&path).unwrap();
+    assert!(!editor.current_buffer().changed_on_disk());
+}
+
+#[test]
+fn test_checktime_reports_external_change() {
+    let (mut editor, path) = editor_with_file("checktime", "a\n");
+    modify_externally(&path, "b\n");
+
+    assert!(editor.current_buffer().changed_on_disk());
+    editor.check_file_changed();
+    let name = path.file_name().unwrap().to_string_lossy();
+    assert_eq!(
+        editor.message(),
+        Some(format!("{} has changed on disk (:e! to reload, :w! to overwrite)", name).as_str())
+    );
+}
+
+#[test]
+fn test_write_confirms_before_overwriting_changed_file() {
+    let (mut editor, path) = editor_with_file("write", "a\n");
+    editor.current_buffer_mut().insert(0, "x");
+    modify_externally(&path, "external\n");
+
+    editor.execute_command("w").unwrap();
+    assert_eq!(editor.pending_confirmation(), Some(&PendingConfirmation::OverwriteChanged(path.clone())));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "external\n");
+
+    editor.resolve_confirmation(false).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "external\n");
+
+    editor.execute_command("w").unwrap();
+    editor.resolve_confirmation(true).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "xa\n");
+    // 写入后记录新的修改时间
+    assert!(!editor.current_buffer().changed_on_disk());
+}
+
+#[test]
+fn test_write_bang_overwrites_without_confirmation() {
+    let (mut editor, path) = editor_with_file("write_bang", "a\n");
+    modify_externally(&path, "external\n");
+
+    editor.execute_command("w!").unwrap();
+    assert_eq!(editor.pending_confirmation(), None);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\n");
+}
+
+#[test]
+fn test_wq_refuses_changed_file() {
+    let (mut editor, path) = editor_with_file("wq", "a\n");
+    modify_externally(&path, "external\n");
+
+    assert!(editor.execute_command("wq").is_err());
+    assert!(!editor.quit_requested());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "external\n");
+
+    editor.execute_command("wq!").unwrap();
+    assert!(editor.quit_requested());
+}
+
+#[test]
+fn test_edit_bang_reloads_from_disk() {
+    let (mut editor, path) = editor_with_file("reload", "a\nb\nc\n");
+    editor.execute_command("3").unwrap();
+    editor.current_buffer_mut().insert(0, "x");
+    modify_externally(&path, "new\n");
+
+    // 有未保存的修改时 :e 拒绝重新读入
+    assert!(editor.execute_command("e").is_err());
+
+    editor.execute_command("e!").unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "new\n");
+    assert!(!editor.current_buffer().is_modified());
+    assert!(!editor.current_buffer().changed_on_disk());
+    assert_eq!(editor.cursor().line, 0);
+}
+
+// ==================== autoread 测试 ====================
+
+/// 在单独的目录中创建文件，避免其他测试的文件产生通知
+fn watched_file(name: &str, content: &str) -> PathBuf {
+    let dir = PathBuf::from(format!("/tmp/aivim_watch_{}", name));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("file.txt");
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_watcher_reports_changed_file() {
+    let path = watched_file("watcher", "a\n");
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = FileWatcher::new(Waker::new(move || {
+        let _ = sender.send(());
+    }))
+    .unwrap();
+    watcher.watch(&path);
+    assert!(watcher.is_watching(&path));
+
+    fs::write(path.with_file_name("other.txt"), "x").unwrap();
+    modify_externally(&path, "b\n");
+    receiver.recv_timeout(Duration::from_secs(5)).expect("waker not called");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut changed = Vec::new();
+    while changed.is_empty() && Instant::now() < deadline {
+        changed = watcher.poll();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    // 同一目录中不被监视的文件不报告
+    assert_eq!(changed, vec![path]);
+}
+
+#[test]
+fn test_autoread_reloads_unmodified_buffer() {
+    let path = watched_file("reload", "a\n");
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("set autoread").unwrap();
+    assert!(editor.options().autoread);
+    modify_externally(&path, "b\n");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !editor.poll_file_changes() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(editor.current_buffer().to_string(), "b\n");
+    assert_eq!(editor.message(), Some("file.txt changed on disk, reloaded"));
+}
+
+#[test]
+fn test_autoread_keeps_modified_buffer() {
+    let path = watched_file("modified", "a\n");
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("set ar").unwrap();
+    editor.current_buffer_mut().insert(0, "x");
+    modify_externally(&path, "b\n");
+
+    editor.check_file_changed();
+    assert_eq!(editor.current_buffer().to_string(), "xa\n");
+    assert_eq!(editor.message(), Some("file.txt has changed on disk (:e! to reload, :w! to overwrite)"));
+}
+
+#[test]
+fn test_checktime_reloads_with_autoread() {
+    let path = watched_file("checktime", "a\nb\nc\n");
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("set autoread").unwrap();
+    editor.execute_command("3").unwrap();
+    modify_externally(&path, "new\n");
+
+    editor.execute_command("checktime").unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "new\n");
+    assert_eq!(editor.cursor().line, 0);
+
+    // 关闭 autoread 后只提示
+    editor.execute_command("set noautoread").unwrap();
+    modify_externally(&path, "again\n");
+    assert!(!editor.poll_file_changes());
+    editor.execute_command("checktime").unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "new\n");
+}
@@ -0,0 +1,252 @@
+//! 文件管理命令单元测试
+//!
+//! 对应源文件: src/file_ops.rs, src/editor.rs (:Rename, :Delete, 受保护路径)
+
+use aivim_core::editor::{Editor, PendingConfirmation};
+use aivim_core::file_ops::{delete_file, is_protected, parse_protected_paths, rename_file};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn write_temp(path: &str, content: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+// ==================== file_ops 函数测试 ====================
+
+#[test]
+fn test_rename_file_moves_content() {
+    let from = write_temp("/tmp/aivim_rename_from.txt", "hello\n");
+    let to = PathBuf::from("/tmp/aivim_rename_to.txt");
+    fs::remove_file(&to).ok();
+
+    rename_file(&from, &to, false).unwrap();
+
+    assert!(!from.exists());
+    assert_eq!(fs::read_to_string(&to).unwrap(), "hello\n");
+    fs::remove_file(&to).unwrap();
+}
+
+#[test]
+fn test_rename_file_refuses_overwrite_without_force() {
+    let from = write_temp("/tmp/aivim_rename_noforce_from.txt", "new\n");
+    let to = write_temp("/tmp/aivim_rename_noforce_to.txt", "old\n");
+
+    let result = rename_file(&from, &to, false);
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(&to).unwrap(), "old\n");
+
+    // 使用 force 覆盖
+    rename_file(&from, &to, true).unwrap();
+    assert_eq!(fs::read_to_string(&to).unwrap(), "new\n");
+    fs::remove_file(&to).unwrap();
+}
+
+#[test]
+fn test_rename_unsaved_file_is_ok() {
+    let from = Path::new("/tmp/aivim_rename_missing.txt");
+    fs::remove_file(from).ok();
+    let to = Path::new("/tmp/aivim_rename_missing_to.txt");
+
+    assert!(rename_file(from, to, false).is_ok());
+    assert!(!to.exists());
+}
+
+#[test]
+fn test_delete_missing_file_fails() {
+    let path = Path::new("/tmp/aivim_delete_missing.txt");
+    fs::remove_file(path).ok();
+    assert!(delete_file(path).is_err());
+}
+
+// ==================== Editor 命令测试 ====================
+
+#[test]
+fn test_rename_command_updates_buffer_path() {
+    let from = write_temp("/tmp/aivim_cmd_rename_from.txt", "content\n");
+    let to = PathBuf::from("/tmp/aivim_cmd_rename_to.txt");
+    fs::remove_file(&to).ok();
+
+    let mut editor = Editor::with_file(&from).unwrap();
+    editor.execute_command("Rename /tmp/aivim_cmd_rename_to.txt").unwrap();
+
+    assert_eq!(editor.current_buffer().file_path(), Some(to.as_path()));
+    assert!(!from.exists());
+    assert!(to.exists());
+    fs::remove_file(&to).unwrap();
+}
+
+#[test]
+fn test_rename_command_requires_filename() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("Rename").is_err());
+}
+
+#[test]
+fn test_rename_command_without_file_name() {
+    let mut editor = Editor::new();
+    let result = editor.execute_command("Rename /tmp/aivim_noname.txt");
+    assert_eq!(result, Err("No file name".to_string()));
+}
+
+#[test]
+fn test_delete_command_asks_for_confirmation() {
+    let path = write_temp("/tmp/aivim_cmd_delete_confirm.txt", "bye\n");
+
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("Delete").unwrap();
+
+    assert_eq!(
+        editor.pending_confirmation(),
+        Some(&PendingConfirmation::DeleteFile(path.clone()))
+    );
+    assert!(path.exists(), "确认前不应删除文件");
+
+    editor.resolve_confirmation(true).unwrap();
+    assert!(!path.exists());
+    assert!(editor.pending_confirmation().is_none());
+    assert!(editor.current_buffer().file_path().is_none());
+}
+
+#[test]
+fn test_delete_command_cancelled() {
+    let path = write_temp("/tmp/aivim_cmd_delete_cancel.txt", "keep\n");
+
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("Delete").unwrap();
+    editor.resolve_confirmation(false).unwrap();
+
+    assert!(path.exists());
+    assert_eq!(editor.current_buffer().file_path(), Some(path.as_path()));
+    assert_eq!(editor.message(), Some("Cancelled"));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_delete_bang_deletes_immediately() {
+    let path = write_temp("/tmp/aivim_cmd_delete_bang.txt", "gone\n");
+
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("Delete!").unwrap();
+
+    assert!(!path.exists());
+    assert!(editor.pending_confirmation().is_none());
+}
+
+// ==================== 受保护路径测试 ====================
+
+#[test]
+fn test_is_protected_matches_glob() {
+    let patterns = vec!["/etc/*".to_string()];
+    assert!(is_protected(Path::new("/etc/hosts"), &patterns));
+    assert!(is_protected(Path::new("/etc/ssh/sshd_config"), &patterns));
+    assert!(!is_protected(Path::new("/tmp/hosts"), &patterns));
+}
+
+#[test]
+fn test_is_protected_ignores_invalid_pattern() {
+    let patterns = vec!["[".to_string()];
+    assert!(!is_protected(Path::new("/etc/hosts"), &patterns));
+}
+
+#[test]
+fn test_default_protected_paths() {
+    let editor = Editor::new();
+    assert!(editor.is_protected_path(Path::new("/etc/passwd")));
+    assert!(!editor.is_protected_path(Path::new("/tmp/aivim_not_protected.txt")));
+}
+
+#[test]
+fn test_set_protected_paths_option() {
+    let mut editor = Editor::new();
+    editor.execute_command("set protectedpaths=/tmp/aivim_guarded/*,/srv/*").unwrap();
+    assert!(editor.is_protected_path(Path::new("/tmp/aivim_guarded/a.txt")));
+    assert!(!editor.is_protected_path(Path::new("/etc/passwd")));
+    editor.execute_command("set protectedpaths?").unwrap();
+    assert_eq!(editor.message(), Some("protectedpaths=/tmp/aivim_guarded/*,/srv/*"));
+
+    // 为空时不保护任何路径
+    editor.execute_command("set protectedpaths=").unwrap();
+    assert!(editor.protected_paths().is_empty());
+}
+
+#[test]
+fn test_protected_paths_from_config() {
+    assert_eq!(
+        parse_protected_paths("[files]\nprotected_paths = [\"/srv/*\"]\n"),
+        Ok(Some(vec!["/srv/*".to_string()]))
+    );
+    // 没有 [files] 时保持默认值
+    assert_eq!(parse_protected_paths("[statusline]\nleft = [\"mode\"]\n"), Ok(None));
+    assert!(parse_protected_paths("[files]\nprotected = []\n").is_err());
+
+    let path = std::env::temp_dir().join(format!("aivim_protected_config_{}.toml", std::process::id()));
+    fs::write(&path, "[files]\nprotected_paths = [\"/srv/*\"]\n").unwrap();
+    let mut editor = Editor::new();
+    editor.load_protected_paths(&path).unwrap();
+    assert_eq!(editor.protected_paths(), ["/srv/*".to_string()]);
+    assert!(!editor.is_protected_path(Path::new("/etc/passwd")));
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_write_protected_path_asks_for_confirmation() {
+    let path = PathBuf::from("/tmp/aivim_protected_dir/guarded.txt");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::remove_file(&path).ok();
+
+    let mut editor = Editor::new();
+    editor.set_protected_paths(vec!["/tmp/aivim_protected_dir/*".to_string()]);
+    editor.current_buffer_mut().insert(0, "data\n");
+
+    editor.execute_command("w /tmp/aivim_protected_dir/guarded.txt").unwrap();
+    assert_eq!(
+        editor.pending_confirmation(),
+        Some(&PendingConfirmation::WriteProtected(path.clone()))
+    );
+    assert!(!path.exists(), "确认前不应写入");
+
+    editor.resolve_confirmation(true).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "data\n");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_write_bang_skips_protection() {
+    let path = PathBuf::from("/tmp/aivim_protected_bang/guarded.txt");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::remove_file(&path).ok();
+
+    let mut editor = Editor::new();
+    editor.set_protected_paths(vec!["/tmp/aivim_protected_bang/*".to_string()]);
+    editor.current_buffer_mut().insert(0, "data\n");
+
+    editor.execute_command("w! /tmp/aivim_protected_bang/guarded.txt").unwrap();
+    assert!(editor.pending_confirmation().is_none());
+    assert!(path.exists());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wq_on_protected_path_fails() {
+    let path = write_temp("/tmp/aivim_protected_wq.txt", "x\n");
+
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.set_protected_paths(vec!["/tmp/aivim_protected_wq.txt".to_string()]);
+
+    assert!(editor.execute_command("wq").is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_set_trash_option() {
+    let mut editor = Editor::new();
+    assert!(!editor.options().trash, "默认直接删除");
+
+    editor.execute_command("set trash").unwrap();
+    assert!(editor.options().trash);
+
+    editor.execute_command("set notrash").unwrap();
+    assert!(!editor.options().trash);
+}
@@ -0,0 +1,90 @@
+//! 界面填充字符单元测试
+//!
+//! 对应源文件: src/fillchars.rs
+
+use aivim_core::editor::Editor;
+use aivim_core::fillchars::FillChars;
+
+// ==================== 解析测试 ====================
+
+#[test]
+fn test_parse_empty_uses_defaults() {
+    assert_eq!(FillChars::parse("", false), Some(FillChars::UNICODE));
+    assert_eq!(FillChars::parse("", true), Some(FillChars::ASCII));
+}
+
+#[test]
+fn test_parse_overrides_items() {
+    let fillchars = FillChars::parse("vert:|,eob:.", false).unwrap();
+    assert_eq!(fillchars.vert, '|');
+    assert_eq!(fillchars.eob, '.');
+    // 未指定的项使用默认值
+    assert_eq!(fillchars.horiz, FillChars::UNICODE.horiz);
+    assert_eq!(fillchars.foldclose, FillChars::UNICODE.foldclose);
+}
+
+#[test]
+fn test_parse_invalid() {
+    assert_eq!(FillChars::parse("vert", false), None);
+    assert_eq!(FillChars::parse("vert:ab", false), None);
+    assert_eq!(FillChars::parse("vert:", false), None);
+    assert_eq!(FillChars::parse("nosuch:x", false), None);
+}
+
+#[test]
+fn test_format_roundtrip() {
+    let fillchars = FillChars::parse("fold:=,eob:@", true).unwrap();
+    assert_eq!(FillChars::parse(&fillchars.format(), false), Some(fillchars));
+}
+
+// ==================== ASCII 回退测试 ====================
+
+#[test]
+fn test_with_defaults_keeps_user_items() {
+    let fillchars = FillChars::parse("eob:@", false).unwrap().with_defaults(true);
+    assert_eq!(fillchars.vert, '|');
+    assert_eq!(fillchars.horiz, '-');
+    assert_eq!(fillchars.eob, '@');
+
+    let fillchars = fillchars.with_defaults(false);
+    assert_eq!(fillchars.vert, FillChars::UNICODE.vert);
+    assert_eq!(fillchars.eob, '@');
+}
+
+#[test]
+fn test_ascii_set_is_ascii() {
+    let ascii = FillChars::ASCII.format();
+    assert!(ascii.is_ascii());
+}
+
+// ==================== :set 测试 ====================
+
+#[test]
+fn test_set_fillchars() {
+    let mut editor = Editor::new();
+    assert_eq!(editor.options().fillchars, FillChars::UNICODE);
+
+    assert!(editor.execute_command("set fillchars=eob:.").is_ok());
+    assert_eq!(editor.options().fillchars.eob, '.');
+
+    assert!(editor.execute_command("set fcs=eob:xy").is_err());
+    assert_eq!(editor.options().fillchars.eob, '.');
+}
+
+#[test]
+fn test_set_asciiglyphs() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("set fillchars=fold:=").is_ok());
+
+    assert!(editor.execute_command("set asciiglyphs").is_ok());
+    assert!(editor.options().asciiglyphs);
+    assert_eq!(editor.options().fillchars.vert, '|');
+    assert_eq!(editor.options().fillchars.fold, '=');
+
+    // 之后的 :set fillchars 以 ASCII 字符为默认值
+    assert!(editor.execute_command("set fillchars=eob:~").is_ok());
+    assert_eq!(editor.options().fillchars.horiz, '-');
+
+    assert!(editor.execute_command("set noasciiglyphs").is_ok());
+    assert_eq!(editor.options().fillchars, FillChars::UNICODE);
+}
@@ -0,0 +1,179 @@
+//! 文件查找单元测试
+//!
+//! 对应源文件: src/finder.rs, src/editor.rs (Ctrl+P / :Files)
+
+use aivim_core::editor::Editor;
+use aivim_core::finder::{list_files, FileFinder, Gitignore};
+use aivim_core::window::SplitDirection;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 创建测试目录，`files` 为相对路径和内容
+fn make_tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("aivim_finder_{}_{}", name, std::process::id()));
+    fs::remove_dir_all(&root).ok();
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    root
+}
+
+fn names(files: Vec<PathBuf>) -> Vec<String> {
+    files.iter().map(|path| path.display().to_string()).collect()
+}
+
+/// 等待后台查找结束
+fn wait_for_finder(editor: &mut Editor) {
+    let start = Instant::now();
+    while editor.picker().is_some_and(|picker| picker.is_loading()) {
+        editor.poll_finder();
+        assert!(start.elapsed() < Duration::from_secs(5), "finder did not finish");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+// ==================== .gitignore 测试 ====================
+
+#[test]
+fn test_gitignore_name_patterns() {
+    let ignore = Gitignore::parse("# comment\n*.log\n\ntarget/\n");
+    assert_eq!(ignore.matched("debug.log", false), Some(true));
+    // 不含 / 的模式匹配任意层级的文件名
+    assert_eq!(ignore.matched("a/b/debug.log", false), Some(true));
+    assert_eq!(ignore.matched("target", true), Some(true));
+    // 以 / 结尾的模式只匹配目录
+    assert_eq!(ignore.matched("target", false), None);
+    assert_eq!(ignore.matched("src/main.rs", false), None);
+}
+
+#[test]
+fn test_gitignore_anchored_and_negated() {
+    let ignore = Gitignore::parse("/build\ndocs/*.html\n*.txt\n!keep.txt\n**/gen/*.rs\n");
+    assert_eq!(ignore.matched("build", true), Some(true));
+    // 开头的 / 只匹配 .gitignore 所在目录
+    assert_eq!(ignore.matched("src/build", true), None);
+    assert_eq!(ignore.matched("docs/index.html", false), Some(true));
+    assert_eq!(ignore.matched("docs/api/index.html", false), None);
+    // 后面的规则优先
+    assert_eq!(ignore.matched("notes.txt", false), Some(true));
+    assert_eq!(ignore.matched("keep.txt", false), Some(false));
+    assert_eq!(ignore.matched("gen/a.rs", false), Some(true));
+    assert_eq!(ignore.matched("src/gen/a.rs", false), Some(true));
+}
+
+#[test]
+fn test_gitignore_escaped_characters() {
+    let ignore = Gitignore::parse("\\#notes\n\\!important\n");
+    assert_eq!(ignore.matched("#notes", false), Some(true));
+    assert_eq!(ignore.matched("!important", false), Some(true));
+}
+
+// ==================== 遍历测试 ====================
+
+#[test]
+fn test_list_files_respects_gitignore() {
+    let root = make_tree(
+        "list",
+        &[
+            (".gitignore", "target/\n*.log\n"),
+            (".hidden", ""),
+            ("README.md", ""),
+            ("debug.log", ""),
+            ("src/main.rs", ""),
+            ("src/.gitignore", "generated.rs\n!keep.log\n"),
+            ("src/generated.rs", ""),
+            ("src/keep.log", ""),
+            ("target/debug/app", ""),
+            ("docs/generated.rs", ""),
+        ],
+    );
+    fs::create_dir_all(root.join(".git/info")).unwrap();
+    fs::write(root.join(".git/info/exclude"), "README.md\n").unwrap();
+
+    // 子目录的 .gitignore 只作用于子目录，并且优先于上层目录
+    assert_eq!(names(list_files(&root)), vec!["docs/generated.rs", "src/keep.log", "src/main.rs"]);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_file_finder_runs_in_background() {
+    let files: Vec<(String, &str)> = (0..1200).map(|i| (format!("dir{}/file{}.txt", i % 3, i), "")).collect();
+    let files: Vec<(&str, &str)> = files.iter().map(|(path, content)| (path.as_str(), *content)).collect();
+    let root = make_tree("background", &files);
+
+    let mut finder = FileFinder::start(root.clone());
+    let mut found = Vec::new();
+    let start = Instant::now();
+    while !finder.is_done() {
+        found.extend(finder.poll());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    found.extend(finder.poll());
+    assert_eq!(found.len(), 1200);
+    assert_eq!(finder.root(), root.as_path());
+    fs::remove_dir_all(&root).ok();
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_files_command_opens_selected_file() {
+    let root = make_tree("open", &[("src/lib.rs", "lib\n"), ("src/main.rs", "main\n"), ("notes.md", "notes\n")]);
+    let mut editor = Editor::new();
+    editor.execute_command(&format!("Files {}", root.display())).unwrap();
+    assert!(editor.picker().unwrap().is_loading());
+    wait_for_finder(&mut editor);
+
+    let picker = editor.picker_mut().unwrap();
+    assert_eq!(picker.total(), 3);
+    picker.set_query("smain");
+    editor.accept_picker().unwrap();
+    assert!(editor.picker().is_none());
+    assert_eq!(editor.current_buffer().file_path(), Some(root.join("src/main.rs").as_path()));
+    assert_eq!(editor.get_line_text(0).as_deref(), Some("main"));
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_finder_opens_file_in_split() {
+    let root = make_tree("split", &[("a.txt", "a\n"), ("b.txt", "b\n")]);
+    let mut editor = Editor::new();
+    let original = editor.current_buffer_id();
+    editor.open_file_finder(Some(&root)).unwrap();
+    wait_for_finder(&mut editor);
+
+    editor.picker_mut().unwrap().set_query("b");
+    editor.accept_picker_in_split(SplitDirection::Vertical).unwrap();
+    assert_eq!(editor.windows().len(), 2);
+    assert_eq!(editor.get_line_text(0).as_deref(), Some("b"));
+    editor.window_command('w', 1).unwrap();
+    assert_eq!(editor.current_buffer_id(), original);
+
+    // 没有匹配项时只关闭选择器
+    editor.open_file_finder(Some(&root)).unwrap();
+    wait_for_finder(&mut editor);
+    editor.picker_mut().unwrap().set_query("zzz");
+    editor.accept_picker_in_split(SplitDirection::Horizontal).unwrap();
+    assert!(editor.picker().is_none());
+    assert_eq!(editor.windows().len(), 2);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_finder_stops_when_picker_closed() {
+    let root = make_tree("cancel", &[("a.txt", "")]);
+    let mut editor = Editor::new();
+    editor.open_file_finder(Some(&root)).unwrap();
+    editor.cancel_picker();
+    assert!(!editor.poll_finder());
+
+    assert_eq!(
+        editor.open_file_finder(Some(Path::new("/nonexistent/aivim"))),
+        Err("Not a directory: /nonexistent/aivim".to_string())
+    );
+    fs::remove_dir_all(&root).ok();
+}
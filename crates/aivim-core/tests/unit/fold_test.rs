@@ -0,0 +1,303 @@
+//! 折叠单元测试
+//!
+//! 对应源文件: src/fold.rs, src/buffer.rs (编辑时移动折叠), src/editor.rs (zf / zo / zc / za、折叠的显示和光标移动),
+//! src/session.rs (保存折叠)
+
+use aivim_core::buffer::{Buffer, BufferId};
+use aivim_core::cursor::Cursor;
+use aivim_core::fold::{indent_folds, Fold, FoldMethod, FoldSet};
+use aivim_core::keymap::{KeyParser, NormalCommand, Operator, OperatorTarget, ParseResult};
+use aivim_core::motion::Motion;
+use aivim_core::session::fold_commands;
+use aivim_core::syntax::fold_ranges;
+use super::common::editor_with;
+
+fn ranges(folds: &FoldSet) -> Vec<(usize, usize, bool)> {
+    folds.folds().iter().map(|fold| (fold.start, fold.end, fold.closed)).collect()
+}
+
+fn parse(keys: &str) -> ParseResult {
+    let mut parser = KeyParser::new();
+    let mut result = ParseResult::Invalid;
+    for key in keys.chars() {
+        result = parser.feed(key);
+    }
+    result
+}
+
+const LINES: &str = "zero\none\ntwo\nthree\nfour\nfive\n";
+
+// ==================== 折叠模型测试 ====================
+
+#[test]
+fn test_nested_open_close() {
+    let mut folds = FoldSet::default();
+    folds.create(1, 6);
+    folds.create(2, 3);
+    assert_eq!(ranges(&folds), vec![(1, 6, true), (2, 3, true)]);
+    assert_eq!(folds.closed_range(2), Some((1, 6)));
+    assert_eq!(folds.level(2, 3), 2);
+
+    // zo 打开显示出来的最外层折叠，再次 zo 打开内层
+    assert!(folds.open(2, false));
+    assert_eq!(folds.closed_range(2), Some((2, 3)));
+    assert!(folds.open(2, false));
+    assert_eq!(folds.closed_range(2), None);
+    assert!(!folds.open(2, false));
+
+    // zc 先关闭最内层，已经被折叠时关闭外面一层
+    assert!(folds.close(3, false));
+    assert_eq!(folds.closed_range(3), Some((2, 3)));
+    assert!(folds.close(3, false));
+    assert_eq!(folds.closed_range(3), Some((1, 6)));
+    assert!(!folds.close(0, false));
+
+    // zO 打开所有层，za 切换
+    assert!(folds.open(3, true));
+    assert_eq!(ranges(&folds), vec![(1, 6, false), (2, 3, false)]);
+    assert!(folds.toggle(5, false));
+    assert_eq!(folds.closed_range(5), Some((1, 6)));
+    assert!(folds.toggle(5, false));
+    assert_eq!(folds.closed_range(5), None);
+}
+
+#[test]
+fn test_delete_keeps_nested_folds() {
+    let mut folds = FoldSet::default();
+    folds.create(1, 6);
+    folds.create(2, 3);
+    // 折叠的行上 zd 删除显示的折叠，嵌套的折叠保留
+    assert!(folds.delete(2, false));
+    assert_eq!(ranges(&folds), vec![(2, 3, true)]);
+    assert!(!folds.delete(5, false));
+    folds.create(0, 4);
+    assert!(folds.delete(3, true));
+    assert!(folds.is_empty());
+}
+
+#[test]
+fn test_range_open_close() {
+    let mut folds = FoldSet::default();
+    folds.create(0, 9);
+    folds.create(2, 4);
+    folds.create(6, 7);
+    folds.open_range(3, 3, false);
+    assert_eq!(ranges(&folds), vec![(0, 9, false), (2, 4, true), (6, 7, true)]);
+    folds.open_range(0, 9, false);
+    assert_eq!(ranges(&folds), vec![(0, 9, false), (2, 4, false), (6, 7, false)]);
+    folds.close_range(0, 9, false);
+    assert_eq!(ranges(&folds), vec![(0, 9, false), (2, 4, true), (6, 7, true)]);
+    folds.close_range(5, 5, true);
+    assert_eq!(folds.closed_range(5), Some((0, 9)));
+}
+
+#[test]
+fn test_folds_move_with_edits() {
+    let mut buffer = Buffer::new(BufferId::new(1));
+    buffer.insert(0, LINES);
+    buffer.folds_mut().create(2, 4);
+
+    // 在折叠之前插入行，折叠下移
+    buffer.insert(0, "new\n");
+    assert_eq!(ranges(buffer.folds()), vec![(3, 5, true)]);
+    // 在折叠中间插入行，折叠变长
+    let idx = buffer.line_to_char(4) + 1;
+    buffer.insert(idx, "\n");
+    assert_eq!(ranges(buffer.folds()), vec![(3, 6, true)]);
+    // 删除折叠之前的整行，折叠上移
+    buffer.remove(0, 4);
+    assert_eq!(ranges(buffer.folds()), vec![(2, 5, true)]);
+    // 删除折叠中的所有行，折叠被删除
+    let (start, end) = (buffer.line_to_char(2), buffer.line_to_char(6));
+    buffer.remove(start, end - start);
+    assert!(buffer.folds().is_empty());
+}
+
+#[test]
+fn test_computed_folds_keep_closed_state() {
+    let mut folds = FoldSet::default();
+    // 切换折叠方式时所有折叠关闭
+    folds.set_computed(FoldMethod::Indent, 1, vec![(1, 3), (5, 8)]);
+    assert_eq!(ranges(&folds), vec![(1, 3, true), (5, 8, true)]);
+    folds.open(1, false);
+    // 重新计算时保留起始行相同的折叠的状态，新的折叠打开
+    folds.set_computed(FoldMethod::Indent, 2, vec![(1, 4), (5, 8), (10, 12)]);
+    assert_eq!(ranges(&folds), vec![(1, 4, false), (5, 8, true), (10, 12, false)]);
+    assert_eq!(folds.computed(), Some((FoldMethod::Indent, 2)));
+    folds.set_manual();
+    assert_eq!(folds.computed(), None);
+    assert_eq!(folds.folds().len(), 3);
+}
+
+#[test]
+fn test_indent_folds() {
+    let mut buffer = Buffer::new(BufferId::new(1));
+    buffer.insert(0, "fn a() {\n    let x = 1;\n\n    if x {\n        y();\n        z();\n    }\n}\nfn b() {}\n");
+    assert_eq!(indent_folds(&buffer, 4, 8), vec![(4, 5), (1, 6)]);
+    // 只有一行的缩进不创建折叠
+    let mut buffer = Buffer::new(BufferId::new(1));
+    buffer.insert(0, "a\n  b\nc\n");
+    assert!(indent_folds(&buffer, 2, 8).is_empty());
+}
+
+#[test]
+fn test_syntax_fold_ranges() {
+    let mut buffer = Buffer::new(BufferId::new(1));
+    buffer.set_filetype("rust");
+    buffer.insert(0, "struct P {\n    x: i32,\n}\n\nimpl P {\n    fn f(&self) {\n        g();\n    }\n    fn h() {}\n}\n");
+    assert_eq!(fold_ranges(&buffer), vec![(0, 2), (4, 9), (5, 7)]);
+    // 没有语法树的文件没有折叠
+    buffer.set_filetype("text");
+    assert!(fold_ranges(&buffer).is_empty());
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_zf_parses_as_operator() {
+    match parse("zfap") {
+        ParseResult::Complete(NormalCommand::Operator(command)) => assert_eq!(command.operator, Operator::Fold),
+        other => panic!("unexpected {:?}", other),
+    }
+    match parse("3zF") {
+        ParseResult::Complete(NormalCommand::Operator(command)) => {
+            assert_eq!(command.target, OperatorTarget::Line);
+            assert_eq!(command.count, 3);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(parse("zo"), ParseResult::Complete(NormalCommand::Z { key: 'o', count: 1 }));
+}
+
+#[test]
+fn test_closed_fold_renders_summary() {
+    let mut editor = editor_with(LINES, 2, 1);
+    assert!(editor.run_script(&["k", "zf2j"]).is_ok());
+    assert_eq!(editor.closed_fold(3), Some((1, 3)));
+    // 光标移到折叠的第一行
+    assert_eq!(editor.cursor().line, 1);
+
+    let rows = editor.visible_lines(10, 30, 0);
+    assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), vec![0, 1, 4, 5, 6]);
+    assert_eq!(rows[1].fold_end, Some(3));
+    assert!(rows[1].text.starts_with("▸··  3 lines: one"));
+    assert_eq!(rows[1].text.chars().count(), 30);
+    assert_eq!(editor.cursor_screen_position(30, 0), Some((0, 1)));
+
+    editor.execute_command("set fillchars=fold:-,foldclose:+").unwrap();
+    assert!(editor.visible_lines(10, 30, 0)[1].text.starts_with("+--  3 lines: one---"));
+}
+
+#[test]
+fn test_vertical_motion_skips_closed_fold() {
+    let mut editor = editor_with(LINES, 0, 0);
+    editor.execute_command("2,4fold").unwrap();
+    *editor.cursor_mut() = Cursor::new(0, 0);
+    editor.execute_motion(Motion::Down);
+    assert_eq!(editor.cursor().line, 1);
+    editor.execute_motion(Motion::Down);
+    assert_eq!(editor.cursor().line, 4);
+    editor.execute_motion(Motion::Up);
+    assert_eq!(editor.cursor().line, 1);
+    editor.execute_motion(Motion::Up);
+    assert_eq!(editor.cursor().line, 0);
+    // 光标在折叠中间的行上时，从折叠的两端移动
+    *editor.cursor_mut() = Cursor::new(2, 0);
+    editor.execute_motion(Motion::Down);
+    assert_eq!(editor.cursor().line, 4);
+}
+
+#[test]
+fn test_dd_deletes_closed_fold() {
+    let mut editor = editor_with(LINES, 1, 0);
+    assert!(editor.run_script(&["zfj", "dd"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "zero\nthree\nfour\nfive\n");
+    assert!(editor.current_buffer().folds().is_empty());
+}
+
+#[test]
+fn test_z_commands() {
+    let mut editor = editor_with(LINES, 1, 0);
+    assert!(editor.run_script(&["zfj", "zo"]).is_ok());
+    assert_eq!(editor.closed_fold(1), None);
+    assert!(editor.run_script(&["za"]).is_ok());
+    assert_eq!(editor.closed_fold(1), Some((1, 2)));
+    assert!(editor.run_script(&["zR"]).is_ok());
+    assert_eq!(editor.closed_fold(2), None);
+    assert!(editor.run_script(&["zM"]).is_ok());
+    assert_eq!(editor.closed_fold(2), Some((1, 2)));
+    assert!(editor.run_script(&["zE"]).is_ok());
+    assert!(editor.current_buffer().folds().is_empty());
+
+    // 没有折叠时 zo 报错
+    editor.run_script(&["zo"]);
+    assert_eq!(editor.message(), Some("No fold found"));
+}
+
+#[test]
+fn test_foldmethod_indent() {
+    let mut editor = editor_with("a\n    b\n    c\nd\n    e\n    f\n", 0, 0);
+    editor.execute_command("set foldmethod=indent").unwrap();
+    assert_eq!(editor.options().foldmethod, FoldMethod::Indent);
+    assert_eq!(editor.closed_fold(2), Some((1, 2)));
+    assert_eq!(editor.closed_fold(5), Some((4, 5)));
+
+    // 按缩进计算的折叠不能手动创建或删除
+    assert_eq!(
+        editor.create_fold(0, 1),
+        Err("Cannot create fold with foldmethod=indent".to_string())
+    );
+    assert!(editor.fold_command('E').is_err());
+
+    // 修改缓冲区之后重新计算，保留折叠的状态
+    editor.fold_command('o').unwrap_err();
+    *editor.cursor_mut() = Cursor::new(1, 0);
+    editor.fold_command('o').unwrap();
+    let idx = editor.current_buffer().line_to_char(3);
+    editor.current_buffer_mut().insert(idx, "    g\n");
+    editor.update_folds();
+    assert_eq!(editor.closed_fold(1), None);
+    assert_eq!(editor.current_buffer().folds().folds()[0], Fold { start: 1, end: 3, closed: false });
+    assert_eq!(editor.closed_fold(5), Some((5, 6)));
+
+    // 切换回手动折叠时保留现有的折叠
+    editor.execute_command("set fdm=manual").unwrap();
+    assert_eq!(editor.current_buffer().folds().folds().len(), 2);
+    assert!(editor.execute_command("set foldmethod=marker").is_err());
+}
+
+#[test]
+fn test_foldopen_foldclose_commands() {
+    let mut editor = editor_with(LINES, 0, 0);
+    editor.execute_command("2,5fold").unwrap();
+    editor.execute_command("3,4fold").unwrap();
+    editor.execute_command("3foldopen").unwrap();
+    assert_eq!(editor.closed_fold(3), Some((2, 3)));
+    editor.execute_command("%foldopen!").unwrap();
+    assert_eq!(editor.closed_fold(3), None);
+    editor.execute_command("4foldclose!").unwrap();
+    assert_eq!(editor.closed_fold(3), Some((1, 4)));
+}
+
+// ==================== 会话测试 ====================
+
+#[test]
+fn test_fold_commands_restore_open_state() {
+    // 外层关闭、内层打开的折叠：先打开外层和内层，再关闭外层
+    let folds = [
+        Fold { start: 0, end: 9, closed: true },
+        Fold { start: 2, end: 4, closed: false },
+        Fold { start: 6, end: 7, closed: true },
+    ];
+    assert_eq!(
+        fold_commands(&folds, FoldMethod::Manual),
+        vec!["1,10fold", "3,5fold", "7,8fold", "1foldopen", "3foldopen", "1foldclose"]
+    );
+    assert_eq!(fold_commands(&folds, FoldMethod::Indent), vec!["1foldopen", "3foldopen", "1foldclose"]);
+
+    let mut editor = editor_with(&"x\n".repeat(10), 0, 0);
+    for command in fold_commands(&folds, FoldMethod::Manual) {
+        editor.execute_command(&command).unwrap();
+    }
+    assert_eq!(editor.current_buffer().folds().folds(), &folds);
+}
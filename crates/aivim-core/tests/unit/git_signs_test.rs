@@ -0,0 +1,224 @@
+//! Git 差异标记单元测试
+//!
+//! 对应源文件: src/git_signs.rs, src/git.rs (show_head、apply_cached),
+//! src/git_plugin.rs (:set gitsigns、]c / [c、:GitPreviewHunk、:GitStageHunk、:GitRevertHunk)
+
+use aivim_core::editor::Editor;
+use aivim_core::git;
+use aivim_core::git_plugin::GitPlugin;
+use aivim_core::git_signs::{diff_hunks, hunk_line, line_sign, Hunk, HunkPreview, Sign};
+use aivim_core::keymap::{KeyParser, NormalCommand, ParseResult};
+use ropey::Rope;
+use std::fs;
+use std::time::{Duration, Instant};
+use super::common::{has_git, init_repo};
+
+/// git 插件中当前缓冲区的差异标记
+fn hunks(editor: &Editor) -> &[Hunk] {
+    editor.plugin::<GitPlugin>().unwrap().hunks(editor, editor.current_buffer())
+}
+
+fn hunk_preview(editor: &Editor) -> Option<&HunkPreview> {
+    editor.plugin::<GitPlugin>().unwrap().hunk_preview(editor)
+}
+
+/// 等待后台比较完成
+fn wait_for_hunks(editor: &mut Editor, count: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        editor.poll_plugins(Instant::now());
+        if hunks(editor).len() == count {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+// ==================== 比较测试 ====================
+
+#[test]
+fn test_added_and_modified_lines() {
+    let hunks = diff_hunks("a\nb\nc\n", &Rope::from_str("a\nB\nx\nc\n"));
+    assert_eq!(hunks.len(), 1);
+    assert_eq!(hunks[0].old_lines, vec!["b".to_string()]);
+    assert_eq!(hunks[0].new_lines, vec!["B".to_string(), "x".to_string()]);
+    assert_eq!(line_sign(&hunks, 0), None);
+    // 与删除的行一一对应的为修改，多出的为新增
+    assert_eq!(line_sign(&hunks, 1), Some(Sign::Modified));
+    assert_eq!(line_sign(&hunks, 2), Some(Sign::Added));
+    assert_eq!(line_sign(&hunks, 3), None);
+}
+
+#[test]
+fn test_removed_lines() {
+    let hunks = diff_hunks("a\nb\nc\n", &Rope::from_str("a\nc\n"));
+    assert_eq!(hunks.len(), 1);
+    assert_eq!(hunks[0].line_range(), (0, 0));
+    assert_eq!(line_sign(&hunks, 0), Some(Sign::Removed));
+    assert_eq!(line_sign(&hunks, 1), None);
+
+    // 删除第一行时标记在新的第一行上
+    let hunks = diff_hunks("a\nb\n", &Rope::from_str("b\n"));
+    assert_eq!(line_sign(&hunks, 0), Some(Sign::RemovedAbove));
+    assert_eq!(Sign::RemovedAbove.symbol(), '‾');
+}
+
+#[test]
+fn test_separate_hunks() {
+    let hunks = diff_hunks("a\nb\nc\nd\n", &Rope::from_str("x\nb\nc\nd\ny\n"));
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(hunks[0].new_start, 0);
+    assert_eq!(hunks[1].new_start, 4);
+    assert_eq!(line_sign(&hunks, 4), Some(Sign::Added));
+    assert!(diff_hunks("same\n", &Rope::from_str("same\n")).is_empty());
+}
+
+#[test]
+fn test_hunk_line() {
+    let hunks = diff_hunks("a\nb\nc\nd\ne\n", &Rope::from_str("A\nb\nC\nd\nE\n"));
+    assert_eq!(hunk_line(&hunks, 0, true, 1), Some(2));
+    assert_eq!(hunk_line(&hunks, 0, true, 2), Some(4));
+    assert_eq!(hunk_line(&hunks, 4, true, 1), None);
+    assert_eq!(hunk_line(&hunks, 3, false, 1), Some(2));
+    assert_eq!(hunk_line(&hunks, 3, false, 3), None);
+}
+
+#[test]
+fn test_hunk_patch() {
+    let hunks = diff_hunks("a\nb\nc\n", &Rope::from_str("a\nB\nx\nc\n"));
+    assert_eq!(hunks[0].patch("f.txt"), "--- a/f.txt\n+++ b/f.txt\n@@ -2,1 +2,2 @@\n-b\n+B\n+x\n");
+    // 空的范围用范围之前的行号
+    let hunks = diff_hunks("a\nc\n", &Rope::from_str("a\nb\nc\n"));
+    assert!(hunks[0].patch("f").ends_with("@@ -1,0 +2,1 @@\n+b\n"));
+    let hunks = diff_hunks("a\nb\n", &Rope::from_str("b\n"));
+    assert!(hunks[0].patch("f").ends_with("@@ -1,1 +0,0 @@\n-a\n"));
+}
+
+#[test]
+fn test_parse_bracket_commands() {
+    let mut parser = KeyParser::new();
+    assert_eq!(parser.feed('2'), ParseResult::Pending);
+    assert_eq!(parser.feed(']'), ParseResult::Pending);
+    assert!(parser.awaiting_bracket());
+    assert_eq!(
+        parser.feed('c'),
+        ParseResult::Complete(NormalCommand::Bracket { forward: true, key: 'c', count: 2 })
+    );
+    assert_eq!(parser.feed('['), ParseResult::Pending);
+    assert_eq!(
+        parser.feed('c'),
+        ParseResult::Complete(NormalCommand::Bracket { forward: false, key: 'c', count: 1 })
+    );
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_gitsigns_option() {
+    let mut editor = Editor::new();
+    assert!(!editor.options().gitsigns);
+    editor.execute_command("set gitsigns").unwrap();
+    assert!(editor.options().gitsigns);
+    // 没有文件的缓冲区没有标记
+    assert!(!editor.poll_plugins(Instant::now()));
+    assert!(hunks(&editor).is_empty());
+    editor.execute_command("set nogitsigns").unwrap();
+    assert!(!editor.options().gitsigns);
+}
+
+#[test]
+fn test_signs_for_tracked_file() {
+    if !has_git() {
+        return;
+    }
+    let dir = init_repo("tracked", "one\ntwo\n");
+    let path = dir.join("a.txt");
+    assert_eq!(git::show_head(&path).unwrap(), "one\ntwo\n");
+    fs::write(&path, "one\n2\nthree\n").unwrap();
+
+    let mut editor = Editor::with_file(&path).unwrap();
+    // 没有开启时不比较
+    assert!(hunks(&editor).is_empty());
+    editor.execute_command("set gitsigns").unwrap();
+    wait_for_hunks(&mut editor, 1);
+    let hunks = hunks(&editor);
+    assert_eq!(hunks.len(), 1);
+    assert_eq!(line_sign(hunks, 1), Some(Sign::Modified));
+    assert_eq!(line_sign(hunks, 2), Some(Sign::Added));
+
+    // 没有被跟踪的文件没有标记
+    let untracked = dir.join("b.txt");
+    fs::write(&untracked, "new\n").unwrap();
+    assert!(git::show_head(&untracked).is_err());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_hunk_commands_need_gitsigns() {
+    let mut editor = Editor::new();
+    editor.run_script(&["]c"]);
+    assert_eq!(editor.message(), Some("Git signs are off (:set gitsigns)"));
+    assert!(editor.execute_command("GitStageHunk").is_err());
+}
+
+#[test]
+fn test_jump_and_preview_hunks() {
+    if !has_git() {
+        return;
+    }
+    let dir = init_repo("jump", "a\nb\nc\nd\ne\n");
+    let path = dir.join("a.txt");
+    fs::write(&path, "a\nB\nc\nd\nE\n").unwrap();
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("set gitsigns").unwrap();
+    wait_for_hunks(&mut editor, 2);
+
+    assert!(editor.run_script(&["]c"]).is_ok());
+    assert_eq!(editor.cursor().line, 1);
+    assert!(editor.run_script(&["]c"]).is_ok());
+    assert_eq!(editor.cursor().line, 4);
+    editor.run_script(&["]c"]);
+    assert_eq!(editor.message(), Some("No more hunks"));
+    assert!(editor.run_script(&["gg", "2]c", "[c"]).is_ok());
+    assert_eq!(editor.cursor().line, 1);
+
+    editor.execute_command("GitPreviewHunk").unwrap();
+    let preview = hunk_preview(&editor).unwrap();
+    assert_eq!(preview.hunk.preview_lines(), vec!["-b".to_string(), "+B".to_string()]);
+    // 光标离开这一行后不再显示
+    assert!(editor.run_script(&["j"]).is_ok());
+    assert!(hunk_preview(&editor).is_none());
+    assert_eq!(editor.execute_command("GitPreviewHunk"), Err("No hunk at cursor".to_string()));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_stage_and_revert_hunk() {
+    if !has_git() {
+        return;
+    }
+    let dir = init_repo("stage", "a\nb\nc\nd\n");
+    let path = dir.join("a.txt");
+    fs::write(&path, "a\nB\nc\nd\nnew\n").unwrap();
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.execute_command("set gitsigns").unwrap();
+    wait_for_hunks(&mut editor, 2);
+
+    // 只暂存第二行的修改
+    assert!(editor.run_script(&["j", ":GitStageHunk"]).is_ok());
+    assert_eq!(editor.message(), Some("Staged hunk"));
+    let staged = git::diff(&dir, true).unwrap();
+    assert!(staged.contains("-b\n+B\n"));
+    assert!(!staged.contains("+new"));
+
+    // 恢复最后新增的行，可以撤销
+    assert!(editor.run_script(&["gg", "4j", ":GitRevertHunk"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a\nB\nc\nd\n");
+    editor.run_script(&["gg", ":GitRevertHunk"]);
+    assert_eq!(editor.message(), Some("No hunk at cursor"));
+    assert!(editor.run_script(&["j", ":GitRevertHunk"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\nd\n");
+    assert!(editor.run_script(&["u"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a\nB\nc\nd\n");
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,106 @@
+//! Git 单元测试
+//!
+//! 对应源文件: src/git.rs, src/commit_msg.rs, src/ai_plugin.rs (:AiCommitMsg)
+
+use aivim_core::ai_plugin::AiPlugin;
+use aivim_core::commit_msg::{request_body, MAX_DIFF_CHARS};
+use aivim_core::editor::Editor;
+use aivim_core::git;
+use aivim_ai::AiConfig;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use super::common::{git, has_curl, has_git, staged_repo};
+
+/// AI 服务换成返回 `reply` 的 `file://` 地址
+fn set_reply(editor: &mut Editor, dir: &Path, reply: &str) {
+    let path = dir.join("reply.txt");
+    let response = format!("data: {{\"choices\":[{{\"delta\":{{\"content\":\"{}\"}}}}]}}\n\ndata: [DONE]\n", reply);
+    fs::write(&path, response).unwrap();
+    editor.execute_command(&format!("set aiendpoint=file://{}", path.display())).unwrap();
+    editor.execute_command("set aikeyenv=AIVIM_TEST_UNSET_KEY").unwrap();
+}
+
+fn wait_for_reply(editor: &mut Editor) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while editor.plugin::<AiPlugin>().unwrap().commit_message_pending() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        editor.poll_plugins(Instant::now());
+    }
+}
+
+// ==================== git ====================
+
+#[test]
+fn test_staged_and_unstaged_diff() {
+    if !has_git() || !has_curl() {
+        return;
+    }
+    let dir = staged_repo("diff", "one\n");
+    assert!(git::diff(&dir, true).unwrap().contains("+one"));
+    assert_eq!(git::diff(&dir, false).unwrap(), "");
+
+    fs::write(dir.join("a.txt"), "two\n").unwrap();
+    let unstaged = git::diff(&dir, false).unwrap();
+    assert!(unstaged.contains("-one\n+two"), "{}", unstaged);
+
+    assert_eq!(fs::canonicalize(git::work_tree(&dir).unwrap()).unwrap(), fs::canonicalize(&dir).unwrap());
+    assert!(git::git_path(&dir, "COMMIT_EDITMSG").unwrap().ends_with(".git/COMMIT_EDITMSG"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_git_error_outside_repository() {
+    let dir = std::env::temp_dir();
+    if !has_git() || !has_curl() || git::work_tree(&dir).is_ok() {
+        return;
+    }
+    assert!(git::diff(&dir, true).is_err());
+}
+
+// ==================== :AiCommitMsg ====================
+
+#[test]
+fn test_request_body_truncates_diff() {
+    let diff = "x".repeat(MAX_DIFF_CHARS + 10);
+    let body = request_body(&AiConfig::default(), &diff);
+    let message = body["messages"][1]["content"].as_str().unwrap();
+    assert!(message.ends_with("[diff truncated]"));
+    assert_eq!(message.chars().filter(|c| *c == 'x').count(), MAX_DIFF_CHARS);
+}
+
+#[test]
+fn test_ai_commit_msg_inserts_into_commit_editmsg() {
+    if !has_git() || !has_curl() {
+        return;
+    }
+    let dir = staged_repo("commit", "one\n");
+    let mut editor = Editor::new();
+    editor.open_file(&dir.join("a.txt")).unwrap();
+    set_reply(&mut editor, &dir, "```\\nfeat: add a\\n```");
+
+    editor.execute_command("AiCommitMsg").unwrap();
+    wait_for_reply(&mut editor);
+    let buffer = editor.current_buffer();
+    assert!(buffer.file_path().unwrap().ends_with(".git/COMMIT_EDITMSG"));
+    assert_eq!(buffer.to_string(), "feat: add a\n");
+
+    // 一次撤销删除插入的提交信息
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_ai_commit_msg_without_changes() {
+    if !has_git() || !has_curl() {
+        return;
+    }
+    let dir = staged_repo("clean", "one\n");
+    git(&dir, &["reset", "-q"]);
+    let mut editor = Editor::new();
+    editor.open_file(&dir.join("a.txt")).unwrap();
+    assert!(editor.execute_command("AiCommitMsg").is_err());
+    assert!(!editor.plugin::<AiPlugin>().unwrap().commit_message_pending());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,109 @@
+//! 十六进制视图单元测试
+//!
+//! 对应源文件: src/hex.rs, src/buffer.rs (二进制文件的读写)
+
+use aivim_core::hex;
+use aivim_core::Cursor;
+use std::fs;
+use super::common::editor_with_bytes;
+
+// ==================== 转储测试 ====================
+
+#[test]
+fn test_is_binary() {
+    assert!(hex::is_binary(b"ab\0cd"));
+    assert!(!hex::is_binary(b"plain text\n"));
+    // UTF-16 文本中的 NUL 不算
+    assert!(!hex::is_binary(&[0xff, 0xfe, b'a', 0]));
+}
+
+#[test]
+fn test_dump_format() {
+    let bytes: Vec<u8> = b"Hello\0".iter().copied().chain(0..12).collect();
+    assert_eq!(
+        hex::dump(&bytes),
+        "00000000: 4865 6c6c 6f00 0001 0203 0405 0607 0809  Hello...........\n\
+         00000010: 0a0b                                     ..\n"
+    );
+    assert_eq!(hex::dump(&[]), "");
+}
+
+#[test]
+fn test_parse_round_trip() {
+    let bytes: Vec<u8> = (0..=255).collect();
+    assert_eq!(hex::parse(&hex::dump(&bytes)), Ok(bytes));
+}
+
+#[test]
+fn test_parse_ignores_offset_and_ascii_columns() {
+    // 插入和删除字节后偏移和 ASCII 列不再对应，写入时只读取十六进制列
+    let text = "00000000: 41 4243  zzz\n00000000: 44  4545\n\n";
+    assert_eq!(hex::parse(text), Ok(b"ABCD".to_vec()));
+}
+
+#[test]
+fn test_parse_invalid_dump() {
+    assert_eq!(hex::parse("00000000: 4142\nnot a dump\n"), Err(1));
+    assert_eq!(hex::parse("00000000: 414\n"), Err(0));
+    assert_eq!(hex::parse("00000000: 41zz\n"), Err(0));
+}
+
+#[test]
+fn test_byte_offset() {
+    let line = "00000010: 4865 6c6c 6f00                           Hello.";
+    assert_eq!(hex::byte_offset(line, 10), Some(0x10));
+    assert_eq!(hex::byte_offset(line, 11), Some(0x10));
+    assert_eq!(hex::byte_offset(line, 12), Some(0x11));
+    assert_eq!(hex::byte_offset(line, 15), Some(0x12));
+    // 组之间的空格、偏移和 ASCII 列上没有字节
+    assert_eq!(hex::byte_offset(line, 14), None);
+    assert_eq!(hex::byte_offset(line, 3), None);
+    assert_eq!(hex::byte_offset(line, 52), None);
+}
+
+// ==================== 读写文件测试 ====================
+
+#[test]
+fn test_binary_file_opens_as_hex_dump() {
+    let (editor, _) = editor_with_bytes("open", b"ab\0\xff");
+    let buffer = editor.current_buffer();
+    assert!(buffer.is_binary());
+    assert_eq!(buffer.line_text(0).unwrap(), format!("00000000: 6162 00ff{}  ab..", " ".repeat(30)));
+    assert_eq!(editor.message(), Some("Binary file: editing as hex dump"));
+}
+
+#[test]
+fn test_replace_hex_digit_edits_byte() {
+    let (mut editor, path) = editor_with_bytes("replace", b"ab\0\xff");
+    *editor.cursor_mut() = Cursor::new(0, 11);
+    editor.replace_chars('2', 1);
+    editor.execute_command("w").unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), b"bb\0\xff");
+    // 写入后重新生成 ASCII 列
+    assert!(editor.current_buffer().line_text(0).unwrap().ends_with("  bb.."));
+    assert!(!editor.current_buffer().is_modified());
+}
+
+#[test]
+fn test_delete_hex_digits_removes_byte() {
+    let (mut editor, path) = editor_with_bytes("delete", b"ab\0\xff");
+    *editor.cursor_mut() = Cursor::new(0, 10);
+    editor.delete_char();
+    editor.delete_char();
+    editor.execute_command("w").unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), b"b\0\xff");
+}
+
+#[test]
+fn test_invalid_hex_dump_is_not_written() {
+    let (mut editor, path) = editor_with_bytes("invalid", b"ab\0\xff");
+    *editor.cursor_mut() = Cursor::new(0, 10);
+    editor.replace_chars('g', 1);
+    let err = editor.execute_command("w").unwrap_err();
+
+    assert_eq!(err, "Invalid hex dump at line 1");
+    assert_eq!(fs::read(&path).unwrap(), b"ab\0\xff");
+    assert!(editor.current_buffer().is_modified());
+}
@@ -0,0 +1,102 @@
+//! 命令行历史单元测试
+//!
+//! 对应源文件: src/history.rs, src/editor.rs (Command / 搜索模式上下方向键)
+
+use aivim_core::editor::Editor;
+use aivim_core::history::History;
+use aivim_core::mode::Mode;
+use aivim_core::search::SearchDirection;
+
+fn history_with(entries: &[&str]) -> History {
+    let mut history = History::default();
+    for entry in entries {
+        history.push(entry);
+    }
+    history
+}
+
+// ==================== History 测试 ====================
+
+#[test]
+fn test_push_ignores_blank_and_moves_duplicates() {
+    let history = history_with(&["w", "  ", "set nu", "w"]);
+    assert_eq!(history.entries(), ["set nu", "w"]);
+}
+
+#[test]
+fn test_push_respects_capacity() {
+    let mut history = History::new(2);
+    history.push("a");
+    history.push("b");
+    history.push("c");
+    assert_eq!(history.entries(), ["b", "c"]);
+}
+
+#[test]
+fn test_prev_and_next() {
+    let mut history = history_with(&["w", "set nu", "q"]);
+    assert_eq!(history.prev("").as_deref(), Some("q"));
+    assert_eq!(history.prev("q").as_deref(), Some("set nu"));
+    assert_eq!(history.prev("set nu").as_deref(), Some("w"));
+    // 已经是最早的一条
+    assert_eq!(history.prev("w"), None);
+    assert_eq!(history.next("w").as_deref(), Some("set nu"));
+    assert_eq!(history.next("set nu").as_deref(), Some("q"));
+    // 越过最新的一条时恢复输入的文本
+    assert_eq!(history.next("q").as_deref(), Some(""));
+    assert_eq!(history.next(""), None);
+}
+
+#[test]
+fn test_prefix_filtering() {
+    let mut history = history_with(&["bnext", "w", "bprev", "set nu"]);
+    assert_eq!(history.prev("b").as_deref(), Some("bprev"));
+    assert_eq!(history.prev("bprev").as_deref(), Some("bnext"));
+    assert_eq!(history.prev("bnext"), None);
+    assert_eq!(history.next("bnext").as_deref(), Some("bprev"));
+    assert_eq!(history.next("bprev").as_deref(), Some("b"));
+}
+
+#[test]
+fn test_editing_restarts_browsing() {
+    let mut history = history_with(&["bnext", "set nu", "w"]);
+    assert_eq!(history.prev("").as_deref(), Some("w"));
+    // 浏览后修改了命令行，以新的文本为前缀重新开始
+    assert_eq!(history.prev("s").as_deref(), Some("set nu"));
+    assert_eq!(history.next("set nu").as_deref(), Some("s"));
+}
+
+// ==================== Editor 测试 ====================
+
+#[test]
+fn test_command_history_navigation() {
+    let mut editor = Editor::new();
+    editor.add_command_history("set number");
+    editor.add_command_history("bnext");
+    editor.set_mode(Mode::Command);
+
+    editor.command_line_mut().push_str("se");
+    editor.history_prev();
+    assert_eq!(editor.command_line(), "set number");
+    editor.history_next();
+    assert_eq!(editor.command_line(), "se");
+}
+
+#[test]
+fn test_search_history_is_separate() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "foo bar\n");
+    editor.add_command_history("w");
+    editor.start_search(SearchDirection::Forward, "bar");
+    assert_eq!(editor.search_history().entries(), ["bar"]);
+
+    editor.set_mode(Mode::SearchBackward);
+    editor.history_prev();
+    assert_eq!(editor.command_line(), "bar");
+
+    // 其他模式下没有历史
+    editor.command_line_mut().clear();
+    editor.set_mode(Mode::Normal);
+    editor.history_prev();
+    assert_eq!(editor.command_line(), "");
+}
@@ -0,0 +1,69 @@
+//! 界面文本本地化单元测试
+//!
+//! 对应源文件: src/i18n.rs
+
+use aivim_core::editor::Editor;
+use aivim_core::i18n::{format_message, translate, Locale};
+
+// ==================== 语言解析测试 ====================
+
+#[test]
+fn test_locale_parse() {
+    assert_eq!(Locale::parse("zh"), Some(Locale::Zh));
+    assert_eq!(Locale::parse("zh_CN.UTF-8"), Some(Locale::Zh));
+    assert_eq!(Locale::parse("en_US.UTF-8"), Some(Locale::En));
+    assert_eq!(Locale::parse("C"), Some(Locale::En));
+    assert_eq!(Locale::parse("fr_FR"), None);
+}
+
+#[test]
+fn test_locale_code() {
+    assert_eq!(Locale::En.code(), "en");
+    assert_eq!(Locale::Zh.code(), "zh");
+    assert_eq!(Locale::default(), Locale::En);
+}
+
+// ==================== 翻译测试 ====================
+
+#[test]
+fn test_translate() {
+    assert_eq!(translate(Locale::En, "Saved"), "Saved");
+    assert_eq!(translate(Locale::Zh, "Saved"), "已保存");
+}
+
+#[test]
+fn test_translate_missing_falls_back_to_english() {
+    assert_eq!(translate(Locale::Zh, "Not in catalog"), "Not in catalog");
+}
+
+#[test]
+fn test_format_message() {
+    assert_eq!(format_message("Opened {}", &[&"a.txt"]), "Opened a.txt");
+    assert_eq!(format_message("{1} before {0}", &[&1, &2]), "2 before 1");
+    // 不是参数的花括号原样保留
+    assert_eq!(format_message("Usage: :job kill {id}", &[]), "Usage: :job kill {id}");
+    assert_eq!(format_message("{} and {}", &[&1]), "1 and {}");
+}
+
+// ==================== 编辑器语言设置测试 ====================
+
+#[test]
+fn test_set_lang_translates_messages() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("set lang=zh").is_ok());
+    assert_eq!(editor.locale(), Locale::Zh);
+
+    let err = editor.execute_command("nosuchcommand").unwrap_err();
+    assert_eq!(err, "未知命令: nosuchcommand");
+
+    assert!(editor.execute_command("set language=en").is_ok());
+    let err = editor.execute_command("nosuchcommand").unwrap_err();
+    assert_eq!(err, "Unknown command: nosuchcommand");
+}
+
+#[test]
+fn test_set_lang_invalid() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("set lang=fr").is_err());
+    assert_eq!(editor.locale(), Locale::En);
+}
@@ -0,0 +1,96 @@
+//! 缩进操作符单元测试
+//!
+//! 对应源文件: src/editor.rs (shift_lines, shiftwidth 选项)
+
+use aivim_core::editor::{Editor, DEFAULT_SHIFTWIDTH};
+use aivim_core::keymap::{KeyParser, NormalCommand, ParseResult};
+use super::common::editor_with;
+
+/// 解析按键序列并执行操作符
+fn run(editor: &mut Editor, keys: &str) {
+    let mut parser = KeyParser::new();
+    for key in keys.chars() {
+        if let ParseResult::Complete(NormalCommand::Operator(command)) = parser.feed(key) {
+            editor.execute_operator(command);
+            return;
+        }
+    }
+    panic!("{:?} 不是完整的操作符命令", keys);
+}
+
+#[test]
+fn test_default_shiftwidth() {
+    let editor = Editor::new();
+    assert_eq!(editor.options().shiftwidth, DEFAULT_SHIFTWIDTH);
+}
+
+#[test]
+fn test_indent_line_with_count() {
+    let mut editor = editor_with("a\nb\nc\nd\n", 0, 0);
+    run(&mut editor, "3>>");
+
+    assert_eq!(editor.current_buffer().to_string(), "    a\n    b\n    c\nd\n");
+    // 光标移到第一个非空白字符
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 4));
+}
+
+#[test]
+fn test_indent_paragraph_text_object() {
+    let mut editor = editor_with("a\nb\n\nc\n", 0, 0);
+    run(&mut editor, ">ip");
+
+    assert_eq!(editor.current_buffer().to_string(), "    a\n    b\n\nc\n");
+}
+
+#[test]
+fn test_shiftwidth_option() {
+    let mut editor = editor_with("x\n", 0, 0);
+    editor.execute_command("set sw=2").unwrap();
+    assert_eq!(editor.options().shiftwidth, 2);
+
+    run(&mut editor, ">>");
+    assert_eq!(editor.current_buffer().to_string(), "  x\n");
+
+    editor.execute_command("set shiftwidth=8").unwrap();
+    run(&mut editor, ">>");
+    assert_eq!(editor.current_buffer().to_string(), "          x\n");
+}
+
+#[test]
+fn test_shiftwidth_invalid_value() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("set sw=0").is_err());
+    assert!(editor.execute_command("set sw=abc").is_err());
+    assert!(editor.execute_command("set foo=1").is_err());
+    assert_eq!(editor.options().shiftwidth, DEFAULT_SHIFTWIDTH);
+}
+
+#[test]
+fn test_outdent_removes_at_most_shiftwidth() {
+    let mut editor = editor_with("      a\n  b\n\tc\n", 0, 0);
+    run(&mut editor, "<2j");
+
+    assert_eq!(editor.current_buffer().to_string(), "  a\nb\nc\n");
+}
+
+#[test]
+fn test_indent_is_single_undo() {
+    let mut editor = editor_with("a\nb\nc\n", 0, 0);
+    run(&mut editor, ">G");
+    assert_eq!(editor.current_buffer().to_string(), "    a\n    b\n    c\n");
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\n");
+}
+
+#[test]
+fn test_indent_noexpandtab_uses_tabs() {
+    let mut editor = editor_with("x\n    y\n", 0, 0);
+    editor.execute_command("set noet ts=8").unwrap();
+
+    // 缩进按 tabstop 重写为制表符加空格
+    run(&mut editor, ">j");
+    assert_eq!(editor.current_buffer().to_string(), "    x\n\ty\n");
+    run(&mut editor, ">>");
+    assert_eq!(editor.current_buffer().to_string(), "\tx\n\ty\n");
+}
@@ -0,0 +1,161 @@
+//! 后台任务单元测试
+//!
+//! 对应源文件: src/job.rs, src/waker.rs, src/editor.rs (:jobs, :job kill)
+
+use aivim_core::editor::Editor;
+use aivim_core::job::{JobEvent, JobId, JobManager, JobSpec, JobStatus, JobStream};
+use aivim_core::waker::Waker;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 轮询直到任务结束，返回期间收到的所有事件
+fn wait_for_exit(manager: &mut JobManager, id: JobId) -> Vec<JobEvent> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut events = Vec::new();
+    while Instant::now() < deadline {
+        events.extend(manager.poll());
+        if !manager.get(id).unwrap().status().is_running() {
+            return events;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("任务 {} 没有在超时前结束", id);
+}
+
+// ==================== JobSpec 测试 ====================
+
+#[test]
+fn test_spec_display() {
+    assert_eq!(JobSpec::new("cargo").args(["build", "--release"]).display(), "cargo build --release");
+    assert_eq!(JobSpec::shell("make test").display(), "make test");
+}
+
+// ==================== JobManager 测试 ====================
+
+#[test]
+fn test_job_streams_output_and_exit() {
+    let mut manager = JobManager::new();
+    let id = manager.spawn(JobSpec::shell("echo one; echo two >&2; exit 3")).unwrap();
+    assert_eq!(manager.running_count(), 1);
+
+    let events = wait_for_exit(&mut manager, id);
+    assert!(events.contains(&JobEvent::Output {
+        id,
+        stream: JobStream::Stdout,
+        line: "one".to_string(),
+    }));
+    assert!(events.contains(&JobEvent::Output {
+        id,
+        stream: JobStream::Stderr,
+        line: "two".to_string(),
+    }));
+    // 退出事件在所有输出之后
+    assert_eq!(
+        events.last(),
+        Some(&JobEvent::Exited { id, status: JobStatus::Exited(Some(3)) })
+    );
+
+    let job = manager.get(id).unwrap();
+    assert_eq!(job.output().count(), 2);
+    assert_eq!(manager.running_count(), 0);
+}
+
+#[test]
+fn test_job_stdin_and_cwd() {
+    let mut manager = JobManager::new();
+    let spec = JobSpec::shell("cat; pwd").stdin("hello\n").cwd("/tmp");
+    let id = manager.spawn(spec).unwrap();
+    wait_for_exit(&mut manager, id);
+
+    let output: Vec<&str> = manager.get(id).unwrap().output().collect();
+    assert_eq!(output[0], "hello");
+    assert!(output[1].ends_with("tmp"));
+}
+
+#[test]
+fn test_kill_job() {
+    let mut manager = JobManager::new();
+    let id = manager.spawn(JobSpec::new("sleep").arg("30")).unwrap();
+    manager.kill(id).unwrap();
+
+    let events = wait_for_exit(&mut manager, id);
+    assert_eq!(events.last(), Some(&JobEvent::Exited { id, status: JobStatus::Killed }));
+    // 已结束的任务不能再次终止
+    assert!(manager.kill(id).is_err());
+    assert!(manager.kill(JobId::new(99)).is_err());
+
+    assert_eq!(manager.clear_finished(), 1);
+    assert!(manager.get(id).is_none());
+}
+
+#[test]
+fn test_spawn_with_callback() {
+    let mut manager = JobManager::new();
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let sink = Rc::clone(&seen);
+    let id = manager
+        .spawn_with(
+            JobSpec::shell("printf 'a\\nb\\n'"),
+            Box::new(move |event| sink.borrow_mut().push(event.clone())),
+        )
+        .unwrap();
+    wait_for_exit(&mut manager, id);
+
+    let seen = seen.borrow();
+    assert_eq!(seen.len(), 3);
+    assert!(matches!(seen[2], JobEvent::Exited { .. }));
+}
+
+#[test]
+fn test_waker_called_for_output() {
+    let mut manager = JobManager::new();
+    let (sender, receiver) = mpsc::channel();
+    manager.set_waker(Waker::new(move || {
+        let _ = sender.send(());
+    }));
+    let id = manager.spawn(JobSpec::shell("echo hello")).unwrap();
+    // 不轮询，只等待唤醒
+    receiver.recv_timeout(Duration::from_secs(10)).expect("waker not called");
+    let events = wait_for_exit(&mut manager, id);
+    assert!(matches!(&events[0], JobEvent::Output { line, .. } if line == "hello"));
+}
+
+#[test]
+fn test_spawn_missing_program() {
+    let mut manager = JobManager::new();
+    assert!(manager.spawn(JobSpec::new("aivim-no-such-program")).is_err());
+    assert_eq!(manager.jobs().count(), 0);
+}
+
+// ==================== :jobs / :job 命令测试 ====================
+
+#[test]
+fn test_jobs_command_and_kill() {
+    let mut editor = Editor::new();
+    assert_eq!(editor.format_jobs(), "No jobs");
+
+    let id = editor.jobs_mut().spawn(JobSpec::new("sleep").arg("30")).unwrap();
+    editor.execute_command("jobs").unwrap();
+    assert!(editor.show_jobs_panel());
+    assert!(editor.format_jobs().contains("[1] running    sleep 30"));
+
+    editor.execute_command(&format!("job kill {}", id)).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while editor.jobs().get(id).unwrap().status().is_running() && Instant::now() < deadline {
+        editor.poll_jobs();
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(editor.message(), Some("Job 1 (sleep 30) killed"));
+}
+
+#[test]
+fn test_job_command_errors() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("job kill").is_err());
+    assert!(editor.execute_command("job kill abc").is_err());
+    assert_eq!(editor.execute_command("job kill 7"), Err("No job 7".to_string()));
+    assert!(editor.execute_command("job").is_err());
+}
@@ -0,0 +1,92 @@
+//! 合并行（J / gJ）单元测试
+//!
+//! 对应源文件: src/editor.rs (join_lines, join_line_range)
+
+use super::common::editor_with;
+
+#[test]
+fn test_join_inserts_single_space() {
+    let mut editor = editor_with("foo\n    bar\nbaz\n", 0, 1);
+    assert!(editor.join_lines(1, true));
+
+    assert_eq!(editor.current_buffer().to_string(), "foo bar\nbaz\n");
+    // 光标停在连接处
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 3));
+}
+
+#[test]
+fn test_join_verbatim() {
+    let mut editor = editor_with("foo\n    bar\n", 0, 0);
+    assert!(editor.join_lines(1, false));
+
+    assert_eq!(editor.current_buffer().to_string(), "foo    bar\n");
+}
+
+#[test]
+fn test_join_with_count() {
+    let mut editor = editor_with("a\nb\nc\nd\n", 0, 0);
+    assert!(editor.join_lines(3, true));
+
+    assert_eq!(editor.current_buffer().to_string(), "a b c\nd\n");
+    assert_eq!(editor.cursor().column, 3);
+}
+
+#[test]
+fn test_join_count_past_end() {
+    let mut editor = editor_with("a\nb\nc", 1, 0);
+    assert!(editor.join_lines(10, true));
+
+    assert_eq!(editor.current_buffer().to_string(), "a\nb c");
+}
+
+#[test]
+fn test_join_on_last_line_does_nothing() {
+    let mut editor = editor_with("a\nb\n", 1, 0);
+    assert!(!editor.join_lines(1, true));
+
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\n");
+}
+
+#[test]
+fn test_join_space_rules() {
+    // 当前行以空白结尾时不再加空格
+    let mut editor = editor_with("a \n  b\n", 0, 0);
+    editor.join_lines(1, true);
+    assert_eq!(editor.current_buffer().to_string(), "a b\n");
+
+    // 下一行以 ) 开头
+    let mut editor = editor_with("f(x\n  )\n", 0, 0);
+    editor.join_lines(1, true);
+    assert_eq!(editor.current_buffer().to_string(), "f(x)\n");
+
+    // 空行
+    let mut editor = editor_with("\n  a\nb\n   \n", 0, 0);
+    editor.join_lines(4, true);
+    assert_eq!(editor.current_buffer().to_string(), "a b\n");
+}
+
+#[test]
+fn test_join_crlf() {
+    let mut editor = editor_with("a\r\nb\r\n", 0, 0);
+    editor.join_lines(1, true);
+
+    assert_eq!(editor.current_buffer().to_string(), "a b\r\n");
+}
+
+#[test]
+fn test_join_line_range() {
+    let mut editor = editor_with("a\nb\nc\nd\n", 3, 0);
+    assert!(editor.join_line_range(1, 2, false));
+
+    assert_eq!(editor.current_buffer().to_string(), "a\nbc\nd\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 1));
+}
+
+#[test]
+fn test_join_is_single_undo() {
+    let mut editor = editor_with("a\nb\nc\n", 0, 0);
+    editor.join_lines(3, true);
+    editor.undo();
+
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\n");
+}
@@ -0,0 +1,341 @@
+//! 按键序列解析与操作符执行单元测试
+//!
+//! 对应源文件: src/keymap.rs, src/editor.rs (execute_operator)
+
+use aivim_core::editor::Editor;
+use aivim_core::keymap::{KeyParser, NormalCommand, Operator, OperatorCommand, OperatorTarget, ParseResult};
+use aivim_core::motion::Motion;
+use aivim_core::text_object::TextObject;
+use aivim_core::Mode;
+use super::common::editor_with;
+
+/// 依次输入按键，返回最后一个按键的解析结果
+fn feed_keys(parser: &mut KeyParser, keys: &str) -> ParseResult {
+    let mut result = ParseResult::Invalid;
+    for key in keys.chars() {
+        result = parser.feed(key);
+    }
+    result
+}
+
+fn parse(keys: &str) -> ParseResult {
+    feed_keys(&mut KeyParser::new(), keys)
+}
+
+fn operator(operator: Operator, target: OperatorTarget, count: usize, register: Option<char>) -> ParseResult {
+    ParseResult::Complete(NormalCommand::Operator(OperatorCommand {
+        operator,
+        target,
+        count,
+        register,
+    }))
+}
+
+/// 解析按键序列并在编辑器上执行操作符
+fn run(editor: &mut Editor, keys: &str) -> Option<String> {
+    match parse(keys) {
+        ParseResult::Complete(NormalCommand::Operator(command)) => editor.execute_operator(command),
+        other => panic!("{:?} 不是操作符命令: {:?}", keys, other),
+    }
+}
+
+// ==================== 解析器测试 ====================
+
+#[test]
+fn test_parse_motion_with_count() {
+    assert_eq!(
+        parse("3w"),
+        ParseResult::Complete(NormalCommand::Motion { motion: Motion::WordForward, count: 3 })
+    );
+    assert_eq!(
+        parse("gg"),
+        ParseResult::Complete(NormalCommand::Motion { motion: Motion::DocumentStart, count: 1 })
+    );
+}
+
+#[test]
+fn test_parse_g_prefixed_key() {
+    assert_eq!(parse("3gJ"), ParseResult::Complete(NormalCommand::G { key: 'J', count: 3 }));
+    assert_eq!(parse("2g-"), ParseResult::Complete(NormalCommand::G { key: '-', count: 2 }));
+    // 操作符之后只接受 gg
+    assert_eq!(parse("dgJ"), ParseResult::Invalid);
+}
+
+#[test]
+fn test_parse_zero_is_motion_or_count() {
+    assert_eq!(
+        parse("0"),
+        ParseResult::Complete(NormalCommand::Motion { motion: Motion::LineStart, count: 1 })
+    );
+    assert_eq!(
+        parse("10j"),
+        ParseResult::Complete(NormalCommand::Motion { motion: Motion::Down, count: 10 })
+    );
+}
+
+#[test]
+fn test_parse_operator_motion() {
+    assert_eq!(parse("dw"), operator(Operator::Delete, OperatorTarget::Motion(Motion::WordForward), 1, None));
+    assert_eq!(parse("y$"), operator(Operator::Yank, OperatorTarget::Motion(Motion::LineEnd), 1, None));
+    assert_eq!(parse(">j"), operator(Operator::Indent, OperatorTarget::Motion(Motion::Down), 1, None));
+    assert_eq!(parse("dgg"), operator(Operator::Delete, OperatorTarget::Motion(Motion::DocumentStart), 1, None));
+    assert_eq!(parse("dgE"), operator(Operator::Delete, OperatorTarget::Motion(Motion::WORDEndBackward), 1, None));
+}
+
+#[test]
+fn test_parse_to_column() {
+    // 计数是目标列，移动只执行一次
+    assert_eq!(parse("12|"), ParseResult::Complete(NormalCommand::Motion { motion: Motion::ToColumn(12), count: 1 }));
+    assert_eq!(parse("|"), ParseResult::Complete(NormalCommand::Motion { motion: Motion::ToColumn(1), count: 1 }));
+    assert_eq!(parse("d5|"), operator(Operator::Delete, OperatorTarget::Motion(Motion::ToColumn(5)), 1, None));
+    assert_eq!(parse("2d3|"), operator(Operator::Delete, OperatorTarget::Motion(Motion::ToColumn(6)), 1, None));
+}
+
+#[test]
+fn test_parse_to_line() {
+    // {count}G / {count}gg 的计数是目标行，没有计数时移到文件结尾 / 开头
+    assert_eq!(parse("5G"), ParseResult::Complete(NormalCommand::Motion { motion: Motion::ToLine(5), count: 1 }));
+    assert_eq!(parse("2gg"), ParseResult::Complete(NormalCommand::Motion { motion: Motion::ToLine(2), count: 1 }));
+    assert_eq!(parse("G"), ParseResult::Complete(NormalCommand::Motion { motion: Motion::DocumentEnd, count: 1 }));
+    assert_eq!(parse("d3G"), operator(Operator::Delete, OperatorTarget::Motion(Motion::ToLine(3)), 1, None));
+    assert_eq!(parse("3dgg"), operator(Operator::Delete, OperatorTarget::Motion(Motion::ToLine(3)), 1, None));
+}
+
+#[test]
+fn test_count_g_goes_to_line() {
+    let mut editor = editor_with("one
+  two
+three
+four
+five
+six
+", 0, 0);
+    assert!(editor.run_script(&["5G"]).is_ok());
+    assert_eq!(editor.cursor().line, 4);
+    assert!(editor.run_script(&["2gg"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 2));
+    // 超过最后一行时移到最后一行
+    assert!(editor.run_script(&["100G"]).is_ok());
+    assert_eq!(editor.cursor().line, 5);
+
+    assert!(editor.run_script(&["gg", "d3G"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "four
+five
+six
+");
+    assert!(editor.run_script(&["G", "2dgg"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "four
+");
+}
+
+#[test]
+fn test_parse_g_line_motions() {
+    assert_eq!(parse("g_"), ParseResult::Complete(NormalCommand::Motion { motion: Motion::LastNonBlank, count: 1 }));
+    assert_eq!(parse("dg$"), operator(Operator::Delete, OperatorTarget::Motion(Motion::ScreenLineEnd), 1, None));
+    assert_eq!(parse("yg0"), operator(Operator::Yank, OperatorTarget::Motion(Motion::ScreenLineStart), 1, None));
+}
+
+#[test]
+fn test_parse_operator_search() {
+    let command = OperatorCommand {
+        operator: Operator::Delete,
+        target: OperatorTarget::Motion(Motion::SearchNext),
+        count: 2,
+        register: Some('a'),
+    };
+    assert_eq!(
+        parse("\"a2d/"),
+        ParseResult::Complete(NormalCommand::SearchOperator { command, backward: false })
+    );
+    assert!(matches!(parse("c?"), ParseResult::Complete(NormalCommand::SearchOperator { backward: true, .. })));
+    assert_eq!(parse("yN"), operator(Operator::Yank, OperatorTarget::Motion(Motion::SearchPrev), 1, None));
+    // 包围没有搜索目标
+    assert_eq!(parse("ys/"), ParseResult::Invalid);
+}
+
+#[test]
+fn test_parse_word_end_backward() {
+    assert_eq!(
+        parse("2ge"),
+        ParseResult::Complete(NormalCommand::Motion { motion: Motion::WordEndBackward, count: 2 })
+    );
+    assert_eq!(parse("gE"), ParseResult::Complete(NormalCommand::Motion { motion: Motion::WORDEndBackward, count: 1 }));
+}
+
+#[test]
+fn test_parse_counts_multiply() {
+    assert_eq!(parse("2d3w"), operator(Operator::Delete, OperatorTarget::Motion(Motion::WordForward), 6, None));
+    assert_eq!(parse("d0"), operator(Operator::Delete, OperatorTarget::Motion(Motion::LineStart), 1, None));
+}
+
+#[test]
+fn test_parse_register_and_line_operator() {
+    assert_eq!(parse("\"a3yy"), operator(Operator::Yank, OperatorTarget::Line, 3, Some('a')));
+    assert_eq!(parse("<<"), operator(Operator::Outdent, OperatorTarget::Line, 1, None));
+}
+
+#[test]
+fn test_parse_text_object() {
+    assert_eq!(parse("ciw"), operator(Operator::Change, OperatorTarget::TextObject(TextObject::InnerWord), 1, None));
+    assert_eq!(parse("dap"), operator(Operator::Delete, OperatorTarget::TextObject(TextObject::AroundParagraph), 1, None));
+    assert_eq!(parse("yic"), operator(Operator::Yank, OperatorTarget::TextObject(TextObject::InnerClass), 1, None));
+}
+
+#[test]
+fn test_parse_other_key_keeps_count_and_register() {
+    assert_eq!(
+        parse("\"b2p"),
+        ParseResult::Complete(NormalCommand::Key { key: 'p', count: 2, register: Some('b') })
+    );
+}
+
+#[test]
+fn test_parse_invalid_sequence_resets() {
+    let mut parser = KeyParser::new();
+    assert_eq!(feed_keys(&mut parser, "d"), ParseResult::Pending);
+    assert!(parser.is_pending());
+    assert_eq!(parser.operator(), Some(Operator::Delete));
+
+    assert_eq!(parser.feed('z'), ParseResult::Invalid);
+    assert!(!parser.is_pending());
+    assert_eq!(parser.operator(), None);
+}
+
+// ==================== 操作符执行测试 ====================
+
+#[test]
+fn test_delete_word_with_count() {
+    let mut editor = editor_with("one two three four\n", 0, 0);
+    assert_eq!(run(&mut editor, "d2w"), Some("one two ".to_string()));
+    assert_eq!(editor.current_buffer().to_string(), "three four\n");
+}
+
+#[test]
+fn test_delete_to_line_end_is_inclusive() {
+    let mut editor = editor_with("hello world\nnext\n", 0, 6);
+    assert_eq!(run(&mut editor, "d$"), Some("world".to_string()));
+    assert_eq!(editor.current_buffer().to_string(), "hello \nnext\n");
+}
+
+#[test]
+fn test_delete_lines_with_motion() {
+    let mut editor = editor_with("a\nb\nc\nd\n", 1, 0);
+    assert_eq!(run(&mut editor, "dj"), Some("b\nc\n".to_string()));
+    assert_eq!(editor.current_buffer().to_string(), "a\nd\n");
+    assert_eq!(editor.cursor().line, 1);
+
+    let reg = editor.register_manager().get('"').unwrap();
+    assert!(reg.linewise);
+}
+
+#[test]
+fn test_delete_last_line_without_newline() {
+    let mut editor = editor_with("a\nb", 1, 0);
+    assert_eq!(run(&mut editor, "dd"), Some("b\n".to_string()));
+    assert_eq!(editor.current_buffer().to_string(), "a");
+}
+
+#[test]
+fn test_yank_lines_to_named_register() {
+    let mut editor = editor_with("a\nb\nc\n", 0, 0);
+    run(&mut editor, "\"a2yy");
+
+    assert_eq!(editor.register_manager().get('a').unwrap().content, "a\nb\n");
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\n");
+}
+
+#[test]
+fn test_black_hole_register() {
+    let mut editor = editor_with("keep drop\n", 0, 5);
+    editor.register_manager_mut().set('"', "old", false);
+
+    run(&mut editor, "\"_dw");
+
+    assert_eq!(editor.current_buffer().to_string(), "keep \n");
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "old");
+}
+
+#[test]
+fn test_black_hole_register_keeps_numbered_registers() {
+    let mut editor = editor_with("a\nb\n", 0, 0);
+    run(&mut editor, "yy");
+    run(&mut editor, "\"_dd");
+
+    assert_eq!(editor.current_buffer().to_string(), "b\n");
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "a\n");
+    assert_eq!(editor.register_manager().get('0').unwrap().content, "a\n");
+    assert!(editor.register_manager().get('1').unwrap().is_empty());
+}
+
+#[test]
+fn test_uppercase_register_appends_on_yank() {
+    let mut editor = editor_with("a\nb\nc\n", 0, 0);
+    run(&mut editor, "\"ayy");
+    editor.cursor_mut().line = 2;
+    run(&mut editor, "\"Ayy");
+
+    let register = editor.register_manager().get('a').unwrap();
+    assert_eq!(register.content, "a\nc\n");
+    assert!(register.linewise);
+    // 无名寄存器指向追加后的完整内容
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "a\nc\n");
+
+    editor.paste(Some('A'), false);
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\na\nc\n");
+}
+
+#[test]
+fn test_change_word_enters_insert_mode() {
+    let mut editor = editor_with("foo bar\n", 0, 0);
+    assert_eq!(run(&mut editor, "cw"), Some("foo".to_string()));
+
+    // cw 不删除单词后的空格
+    assert_eq!(editor.current_buffer().to_string(), " bar\n");
+    assert_eq!(editor.mode(), Mode::Insert);
+}
+
+#[test]
+fn test_change_line_keeps_empty_line() {
+    let mut editor = editor_with("a\nb\nc\n", 1, 0);
+    run(&mut editor, "cc");
+
+    assert_eq!(editor.current_buffer().to_string(), "a\n\nc\n");
+    assert_eq!(editor.cursor().line, 1);
+    assert_eq!(editor.mode(), Mode::Insert);
+}
+
+#[test]
+fn test_indent_and_outdent_lines() {
+    let mut editor = editor_with("a\n\nb\n", 0, 0);
+    run(&mut editor, ">2j");
+    assert_eq!(editor.current_buffer().to_string(), "    a\n\n    b\n");
+
+    run(&mut editor, "<<");
+    assert_eq!(editor.current_buffer().to_string(), "a\n\n    b\n");
+}
+
+#[test]
+fn test_operator_with_text_object() {
+    let mut editor = editor_with("hello world\n", 0, 7);
+    assert_eq!(run(&mut editor, "yiw"), Some("world".to_string()));
+    assert_eq!(editor.cursor().column, 6);
+}
+
+#[test]
+fn test_operator_is_undoable() {
+    let mut editor = editor_with("one two\n", 0, 0);
+    run(&mut editor, "dw");
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "one two\n");
+}
+
+#[test]
+fn test_delete_to_column_and_last_non_blank() {
+    let mut editor = editor_with("abcdefgh  \n", 0, 6);
+    run(&mut editor, "d3|");
+    assert_eq!(editor.current_buffer().to_string(), "abgh  \n");
+
+    let mut editor = editor_with("abcdefgh  \n", 0, 2);
+    run(&mut editor, "dg_");
+    assert_eq!(editor.current_buffer().to_string(), "ab  \n");
+}
@@ -0,0 +1,158 @@
+//! 行尾符单元测试
+//!
+//! 对应源文件: src/line_ending.rs, src/buffer.rs (文件格式的读写), src/editor.rs (:fixlineendings, :set fileformat)
+
+use aivim_core::editor::{Editor, PendingConfirmation};
+use aivim_core::line_ending::{self, LineEnding, LineEndingStats};
+use ropey::Rope;
+use std::fs;
+use std::path::PathBuf;
+use super::common::{editor_with, editor_with_file};
+
+// ==================== 统计测试 ====================
+
+#[test]
+fn test_scan_counts_line_endings() {
+    let stats = LineEndingStats::scan(&Rope::from_str("a\r\nb\nc\r\nd"));
+    assert_eq!(stats, LineEndingStats { lf: 1, crlf: 2 });
+    assert!(stats.is_mixed());
+    assert_eq!(stats.dominant(), LineEnding::Crlf);
+    assert_eq!(stats.lines_to_convert(LineEnding::Crlf), 1);
+    assert_eq!(stats.lines_to_convert(LineEnding::Lf), 2);
+}
+
+#[test]
+fn test_consistent_line_endings_not_mixed() {
+    assert!(!LineEndingStats::scan(&Rope::from_str("a\nb\n")).is_mixed());
+    assert!(!LineEndingStats::scan(&Rope::from_str("a\r\nb\r\n")).is_mixed());
+    // 数量相同时取 LF
+    assert_eq!(LineEndingStats::default().dominant(), LineEnding::Lf);
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(line_ending::normalize("a\r\nb\nc", LineEnding::Lf), "a\nb\nc");
+    assert_eq!(line_ending::normalize("a\r\nb\n", LineEnding::Crlf), "a\r\nb\r\n");
+    assert_eq!(LineEnding::parse("DOS"), Some(LineEnding::Crlf));
+    assert_eq!(LineEnding::parse("mac"), None);
+}
+
+// ==================== :fixlineendings 测试 ====================
+
+#[test]
+fn test_fixlineendings_previews_before_converting() {
+    let mut editor = editor_with("a\nb\r\nc\n", 0, 0);
+    editor.execute_command("fixlineendings").unwrap();
+
+    assert_eq!(
+        editor.pending_confirmation(),
+        Some(&PendingConfirmation::FixLineEndings { target: LineEnding::Lf, lines: 1 })
+    );
+    assert_eq!(editor.message(), Some("Convert 1 line(s) to LF? (y/n)"));
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\r\nc\n");
+
+    editor.resolve_confirmation(true).unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\n");
+    assert!(!editor.line_ending_stats().is_mixed());
+}
+
+#[test]
+fn test_fixlineendings_bang_with_target() {
+    let mut editor = editor_with("a\nb\r\nc\n", 0, 0);
+    editor.execute_command("fixlineendings! crlf").unwrap();
+
+    assert!(editor.pending_confirmation().is_none());
+    assert_eq!(editor.current_buffer().to_string(), "a\r\nb\r\nc\r\n");
+    assert_eq!(editor.message(), Some("Converted 2 line(s) to CRLF"));
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\r\nc\n");
+}
+
+#[test]
+fn test_fixlineendings_consistent_file() {
+    let mut editor = editor_with("a\nb\n", 0, 0);
+    editor.execute_command("fixlineendings").unwrap();
+
+    assert!(editor.pending_confirmation().is_none());
+    assert_eq!(editor.message(), Some("All lines already use LF"));
+    assert!(editor.execute_command("fixlineendings mac").is_err());
+}
+
+// ==================== 文件格式测试 ====================
+
+#[test]
+fn test_detect_file_format() {
+    let (format, text) = line_ending::detect("a\r\nb\r\n");
+    assert_eq!((format, text.as_ref()), (LineEnding::Crlf, "a\nb\n"));
+    // 混合行尾按 unix 格式原样保留
+    let (format, text) = line_ending::detect("a\r\nb\n");
+    assert_eq!((format, text.as_ref()), (LineEnding::Lf, "a\r\nb\n"));
+    assert_eq!(line_ending::detect("abc").0, LineEnding::Lf);
+    assert_eq!(line_ending::detect("").0, LineEnding::Lf);
+}
+
+#[test]
+fn test_crlf_file_preserved_on_save() {
+    let (mut editor, path) = editor_with_file("crlf", "one\r\ntwo\r\n");
+    assert_eq!(editor.current_buffer().line_ending(), LineEnding::Crlf);
+    assert_eq!(editor.current_buffer().to_string(), "one\ntwo\n");
+    assert!(!editor.line_ending_stats().is_mixed());
+
+    editor.current_buffer_mut().insert(0, "zero\n");
+    editor.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "zero\r\none\r\ntwo\r\n");
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_missing_final_newline_preserved_on_save() {
+    let (mut editor, path) = editor_with_file("noeol", "a\nb");
+    assert!(!editor.current_buffer().has_final_newline());
+    editor.current_buffer_mut().insert(0, "x");
+    editor.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "xa\nb");
+    let _ = fs::remove_file(&path);
+
+    // 新文件写入时补上换行符
+    let path = PathBuf::from("/tmp/aivim_fileformat_new.txt");
+    let _ = fs::remove_file(&path);
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.current_buffer_mut().insert(0, "new");
+    editor.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_set_fileformat_converts_on_save() {
+    let (mut editor, path) = editor_with_file("setff", "a\nb\n");
+    editor.execute_command("set ff").unwrap();
+    assert_eq!(editor.message(), Some("fileformat=unix"));
+
+    editor.execute_command("set fileformat=dos").unwrap();
+    assert_eq!(editor.message(), Some("fileformat=dos"));
+    assert!(editor.current_buffer().is_modified());
+    editor.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\r\nb\r\n");
+
+    editor.execute_command("set ff=unix").unwrap();
+    editor.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+    assert!(editor.execute_command("set ff=mac").is_err());
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_fixlineendings_on_dos_buffer_changes_format() {
+    let (mut editor, path) = editor_with_file("fixdos", "a\r\nb\r\n");
+    editor.execute_command("fixlineendings crlf").unwrap();
+    assert_eq!(editor.message(), Some("All lines already use CRLF"));
+
+    editor.execute_command("fixlineendings! lf").unwrap();
+    assert_eq!(editor.current_buffer().line_ending(), LineEnding::Lf);
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\n");
+    editor.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+    let _ = fs::remove_file(&path);
+}
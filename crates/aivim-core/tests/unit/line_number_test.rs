@@ -2,7 +2,7 @@
 //!
 //! 对应源文件: src/editor.rs (EditorOptions)
 
-use aivim_core::editor::{Editor, EditorOptions};
+use aivim_core::editor::Editor;
 
 #[test]
 fn test_default_options() {
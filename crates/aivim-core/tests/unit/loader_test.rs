@@ -0,0 +1,130 @@
+//! 后台读入大文件单元测试
+//!
+//! 对应源文件: src/loader.rs, src/editor.rs (:e)
+
+use aivim_core::buffer::BufferId;
+use aivim_core::editor::Editor;
+use aivim_core::loader::{spinner, FileLoad};
+use aivim_core::waker::Waker;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("aivim_loader_{}_{}.txt", name, std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// 等待后台读入结束
+fn wait_for_load(editor: &mut Editor) {
+    let start = Instant::now();
+    while editor.file_load().is_some() {
+        editor.poll_file_load(Instant::now());
+        assert!(start.elapsed() < Duration::from_secs(5), "load did not finish");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_spinner_frames_wrap() {
+    assert_eq!(spinner(0, true), "|");
+    assert_eq!(spinner(4, true), "|");
+    assert_eq!(spinner(1, false), "⠙");
+    assert_eq!(spinner(10, false), spinner(0, false));
+}
+
+#[test]
+fn test_file_load_reports_progress_and_buffer() {
+    let path = write_temp("direct", "one\ntwo\n");
+    let wakes = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&wakes);
+    let waker = Waker::new(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    let start = Instant::now();
+    let mut load = FileLoad::start(&path, BufferId::new(7), 8, start, waker);
+    assert_eq!(load.percent(), 0);
+    assert_eq!(load.buffer(), BufferId::new(7));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let buffer = loop {
+        if let (_, Some(result)) = load.poll(start) {
+            break result.unwrap();
+        }
+        assert!(Instant::now() < deadline, "load did not finish");
+        std::thread::sleep(Duration::from_millis(5));
+    };
+    assert_eq!(buffer.id(), BufferId::new(7));
+    assert_eq!(buffer.len_lines(), 3);
+    assert_eq!(buffer.line(1).unwrap().to_string(), "two\n");
+    assert_eq!(buffer.file_path(), Some(path.as_path()));
+    assert!(!buffer.is_modified());
+    // 至少唤醒一次进度、一次结束
+    assert!(wakes.load(Ordering::SeqCst) >= 2);
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_file_load_spinner_advances_with_time() {
+    let path = write_temp("spinner", "x\n");
+    let start = Instant::now();
+    let mut load = FileLoad::start(&path, BufferId::new(1), 2, start, Waker::default());
+    let (changed, _) = load.poll(start + Duration::from_millis(250));
+    assert!(changed);
+    assert_eq!(load.frame(), 2);
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_file_load_missing_file_fails() {
+    let path = Path::new("/nonexistent/aivim_loader_missing.txt");
+    let start = Instant::now();
+    let mut load = FileLoad::start(path, BufferId::new(1), 10, start, Waker::default());
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let result = loop {
+        if let (_, Some(result)) = load.poll(start) {
+            break result;
+        }
+        assert!(Instant::now() < deadline, "load did not finish");
+        std::thread::sleep(Duration::from_millis(5));
+    };
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_edit_large_file_loads_in_background() {
+    let path = write_temp("edit", "first\nsecond\n");
+    let mut editor = Editor::new();
+    editor.set_async_load_size(4);
+    let previous = editor.current_buffer().id();
+    editor.execute_command(&format!("e {}", path.display())).unwrap();
+
+    // 读完之前仍然在原来的缓冲区中，并且可以编辑
+    assert!(editor.file_load().is_some());
+    assert_eq!(editor.current_buffer().id(), previous);
+    assert_eq!(editor.message(), Some(format!("Loading {}...", path.display()).as_str()));
+    assert!(editor.run_script(&["ihello<Esc>"]).is_ok());
+    assert_eq!(editor.current_buffer().line(0).unwrap().to_string(), "hello");
+
+    wait_for_load(&mut editor);
+    assert_ne!(editor.current_buffer().id(), previous);
+    assert_eq!(editor.current_buffer().file_path(), Some(path.as_path()));
+    assert_eq!(editor.current_buffer().line(1).unwrap().to_string(), "second\n");
+    assert_eq!(editor.message(), Some(format!("Opened {}", path.display()).as_str()));
+    assert_eq!(editor.buffer_count(), 2);
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_edit_small_file_loads_immediately() {
+    let path = write_temp("small", "tiny\n");
+    let mut editor = Editor::new();
+    editor.execute_command(&format!("e {}", path.display())).unwrap();
+    assert!(editor.file_load().is_none());
+    assert_eq!(editor.current_buffer().file_path(), Some(path.as_path()));
+    assert!(!editor.poll_file_load(Instant::now()));
+    fs::remove_file(path).ok();
+}
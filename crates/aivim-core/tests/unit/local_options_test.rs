@@ -0,0 +1,134 @@
+//! 局部选项单元测试
+//!
+//! 对应源文件: src/local_options.rs, src/editor.rs (:setlocal、:setglobal、窗口和缓冲区中生效的选项)
+
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::EditorOptions;
+use aivim_core::local_options::{self, BufferOptions, LocalOption, WindowOptions};
+use super::common::editor_with;
+
+#[test]
+fn test_parse_local_option() {
+    assert_eq!(LocalOption::parse("nu"), Some(LocalOption::Number));
+    assert_eq!(LocalOption::parse("nonumber"), Some(LocalOption::Number));
+    assert_eq!(LocalOption::parse("nowrap"), Some(LocalOption::Wrap));
+    assert_eq!(LocalOption::parse("ts=8"), Some(LocalOption::TabStop));
+    assert_eq!(LocalOption::parse("noet"), Some(LocalOption::ExpandTab));
+    // 只有全局值的选项
+    assert_eq!(LocalOption::parse("paste"), None);
+    assert_eq!(LocalOption::parse("tm=500"), None);
+
+    assert!(LocalOption::Number.is_window_local());
+    assert!(!LocalOption::ShiftWidth.is_window_local());
+}
+
+#[test]
+fn test_effective_options() {
+    let global = EditorOptions::default();
+    let window = WindowOptions { number: Some(true), wrap: Some(false), ..WindowOptions::default() };
+    let buffer = BufferOptions { tabstop: Some(2), ..BufferOptions::default() };
+    let options = local_options::effective(&global, &window, &buffer);
+    assert!(options.number);
+    assert!(!options.wrap);
+    assert_eq!(options.tabstop, 2);
+    // 没有局部值的选项使用全局值
+    assert_eq!(options.relativenumber, global.relativenumber);
+    assert_eq!(options.shiftwidth, global.shiftwidth);
+}
+
+#[test]
+fn test_setlocal_number_only_in_current_window() {
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    let original = editor.windows().current_id();
+    editor.execute_command("vsplit").unwrap();
+    let new = editor.windows().current_id();
+
+    editor.execute_command("setlocal nu").unwrap();
+    assert!(editor.window_options(new).number);
+    assert!(!editor.window_options(original).number);
+    // 全局值不变
+    assert!(!editor.options().number);
+}
+
+#[test]
+fn test_split_copies_window_local_options() {
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    editor.execute_command("setl nowrap").unwrap();
+    let original = editor.windows().current_id();
+    editor.execute_command("split").unwrap();
+    let new = editor.windows().current_id();
+    assert!(!editor.window_options(new).wrap);
+    assert!(!editor.window_options(original).wrap);
+}
+
+#[test]
+fn test_set_overrides_local_value_of_current_window() {
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    let original = editor.windows().current_id();
+    editor.execute_command("setlocal nu").unwrap();
+    editor.execute_command("vsplit").unwrap();
+    let new = editor.windows().current_id();
+
+    // :set 设置全局值并清除当前窗口的局部值，其他窗口的局部值保持不变
+    editor.execute_command("set nonu").unwrap();
+    assert!(!editor.options().number);
+    assert!(!editor.window_options(new).number);
+    assert!(editor.window_options(original).number);
+}
+
+#[test]
+fn test_setglobal_keeps_local_value() {
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    editor.execute_command("setlocal nowrap").unwrap();
+    editor.execute_command("setglobal wrap").unwrap();
+    assert!(editor.options().wrap);
+    assert!(!editor.current_options().wrap);
+}
+
+#[test]
+fn test_setlocal_tabstop_is_buffer_local() {
+    let mut editor = editor_with("\tx\n", 0, 0);
+    editor.execute_command("setlocal ts=8").unwrap();
+    assert_eq!(editor.current_options().tabstop, 8);
+    assert_eq!(editor.display_column(0, 1), 8);
+
+    // 同一缓冲区的其他窗口也使用局部值，其他缓冲区使用全局值
+    editor.execute_command("vsplit").unwrap();
+    assert_eq!(editor.current_options().tabstop, 8);
+    editor.execute_command("new").unwrap();
+    assert_eq!(editor.current_options().tabstop, editor.options().tabstop);
+}
+
+#[test]
+fn test_setlocal_shiftwidth_used_by_indent() {
+    let mut editor = editor_with("x\n", 0, 0);
+    editor.execute_command("setlocal sw=2").unwrap();
+    editor.shift_lines(0, 0, true);
+    assert_eq!(editor.get_line_text(0).unwrap(), "  x");
+}
+
+#[test]
+fn test_setlocal_wrap_affects_screen_rows() {
+    let mut editor = editor_with(&format!("{}\n", "a".repeat(30)), 0, 0);
+    assert_eq!(editor.screen_rows(0, 10), 3);
+    editor.execute_command("setlocal nowrap").unwrap();
+    assert_eq!(editor.screen_rows(0, 10), 1);
+    *editor.cursor_mut() = Cursor::new(0, 25);
+    assert_eq!(editor.cursor_screen_position(10, 0), Some((9, 0)));
+}
+
+#[test]
+fn test_setlocal_invalid_value_keeps_global() {
+    let mut editor = editor_with("x\n", 0, 0);
+    let tabstop = editor.options().tabstop;
+    assert!(editor.execute_command("setlocal ts=0").is_err());
+    assert_eq!(editor.options().tabstop, tabstop);
+    assert_eq!(editor.current_options().tabstop, tabstop);
+}
+
+#[test]
+fn test_setlocal_global_only_option_sets_global() {
+    let mut editor = editor_with("x\n", 0, 0);
+    editor.execute_command("setlocal tm=500").unwrap();
+    assert_eq!(editor.options().timeoutlen, 500);
+}
@@ -0,0 +1,408 @@
+//! 语言服务器单元测试
+//!
+//! 对应源文件: src/lsp.rs, src/lsp_plugin.rs, src/buffer.rs (changedtick)
+//!
+//! 用 Python 脚本模拟语言服务器：为包含 `ERROR` 或 `WARN` 的行发布诊断，`.` 触发补全，
+//! 补全请求总是返回同样的候选项；系统没有 python3 时跳过
+
+use aivim_core::buffer::{Buffer, BufferId};
+use aivim_core::editor::Editor;
+use aivim_core::cursor::Cursor;
+use aivim_core::lsp::{diagnostic_columns, line_severity, Diagnostic, LspManager, Severity};
+use aivim_core::lsp_plugin::LspPlugin;
+use aivim_core::quickfix::{QuickfixKind, QuickfixList};
+use aivim_core::mode::Mode;
+use aivim_core::range::LineRange;
+use aivim_lsp::Position;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const FAKE_SERVER: &str = r#"
+import json, sys
+
+def read():
+    length = None
+    while True:
+        line = sys.stdin.buffer.readline()
+        if not line:
+            return None
+        line = line.strip()
+        if not line:
+            break
+        name, value = line.split(b":", 1)
+        if name.lower() == b"content-length":
+            length = int(value)
+    return json.loads(sys.stdin.buffer.read(length))
+
+def offset(text, position):
+    lines = text.split("\n")
+    return sum(len(line) + 1 for line in lines[:position["line"]]) + position["character"]
+
+def send(message):
+    body = json.dumps(message).encode()
+    sys.stdout.buffer.write(b"Content-Length: %d\r\n\r\n" % len(body) + body)
+    sys.stdout.buffer.flush()
+
+documents = {}
+while True:
+    message = read()
+    if message is None or message.get("method") == "exit":
+        break
+    method = message.get("method")
+    params = message.get("params") or {}
+    if method == "initialize":
+        capabilities = {"completionProvider": {"triggerCharacters": ["."]}, "textDocumentSync": {"change": 2}}
+        send({"jsonrpc": "2.0", "id": message["id"], "result": {"capabilities": capabilities}})
+    elif method == "textDocument/completion":
+        import_edit = {"range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 0}},
+                       "newText": "use std::collections::HashMap;\n"}
+        items = [
+            {"label": "push", "kind": 2, "detail": "fn(&mut self, T)", "sortText": "1",
+             "documentation": {"kind": "markdown", "value": "Appends an element"}},
+            {"label": "pop", "kind": 2, "sortText": "2"},
+            {"label": "HashMap", "kind": 22, "sortText": "0", "insertText": "HashMap::new($0)",
+             "insertTextFormat": 2, "additionalTextEdits": [import_edit]},
+        ]
+        send({"jsonrpc": "2.0", "id": message["id"], "result": {"isIncomplete": False, "items": items}})
+    elif "id" in message:
+        send({"jsonrpc": "2.0", "id": message["id"], "result": None})
+    elif method in ("textDocument/didOpen", "textDocument/didChange"):
+        document = params["textDocument"]
+        # 增量修改按修改前的位置替换
+        text = document.get("text", documents.get(document["uri"], ""))
+        for change in params.get("contentChanges", []):
+            if "range" in change:
+                start, end = offset(text, change["range"]["start"]), offset(text, change["range"]["end"])
+                text = text[:start] + change["text"] + text[end:]
+            else:
+                text = change["text"]
+        documents[document["uri"]] = text
+        diagnostics = []
+        for number, line in enumerate(text.split("\n")):
+            for word, severity in (("ERROR", 1), ("WARN", 2)):
+                column = line.find(word)
+                if column >= 0:
+                    diagnostics.append({
+                        "range": {"start": {"line": number, "character": column},
+                                  "end": {"line": number, "character": column + len(word)}},
+                        "severity": severity, "message": word})
+        send({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics",
+              "params": {"uri": document["uri"], "diagnostics": diagnostics}})
+"#;
+
+/// lsp 插件的语言服务器
+fn lsp(editor: &Editor) -> &LspManager {
+    editor.plugin::<LspPlugin>().unwrap().manager()
+}
+
+fn has_python() -> bool {
+    Command::new("python3").arg("--version").output().is_ok()
+}
+
+/// 在临时目录中写入 `main.rs` 和模拟服务器，返回打开了这个文件、使用模拟服务器作为 Rust 服务器的编辑器
+fn editor_with_server(name: &str, text: &str) -> (Editor, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("aivim_lsp_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("server.py");
+    fs::write(&script, FAKE_SERVER).unwrap();
+    let config = dir.join("config.toml");
+    fs::write(
+        &config,
+        format!("[lsp.rust]\ncommand = \"python3\"\nargs = [\"{}\"]\n", script.display()),
+    )
+    .unwrap();
+    let file = dir.join("main.rs");
+    fs::write(&file, text).unwrap();
+
+    let mut editor = Editor::new();
+    editor.load_lsp_settings(&config).unwrap();
+    editor.open_file(&file).unwrap();
+    (editor, file)
+}
+
+/// 处理服务器的消息，直到当前缓冲区的诊断满足 `done` 或超时
+fn wait_for_diagnostics(editor: &mut Editor, done: impl Fn(&[Diagnostic]) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        editor.poll_plugins(Instant::now());
+        if done(lsp(editor).diagnostics(editor.current_buffer())) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("Timed out waiting for diagnostics");
+}
+
+/// 处理服务器的消息，直到 `done` 成立或超时
+fn wait_until(editor: &mut Editor, done: impl Fn(&Editor) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        editor.poll_plugins(Instant::now());
+        if done(editor) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("Timed out waiting for the language server");
+}
+
+fn labels(editor: &Editor) -> Vec<String> {
+    editor
+        .completion()
+        .map(|menu| menu.items().iter().map(|item| item.label.clone()).collect())
+        .unwrap_or_default()
+}
+
+fn lines_of(diagnostics: &[Diagnostic]) -> Vec<(usize, Severity)> {
+    diagnostics.iter().map(|diagnostic| (diagnostic.start.line, diagnostic.severity)).collect()
+}
+
+#[test]
+fn test_lsp_option_off_by_default() {
+    let (mut editor, _) = editor_with_server("off", "ERROR\n");
+    assert!(!editor.options().lsp);
+    editor.poll_plugins(Instant::now());
+    assert!(!lsp(&editor).is_attached(editor.current_buffer().id()));
+    assert!(lsp(&editor).running_servers().is_empty());
+}
+
+#[test]
+fn test_diagnostics_follow_edits() {
+    if !has_python() {
+        return;
+    }
+    let (mut editor, _) = editor_with_server("edits", "fn main() {\n    ERROR\n}\n");
+    editor.execute_command("set lsp").unwrap();
+    assert!(lsp(&editor).is_attached(editor.current_buffer().id()));
+    assert_eq!(lsp(&editor).running_servers(), vec!["rust"]);
+    wait_for_diagnostics(&mut editor, |diagnostics| !diagnostics.is_empty());
+    assert_eq!(lines_of(lsp(&editor).diagnostics(editor.current_buffer())), vec![(1, Severity::Error)]);
+    assert_eq!(lsp(&editor).diagnostic_counts(editor.current_buffer()), [1, 0, 0, 0]);
+
+    // 缓冲区的修改发送给服务器，服务器重新发布诊断
+    editor.execute_command("1s/fn/WARN fn/").unwrap();
+    wait_for_diagnostics(&mut editor, |diagnostics| diagnostics.len() == 2);
+    assert_eq!(
+        lines_of(lsp(&editor).diagnostics(editor.current_buffer())),
+        vec![(0, Severity::Warning), (1, Severity::Error)]
+    );
+    editor.undo();
+    wait_for_diagnostics(&mut editor, |diagnostics| diagnostics.len() == 1);
+    // 服务器支持增量同步，删除整行只发送修改的区域
+    assert!(editor.run_script(&["ggdd"]).is_ok());
+    wait_for_diagnostics(&mut editor, |diagnostics| lines_of(diagnostics) == vec![(0, Severity::Error)]);
+
+    // :set nolsp 关闭服务器，清除诊断
+    editor.execute_command("set nolsp").unwrap();
+    assert!(lsp(&editor).running_servers().is_empty());
+    assert!(lsp(&editor).diagnostics(editor.current_buffer()).is_empty());
+}
+
+#[test]
+fn test_diagnostics_fill_location_list() {
+    if !has_python() {
+        return;
+    }
+    let (mut editor, file) = editor_with_server("loclist", "WARN\nfn main() {\n    ERROR\n}\n");
+    editor.execute_command("set lsp").unwrap();
+    wait_until(&mut editor, |editor| editor.location_list().is_some_and(|list| list.len() == 2));
+    let list = editor.location_list().unwrap();
+    assert_eq!(list.title(), "Diagnostics");
+    let entry = &list.entries()[1];
+    assert_eq!((entry.path.as_path(), entry.line, entry.column), (file.as_path(), 2, 4));
+    assert_eq!((entry.text.as_str(), entry.kind), ("ERROR", Some(QuickfixKind::Error)));
+
+    // :lnext 跳到下一个诊断，服务器重新发布诊断后保留选中的位置
+    editor.execute_command("lnext").unwrap();
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 4));
+    editor.execute_command("1s/WARN/ok/").unwrap();
+    wait_until(&mut editor, |editor| editor.location_list().is_some_and(|list| list.len() == 1));
+    assert_eq!(editor.location_list().unwrap().current_index(), 0);
+
+    // 其他来源的位置列表不覆盖
+    editor.set_location_list(Some(QuickfixList::new("mine")));
+    editor.execute_command("1s/ok/WARN/").unwrap();
+    wait_for_diagnostics(&mut editor, |diagnostics| diagnostics.len() == 2);
+    assert_eq!(editor.location_list().unwrap().title(), "mine");
+
+    editor.set_location_list(None);
+    editor.execute_command("3s/ERROR/x/").unwrap();
+    wait_until(&mut editor, |editor| editor.location_list().is_some_and(|list| list.len() == 1));
+    // :set nolsp 清除诊断的位置列表
+    editor.execute_command("set nolsp").unwrap();
+    assert!(editor.location_list().is_none());
+}
+
+#[test]
+fn test_deleted_buffer_is_closed() {
+    if !has_python() {
+        return;
+    }
+    let (mut editor, file) = editor_with_server("close", "ERROR\n");
+    editor.execute_command("set lsp").unwrap();
+    wait_for_diagnostics(&mut editor, |diagnostics| !diagnostics.is_empty());
+    let id = editor.current_buffer().id();
+    // 没有对应服务器的文件不发送
+    let notes = file.with_file_name("notes.md");
+    fs::write(&notes, "ERROR\n").unwrap();
+    editor.open_file(&notes).unwrap();
+    editor.poll_plugins(Instant::now());
+    assert!(!lsp(&editor).is_attached(editor.current_buffer().id()));
+
+    editor.execute_command(&format!("bdelete {}", id.as_usize())).unwrap();
+    editor.poll_plugins(Instant::now());
+    assert!(!lsp(&editor).is_attached(id));
+}
+
+#[test]
+fn test_completion_on_trigger_character() {
+    if !has_python() {
+        return;
+    }
+    let (mut editor, _) = editor_with_server("trigger", "fn main() {\n    v\n}\n");
+    editor.execute_command("set lsp").unwrap();
+    let id = editor.current_buffer().id();
+    wait_until(&mut editor, |editor| !lsp(editor).trigger_characters(id).is_empty());
+
+    editor.set_mode(Mode::Insert);
+    *editor.cursor_mut() = Cursor::new(1, 5);
+    editor.insert_char('.');
+    assert!(editor.completion_trigger('.'));
+    wait_until(&mut editor, |editor| editor.completion().is_some());
+    // 按 sortText 排序，带类型图标和文档
+    assert_eq!(labels(&editor), vec!["HashMap", "push", "pop"]);
+    let menu = editor.completion().unwrap();
+    assert_eq!(menu.start_column(), 6);
+    assert_eq!(menu.items()[1].kind, Some("m "));
+    assert_eq!(menu.items()[1].detail.as_deref(), Some("fn(&mut self, T)"));
+    editor.completion_next();
+    assert_eq!(editor.completion_preview().as_deref(), Some("Appends an element"));
+
+    // 继续输入时按前缀过滤服务器的候选项
+    editor.insert_char('p');
+    assert!(editor.refresh_completion());
+    assert_eq!(labels(&editor), vec!["push", "pop"]);
+    editor.insert_char('u');
+    editor.refresh_completion();
+    assert_eq!(labels(&editor), vec!["push"]);
+    assert!(editor.accept_completion());
+    assert_eq!(editor.get_line_text(1).unwrap(), "    v.push");
+    assert_eq!(editor.cursor().column, 10);
+
+    // 其他字符不触发补全
+    editor.insert_char(' ');
+    assert!(!editor.completion_trigger(' '));
+}
+
+#[test]
+fn test_completion_applies_snippet_and_additional_edits() {
+    if !has_python() {
+        return;
+    }
+    let (mut editor, _) = editor_with_server("snippet", "fn main() {\n    let m = Ha\n}\n");
+    editor.execute_command("set lsp").unwrap();
+    editor.set_mode(Mode::Insert);
+    *editor.cursor_mut() = Cursor::new(1, 14);
+    assert!(editor.request_completion());
+    wait_until(&mut editor, |editor| editor.completion().is_some());
+    assert_eq!(labels(&editor), vec!["HashMap"]);
+    assert_eq!(editor.completion().unwrap().items()[0].kind, Some("S "));
+
+    // 代码片段展开后光标在 $0 处，import 插入到文件开头
+    assert!(editor.accept_completion());
+    assert_eq!(editor.get_line_text(0).unwrap(), "use std::collections::HashMap;");
+    assert_eq!(editor.get_line_text(2).unwrap(), "    let m = HashMap::new()");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 25));
+}
+
+#[test]
+fn test_cancelled_completion_is_ignored() {
+    if !has_python() {
+        return;
+    }
+    let (mut editor, _) = editor_with_server("cancel", "fn main() {\n    v\n}\n");
+    editor.execute_command("set lsp").unwrap();
+    editor.set_mode(Mode::Insert);
+    *editor.cursor_mut() = Cursor::new(1, 5);
+    assert!(editor.request_completion());
+    // 回复到达之前离开 Insert 模式，之后的回复不再打开菜单
+    editor.set_mode(Mode::Normal);
+    let deadline = Instant::now() + Duration::from_millis(300);
+    while Instant::now() < deadline {
+        editor.poll_plugins(Instant::now());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(editor.completion().is_none());
+}
+
+#[test]
+fn test_missing_server() {
+    let dir = std::env::temp_dir().join(format!("aivim_lsp_missing_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("config.toml");
+    fs::write(&config, "[lsp.rust]\ncommand = \"aivim-no-such-language-server\"\n").unwrap();
+    fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+    let mut editor = Editor::new();
+    editor.load_lsp_settings(&config).unwrap();
+    editor.open_file(&dir.join("main.rs")).unwrap();
+    editor.execute_command("set lsp").unwrap();
+    editor.poll_plugins(Instant::now());
+    assert!(editor.message().unwrap_or_default().contains("aivim-no-such-language-server"));
+    assert!(lsp(&editor).running_servers().is_empty());
+}
+
+#[test]
+fn test_invalid_lsp_settings() {
+    let path = std::env::temp_dir().join(format!("aivim_lsp_invalid_{}.toml", std::process::id()));
+    fs::write(&path, "[lsp.rust]\nunknown = 1\n").unwrap();
+    let mut editor = Editor::new();
+    let error = editor.load_lsp_settings(&path).unwrap_err();
+    assert!(error.contains("config.toml") || error.contains(&path.display().to_string()), "{}", error);
+}
+
+#[test]
+fn test_diagnostic_columns() {
+    let diagnostic = |start: (usize, usize), end: (usize, usize)| Diagnostic {
+        start: Position::new(start.0, start.1),
+        end: Position::new(end.0, end.1),
+        severity: Severity::Warning,
+        message: String::new(),
+        source: None,
+    };
+    // 列按 UTF-16 编码单元计数
+    assert_eq!(diagnostic_columns(&diagnostic((0, 2), (0, 4)), 0, "😀ab"), Some((1, 3)));
+    assert_eq!(diagnostic_columns(&diagnostic((0, 2), (0, 4)), 1, "😀ab"), None);
+    // 跨行的诊断，中间的行整行都在范围内
+    let multiline = diagnostic((1, 3), (3, 2));
+    assert_eq!(diagnostic_columns(&multiline, 1, "abcdef"), Some((3, 6)));
+    assert_eq!(diagnostic_columns(&multiline, 2, "xyz"), Some((0, 3)));
+    assert_eq!(diagnostic_columns(&multiline, 3, "xyz"), Some((0, 2)));
+    // 空范围至少覆盖一个字符
+    assert_eq!(diagnostic_columns(&diagnostic((0, 1), (0, 1)), 0, "abc"), Some((1, 2)));
+
+    let mut error = diagnostic((3, 0), (3, 1));
+    error.severity = Severity::Error;
+    let diagnostics = [multiline, error];
+    assert_eq!(line_severity(&diagnostics, 1), Some(Severity::Warning));
+    assert_eq!(line_severity(&diagnostics, 2), None);
+    assert_eq!(line_severity(&diagnostics, 3), Some(Severity::Error));
+}
+
+#[test]
+fn test_buffer_changedtick() {
+    let mut buffer = Buffer::new(BufferId::new(1));
+    let tick = buffer.changedtick();
+    buffer.insert(0, "abc\n");
+    assert!(buffer.changedtick() > tick);
+    let tick = buffer.changedtick();
+    let _ = buffer.line_text(0);
+    assert_eq!(buffer.changedtick(), tick);
+    buffer.replace_lines(LineRange::single(0), "xyz\n");
+    assert!(buffer.changedtick() > tick);
+    let tick = buffer.changedtick();
+    buffer.remove_char(0);
+    assert!(buffer.changedtick() > tick);
+}
@@ -0,0 +1,208 @@
+//! 按键映射单元测试
+//!
+//! 对应源文件: src/mapping.rs, src/editor.rs (:map 系列命令)
+
+use aivim_core::buffer::BufferId;
+use aivim_core::editor::Editor;
+use aivim_core::mapping::{
+    format_keys, parse_keys, parse_keys_with_leader, parse_map_command, KeyMappings, MapCommandKind,
+    MapKey, MapLookup, MapMode, MapScope,
+};
+
+fn keys(notation: &str) -> Vec<MapKey> {
+    parse_keys(notation)
+}
+
+// ==================== 按键表示法测试 ====================
+
+#[test]
+fn test_parse_key_notation() {
+    assert_eq!(
+        keys(":w<CR>"),
+        vec![MapKey::Char(':'), MapKey::Char('w'), MapKey::Enter]
+    );
+    assert_eq!(keys("<C-W>j"), vec![MapKey::Ctrl('w'), MapKey::Char('j')]);
+    assert_eq!(keys("<Space><lt>"), vec![MapKey::Char(' '), MapKey::Char('<')]);
+    // 无法识别的 <...> 按字面处理
+    assert_eq!(keys("<foo>").len(), 5);
+    assert_eq!(format_keys(&keys("<c-x><esc> x")), "<C-x><Esc><Space>x");
+    assert_eq!(keys("<C-Space>"), vec![MapKey::Ctrl(' ')]);
+    assert_eq!(format_keys(&[MapKey::Ctrl(' ')]), "<C-Space>");
+}
+
+#[test]
+fn test_parse_navigation_key_notation() {
+    assert_eq!(
+        keys("<PageUp><pagedown><Home><End><S-Tab>"),
+        vec![MapKey::PageUp, MapKey::PageDown, MapKey::Home, MapKey::End, MapKey::BackTab]
+    );
+    assert_eq!(format_keys(&keys("<s-tab><PAGEDOWN>")), "<S-Tab><PageDown>");
+}
+
+#[test]
+fn test_parse_map_command_names() {
+    assert_eq!(
+        parse_map_command("nnoremap"),
+        Some((MapCommandKind::Noremap, vec![MapMode::Normal]))
+    );
+    assert_eq!(
+        parse_map_command("noremap"),
+        Some((MapCommandKind::Noremap, vec![MapMode::Normal, MapMode::Visual]))
+    );
+    assert_eq!(
+        parse_map_command("map!"),
+        Some((MapCommandKind::Map, vec![MapMode::Insert, MapMode::Command]))
+    );
+    assert_eq!(parse_map_command("iunmap"), Some((MapCommandKind::Unmap, vec![MapMode::Insert])));
+    assert_eq!(parse_map_command("imap!"), None);
+    assert_eq!(parse_map_command("nohlsearch"), None);
+    assert_eq!(parse_map_command("vsplit"), None);
+}
+
+// ==================== 映射表测试 ====================
+
+#[test]
+fn test_lookup_prefix_and_match() {
+    let buffer = BufferId::new(0);
+    let mut mappings = KeyMappings::new();
+    mappings.map(MapScope::Global, MapMode::Insert, keys("jk"), keys("<Esc>"), true);
+
+    assert!(matches!(
+        mappings.lookup(MapMode::Insert, buffer, &keys("j")),
+        MapLookup::Prefix { exact: None }
+    ));
+    assert!(matches!(
+        mappings.lookup(MapMode::Insert, buffer, &keys("jk")),
+        MapLookup::Match(m) if m.rhs == vec![MapKey::Esc]
+    ));
+    assert_eq!(mappings.lookup(MapMode::Insert, buffer, &keys("x")), MapLookup::None);
+    // 映射只在指定的模式生效
+    assert_eq!(mappings.lookup(MapMode::Normal, buffer, &keys("j")), MapLookup::None);
+}
+
+#[test]
+fn test_lookup_exact_match_with_longer_mapping() {
+    let buffer = BufferId::new(0);
+    let mut mappings = KeyMappings::new();
+    mappings.map(MapScope::Global, MapMode::Normal, keys(",a"), keys("x"), false);
+    mappings.map(MapScope::Global, MapMode::Normal, keys(",ab"), keys("y"), false);
+
+    assert!(matches!(
+        mappings.lookup(MapMode::Normal, buffer, &keys(",a")),
+        MapLookup::Prefix { exact: Some(m) } if m.rhs == keys("x")
+    ));
+    assert_eq!(
+        mappings.longest_match(MapMode::Normal, buffer, &keys(",abc")).map(|m| m.rhs.clone()),
+        Some(keys("y"))
+    );
+}
+
+#[test]
+fn test_buffer_mapping_overrides_global() {
+    let first = BufferId::new(0);
+    let second = BufferId::new(1);
+    let mut mappings = KeyMappings::new();
+    mappings.map(MapScope::Global, MapMode::Normal, keys("<CR>"), keys("j"), false);
+    mappings.map(MapScope::Buffer(first), MapMode::Normal, keys("<CR>"), keys("gf"), false);
+
+    let rhs = |mappings: &KeyMappings, buffer| match mappings.lookup(MapMode::Normal, buffer, &keys("<CR>")) {
+        MapLookup::Match(m) => m.rhs.clone(),
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(rhs(&mappings, first), keys("gf"));
+    assert_eq!(rhs(&mappings, second), keys("j"));
+
+    mappings.clear_buffer(first);
+    assert_eq!(rhs(&mappings, first), keys("j"));
+}
+
+// ==================== :map 命令测试 ====================
+
+#[test]
+fn test_map_commands() {
+    let mut editor = Editor::new();
+    let buffer = editor.current_buffer_id();
+    editor.execute_command("nnoremap ,w :w now<CR>").unwrap();
+
+    let mapping = editor.mappings().get(MapScope::Global, MapMode::Normal, &keys(",w")).unwrap();
+    assert_eq!(format_keys(&mapping.rhs), ":w<Space>now<CR>");
+    assert!(mapping.noremap);
+    assert!(editor.mappings().get(MapScope::Global, MapMode::Visual, &keys(",w")).is_none());
+
+    editor.execute_command("imap jk <Esc>").unwrap();
+    assert!(matches!(
+        editor.mappings().lookup(MapMode::Insert, buffer, &keys("jk")),
+        MapLookup::Match(m) if !m.noremap
+    ));
+
+    editor.execute_command("nunmap ,w").unwrap();
+    assert!(editor.mappings().get(MapScope::Global, MapMode::Normal, &keys(",w")).is_none());
+    assert_eq!(editor.execute_command("nunmap ,w"), Err("No such mapping: ,w".to_string()));
+}
+
+#[test]
+fn test_buffer_local_map_command() {
+    let mut editor = Editor::new();
+    let first = editor.current_buffer_id();
+    editor.execute_command("nmap <buffer> <CR> gf").unwrap();
+    assert!(editor.mappings().get(MapScope::Buffer(first), MapMode::Normal, &keys("<CR>")).is_some());
+    assert!(editor.mappings().get(MapScope::Global, MapMode::Normal, &keys("<CR>")).is_none());
+
+    editor.execute_command("nmap <CR>").unwrap();
+    assert_eq!(editor.message(), Some("n <CR> @gf"));
+
+    // 新缓冲区看不到其他缓冲区的局部映射
+    editor.execute_command("new").unwrap();
+    editor.execute_command("nmap <CR>").unwrap();
+    assert_eq!(editor.message(), Some("No mapping found"));
+
+    // 删除缓冲区时清除局部映射
+    editor.delete_buffer_force(first).unwrap();
+    assert!(editor.mappings().get(MapScope::Buffer(first), MapMode::Normal, &keys("<CR>")).is_none());
+}
+
+#[test]
+fn test_mapclear() {
+    let mut editor = Editor::new();
+    editor.execute_command("map x y").unwrap();
+    editor.execute_command("imap x y").unwrap();
+    editor.execute_command("mapclear").unwrap();
+
+    let buffer = editor.current_buffer_id();
+    assert!(editor.mappings().list(MapMode::Normal, buffer).is_empty());
+    assert!(editor.mappings().list(MapMode::Visual, buffer).is_empty());
+    assert_eq!(editor.mappings().list(MapMode::Insert, buffer).len(), 1);
+}
+
+// ==================== <Leader> 测试 ====================
+
+#[test]
+fn test_parse_leader_notation() {
+    assert_eq!(keys("<Leader>w"), vec![MapKey::Char('\\'), MapKey::Char('w')]);
+    assert_eq!(
+        parse_keys_with_leader("<leader>w", &keys("<Space>")),
+        vec![MapKey::Char(' '), MapKey::Char('w')]
+    );
+}
+
+#[test]
+fn test_let_mapleader() {
+    let mut editor = Editor::new();
+    editor.execute_command("nmap <leader>a x").unwrap();
+    assert!(editor.mappings().get(MapScope::Global, MapMode::Normal, &keys("\\a")).is_some());
+
+    // 修改 leader 只影响之后定义的映射
+    editor.execute_command("let mapleader = \",\"").unwrap();
+    assert_eq!(editor.map_leader(), &[MapKey::Char(',')]);
+    editor.execute_command("nnoremap <Leader>w :w<CR>").unwrap();
+    assert!(editor.mappings().get(MapScope::Global, MapMode::Normal, &keys(",w")).is_some());
+    assert!(editor.mappings().get(MapScope::Global, MapMode::Normal, &keys("\\a")).is_some());
+
+    editor.execute_command("let mapleader=\"\\<Space>\"").unwrap();
+    assert_eq!(editor.map_leader(), &[MapKey::Char(' ')]);
+    editor.execute_command("let mapleader = ' '").unwrap();
+    assert_eq!(editor.map_leader(), &[MapKey::Char(' ')]);
+
+    assert_eq!(editor.execute_command("let mapleader = \"\""), Err("Invalid value for mapleader: \"\"".to_string()));
+    assert_eq!(editor.execute_command("let foo = 1"), Err("Unknown variable: foo".to_string()));
+}
@@ -0,0 +1,80 @@
+//! 匹配位置索引单元测试
+//!
+//! 对应源文件: src/match_index.rs
+
+use aivim_core::match_index::MatchIndex;
+
+/// 与 `MatchIndex::replace` 相同的修改，作用于按位置排列的数组
+fn replace_vec(positions: &mut Vec<usize>, removed: std::ops::Range<usize>, delta: isize, found: Vec<usize>) {
+    let from = positions.partition_point(|&m| m < removed.start);
+    let to = positions.partition_point(|&m| m < removed.end);
+    for m in &mut positions[to..] {
+        *m = (*m as isize + delta) as usize;
+    }
+    positions.splice(from..to, found);
+}
+
+fn assert_same(index: &MatchIndex, positions: &[usize]) {
+    assert_eq!(index.to_vec(), positions);
+    assert_eq!(index.len(), positions.len());
+    for (idx, &pos) in positions.iter().enumerate() {
+        assert_eq!(index.get(idx), Some(pos));
+        assert_eq!(index.count_before(pos), idx);
+        assert_eq!(index.count_before(pos + 1), idx + 1);
+    }
+    assert_eq!(index.get(positions.len()), None);
+}
+
+#[test]
+fn test_lookup() {
+    let index: MatchIndex = vec![3, 8, 20].into_iter().collect();
+    assert_same(&index, &[3, 8, 20]);
+    assert_eq!(index.count_before(0), 0);
+    assert_eq!(index.count_before(10), 2);
+    assert_eq!(index.count_before(100), 3);
+
+    let empty = MatchIndex::new();
+    assert!(empty.is_empty());
+    assert_eq!(empty.get(0), None);
+    assert_eq!(empty.count_before(5), 0);
+}
+
+#[test]
+fn test_replace() {
+    let mut index: MatchIndex = vec![0, 10, 20, 30].into_iter().collect();
+    // 删除 [10, 20) 中的匹配，之后的匹配左移 5，新的匹配在 12
+    index.replace(10..20, -5, vec![12]);
+    assert_same(&index, &[0, 12, 15, 25]);
+    // 插入文本，之后的匹配右移
+    index.replace(1..1, 3, Vec::new());
+    assert_same(&index, &[0, 15, 18, 28]);
+    // 清空之后重新加入
+    index.replace(0..100, 0, vec![1, 2]);
+    assert_same(&index, &[1, 2]);
+    index.clear();
+    assert!(index.is_empty());
+}
+
+#[test]
+fn test_replace_matches_vec() {
+    // 伪随机的修改序列，结果与数组的做法相同
+    let mut seed = 12345u64;
+    let mut next = |limit: usize| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % limit.max(1)
+    };
+    let mut positions: Vec<usize> = (0..500).map(|i| i * 7).collect();
+    let mut index: MatchIndex = positions.iter().copied().collect();
+    for _ in 0..300 {
+        let end = positions.last().copied().unwrap_or(0) + 10;
+        let start = next(end);
+        let old_end = start + next(30);
+        let inserted = next(30);
+        let delta = inserted as isize - (old_end - start) as isize;
+        let found: Vec<usize> = (start..start + inserted).step_by(4).collect();
+        replace_vec(&mut positions, start..old_end, delta, found.clone());
+        index.replace(start..old_end, delta, found);
+        assert_eq!(index.to_vec(), positions);
+    }
+    assert_same(&index, &positions);
+}
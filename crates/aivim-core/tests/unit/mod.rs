@@ -15,7 +15,73 @@
 //! - editor_delete_test.rs -> src/editor.rs (delete operations)
 //! - buffer_manager_test.rs -> src/editor.rs (buffer management)
 //! - line_number_test.rs -> src/editor.rs (line number options)
+//! - file_ops_test.rs -> src/file_ops.rs (file rename/delete commands)
+//! - completion_test.rs -> src/completion.rs (completion menu)
+//! - keymap_test.rs -> src/keymap.rs (operator + motion parsing)
+//! - picker_test.rs -> src/picker.rs, src/symbols.rs (fuzzy picker, :symbols)
+//! - indent_test.rs -> src/editor.rs (indent operators, shiftwidth)
+//! - tail_test.rs -> src/tail.rs (:tail follow mode)
+//! - autoindent_test.rs -> src/editor.rs (autoindent, o/O)
+//! - line_ending_test.rs -> src/line_ending.rs (行尾符、:fixlineendings)
+//! - job_test.rs -> src/job.rs (后台任务、:jobs、:job kill)
+//! - join_test.rs -> src/editor.rs (J / gJ 合并行)
+//! - mapping_test.rs -> src/mapping.rs (按键映射、:map 系列命令、<buffer> 局部映射)
+//! - replace_mode_test.rs -> src/editor.rs (r 替换字符、R Replace 模式)
+//! - paste_test.rs -> src/paste.rs (粘贴模式、粘贴检测、括号粘贴)
+//! - number_test.rs -> src/number.rs (Ctrl+A / Ctrl+X 数字加减)
+//! - i18n_test.rs -> src/i18n.rs (界面文本本地化)
+//! - fillchars_test.rs -> src/fillchars.rs (界面填充字符)
+//! - undo_tree_test.rs -> src/undo_tree.rs (撤销树, g- / g+, :undolist, undolevels)
+//! - range_test.rs -> src/range.rs (命令行范围, :10,20w, :10,20y)
+//! - diff_test.rs -> src/diff.rs (行级差异, :DiffOrig)
+//! - undo_file_test.rs -> src/undo_file.rs (持久化撤销历史, :set undofile)
+//! - expr_test.rs -> src/expr.rs (表达式寄存器, Ctrl+R, ". 寄存器)
+//! - history_test.rs -> src/history.rs (命令行历史)
+//! - ex_command_test.rs -> src/ex_command.rs (Ex 命令解析)
+//! - shell_test.rs -> src/shell.rs (外部命令)
+//! - config_test.rs -> src/config.rs (配置文件、:source)
+//! - display_test.rs -> src/display.rs (显示宽度、tabstop / expandtab)
+//! - colorscheme_test.rs -> src/colorscheme.rs (:colorscheme 和 :highlight)
+//! - window_test.rs -> src/window.rs (窗口分割和布局)
+//! - tabpage_test.rs -> src/tabpage.rs (标签页)
+//! - finder_test.rs -> src/finder.rs (文件查找和 .gitignore)
+//! - quickfix_test.rs -> src/quickfix.rs (Quickfix 列表和 :grep)
+//! - encoding_test.rs -> src/encoding.rs (文件编码、BOM、:set fileencoding)
+//! - hex_test.rs -> src/hex.rs (二进制文件的十六进制视图)
+//! - file_change_test.rs -> src/buffer.rs, src/watch.rs (外部修改检测、:e!、:checktime、autoread)
+//! - session_test.rs -> src/session.rs (:mksession、会话恢复)
+//! - positions_test.rs -> src/positions.rs (上次光标位置)
+//! - startup_test.rs -> src/editor.rs (启动参数: -R、-、+N、-c)
+//! - script_test.rs -> src/script.rs, src/input.rs (Editor::run_script、按键处理)
+//! - suggestion_test.rs -> src/suggestion.rs (AI 补全建议、Tab 接受、Esc 丢弃)
+//! - chat_test.rs -> src/chat.rs (:AiChat 对话窗口、附带上下文、流式回复)
+//! - ai_edit_test.rs -> src/ai_edit.rs (:AiEdit diff 预览、确认后作为一次修改应用)
+//! - explain_test.rs -> src/explain.rs (:AiExplain / K 解释光标所在的函数或单词)
+//! - git_test.rs -> src/git.rs, src/commit_msg.rs (git diff、:AiCommitMsg 生成提交信息)
+//! - lsp_test.rs -> src/lsp.rs (语言服务器、诊断、:set lsp)
+//! - syntax_test.rs -> src/syntax.rs, src/text_object.rs (tree-sitter 语法树、af/if/ac/ic)
+//! - fold_test.rs -> src/fold.rs, src/editor.rs (折叠、zf/zo/zc/za、foldmethod)
+//! - pairs_test.rs -> src/pairs.rs, src/text_object.rs (autopairs、括号和引号文本对象、ys/ds/cs)
+//! - git_signs_test.rs -> src/git_signs.rs, src/git.rs (与 HEAD 的差异标记、:set gitsigns)
+//! - blame_test.rs -> src/blame.rs, src/git.rs (:GitBlame)
+//! - statusline_test.rs -> src/statusline.rs, src/git_plugin.rs (Git 分支), src/editor.rs (状态栏片段、q / @ 宏)
+//! - confirm_test.rs -> src/editor.rs (确认提示、:q / :qa 退出前保存)
+//! - edit_log_test.rs -> src/edit_log.rs, src/buffer.rs (修改记录), src/search.rs (增量更新匹配)
+//! - match_index_test.rs -> src/match_index.rs (匹配位置的平衡树)
+//! - loader_test.rs -> src/loader.rs, src/editor.rs (后台读入大文件)
+//! - terminal_test.rs -> src/terminal.rs, src/editor.rs (:terminal)
+//! - local_options_test.rs -> src/local_options.rs (:setlocal、窗口和缓冲区局部选项)
+//! - set_option_test.rs -> src/editor.rs (:set name=value、:set name?、:set all)
+//! - scroll_test.rs -> src/editor.rs, src/input.rs (zt / zz / zb、Ctrl+E / Ctrl+Y)
+//! - case_test.rs -> src/keymap.rs, src/editor.rs (gu / gU / g~ 大小写、gq 重排文本)
+//! - multi_cursor_test.rs -> src/multi_cursor.rs, src/editor.rs (Ctrl+N 多光标)
+//! - autocmd_test.rs -> src/autocmd.rs, src/editor.rs (:autocmd、事件、:StripWhitespace)
+//! - plugin_test.rs -> src/plugin.rs, src/editor.rs (插件命令、按键映射、事件和钩子)
+//! - visual_test.rs -> src/visual.rs, src/editor.rs, src/input.rs (Visual 模式、选择区域的操作符、o / O、gv)
+//!
+//! common.rs 是各测试文件共用的辅助函数（`editor_with`、`editor_with_reply`、`init_repo` 等）
 
+pub mod common;
 pub mod motion_test;
 pub mod buffer_test;
 pub mod cursor_test;
@@ -30,3 +96,66 @@ pub mod edit_test;
 pub mod editor_delete_test;
 pub mod buffer_manager_test;
 pub mod line_number_test;
+pub mod file_ops_test;
+pub mod completion_test;
+pub mod keymap_test;
+pub mod picker_test;
+pub mod indent_test;
+pub mod tail_test;
+pub mod autoindent_test;
+pub mod line_ending_test;
+pub mod job_test;
+pub mod join_test;
+pub mod mapping_test;
+pub mod replace_mode_test;
+pub mod paste_test;
+pub mod number_test;
+pub mod i18n_test;
+pub mod fillchars_test;
+pub mod undo_tree_test;
+pub mod range_test;
+pub mod diff_test;
+pub mod undo_file_test;
+pub mod expr_test;
+pub mod history_test;
+pub mod ex_command_test;
+pub mod shell_test;
+pub mod config_test;
+pub mod display_test;
+pub mod colorscheme_test;
+pub mod window_test;
+pub mod tabpage_test;
+pub mod finder_test;
+pub mod quickfix_test;
+pub mod encoding_test;
+pub mod hex_test;
+pub mod file_change_test;
+pub mod session_test;
+pub mod positions_test;
+pub mod startup_test;
+pub mod script_test;
+pub mod suggestion_test;
+pub mod chat_test;
+pub mod ai_edit_test;
+pub mod explain_test;
+pub mod git_test;
+pub mod lsp_test;
+pub mod syntax_test;
+pub mod fold_test;
+pub mod pairs_test;
+pub mod git_signs_test;
+pub mod blame_test;
+pub mod statusline_test;
+pub mod confirm_test;
+pub mod edit_log_test;
+pub mod match_index_test;
+pub mod loader_test;
+pub mod terminal_test;
+pub mod local_options_test;
+pub mod set_option_test;
+pub mod scroll_test;
+pub mod case_test;
+pub mod multi_cursor_test;
+pub mod autocmd_test;
+pub mod plugin_test;
+pub mod visual_test;
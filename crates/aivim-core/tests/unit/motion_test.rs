@@ -1,11 +1,11 @@
 //! Motion 模块单元测试
 //!
 //! 对应源文件: src/motion.rs
-//! 测试范围: 光标移动命令 (h/j/k/l, w/b/e, $/^/0, gg/G, Ctrl+D/U)
+//! 测试范围: 光标移动命令 (h/j/k/l, w/b/e, W/B/E, ge/gE, $/^/0, g_、|、g0/g$, gg/G、{count}G, Ctrl+D/U)，中日韩文字和 emoji 的列
 
 use aivim_core::buffer::{Buffer, BufferId};
 use aivim_core::cursor::Cursor;
-use aivim_core::motion::Motion;
+use aivim_core::motion::{Motion, Viewport};
 
 /// 测试辅助函数：创建包含指定内容的缓冲区
 fn create_buffer(content: &str) -> Buffer {
@@ -138,6 +138,63 @@ fn test_move_first_non_blank_no_indent() {
     assert_eq!(cursor.column, 0); // 已经是第一个非空字符
 }
 
+#[test]
+fn test_last_non_blank() {
+    let buffer = create_buffer("  foo bar  \n   \n");
+    let mut cursor = Cursor::new(0, 0);
+    Motion::LastNonBlank.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 8);
+    // 只有空白的行停在行首
+    let mut cursor = Cursor::new(1, 2);
+    Motion::LastNonBlank.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 0);
+}
+
+#[test]
+fn test_to_column() {
+    let buffer = create_buffer("\tab中文x\n");
+    let mut cursor = Cursor::new(0, 0);
+    Motion::ToColumn(9).execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 1);
+    // 第 12 列在宽字符 '中' 的右半边
+    Motion::ToColumn(12).execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 3);
+    // 超过行尾时停在最后一个字符
+    Motion::ToColumn(100).execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 5);
+    Motion::ToColumn(1).execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 0);
+}
+
+#[test]
+fn test_screen_line_start_and_end_with_wrap() {
+    let buffer = create_buffer(&format!("{}\n", "abcdefghij".repeat(3)));
+    let viewport = Viewport { width: 10, ..Viewport::default() };
+    let mut cursor = Cursor::new(0, 14);
+    Motion::ScreenLineStart.execute_in(&mut cursor, &buffer, &viewport);
+    assert_eq!(cursor.column, 10);
+    Motion::ScreenLineEnd.execute_in(&mut cursor, &buffer, &viewport);
+    assert_eq!(cursor.column, 19);
+    // 最后一个屏幕行不满时停在行尾
+    let buffer = create_buffer("abcdefghijkl\n");
+    let mut cursor = Cursor::new(0, 11);
+    Motion::ScreenLineEnd.execute_in(&mut cursor, &buffer, &viewport);
+    assert_eq!(cursor.column, 11);
+}
+
+#[test]
+fn test_screen_line_start_and_end_without_wrap() {
+    let buffer = create_buffer(&format!("{}\n", "abcdefghij".repeat(3)));
+    let viewport = Viewport { width: 10, wrap: false, ..Viewport::default() };
+    // 光标在第 15 列时窗口显示第 6-15 列
+    let mut cursor = Cursor::new(0, 15);
+    Motion::ScreenLineStart.execute_in(&mut cursor, &buffer, &viewport);
+    assert_eq!(cursor.column, 6);
+    let mut cursor = Cursor::new(0, 3);
+    Motion::ScreenLineEnd.execute_in(&mut cursor, &buffer, &viewport);
+    assert_eq!(cursor.column, 9);
+}
+
 // ==================== 单词移动测试 ====================
 
 #[test]
@@ -257,6 +314,82 @@ fn test_move_word_end_multiple() {
     // assert_eq!(cursor.column, 10); // 'world' 结尾
 }
 
+#[test]
+fn test_word_forward_big_skips_punctuation() {
+    let buffer = create_buffer("foo.bar(x) baz\n");
+    let mut cursor = Cursor::new(0, 0);
+    Motion::WORDForward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 11);
+    // 最后一个 WORD 之后停在最后一个字符
+    Motion::WORDForward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 13);
+}
+
+#[test]
+fn test_word_forward_big_crosses_lines_and_stops_on_empty_line() {
+    let buffer = create_buffer("a.b\n\n  c-d\n");
+    let mut cursor = Cursor::new(0, 1);
+    Motion::WORDForward.execute(&mut cursor, &buffer);
+    assert_eq!((cursor.line, cursor.column), (1, 0));
+    Motion::WORDForward.execute(&mut cursor, &buffer);
+    assert_eq!((cursor.line, cursor.column), (2, 2));
+}
+
+#[test]
+fn test_word_backward_big() {
+    let buffer = create_buffer("x=1 foo.bar\n  baz\n");
+    let mut cursor = Cursor::new(1, 2);
+    Motion::WORDBackward.execute(&mut cursor, &buffer);
+    assert_eq!((cursor.line, cursor.column), (0, 4));
+    // 在 WORD 中间时移到这个 WORD 的开头
+    let mut cursor = Cursor::new(0, 9);
+    Motion::WORDBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 4);
+    Motion::WORDBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 0);
+}
+
+#[test]
+fn test_word_end_big() {
+    let buffer = create_buffer("a.b c,d\n\n  e\n");
+    let mut cursor = Cursor::new(0, 0);
+    Motion::WORDEnd.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 2);
+    Motion::WORDEnd.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 6);
+    // 跳过空行
+    Motion::WORDEnd.execute(&mut cursor, &buffer);
+    assert_eq!((cursor.line, cursor.column), (2, 2));
+}
+
+#[test]
+fn test_word_end_backward() {
+    let buffer = create_buffer("foo.bar baz\n");
+    let mut cursor = Cursor::new(0, 9);
+    Motion::WordEndBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 6);
+    Motion::WordEndBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 3);
+    Motion::WordEndBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 2);
+    // 之前没有单词时停在文件开头
+    Motion::WordEndBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 0);
+}
+
+#[test]
+fn test_word_end_backward_big_crosses_lines() {
+    let buffer = create_buffer("one foo.bar\n\n  baz\n");
+    let mut cursor = Cursor::new(2, 3);
+    Motion::WORDEndBackward.execute(&mut cursor, &buffer);
+    // 空行算一个单词
+    assert_eq!((cursor.line, cursor.column), (1, 0));
+    Motion::WORDEndBackward.execute(&mut cursor, &buffer);
+    assert_eq!((cursor.line, cursor.column), (0, 10));
+    Motion::WORDEndBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 2);
+}
+
 // ==================== 文档移动测试 ====================
 
 #[test]
@@ -277,6 +410,21 @@ fn test_move_document_end() {
     Motion::DocumentEnd.execute(&mut cursor, &buffer);
     assert_eq!(cursor.line, 2);
     assert_eq!(cursor.column, 4); // 'line3' 的 '3'
+
+    // 文件以换行符结尾时停在最后一行文本，不是换行符之后的空行
+    let buffer = create_buffer("line1\nline2\n");
+    Motion::DocumentEnd.execute(&mut cursor, &buffer);
+    assert_eq!((cursor.line, cursor.column), (1, 4));
+}
+
+#[test]
+fn test_move_to_line() {
+    let buffer = create_buffer("line1\n  line2\nline3\n");
+    let mut cursor = Cursor::new(0, 3);
+    Motion::ToLine(2).execute(&mut cursor, &buffer);
+    assert_eq!((cursor.line, cursor.column), (1, 2));
+    Motion::ToLine(99).execute(&mut cursor, &buffer);
+    assert_eq!(cursor.line, 2);
 }
 
 // ==================== 页面滚动测试 ====================
@@ -366,3 +514,72 @@ fn test_move_down_column_adjustment() {
     assert_eq!(cursor.line, 1);
     assert_eq!(cursor.column, 4); // 调整到短行的行尾
 }
+
+// ==================== Unicode 测试 ====================
+
+#[test]
+fn test_move_right_left_over_graphemes() {
+    // é 由 e 和组合符号组成，👍🏽 由 emoji 和肤色修饰符组成
+    let buffer = create_buffer("ae\u{301}👍🏽中b\n");
+    let mut cursor = Cursor::new(0, 0);
+
+    let mut columns = Vec::new();
+    for _ in 0..5 {
+        Motion::Right.execute(&mut cursor, &buffer);
+        columns.push(cursor.column);
+    }
+    assert_eq!(columns, vec![1, 3, 5, 6, 6]);
+
+    let mut columns = Vec::new();
+    for _ in 0..5 {
+        Motion::Left.execute(&mut cursor, &buffer);
+        columns.push(cursor.column);
+    }
+    assert_eq!(columns, vec![5, 3, 1, 0, 0]);
+}
+
+#[test]
+fn test_line_end_on_emoji() {
+    let buffer = create_buffer("ok 👨\u{200d}👩\u{200d}👧\n");
+    let mut cursor = Cursor::new(0, 0);
+    Motion::LineEnd.execute(&mut cursor, &buffer);
+    // 停在家庭 emoji 的开头，而不是它的最后一个字符
+    assert_eq!(cursor.column, 3);
+}
+
+#[test]
+fn test_word_motions_with_cjk() {
+    let buffer = create_buffer("中文 单词 abc\n");
+    let mut cursor = Cursor::new(0, 0);
+
+    Motion::WordForward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 3);
+    Motion::WordForward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 6);
+    Motion::WordEnd.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 8);
+    Motion::WordBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 6);
+    Motion::WordBackward.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 3);
+}
+
+#[test]
+fn test_vertical_motion_keeps_screen_column() {
+    // 第二行的每个字占两列
+    let buffer = create_buffer("abcdefgh\n中文字符\nabcdef\n");
+    let mut cursor = Cursor::new(0, 4);
+    cursor.update_preferred_column(&buffer);
+
+    Motion::Down.execute(&mut cursor, &buffer);
+    // 第 4 屏幕列是 "字"
+    assert_eq!(cursor.column, 2);
+    Motion::Down.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 4);
+
+    // 从宽字符出发，回到相同的屏幕列
+    let mut cursor = Cursor::new(1, 3);
+    cursor.update_preferred_column(&buffer);
+    Motion::Up.execute(&mut cursor, &buffer);
+    assert_eq!(cursor.column, 6);
+}
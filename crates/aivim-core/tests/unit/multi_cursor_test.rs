@@ -0,0 +1,94 @@
+//! 多光标单元测试
+//!
+//! 对应源文件: src/multi_cursor.rs, src/editor.rs (Ctrl+N 添加光标、for_each_cursor), src/input.rs
+
+use aivim_core::editor::Editor;
+use aivim_core::multi_cursor::next_word_match;
+use ropey::Rope;
+use aivim_core::Mode;
+use super::common::editor_with;
+
+fn positions(editor: &Editor) -> Vec<(usize, usize)> {
+    let mut positions: Vec<(usize, usize)> =
+        editor.extra_cursors().iter().map(|cursor| (cursor.line, cursor.column)).collect();
+    positions.push((editor.cursor().line, editor.cursor().column));
+    positions.sort();
+    positions
+}
+
+#[test]
+fn test_next_word_match() {
+    let text = &Rope::from_str("foo foobar foo\nfoo");
+    // 整词匹配，跳过 foobar
+    assert_eq!(next_word_match(text, "foo", 0, &[0]), Some(11));
+    // 到末尾后从头开始，跳过已有光标的位置
+    assert_eq!(next_word_match(text, "foo", 15, &[15]), Some(0));
+    assert_eq!(next_word_match(text, "foo", 15, &[0, 11, 15]), None);
+    assert_eq!(next_word_match(text, "", 0, &[]), None);
+    assert_eq!(next_word_match(text, "foo foobar foo\nfoo!", 0, &[]), None);
+    // 文本末尾的单词，多字节字符按字符下标计算
+    assert_eq!(next_word_match(&Rope::from_str("中 x 中"), "中", 0, &[0]), Some(4));
+}
+
+#[test]
+fn test_ctrl_n_adds_cursor_at_next_occurrence() {
+    let mut editor = editor_with("let x = 1;\nlet y = x + x;\n", 0, 4);
+    assert!(editor.run_script(&["<C-n>"]).is_ok());
+    assert_eq!(positions(&editor), vec![(0, 4), (1, 8)]);
+    // 新光标成为主光标
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 8));
+
+    assert!(editor.run_script(&["<C-n>"]).is_ok());
+    assert_eq!(positions(&editor), vec![(0, 4), (1, 8), (1, 12)]);
+
+    // 所有出现位置都有光标
+    let result = editor.run_script(&["<C-n>"]);
+    assert!(result.steps[0].message.as_deref().is_some_and(|m| m.contains("x")));
+    assert_eq!(editor.extra_cursors().len(), 2);
+}
+
+#[test]
+fn test_insert_at_every_cursor() {
+    let mut editor = editor_with("foo bar\nfoo baz\n", 0, 1);
+    assert!(editor.run_script(&["<C-n>", "ifoo_<Esc>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "foo_foo bar\nfoo_foo baz\n");
+    assert_eq!(editor.mode(), Mode::Normal);
+
+    // 所有光标的输入作为一次撤销
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "foo bar\nfoo baz\n");
+    assert!(editor.extra_cursors().is_empty());
+}
+
+#[test]
+fn test_cursors_on_same_line_shift_after_edit() {
+    let mut editor = editor_with("ab ab ab\n", 0, 0);
+    assert!(editor.run_script(&["<C-n><C-n>", "cwxyz<Esc>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "xyz xyz xyz\n");
+    assert_eq!(editor.extra_cursors().len(), 2);
+}
+
+#[test]
+fn test_motion_and_delete_at_every_cursor() {
+    let mut editor = editor_with("one two\none three\n", 0, 0);
+    assert!(editor.run_script(&["<C-n>", "w", "dw"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "one \none \n");
+}
+
+#[test]
+fn test_esc_returns_to_single_cursor() {
+    let mut editor = editor_with("a a a\n", 0, 0);
+    assert!(editor.run_script(&["<C-n><C-n>"]).is_ok());
+    assert_eq!(editor.extra_cursors().len(), 2);
+
+    assert!(editor.run_script(&["<Esc>"]).is_ok());
+    assert!(editor.extra_cursors().is_empty());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 4));
+}
+
+#[test]
+fn test_cursors_merge_when_overlapping() {
+    let mut editor = editor_with("a a\n", 0, 0);
+    assert!(editor.run_script(&["<C-n>", "0"]).is_ok());
+    assert!(editor.extra_cursors().is_empty());
+}
@@ -0,0 +1,117 @@
+//! 数字加减（Ctrl+A / Ctrl+X）单元测试
+//!
+//! 对应源文件: src/number.rs, src/editor.rs (increment_number)
+
+use aivim_core::number::{add_to_number, find_number, NumberFormat, NumberMatch};
+use super::common::editor_with;
+
+fn find(line: &str, column: usize) -> Option<NumberMatch> {
+    let chars: Vec<char> = line.chars().collect();
+    find_number(&chars, column)
+}
+
+// ==================== 查找数字测试 ====================
+
+#[test]
+fn test_find_number_under_and_after_cursor() {
+    let decimal = |start, end| Some(NumberMatch { start, end, format: NumberFormat::Decimal });
+    assert_eq!(find("foo 123 bar", 0), decimal(4, 7));
+    assert_eq!(find("foo 123 bar", 5), decimal(4, 7));
+    assert_eq!(find("1 22 333", 2), decimal(2, 4));
+    assert_eq!(find("foo 123 bar", 7), None);
+    assert_eq!(find("x = -5;", 0), decimal(4, 6));
+    // 紧跟在单词之后的 - 不是负号
+    assert_eq!(find("a-1", 0), decimal(2, 3));
+}
+
+#[test]
+fn test_find_hex_number() {
+    assert_eq!(
+        find("color 0xff;", 7),
+        Some(NumberMatch { start: 6, end: 10, format: NumberFormat::Hex })
+    );
+    // 0x 后没有十六进制数字时按十进制处理
+    assert_eq!(
+        find("0xg", 0),
+        Some(NumberMatch { start: 0, end: 1, format: NumberFormat::Decimal })
+    );
+    // 十六进制数没有符号，- 不属于数字
+    let hex = Some(NumberMatch { start: 3, end: 7, format: NumberFormat::Hex });
+    assert_eq!(find("x -0x0f y", 0), hex);
+    assert_eq!(find("x -0x0f y", 2), hex);
+}
+
+#[test]
+fn test_add_to_number() {
+    assert_eq!(add_to_number("9", NumberFormat::Decimal, 1).as_deref(), Some("10"));
+    assert_eq!(add_to_number("-1", NumberFormat::Decimal, 3).as_deref(), Some("2"));
+    assert_eq!(add_to_number("3", NumberFormat::Decimal, -5).as_deref(), Some("-2"));
+    assert_eq!(add_to_number("007", NumberFormat::Decimal, 1).as_deref(), Some("008"));
+    assert_eq!(add_to_number("0x0f", NumberFormat::Hex, 1).as_deref(), Some("0x10"));
+    assert_eq!(add_to_number("0XFF", NumberFormat::Hex, 1).as_deref(), Some("0X100"));
+    assert_eq!(add_to_number("0x00", NumberFormat::Hex, -1).as_deref(), Some("0xffffffffffffffff"));
+    assert_eq!(add_to_number("9223372036854775807", NumberFormat::Decimal, 1), None);
+}
+
+// ==================== increment_number 测试 ====================
+
+#[test]
+fn test_increment_number() {
+    let mut editor = editor_with("width: 99px\n", 0, 0);
+    assert!(editor.increment_number(1));
+
+    assert_eq!(editor.current_buffer().to_string(), "width: 100px\n");
+    // 光标停在数字的最后一位
+    assert_eq!(editor.cursor().column, 9);
+}
+
+#[test]
+fn test_decrement_with_count() {
+    let mut editor = editor_with("x = 3\n", 0, 4);
+    assert!(editor.increment_number(-5));
+
+    assert_eq!(editor.current_buffer().to_string(), "x = -2\n");
+    assert_eq!(editor.cursor().column, 5);
+
+    assert!(editor.increment_number(5));
+    assert_eq!(editor.current_buffer().to_string(), "x = 3\n");
+}
+
+#[test]
+fn test_increment_hex() {
+    let mut editor = editor_with("0xFE\n", 0, 2);
+    assert!(editor.increment_number(2));
+
+    assert_eq!(editor.current_buffer().to_string(), "0x100\n");
+}
+
+#[test]
+fn test_increment_negative_looking_hex() {
+    let mut editor = editor_with("x -0x0f y\n", 0, 0);
+    assert!(editor.increment_number(1));
+    assert_eq!(editor.current_buffer().to_string(), "x -0x10 y\n");
+
+    // 光标在 - 上
+    let mut editor = editor_with("x -0x0f y\n", 0, 2);
+    assert!(editor.increment_number(-1));
+    assert_eq!(editor.current_buffer().to_string(), "x -0x0e y\n");
+    assert_eq!(editor.cursor().column, 6);
+}
+
+#[test]
+fn test_increment_without_number() {
+    let mut editor = editor_with("no digits\n42\n", 0, 0);
+    assert!(!editor.increment_number(1));
+
+    assert_eq!(editor.current_buffer().to_string(), "no digits\n42\n");
+}
+
+#[test]
+fn test_increment_is_undoable() {
+    let mut editor = editor_with("v1\n", 0, 0);
+    editor.increment_number(1);
+    assert_eq!(editor.current_buffer().to_string(), "v2\n");
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "v1\n");
+}
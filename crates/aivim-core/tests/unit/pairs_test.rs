@@ -0,0 +1,224 @@
+//! 括号和引号对单元测试
+//!
+//! 对应源文件: src/pairs.rs, src/text_object.rs (括号和引号文本对象), src/editor.rs (autopairs、ys / ds / cs)
+
+use aivim_core::buffer::{Buffer, BufferId};
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::keymap::{KeyParser, NormalCommand, Operator, OperatorTarget, ParseResult};
+use aivim_core::motion::Motion;
+use aivim_core::pairs::{auto_close, surround_delimiters};
+use aivim_core::text_object::TextObject;
+use super::common::editor_with;
+
+fn text(editor: &Editor) -> String {
+    editor.current_buffer().to_string()
+}
+
+fn object_text(content: &str, object: TextObject, line: usize, column: usize) -> Option<String> {
+    let mut buffer = Buffer::new(BufferId::new(1));
+    buffer.insert(0, content);
+    let (start, end) = object.get_range(&Cursor::new(line, column), &buffer)?;
+    Some(buffer.slice(start..end).to_string())
+}
+
+fn parse(keys: &str) -> ParseResult {
+    let mut parser = KeyParser::new();
+    let mut result = ParseResult::Invalid;
+    for key in keys.chars() {
+        result = parser.feed(key);
+    }
+    result
+}
+
+// ==================== 文本对象测试 ====================
+
+#[test]
+fn test_bracket_objects() {
+    let content = "f(a, (b + c), d)\n";
+    assert_eq!(object_text(content, TextObject::InnerBracket('('), 0, 3).as_deref(), Some("a, (b + c), d"));
+    assert_eq!(object_text(content, TextObject::AroundBracket('('), 0, 7).as_deref(), Some("(b + c)"));
+    // 光标在括号上时选择这个括号所在的括号对
+    assert_eq!(object_text(content, TextObject::AroundBracket('('), 0, 5).as_deref(), Some("(b + c)"));
+    assert_eq!(object_text(content, TextObject::AroundBracket('('), 0, 11).as_deref(), Some("(b + c)"));
+    assert_eq!(object_text(content, TextObject::InnerBracket('['), 0, 3), None);
+
+    // 多行的代码块：i{ 不包含括号所在的行
+    let block = "fn f() {\n    a();\n    b();\n}\n";
+    assert_eq!(object_text(block, TextObject::InnerBracket('{'), 1, 4).as_deref(), Some("    a();\n    b();\n"));
+    assert_eq!(object_text(block, TextObject::AroundBracket('{'), 2, 0).as_deref(), Some("{\n    a();\n    b();\n}"));
+}
+
+#[test]
+fn test_quote_objects() {
+    let content = "say \"hi\" and \"bye\" now\n";
+    assert_eq!(object_text(content, TextObject::InnerQuote('"'), 0, 6).as_deref(), Some("hi"));
+    assert_eq!(object_text(content, TextObject::AroundQuote('"'), 0, 6).as_deref(), Some("\"hi\" "));
+    // 光标在引号之间的空白上时，引号按行首开始两两配对
+    assert_eq!(object_text(content, TextObject::InnerQuote('"'), 0, 9).as_deref(), Some("bye"));
+    // 光标在引号之前时选择后面第一个引号对
+    assert_eq!(object_text(content, TextObject::InnerQuote('"'), 0, 0).as_deref(), Some("hi"));
+    // 后面没有空白时包含前面的空白
+    assert_eq!(object_text("x = 'a\\'b'\n", TextObject::AroundQuote('\''), 0, 6).as_deref(), Some(" 'a\\'b'"));
+    assert_eq!(object_text(content, TextObject::InnerQuote('\''), 0, 6), None);
+}
+
+#[test]
+fn test_bracket_text_object_with_operator() {
+    let mut editor = editor_with("call(one, two)\n", 0, 6);
+    assert!(editor.run_script(&["ci(x<Esc>"]).is_ok());
+    assert_eq!(text(&editor), "call(x)\n");
+    let mut editor = editor_with("let s = \"abc\";\n", 0, 10);
+    assert!(editor.run_script(&["di\""]).is_ok());
+    assert_eq!(text(&editor), "let s = \"\";\n");
+}
+
+// ==================== autopairs 测试 ====================
+
+#[test]
+fn test_auto_close_rules() {
+    assert_eq!(auto_close('(', None, None), Some(')'));
+    assert_eq!(auto_close('{', Some('x'), Some(')')), Some('}'));
+    // 光标后是单词字符时不补全
+    assert_eq!(auto_close('(', None, Some('a')), None);
+    // 单词后的引号不补全（don't、'a）
+    assert_eq!(auto_close('\'', Some('n'), None), None);
+    assert_eq!(auto_close('"', Some(' '), None), Some('"'));
+    assert_eq!(auto_close('x', None, None), None);
+}
+
+#[test]
+fn test_autopairs_insert_and_skip() {
+    let mut editor = editor_with("\n", 0, 0);
+    editor.execute_command("set autopairs").unwrap();
+    assert!(editor.options().autopairs);
+    assert!(editor.run_script(&["if(\"a\")"]).is_ok());
+    assert_eq!(text(&editor), "f(\"a\")\n");
+    assert_eq!(editor.cursor().column, 6);
+    // 在空的一对字符之间退格时一起删除
+    assert!(editor.run_script(&["<Esc>o[<BS>x<Esc>"]).is_ok());
+    assert_eq!(text(&editor), "f(\"a\")\nx\n");
+}
+
+#[test]
+fn test_autopairs_off_and_paste() {
+    let mut editor = editor_with("\n", 0, 0);
+    assert!(editor.run_script(&["i(<Esc>"]).is_ok());
+    assert_eq!(text(&editor), "(\n");
+
+    let mut editor = editor_with("\n", 0, 0);
+    editor.execute_command("set autopairs").unwrap();
+    editor.execute_command("set paste").unwrap();
+    assert!(editor.run_script(&["i(<Esc>"]).is_ok());
+    assert_eq!(text(&editor), "(\n");
+}
+
+// ==================== 包围测试 ====================
+
+#[test]
+fn test_parse_surround() {
+    assert_eq!(
+        parse("ysiw)"),
+        ParseResult::Complete(NormalCommand::Operator(aivim_core::OperatorCommand {
+            operator: Operator::Surround(')'),
+            target: OperatorTarget::TextObject(TextObject::InnerWord),
+            count: 1,
+            register: None,
+        }))
+    );
+    match parse("ys2w\"") {
+        ParseResult::Complete(NormalCommand::Operator(command)) => {
+            assert_eq!(command.operator, Operator::Surround('"'));
+            assert_eq!(command.target, OperatorTarget::Motion(Motion::WordForward));
+            assert_eq!(command.count, 2);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match parse("yss]") {
+        ParseResult::Complete(NormalCommand::Operator(command)) => assert_eq!(command.target, OperatorTarget::Line),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(parse("ds("), ParseResult::Complete(NormalCommand::DeleteSurround { target: '(' }));
+    assert_eq!(
+        parse("cs'\""),
+        ParseResult::Complete(NormalCommand::ChangeSurround { target: '\'', replacement: '"' })
+    );
+    assert_eq!(parse(">s"), ParseResult::Invalid);
+}
+
+#[test]
+fn test_surround_delimiters() {
+    assert_eq!(surround_delimiters('('), Some(("( ".to_string(), " )".to_string())));
+    assert_eq!(surround_delimiters('b'), Some(("(".to_string(), ")".to_string())));
+    assert_eq!(surround_delimiters('*'), Some(("*".to_string(), "*".to_string())));
+    assert_eq!(surround_delimiters('x'), None);
+}
+
+#[test]
+fn test_ys_surrounds_motion_and_line() {
+    let mut editor = editor_with("hello world\n", 0, 0);
+    assert!(editor.run_script(&["ysiw)"]).is_ok());
+    assert_eq!(text(&editor), "(hello) world\n");
+    assert_eq!(editor.cursor().column, 0);
+
+    // 不包围范围末尾的空白
+    let mut editor = editor_with("hello world\n", 0, 0);
+    assert!(editor.run_script(&["ysw\""]).is_ok());
+    assert_eq!(text(&editor), "\"hello\" world\n");
+
+    // yss 包围第一个非空字符到行尾，左括号在内侧加空格
+    let mut editor = editor_with("    a + b\n", 0, 6);
+    assert!(editor.run_script(&["yss("]).is_ok());
+    assert_eq!(text(&editor), "    ( a + b )\n");
+    assert_eq!(editor.cursor().column, 4);
+
+    // 一次撤销恢复
+    assert!(editor.run_script(&["u"]).is_ok());
+    assert_eq!(text(&editor), "    a + b\n");
+
+    let mut editor = editor_with("word\n", 0, 0);
+    editor.run_script(&["ysiwx"]);
+    assert_eq!(editor.message(), Some("Invalid surround character: x"));
+    assert_eq!(text(&editor), "word\n");
+}
+
+#[test]
+fn test_ds_deletes_surrounding() {
+    let mut editor = editor_with("f(( a ))\n", 0, 4);
+    assert!(editor.run_script(&["ds)"]).is_ok());
+    assert_eq!(text(&editor), "f( a )\n");
+    // ds( 连同内侧的空白一起删除
+    assert!(editor.run_script(&["ds("]).is_ok());
+    assert_eq!(text(&editor), "fa\n");
+
+    let mut editor = editor_with("x = \"quoted\";\n", 0, 7);
+    assert!(editor.run_script(&["ds\""]).is_ok());
+    assert_eq!(text(&editor), "x = quoted;\n");
+    assert_eq!(editor.cursor().column, 4);
+
+    let mut editor = editor_with("<b>bold</b>\n", 0, 4);
+    assert!(editor.run_script(&["dst"]).is_ok());
+    assert_eq!(text(&editor), "bold\n");
+
+    let mut editor = editor_with("plain\n", 0, 1);
+    editor.run_script(&["ds("]);
+    assert_eq!(editor.message(), Some("No surrounding ( found"));
+}
+
+#[test]
+fn test_cs_changes_surrounding() {
+    let mut editor = editor_with("say 'hi'\n", 0, 5);
+    assert!(editor.run_script(&["cs'\""]).is_ok());
+    assert_eq!(text(&editor), "say \"hi\"\n");
+    assert!(editor.run_script(&["cs\"{"]).is_ok());
+    assert_eq!(text(&editor), "say { hi }\n");
+    assert!(editor.run_script(&["cs{]"]).is_ok());
+    assert_eq!(text(&editor), "say [hi]\n");
+
+    // 跨行的括号
+    let mut editor = editor_with("f(\n    a,\n)\n", 1, 4);
+    assert!(editor.run_script(&["csb]"]).is_ok());
+    assert_eq!(text(&editor), "f[\n    a,\n]\n");
+    assert!(editor.run_script(&["u"]).is_ok());
+    assert_eq!(text(&editor), "f(\n    a,\n)\n");
+}
@@ -0,0 +1,169 @@
+//! 粘贴模式、粘贴检测与括号粘贴单元测试
+//!
+//! 对应源文件: src/paste.rs, src/editor.rs (paste / pastedetect 选项、括号粘贴)
+
+use aivim_core::editor::Editor;
+use aivim_core::paste::{PasteDetector, PASTE_BURST_KEYS, PASTE_IDLE_TIMEOUT};
+use aivim_core::Mode;
+use std::time::{Duration, Instant};
+use super::common::editor_with;
+
+/// 以 1ms 的间隔连续输入 `count` 个按键，返回最后一个按键的时间
+fn burst(editor: &mut Editor, start: Instant, count: usize) -> Instant {
+    let mut now = start;
+    for _ in 0..count {
+        now += Duration::from_millis(1);
+        editor.record_key_timing(now);
+    }
+    now
+}
+
+// ==================== PasteDetector 测试 ====================
+
+#[test]
+fn test_detector_ignores_human_typing() {
+    let mut detector = PasteDetector::new();
+    let mut now = Instant::now();
+    for _ in 0..20 {
+        now += Duration::from_millis(60);
+        assert!(!detector.record_key(now));
+    }
+    assert!(!detector.is_active());
+}
+
+#[test]
+fn test_detector_burst_and_idle() {
+    let mut detector = PasteDetector::new();
+    let mut now = Instant::now();
+    let mut started = 0;
+    for _ in 0..PASTE_BURST_KEYS * 2 {
+        now += Duration::from_millis(1);
+        started += detector.record_key(now) as usize;
+    }
+    // 只在开始时报告一次
+    assert_eq!(started, 1);
+    assert!(detector.is_active());
+
+    assert!(!detector.poll(now + Duration::from_millis(10)));
+    assert!(detector.poll(now + PASTE_IDLE_TIMEOUT));
+    assert!(!detector.is_active());
+}
+
+// ==================== paste 选项测试 ====================
+
+#[test]
+fn test_paste_option_disables_autoindent() {
+    let mut editor = editor_with("    foo\n", 0, 7);
+    editor.execute_command("set autoindent").unwrap();
+    editor.execute_command("set paste").unwrap();
+    assert!(editor.options().paste);
+
+    editor.set_mode(Mode::Insert);
+    editor.insert_newline();
+    editor.insert_char('x');
+    assert_eq!(editor.current_buffer().to_string(), "    foo\nx\n");
+
+    // 手动开启的粘贴模式不会因为离开 Insert 模式而关闭
+    editor.set_mode(Mode::Normal);
+    assert!(editor.options().paste);
+    editor.execute_command("set nopaste").unwrap();
+    assert!(!editor.options().paste);
+}
+
+#[test]
+fn test_burst_enables_paste_until_idle() {
+    let mut editor = Editor::new();
+    editor.set_mode(Mode::Insert);
+    let last = burst(&mut editor, Instant::now(), PASTE_BURST_KEYS);
+
+    assert!(editor.options().paste);
+    assert!(!editor.poll_paste_detection(last + Duration::from_millis(10)));
+    assert!(editor.poll_paste_detection(last + PASTE_IDLE_TIMEOUT));
+    assert!(!editor.options().paste);
+}
+
+#[test]
+fn test_burst_ignored_outside_insert_mode() {
+    let mut editor = Editor::new();
+    burst(&mut editor, Instant::now(), PASTE_BURST_KEYS * 2);
+    assert!(!editor.options().paste);
+}
+
+#[test]
+fn test_leaving_insert_ends_detected_paste() {
+    let mut editor = Editor::new();
+    editor.set_mode(Mode::Insert);
+    burst(&mut editor, Instant::now(), PASTE_BURST_KEYS);
+    assert!(editor.options().paste);
+
+    editor.set_mode(Mode::Normal);
+    assert!(!editor.options().paste);
+}
+
+#[test]
+fn test_nopastedetect() {
+    let mut editor = Editor::new();
+    assert!(editor.options().pastedetect);
+    editor.execute_command("set nopastedetect").unwrap();
+    editor.set_mode(Mode::Insert);
+    burst(&mut editor, Instant::now(), PASTE_BURST_KEYS * 2);
+
+    assert!(!editor.options().paste);
+}
+
+// ==================== 括号粘贴测试 ====================
+
+#[test]
+fn test_bracketed_paste_in_insert_mode_is_one_undo_step() {
+    let mut editor = editor_with("ab\n", 0, 1);
+    editor.set_mode(Mode::Insert);
+    editor.insert_char('x');
+    editor.paste_text("one\r\n    two\rthree");
+    assert_eq!(editor.current_buffer().to_string(), "axone\n    two\nthreeb\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 5));
+
+    // 粘贴和前面的输入属于同一个 Insert 会话
+    editor.set_mode(Mode::Normal);
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "ab\n");
+}
+
+#[test]
+fn test_bracketed_paste_skips_autoindent() {
+    let mut editor = editor_with("    fn main() {\n", 0, 15);
+    editor.execute_command("set autoindent").unwrap();
+    editor.set_mode(Mode::Insert);
+    editor.paste_text("\nbody();");
+    assert_eq!(editor.current_buffer().to_string(), "    fn main() {\nbody();\n");
+}
+
+#[test]
+fn test_bracketed_paste_in_normal_mode() {
+    let mut editor = editor_with("ab\n", 0, 1);
+    editor.paste_text("xyz");
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.current_buffer().to_string(), "axyzb\n");
+    assert_eq!(editor.cursor().column, 3);
+    assert_eq!(editor.register_manager().get('.').unwrap().content, "xyz");
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "ab\n");
+}
+
+#[test]
+fn test_bracketed_paste_in_command_line() {
+    let mut editor = Editor::new();
+    editor.set_mode(Mode::Command);
+    editor.command_line_mut().push_str("e ");
+    editor.paste_text("a b\nc\n");
+    assert_eq!(editor.command_line(), "e a b c");
+    assert_eq!(editor.current_buffer().to_string(), "");
+}
+
+#[test]
+fn test_bracketed_paste_in_replace_mode() {
+    let mut editor = editor_with("abcd\n", 0, 0);
+    editor.set_mode(Mode::Replace);
+    editor.paste_text("xy");
+    assert_eq!(editor.current_buffer().to_string(), "xycd\n");
+}
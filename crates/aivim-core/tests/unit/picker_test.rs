@@ -0,0 +1,196 @@
+//! 模糊选择器与符号提取单元测试
+//!
+//! 对应源文件: src/picker.rs, src/symbols.rs, src/editor.rs (:symbols)
+
+use aivim_core::editor::Editor;
+use aivim_core::picker::{fuzzy_score, Picker, PickerItem};
+use aivim_core::symbols::{document_symbols, workspace_symbols, SymbolKind};
+use std::fs;
+use std::path::Path;
+
+fn labels(picker: &Picker) -> Vec<String> {
+    picker.visible_items().map(|(_, item)| item.label.clone()).collect()
+}
+
+// ==================== 模糊匹配测试 ====================
+
+#[test]
+fn test_fuzzy_score_subsequence() {
+    assert!(fuzzy_score("bfr", "buffer").is_some());
+    assert!(fuzzy_score("xyz", "buffer").is_none());
+    assert_eq!(fuzzy_score("", "anything"), Some(0));
+}
+
+#[test]
+fn test_fuzzy_score_smartcase() {
+    assert!(fuzzy_score("buf", "Buffer").is_some());
+    assert!(fuzzy_score("Buf", "buffer").is_none());
+}
+
+#[test]
+fn test_fuzzy_score_prefers_consecutive_and_boundary() {
+    let exact = fuzzy_score("save", "save_as").unwrap();
+    let scattered = fuzzy_score("save", "set_active_value_e").unwrap();
+    assert!(exact > scattered);
+
+    let boundary = fuzzy_score("dm", "delete_motion").unwrap();
+    let inner = fuzzy_score("dm", "random").unwrap();
+    assert!(boundary > inner);
+}
+
+// ==================== Picker 测试 ====================
+
+#[test]
+fn test_picker_filters_and_sorts() {
+    let items = vec![
+        PickerItem::new("open_file", 0, 0),
+        PickerItem::new("save", 1, 0),
+        PickerItem::new("save_as", 2, 0),
+    ];
+    let mut picker = Picker::new("Symbols", items);
+    assert_eq!(picker.match_count(), 3);
+
+    picker.set_query("sav");
+    assert_eq!(labels(&picker), vec!["save", "save_as"]);
+
+    picker.push_char('_');
+    assert_eq!(labels(&picker), vec!["save_as"]);
+
+    picker.pop_char();
+    assert_eq!(picker.match_count(), 2);
+}
+
+#[test]
+fn test_picker_selection_wraps_and_scrolls() {
+    let items = (0..5).map(|i| PickerItem::new(format!("item{}", i), i, 0)).collect();
+    let mut picker = Picker::new("Test", items);
+    picker.set_max_visible(2);
+
+    picker.select_prev();
+    assert_eq!(picker.selected_item().unwrap().label, "item4");
+    assert_eq!(labels(&picker), vec!["item3", "item4"]);
+
+    picker.select_next();
+    assert_eq!(picker.selected_item().unwrap().label, "item0");
+}
+
+#[test]
+fn test_picker_extend_keeps_selection() {
+    let items = vec![PickerItem::new("b.rs", 0, 0), PickerItem::new("c.rs", 0, 0)];
+    let mut picker = Picker::new("Files", items);
+    picker.select_next();
+    assert_eq!(picker.selected_item().unwrap().label, "c.rs");
+
+    picker.extend(vec![PickerItem::new("a.rs", 0, 0)]);
+    assert_eq!(picker.total(), 3);
+    assert_eq!(picker.selected_item().unwrap().label, "c.rs");
+
+    // 新加入的候选项同样按查询过滤
+    picker.set_query("a");
+    picker.extend(vec![PickerItem::new("x.txt", 0, 0), PickerItem::new("ab.rs", 0, 0)]);
+    assert_eq!(labels(&picker), vec!["a.rs", "ab.rs"]);
+}
+
+// ==================== 符号提取测试 ====================
+
+#[test]
+fn test_document_symbols() {
+    let source = "\
+use std::io;
+
+pub struct Buffer {
+    rope: Rope,
+}
+
+impl<T: Clone> Display for Wrapper<T> where T: Debug {
+    pub(crate) fn len(&self) -> usize {
+        let x = 1;
+    }
+    pub const fn new() -> Self {}
+}
+
+pub const MAX: usize = 3;
+macro_rules! helper {
+extern crate foo;
+";
+    let symbols = document_symbols(source.lines());
+    let names: Vec<(&str, SymbolKind)> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+
+    assert_eq!(
+        names,
+        vec![
+            ("Buffer", SymbolKind::Struct),
+            ("Display for Wrapper<T>", SymbolKind::Impl),
+            ("len", SymbolKind::Function),
+            ("new", SymbolKind::Function),
+            ("MAX", SymbolKind::Constant),
+            ("helper", SymbolKind::Macro),
+        ]
+    );
+
+    // 行列指向符号名
+    assert_eq!((symbols[0].line, symbols[0].column), (2, 11));
+    assert_eq!((symbols[2].line, symbols[2].column), (7, 18));
+}
+
+#[test]
+fn test_workspace_symbols_skips_target() {
+    let root = Path::new("/tmp/aivim_ws_symbols");
+    fs::remove_dir_all(root).ok();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("target")).unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn alpha() {}\n").unwrap();
+    fs::write(root.join("target/gen.rs"), "pub fn generated() {}\n").unwrap();
+
+    let items = workspace_symbols(root);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].label, "alpha");
+    assert_eq!(items[0].path.as_deref(), Some(root.join("src/lib.rs").as_path()));
+    assert_eq!(items[0].detail.as_deref(), Some("function src/lib.rs:1"));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+// ==================== :symbols 命令测试 ====================
+
+#[test]
+fn test_symbols_command_jumps_to_symbol() {
+    let mut editor = Editor::new();
+    editor
+        .current_buffer_mut()
+        .insert(0, "fn first() {}\n\nfn second() {}\n");
+
+    editor.execute_command("symbols").unwrap();
+    assert_eq!(editor.picker().unwrap().total(), 2);
+
+    editor.picker_mut().unwrap().set_query("sec");
+    editor.accept_picker().unwrap();
+
+    assert!(editor.picker().is_none());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 3));
+}
+
+#[test]
+fn test_symbols_command_without_symbols() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "plain text\n");
+
+    assert_eq!(editor.execute_command("symbols"), Err("No symbols found".to_string()));
+    assert!(editor.picker().is_none());
+    assert!(editor.execute_command("symbols everywhere").is_err());
+}
+
+#[test]
+fn test_accept_picker_opens_file() {
+    let path = Path::new("/tmp/aivim_picker_target.rs");
+    fs::write(path, "// header\npub fn target() {}\n").unwrap();
+
+    let mut editor = Editor::new();
+    let item = PickerItem::new("target", 1, 7).with_path(path);
+    editor.open_picker(Picker::new("Workspace Symbols", vec![item]));
+    editor.accept_picker().unwrap();
+
+    assert_eq!(editor.current_buffer().file_path(), Some(path));
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 7));
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,242 @@
+//! 插件接口单元测试
+//!
+//! 对应源文件: src/plugin.rs, src/ex_command.rs (parse_with), src/editor.rs (register_plugin、with_plugins、插件命令和事件)
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+use aivim_core::autocmd::Event;
+use aivim_core::buffer::BufferId;
+use aivim_core::editor::{ConfirmAnswer, Editor};
+use aivim_core::ex_command::{parse_with, spec, CommandSpec, ExCommand, ExError};
+use aivim_core::mapping::MapKey;
+use aivim_core::plugin::{AiPlugin, GitPlugin, Plugin, PluginKeymap};
+use aivim_core::{MapMode, Mode};
+
+type Log<T> = Rc<RefCell<Vec<T>>>;
+
+/// 记录收到的事件和命令参数的测试插件
+#[derive(Default)]
+struct Recorder {
+    events: Log<Event>,
+    calls: Log<String>,
+}
+
+impl Plugin for Recorder {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn setup(&mut self, editor: &mut Editor) -> Result<(), String> {
+        editor.execute_command("set nu")
+    }
+
+    fn commands(&self) -> Vec<CommandSpec> {
+        vec![spec("Record", 3, true, true), spec("RecordSelf", 7, false, false)]
+    }
+
+    fn keymaps(&self) -> Vec<PluginKeymap> {
+        vec![PluginKeymap::new(MapMode::Normal, "<Leader>r", ":Record key<CR>")]
+    }
+
+    fn execute(&mut self, editor: &mut Editor, command: &ExCommand) -> Result<(), String> {
+        if command.name == "RecordSelf" {
+            return editor.execute_command("RecordSelf");
+        }
+        let range = command.range.map(|range| format!("{},{}", range.start, range.end)).unwrap_or_default();
+        self.calls.borrow_mut().push(format!("{}{}{} {}", range, command.name, if command.bang { "!" } else { "" }, command.args));
+        Ok(())
+    }
+
+    fn on_event(&mut self, _editor: &mut Editor, event: Event, _buffer: BufferId) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+/// 只提供一个命令的插件
+struct Named(&'static str);
+
+impl Plugin for Named {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+
+    fn commands(&self) -> Vec<CommandSpec> {
+        vec![spec(self.0, 1, false, false)]
+    }
+}
+
+/// 使用插件钩子的测试插件：保存自己的状态，界面和测试按类型查询
+#[derive(Default)]
+struct Hooks {
+    polls: usize,
+    options_set: usize,
+    keys: Vec<MapKey>,
+    brackets: Vec<(char, bool, usize)>,
+    answers: Vec<ConfirmAnswer>,
+}
+
+impl Plugin for Hooks {
+    fn name(&self) -> &'static str {
+        "hooks"
+    }
+
+    fn poll(&mut self, _editor: &mut Editor, _now: Instant) -> bool {
+        self.polls += 1;
+        true
+    }
+
+    fn on_option_set(&mut self, _editor: &mut Editor) {
+        self.options_set += 1;
+    }
+
+    fn on_key(&mut self, editor: &mut Editor, key: MapKey) -> bool {
+        // 只接管 Normal 模式的 Enter
+        if editor.mode() != Mode::Normal || key != MapKey::Enter {
+            return false;
+        }
+        self.keys.push(key);
+        editor.request_confirmation::<Hooks>("Really? (y/n)".to_string());
+        true
+    }
+
+    fn bracket(&mut self, _editor: &mut Editor, key: char, forward: bool, count: usize) -> Result<bool, String> {
+        if key != 'x' {
+            return Ok(false);
+        }
+        self.brackets.push((key, forward, count));
+        Ok(true)
+    }
+
+    fn on_confirm(&mut self, _editor: &mut Editor, answer: ConfirmAnswer) -> Result<(), String> {
+        self.answers.push(answer);
+        Ok(())
+    }
+}
+
+fn editor_with_recorder() -> (Editor, Log<Event>, Log<String>) {
+    let recorder = Recorder::default();
+    let (events, calls) = (recorder.events.clone(), recorder.calls.clone());
+    let mut editor = Editor::new();
+    editor.register_plugin(Box::new(recorder)).unwrap();
+    (editor, events, calls)
+}
+
+#[test]
+fn test_builtin_plugins() {
+    let editor = Editor::new();
+    assert_eq!(editor.plugins().names(), vec!["git", "lsp", "ai"]);
+    assert!(editor.plugins().command_owner("GitBlame").is_some());
+    assert!(editor.plugins().command_owner("AiChat").is_some());
+    assert!(editor.plugins().command_owner("write").is_none());
+}
+
+#[test]
+fn test_leaving_out_builtin_plugins_removes_features() {
+    let mut editor = Editor::with_plugins(vec![Box::new(GitPlugin::default())]).unwrap();
+    assert_eq!(editor.plugins().names(), vec!["git"]);
+    assert!(editor.plugins().contains("git"));
+    assert!(!editor.plugins().contains("ai"));
+    assert!(editor.plugin::<GitPlugin>().is_some());
+    assert!(editor.plugin::<AiPlugin>().is_none());
+    assert_eq!(editor.execute_command("AiChat hi"), Err("Unknown command: AiChat".to_string()));
+    // K 是 ai 插件的映射，没有注册时什么也不做
+    assert!(editor.run_script(&["K"]).is_ok());
+    assert_eq!(editor.message(), None);
+
+    let mut editor = Editor::with_plugins(Vec::new()).unwrap();
+    assert!(editor.plugins().is_empty());
+    assert!(editor.execute_command("GitBlame").is_err());
+    editor.execute_command("set gitsigns").unwrap();
+    assert!(!editor.poll_plugins(Instant::now()));
+    // ]c 由 git 插件处理，没有注册时什么也不做
+    assert!(editor.run_script(&["]c"]).is_ok());
+    assert_eq!(editor.message(), None);
+}
+
+#[test]
+fn test_with_plugins_checks_commands() {
+    assert!(Editor::with_plugins(vec![Box::new(Named("Hello")), Box::new(Named("Hello"))]).is_err());
+    assert!(Editor::with_plugins(vec![Box::new(Named("Hello"))]).is_ok());
+}
+
+#[test]
+fn test_parse_with_extra_commands() {
+    let extra = [spec("Record", 3, true, false)];
+    let command = parse_with("Rec! a b", 0, 0, &extra).unwrap().unwrap();
+    assert_eq!((command.name, command.bang, command.args), ("Record", true, "a b"));
+    assert_eq!(parse_with("1,2Rec", 0, 5, &extra), Err(ExError::NoRange("Record".to_string())));
+    // 没有注册时是未知的命令
+    assert_eq!(parse_with("Record", 0, 0, &[]), Err(ExError::Unknown("Record".to_string())));
+}
+
+#[test]
+fn test_plugin_command() {
+    let (mut editor, _, calls) = editor_with_recorder();
+    editor.current_buffer_mut().insert(0, "a\nb\nc\n");
+    editor.execute_command("Record hello").unwrap();
+    editor.execute_command("2,3Rec! x").unwrap();
+    assert_eq!(*calls.borrow(), vec!["Record hello".to_string(), "1,2Record! x".to_string()]);
+
+    assert!(editor.execute_command("RecordSelf").is_err());
+}
+
+#[test]
+fn test_plugin_setup_and_keymap() {
+    let (mut editor, _, calls) = editor_with_recorder();
+    assert!(editor.options().number);
+
+    assert!(editor.run_script(&["\\r"]).is_ok());
+    assert_eq!(*calls.borrow(), vec!["Record key".to_string()]);
+}
+
+#[test]
+fn test_plugin_receives_events() {
+    let (mut editor, events, _) = editor_with_recorder();
+    assert!(editor.run_script(&["i", "<Esc>"]).is_ok());
+    assert_eq!(*events.borrow(), vec![Event::InsertEnter, Event::InsertLeave]);
+}
+
+#[test]
+fn test_plugin_command_names_are_checked() {
+    let mut editor = Editor::new();
+    assert!(editor.register_plugin(Box::new(Named("lower"))).is_err());
+    assert!(editor.register_plugin(Box::new(Named("Files"))).is_err());
+    assert!(editor.register_plugin(Box::new(Named("GitBlame"))).is_err());
+    assert_eq!(editor.plugins().len(), 3);
+
+    editor.register_plugin(Box::new(Named("Hello"))).unwrap();
+    assert!(editor.register_plugin(Box::new(Named("Hello"))).is_err());
+    assert_eq!(editor.plugins().len(), 4);
+    assert!(editor.execute_command("H").is_ok());
+}
+
+#[test]
+fn test_plugin_hooks() {
+    let mut editor = Editor::with_plugins(vec![Box::new(Hooks::default())]).unwrap();
+    assert!(editor.poll_plugins(Instant::now()));
+    editor.execute_command("set ts=4 sw=4").unwrap();
+    editor.execute_command("setlocal nu").unwrap();
+    assert!(editor.run_script(&["2]x", "[x", "]q"]).is_ok());
+
+    // Enter 由插件处理，插件的 y/n 提示的回答交给插件
+    assert!(editor.run_script(&["<CR>"]).is_ok());
+    assert_eq!(editor.message(), Some("Really? (y/n)"));
+    editor.answer_confirmation(ConfirmAnswer::Yes).unwrap();
+
+    let hooks = editor.plugin::<Hooks>().unwrap();
+    assert_eq!(hooks.polls, 1);
+    assert_eq!(hooks.options_set, 3);
+    assert_eq!(hooks.keys, vec![MapKey::Enter]);
+    assert_eq!(hooks.brackets, vec![('x', true, 2), ('x', false, 1)]);
+    assert_eq!(hooks.answers, vec![ConfirmAnswer::Yes]);
+
+    // with_plugin 取出插件，期间可以修改编辑器
+    let polls = editor.with_plugin::<Hooks, _>(|hooks, editor| {
+        editor.set_message("inside");
+        hooks.polls
+    });
+    assert_eq!(polls, Some(1));
+    assert!(editor.with_plugin::<GitPlugin, _>(|_, _| ()).is_none());
+}
@@ -0,0 +1,105 @@
+//! 上次光标位置单元测试
+//!
+//! 对应源文件: src/positions.rs, src/editor.rs (打开文件时恢复位置、退出和删除缓冲区时记录)
+
+use aivim_core::editor::Editor;
+use aivim_core::positions::{FilePositions, MAX_POSITIONS};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 创建测试文件和单独的状态文件
+fn setup(name: &str, content: &str) -> (PathBuf, PathBuf) {
+    let dir = PathBuf::from(format!("/tmp/aivim_positions_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("file.txt");
+    fs::write(&file, content).unwrap();
+    (file, dir.join("state").join("positions"))
+}
+
+fn editor_with_state(state: &Path) -> Editor {
+    let mut editor = Editor::new();
+    editor.set_positions_file(Some(state.to_path_buf()));
+    editor
+}
+
+// ==================== 状态文件测试 ====================
+
+#[test]
+fn test_parse_and_format() {
+    let positions = FilePositions::parse("3 4 /tmp/a b.txt\nbad line\n0 0 /tmp/c.txt\n");
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions.get(Path::new("/tmp/a b.txt")), Some((3, 4)));
+    assert_eq!(positions.format(), "3 4 /tmp/a b.txt\n0 0 /tmp/c.txt\n");
+}
+
+#[test]
+fn test_set_moves_file_to_front() {
+    let mut positions = FilePositions::parse("1 0 /tmp/a.txt\n2 0 /tmp/b.txt\n");
+    positions.set(Path::new("/tmp/b.txt"), 5, 1);
+    assert_eq!(positions.format(), "5 1 /tmp/b.txt\n1 0 /tmp/a.txt\n");
+}
+
+#[test]
+fn test_keeps_most_recent_files() {
+    let mut positions = FilePositions::default();
+    for i in 0..MAX_POSITIONS + 5 {
+        positions.set(Path::new(&format!("/tmp/file{}.txt", i)), i, 0);
+    }
+    assert_eq!(positions.len(), MAX_POSITIONS);
+    assert_eq!(positions.get(Path::new("/tmp/file0.txt")), None);
+    assert_eq!(positions.get(Path::new(&format!("/tmp/file{}.txt", MAX_POSITIONS + 4))), Some((MAX_POSITIONS + 4, 0)));
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_reopen_restores_cursor() {
+    let (file, state) = setup("reopen", "one\ntwo\nthree words\n");
+    let mut editor = editor_with_state(&state);
+    editor.open_file(&file).unwrap();
+    editor.execute_command("call cursor(3, 7)").unwrap();
+    editor.save_positions(&[]);
+
+    let mut reopened = editor_with_state(&state);
+    reopened.open_file(&file).unwrap();
+    assert_eq!((reopened.cursor().line, reopened.cursor().column), (2, 6));
+}
+
+#[test]
+fn test_position_clamped_to_shorter_file() {
+    let (file, state) = setup("clamped", "one\ntwo\nthree words\n");
+    let mut editor = editor_with_state(&state);
+    editor.open_file(&file).unwrap();
+    editor.execute_command("call cursor(3, 7)").unwrap();
+    editor.save_positions(&[]);
+    fs::write(&file, "short\n").unwrap();
+
+    let mut reopened = editor_with_state(&state);
+    reopened.open_file(&file).unwrap();
+    assert_eq!((reopened.cursor().line, reopened.cursor().column), (0, 4));
+}
+
+#[test]
+fn test_delete_buffer_records_position() {
+    let (file, state) = setup("bdelete", "one\ntwo\n");
+    let mut editor = editor_with_state(&state);
+    editor.open_file(&file).unwrap();
+    editor.execute_command("2").unwrap();
+    editor.execute_command("bdelete").unwrap();
+
+    assert_eq!(FilePositions::load(&state).get(&file), Some((1, 0)));
+}
+
+#[test]
+fn test_no_state_file_starts_at_origin() {
+    let (file, state) = setup("disabled", "one\ntwo\n");
+    let mut editor = editor_with_state(&state);
+    editor.open_file(&file).unwrap();
+    editor.execute_command("2").unwrap();
+    editor.save_positions(&[]);
+
+    let mut other = Editor::new();
+    other.open_file(&file).unwrap();
+    assert_eq!(other.cursor().line, 0);
+}
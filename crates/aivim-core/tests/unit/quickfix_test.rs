@@ -0,0 +1,330 @@
+//! Quickfix 列表与工作区搜索单元测试
+//!
+//! 对应源文件: src/quickfix.rs, src/grep.rs, src/editor.rs (:grep、:make、:cnext / :cprev、:copen / :cclose、位置列表)
+
+use aivim_core::editor::Editor;
+use aivim_core::grep::{grep_files, grep_text, GrepSearch};
+use aivim_core::quickfix::{parse_errors, ListKind, QuickfixEntry, QuickfixKind, QuickfixList};
+use aivim_core::waker::Waker;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 创建测试目录，`files` 为相对路径和内容
+fn make_tree(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("aivim_grep_{}_{}", name, std::process::id()));
+    fs::remove_dir_all(&root).ok();
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    root
+}
+
+fn list(count: usize) -> QuickfixList {
+    let mut list = QuickfixList::new("test");
+    list.extend((0..count).map(|i| QuickfixEntry::new("a.txt", i, 0, format!("entry {}", i))));
+    list
+}
+
+/// 等待 :make 结束
+fn wait_for_make(editor: &mut Editor) {
+    let start = Instant::now();
+    while editor.pending_shell_commands() > 0 {
+        editor.poll_jobs();
+        assert!(start.elapsed() < Duration::from_secs(10), "make did not finish");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// 等待后台搜索结束
+fn wait_for_grep(editor: &mut Editor) {
+    let start = Instant::now();
+    while editor.is_grepping() {
+        editor.poll_grep();
+        assert!(start.elapsed() < Duration::from_secs(5), "grep did not finish");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+// ==================== 列表测试 ====================
+
+#[test]
+fn test_next_and_previous_stop_at_ends() {
+    let mut list = list(3);
+    assert_eq!(list.current().unwrap().line, 0);
+    assert_eq!(list.previous(1), None);
+    assert_eq!(list.next(1).unwrap().line, 1);
+    // 计数超出范围时停在最后一项
+    assert_eq!(list.next(5).unwrap().line, 2);
+    assert_eq!(list.next(1), None);
+    assert_eq!(list.previous(2).unwrap().line, 0);
+}
+
+#[test]
+fn test_select_clamps() {
+    let mut list = list(3);
+    assert_eq!(list.select(10).unwrap().line, 2);
+    assert_eq!(list.current_index(), 2);
+    assert_eq!(QuickfixList::new("empty").select(1), None);
+}
+
+// ==================== 编译器输出测试 ====================
+
+#[test]
+fn test_parse_gcc_style_errors() {
+    let output = "make: *** [all] Error 1\n\
+                  src/main.c:12:5: error: expected ';' before '}' token\n\
+                  src/util.c:3: warning: unused variable 'x'\n\
+                  notes.txt:7:TODO later\n\
+                  In file included from x.h:1,\n";
+    assert_eq!(
+        parse_errors(output),
+        vec![
+            QuickfixEntry::new("src/main.c", 11, 4, "expected ';' before '}' token").with_kind(QuickfixKind::Error),
+            QuickfixEntry::new("src/util.c", 2, 0, "unused variable 'x'").with_kind(QuickfixKind::Warning),
+            QuickfixEntry::new("notes.txt", 6, 0, "TODO later"),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_rustc_errors() {
+    let output = "   Compiling demo v0.1.0 (/tmp/demo)\n\
+                  error[E0425]: cannot find value `y` in this scope\n\
+                  \x20--> src/main.rs:3:13\n\
+                  \x20 |\n\
+                  3 |     let x = y;\n\
+                  warning: unused variable: `x`\n\
+                  \x20 --> src/lib.rs:10:9\n\
+                  error: aborting due to 1 previous error\n";
+    assert_eq!(
+        parse_errors(output),
+        vec![
+            QuickfixEntry::new("src/main.rs", 2, 12, "cannot find value `y` in this scope").with_kind(QuickfixKind::Error),
+            QuickfixEntry::new("src/lib.rs", 9, 8, "unused variable: `x`").with_kind(QuickfixKind::Warning),
+        ]
+    );
+}
+
+// ==================== 搜索测试 ====================
+
+#[test]
+fn test_grep_text_columns() {
+    let entries = grep_text(Path::new("f.rs"), "let x = 1;\n  // 注释 TODO: x\nTODO\n", "TODO");
+    assert_eq!(
+        entries,
+        vec![
+            QuickfixEntry::new("f.rs", 1, 8, "// 注释 TODO: x"),
+            QuickfixEntry::new("f.rs", 2, 0, "TODO"),
+        ]
+    );
+    // 区分大小写
+    assert!(grep_text(Path::new("f.rs"), "todo\n", "TODO").is_empty());
+    assert!(grep_text(Path::new("f.rs"), "text\n", "").is_empty());
+}
+
+#[test]
+fn test_grep_files_skips_ignored_and_binary() {
+    let root = make_tree(
+        "files",
+        &[
+            (".gitignore", b"target/\n"),
+            ("src/main.rs", b"fn main() {\n    needle();\n}\n"),
+            ("notes.md", b"needle\n"),
+            ("target/out.rs", b"needle\n"),
+            ("data.bin", b"needle\0\x01\x02"),
+        ],
+    );
+    let entries = grep_files(&root, "needle");
+    let locations: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.path.strip_prefix(&root).unwrap().display().to_string(), entry.line, entry.column))
+        .collect();
+    assert_eq!(locations, vec![("notes.md".to_string(), 0, 0), ("src/main.rs".to_string(), 1, 4)]);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_grep_search_runs_in_background() {
+    let root = make_tree("background", &[("a.txt", b"one\nneedle\n"), ("b.txt", b"needle\n")]);
+    let mut search = GrepSearch::start(root.clone(), "needle");
+    assert_eq!(search.pattern(), "needle");
+    let mut entries = Vec::new();
+    let start = Instant::now();
+    while !search.is_done() {
+        entries.extend(search.poll());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    entries.extend(search.poll());
+    assert_eq!(entries.len(), 2);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_grep_search_wakes_event_loop() {
+    let root = make_tree("waker", &[("a.txt", b"needle\n")]);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let waker = Waker::new(move || {
+        let _ = sender.send(());
+    });
+    let mut search = GrepSearch::start_with_waker(root.clone(), "needle", waker);
+    // 结果和结束各唤醒一次，之后不轮询也能取到全部结果
+    for _ in 0..2 {
+        receiver.recv_timeout(Duration::from_secs(5)).expect("waker not called");
+    }
+    assert_eq!(search.poll().len(), 1);
+    assert!(search.is_done());
+    fs::remove_dir_all(&root).ok();
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_grep_command_fills_quickfix() {
+    let root = make_tree("command", &[("a.txt", b"alpha\nneedle one\n"), ("b.txt", b"needle two\n")]);
+    let mut editor = Editor::new();
+    editor.execute_command(&format!("grep needle {}", root.display())).unwrap();
+    assert!(editor.show_quickfix());
+    assert!(editor.quickfix_focused());
+    wait_for_grep(&mut editor);
+
+    assert_eq!(editor.quickfix().len(), 2);
+    assert_eq!(editor.quickfix().title(), ":grep needle");
+    assert_eq!(editor.message(), Some("2 matches for needle"));
+
+    // 回车（:cc）跳转到当前项，焦点回到编辑窗口
+    editor.execute_command("cc").unwrap();
+    assert!(!editor.quickfix_focused());
+    assert_eq!(editor.current_buffer().file_path(), Some(root.join("a.txt").as_path()));
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 0));
+    assert_eq!(editor.message(), Some("(1 of 2): needle one"));
+
+    editor.execute_command("cn").unwrap();
+    assert_eq!(editor.current_buffer().file_path(), Some(root.join("b.txt").as_path()));
+    assert_eq!(editor.execute_command("cnext"), Err("No more items".to_string()));
+    editor.execute_command("cp").unwrap();
+    assert_eq!(editor.current_buffer().file_path(), Some(root.join("a.txt").as_path()));
+    editor.execute_command("cc 2").unwrap();
+    assert_eq!(editor.quickfix().current_index(), 1);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_grep_without_matches() {
+    let root = make_tree("empty", &[("a.txt", b"alpha\n")]);
+    let mut editor = Editor::new();
+    editor.execute_command(&format!("grep \"not here\" {}", root.display())).unwrap();
+    wait_for_grep(&mut editor);
+    assert!(editor.quickfix().is_empty());
+    assert_eq!(editor.message(), Some("No matches for not here"));
+    assert_eq!(editor.execute_command("cnext"), Err("Quickfix list is empty".to_string()));
+    assert_eq!(editor.execute_command("grep"), Err("Argument required".to_string()));
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_copen_and_cclose() {
+    let mut editor = Editor::new();
+    editor.set_quickfix(list(2));
+    editor.execute_command("copen").unwrap();
+    assert!(editor.show_quickfix() && editor.quickfix_focused());
+    editor.select_quickfix(1);
+    editor.unfocus_quickfix();
+    assert!(editor.show_quickfix() && !editor.quickfix_focused());
+    editor.execute_command("ccl").unwrap();
+    assert!(!editor.show_quickfix());
+    assert_eq!(editor.quickfix().current_index(), 1);
+}
+
+#[test]
+fn test_make_fills_quickfix_and_jumps_to_first_error() {
+    let root = make_tree(
+        "make",
+        &[
+            ("main.c", b"int main() {\n    return 0\n}\n"),
+            // 编译失败（非零退出状态）时仍然解析输出
+            ("build.sh", b"echo \"$(dirname $0)/main.c:2:13: error: expected semicolon\" >&2\nexit 1\n"),
+        ],
+    );
+    let file = root.join("main.c");
+    let mut editor = Editor::new();
+    editor.execute_command(&format!("set makeprg=sh\\ {}", root.join("build.sh").display())).unwrap();
+    editor.execute_command("make").unwrap();
+    wait_for_make(&mut editor);
+
+    assert_eq!(editor.quickfix().len(), 1);
+    assert_eq!(editor.quickfix().entries()[0].kind, Some(QuickfixKind::Error));
+    assert_eq!(editor.current_buffer().file_path(), Some(file.as_path()));
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 12));
+    assert_eq!(editor.message(), Some("(1 of 1): error: expected semicolon"));
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_make_bang_does_not_jump() {
+    let mut editor = Editor::new();
+    editor.execute_command("set makeprg=echo").unwrap();
+    assert_eq!(editor.make_program(), "echo");
+    editor.execute_command("make! a.c:1:1: warning: w").unwrap();
+    wait_for_make(&mut editor);
+    assert_eq!(editor.quickfix().len(), 1);
+    assert_eq!(editor.quickfix().title(), ":echo a.c:1:1: warning: w");
+    assert_eq!(editor.current_buffer().file_path(), None);
+    assert_eq!(editor.message(), Some("echo a.c:1:1: warning: w finished (exit 0): 1 errors"));
+}
+
+#[test]
+fn test_cfirst_and_clast() {
+    let root = make_tree("cfirst", &[("a.txt", b"0\n1\n2\n")]);
+    let mut editor = Editor::new();
+    let mut quickfix = QuickfixList::new("test");
+    quickfix.extend((0..3).map(|i| QuickfixEntry::new(root.join("a.txt"), i, 0, "")));
+    editor.set_quickfix(quickfix);
+    editor.execute_command("clast").unwrap();
+    assert_eq!(editor.cursor().line, 2);
+    editor.execute_command("cfirst").unwrap();
+    assert_eq!(editor.cursor().line, 0);
+    fs::remove_dir_all(&root).ok();
+}
+
+// ==================== 位置列表测试 ====================
+
+#[test]
+fn test_location_list_belongs_to_window() {
+    let root = make_tree("loclist", &[("a.txt", b"0\n1\n2\n")]);
+    let mut editor = Editor::new();
+    assert_eq!(editor.execute_command("lnext"), Err("No location list".to_string()));
+    assert_eq!(editor.execute_command("lopen"), Err("No location list".to_string()));
+
+    let mut list = QuickfixList::new("diagnostics");
+    list.extend([
+        QuickfixEntry::new(root.join("a.txt"), 1, 0, "first").with_kind(QuickfixKind::Warning),
+        QuickfixEntry::new(root.join("a.txt"), 2, 0, "second"),
+    ]);
+    editor.set_location_list(Some(list));
+    editor.execute_command("ll").unwrap();
+    assert_eq!(editor.cursor().line, 1);
+    assert_eq!(editor.message(), Some("(1 of 2): warning: first"));
+    editor.execute_command("lne").unwrap();
+    assert_eq!(editor.cursor().line, 2);
+    assert_eq!(editor.execute_command("lnext"), Err("No more items".to_string()));
+    // Quickfix 列表不受影响
+    assert!(editor.quickfix().is_empty());
+
+    // 分割时复制到新窗口，关闭面板只关闭位置列表
+    editor.execute_command("split").unwrap();
+    assert_eq!(editor.location_list().unwrap().title(), "diagnostics");
+    editor.execute_command("lopen").unwrap();
+    assert_eq!(editor.quickfix_panel().map(|(kind, _)| kind), Some(ListKind::Location));
+    editor.execute_command("cclose").unwrap();
+    assert!(editor.show_quickfix());
+    editor.execute_command("lclose").unwrap();
+    assert!(!editor.show_quickfix());
+
+    editor.set_location_list(None);
+    assert!(editor.location_list().is_none());
+    fs::remove_dir_all(&root).ok();
+}
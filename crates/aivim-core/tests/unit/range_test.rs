@@ -0,0 +1,225 @@
+//! 命令行范围单元测试
+//!
+//! 对应源文件: src/range.rs, src/editor.rs (:10,20w, :10,20y, :N,Ms, :m, :t)
+
+use std::fs;
+use std::path::Path;
+
+use aivim_core::editor::Editor;
+use aivim_core::range::{is_zero_address, parse_destination, parse_range, LineRange, RangeError};
+use super::common::editor_with;
+
+// ==================== 范围解析测试 ====================
+
+#[test]
+fn test_parse_no_range() {
+    assert_eq!(parse_range("w", 0, 9), Ok((None, "w")));
+}
+
+#[test]
+fn test_parse_numbers() {
+    assert_eq!(parse_range("10,20w", 0, 29), Ok((Some(LineRange::new(9, 19)), "w")));
+    assert_eq!(parse_range("3y a", 0, 9), Ok((Some(LineRange::single(2)), "y a")));
+}
+
+#[test]
+fn test_parse_symbols() {
+    assert_eq!(parse_range("%y", 4, 9), Ok((Some(LineRange::new(0, 9)), "y")));
+    assert_eq!(parse_range(".,$w", 4, 9), Ok((Some(LineRange::new(4, 9)), "w")));
+    assert_eq!(parse_range(",6w", 4, 9), Ok((Some(LineRange::new(4, 5)), "w")));
+}
+
+#[test]
+fn test_parse_offsets() {
+    assert_eq!(parse_range(".+1,$-2y", 4, 9), Ok((Some(LineRange::new(5, 7)), "y")));
+    assert_eq!(parse_range("-,+y", 4, 9), Ok((Some(LineRange::new(3, 5)), "y")));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_range("20w", 0, 9), Err(RangeError::Invalid));
+    assert_eq!(parse_range(".-5y", 2, 9), Err(RangeError::Invalid));
+    assert_eq!(parse_range("5,3y", 0, 9), Err(RangeError::Backwards));
+}
+
+#[test]
+fn test_line_count() {
+    assert_eq!(LineRange::new(9, 19).line_count(), 11);
+    assert_eq!(LineRange::single(3).line_count(), 1);
+}
+
+#[test]
+fn test_parse_destination() {
+    assert_eq!(parse_destination("0", 4, 9), Ok(0));
+    assert_eq!(parse_destination("3", 4, 9), Ok(3));
+    assert_eq!(parse_destination("$", 4, 9), Ok(10));
+    assert_eq!(parse_destination(" .-2 ", 4, 9), Ok(3));
+    assert_eq!(parse_destination("", 4, 9), Err(RangeError::Invalid));
+    assert_eq!(parse_destination("11", 4, 9), Err(RangeError::Invalid));
+}
+
+#[test]
+fn test_is_zero_address() {
+    assert!(is_zero_address("0r file"));
+    assert!(is_zero_address(" 00read"));
+    assert!(!is_zero_address("1r file"));
+    assert!(!is_zero_address("10r file"));
+    assert!(!is_zero_address("0+1r file"));
+    assert!(!is_zero_address("0,2y"));
+    assert!(!is_zero_address("r file"));
+}
+
+// ==================== :y 测试 ====================
+
+#[test]
+fn test_yank_range_to_register() {
+    let mut editor = editor_with("a\nb\nc\nd\n", 0, 0);
+    assert!(editor.execute_command("2,3y a").is_ok());
+
+    let register = editor.register_manager().get('a').unwrap();
+    assert_eq!(register.content, "b\nc\n");
+    assert!(register.linewise);
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "b\nc\n");
+    assert_eq!(editor.message(), Some("2 line(s) yanked"));
+    // 复制不修改缓冲区也不移动光标
+    assert_eq!(editor.cursor().line, 0);
+}
+
+#[test]
+fn test_yank_current_line_without_range() {
+    let mut editor = editor_with("a\nb\nc\n", 1, 0);
+    assert!(editor.execute_command("y").is_ok());
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "b\n");
+}
+
+#[test]
+fn test_yank_last_line_without_newline() {
+    let mut editor = editor_with("a\nb", 0, 0);
+    assert!(editor.execute_command("$y x").is_ok());
+    assert_eq!(editor.register_manager().get('x').unwrap().content, "b\n");
+}
+
+#[test]
+fn test_yank_invalid_register() {
+    let mut editor = editor_with("a\n", 0, 0);
+    assert!(editor.execute_command("1y ab").is_err());
+}
+
+// ==================== :w 测试 ====================
+
+#[test]
+fn test_write_range_to_file() {
+    let path = Path::new("/tmp/aivim_range_write.txt");
+    let _ = fs::remove_file(path);
+    let mut editor = editor_with("a\nb\nc\nd\n", 0, 0);
+
+    assert!(editor.execute_command("2,3w /tmp/aivim_range_write.txt").is_ok());
+    assert_eq!(fs::read_to_string(path).unwrap(), "b\nc\n");
+    assert_eq!(editor.message(), Some("\"/tmp/aivim_range_write.txt\" 2L written"));
+
+    // 已存在的文件需要 ! 覆盖
+    assert!(editor.execute_command("1w /tmp/aivim_range_write.txt").is_err());
+    assert!(editor.execute_command("1w! /tmp/aivim_range_write.txt").is_ok());
+    assert_eq!(fs::read_to_string(path).unwrap(), "a\n");
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_write_range_append() {
+    let path = Path::new("/tmp/aivim_range_append.txt");
+    fs::write(path, "x\n").unwrap();
+    let mut editor = editor_with("a\nb\nc\n", 0, 0);
+
+    assert!(editor.execute_command("3w >> /tmp/aivim_range_append.txt").is_ok());
+    assert!(editor.execute_command("w >>/tmp/aivim_range_append.txt").is_ok());
+    assert_eq!(fs::read_to_string(path).unwrap(), "x\nc\na\nb\nc\n");
+    // 写入其他文件不影响缓冲区的修改状态
+    assert!(editor.current_buffer().is_modified());
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_write_partial_buffer_to_own_file_needs_bang() {
+    let path = Path::new("/tmp/aivim_range_own.txt");
+    fs::write(path, "a\nb\n").unwrap();
+    let mut editor = Editor::with_file(path).unwrap();
+
+    assert!(editor.execute_command("1w").is_err());
+    assert!(editor.execute_command("1w!").is_ok());
+    assert_eq!(fs::read_to_string(path).unwrap(), "a\n");
+    let _ = fs::remove_file(path);
+}
+
+// ==================== :m / :t 测试 ====================
+
+#[test]
+fn test_move_lines() {
+    let mut editor = editor_with("1\n2\n3\n4\n", 0, 0);
+    assert!(editor.execute_command("1,2m$").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "3\n4\n1\n2\n");
+    assert_eq!(editor.cursor().line, 3);
+    assert_eq!(editor.message(), Some("2 line(s) moved"));
+
+    assert!(editor.execute_command("m 0").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "2\n3\n4\n1\n");
+
+    // 一次撤销恢复整个移动
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "3\n4\n1\n2\n");
+}
+
+#[test]
+fn test_move_lines_into_itself() {
+    let mut editor = editor_with("1\n2\n3\n4\n", 0, 0);
+    assert!(editor.execute_command("1,3m2").is_err());
+    // 目标紧邻范围时文本不变
+    assert!(editor.execute_command("2,3m1").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "1\n2\n3\n4\n");
+}
+
+#[test]
+fn test_move_last_line_without_newline() {
+    let mut editor = editor_with("1\n2\n3", 0, 0);
+    assert!(editor.execute_command("1m$").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "2\n3\n1");
+    assert!(editor.execute_command("$m0").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "1\n2\n3");
+}
+
+#[test]
+fn test_copy_lines() {
+    let mut editor = editor_with("a\n  b\nc\n", 2, 0);
+    assert!(editor.execute_command("2t0").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "  b\na\n  b\nc\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 2));
+
+    assert!(editor.execute_command("1,2co .").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "  b\n  b\na\na\n  b\nc\n");
+    assert!(editor.execute_command("t 99").is_err());
+}
+
+// ==================== 其他带范围的命令 ====================
+
+#[test]
+fn test_substitute_with_range() {
+    let mut editor = editor_with("a\na\na\na\n", 0, 0);
+    assert!(editor.execute_command("2,3s/a/b").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nb\na\n");
+
+    assert!(editor.execute_command("%s/a/c/g").is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "c\nb\nb\nc\n");
+}
+
+#[test]
+fn test_range_only_jumps_to_line() {
+    let mut editor = editor_with("a\n  b\nc\n", 0, 0);
+    assert!(editor.execute_command("2").is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 2));
+    assert!(editor.execute_command("9").is_err());
+}
+
+#[test]
+fn test_range_not_allowed() {
+    let mut editor = editor_with("a\nb\n", 0, 0);
+    assert!(editor.execute_command("1,2reg").is_err());
+}
@@ -105,7 +105,7 @@ fn test_numbered_registers_shift() {
 
     // 填充所有数字寄存器
     for i in 0..10 {
-        manager.set_unnamed_delete(&format!("content{}", i), false);
+        manager.set_unnamed_delete(format!("content{}", i), false);
     }
 
     // 9号应该是最旧的（content0）
@@ -134,7 +134,7 @@ fn test_named_registers_all_letters() {
 
     // 测试所有小写字母
     for c in 'a'..='z' {
-        manager.set(c, &format!("content {}", c), false);
+        manager.set(c, format!("content {}", c), false);
         assert_eq!(manager.get(c).unwrap().content, format!("content {}", c));
     }
 }
@@ -150,6 +150,32 @@ fn test_named_registers_append_multiple() {
     assert_eq!(manager.get('a').unwrap().content, "first second third");
 }
 
+#[test]
+fn test_named_registers_append_linewise() {
+    let mut manager = RegisterManager::new();
+
+    // 整行追加到字符内容：用换行分隔，结果为整行
+    manager.set('a', "word", false);
+    manager.set('A', "line\n", true);
+    let register = manager.get('a').unwrap();
+    assert_eq!(register.content, "word\nline\n");
+    assert!(register.linewise);
+
+    // 字符内容追加到整行：结果仍以换行结尾
+    manager.set('b', "line\n", true);
+    manager.set('B', "word", false);
+    let register = manager.get('b').unwrap();
+    assert_eq!(register.content, "line\nword\n");
+    assert!(register.linewise);
+}
+
+#[test]
+fn test_black_hole_register_is_ignored() {
+    let mut manager = RegisterManager::new();
+    manager.set('_', "gone", false);
+    assert!(manager.get('_').is_none());
+}
+
 #[test]
 fn test_named_registers_overwrite() {
     let mut manager = RegisterManager::new();
@@ -22,7 +22,7 @@ fn test_get_all_registers_unnamed() {
     let registers = manager.get_all_registers();
     
     // 无名寄存器和 0 号寄存器都会存储内容
-    assert!(registers.len() >= 1);
+    assert!(!registers.is_empty());
     
     // 检查是否包含无名寄存器
     let has_unnamed = registers.iter().any(|r| r.name == '"');
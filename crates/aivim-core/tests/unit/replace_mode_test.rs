@@ -0,0 +1,146 @@
+//! 替换字符（r）与 Replace 模式（R）单元测试
+//!
+//! 对应源文件: src/editor.rs (replace_chars, Replace 模式下的 insert_char / backspace)
+
+use aivim_core::cursor::Cursor;
+use aivim_core::keymap::{KeyParser, NormalCommand, ParseResult};
+use aivim_core::Mode;
+use super::common::editor_with;
+
+// ==================== r{char} 测试 ====================
+
+#[test]
+fn test_parse_replace_char() {
+    let mut parser = KeyParser::new();
+    assert_eq!(parser.feed('3'), ParseResult::Pending);
+    assert_eq!(parser.feed('r'), ParseResult::Pending);
+    assert!(parser.awaiting_replace_char());
+    // r 之后的任意字符都是替换字符，包括操作符和数字
+    assert_eq!(
+        parser.feed('d'),
+        ParseResult::Complete(NormalCommand::ReplaceChar { ch: 'd', count: 3 })
+    );
+    assert!(!parser.is_pending());
+}
+
+#[test]
+fn test_replace_single_char() {
+    let mut editor = editor_with("hello\n", 0, 1);
+    assert!(editor.replace_chars('a', 1));
+
+    assert_eq!(editor.current_buffer().to_string(), "hallo\n");
+    assert_eq!(editor.cursor().column, 1);
+    assert_eq!(editor.mode(), Mode::Normal);
+}
+
+#[test]
+fn test_replace_with_count() {
+    let mut editor = editor_with("hello\n", 0, 1);
+    assert!(editor.replace_chars('x', 3));
+
+    assert_eq!(editor.current_buffer().to_string(), "hxxxo\n");
+    // 光标停在最后一个替换的字符上
+    assert_eq!(editor.cursor().column, 3);
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "hello\n");
+}
+
+#[test]
+fn test_replace_count_too_large() {
+    let mut editor = editor_with("abc\ndef\n", 0, 1);
+    assert!(!editor.replace_chars('x', 3));
+
+    assert_eq!(editor.current_buffer().to_string(), "abc\ndef\n");
+}
+
+#[test]
+fn test_replace_empty_line() {
+    let mut editor = editor_with("\nabc\n", 0, 0);
+    assert!(!editor.replace_chars('x', 1));
+
+    assert_eq!(editor.current_buffer().to_string(), "\nabc\n");
+}
+
+#[test]
+fn test_replace_with_newline() {
+    let mut editor = editor_with("foo bar\n", 0, 3);
+    assert!(editor.replace_chars('\n', 1));
+
+    assert_eq!(editor.current_buffer().to_string(), "foo\nbar\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 0));
+}
+
+// ==================== Replace 模式测试 ====================
+
+#[test]
+fn test_replace_mode_overwrites() {
+    let mut editor = editor_with("abcdef\n", 0, 1);
+    editor.set_mode(Mode::Replace);
+    for ch in "XY".chars() {
+        editor.insert_char(ch);
+    }
+
+    assert_eq!(editor.current_buffer().to_string(), "aXYdef\n");
+    assert_eq!(editor.cursor().column, 3);
+}
+
+#[test]
+fn test_replace_mode_appends_past_line_end() {
+    let mut editor = editor_with("ab\ncd\n", 0, 1);
+    editor.set_mode(Mode::Replace);
+    for ch in "xyz".chars() {
+        editor.insert_char(ch);
+    }
+
+    assert_eq!(editor.current_buffer().to_string(), "axyz\ncd\n");
+}
+
+#[test]
+fn test_replace_mode_backspace_restores() {
+    let mut editor = editor_with("ab\ncd\n", 0, 0);
+    editor.set_mode(Mode::Replace);
+    for ch in "xyz".chars() {
+        editor.insert_char(ch);
+    }
+    assert_eq!(editor.current_buffer().to_string(), "xyz\ncd\n");
+
+    editor.backspace();
+    assert_eq!(editor.current_buffer().to_string(), "xy\ncd\n");
+    editor.backspace();
+    editor.backspace();
+    assert_eq!(editor.current_buffer().to_string(), "ab\ncd\n");
+    assert_eq!(editor.cursor().column, 0);
+
+    // 超出本次输入的范围时只移动光标，不修改文本
+    *editor.cursor_mut() = Cursor::new(0, 2);
+    editor.backspace();
+    assert_eq!(editor.current_buffer().to_string(), "ab\ncd\n");
+    assert_eq!(editor.cursor().column, 1);
+}
+
+#[test]
+fn test_replace_mode_newline_and_backspace() {
+    let mut editor = editor_with("abc\n", 0, 1);
+    editor.set_mode(Mode::Replace);
+    editor.insert_newline();
+    editor.insert_char('X');
+    assert_eq!(editor.current_buffer().to_string(), "a\nXc\n");
+
+    editor.backspace();
+    editor.backspace();
+    assert_eq!(editor.current_buffer().to_string(), "abc\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 1));
+}
+
+#[test]
+fn test_replace_mode_is_single_undo() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    editor.set_mode(Mode::Replace);
+    editor.insert_char('x');
+    editor.insert_char('y');
+    editor.set_mode(Mode::Normal);
+    editor.undo();
+
+    assert_eq!(editor.current_buffer().to_string(), "abc\n");
+}
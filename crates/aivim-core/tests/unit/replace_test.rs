@@ -4,7 +4,18 @@
 //! 测试范围: 替换操作
 
 use aivim_core::buffer::{Buffer, BufferId};
-use aivim_core::replace::{parse_substitute_command, replace_in_buffer};
+use aivim_core::replace::{count_matches, parse_substitute_command, replace_in_buffer, ConfirmSubstitute, SubstituteCommand};
+
+fn command(pattern: &str, replacement: &str, global: bool, full_file: bool) -> SubstituteCommand {
+    SubstituteCommand {
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        global,
+        confirm: false,
+        count_only: false,
+        full_file,
+    }
+}
 
 #[test]
 fn test_replace_first() {
@@ -14,7 +25,8 @@ fn test_replace_first() {
     let result = replace_in_buffer(&mut buffer, "hello", "hi", false, None);
 
     assert_eq!(result.count, 1);
-    assert!(result.new_text.contains("hi world hello"));
+    assert_eq!(result.last_line, Some(0));
+    assert_eq!(buffer.to_string(), "hi world hello\n");
 }
 
 #[test]
@@ -25,7 +37,7 @@ fn test_replace_global() {
     let result = replace_in_buffer(&mut buffer, "hello", "hi", true, None);
 
     assert_eq!(result.count, 2);
-    assert!(result.new_text.contains("hi world hi"));
+    assert_eq!(buffer.to_string(), "hi world hi\n");
 }
 
 #[test]
@@ -36,7 +48,8 @@ fn test_replace_empty_pattern() {
     let result = replace_in_buffer(&mut buffer, "", "hi", true, None);
 
     assert_eq!(result.count, 0);
-    assert_eq!(result.new_text, buffer.to_string());
+    assert_eq!(result.last_line, None);
+    assert_eq!(buffer.to_string(), "hello world\n");
 }
 
 #[test]
@@ -47,31 +60,32 @@ fn test_replace_multiline() {
     let result = replace_in_buffer(&mut buffer, "hello", "hi", true, None);
 
     assert_eq!(result.count, 3);
-    assert_eq!(result.new_text, "hi\nhi\nhi\n");
+    assert_eq!(result.last_line, Some(2));
+    assert_eq!(buffer.to_string(), "hi\nhi\nhi\n");
 }
 
 #[test]
 fn test_parse_substitute_basic() {
     let result = parse_substitute_command(":s/old/new");
-    assert_eq!(result, Some(("old".to_string(), "new".to_string(), false, false)));
+    assert_eq!(result, Some(command("old", "new", false, false)));
 }
 
 #[test]
 fn test_parse_substitute_global() {
     let result = parse_substitute_command(":s/old/new/g");
-    assert_eq!(result, Some(("old".to_string(), "new".to_string(), true, false)));
+    assert_eq!(result, Some(command("old", "new", true, false)));
 }
 
 #[test]
 fn test_parse_substitute_full_file() {
     let result = parse_substitute_command(":%s/old/new/g");
-    assert_eq!(result, Some(("old".to_string(), "new".to_string(), true, true)));
+    assert_eq!(result, Some(command("old", "new", true, true)));
 }
 
 #[test]
 fn test_parse_substitute_no_colon() {
     let result = parse_substitute_command("s/old/new");
-    assert_eq!(result, Some(("old".to_string(), "new".to_string(), false, false)));
+    assert_eq!(result, Some(command("old", "new", false, false)));
 }
 
 #[test]
@@ -85,3 +99,52 @@ fn test_parse_substitute_no_slash() {
     let result = parse_substitute_command(":soldnew");
     assert_eq!(result, None);
 }
+
+#[test]
+fn test_parse_substitute_flags() {
+    let result = parse_substitute_command("s/a/b/gc").unwrap();
+    assert!(result.global && result.confirm);
+    let result = parse_substitute_command("s/a/b/c").unwrap();
+    assert!(!result.global && result.confirm);
+    let result = parse_substitute_command("%s/a//gn").unwrap();
+    assert!(result.count_only && result.replacement.is_empty());
+    // 替换文本之后只能是标志
+    assert_eq!(parse_substitute_command("s/a/b/x"), None);
+}
+
+#[test]
+fn test_confirm_substitute_steps() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "a a\nb\na\n");
+    let mut command = command("a", "xa", true, false);
+    command.confirm = true;
+
+    let mut state = ConfirmSubstitute::start(command.clone(), &buffer, 0, 2).unwrap();
+    assert_eq!((state.line, state.columns()), (0, (0, 1)));
+    // 替换后从替换文本之后查找，不会匹配替换文本中的 a
+    state.replace(&mut buffer);
+    assert!(state.advance(&buffer, true));
+    assert_eq!((state.line, state.column), (0, 3));
+    assert!(state.advance(&buffer, false));
+    assert_eq!((state.line, state.column), (2, 0));
+    assert!(!state.advance(&buffer, false));
+    assert_eq!(state.count, 1);
+    assert_eq!(buffer.to_string(), "xa a\nb\na\n");
+
+    // 没有 g 标志时每行只有第一个匹配
+    command.global = false;
+    let mut state = ConfirmSubstitute::start(command.clone(), &buffer, 0, 1).unwrap();
+    assert_eq!(state.column, 1);
+    assert!(!state.advance(&buffer, false));
+    assert!(ConfirmSubstitute::start(command, &buffer, 1, 1).is_none());
+}
+
+#[test]
+fn test_count_matches() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "a a\nb\na\n");
+    assert_eq!(count_matches(&buffer, "a", true, (0, 3)), (3, 2));
+    assert_eq!(count_matches(&buffer, "a", false, (0, 3)), (2, 2));
+    assert_eq!(count_matches(&buffer, "a", true, (1, 2)), (0, 0));
+    assert_eq!(buffer.to_string(), "a a\nb\na\n");
+}
@@ -0,0 +1,165 @@
+//! 脚本接口单元测试
+//!
+//! 对应源文件: src/script.rs, src/input.rs, src/editor.rs (Editor::run_script)
+
+use aivim_core::editor::Editor;
+use aivim_core::mode::Mode;
+use aivim_core::script::ScriptInput;
+use aivim_core::MapKey;
+use std::fs;
+use std::path::PathBuf;
+
+fn open_text(text: &str) -> Editor {
+    let mut editor = Editor::new();
+    editor.open_bytes(text.as_bytes());
+    editor
+}
+
+// ==================== 解析测试 ====================
+
+#[test]
+fn test_parse_script_input() {
+    assert_eq!(ScriptInput::parse(":%s/a/b/g"), ScriptInput::Command("%s/a/b/g".to_string()));
+    assert_eq!(
+        ScriptInput::parse("ix<Esc>"),
+        ScriptInput::Keys(vec![MapKey::Char('i'), MapKey::Char('x'), MapKey::Esc])
+    );
+}
+
+// ==================== 执行测试 ====================
+
+#[test]
+fn test_commands_and_keys() {
+    let mut editor = open_text("one\ntwo\nthree\n");
+    let result = editor.run_script(&["ggdd", ":$", "Afour<Esc>"]);
+
+    assert!(result.is_ok());
+    assert_eq!(result.steps.len(), 3);
+    assert_eq!(editor.current_buffer().to_string(), "two\nthreefour\n");
+    let last = result.steps.last().unwrap();
+    assert_eq!(last.mode, Mode::Normal);
+    assert_eq!(last.cursor.line, 1);
+}
+
+#[test]
+fn test_step_records_mode_and_cursor() {
+    let mut editor = open_text("hello\n");
+    let result = editor.run_script(&["A world", "<Esc>0w"]);
+
+    assert_eq!(result.steps[0].mode, Mode::Insert);
+    assert_eq!(result.steps[1].mode, Mode::Normal);
+    assert_eq!(result.steps[1].cursor.column, 6);
+    assert_eq!(editor.current_buffer().to_string(), "hello world\n");
+}
+
+#[test]
+fn test_command_error_is_recorded() {
+    let mut editor = open_text("text\n");
+    let result = editor.run_script(&[":nosuchcommand", "x"]);
+
+    assert!(!result.is_ok());
+    let failed = result.first_error().unwrap();
+    assert_eq!(failed.input, ":nosuchcommand");
+    assert_eq!(failed.message, failed.error);
+    // 出错之后继续执行后面的步骤
+    assert_eq!(editor.current_buffer().to_string(), "ext\n");
+}
+
+#[test]
+fn test_message_of_each_step() {
+    let mut editor = open_text("a a\na\n");
+    let result = editor.run_script(&[":%s/a/b/g", "j"]);
+
+    assert!(result.steps[0].message.is_some());
+    assert_eq!(result.steps[1].message, None);
+    assert_eq!(result.last_message(), None);
+}
+
+#[test]
+fn test_keys_use_mappings() {
+    let mut editor = open_text("one\ntwo\n");
+    let result = editor.run_script(&[":nnoremap Q dd", "Q", ":inoremap jk <Esc>", "ixjk"]);
+
+    assert!(result.is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "xtwo\n");
+    assert_eq!(editor.mode(), Mode::Normal);
+}
+
+#[test]
+fn test_pending_mapping_prefix_times_out() {
+    let mut editor = open_text("one\ntwo\n");
+    editor.run_script(&[":nnoremap dx x", "dd"]);
+    assert_eq!(editor.current_buffer().to_string(), "two\n");
+
+    // 结束时还在等待的前缀按超时处理：d 之后没有动作，序列被放弃
+    editor.run_script(&["d"]);
+    editor.run_script(&["x"]);
+    assert_eq!(editor.current_buffer().to_string(), "wo\n");
+}
+
+#[test]
+fn test_normal_command() {
+    let mut editor = open_text("a\nb\nc\n");
+    let result = editor.run_script(&[":%normal A;"]);
+
+    assert!(result.is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a;\nb;\nc;\n");
+}
+
+#[test]
+fn test_quit_stops_script() {
+    let mut editor = open_text("text\n");
+    let result = editor.run_script(&[":q!", "x"]);
+
+    assert!(result.quit);
+    assert_eq!(result.steps.len(), 1);
+    assert_eq!(editor.current_buffer().to_string(), "text\n");
+}
+
+#[test]
+fn test_confirmation_answered_by_next_step() {
+    let path = PathBuf::from("/tmp/aivim_script_confirm.txt");
+    fs::write(&path, "old\n").unwrap();
+    let mut editor = Editor::new();
+    editor.open_file(&path).unwrap();
+    editor.run_script(&["x"]);
+    // 让文件看起来在外部被修改过
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(&path, "changed\n").unwrap();
+
+    let result = editor.run_script(&[":w", "y"]);
+    assert!(result.steps[0].message.is_some());
+    assert!(editor.pending_confirmation().is_none());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ld\n");
+}
+
+#[test]
+fn test_command_line_keys() {
+    let mut editor = open_text("one\ntwo\n");
+    let result = editor.run_script(&[":2", "dd", ":put<CR>"]);
+
+    // : 开头的一步按 Ex 命令执行，`<CR>` 不会被解析为按键
+    assert!(!result.is_ok());
+    let result = editor.run_script(&["Gdd:1<CR>"]);
+    assert!(result.is_ok());
+    assert_eq!(editor.cursor().line, 0);
+}
+
+#[test]
+fn test_shell_commands_finish_within_step() {
+    let mut editor = open_text("c\nb\na\n");
+    let result = editor.run_script(&[":%!sort", "gg", ":r !echo hi"]);
+    assert!(result.is_ok());
+    assert_eq!(result.steps[0].message.as_deref(), Some("3 line(s) filtered"));
+    assert_eq!(editor.current_buffer().to_string(), "a\nhi\nb\nc\n");
+    assert_eq!(editor.pending_shell_commands(), 0);
+}
+
+#[test]
+fn test_failed_shell_command_is_step_error() {
+    let mut editor = open_text("b\na\n");
+    let result = editor.run_script(&[":%!exit 3", "x"]);
+    assert!(result.steps[0].error.as_deref().is_some_and(|error| error.contains("exit 3")));
+    assert!(result.steps[1].error.is_none());
+    assert_eq!(editor.current_buffer().to_string(), "\na\n");
+}
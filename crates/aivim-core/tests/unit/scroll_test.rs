@@ -0,0 +1,247 @@
+//! 滚动命令单元测试
+//!
+//! 对应源文件: src/editor.rs, src/input.rs, src/motion.rs (zt / zz / zb、z. / z-、Ctrl+E / Ctrl+Y、Ctrl+D / Ctrl+U、Ctrl+F / Ctrl+B、H / M / L)
+
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::{Editor, ScrollAnchor};
+
+fn editor_with_lines(count: usize) -> Editor {
+    let mut editor = Editor::new();
+    let text: String = (1..=count).map(|n| format!("  line {}\n", n)).collect();
+    editor.current_buffer_mut().insert(0, &text);
+    editor
+}
+
+#[test]
+fn test_scroll_cursor_to_top_center_bottom() {
+    let mut editor = editor_with_lines(100);
+    *editor.cursor_mut() = Cursor::new(50, 0);
+
+    editor.scroll_cursor_to(ScrollAnchor::Top, 80, 20);
+    assert_eq!(editor.scroll_offset(), 50);
+    editor.scroll_cursor_to(ScrollAnchor::Center, 80, 20);
+    assert_eq!(editor.scroll_offset(), 41);
+    editor.scroll_cursor_to(ScrollAnchor::Bottom, 80, 20);
+    assert_eq!(editor.scroll_offset(), 31);
+    // 光标不动
+    assert_eq!(*editor.cursor(), Cursor::new(50, 0));
+}
+
+#[test]
+fn test_scroll_cursor_near_start_of_file() {
+    let mut editor = editor_with_lines(100);
+    *editor.cursor_mut() = Cursor::new(3, 0);
+    editor.scroll_cursor_to(ScrollAnchor::Bottom, 80, 20);
+    assert_eq!(editor.scroll_offset(), 0);
+}
+
+#[test]
+fn test_scroll_cursor_counts_wrapped_rows() {
+    let mut editor = Editor::new();
+    let long = "x".repeat(25);
+    editor.current_buffer_mut().insert(0, &format!("{}\n{}\n{}\nshort\n", long, long, long));
+    *editor.cursor_mut() = Cursor::new(3, 0);
+    // 每个长行在宽度 10 时占 3 个屏幕行，高度 7 的窗口在光标行之上只放得下两个长行
+    editor.scroll_cursor_to(ScrollAnchor::Bottom, 10, 7);
+    assert_eq!(editor.scroll_offset(), 1);
+}
+
+#[test]
+fn test_zz_keys() {
+    let mut editor = editor_with_lines(100);
+    *editor.cursor_mut() = Cursor::new(50, 5);
+    assert!(editor.run_script(&["zt"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 50);
+    assert!(editor.run_script(&["zb"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 29);
+    assert!(editor.run_script(&["zz"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 40);
+    assert_eq!(*editor.cursor(), Cursor::new(50, 5));
+
+    // z. 同时把光标移到第一个非空白字符
+    assert!(editor.run_script(&["z."]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (50, 2));
+}
+
+#[test]
+fn test_ctrl_e_scrolls_down_and_keeps_cursor_visible() {
+    let mut editor = editor_with_lines(100);
+    *editor.cursor_mut() = Cursor::new(1, 0);
+    assert!(editor.run_script(&["<C-e>"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 1);
+    assert_eq!(editor.cursor().line, 1);
+
+    assert!(editor.run_script(&["3<C-e>"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 4);
+    // 光标所在行滚出窗口顶部时移到窗口的第一行
+    assert_eq!(editor.cursor().line, 4);
+}
+
+#[test]
+fn test_ctrl_e_stops_at_last_line() {
+    let mut editor = editor_with_lines(5);
+    assert!(editor.run_script(&["10<C-e>"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 4);
+    assert_eq!(editor.cursor().line, 4);
+}
+
+#[test]
+fn test_ctrl_y_scrolls_up_and_keeps_cursor_visible() {
+    let mut editor = editor_with_lines(100);
+    editor.set_scroll_offset(50);
+    *editor.cursor_mut() = Cursor::new(71, 0);
+    editor.scroll_window(-1, 80, 22);
+    assert_eq!(editor.scroll_offset(), 49);
+    // 光标所在行滚出窗口底部时移到窗口的最后一行
+    assert_eq!(editor.cursor().line, 70);
+
+    editor.scroll_window(-100, 80, 22);
+    assert_eq!(editor.scroll_offset(), 0);
+    assert_eq!(editor.cursor().line, 21);
+}
+
+#[test]
+fn test_scroll_window_skips_closed_fold() {
+    let mut editor = editor_with_lines(20);
+    editor.execute_command("2,5fold").unwrap();
+    editor.scroll_window(2, 80, 10);
+    // 第 1 行和折叠各算一行
+    assert_eq!(editor.scroll_offset(), 5);
+}
+
+#[test]
+fn test_viewport_excludes_partly_shown_line() {
+    let mut editor = Editor::new();
+    let long = "x".repeat(25);
+    editor.current_buffer_mut().insert(0, &format!("{}\n{}\n{}\nshort\n", long, long, long));
+    // 前两行占 6 个屏幕行，第三行只显示了第一个屏幕行
+    editor.set_text_size(10, 7);
+    let viewport = editor.viewport();
+    assert_eq!((viewport.top, viewport.bottom, viewport.height, viewport.width), (0, 1, 7, 10));
+}
+
+#[test]
+fn test_ctrl_f_and_ctrl_b_page_by_window_height() {
+    let mut editor = editor_with_lines(100);
+    editor.set_text_size(80, 20);
+    // 窗口显示第 0-19 行，翻页后倒数第二行在窗口顶部
+    assert!(editor.run_script(&["<C-f>"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 18);
+    assert_eq!((editor.cursor().line, editor.cursor().column), (18, 2));
+    assert!(editor.run_script(&["<C-f>"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 36);
+    assert_eq!(editor.cursor().line, 36);
+
+    // 向上翻页后原来的第二行在窗口底部
+    assert!(editor.run_script(&["<C-b>"]).is_ok());
+    assert_eq!(editor.cursor().line, 37);
+    assert_eq!(editor.scroll_offset(), 18);
+}
+
+#[test]
+fn test_ctrl_f_with_count_and_ctrl_b_at_start_of_file() {
+    let mut editor = editor_with_lines(100);
+    editor.set_text_size(80, 10);
+    assert!(editor.run_script(&["<C-b>"]).is_ok());
+    assert_eq!(editor.cursor().line, 0);
+    assert_eq!(editor.scroll_offset(), 0);
+
+    assert!(editor.run_script(&["3<C-f>"]).is_ok());
+    assert_eq!(editor.scroll_offset(), 24);
+    assert_eq!(editor.cursor().line, 24);
+}
+
+#[test]
+fn test_ctrl_f_stops_at_last_line() {
+    let mut editor = editor_with_lines(5);
+    editor.set_text_size(80, 20);
+    assert!(editor.run_script(&["<C-f>"]).is_ok());
+    assert_eq!(editor.cursor().line, 4);
+    assert_eq!(editor.scroll_offset(), 4);
+}
+
+#[test]
+fn test_h_m_l_move_within_window() {
+    let mut editor = editor_with_lines(100);
+    editor.set_text_size(80, 20);
+    editor.set_scroll_offset(10);
+    *editor.cursor_mut() = Cursor::new(15, 0);
+
+    assert!(editor.run_script(&["L"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (29, 2));
+    assert!(editor.run_script(&["M"]).is_ok());
+    assert_eq!(editor.cursor().line, 19);
+    assert!(editor.run_script(&["H"]).is_ok());
+    assert_eq!(editor.cursor().line, 10);
+    assert_eq!(editor.scroll_offset(), 10);
+}
+
+#[test]
+fn test_h_m_l_in_short_buffer() {
+    let mut editor = editor_with_lines(5);
+    editor.set_text_size(80, 20);
+    assert!(editor.run_script(&["L"]).is_ok());
+    assert_eq!(editor.cursor().line, 4);
+    assert!(editor.run_script(&["M"]).is_ok());
+    assert_eq!(editor.cursor().line, 2);
+}
+
+#[test]
+fn test_m_skips_closed_fold() {
+    let mut editor = editor_with_lines(20);
+    editor.set_text_size(80, 10);
+    editor.execute_command("4,7fold").unwrap();
+    // 窗口显示第 0-2 行、折叠和第 7-13 行，中间的第 6 行在折叠中
+    assert!(editor.run_script(&["M"]).is_ok());
+    assert_eq!(editor.cursor().line, 3);
+}
+
+#[test]
+fn test_delete_to_window_bottom_is_linewise() {
+    let mut editor = editor_with_lines(100);
+    editor.set_text_size(80, 20);
+    editor.set_scroll_offset(10);
+    *editor.cursor_mut() = Cursor::new(10, 4);
+    assert!(editor.run_script(&["dL"]).is_ok());
+    assert_eq!(editor.current_buffer().last_content_line(), 79);
+    assert_eq!(editor.get_line_text(10).unwrap(), "  line 31");
+}
+
+#[test]
+fn test_ctrl_d_and_ctrl_u_move_half_window_height() {
+    let mut editor = editor_with_lines(100);
+    editor.set_text_size(80, 30);
+    *editor.cursor_mut() = Cursor::new(5, 0);
+    assert!(editor.run_script(&["<C-d>"]).is_ok());
+    assert_eq!(editor.cursor().line, 20);
+    // 窗口滚动相同的行数，光标在窗口中的位置不变
+    assert_eq!(editor.scroll_offset(), 15);
+
+    editor.set_text_size(80, 9);
+    assert!(editor.run_script(&["<C-u>"]).is_ok());
+    assert_eq!(editor.cursor().line, 16);
+    assert_eq!(editor.scroll_offset(), 11);
+}
+
+#[test]
+fn test_ctrl_d_keeps_preferred_column() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "long line here\nx\nlong line here\n");
+    editor.set_text_size(80, 4);
+    *editor.cursor_mut() = Cursor::new(0, 9);
+    editor.cursor_mut().preferred_column = Some(9);
+    assert!(editor.run_script(&["<C-d>"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 9));
+    assert!(editor.run_script(&["<C-u>"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 9));
+}
+
+#[test]
+fn test_ctrl_d_stops_at_last_line() {
+    let mut editor = editor_with_lines(10);
+    editor.set_text_size(80, 20);
+    *editor.cursor_mut() = Cursor::new(5, 0);
+    assert!(editor.run_script(&["<C-d>"]).is_ok());
+    assert_eq!(editor.cursor().line, 9);
+    assert_eq!(editor.scroll_offset(), 4);
+}
@@ -1,11 +1,13 @@
 //! Search 模块单元测试
 //!
-//! 对应源文件: src/search.rs
+//! 对应源文件: src/search.rs, src/editor.rs (匹配计数、:s 的 n 标志、* / #、搜索偏移、d/pattern)
 //! 测试范围: 搜索功能、匹配查找、方向搜索
 
 use aivim_core::buffer::{Buffer, BufferId};
 use aivim_core::cursor::Cursor;
-use aivim_core::search::{SearchDirection, SearchState};
+use aivim_core::editor::Editor;
+use aivim_core::search::{split_search_input, word_under_cursor, SearchDirection, SearchOffset, SearchState};
+use aivim_core::Mode;
 
 // ==================== 基本搜索测试 ====================
 
@@ -34,8 +36,8 @@ fn test_find_matches() {
     search.set_pattern("hello", SearchDirection::Forward, &buffer);
 
     assert_eq!(search.matches.len(), 2);
-    assert_eq!(search.matches[0], 0);
-    assert_eq!(search.matches[1], 12);
+    assert_eq!(search.matches.get(0), Some(0));
+    assert_eq!(search.matches.get(1), Some(12));
     assert!(search.is_active());
 }
 
@@ -71,9 +73,9 @@ fn test_find_matches_overlapping() {
 
     // "aaaa" 中有 3 个重叠的 "aa": (0,1), (1,2), (2,3)
     assert_eq!(search.matches.len(), 3);
-    assert_eq!(search.matches[0], 0);
-    assert_eq!(search.matches[1], 1);
-    assert_eq!(search.matches[2], 2);
+    assert_eq!(search.matches.get(0), Some(0));
+    assert_eq!(search.matches.get(1), Some(1));
+    assert_eq!(search.matches.get(2), Some(2));
 }
 
 // ==================== 正向搜索测试 ====================
@@ -313,8 +315,8 @@ fn test_find_matches_multiline() {
     search.set_pattern("hello", SearchDirection::Forward, &buffer);
 
     assert_eq!(search.matches.len(), 2);
-    assert_eq!(search.matches[0], 0);
-    assert_eq!(search.matches[1], 12); // 跨行后的位置
+    assert_eq!(search.matches.get(0), Some(0));
+    assert_eq!(search.matches.get(1), Some(12)); // 跨行后的位置
 }
 
 #[test]
@@ -327,7 +329,7 @@ fn test_find_matches_multiline_pattern() {
     search.set_pattern("hello\nworld", SearchDirection::Forward, &buffer);
 
     assert_eq!(search.matches.len(), 1);
-    assert_eq!(search.matches[0], 0);
+    assert_eq!(search.matches.get(0), Some(0));
 }
 
 // ==================== 边界情况测试 ====================
@@ -353,13 +355,12 @@ fn test_single_char_pattern() {
     search.set_pattern("b", SearchDirection::Forward, &buffer);
 
     assert_eq!(search.matches.len(), 3);
-    assert_eq!(search.matches[0], 1);
-    assert_eq!(search.matches[1], 4);
-    assert_eq!(search.matches[2], 7);
+    assert_eq!(search.matches.get(0), Some(1));
+    assert_eq!(search.matches.get(1), Some(4));
+    assert_eq!(search.matches.get(2), Some(7));
 }
 
 #[test]
-#[ignore = "Unicode search has byte index bug in implementation"]
 fn test_unicode_search() {
     let mut buffer = Buffer::new(BufferId::new(0));
     buffer.insert(0, "Hello 世界! 世界!");
@@ -368,8 +369,22 @@ fn test_unicode_search() {
     search.set_pattern("世界", SearchDirection::Forward, &buffer);
 
     assert_eq!(search.matches.len(), 2);
-    // 注意：当前实现使用字节索引，"Hello " 是 6 个字节
-    // "世界" 的第一个字符在字节位置 6
+    // 匹配位置和范围都是字符索引："Hello " 是 6 个字符
+    assert_eq!(search.matches.to_vec(), vec![6, 10]);
+    assert_eq!(search.all_match_ranges(), vec![(6, 8), (10, 12)]);
+}
+
+#[test]
+fn test_unicode_search_jumps_to_char_position() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "中文 中文\n第二行 中文\n");
+
+    editor.start_search(SearchDirection::Forward, "中文");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 0));
+    editor.search_next();
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 3));
+    editor.search_next();
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 4));
 }
 
 #[test]
@@ -382,3 +397,228 @@ fn test_pattern_longer_than_buffer() {
 
     assert!(search.matches.is_empty());
 }
+
+// ==================== 匹配计数测试 ====================
+
+#[test]
+fn test_search_count() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "foo bar\nfoo\nbaz foo\n");
+
+    editor.start_search(SearchDirection::Forward, "foo");
+    assert_eq!(editor.search_count(), Some((1, 3)));
+    editor.search_next();
+    assert_eq!(editor.search_count(), Some((2, 3)));
+    editor.search_prev();
+    editor.search_prev();
+    assert_eq!(editor.search_count(), Some((3, 3)));
+
+    // 光标离开匹配后不显示
+    *editor.cursor_mut() = Cursor::new(1, 2);
+    assert_eq!(editor.search_count(), None);
+
+    // 修改缓冲区后 n 重新计数
+    editor.current_buffer_mut().insert(0, "foo\n");
+    editor.search_next();
+    assert_eq!(editor.search_count(), Some((3, 4)));
+}
+
+#[test]
+fn test_substitute_count_only() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "foo foo\nbar\nfoo\n");
+
+    editor.execute_command("%s/foo//gn").unwrap();
+    assert_eq!(editor.message(), Some("3 match(es) on 2 line(s)"));
+    let tick = editor.current_buffer().changedtick();
+    editor.execute_command("%s/foo//gn").unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "foo foo\nbar\nfoo\n");
+    assert_eq!(editor.current_buffer().changedtick(), tick);
+
+    // 模式为空时使用上一次搜索的模式
+    assert!(editor.execute_command("%s///gn").is_err());
+    editor.start_search(SearchDirection::Forward, "bar");
+    editor.execute_command("%s///gn").unwrap();
+    assert_eq!(editor.message(), Some("1 match(es) on 1 line(s)"));
+    assert_eq!(editor.execute_command("%s/qux//gn"), Err("Pattern not found: qux".to_string()));
+}
+
+// ==================== * / # 测试 ====================
+
+#[test]
+fn test_word_under_cursor() {
+    assert_eq!(word_under_cursor("let foo_1 = x;", 5), Some((4, "foo_1".to_string())));
+    // 光标不在单词上时取后面的第一个单词
+    assert_eq!(word_under_cursor("a = (bar)", 3), Some((5, "bar".to_string())));
+    assert_eq!(word_under_cursor("x += 1;", 6), None);
+}
+
+#[test]
+fn test_word_boundary_pattern() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "foo foobar foo_ (foo)");
+
+    let mut search = SearchState::new();
+    search.set_pattern("\\<foo\\>", SearchDirection::Forward, &buffer);
+    assert_eq!(search.matches.to_vec(), vec![0, 17]);
+    assert_eq!(search.all_match_ranges(), vec![(0, 3), (17, 20)]);
+}
+
+#[test]
+fn test_star_and_hash() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "foo foobar\nbar foo\nfoo\n");
+    *editor.cursor_mut() = Cursor::new(0, 1);
+
+    assert!(editor.run_script(&["*"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 4));
+    assert_eq!(editor.register_manager().get('/').unwrap().content, "\\<foo\\>");
+    // n 继续向前，跳过 foobar
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 0));
+
+    // # 向后搜索，之后的 n 也向后
+    assert!(editor.run_script(&["#"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 4));
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 0));
+
+    // g* 不要求单词边界
+    assert!(editor.run_script(&["g*"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 4));
+
+    *editor.cursor_mut() = Cursor::new(2, 3);
+    editor.run_script(&["*"]);
+    assert_eq!(editor.message(), Some("No string under cursor"));
+}
+
+// ==================== 搜索偏移测试 ====================
+
+#[test]
+fn test_parse_search_offset() {
+    assert_eq!(SearchOffset::parse(""), Some(SearchOffset::None));
+    assert_eq!(SearchOffset::parse("e"), Some(SearchOffset::End(0)));
+    assert_eq!(SearchOffset::parse("e-1"), Some(SearchOffset::End(-1)));
+    assert_eq!(SearchOffset::parse("s+2"), Some(SearchOffset::Start(2)));
+    assert_eq!(SearchOffset::parse("b-"), Some(SearchOffset::Start(-1)));
+    assert_eq!(SearchOffset::parse("+"), Some(SearchOffset::Line(1)));
+    assert_eq!(SearchOffset::parse("-2"), Some(SearchOffset::Line(-2)));
+    assert_eq!(SearchOffset::parse("3"), Some(SearchOffset::Line(3)));
+    assert_eq!(SearchOffset::parse("x"), None);
+    assert_eq!(SearchOffset::parse("e+x"), None);
+}
+
+#[test]
+fn test_split_search_input() {
+    assert_eq!(split_search_input("foo", '/'), ("foo".to_string(), ""));
+    assert_eq!(split_search_input("foo/e", '/'), ("foo".to_string(), "e"));
+    // 转义的分隔符属于模式
+    assert_eq!(split_search_input("a\\/b/+1", '/'), ("a/b".to_string(), "+1"));
+    assert_eq!(split_search_input("a/b?e", '?'), ("a/b".to_string(), "e"));
+}
+
+#[test]
+fn test_search_offsets() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "x foo\n  bar\nfoo y\n");
+
+    assert!(editor.run_script(&["/foo/e<CR>"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 4));
+    // 寄存器中只有模式，n 沿用偏移
+    assert_eq!(editor.register_manager().get('/').unwrap().content, "foo");
+    assert_eq!(editor.search_count(), Some((1, 2)));
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 2));
+
+    *editor.cursor_mut() = Cursor::new(0, 0);
+    assert!(editor.run_script(&["/foo/+1<CR>"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 0));
+    // 空模式使用上一次的模式
+    assert!(editor.run_script(&["//s+1<CR>"]).is_ok());
+    assert_eq!((editor.cursor().line, editor.cursor().column), (2, 1));
+
+    editor.run_script(&["/foo/z<CR>"]);
+    assert_eq!(editor.message(), Some("Invalid search offset: z"));
+}
+
+#[test]
+fn test_backward_search_with_end_offset() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "foo foo foo\n");
+    *editor.cursor_mut() = Cursor::new(0, 10);
+
+    // ? 搜索之后 n 继续向后、N 向前，光标在匹配末尾时不会停在同一个匹配上
+    assert!(editor.run_script(&["?foo?e<CR>"]).is_ok());
+    assert_eq!(editor.cursor().column, 10);
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!(editor.cursor().column, 6);
+    assert!(editor.run_script(&["n"]).is_ok());
+    assert_eq!(editor.cursor().column, 2);
+    assert!(editor.run_script(&["N"]).is_ok());
+    assert_eq!(editor.cursor().column, 6);
+    assert_eq!(editor.search_count(), Some((2, 3)));
+}
+
+#[test]
+fn test_delete_to_search_match() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "one two\nthree foo four\n");
+    assert!(editor.run_script(&["d/foo<CR>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "foo four\n");
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "one two\nthree ");
+    // 模式保存到搜索寄存器，之后 n 继续使用
+    assert_eq!(editor.register_manager().get('/').unwrap().content, "foo");
+    assert_eq!(editor.mode(), Mode::Normal);
+}
+
+#[test]
+fn test_change_to_backward_search_match() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "a foo b c\n");
+    *editor.cursor_mut() = Cursor::new(0, 8);
+    assert!(editor.run_script(&["c?foo<CR>X<Esc>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a Xc\n");
+}
+
+#[test]
+fn test_operator_search_with_offsets_and_count() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "x foo y foo z\n");
+    // /e 包括匹配的最后一个字符
+    assert!(editor.run_script(&["d/foo/e<CR>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), " y foo z\n");
+
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "x foo y foo z\n");
+    assert!(editor.run_script(&["y2/foo<CR>"]).is_ok());
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "x foo y ");
+    assert_eq!(editor.cursor().column, 0);
+
+    // 行偏移按整行
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "a\nb\nfoo\nc\n");
+    assert!(editor.run_script(&["d/foo/-1<CR>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "foo\nc\n");
+}
+
+#[test]
+fn test_operator_search_not_found_or_cancelled() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "abc\n");
+    editor.run_script(&["d/zzz<CR>"]);
+    assert_eq!(editor.current_buffer().to_string(), "abc\n");
+    assert_eq!(editor.message(), Some("Pattern not found"));
+
+    // Esc 取消搜索时也取消操作符，之后的 / 是普通搜索
+    assert!(editor.run_script(&["d/b<Esc>", "/c<CR>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "abc\n");
+    assert_eq!(editor.cursor().column, 2);
+}
+
+#[test]
+fn test_delete_to_next_match_with_n() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "a; b; c\n");
+    assert!(editor.run_script(&["/;<CR>", "0dn"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "; b; c\n");
+}
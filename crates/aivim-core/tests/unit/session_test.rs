@@ -0,0 +1,153 @@
+//! 会话单元测试
+//!
+//! 对应源文件: src/session.rs, src/editor.rs (:mksession, :buffer {file}, :wincmd)
+
+use aivim_core::buffer::BufferId;
+use aivim_core::editor::{Editor, EditorOptions};
+use aivim_core::session::{self, layout_commands, option_commands, quote};
+use aivim_core::window::{Layout, SplitDirection, WindowId, WindowManager};
+use aivim_core::Cursor;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 在单独的目录中创建测试文件
+fn session_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = PathBuf::from(format!("/tmp/aivim_session_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        fs::write(dir.join(file), content).unwrap();
+    }
+    dir
+}
+
+/// 布局的形状：窗口显示的文件名，分割写成 `h(...)` / `v(...)`
+fn layout_shape(editor: &Editor, layout: &Layout) -> String {
+    match layout {
+        Layout::Window(id) => {
+            let (buffer, cursor) = editor.window_view(*id).unwrap();
+            format!("{}@{}:{}", buffer.display_name().unwrap_or("-"), cursor.line, cursor.column)
+        }
+        Layout::Split { direction, children, .. } => {
+            let prefix = if *direction == SplitDirection::Horizontal { "h" } else { "v" };
+            let children: Vec<String> = children.iter().map(|child| layout_shape(editor, child)).collect();
+            format!("{}({})", prefix, children.join(" "))
+        }
+    }
+}
+
+// ==================== 会话命令生成测试 ====================
+
+#[test]
+fn test_quote() {
+    assert_eq!(quote("src/main.rs"), "src/main.rs");
+    assert_eq!(quote("my file.txt"), "\"my file.txt\"");
+    assert_eq!(quote("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    assert_eq!(quote(""), "\"\"");
+}
+
+#[test]
+fn test_session_path_relative_to_cwd() {
+    let cwd = Path::new("/home/user/project");
+    assert_eq!(session::session_path(Path::new("/home/user/project/src/a.rs"), cwd), "src/a.rs");
+    assert_eq!(session::session_path(Path::new("src/a.rs"), cwd), "src/a.rs");
+    assert_eq!(session::session_path(Path::new("/etc/hosts"), cwd), "/etc/hosts");
+}
+
+#[test]
+fn test_option_commands_only_changed_options() {
+    assert!(option_commands(&EditorOptions::default()).is_empty());
+
+    let options = EditorOptions { number: true, wrap: false, shiftwidth: 2, ..EditorOptions::default() };
+    assert_eq!(option_commands(&options), vec!["set number", "set nowrap", "set shiftwidth=2"]);
+}
+
+#[test]
+fn test_layout_commands_rebuild_nested_splits() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    windows.split(SplitDirection::Vertical);
+    windows.split(SplitDirection::Horizontal);
+
+    let mut window = |id: WindowId| vec![format!("window {}", id.as_usize())];
+    assert_eq!(
+        layout_commands(windows.layout(), &mut window),
+        vec!["vsplit", "split", "window 2", "wincmd w", "window 1", "wincmd w", "window 0"]
+    );
+}
+
+// ==================== :mksession 测试 ====================
+
+#[test]
+fn test_mksession_round_trip() {
+    let dir = session_dir("round_trip", &[("a.txt", "one\ntwo\nthree\n"), ("b.txt", "alpha beta\n"), ("c d.txt", "x\n")]);
+    let session_file = dir.join("Session.vim");
+
+    let mut editor = Editor::with_file(&dir.join("a.txt")).unwrap();
+    editor.execute_command("set number shiftwidth=2").unwrap();
+    editor.execute_command("2").unwrap();
+    editor.execute_command(&format!("vsplit {}", dir.join("b.txt").display())).unwrap();
+    *editor.cursor_mut() = Cursor::new(0, 6);
+    editor.execute_command("split").unwrap();
+    editor.execute_command(&format!("e {}", quote(&dir.join("c d.txt").to_string_lossy()))).unwrap();
+    editor.execute_command("wincmd w").unwrap();
+    editor.execute_command(&format!("tabnew {}", dir.join("a.txt").display())).unwrap();
+    editor.execute_command("tabnext 1").unwrap();
+
+    let expected_layout = layout_shape(&editor, editor.windows().layout());
+    let expected_window = editor.windows().ids().iter().position(|id| *id == editor.windows().current_id());
+    editor.execute_command(&format!("mksession {}", session_file.display())).unwrap();
+    assert_eq!(editor.message(), Some(format!("Session saved to {}", session_file.display()).as_str()));
+
+    let mut restored = Editor::new();
+    restored.source_file(&session_file).unwrap();
+    assert_eq!(layout_shape(&restored, restored.windows().layout()), expected_layout);
+    assert_eq!(expected_layout, "v(h(c d.txt@0:0 b.txt@0:6) a.txt@1:0)");
+    assert_eq!(
+        restored.windows().ids().iter().position(|id| *id == restored.windows().current_id()),
+        expected_window
+    );
+    assert_eq!(restored.tab_count(), 2);
+    assert_eq!(restored.tab_index(), 0);
+    assert!(restored.options().number);
+    assert_eq!(restored.options().shiftwidth, 2);
+}
+
+#[test]
+fn test_mksession_refuses_to_overwrite() {
+    let dir = session_dir("overwrite", &[("Session.vim", "old\n")]);
+    let session_file = dir.join("Session.vim");
+    let mut editor = Editor::new();
+
+    let err = editor.execute_command(&format!("mksession {}", session_file.display())).unwrap_err();
+    assert_eq!(err, "File exists (add ! to override)");
+    assert_eq!(fs::read_to_string(&session_file).unwrap(), "old\n");
+
+    editor.execute_command(&format!("mksession! {}", session_file.display())).unwrap();
+    assert!(fs::read_to_string(&session_file).unwrap().starts_with("\" aivim session"));
+}
+
+#[test]
+fn test_buffer_command_accepts_file_name() {
+    let dir = session_dir("buffer_name", &[("a.txt", "a\n"), ("b.txt", "b\n")]);
+    let mut editor = Editor::with_file(&dir.join("a.txt")).unwrap();
+    editor.execute_command(&format!("e {}", dir.join("b.txt").display())).unwrap();
+
+    editor.execute_command(&format!("buffer {}", dir.join("a.txt").display())).unwrap();
+    assert_eq!(editor.current_buffer().display_name(), Some("a.txt"));
+    assert!(editor.execute_command("buffer missing.txt").is_err());
+}
+
+#[test]
+fn test_call_cursor() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "one\ntwo words\n");
+    editor.execute_command("call cursor(2, 5)").unwrap();
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 4));
+
+    // 超出范围时移到最后一行的行尾
+    editor.execute_command("call cursor(9, 99)").unwrap();
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 8));
+
+    assert_eq!(editor.execute_command("call foo(1)"), Err("Unknown function: foo".to_string()));
+    assert!(editor.execute_command("call cursor(1)").is_err());
+}
@@ -0,0 +1,80 @@
+//! :set 单元测试
+//!
+//! 对应源文件: src/editor.rs (:set name=value、:set name?、:set all)
+
+use aivim_core::editor::Editor;
+
+#[test]
+fn test_set_value_and_query() {
+    let mut editor = Editor::new();
+    editor.execute_command("set tabstop=4").unwrap();
+    editor.execute_command("set shiftwidth=2").unwrap();
+    assert_eq!(editor.options().tabstop, 4);
+    assert_eq!(editor.options().shiftwidth, 2);
+
+    editor.execute_command("set ts?").unwrap();
+    assert_eq!(editor.message(), Some("tabstop=4"));
+    editor.execute_command("set shiftwidth?").unwrap();
+    assert_eq!(editor.message(), Some("shiftwidth=2"));
+}
+
+#[test]
+fn test_query_boolean_option() {
+    let mut editor = Editor::new();
+    editor.execute_command("set nu?").unwrap();
+    assert_eq!(editor.message(), Some("nonumber"));
+    editor.execute_command("set nu").unwrap();
+    editor.execute_command("set number?").unwrap();
+    assert_eq!(editor.message(), Some("number"));
+}
+
+#[test]
+fn test_query_does_not_change_option() {
+    let mut editor = Editor::new();
+    editor.execute_command("set wrap?").unwrap();
+    assert!(editor.options().wrap);
+    assert_eq!(editor.message(), Some("wrap"));
+}
+
+#[test]
+fn test_bare_value_option_shows_value() {
+    let mut editor = Editor::new();
+    editor.execute_command("set tm=500").unwrap();
+    editor.execute_command("set timeoutlen").unwrap();
+    assert_eq!(editor.message(), Some("timeoutlen=500"));
+    editor.execute_command("set ff").unwrap();
+    assert_eq!(editor.message(), Some("fileformat=unix"));
+}
+
+#[test]
+fn test_query_shows_local_value() {
+    let mut editor = Editor::new();
+    editor.execute_command("setlocal ts=3").unwrap();
+    editor.execute_command("set ts?").unwrap();
+    assert_eq!(editor.message(), Some("tabstop=3"));
+}
+
+#[test]
+fn test_query_unknown_option() {
+    let mut editor = Editor::new();
+    assert!(editor.execute_command("set nosuchoption?").is_err());
+    assert!(editor.execute_command("set nosuchoption").is_err());
+}
+
+#[test]
+fn test_set_all_opens_panel() {
+    let mut editor = Editor::new();
+    editor.execute_command("set all").unwrap();
+    assert!(editor.show_options_panel());
+
+    let values = editor.option_values();
+    assert!(values.contains(&"nonumber".to_string()));
+    assert!(values.iter().any(|value| value.starts_with("tabstop=")));
+    let mut sorted = values.clone();
+    sorted.sort_by_key(|value| value.trim_start_matches("no").split('=').next().unwrap().to_string());
+    assert_eq!(values, sorted);
+
+    // q 关闭面板
+    assert!(editor.run_script(&["q"]).is_ok());
+    assert!(!editor.show_options_panel());
+}
@@ -0,0 +1,172 @@
+//! 外部命令单元测试
+//!
+//! 对应源文件: src/shell.rs, src/editor.rs (:!cmd, :r !cmd, :r {file}, :{range}!cmd, :shell, :suspend)
+
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use aivim_core::editor::Editor;
+use aivim_core::shell::{interactive_shell, TerminalRequest};
+use super::common::editor_with;
+
+/// 轮询直到所有外部命令结束
+fn wait_for_shell(editor: &mut Editor) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        editor.poll_jobs();
+        if editor.pending_shell_commands() == 0 {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("外部命令没有在超时前结束");
+}
+
+fn text(editor: &Editor) -> String {
+    editor.current_buffer().to_string()
+}
+
+// ==================== :!cmd 测试 ====================
+
+#[test]
+fn test_shell_command_shows_output() {
+    let mut editor = editor_with("text\n", 0, 0);
+    assert!(editor.execute_command("!echo one; echo two").is_ok());
+    assert_eq!(editor.pending_shell_commands(), 1);
+    wait_for_shell(&mut editor);
+
+    assert_eq!(editor.message(), Some("one\ntwo"));
+    assert_eq!(text(&editor), "text\n");
+}
+
+#[test]
+fn test_shell_command_failure() {
+    let mut editor = editor_with("text\n", 0, 0);
+    assert!(editor.execute_command("!echo oops >&2; exit 2").is_ok());
+    wait_for_shell(&mut editor);
+    assert_eq!(editor.message(), Some("oops\nShell command failed (exit 2): echo oops >&2; exit 2"));
+}
+
+#[test]
+fn test_shell_command_requires_argument() {
+    let mut editor = editor_with("text\n", 0, 0);
+    assert!(editor.execute_command("!").is_err());
+    assert!(editor.execute_command("r !").is_err());
+}
+
+// ==================== :r 测试 ====================
+
+#[test]
+fn test_read_command_output() {
+    let mut editor = editor_with("a\nb\n", 0, 0);
+    assert!(editor.execute_command("r !printf 'x\\n  y\\n'").is_ok());
+    wait_for_shell(&mut editor);
+
+    assert_eq!(text(&editor), "a\nx\n  y\nb\n");
+    assert_eq!(editor.cursor().line, 1);
+    assert_eq!(editor.message(), Some("2 line(s) read"));
+
+    editor.undo();
+    assert_eq!(text(&editor), "a\nb\n");
+}
+
+#[test]
+fn test_read_file() {
+    let path = "/tmp/aivim_shell_read.txt";
+    fs::write(path, "one\ntwo").unwrap();
+    let mut editor = editor_with("a\nb\n", 0, 0);
+
+    assert!(editor.execute_command("$r /tmp/aivim_shell_read.txt").is_ok());
+    assert_eq!(text(&editor), "a\nb\none\ntwo\n");
+    assert_eq!(editor.cursor().line, 2);
+    assert!(editor.execute_command("r /tmp/aivim_no_such_file.txt").is_err());
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_read_above_first_line() {
+    let path = "/tmp/aivim_shell_read_zero.txt";
+    fs::write(path, "one\n").unwrap();
+    let mut editor = editor_with("a\nb\n", 1, 0);
+
+    // 地址 0 插入到第一行之前，地址 1 插入到第一行之后
+    assert!(editor.execute_command("0r /tmp/aivim_shell_read_zero.txt").is_ok());
+    assert_eq!(text(&editor), "one\na\nb\n");
+    assert_eq!(editor.cursor().line, 0);
+    assert!(editor.execute_command("1r /tmp/aivim_shell_read_zero.txt").is_ok());
+    assert_eq!(text(&editor), "one\none\na\nb\n");
+    assert_eq!(editor.cursor().line, 1);
+
+    assert!(editor.execute_command("0r !echo top").is_ok());
+    wait_for_shell(&mut editor);
+    assert_eq!(text(&editor), "top\none\none\na\nb\n");
+    let _ = fs::remove_file(path);
+}
+
+// ==================== :{range}!cmd 测试 ====================
+
+#[test]
+fn test_filter_range() {
+    let mut editor = editor_with("keep\nc\na\nb\nkeep\n", 0, 0);
+    assert!(editor.execute_command("2,4!sort").is_ok());
+    wait_for_shell(&mut editor);
+
+    assert_eq!(text(&editor), "keep\na\nb\nc\nkeep\n");
+    assert_eq!(editor.message(), Some("3 line(s) filtered"));
+
+    // 一次撤销恢复过滤前的文本
+    editor.undo();
+    assert_eq!(text(&editor), "keep\nc\na\nb\nkeep\n");
+}
+
+#[test]
+fn test_filter_whole_file_without_trailing_newline() {
+    let mut editor = editor_with("b\na", 0, 0);
+    assert!(editor.execute_command("%!sort").is_ok());
+    wait_for_shell(&mut editor);
+    assert_eq!(text(&editor), "a\nb");
+}
+
+#[test]
+fn test_filter_failure_keeps_text() {
+    let mut editor = editor_with("a\nb\n", 0, 0);
+    assert!(editor.execute_command("%!cat >/dev/null; echo bad >&2; exit 1").is_ok());
+    wait_for_shell(&mut editor);
+
+    assert_eq!(text(&editor), "a\nb\n");
+    let message = editor.message().unwrap();
+    assert!(message.starts_with("bad\nShell command failed (exit 1)"));
+}
+
+// ==================== :shell / :suspend ====================
+
+#[test]
+fn test_shell_and_suspend_request_terminal() {
+    let mut editor = Editor::new();
+    assert!(editor.take_terminal_request().is_none());
+
+    assert!(editor.execute_command("sh").is_ok());
+    assert_eq!(editor.take_terminal_request(), Some(TerminalRequest::Shell(interactive_shell())));
+    // 只能取出一次
+    assert!(editor.take_terminal_request().is_none());
+
+    for command in ["sus", "suspend!", "st", "stop"] {
+        assert!(editor.execute_command(command).is_ok(), "{}", command);
+        assert_eq!(editor.take_terminal_request(), Some(TerminalRequest::Suspend), "{}", command);
+    }
+    assert!(editor.execute_command("sh!").is_err());
+}
+
+#[test]
+fn test_ctrl_z_requests_suspend() {
+    let mut editor = editor_with("text\n", 0, 0);
+    assert!(editor.run_script(&["<C-z>"]).is_ok());
+    assert_eq!(editor.take_terminal_request(), Some(TerminalRequest::Suspend));
+    assert_eq!(editor.current_buffer().len_lines(), 2);
+}
+
+#[test]
+fn test_interactive_shell_is_not_empty() {
+    assert!(!interactive_shell().is_empty());
+}
@@ -0,0 +1,157 @@
+//! 启动参数单元测试
+//!
+//! 对应源文件: src/editor.rs, src/buffer.rs (aivim -R 只读模式、aivim - 读入标准输入、+N / -c 命令、--headless 输出)
+
+use aivim_core::line_ending::LineEnding;
+use aivim_core::editor::{Editor, PendingConfirmation};
+use aivim_core::encoding::Encoding;
+use std::fs;
+use std::path::PathBuf;
+
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let path = PathBuf::from(format!("/tmp/aivim_startup_{}.txt", name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+// ==================== 只读模式 ====================
+
+#[test]
+fn test_read_only_mode_blocks_edits() {
+    let path = write_temp("ro_edit", "hello\n");
+    let mut editor = Editor::new();
+    editor.set_read_only_mode(true);
+    editor.open_file(&path).unwrap();
+
+    assert!(editor.current_buffer().is_read_only());
+    editor.execute_command("d").unwrap();
+    assert_eq!(editor.current_buffer().to_string(), "hello\n");
+    assert!(!editor.current_buffer().is_modified());
+}
+
+#[test]
+fn test_read_only_mode_write_needs_bang() {
+    let path = write_temp("ro_write", "hello\n");
+    let mut editor = Editor::new();
+    editor.set_read_only_mode(true);
+    editor.open_file(&path).unwrap();
+
+    // :w 确认之后才写入
+    editor.execute_command("w").unwrap();
+    assert_eq!(editor.pending_confirmation(), Some(&PendingConfirmation::WriteReadOnly(path.clone())));
+    editor.resolve_confirmation(false).unwrap();
+    assert!(editor.execute_command("wq").is_err());
+    assert!(!editor.quit_requested());
+    editor.execute_command("w!").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "hello\n");
+}
+
+#[test]
+fn test_read_only_mode_write_to_other_file() {
+    let path = write_temp("ro_source", "hello\n");
+    let copy = PathBuf::from("/tmp/aivim_startup_ro_copy.txt");
+    let _ = fs::remove_file(&copy);
+    let mut editor = Editor::new();
+    editor.set_read_only_mode(true);
+    editor.open_file(&path).unwrap();
+
+    editor.execute_command(&format!("w {}", copy.display())).unwrap();
+    assert_eq!(fs::read_to_string(&copy).unwrap(), "hello\n");
+}
+
+#[test]
+fn test_buffers_are_writable_by_default() {
+    let path = write_temp("rw", "hello\n");
+    let mut editor = Editor::new();
+    editor.open_file(&path).unwrap();
+
+    assert!(!editor.current_buffer().is_read_only());
+    editor.execute_command("d").unwrap();
+    editor.execute_command("w").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+}
+
+// ==================== 标准输入 ====================
+
+#[test]
+fn test_open_bytes_creates_unnamed_buffer() {
+    let mut editor = Editor::new();
+    editor.open_bytes(b"one\r\ntwo\r\n");
+
+    let buffer = editor.current_buffer();
+    assert_eq!(buffer.file_path(), None);
+    assert_eq!(buffer.to_string(), "one\ntwo\n");
+    assert_eq!(buffer.line_ending(), LineEnding::Crlf);
+    assert!(!buffer.is_modified());
+    assert_eq!(editor.buffer_count(), 2);
+    assert!(editor.execute_command("w").is_err());
+}
+
+#[test]
+fn test_open_bytes_detects_encoding() {
+    let mut editor = Editor::new();
+    editor.open_bytes(&[b'c', b'a', b'f', 0xe9, b'\n']);
+
+    assert_eq!(editor.current_buffer().encoding(), Encoding::Latin1);
+    assert_eq!(editor.current_buffer().to_string(), "café\n");
+}
+
+#[test]
+fn test_open_bytes_read_only_mode() {
+    let mut editor = Editor::new();
+    editor.set_read_only_mode(true);
+    editor.open_bytes(b"text\n");
+    assert!(editor.current_buffer().is_read_only());
+}
+
+// ==================== +N / -c ====================
+
+#[test]
+fn test_line_number_command_jumps_to_line() {
+    let path = write_temp("goto", "one\n  two\nthree\n");
+    let mut editor = Editor::new();
+    editor.open_file(&path).unwrap();
+
+    editor.execute_command("2").unwrap();
+    assert_eq!((editor.cursor().line, editor.cursor().column), (1, 2));
+    editor.execute_command("$").unwrap();
+    assert_eq!(editor.cursor().line, 2);
+}
+
+// ==================== 输出到标准输出 ====================
+
+#[test]
+fn test_write_contents_keeps_format() {
+    let mut editor = Editor::new();
+    editor.open_bytes(&[b'a', 0xe9, b'\r', b'\n', b'b']);
+    editor.execute_command("s/a/x").unwrap();
+
+    let mut out = Vec::new();
+    editor.current_buffer().write_contents(&mut out).unwrap();
+    assert_eq!(out, vec![b'x', 0xe9, b'\r', b'\n', b'b']);
+    assert!(editor.current_buffer().is_modified());
+}
+
+#[test]
+fn test_write_contents_of_binary_buffer() {
+    let path = PathBuf::from("/tmp/aivim_startup_binary.bin");
+    fs::write(&path, [0u8, 1, 2, 255]).unwrap();
+    let mut editor = Editor::new();
+    editor.open_file(&path).unwrap();
+
+    let mut out = Vec::new();
+    editor.current_buffer().write_contents(&mut out).unwrap();
+    assert_eq!(out, vec![0u8, 1, 2, 255]);
+}
+
+#[test]
+fn test_buffer_by_id() {
+    let mut editor = Editor::new();
+    editor.open_bytes(b"stdin\n");
+    let id = editor.current_buffer_id();
+    editor.execute_command("new").unwrap();
+
+    assert_eq!(editor.buffer(id).unwrap().to_string(), "stdin\n");
+    editor.execute_command(&format!("bdelete {}", id.as_usize())).unwrap();
+    assert!(editor.buffer(id).is_none());
+}
@@ -0,0 +1,144 @@
+//! 状态栏单元测试
+//!
+//! 对应源文件: src/statusline.rs, src/git.rs (current_branch), src/git_plugin.rs (Git 分支), src/editor.rs (q / @ 宏)
+
+use aivim_core::editor::Editor;
+use aivim_core::git_plugin::GitPlugin;
+use aivim_core::keymap::{KeyParser, NormalCommand, ParseResult};
+use aivim_core::statusline::{fit, Segment, StatuslineSettings};
+use std::fs;
+use std::process::Command;
+use std::time::Instant;
+use super::common::editor_with;
+
+fn branch(editor: &Editor) -> Option<&str> {
+    editor.plugin::<GitPlugin>().unwrap().branch(editor.current_buffer())
+}
+
+fn text(editor: &Editor) -> String {
+    editor.current_buffer().to_string()
+}
+
+fn parse(keys: &str) -> ParseResult {
+    let mut parser = KeyParser::new();
+    let mut result = ParseResult::Invalid;
+    for key in keys.chars() {
+        result = parser.feed(key);
+    }
+    result
+}
+
+// ==================== 配置测试 ====================
+
+#[test]
+fn test_parse_settings() {
+    let settings = StatuslineSettings::parse("[statusline]\nleft = [\"filename\", \"mode\"]\n").unwrap();
+    assert_eq!(settings.left, vec![Segment::FileName, Segment::Mode]);
+    // 省略的一组使用默认值
+    assert_eq!(settings.right, StatuslineSettings::default().right);
+    // 其他表不影响状态栏
+    assert_eq!(StatuslineSettings::parse("[ai]\nprovider = \"x\"\n").unwrap(), StatuslineSettings::default());
+    assert_eq!(StatuslineSettings::parse("[statusline]\nright = []\n").unwrap().right, Vec::new());
+
+    assert_eq!(
+        StatuslineSettings::parse("[statusline]\nleft = [\"clock\"]\n"),
+        Err("statusline: unknown segment: clock".to_string())
+    );
+    assert!(StatuslineSettings::parse("[statusline]\ncenter = []\n").is_err());
+}
+
+#[test]
+fn test_segment_names() {
+    for segment in Segment::ALL {
+        assert_eq!(Segment::from_name(segment.name()), Some(segment));
+    }
+    assert_eq!(Segment::from_name("Mode"), None);
+}
+
+// ==================== 截断测试 ====================
+
+#[test]
+fn test_fit_drops_low_priority_segments() {
+    let segments = [
+        (Segment::Mode, 9),
+        (Segment::FileName, 10),
+        (Segment::Branch, 5),
+        (Segment::Position, 4),
+        (Segment::Percent, 4),
+    ];
+    assert_eq!(fit(&segments, 40), vec![true; 5]);
+    assert_eq!(fit(&segments, 30), vec![true, true, true, true, false]);
+    assert_eq!(fit(&segments, 23), vec![true, true, false, true, false]);
+    // 文件名不省略
+    assert_eq!(fit(&segments, 5), vec![false, true, false, false, false]);
+    // 没有内容的片段不显示
+    assert_eq!(fit(&[(Segment::Recording, 0), (Segment::FileName, 3)], 10), vec![false, true]);
+}
+
+// ==================== Git 分支测试 ====================
+
+#[test]
+fn test_git_branch_of_buffer() {
+    if Command::new("git").arg("--version").output().is_err() {
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("aivim_statusline_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let status = Command::new("git").args(["init", "-q", "-b", "topic"]).current_dir(&dir).output().unwrap().status;
+    assert!(status.success());
+    fs::write(dir.join("a.txt"), "a\n").unwrap();
+
+    let mut editor = Editor::new();
+    editor.open_file(&dir.join("a.txt")).unwrap();
+    assert_eq!(branch(&editor), None);
+    editor.poll_plugins(Instant::now());
+    assert_eq!(branch(&editor), Some("topic"));
+    // 重新获得焦点时清除，下一次定时检查重新读取
+    editor.focus_gained();
+    assert_eq!(branch(&editor), None);
+
+    // 不在仓库中的缓冲区没有分支
+    let mut other = Editor::new();
+    other.poll_plugins(Instant::now());
+    assert_eq!(branch(&other), None);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+// ==================== 宏测试 ====================
+
+#[test]
+fn test_parse_record_and_replay() {
+    assert_eq!(parse("qa"), ParseResult::Complete(NormalCommand::Record { register: 'a' }));
+    assert_eq!(parse("3@b"), ParseResult::Complete(NormalCommand::Replay { register: 'b', count: 3 }));
+    assert_eq!(parse("@@"), ParseResult::Complete(NormalCommand::Replay { register: '@', count: 1 }));
+}
+
+#[test]
+fn test_record_and_replay_macro() {
+    let mut editor = editor_with("one\ntwo\nthree\nfour\n", 0, 0);
+    assert!(editor.run_script(&["qaA;<Esc>j"]).is_ok());
+    assert_eq!(editor.recording_register(), Some('a'));
+    assert!(editor.run_script(&["q"]).is_ok());
+    assert_eq!(editor.recording_register(), None);
+    assert_eq!(editor.register_manager().get('a').unwrap().content, "A;<Esc>j");
+
+    assert!(editor.run_script(&["@a"]).is_ok());
+    assert_eq!(text(&editor), "one;\ntwo;\nthree\nfour\n");
+    // @@ 执行上一次的宏，计数执行多次
+    assert!(editor.run_script(&["2@@"]).is_ok());
+    assert_eq!(text(&editor), "one;\ntwo;\nthree;\nfour;\n");
+}
+
+#[test]
+fn test_record_append_and_errors() {
+    let mut editor = editor_with("x\n", 0, 0);
+    assert!(editor.run_script(&["qbiab<Esc>q", "qBx<Esc>q"]).is_ok());
+    assert_eq!(editor.register_manager().get('b').unwrap().content, "iab<Esc>x<Esc>");
+
+    editor.run_script(&["@@"]);
+    assert_eq!(editor.message(), Some("No previously used register"));
+    editor.run_script(&["q!"]);
+    assert_eq!(editor.message(), Some("Invalid register: !"));
+    assert_eq!(editor.recording_register(), None);
+}
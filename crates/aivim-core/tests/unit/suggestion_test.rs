@@ -0,0 +1,186 @@
+//! AI 补全建议单元测试
+//!
+//! 对应源文件: src/suggestion.rs, src/ai_plugin.rs (aicomplete 选项、补全建议的请求、Tab / Esc)
+
+use aivim_ai::{AiConfig, ProviderKind};
+use aivim_core::ai_plugin::AiPlugin;
+use aivim_core::editor::Editor;
+use aivim_core::mode::Mode;
+use aivim_core::session;
+use std::fs;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// 打开文本并在第一行末尾进入 Insert 模式
+fn editor_in_insert(text: &str) -> Editor {
+    let mut editor = Editor::new();
+    editor.open_bytes(text.as_bytes());
+    assert!(editor.run_script(&["A"]).is_ok());
+    editor
+}
+
+fn ai(editor: &Editor) -> &AiPlugin {
+    editor.plugin::<AiPlugin>().unwrap()
+}
+
+fn suggestion(editor: &Editor) -> Option<&str> {
+    ai(editor).suggestion(editor)
+}
+
+/// 不经过模型直接在光标处显示建议
+fn show_suggestion(editor: &mut Editor, text: &str) {
+    editor.with_plugin::<AiPlugin, _>(|ai, editor| ai.show_suggestion(editor, text));
+}
+
+// ==================== 接受和丢弃 ====================
+
+#[test]
+fn test_tab_accepts_suggestion() {
+    let mut editor = editor_in_insert("let x\n");
+    show_suggestion(&mut editor, " = compute(\n    1,\n);");
+    assert_eq!(suggestion(&editor), Some(" = compute(\n    1,\n);"));
+
+    assert!(editor.run_script(&["<Tab>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "let x = compute(\n    1,\n);\n");
+    assert_eq!(editor.cursor().line, 2);
+    assert_eq!(editor.cursor().column, 2);
+    assert_eq!(editor.mode(), Mode::Insert);
+    assert_eq!(suggestion(&editor), None);
+}
+
+#[test]
+fn test_esc_dismisses_suggestion() {
+    let mut editor = editor_in_insert("let x\n");
+    show_suggestion(&mut editor, " = 1;");
+
+    assert!(editor.run_script(&["<Esc>"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Insert);
+    assert_eq!(suggestion(&editor), None);
+    assert_eq!(editor.current_buffer().to_string(), "let x\n");
+
+    // 没有建议时 Esc 回到 Normal 模式
+    assert!(editor.run_script(&["<Esc>"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+}
+
+#[test]
+fn test_tab_without_suggestion_inserts_indent() {
+    let mut editor = editor_in_insert("x\n");
+    assert!(editor.run_script(&["<Tab>"]).is_ok());
+    // 插入空格到下一个 shiftwidth 的整数倍
+    assert_eq!(editor.current_buffer().to_string(), "x   \n");
+}
+
+#[test]
+fn test_typing_discards_suggestion() {
+    let mut editor = editor_in_insert("let x\n");
+    show_suggestion(&mut editor, " = 1;");
+    assert!(editor.run_script(&["y"]).is_ok());
+    assert_eq!(suggestion(&editor), None);
+
+    // 离开 Insert 模式后不显示
+    show_suggestion(&mut editor, " = 1;");
+    editor.set_mode(Mode::Normal);
+    assert_eq!(suggestion(&editor), None);
+}
+
+// ==================== 选项 ====================
+
+#[test]
+fn test_ai_options() {
+    let mut editor = Editor::new();
+    assert!(!editor.options().aicomplete);
+    editor.execute_command("set aicomplete").unwrap();
+    editor.execute_command("set aidelay=50").unwrap();
+    editor.execute_command("set aimodel=qwen2.5-coder").unwrap();
+    editor.execute_command("set aiendpoint=http://localhost:11434/v1/chat/completions").unwrap();
+    editor.execute_command("set aikeyenv=MY_KEY").unwrap();
+
+    assert!(editor.options().aicomplete);
+    assert_eq!(editor.options().aidelay, 50);
+    assert_eq!(editor.ai_config().model, "qwen2.5-coder");
+    assert_eq!(editor.ai_config().endpoint, "http://localhost:11434/v1/chat/completions");
+    assert_eq!(editor.ai_config().api_key_env, "MY_KEY");
+    assert!(editor.execute_command("set aidelay=soon").is_err());
+    assert!(editor.execute_command("set aimodel=").is_err());
+
+    let commands = session::option_commands(editor.options());
+    assert!(commands.contains(&"set aicomplete".to_string()));
+    assert!(commands.contains(&"set aidelay=50".to_string()));
+}
+
+#[test]
+fn test_ai_provider_from_config_toml() {
+    let path = std::env::temp_dir().join(format!("aivim_ai_config_{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        "[ai]\nprovider = \"anthropic\"\n\n[ai.anthropic]\nmodel = \"claude-test\"\n\n[ai.ollama]\nmodel = \"local\"\n",
+    )
+    .unwrap();
+    let mut editor = Editor::new();
+    editor.load_ai_settings(&path).unwrap();
+    assert_eq!(editor.ai_config().provider, ProviderKind::Anthropic);
+    assert_eq!(editor.ai_config().model, "claude-test");
+
+    // 切换服务时使用 config.toml 中该服务的设置
+    editor.execute_command("set aiprovider=ollama").unwrap();
+    assert_eq!(editor.ai_config().provider, ProviderKind::Ollama);
+    assert_eq!(editor.ai_config().model, "local");
+    editor.execute_command("set aiprovider=openai").unwrap();
+    assert_eq!(editor.ai_config(), &AiConfig::default());
+    assert!(editor.execute_command("set aiprovider=gemini").is_err());
+
+    fs::write(&path, "[ai]\nprovider = 1\n").unwrap();
+    assert!(editor.load_ai_settings(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_no_request_when_disabled() {
+    let mut editor = editor_in_insert("let x\n");
+    editor.execute_command("set aidelay=0").unwrap();
+    let now = Instant::now();
+    editor.poll_plugins(now);
+    editor.poll_plugins(now + Duration::from_secs(1));
+    assert!(!ai(&editor).suggestion_pending());
+    assert_eq!(suggestion(&editor), None);
+}
+
+// ==================== 请求 ====================
+
+/// 用 `file://` 地址代替服务，curl 读取事先写好的响应；系统没有 curl 时跳过
+#[test]
+fn test_poll_requests_after_delay() {
+    if Command::new("curl").arg("--version").output().is_err() {
+        return;
+    }
+    let response = "/tmp/aivim_suggestion_response.txt";
+    fs::write(
+        response,
+        "data: {\"choices\":[{\"delta\":{\"content\":\" = \"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"42;\"}}]}\n\ndata: [DONE]\n",
+    )
+    .unwrap();
+
+    let mut editor = editor_in_insert("let x\n");
+    editor.execute_command("set aicomplete").unwrap();
+    editor.execute_command("set aidelay=100").unwrap();
+    editor.execute_command(&format!("set aiendpoint=file://{}", response)).unwrap();
+    editor.execute_command("set aikeyenv=AIVIM_TEST_UNSET_KEY").unwrap();
+
+    // 光标停留的时间不够时不请求
+    let start = Instant::now();
+    editor.poll_plugins(start);
+    editor.poll_plugins(start + Duration::from_millis(50));
+    assert!(!ai(&editor).suggestion_pending());
+
+    editor.poll_plugins(start + Duration::from_millis(100));
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while ai(&editor).suggestion_pending() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        editor.poll_plugins(start + Duration::from_millis(100));
+    }
+    assert_eq!(suggestion(&editor), Some(" = 42;"));
+
+    assert!(editor.run_script(&["<Tab><Esc>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "let x = 42;\n");
+}
@@ -0,0 +1,163 @@
+//! 语法树文本对象单元测试
+//!
+//! 对应源文件: src/syntax.rs, src/text_object.rs (af/if/ac/ic), src/editor.rs (operator_range)
+
+use aivim_core::buffer::{Buffer, BufferId};
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::keymap::{KeyParser, NormalCommand, ParseResult};
+use aivim_core::syntax::{syntax_tree, SyntaxLanguage};
+use aivim_core::text_object::{parse_text_object, TextObject};
+use std::path::Path;
+
+const RUST: &str = "\
+struct Point {
+    x: i32,
+}
+
+impl Point {
+    #[inline]
+    fn norm(&self) -> i32 {
+        let y = self.x;
+        y * y
+    }
+}
+";
+
+fn buffer(filetype: &str, text: &str) -> Buffer {
+    let mut buffer = Buffer::new(BufferId::new(1));
+    buffer.set_filetype(filetype);
+    buffer.insert(0, text);
+    buffer
+}
+
+/// 文本对象在 (行, 列) 处的文本
+fn object_text(buffer: &Buffer, object: TextObject, line: usize, column: usize) -> Option<String> {
+    let (start, end) = object.get_range(&Cursor::new(line, column), buffer)?;
+    Some(buffer.slice(start..end).to_string())
+}
+
+/// 在编辑器中执行操作符命令（如 `daf`）
+fn run(editor: &mut Editor, keys: &str) -> Option<String> {
+    let mut parser = KeyParser::new();
+    let mut result = ParseResult::Invalid;
+    for key in keys.chars() {
+        result = parser.feed(key);
+    }
+    match result {
+        ParseResult::Complete(NormalCommand::Operator(command)) => editor.execute_operator(command),
+        other => panic!("{:?} 不是操作符命令: {:?}", keys, other),
+    }
+}
+
+#[test]
+fn test_language_detection() {
+    assert_eq!(SyntaxLanguage::from_extension("rs"), Some(SyntaxLanguage::Rust));
+    assert_eq!(SyntaxLanguage::from_extension("tsx"), None);
+    assert_eq!(SyntaxLanguage::from_extension("md"), None);
+    let mut buffer = Buffer::new_with_path(BufferId::new(1), Path::new("/tmp/main.go"));
+    assert_eq!(SyntaxLanguage::for_buffer(&buffer), Some(SyntaxLanguage::Go));
+    buffer.set_filetype("python");
+    assert_eq!(SyntaxLanguage::for_buffer(&buffer), Some(SyntaxLanguage::Python));
+    assert!(syntax_tree(&Buffer::new(BufferId::new(2))).is_none());
+}
+
+#[test]
+fn test_syntax_tree_follows_changes() {
+    let mut buffer = buffer("rust", "fn a() {}\n");
+    let (_, tree) = syntax_tree(&buffer).unwrap();
+    assert_eq!(tree.root_node().named_child_count(), 1);
+    buffer.insert(buffer.len_chars(), "fn b() {}\n");
+    let (_, tree) = syntax_tree(&buffer).unwrap();
+    assert_eq!(tree.root_node().named_child_count(), 2);
+}
+
+#[test]
+fn test_parse_text_object_syntax() {
+    assert_eq!(parse_text_object('f'), Some(TextObject::AroundFunction));
+    assert_eq!(parse_text_object('F'), Some(TextObject::InnerFunction));
+    assert_eq!(parse_text_object('c'), Some(TextObject::AroundClass));
+    assert_eq!(parse_text_object('C'), Some(TextObject::InnerClass));
+}
+
+#[test]
+fn test_rust_function_objects() {
+    let buffer = buffer("rust", RUST);
+    // 整个函数连同属性，扩展为整行
+    let function = "    #[inline]\n    fn norm(&self) -> i32 {\n        let y = self.x;\n        y * y\n    }\n";
+    assert_eq!(object_text(&buffer, TextObject::AroundFunction, 7, 12).as_deref(), Some(function));
+    // 光标在行首的缩进中也能找到
+    assert_eq!(object_text(&buffer, TextObject::AroundFunction, 6, 0).as_deref(), Some(function));
+    assert_eq!(object_text(&buffer, TextObject::AroundFunction, 5, 6).as_deref(), Some(function));
+    // 函数体为花括号之间的行
+    assert_eq!(
+        object_text(&buffer, TextObject::InnerFunction, 6, 8).as_deref(),
+        Some("        let y = self.x;\n        y * y\n")
+    );
+    // 函数之外没有函数
+    assert_eq!(object_text(&buffer, TextObject::AroundFunction, 1, 4), None);
+}
+
+#[test]
+fn test_rust_class_objects() {
+    let buffer = buffer("rust", RUST);
+    assert_eq!(object_text(&buffer, TextObject::AroundClass, 1, 4).as_deref(), Some("struct Point {\n    x: i32,\n}\n"));
+    assert_eq!(object_text(&buffer, TextObject::InnerClass, 0, 0).as_deref(), Some("    x: i32,\n"));
+    // 最内层的类型定义：函数中为 impl 块
+    let inner = object_text(&buffer, TextObject::InnerClass, 8, 8).unwrap();
+    assert!(inner.starts_with("    #[inline]\n") && inner.ends_with("    }\n"), "{:?}", inner);
+}
+
+#[test]
+fn test_single_line_function_is_charwise() {
+    let buffer = buffer("rust", "let f = |x| { x + 1 };\n");
+    assert_eq!(object_text(&buffer, TextObject::AroundFunction, 0, 10).as_deref(), Some("|x| { x + 1 }"));
+    assert_eq!(object_text(&buffer, TextObject::InnerFunction, 0, 10).as_deref(), Some("x + 1"));
+    // 空的函数体没有内容
+    let buffer = self::buffer("rust", "fn a() {}\n");
+    assert_eq!(object_text(&buffer, TextObject::InnerFunction, 0, 0), None);
+}
+
+#[test]
+fn test_python_objects() {
+    let text = "class A:\n    @staticmethod\n    def f(x):\n        return x\n\n    def g(self):\n        pass\n";
+    let buffer = buffer("python", text);
+    assert_eq!(
+        object_text(&buffer, TextObject::AroundFunction, 3, 8).as_deref(),
+        Some("    @staticmethod\n    def f(x):\n        return x\n")
+    );
+    assert_eq!(object_text(&buffer, TextObject::InnerFunction, 2, 4).as_deref(), Some("        return x\n"));
+    assert_eq!(object_text(&buffer, TextObject::InnerFunction, 1, 6).as_deref(), Some("        return x\n"));
+    assert_eq!(object_text(&buffer, TextObject::AroundClass, 6, 8).as_deref(), Some(text));
+}
+
+#[test]
+fn test_go_type_objects() {
+    let buffer = buffer("go", "type P struct {\n\tX int\n}\n\nfunc (p P) F() int {\n\treturn p.X\n}\n");
+    assert_eq!(object_text(&buffer, TextObject::InnerClass, 0, 0).as_deref(), Some("\tX int\n"));
+    assert_eq!(object_text(&buffer, TextObject::InnerFunction, 5, 1).as_deref(), Some("\treturn p.X\n"));
+}
+
+#[test]
+fn test_operators_on_syntax_objects() {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().set_filetype("rust");
+    editor.current_buffer_mut().insert(0, RUST);
+    *editor.cursor_mut() = Cursor::new(8, 8);
+
+    // yaf 按行复制整个函数
+    assert!(run(&mut editor, "yaf").unwrap().starts_with("    #[inline]\n"));
+    // 光标在属性上时也作用于函数
+    assert_eq!(editor.cursor().line, 5);
+    // dif 删除函数体，花括号保留
+    run(&mut editor, "dif");
+    assert_eq!(editor.get_line_text(6).unwrap(), "    fn norm(&self) -> i32 {");
+    assert_eq!(editor.get_line_text(7).unwrap(), "    }");
+    // daf 删除整个函数
+    *editor.cursor_mut() = Cursor::new(6, 4);
+    run(&mut editor, "daf");
+    assert_eq!(editor.current_buffer().rope().to_string(), "struct Point {\n    x: i32,\n}\n\nimpl Point {\n}\n");
+    // 没有语法的缓冲区中无效
+    editor.current_buffer_mut().set_filetype("text");
+    assert_eq!(run(&mut editor, "dac"), None);
+}
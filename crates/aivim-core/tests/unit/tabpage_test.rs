@@ -0,0 +1,208 @@
+//! 标签页单元测试
+//!
+//! 对应源文件: src/tabpage.rs, src/editor.rs (:tabnew / :tabclose、gt / gT)
+
+use aivim_core::buffer::BufferId;
+use aivim_core::cursor::Cursor;
+use aivim_core::tabpage::{TabLabel, TabPages};
+use aivim_core::window::WindowManager;
+use super::common::editor_with;
+
+/// 显示缓冲区 `buffer` 的单窗口布局
+fn tab(buffer: usize) -> WindowManager {
+    WindowManager::new(BufferId::new(buffer))
+}
+
+/// 标签页按顺序显示的缓冲区
+fn buffers(tabs: &TabPages, current: &WindowManager) -> Vec<usize> {
+    tabs.all(current).iter().map(|windows| windows.current().buffer.as_usize()).collect()
+}
+
+// ==================== 标签页列表测试 ====================
+
+#[test]
+fn test_insert_after_current() {
+    let mut tabs = TabPages::new();
+    let mut current = tab(0);
+    tabs.insert(&mut current, tab(1));
+    tabs.insert(&mut current, tab(2));
+    assert_eq!(tabs.len(), 3);
+    assert_eq!(tabs.current_index(), 2);
+    assert_eq!(buffers(&tabs, &current), vec![0, 1, 2]);
+
+    tabs.switch(&mut current, 0);
+    tabs.insert(&mut current, tab(3));
+    assert_eq!(tabs.current_index(), 1);
+    assert_eq!(buffers(&tabs, &current), vec![0, 3, 1, 2]);
+}
+
+#[test]
+fn test_switch_keeps_order() {
+    let mut tabs = TabPages::new();
+    let mut current = tab(0);
+    tabs.insert(&mut current, tab(1));
+    tabs.insert(&mut current, tab(2));
+    tabs.insert(&mut current, tab(3));
+
+    assert!(tabs.switch(&mut current, 1));
+    assert_eq!(current.current().buffer.as_usize(), 1);
+    assert_eq!(buffers(&tabs, &current), vec![0, 1, 2, 3]);
+    assert!(tabs.switch(&mut current, 3));
+    assert_eq!(current.current().buffer.as_usize(), 3);
+    assert_eq!(buffers(&tabs, &current), vec![0, 1, 2, 3]);
+    assert!(!tabs.switch(&mut current, 3));
+    assert!(!tabs.switch(&mut current, 4));
+}
+
+#[test]
+fn test_close_moves_to_next_tab() {
+    let mut tabs = TabPages::new();
+    let mut current = tab(0);
+    tabs.insert(&mut current, tab(1));
+    tabs.insert(&mut current, tab(2));
+    tabs.switch(&mut current, 1);
+
+    assert!(tabs.close(&mut current));
+    assert_eq!(current.current().buffer.as_usize(), 2);
+    assert_eq!(buffers(&tabs, &current), vec![0, 2]);
+    // 关闭最后一个标签页后切换到前一个
+    assert!(tabs.close(&mut current));
+    assert_eq!(buffers(&tabs, &current), vec![0]);
+    assert!(!tabs.close(&mut current));
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_tabnew_opens_empty_buffer() {
+    let mut editor = editor_with("main\n", 0, 2);
+    let main_buffer = editor.current_buffer_id();
+    editor.execute_command("tabnew").unwrap();
+    assert_eq!(editor.tab_count(), 2);
+    assert_eq!(editor.tab_index(), 1);
+    assert_ne!(editor.current_buffer_id(), main_buffer);
+    assert_eq!(editor.windows().len(), 1);
+
+    // 切换回来时恢复原标签页的缓冲区和光标
+    editor.previous_tab(1);
+    assert_eq!(editor.current_buffer_id(), main_buffer);
+    assert_eq!(*editor.cursor(), Cursor::new(0, 2));
+}
+
+#[test]
+fn test_tabnew_with_file() {
+    let dir = std::env::temp_dir().join(format!("aivim_tabpage_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("other.txt");
+    std::fs::write(&path, "other\n").unwrap();
+
+    let mut editor = editor_with("main\n", 0, 0);
+    editor.execute_command(&format!("tabnew {}", path.display())).unwrap();
+    assert_eq!(editor.get_line_text(0).as_deref(), Some("other"));
+
+    // 打开失败（这里打开的是目录）时不保留新标签页
+    assert!(editor.execute_command(&format!("tabe {}", dir.display())).is_err());
+    assert_eq!(editor.tab_count(), 2);
+    assert_eq!(editor.tab_index(), 1);
+    assert_eq!(editor.get_line_text(0).as_deref(), Some("other"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_tabs_keep_separate_layouts() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.execute_command("split").unwrap();
+    editor.execute_command("tabnew").unwrap();
+    assert_eq!(editor.windows().len(), 1);
+    editor.execute_command("vsplit").unwrap();
+    editor.execute_command("vsplit").unwrap();
+
+    editor.next_tab(None);
+    assert_eq!(editor.tab_index(), 0);
+    assert_eq!(editor.windows().len(), 2);
+    editor.next_tab(None);
+    assert_eq!(editor.windows().len(), 3);
+}
+
+#[test]
+fn test_next_and_previous_tab() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command("tabnew").unwrap();
+    assert_eq!(editor.tab_index(), 2);
+
+    // gt 在最后一个标签页之后回到第一个
+    editor.next_tab(None);
+    assert_eq!(editor.tab_index(), 0);
+    // {count}gt 切换到第 count 个标签页
+    editor.next_tab(Some(2));
+    assert_eq!(editor.tab_index(), 1);
+    editor.next_tab(Some(9));
+    assert_eq!(editor.tab_index(), 1);
+    // gT 向前切换 count 个，首尾相接
+    editor.previous_tab(2);
+    assert_eq!(editor.tab_index(), 2);
+
+    editor.execute_command("tabn 1").unwrap();
+    assert_eq!(editor.tab_index(), 0);
+    editor.execute_command("tabp").unwrap();
+    assert_eq!(editor.tab_index(), 2);
+}
+
+#[test]
+fn test_tabclose_and_quit() {
+    let mut editor = editor_with("text\n", 0, 0);
+    let main_buffer = editor.current_buffer_id();
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command("tabc").unwrap();
+    assert_eq!(editor.tab_count(), 1);
+    assert_eq!(editor.current_buffer_id(), main_buffer);
+    assert_eq!(
+        editor.execute_command("tabclose"),
+        Err("Cannot close last tab page".to_string())
+    );
+
+    // 标签页只剩一个窗口时 :q 关闭标签页而不是退出
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command("q").unwrap();
+    assert_eq!(editor.tab_count(), 1);
+    assert!(!editor.quit_requested());
+
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command("tabonly").unwrap();
+    assert_eq!(editor.tab_count(), 1);
+    assert_eq!(editor.tab_index(), 0);
+}
+
+#[test]
+fn test_tab_labels() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command("split").unwrap();
+
+    let labels = editor.tab_labels();
+    assert_eq!(
+        labels,
+        vec![
+            TabLabel { title: None, modified: true, windows: 1 },
+            TabLabel { title: None, modified: false, windows: 2 },
+        ]
+    );
+}
+
+#[test]
+fn test_deleted_buffer_leaves_other_tabs() {
+    let mut editor = editor_with("text\n", 0, 0);
+    let main_buffer = editor.current_buffer_id();
+    editor.execute_command("tabnew").unwrap();
+    let scratch = editor.current_buffer_id();
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command(&format!("bd {}", scratch.as_usize())).unwrap();
+
+    // 第二个标签页改为显示当前缓冲区
+    editor.previous_tab(1);
+    assert_ne!(editor.current_buffer_id(), scratch);
+    assert_ne!(editor.current_buffer_id(), main_buffer);
+}
@@ -0,0 +1,129 @@
+//! 文件跟踪（:tail）单元测试
+//!
+//! 对应源文件: src/tail.rs, src/editor.rs (:tail)
+
+use aivim_core::buffer::BufferId;
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::tail::{TailState, TailUpdate};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+fn append(path: &Path, text: &[u8]) {
+    let mut file = OpenOptions::new().append(true).open(path).unwrap();
+    file.write_all(text).unwrap();
+}
+
+// ==================== TailState 测试 ====================
+
+#[test]
+fn test_tail_state_reads_appended_content() {
+    let path = Path::new("/tmp/aivim_tail_state.log");
+    fs::write(path, "one\n").unwrap();
+
+    let mut state = TailState::new(BufferId::new(0), 4, false);
+    assert_eq!(state.poll(path).unwrap(), TailUpdate::Unchanged);
+
+    append(path, b"two\n");
+    assert_eq!(state.poll(path).unwrap(), TailUpdate::Appended("two\n".to_string()));
+    assert_eq!(state.offset(), 8);
+
+    fs::write(path, "x\n").unwrap();
+    assert_eq!(state.poll(path).unwrap(), TailUpdate::Truncated);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_tail_state_waits_for_complete_utf8() {
+    let path = Path::new("/tmp/aivim_tail_utf8.log");
+    fs::write(path, "").unwrap();
+    let mut state = TailState::new(BufferId::new(0), 0, false);
+
+    // "世" 的 UTF-8 编码为 3 个字节，先只写入前 2 个
+    let bytes = "世".as_bytes();
+    append(path, &bytes[..2]);
+    assert_eq!(state.poll(path).unwrap(), TailUpdate::Unchanged);
+
+    append(path, &bytes[2..]);
+    assert_eq!(state.poll(path).unwrap(), TailUpdate::Appended("世".to_string()));
+    fs::remove_file(path).unwrap();
+}
+
+// ==================== :tail 命令测试 ====================
+
+#[test]
+fn test_tail_command_follows_file() {
+    let path = Path::new("/tmp/aivim_tail_follow.log");
+    fs::write(path, "a\nb\n").unwrap();
+
+    let mut editor = Editor::with_file(path).unwrap();
+    editor.execute_command("tail").unwrap();
+    assert!(editor.is_tailing());
+    assert!(editor.current_buffer().is_read_only());
+
+    append(path, b"c\nd\n");
+    assert!(editor.poll_tail());
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\nd\n");
+    assert!(!editor.current_buffer().is_modified());
+    // 光标跟随到末尾
+    assert!(editor.cursor().line >= 3);
+
+    assert!(!editor.poll_tail());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_tail_stops_following_when_cursor_moves() {
+    let path = Path::new("/tmp/aivim_tail_nofollow.log");
+    fs::write(path, "a\nb\nc\n").unwrap();
+
+    let mut editor = Editor::with_file(path).unwrap();
+    editor.execute_command("tail").unwrap();
+    *editor.cursor_mut() = Cursor::new(0, 0);
+
+    append(path, b"d\n");
+    assert!(editor.poll_tail());
+    assert_eq!(editor.cursor().line, 0);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_tail_reloads_truncated_file() {
+    let path = Path::new("/tmp/aivim_tail_truncate.log");
+    fs::write(path, "old line 1\nold line 2\n").unwrap();
+
+    let mut editor = Editor::with_file(path).unwrap();
+    editor.execute_command("tail").unwrap();
+
+    fs::write(path, "new\n").unwrap();
+    assert!(editor.poll_tail());
+    assert_eq!(editor.current_buffer().to_string(), "new\n");
+
+    append(path, b"more\n");
+    assert!(editor.poll_tail());
+    assert_eq!(editor.current_buffer().to_string(), "new\nmore\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_tail_toggle_restores_read_only() {
+    let path = Path::new("/tmp/aivim_tail_toggle.log");
+    fs::write(path, "x\n").unwrap();
+
+    let mut editor = Editor::with_file(path).unwrap();
+    editor.execute_command("tail").unwrap();
+    editor.execute_command("tail").unwrap();
+
+    assert!(!editor.is_tailing());
+    assert!(!editor.current_buffer().is_read_only());
+    assert!(!editor.poll_tail());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_tail_requires_saved_file() {
+    let mut editor = Editor::new();
+    assert_eq!(editor.execute_command("tail"), Err("No file name".to_string()));
+    assert!(!editor.is_tailing());
+}
@@ -0,0 +1,83 @@
+//! 终端缓冲区单元测试
+//!
+//! 对应源文件: src/terminal.rs, src/editor.rs (:terminal, Terminal 模式)
+
+use aivim_core::editor::Editor;
+use aivim_core::mapping::MapKey;
+use aivim_core::mode::Mode;
+use aivim_core::terminal::key_bytes;
+use std::time::{Duration, Instant};
+
+/// 轮询终端直到 `done` 成立
+fn wait_for(editor: &mut Editor, done: impl Fn(&Editor) -> bool) {
+    let start = Instant::now();
+    while !done(editor) {
+        editor.poll_terminals();
+        assert!(start.elapsed() < Duration::from_secs(10), "terminal did not finish");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn buffer_text(editor: &Editor) -> String {
+    editor.current_buffer().to_string()
+}
+
+#[test]
+fn test_key_bytes() {
+    assert_eq!(key_bytes(MapKey::Char('é'), false), Some("é".as_bytes().to_vec()));
+    assert_eq!(key_bytes(MapKey::Ctrl('c'), false), Some(vec![3]));
+    assert_eq!(key_bytes(MapKey::Ctrl('\\'), false), Some(vec![0x1c]));
+    assert_eq!(key_bytes(MapKey::Enter, false), Some(b"\r".to_vec()));
+    assert_eq!(key_bytes(MapKey::Backspace, false), Some(vec![0x7f]));
+    // 应用光标键模式下方向键使用 SS3
+    assert_eq!(key_bytes(MapKey::Up, false), Some(b"\x1b[A".to_vec()));
+    assert_eq!(key_bytes(MapKey::Up, true), Some(b"\x1bOA".to_vec()));
+    assert_eq!(key_bytes(MapKey::Ctrl('1'), false), None);
+}
+
+#[test]
+fn test_terminal_command_output_and_exit() {
+    let mut editor = Editor::new();
+    let previous = editor.current_buffer_id();
+    editor.execute_command("terminal echo hello-from-pty").unwrap();
+    assert_eq!(editor.windows().len(), 2);
+    assert_ne!(editor.current_buffer_id(), previous);
+    assert_eq!(editor.mode(), Mode::Terminal);
+    assert!(editor.terminal(editor.current_buffer_id()).is_some());
+
+    wait_for(&mut editor, |editor| {
+        editor.terminal(editor.current_buffer_id()).is_some_and(|job| !job.is_running())
+    });
+    let text = buffer_text(&editor);
+    assert!(text.contains("hello-from-pty"), "{:?}", text);
+    assert!(text.ends_with("[Process exited 0]"), "{:?}", text);
+    // 程序退出后回到 Normal 模式，缓冲区没有修改
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert!(!editor.current_buffer().is_modified());
+    assert!(editor.run_script(&["i"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.message(), Some("Terminal process has exited"));
+}
+
+#[test]
+fn test_terminal_mode_sends_keys_and_escapes() {
+    let mut editor = Editor::new();
+    editor.execute_command("terminal cat").unwrap();
+    assert!(editor.run_script(&["abc<CR>"]).is_ok());
+    wait_for(&mut editor, |editor| buffer_text(editor).matches("abc").count() >= 2);
+
+    // Ctrl+\ Ctrl+N 回到 Normal 模式，可以在输出中移动
+    assert!(editor.run_script(&["<C-\\><C-n>"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert!(editor.run_script(&["gg"]).is_ok());
+    assert_eq!(editor.cursor().line, 0);
+    assert!(editor.run_script(&["a"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Terminal);
+
+    // 程序还在运行时 :bd 报错，:bd! 结束程序
+    assert!(editor.run_script(&["<C-\\><C-n>"]).is_ok());
+    let id = editor.current_buffer_id();
+    assert!(editor.execute_command("bd").is_err());
+    editor.execute_command("bd!").unwrap();
+    assert!(editor.terminal(id).is_none());
+}
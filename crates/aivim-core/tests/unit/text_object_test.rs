@@ -27,6 +27,12 @@ fn test_parse_text_object_paragraph() {
     assert_eq!(parse_text_object('P'), Some(TextObject::InnerParagraph));
 }
 
+#[test]
+fn test_parse_text_object_tag() {
+    assert_eq!(parse_text_object('t'), Some(TextObject::AroundTag));
+    assert_eq!(parse_text_object('T'), Some(TextObject::InnerTag));
+}
+
 #[test]
 fn test_parse_text_object_invalid() {
     assert_eq!(parse_text_object('x'), None);
@@ -106,7 +112,6 @@ fn test_inner_word_on_whitespace() {
 }
 
 #[test]
-#[ignore = "Empty buffer causes panic in text_object.rs - needs fix"]
 fn test_inner_word_empty_buffer() {
     let buffer = Buffer::new(BufferId::new(0));
     let cursor = Cursor::at_origin();
@@ -115,8 +120,59 @@ fn test_inner_word_empty_buffer() {
     assert_eq!(range, None);
 }
 
+#[test]
+fn test_inner_word_empty_line() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "hello\n\nworld");
+
+    let cursor = Cursor::new(1, 0);
+    let range = TextObject::InnerWord.get_range(&cursor, &buffer);
+
+    assert_eq!(range, None);
+}
+
+#[test]
+fn test_inner_word_multibyte() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "héllo 世界 end");
+
+    // 光标在 "世界" 上，范围按字符计算
+    let cursor = Cursor::new(0, 7);
+    let range = TextObject::InnerWord.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((6, 8)));
+
+    let cursor = Cursor::new(0, 1);
+    let range = TextObject::InnerWord.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((0, 5)));
+}
+
+#[test]
+fn test_inner_word_at_line_end_ignores_newline() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "foo bar\nbaz");
+
+    // 光标越过行尾时落在最后一个单词上
+    let cursor = Cursor::new(0, 10);
+    let range = TextObject::InnerWord.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((4, 7)));
+}
+
 // ==================== Around Word (aw) 测试 ====================
 
+#[test]
+fn test_around_word_multibyte_with_space() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "世界 hello");
+
+    let cursor = Cursor::new(0, 0);
+    let range = TextObject::AroundWord.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((0, 3)));
+}
+
 #[test]
 fn test_around_word_with_space() {
     let mut buffer = Buffer::new(BufferId::new(0));
@@ -187,28 +243,148 @@ fn test_around_sentence_not_implemented() {
     assert_eq!(range, None); // TODO: 实现后需要更新
 }
 
-// ==================== 段落测试 (未实现，测试返回 None) ====================
+// ==================== 段落测试 (ip / ap) ====================
 
 #[test]
-fn test_inner_paragraph_not_implemented() {
+fn test_inner_paragraph() {
     let mut buffer = Buffer::new(BufferId::new(0));
     buffer.insert(0, "Paragraph 1\n\nParagraph 2");
 
     let cursor = Cursor::new(0, 0);
     let range = TextObject::InnerParagraph.get_range(&cursor, &buffer);
 
-    assert_eq!(range, None); // TODO: 实现后需要更新
+    assert_eq!(range, Some((0, 12))); // "Paragraph 1\n"
 }
 
 #[test]
-fn test_around_paragraph_not_implemented() {
+fn test_around_paragraph() {
     let mut buffer = Buffer::new(BufferId::new(0));
     buffer.insert(0, "Paragraph 1\n\nParagraph 2");
 
     let cursor = Cursor::new(0, 0);
     let range = TextObject::AroundParagraph.get_range(&cursor, &buffer);
 
-    assert_eq!(range, None); // TODO: 实现后需要更新
+    assert_eq!(range, Some((0, 13))); // 包含后面的空行
+}
+
+#[test]
+fn test_around_last_paragraph_takes_preceding_blank() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "a\n\nb\nc\n");
+
+    let cursor = Cursor::new(3, 0);
+    let range = TextObject::AroundParagraph.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((2, 7)));
+}
+
+#[test]
+fn test_inner_paragraph_on_blank_lines() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "a\n\n\nb\n");
+
+    let cursor = Cursor::new(1, 0);
+    let range = TextObject::InnerParagraph.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((2, 4)));
+}
+
+// ==================== 标签测试 (it / at) ====================
+
+#[test]
+fn test_inner_tag_basic() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "<div>hello</div>\n");
+
+    // 光标在 "hello" 上
+    let cursor = Cursor::new(0, 7);
+    let range = TextObject::InnerTag.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((5, 10))); // "hello"
+}
+
+#[test]
+fn test_around_tag_basic() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "<div>hello</div>\n");
+
+    let cursor = Cursor::new(0, 7);
+    let range = TextObject::AroundTag.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((0, 16))); // "<div>hello</div>"
+}
+
+#[test]
+fn test_tag_cursor_on_open_tag() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "<p>text</p>");
+
+    // 光标在开标签 "<p>" 上
+    let cursor = Cursor::new(0, 1);
+    let range = TextObject::InnerTag.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((3, 7))); // "text"
+}
+
+#[test]
+fn test_tag_nested_innermost() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "<ul><li>one</li></ul>");
+
+    // 光标在 "one" 上，应选中最内层的 <li>
+    let cursor = Cursor::new(0, 9);
+    assert_eq!(TextObject::InnerTag.get_range(&cursor, &buffer), Some((8, 11)));
+    assert_eq!(TextObject::AroundTag.get_range(&cursor, &buffer), Some((4, 16)));
+}
+
+#[test]
+fn test_tag_nested_same_name() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "<div>a<div>b</div>c</div>");
+
+    // 光标在 "c" 上，应匹配外层 div 而不是内层
+    let cursor = Cursor::new(0, 18);
+    let range = TextObject::InnerTag.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((5, 19))); // "a<div>b</div>c"
+}
+
+#[test]
+fn test_tag_with_attributes_multiline() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "<a href=\"x\">\n  link\n</a>\n");
+
+    let cursor = Cursor::new(1, 3);
+    let range = TextObject::InnerTag.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((12, 20))); // "\n  link\n"
+}
+
+#[test]
+fn test_tag_ignores_self_closing_and_comments() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "<p><br/><!-- c -->x</p>");
+
+    let cursor = Cursor::new(0, 18);
+    let range = TextObject::InnerTag.get_range(&cursor, &buffer);
+
+    assert_eq!(range, Some((3, 19)));
+}
+
+#[test]
+fn test_tag_outside_any_tag() {
+    let mut buffer = Buffer::new(BufferId::new(0));
+    buffer.insert(0, "plain <b>bold</b>");
+
+    let cursor = Cursor::new(0, 1);
+    assert_eq!(TextObject::InnerTag.get_range(&cursor, &buffer), None);
+}
+
+#[test]
+fn test_tag_empty_buffer() {
+    let buffer = Buffer::new(BufferId::new(0));
+    let cursor = Cursor::at_origin();
+    assert_eq!(TextObject::AroundTag.get_range(&cursor, &buffer), None);
 }
 
 // ==================== 边界情况测试 ====================
@@ -0,0 +1,149 @@
+//! 持久化撤销历史单元测试
+//!
+//! 对应源文件: src/undo_file.rs, src/undo_tree.rs (encode / decode), src/editor.rs (:set undofile)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::undo_file::undo_file_path;
+use aivim_core::undo_tree::{UndoLimits, UndoTree};
+use ropey::Rope;
+
+/// 打开文件并开启 undofile，撤销目录使用 `undo_dir`
+fn open_with_undofile(path: &Path, undo_dir: &Path) -> Editor {
+    let mut editor = Editor::new();
+    editor.set_undo_dir(undo_dir.to_path_buf());
+    assert!(editor.execute_command("set undofile").is_ok());
+    editor.open_file(path).unwrap();
+    editor
+}
+
+fn text(editor: &Editor) -> String {
+    editor.current_buffer().to_string()
+}
+
+// ==================== 编码测试 ====================
+
+#[test]
+fn test_encode_decode_round_trip() {
+    let mut tree = UndoTree::new();
+    let origin = Cursor::at_origin();
+    tree.record(&Rope::from_str("你好\nworld\n"), origin);
+    tree.record(&Rope::from_str("你们好\nworld\n"), Cursor::new(0, 1));
+    // 撤销后开出新分支
+    let state = tree.undo(&Rope::from_str("你们好\nworld!\n"), origin).unwrap();
+    tree.record(&state.text, origin);
+    tree.record(&Rope::from_str("你们好\n"), Cursor::new(1, 0));
+
+    let mut decoded = UndoTree::decode(&tree.encode(), UndoLimits::default()).unwrap();
+    assert_eq!(decoded.len(), tree.len());
+    assert_eq!(decoded.current(), tree.current());
+    assert_eq!(decoded.bytes(), tree.bytes());
+    assert_eq!(decoded.current_text(), tree.current_text());
+
+    let current = decoded.current_text().unwrap().clone();
+    let state = decoded.undo(&current, origin).unwrap();
+    assert_eq!(state.text.to_string(), "你们好\nworld\n");
+    let state = decoded.travel(&state.text, origin, -10).unwrap();
+    assert_eq!(state.text.to_string(), "你好\nworld\n");
+    assert_eq!(decoded.leaves().len(), 2);
+}
+
+#[test]
+fn test_decode_rejects_garbage() {
+    assert!(UndoTree::decode(b"not an undo file\n", UndoLimits::default()).is_none());
+    assert!(UndoTree::decode(b"aivim-undo 1\ncurrent 3 next 4\n", UndoLimits::default()).is_none());
+}
+
+#[test]
+fn test_undo_file_path_is_stable() {
+    let dir = Path::new("/tmp/undo");
+    let a = undo_file_path(dir, Path::new("/tmp/aivim_no_such_file_a.txt"));
+    assert_eq!(a, undo_file_path(dir, Path::new("/tmp/aivim_no_such_file_a.txt")));
+    assert_ne!(a, undo_file_path(dir, Path::new("/tmp/aivim_no_such_file_b.txt")));
+    assert!(a.starts_with(dir));
+}
+
+// ==================== :set undofile 测试 ====================
+
+fn setup(name: &str, content: &str) -> (PathBuf, PathBuf) {
+    let path = PathBuf::from(format!("/tmp/aivim_undofile_{}.txt", name));
+    let undo_dir = PathBuf::from(format!("/tmp/aivim_undofile_{}_dir", name));
+    let _ = fs::remove_dir_all(&undo_dir);
+    fs::write(&path, content).unwrap();
+    (path, undo_dir)
+}
+
+fn cleanup(path: &Path, undo_dir: &Path) {
+    let _ = fs::remove_file(path);
+    let _ = fs::remove_dir_all(undo_dir);
+}
+
+#[test]
+fn test_undo_across_sessions() {
+    let (path, undo_dir) = setup("session", "abc\n");
+    {
+        let mut editor = open_with_undofile(&path, &undo_dir);
+        editor.replace_chars('x', 1);
+        editor.save().unwrap();
+    }
+
+    let mut editor = open_with_undofile(&path, &undo_dir);
+    assert_eq!(text(&editor), "xbc\n");
+    editor.undo();
+    assert_eq!(text(&editor), "abc\n");
+    editor.redo();
+    assert_eq!(text(&editor), "xbc\n");
+    cleanup(&path, &undo_dir);
+}
+
+#[test]
+fn test_undo_file_ignored_after_external_change() {
+    let (path, undo_dir) = setup("external", "abc\n");
+    {
+        let mut editor = open_with_undofile(&path, &undo_dir);
+        editor.replace_chars('x', 1);
+        editor.save().unwrap();
+    }
+    fs::write(&path, "changed\n").unwrap();
+
+    let mut editor = open_with_undofile(&path, &undo_dir);
+    editor.undo();
+    assert_eq!(text(&editor), "changed\n");
+    assert_eq!(editor.message(), Some("Already at oldest change"));
+    cleanup(&path, &undo_dir);
+}
+
+#[test]
+fn test_undofile_off_does_not_write() {
+    let (path, undo_dir) = setup("off", "abc\n");
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.set_undo_dir(undo_dir.clone());
+    editor.replace_chars('x', 1);
+    editor.save().unwrap();
+    assert!(!undo_dir.exists());
+    cleanup(&path, &undo_dir);
+}
+
+#[test]
+fn test_set_undofile_restores_open_buffer() {
+    let (path, undo_dir) = setup("late", "abc");
+    {
+        let mut editor = open_with_undofile(&path, &undo_dir);
+        editor.replace_chars('x', 1);
+        editor.save().unwrap();
+    }
+    // 原文件没有末尾的换行符，保存时也不补上
+    assert_eq!(fs::read_to_string(&path).unwrap(), "xbc");
+
+    // 打开文件之后才开启 undofile
+    let mut editor = Editor::with_file(&path).unwrap();
+    editor.set_undo_dir(undo_dir.clone());
+    assert!(editor.execute_command("set undofile").is_ok());
+    assert_eq!(text(&editor), "xbc");
+    editor.undo();
+    assert_eq!(text(&editor), "abc");
+    cleanup(&path, &undo_dir);
+}
@@ -0,0 +1,312 @@
+//! 撤销树单元测试
+//!
+//! 对应源文件: src/undo_tree.rs, src/editor.rs (undo, redo, undo_travel)
+
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::mode::Mode;
+use aivim_core::undo_tree::{UndoLimits, UndoTree};
+use ropey::Rope;
+use super::common::editor_with;
+
+fn text(editor: &Editor) -> String {
+    editor.current_buffer().to_string()
+}
+
+// ==================== UndoTree 测试 ====================
+
+#[test]
+fn test_undo_redo_linear() {
+    let mut tree = UndoTree::new();
+    let origin = Cursor::at_origin();
+    tree.record(&Rope::from_str("a"), origin);
+
+    let state = tree.undo(&Rope::from_str("ab"), origin).unwrap();
+    assert_eq!(state.text.to_string(), "a");
+    assert!(tree.undo(&state.text, origin).is_none());
+
+    let state = tree.redo(&Rope::from_str("a"), origin).unwrap();
+    assert_eq!(state.text.to_string(), "ab");
+    assert!(tree.redo(&state.text, origin).is_none());
+}
+
+#[test]
+fn test_record_same_text_does_not_add_node() {
+    let mut tree = UndoTree::new();
+    let text = Rope::from_str("a");
+    tree.record(&text, Cursor::at_origin());
+    tree.record(&text, Cursor::new(0, 1));
+    assert_eq!(tree.len(), 1);
+
+    // 撤销回到最后一次记录的光标位置
+    let state = tree.undo(&Rope::from_str("ab"), Cursor::at_origin()).unwrap();
+    assert_eq!(state.cursor, Cursor::new(0, 1));
+}
+
+#[test]
+fn test_branch_is_kept_after_new_change() {
+    let mut tree = UndoTree::new();
+    let origin = Cursor::at_origin();
+    tree.record(&Rope::from_str("a"), origin);
+    // 修改为 "ab" 后撤销，再修改为 "ac"
+    let state = tree.undo(&Rope::from_str("ab"), origin).unwrap();
+    tree.record(&state.text, origin);
+    let state = tree.undo(&Rope::from_str("ac"), origin).unwrap();
+    assert_eq!(state.text.to_string(), "a");
+
+    // 重做进入最近的分支
+    let state = tree.redo(&state.text, origin).unwrap();
+    assert_eq!(state.text.to_string(), "ac");
+
+    // 按时间顺序可以回到旧分支
+    let state = tree.travel(&state.text, origin, -1).unwrap();
+    assert_eq!(state.text.to_string(), "ab");
+    assert_eq!(tree.current(), Some(1));
+
+    let leaves = tree.leaves();
+    assert_eq!(leaves.len(), 2);
+    assert_eq!((leaves[0].seq, leaves[0].changes), (1, 1));
+    assert_eq!((leaves[1].seq, leaves[1].changes), (2, 1));
+}
+
+#[test]
+fn test_travel_clamps_to_ends() {
+    let mut tree = UndoTree::new();
+    let origin = Cursor::at_origin();
+    tree.record(&Rope::from_str("a"), origin);
+    let state = tree.travel(&Rope::from_str("ab"), origin, -10).unwrap();
+    assert_eq!(state.text.to_string(), "a");
+    assert!(tree.travel(&state.text, origin, -1).is_none());
+
+    let state = tree.travel(&state.text, origin, 10).unwrap();
+    assert_eq!(state.text.to_string(), "ab");
+}
+
+// ==================== Editor 测试 ====================
+
+#[test]
+fn test_editor_redo_after_undo() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    editor.replace_chars('x', 1);
+    editor.undo();
+    assert_eq!(text(&editor), "abc\n");
+    editor.redo();
+    assert_eq!(text(&editor), "xbc\n");
+}
+
+#[test]
+fn test_insert_session_is_one_undo_step() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    editor.set_mode(Mode::Insert);
+    editor.insert_char('x');
+    editor.insert_newline();
+    editor.insert_char('y');
+    editor.backspace();
+    editor.insert_char('z');
+    editor.set_mode(Mode::Normal);
+    assert_eq!(text(&editor), "x\nzabc\n");
+
+    editor.undo();
+    assert_eq!(text(&editor), "abc\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 0));
+    editor.redo();
+    assert_eq!(text(&editor), "x\nzabc\n");
+}
+
+#[test]
+fn test_undo_redo_with_count() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    editor.replace_chars('x', 1);
+    editor.replace_chars('y', 1);
+    editor.replace_chars('z', 1);
+
+    editor.undo_steps(2);
+    assert_eq!(text(&editor), "xbc\n");
+    editor.redo_steps(2);
+    assert_eq!(text(&editor), "zbc\n");
+
+    // 次数超过历史时停在最早的状态，不显示消息
+    editor.undo_steps(10);
+    assert_eq!(text(&editor), "abc\n");
+    assert_eq!(editor.message(), None);
+    editor.undo_steps(1);
+    assert_eq!(editor.message(), Some("Already at oldest change"));
+}
+
+#[test]
+fn test_editor_g_minus_reaches_overwritten_branch() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    editor.replace_chars('x', 1);
+    editor.undo();
+    editor.replace_chars('y', 1);
+    assert_eq!(text(&editor), "ybc\n");
+
+    // 普通的撤销/重做只能在新分支上移动
+    editor.undo();
+    editor.redo();
+    assert_eq!(text(&editor), "ybc\n");
+
+    // g- 按时间顺序：ybc -> xbc -> abc
+    editor.undo_travel(-1);
+    assert_eq!(text(&editor), "xbc\n");
+    editor.undo_travel(-1);
+    assert_eq!(text(&editor), "abc\n");
+    editor.undo_travel(2);
+    assert_eq!(text(&editor), "ybc\n");
+}
+
+#[test]
+fn test_editor_undo_at_oldest_sets_message() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    editor.undo();
+    assert_eq!(editor.message(), Some("Already at oldest change"));
+    assert_eq!(text(&editor), "abc\n");
+}
+
+#[test]
+fn test_undo_history_is_per_buffer() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    editor.replace_chars('x', 1);
+    editor.create_new_buffer();
+    editor.undo();
+    assert_eq!(editor.message(), Some("Already at oldest change"));
+}
+
+#[test]
+fn test_undolist() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    assert!(editor.execute_command("undolist").is_ok());
+    assert_eq!(editor.message(), Some("Nothing to undo"));
+
+    editor.replace_chars('x', 1);
+    editor.undo();
+    editor.replace_chars('y', 1);
+    editor.undo();
+    let list = editor.format_undo_list();
+    assert!(list.starts_with("number changes when | 1 1 "));
+    assert!(list.contains(" | 2 1 "));
+}
+
+// ==================== 历史大小限制测试 ====================
+
+/// 依次记录 "a", "ab", "abc" ... 共 `count` 个状态
+fn record_chain(tree: &mut UndoTree, count: usize) -> Rope {
+    let mut text = String::new();
+    for i in 0..count {
+        text.push((b'a' + i as u8) as char);
+        tree.record(&Rope::from_str(&text), Cursor::at_origin());
+    }
+    Rope::from_str(&text)
+}
+
+#[test]
+fn test_undolevels_evicts_oldest() {
+    let mut tree = UndoTree::with_limits(UndoLimits {
+        levels: 2,
+        ..UndoLimits::default()
+    });
+    let text = record_chain(&mut tree, 5);
+    assert_eq!(tree.len(), 3);
+
+    let origin = Cursor::at_origin();
+    let state = tree.undo(&text, origin).unwrap();
+    assert_eq!(state.text.to_string(), "abcd");
+    let state = tree.undo(&state.text, origin).unwrap();
+    assert_eq!(state.text.to_string(), "abc");
+    assert!(tree.undo(&state.text, origin).is_none());
+}
+
+#[test]
+fn test_undo_memory_limit() {
+    let mut tree = UndoTree::with_limits(UndoLimits {
+        levels: 100,
+        max_bytes: 2,
+    });
+    record_chain(&mut tree, 6);
+    // 每个状态比父状态多 1 字节，最早的状态不计入，保留 "abcd"、"abcde"、"abcdef" 三个状态
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.bytes(), 2);
+}
+
+#[test]
+fn test_memory_limit_counts_changed_bytes() {
+    let mut tree = UndoTree::with_limits(UndoLimits {
+        levels: 100,
+        max_bytes: 1024,
+    });
+    let origin = Cursor::at_origin();
+    let mut text = Rope::from_str(&"0123456789\n".repeat(100_000));
+    tree.record(&text, origin);
+    // 删除中间的一行：只计算删除的 11 字节，不计算整个文本
+    text.remove(500_000..500_011);
+    tree.record(&text, origin);
+    text.insert(0, "x");
+    let state = tree.undo(&text, origin).unwrap();
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.bytes(), 12);
+    let state = tree.undo(&state.text, origin).unwrap();
+    assert_eq!(state.text.len_bytes(), 1_100_000);
+}
+
+#[test]
+fn test_memory_limit_keeps_current_state() {
+    let mut tree = UndoTree::with_limits(UndoLimits {
+        levels: 100,
+        max_bytes: 0,
+    });
+    record_chain(&mut tree, 3);
+    assert_eq!(tree.len(), 1);
+    assert_eq!(tree.current(), Some(2));
+}
+
+#[test]
+fn test_eviction_drops_old_branches() {
+    let mut tree = UndoTree::new();
+    let origin = Cursor::at_origin();
+    tree.record(&Rope::from_str("a"), origin);
+    // 根节点 "a" 下有 "ab"、"ac" 两个分支
+    let state = tree.undo(&Rope::from_str("ab"), origin).unwrap();
+    tree.record(&state.text, origin);
+    tree.record(&Rope::from_str("ac"), origin);
+    assert_eq!(tree.len(), 3);
+
+    tree.set_limits(UndoLimits {
+        levels: 0,
+        ..UndoLimits::default()
+    });
+    assert_eq!(tree.len(), 1);
+    assert!(tree.leaves().is_empty());
+    assert!(tree.travel(&Rope::from_str("ac"), origin, -1).is_none());
+}
+
+#[test]
+fn test_set_undolevels() {
+    let mut editor = editor_with("abc\n", 0, 0);
+    assert!(editor.execute_command("set undolevels=1").is_ok());
+    assert_eq!(editor.options().undolevels, 1);
+
+    editor.replace_chars('x', 1);
+    editor.replace_chars('y', 1);
+    editor.undo();
+    assert_eq!(text(&editor), "xbc\n");
+    editor.undo();
+    assert_eq!(text(&editor), "xbc\n");
+    assert_eq!(editor.message(), Some("Already at oldest change"));
+
+    assert!(editor.execute_command("set undomaxmem=64").is_ok());
+    assert_eq!(editor.options().undomaxmem, 64);
+    assert!(editor.execute_command("set ul=-1").is_err());
+}
+
+#[test]
+fn test_undo_in_file_larger_than_undomaxmem() {
+    // 2 MB 的文本，撤销历史的上限为 1 MB
+    let mut editor = editor_with(&"0123456789abcdefghijklmnopqrstu\n".repeat(64 * 1024), 100, 0);
+    assert!(editor.execute_command("set undomaxmem=1").is_ok());
+    assert!(editor.run_script(&["dd", "dd"]).is_ok());
+    assert_eq!(editor.current_buffer().len_lines(), 64 * 1024 - 1);
+    editor.undo();
+    editor.undo();
+    assert_eq!(editor.current_buffer().len_chars(), 2 * 1024 * 1024);
+    assert_eq!(editor.message().filter(|message| message.contains("oldest")), None);
+}
@@ -0,0 +1,240 @@
+//! Visual 模式单元测试
+//!
+//! 对应源文件: src/visual.rs, src/editor.rs (选择区域、作用于选择区域的操作符、o / O、gv), src/input.rs
+
+use aivim_core::buffer::{Buffer, BufferId};
+use aivim_core::cursor::Cursor;
+use aivim_core::editor::Editor;
+use aivim_core::visual::VisualSelection;
+use aivim_core::Mode;
+use super::common::editor_with;
+
+fn lines(selection: Option<VisualSelection>) -> Option<(usize, usize)> {
+    selection.map(|selection| selection.lines())
+}
+
+/// 选择区域的锚点、光标位置和是否按行选择
+fn ends(editor: &Editor) -> ((usize, usize), (usize, usize), bool) {
+    let selection = editor.visual_selection().unwrap();
+    let position = |cursor: Cursor| (cursor.line, cursor.column);
+    (position(selection.anchor), position(selection.cursor), selection.linewise)
+}
+
+#[test]
+fn test_selection_range_and_columns() {
+    let mut editor = editor_with("hello\nworld\n", 0, 0);
+    let selection = VisualSelection::new(Cursor::new(1, 2), Cursor::new(0, 3), false);
+    assert_eq!(selection.start(), Cursor::new(0, 3));
+    assert_eq!(selection.char_range(editor.current_buffer()), (3, 9));
+    assert_eq!(selection.columns(0, 5), Some((3, 5)));
+    assert_eq!(selection.columns(1, 5), Some((0, 3)));
+    assert_eq!(selection.columns(2, 0), None);
+
+    let linewise = VisualSelection::new(Cursor::new(1, 2), Cursor::new(0, 3), true);
+    assert_eq!(linewise.char_range(editor.current_buffer()), (0, 11));
+    assert_eq!(linewise.columns(0, 5), Some((0, 5)));
+
+    // 文本变短后限制在缓冲区范围内
+    editor.current_buffer_mut().remove(2, 10);
+    assert_eq!(selection.clamp(editor.current_buffer()).anchor, Cursor::new(0, 1));
+}
+
+#[test]
+fn test_v_extends_selection_with_motions() {
+    let mut editor = editor_with("one two\nthree\n", 0, 0);
+    assert!(editor.run_script(&["vw"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Visual);
+    assert_eq!(ends(&editor), ((0, 0), (0, 4), false));
+
+    // V 切换为按行选择，再按 V 回到 Normal 模式
+    assert!(editor.run_script(&["Vj"]).is_ok());
+    assert_eq!(lines(editor.visual_selection()), Some((0, 1)));
+    assert!(editor.visual_selection().unwrap().linewise);
+    assert!(editor.run_script(&["V"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert!(editor.visual_selection().is_none());
+}
+
+#[test]
+fn test_visual_operator_keys_are_not_pending_operators() {
+    let mut editor = editor_with("one\ntwo\nthree\n", 0, 0);
+    // gu 在 Visual 模式下不等待动作，之后的 j 仍然移动光标
+    assert!(editor.run_script(&["Vguj"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "one\ntwo\nthree\n");
+    assert_eq!(lines(editor.visual_selection()), Some((0, 1)));
+}
+
+#[test]
+fn test_visual_indent_is_single_undo_and_gv_reselects() {
+    let mut editor = editor_with("a\nb\nc\n", 0, 0);
+    assert!(editor.run_script(&["Vj>"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.current_buffer().to_string(), "    a\n    b\nc\n");
+    assert_eq!(editor.cursor().line, 0);
+
+    // gv 重新选中同样的行，可以继续缩进
+    assert!(editor.run_script(&["gv"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Visual);
+    assert_eq!(lines(editor.visual_selection()), Some((0, 1)));
+    assert!(editor.run_script(&["<"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\n");
+
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "    a\n    b\nc\n");
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\n");
+}
+
+#[test]
+fn test_visual_case_operators() {
+    let mut editor = editor_with("hello world\nAbc\n", 0, 6);
+    assert!(editor.run_script(&["veU"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "hello WORLD\nAbc\n");
+    assert_eq!(editor.cursor().column, 6);
+
+    assert!(editor.run_script(&["0Vj~"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "HELLO world\naBC\n");
+    assert_eq!((editor.cursor().line, editor.cursor().column), (0, 0));
+
+    assert!(editor.run_script(&["vu"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "hELLO world\naBC\n");
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "HELLO world\naBC\n");
+}
+
+#[test]
+fn test_visual_join_and_format() {
+    let mut editor = editor_with("a\nb\nc\nd\n", 0, 0);
+    assert!(editor.run_script(&["VjjJ"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "a b c\nd\n");
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "a\nb\nc\nd\n");
+
+    assert!(editor.run_script(&["ggjVjgq"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.current_buffer().to_string(), "a\nb c\nd\n");
+}
+
+#[test]
+fn test_visual_delete_yank_and_change() {
+    let mut editor = editor_with("abcdef\nghi\njkl\n", 0, 0);
+    assert!(editor.run_script(&["vlld"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.current_buffer().to_string(), "def\nghi\njkl\n");
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "abc");
+
+    assert!(editor.run_script(&["vllyP"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "defdef\nghi\njkl\n");
+
+    // 按行选择时按整行删除，x 与 d 相同；一次撤销恢复
+    assert!(editor.run_script(&["jVjx"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "defdef\n");
+    let register = editor.register_manager().get('"').unwrap();
+    assert_eq!((register.content.as_str(), register.linewise), ("ghi\njkl\n", true));
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "defdef\nghi\njkl\n");
+
+    assert!(editor.run_script(&["ggVj\"ay"]).is_ok());
+    assert_eq!(editor.register_manager().get('a').unwrap().content, "defdef\nghi\n");
+    assert_eq!((editor.cursor().line, editor.mode()), (0, Mode::Normal));
+
+    assert!(editor.run_script(&["wvec"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Insert);
+}
+
+#[test]
+fn test_visual_change_keeps_line() {
+    let mut editor = editor_with("one\ntwo\nthree\n", 1, 0);
+    assert!(editor.run_script(&["Vcnew<Esc>"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "one\nnew\nthree\n");
+}
+
+#[test]
+fn test_visual_put_replaces_selection() {
+    let mut editor = editor_with("one two\nthree\n", 0, 0);
+    assert!(editor.run_script(&["yiwwvep"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.current_buffer().to_string(), "one one\nthree\n");
+    // 被替换的文本放入无名寄存器
+    assert_eq!(editor.register_manager().get('"').unwrap().content, "two");
+    editor.undo();
+    assert_eq!(editor.current_buffer().to_string(), "one two\nthree\n");
+
+    // 整行内容替换选中的行
+    assert!(editor.run_script(&["yyjVp"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "one two\none two\n");
+
+    // 按字符的内容替换选中的行时单独成行
+    assert!(editor.run_script(&["gg\"byiwjV\"bp"]).is_ok());
+    assert_eq!(editor.current_buffer().to_string(), "one two\none\n");
+    assert_eq!(editor.cursor().line, 1);
+}
+
+#[test]
+fn test_visual_surround() {
+    let mut editor = editor_with("say hello there\n", 0, 4);
+    assert!(editor.run_script(&["veS)"]).is_ok());
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.current_buffer().to_string(), "say (hello) there\n");
+}
+
+#[test]
+fn test_gv_swaps_with_current_selection() {
+    let mut editor = editor_with("one\ntwo\nthree\n", 0, 0);
+    assert!(editor.run_script(&["gv"]).is_ok());
+    assert_eq!(editor.message(), Some("No previous Visual selection"));
+    assert_eq!(editor.mode(), Mode::Normal);
+
+    assert!(editor.run_script(&["vl<Esc>", "jjV"]).is_ok());
+    assert_eq!(editor.cursor().line, 2);
+    assert!(editor.run_script(&["gv"]).is_ok());
+    assert_eq!(ends(&editor), ((0, 0), (0, 1), false));
+    assert!(editor.run_script(&["gv"]).is_ok());
+    assert_eq!(lines(editor.visual_selection()), Some((2, 2)));
+}
+
+#[test]
+fn test_o_swaps_selection_ends() {
+    let mut editor = editor_with("one two three\nfour\n", 0, 4);
+    assert!(editor.run_script(&["vw"]).is_ok());
+    assert_eq!(ends(&editor), ((0, 4), (0, 8), false));
+    assert!(editor.run_script(&["o"]).is_ok());
+    assert_eq!(ends(&editor), ((0, 8), (0, 4), false));
+    // 之后的动作从另一端扩展
+    assert!(editor.run_script(&["b"]).is_ok());
+    assert_eq!(ends(&editor), ((0, 8), (0, 0), false));
+    assert!(editor.run_script(&["O"]).is_ok());
+    assert_eq!(ends(&editor), ((0, 0), (0, 8), false));
+
+    // 离开 Visual 模式后 gv 恢复交换之后的选择区域
+    assert!(editor.run_script(&["<Esc>", "j", "gv"]).is_ok());
+    assert_eq!(ends(&editor), ((0, 0), (0, 8), false));
+}
+
+#[test]
+fn test_last_selection_is_per_buffer() {
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    let first = editor.current_buffer_id();
+    assert!(editor.run_script(&["Vj<Esc>"]).is_ok());
+
+    let other = BufferId::new(100);
+    editor.buffers_mut().insert(other, Buffer::new(other));
+    editor.switch_buffer(other).unwrap();
+    assert!(editor.last_visual_selection().is_none());
+
+    editor.switch_buffer(first).unwrap();
+    assert!(editor.run_script(&["gv"]).is_ok());
+    assert_eq!(lines(editor.visual_selection()), Some((0, 1)));
+}
+
+#[test]
+fn test_visual_cells_cover_wide_chars_and_empty_lines() {
+    let mut editor = editor_with("a中b\n\nxy\n", 0, 0);
+    assert!(editor.run_script(&["vl"]).is_ok());
+    assert_eq!(editor.visual_cells(80, 20), vec![(0, 0), (1, 0), (2, 0)]);
+
+    assert!(editor.run_script(&["<Esc>", "Vj"]).is_ok());
+    assert_eq!(editor.visual_cells(80, 20), vec![(0, 0), (1, 0), (2, 0), (3, 0), (0, 1)]);
+    assert!(editor.run_script(&["<Esc>"]).is_ok());
+    assert!(editor.visual_cells(80, 20).is_empty());
+}
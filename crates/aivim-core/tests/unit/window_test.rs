@@ -0,0 +1,388 @@
+//! 窗口单元测试
+//!
+//! 对应源文件: src/window.rs, src/editor.rs (:split / :vsplit、Ctrl+W 窗口命令)
+
+use aivim_core::buffer::BufferId;
+use aivim_core::cursor::Cursor;
+use aivim_core::window::{Layout, Side, SplitDirection, WindowManager, WindowRect};
+use super::common::editor_with;
+
+fn rect(x: usize, y: usize, width: usize, height: usize) -> WindowRect {
+    WindowRect { x, y, width, height }
+}
+
+// ==================== 布局测试 ====================
+
+#[test]
+fn test_split_places_new_window_first() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    let first = windows.current_id();
+    let second = windows.split(SplitDirection::Horizontal);
+    assert_eq!(windows.current_id(), second);
+    assert_eq!(windows.ids(), vec![second, first]);
+    assert_eq!(windows.len(), 2);
+}
+
+#[test]
+fn test_split_after_places_new_window_last() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Vertical);
+    windows.set_current(a);
+    let c = windows.split_after(SplitDirection::Vertical);
+    assert_eq!(windows.current_id(), c);
+    assert_eq!(
+        windows.layout(),
+        &Layout::Split {
+            direction: SplitDirection::Vertical,
+            children: vec![Layout::Window(b), Layout::Window(a), Layout::Window(c)],
+            sizes: vec![2, 1, 1],
+        }
+    );
+}
+
+#[test]
+fn test_split_same_direction_shares_level() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Vertical);
+    let c = windows.split(SplitDirection::Vertical);
+    assert_eq!(
+        windows.layout(),
+        &Layout::Split {
+            direction: SplitDirection::Vertical,
+            children: vec![Layout::Window(c), Layout::Window(b), Layout::Window(a)],
+            // c 和 b 平分 b 原来的一半
+            sizes: vec![1, 1, 2],
+        }
+    );
+}
+
+#[test]
+fn test_layout_rects() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Vertical);
+    let c = windows.split(SplitDirection::Horizontal);
+    // 41 列去掉一列分隔线后平分；左侧 21 行上下平分，多出的一行给上面的窗口
+    assert_eq!(
+        windows.layout_rects(rect(0, 0, 41, 21)),
+        vec![
+            (c, rect(0, 0, 20, 11)),
+            (b, rect(0, 11, 20, 10)),
+            (a, rect(21, 0, 20, 21)),
+        ]
+    );
+}
+
+#[test]
+fn test_close_collapses_split() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Vertical);
+    let c = windows.split(SplitDirection::Horizontal);
+
+    assert!(windows.close(c));
+    // 关闭当前窗口后，布局顺序中的下一个窗口成为当前窗口
+    assert_eq!(windows.current_id(), b);
+    assert_eq!(
+        windows.layout(),
+        &Layout::Split {
+            direction: SplitDirection::Vertical,
+            children: vec![Layout::Window(b), Layout::Window(a)],
+            sizes: vec![1, 1],
+        }
+    );
+
+    assert!(windows.close(b));
+    assert_eq!(windows.layout(), &Layout::Window(a));
+    assert_eq!(windows.current_id(), a);
+    // 最后一个窗口不能关闭
+    assert!(!windows.close(a));
+}
+
+#[test]
+fn test_close_merges_same_direction() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Horizontal);
+    let c = windows.split(SplitDirection::Vertical);
+    let d = windows.split(SplitDirection::Horizontal);
+    // 布局为 H[V[H[d, c], b], a]，关闭 b 后 V 只剩 H[d, c]，与外层方向相同，合并为一层
+    windows.close(b);
+    assert_eq!(
+        windows.layout(),
+        &Layout::Split {
+            direction: SplitDirection::Horizontal,
+            children: vec![Layout::Window(d), Layout::Window(c), Layout::Window(a)],
+            // d 和 c 仍然平分上半部分
+            sizes: vec![1, 1, 2],
+        }
+    );
+}
+
+#[test]
+fn test_cycle() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Horizontal);
+    assert_eq!(windows.cycle(1), a);
+    assert_eq!(windows.cycle(2), b);
+    assert_eq!(windows.cycle(-1), a);
+}
+
+#[test]
+fn test_neighbor() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    windows.set_area(rect(0, 0, 41, 20));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Vertical);
+    let c = windows.split(SplitDirection::Horizontal);
+    // 布局：左侧上 c 下 b，右侧 a
+    assert_eq!(windows.neighbor(Side::Down), Some(b));
+    assert_eq!(windows.neighbor(Side::Right), Some(a));
+    assert_eq!(windows.neighbor(Side::Up), None);
+    assert_eq!(windows.neighbor(Side::Left), None);
+
+    windows.set_current(a);
+    // 有多个相邻窗口时选择与左上角对齐的
+    assert_eq!(windows.neighbor(Side::Left), Some(c));
+    windows.set_current(b);
+    assert_eq!(windows.neighbor(Side::Up), Some(c));
+}
+
+#[test]
+fn test_resize_moves_space_between_neighbors() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    windows.set_area(rect(0, 0, 40, 20));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Horizontal);
+
+    assert!(windows.resize(SplitDirection::Horizontal, 3));
+    assert_eq!(
+        windows.layout_rects(windows.area()),
+        vec![(b, rect(0, 0, 40, 13)), (a, rect(0, 13, 40, 7))]
+    );
+    // 最后一个窗口与前一个窗口交换空间
+    windows.set_current(a);
+    assert!(windows.resize(SplitDirection::Horizontal, -5));
+    assert_eq!(
+        windows.layout_rects(windows.area()),
+        vec![(b, rect(0, 0, 40, 18)), (a, rect(0, 18, 40, 2))]
+    );
+    // 没有左右分割时调整宽度不做任何事
+    assert!(!windows.resize(SplitDirection::Vertical, 1));
+}
+
+#[test]
+fn test_resize_keeps_minimum_size() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    windows.set_area(rect(0, 0, 41, 20));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Vertical);
+
+    windows.resize(SplitDirection::Vertical, 100);
+    assert_eq!(
+        windows.layout_rects(windows.area()),
+        vec![(b, rect(0, 0, 39, 20)), (a, rect(40, 0, 1, 20))]
+    );
+    windows.resize(SplitDirection::Vertical, -100);
+    assert_eq!(
+        windows.layout_rects(windows.area()),
+        vec![(b, rect(0, 0, 1, 20)), (a, rect(2, 0, 39, 20))]
+    );
+}
+
+#[test]
+fn test_resize_nested_split() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    windows.set_area(rect(0, 0, 41, 20));
+    let a = windows.current_id();
+    let b = windows.split(SplitDirection::Vertical);
+    let c = windows.split(SplitDirection::Horizontal);
+    // c 在左侧的上下分割中，调整宽度时改变整个左侧的宽度
+    windows.resize(SplitDirection::Vertical, 4);
+    assert_eq!(
+        windows.layout_rects(windows.area()),
+        vec![
+            (c, rect(0, 0, 24, 10)),
+            (b, rect(0, 10, 24, 10)),
+            (a, rect(25, 0, 16, 20)),
+        ]
+    );
+
+    windows.equalize();
+    assert_eq!(
+        windows.layout_rects(windows.area()),
+        vec![
+            (c, rect(0, 0, 20, 10)),
+            (b, rect(0, 10, 20, 10)),
+            (a, rect(21, 0, 20, 20)),
+        ]
+    );
+}
+
+#[test]
+fn test_only() {
+    let mut windows = WindowManager::new(BufferId::new(0));
+    windows.split(SplitDirection::Vertical);
+    let b = windows.split(SplitDirection::Horizontal);
+    windows.only();
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows.layout(), &Layout::Window(b));
+}
+
+// ==================== 编辑器测试 ====================
+
+#[test]
+fn test_split_windows_keep_separate_cursors() {
+    let mut editor = editor_with("one\ntwo\nthree\n", 2, 1);
+    editor.execute_command("sp").unwrap();
+    assert_eq!(editor.windows().len(), 2);
+    // 新窗口从原窗口的位置开始
+    assert_eq!(*editor.cursor(), Cursor::new(2, 1));
+
+    *editor.cursor_mut() = Cursor::new(0, 0);
+    editor.window_command('w', 1).unwrap();
+    assert_eq!(*editor.cursor(), Cursor::new(2, 1));
+    editor.window_command('w', 1).unwrap();
+    assert_eq!(*editor.cursor(), Cursor::new(0, 0));
+}
+
+#[test]
+fn test_window_view_of_other_window() {
+    let mut editor = editor_with("one\ntwo\nthree\n", 2, 1);
+    let original = editor.windows().current_id();
+    editor.execute_command("vsplit").unwrap();
+    *editor.cursor_mut() = Cursor::new(0, 2);
+
+    let (_, cursor) = editor.window_view(original).unwrap();
+    assert_eq!(cursor, Cursor::new(2, 1));
+    let current = editor.windows().current_id();
+    assert_eq!(editor.window_view(current).unwrap().1, Cursor::new(0, 2));
+
+    // 在当前窗口删除行后，其他窗口的光标限制在缓冲区内
+    editor.execute_command("2,3d").unwrap();
+    assert_eq!(editor.window_view(original).unwrap().1, Cursor::new(0, 1));
+}
+
+#[test]
+fn test_split_with_file() {
+    let dir = std::env::temp_dir().join(format!("aivim_window_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("other.txt");
+    std::fs::write(&path, "other\n").unwrap();
+
+    let mut editor = editor_with("main\n", 0, 0);
+    let main_buffer = editor.current_buffer_id();
+    editor.execute_command(&format!("sp {}", path.display())).unwrap();
+    assert_eq!(editor.get_line_text(0).as_deref(), Some("other"));
+
+    editor.window_command('w', 1).unwrap();
+    assert_eq!(editor.current_buffer_id(), main_buffer);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_quit_closes_window() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.execute_command("split").unwrap();
+    editor.execute_command("q").unwrap();
+    assert_eq!(editor.windows().len(), 1);
+    assert!(!editor.quit_requested());
+
+    editor.execute_command("q!").unwrap();
+    assert!(editor.quit_requested());
+}
+
+#[test]
+fn test_window_command_split_and_close() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.window_command('v', 1).unwrap();
+    editor.window_command('s', 1).unwrap();
+    assert_eq!(editor.windows().len(), 3);
+    editor.window_command('q', 1).unwrap();
+    editor.window_command('q', 1).unwrap();
+    assert_eq!(editor.windows().len(), 1);
+    assert_eq!(
+        editor.close_window(editor.windows().current_id()),
+        Err("Cannot close last window".to_string())
+    );
+}
+
+#[test]
+fn test_deleted_buffer_leaves_windows() {
+    let mut editor = editor_with("text\n", 0, 0);
+    let original = editor.windows().current_id();
+    editor.execute_command("vsplit").unwrap();
+    editor.execute_command("new").unwrap();
+    let scratch = editor.current_buffer_id();
+    editor.window_command('w', 1).unwrap();
+    editor.execute_command(&format!("bd {}", scratch.as_usize())).unwrap();
+
+    // 显示被删除缓冲区的窗口改为显示当前缓冲区
+    let current = editor.current_buffer_id();
+    for id in editor.windows().ids() {
+        assert_eq!(editor.window_view(id).unwrap().0.id(), current);
+    }
+    assert_eq!(editor.windows().current_id(), original);
+}
+
+#[test]
+fn test_window_command_moves_by_direction() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.set_window_area(rect(0, 0, 41, 20));
+    let a = editor.windows().current_id();
+    editor.window_command('v', 1).unwrap();
+    let b = editor.windows().current_id();
+    editor.window_command('v', 1).unwrap();
+    let c = editor.windows().current_id();
+
+    editor.window_command('l', 1).unwrap();
+    assert_eq!(editor.windows().current_id(), b);
+    // 计数表示移动的次数，到边上后停止
+    editor.window_command('l', 5).unwrap();
+    assert_eq!(editor.windows().current_id(), a);
+    editor.window_command('h', 2).unwrap();
+    assert_eq!(editor.windows().current_id(), c);
+    editor.window_command('j', 1).unwrap();
+    assert_eq!(editor.windows().current_id(), c);
+}
+
+#[test]
+fn test_window_command_resize_with_count() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.set_window_area(rect(0, 0, 40, 20));
+    let a = editor.windows().current_id();
+    editor.window_command('s', 1).unwrap();
+    let b = editor.windows().current_id();
+
+    editor.window_command('-', 4).unwrap();
+    assert_eq!(
+        editor.windows().layout_rects(rect(0, 0, 40, 20)),
+        vec![(b, rect(0, 0, 40, 6)), (a, rect(0, 6, 40, 14))]
+    );
+    editor.window_command('+', 1).unwrap();
+    assert_eq!(editor.windows().layout_rects(rect(0, 0, 40, 20))[0].1.height, 7);
+    editor.window_command('=', 1).unwrap();
+    assert_eq!(editor.windows().layout_rects(rect(0, 0, 40, 20))[0].1.height, 10);
+}
+
+#[test]
+fn test_close_and_only_commands() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.execute_command("split").unwrap();
+    editor.execute_command("vsplit").unwrap();
+    editor.execute_command("clo").unwrap();
+    assert_eq!(editor.windows().len(), 2);
+    editor.window_command('v', 1).unwrap();
+    let current = editor.windows().current_id();
+
+    editor.execute_command("on").unwrap();
+    assert_eq!(editor.windows().ids(), vec![current]);
+    // 最后一个窗口不能关闭，也不会退出
+    assert_eq!(editor.execute_command("close"), Err("Cannot close last window".to_string()));
+    assert_eq!(editor.window_command('c', 1), Err("Cannot close last window".to_string()));
+    assert!(!editor.quit_requested());
+}
@@ -0,0 +1,343 @@
+//! 语言服务器客户端
+//!
+//! 服务器在子进程中运行，消息通过标准输入和标准输出传递（见 protocol 模块）：
+//! - 启动后立即发送 `initialize` 请求，收到回复之前发送的消息排队，
+//!   收到回复后发送 `initialized` 通知和排队的消息
+//! - 后台线程读取服务器的消息，解析后通过通道发送，每收到一个事件调用一次 `notify`
+//!   （编辑器用它唤醒事件循环），主线程调用 `LspClient::poll` 取出
+//! - 服务器发来的请求（如 `workspace/configuration`）由后台线程直接回复空结果
+//! - 文档内容每次变化时发送全部文本（不发送增量修改）
+//! - 被丢弃时发送 `shutdown` 和 `exit`，服务器没有及时退出时终止它
+
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::config::ServerConfig;
+use crate::diagnostic::{self, Diagnostic, Position};
+use crate::protocol;
+use crate::uri;
+
+/// `initialize` 请求的 id
+const INITIALIZE_ID: u64 = 0;
+
+/// 丢弃客户端后等待服务器退出的最长时间
+const EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 服务器产生的事件，由 `LspClient::poll` 返回
+#[derive(Debug, Clone, PartialEq)]
+pub enum LspEvent {
+    /// 服务器完成初始化，附带服务器的能力（`capabilities`）
+    Initialized(Value),
+    /// 一个文件的全部诊断
+    Diagnostics { uri: String, diagnostics: Vec<Diagnostic> },
+    /// 请求的回复，失败时为错误消息
+    Response { id: u64, result: Result<Value, String> },
+    /// 服务器退出，之后不再有事件
+    Exited,
+}
+
+/// 服务器的标准输入，初始化完成之前的消息排队
+struct Writer {
+    stdin: ChildStdin,
+    ready: bool,
+    queued: Vec<Value>,
+}
+
+impl Writer {
+    fn write(&mut self, message: &Value) {
+        let result = self.stdin.write_all(&protocol::encode(message)).and_then(|_| self.stdin.flush());
+        if let Err(e) = result {
+            tracing::warn!("Failed to write to language server: {}", e);
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        if self.ready {
+            self.write(&message);
+        } else {
+            self.queued.push(message);
+        }
+    }
+}
+
+/// 一个正在运行的语言服务器
+pub struct LspClient {
+    child: Option<Child>,
+    writer: Arc<Mutex<Writer>>,
+    receiver: Receiver<LspEvent>,
+    next_id: u64,
+    exited: bool,
+}
+
+impl std::fmt::Debug for LspClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LspClient").field("exited", &self.exited).finish()
+    }
+}
+
+/// `initialize` 请求的参数
+fn initialize_params(root: &Path) -> Value {
+    let root_uri = uri::from_path(root);
+    let name = root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    json!({
+        "processId": std::process::id(),
+        "clientInfo": { "name": "aivim", "version": env!("CARGO_PKG_VERSION") },
+        "rootUri": root_uri,
+        "rootPath": root.to_string_lossy(),
+        "workspaceFolders": [{ "uri": root_uri, "name": name }],
+        "capabilities": {
+            "textDocument": {
+                "synchronization": { "dynamicRegistration": false, "didSave": false },
+                "publishDiagnostics": { "relatedInformation": false },
+                "completion": {
+                    "completionItem": {
+                        "snippetSupport": true,
+                        "documentationFormat": ["plaintext", "markdown"],
+                    },
+                    "contextSupport": true,
+                },
+            },
+            "general": { "positionEncodings": ["utf-16"] },
+        },
+    })
+}
+
+/// 服务器是否接受增量的 `didChange`（`textDocumentSync` 为 2 或 `{ "change": 2 }`）
+pub fn incremental_sync(capabilities: &Value) -> bool {
+    let sync = capabilities.get("textDocumentSync");
+    let kind = sync.and_then(|sync| sync.get("change")).or(sync).and_then(Value::as_u64);
+    kind == Some(2)
+}
+
+impl LspClient {
+    /// 在项目根目录 `root` 中启动服务器并发送 `initialize` 请求，有新事件时在后台线程中调用 `notify`
+    pub fn start(config: &ServerConfig, root: &Path, notify: impl Fn() + Send + 'static) -> io::Result<Self> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(io::Error::other("Failed to open language server pipes"));
+        };
+
+        let mut writer = Writer {
+            stdin,
+            ready: false,
+            queued: Vec::new(),
+        };
+        writer.write(&protocol::request(INITIALIZE_ID, "initialize", initialize_params(root)));
+        let writer = Arc::new(Mutex::new(writer));
+
+        let (sender, receiver) = mpsc::channel();
+        let reader_writer = Arc::clone(&writer);
+        thread::spawn(move || read_messages(stdout, reader_writer, sender, notify));
+
+        Ok(Self {
+            child: Some(child),
+            writer,
+            receiver,
+            next_id: INITIALIZE_ID + 1,
+            exited: false,
+        })
+    }
+
+    fn send(&self, message: Value) {
+        if let Ok(mut writer) = self.writer.lock() {
+            writer.send(message);
+        }
+    }
+
+    /// 发送请求，返回请求的 id，回复由 `poll` 以 `LspEvent::Response` 返回
+    pub fn request(&mut self, method: &str, params: Value) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(protocol::request(id, method, params));
+        id
+    }
+
+    /// 发送通知
+    pub fn notify(&self, method: &str, params: Value) {
+        self.send(protocol::notification(method, params));
+    }
+
+    /// 通知服务器打开了文档
+    pub fn did_open(&self, uri: &str, language_id: &str, version: i64, text: &str) {
+        self.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": uri, "languageId": language_id, "version": version, "text": text } }),
+        );
+    }
+
+    /// 通知服务器文档的新内容
+    pub fn did_change(&self, uri: &str, version: i64, text: &str) {
+        self.notify(
+            "textDocument/didChange",
+            json!({ "textDocument": { "uri": uri, "version": version }, "contentChanges": [{ "text": text }] }),
+        );
+    }
+
+    /// 通知服务器文档中 `start` 到 `end`（修改前的位置）的文本替换成了 `text`，服务器需要支持增量同步
+    pub fn did_change_range(&self, uri: &str, version: i64, start: Position, end: Position, text: &str) {
+        let range = json!({
+            "start": { "line": start.line, "character": start.character },
+            "end": { "line": end.line, "character": end.character },
+        });
+        self.notify(
+            "textDocument/didChange",
+            json!({ "textDocument": { "uri": uri, "version": version }, "contentChanges": [{ "range": range, "text": text }] }),
+        );
+    }
+
+    /// 通知服务器关闭了文档
+    pub fn did_close(&self, uri: &str) {
+        self.notify("textDocument/didClose", json!({ "textDocument": { "uri": uri } }));
+    }
+
+    /// 取出上次调用之后的事件，`Exited` 之后不再有事件
+    pub fn poll(&mut self) -> Vec<LspEvent> {
+        let mut events = Vec::new();
+        while !self.exited {
+            let Ok(event) = self.receiver.try_recv() else {
+                break;
+            };
+            self.exited = event == LspEvent::Exited;
+            events.push(event);
+        }
+        events
+    }
+
+    /// 服务器是否已经退出（`poll` 返回过 `Exited`）
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        if let Ok(mut writer) = self.writer.lock() {
+            if writer.ready {
+                writer.write(&protocol::request(self.next_id, "shutdown", Value::Null));
+                writer.write(&protocol::notification("exit", Value::Null));
+            }
+        }
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        // 不阻塞编辑器：在后台等待服务器退出，超时后终止
+        thread::spawn(move || {
+            let deadline = Instant::now() + EXIT_TIMEOUT;
+            while Instant::now() < deadline {
+                if child.try_wait().ok().flatten().is_some() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        });
+    }
+}
+
+/// 读取服务器的消息直到输出结束，最后总是发送 `Exited`
+fn read_messages(
+    stdout: impl io::Read,
+    writer: Arc<Mutex<Writer>>,
+    sender: Sender<LspEvent>,
+    notify: impl Fn(),
+) {
+    let send = |event: LspEvent| {
+        let sent = sender.send(event).is_ok();
+        notify();
+        sent
+    };
+
+    let mut reader = BufReader::new(stdout);
+    loop {
+        let message = match protocol::read_message(&mut reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("Failed to read from language server: {}", e);
+                break;
+            }
+        };
+        let Some(event) = handle_message(&message, &writer) else {
+            continue;
+        };
+        if !send(event) {
+            return;
+        }
+    }
+    send(LspEvent::Exited);
+}
+
+/// 处理一条服务器的消息，需要交给编辑器时返回事件
+fn handle_message(message: &Value, writer: &Mutex<Writer>) -> Option<LspEvent> {
+    let method = message.get("method").and_then(Value::as_str);
+    let id = message.get("id");
+    match (method, id) {
+        // 服务器的请求：编辑器不提供设置和动态注册，回复空结果
+        (Some(method), Some(id)) => {
+            let result = match method {
+                "workspace/configuration" => {
+                    let items = message.pointer("/params/items").and_then(Value::as_array).map_or(0, Vec::len);
+                    Value::Array(vec![Value::Null; items])
+                }
+                _ => Value::Null,
+            };
+            if let Ok(mut writer) = writer.lock() {
+                writer.write(&protocol::response(id.clone(), result));
+            }
+            None
+        }
+        (Some("textDocument/publishDiagnostics"), None) => {
+            let (uri, diagnostics) = diagnostic::parse_publish(message.get("params")?)?;
+            Some(LspEvent::Diagnostics { uri, diagnostics })
+        }
+        (Some(method), None) => {
+            tracing::debug!("Ignored language server notification {}", method);
+            None
+        }
+        (None, Some(id)) => {
+            let id = id.as_u64()?;
+            let result = match message.get("error") {
+                Some(error) => Err(error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Unknown error")
+                    .to_string()),
+                None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+            };
+            if id != INITIALIZE_ID {
+                return Some(LspEvent::Response { id, result });
+            }
+            let capabilities = match result {
+                Ok(result) => result.get("capabilities").cloned().unwrap_or(Value::Null),
+                Err(message) => {
+                    tracing::warn!("Language server failed to initialize: {}", message);
+                    return None;
+                }
+            };
+            if let Ok(mut writer) = writer.lock() {
+                writer.write(&protocol::notification("initialized", json!({})));
+                for message in std::mem::take(&mut writer.queued) {
+                    writer.write(&message);
+                }
+                writer.ready = true;
+            }
+            Some(LspEvent::Initialized(capabilities))
+        }
+        (None, None) => None,
+    }
+}
@@ -0,0 +1,336 @@
+//! 补全
+//!
+//! `textDocument/completion` 的请求参数和回复的解析：
+//! - 回复可以是候选项数组或 `CompletionList`，按 `sortText`（没有时按 `label`）排序
+//! - 候选项的插入内容优先使用 `textEdit`，其次是 `insertText`，都没有时插入 `label`
+//! - 代码片段（snippet）展开为普通文本：占位符保留默认文本，光标放在 `$0` 处
+//!   （没有 `$0` 时在第一个占位符处，都没有时在末尾）
+
+use serde_json::{json, Value};
+
+use crate::diagnostic::Position;
+
+/// 候选项的类型，决定菜单中显示的图标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Text,
+    Method,
+    Function,
+    Constructor,
+    Field,
+    Variable,
+    Class,
+    Interface,
+    Module,
+    Property,
+    Unit,
+    Value,
+    Enum,
+    Keyword,
+    Snippet,
+    Color,
+    File,
+    Reference,
+    Folder,
+    EnumMember,
+    Constant,
+    Struct,
+    Event,
+    Operator,
+    TypeParameter,
+}
+
+impl CompletionKind {
+    const ALL: [CompletionKind; 25] = [
+        CompletionKind::Text,
+        CompletionKind::Method,
+        CompletionKind::Function,
+        CompletionKind::Constructor,
+        CompletionKind::Field,
+        CompletionKind::Variable,
+        CompletionKind::Class,
+        CompletionKind::Interface,
+        CompletionKind::Module,
+        CompletionKind::Property,
+        CompletionKind::Unit,
+        CompletionKind::Value,
+        CompletionKind::Enum,
+        CompletionKind::Keyword,
+        CompletionKind::Snippet,
+        CompletionKind::Color,
+        CompletionKind::File,
+        CompletionKind::Reference,
+        CompletionKind::Folder,
+        CompletionKind::EnumMember,
+        CompletionKind::Constant,
+        CompletionKind::Struct,
+        CompletionKind::Event,
+        CompletionKind::Operator,
+        CompletionKind::TypeParameter,
+    ];
+
+    /// 协议中的编号（1-25），未知的编号返回 None
+    pub fn from_code(code: u64) -> Option<Self> {
+        Self::ALL.get((code as usize).checked_sub(1)?).copied()
+    }
+
+    /// 菜单中显示的图标，两列宽的文本
+    pub fn icon(&self) -> &'static str {
+        match self {
+            CompletionKind::Text => "tx",
+            CompletionKind::Method => "m ",
+            CompletionKind::Function => "fn",
+            CompletionKind::Constructor => "ct",
+            CompletionKind::Field => "fd",
+            CompletionKind::Variable => "v ",
+            CompletionKind::Class => "C ",
+            CompletionKind::Interface => "I ",
+            CompletionKind::Module => "md",
+            CompletionKind::Property => "p ",
+            CompletionKind::Unit => "u ",
+            CompletionKind::Value => "vl",
+            CompletionKind::Enum => "E ",
+            CompletionKind::Keyword => "kw",
+            CompletionKind::Snippet => "sn",
+            CompletionKind::Color => "cl",
+            CompletionKind::File => "f ",
+            CompletionKind::Reference => "rf",
+            CompletionKind::Folder => "d ",
+            CompletionKind::EnumMember => "em",
+            CompletionKind::Constant => "c ",
+            CompletionKind::Struct => "S ",
+            CompletionKind::Event => "ev",
+            CompletionKind::Operator => "op",
+            CompletionKind::TypeParameter => "T ",
+        }
+    }
+}
+
+/// 对文档的一处修改：用 `new_text` 替换 `start` 到 `end`（不包含）的文本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: Position,
+    pub end: Position,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn parse(value: &Value) -> Option<Self> {
+        // InsertReplaceEdit 使用插入的范围
+        let range = value.get("range").or_else(|| value.get("insert"))?;
+        Some(Self {
+            start: Position::parse(range.get("start")?)?,
+            end: Position::parse(range.get("end")?)?,
+            new_text: value.get("newText")?.as_str()?.to_string(),
+        })
+    }
+}
+
+/// 一个补全候选项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: Option<CompletionKind>,
+    /// 简短说明（如类型签名）
+    pub detail: Option<String>,
+    pub documentation: Option<String>,
+    /// 按前缀过滤时使用的文本
+    pub filter_text: String,
+    sort_text: String,
+    /// 插入的文本（代码片段已展开）
+    pub insert_text: String,
+    /// 插入后光标在 `insert_text` 中的位置（字符数）
+    pub cursor: usize,
+    /// 替换的范围（`textEdit`），None 时替换光标前的单词
+    pub range: Option<(Position, Position)>,
+    /// 同时进行的其他修改（如添加 import）
+    pub additional_edits: Vec<TextEdit>,
+}
+
+impl CompletionItem {
+    pub fn parse(value: &Value) -> Option<Self> {
+        let label = value.get("label")?.as_str()?.to_string();
+        let string = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let snippet = value.get("insertTextFormat").and_then(Value::as_u64) == Some(2);
+        let edit = value.get("textEdit").and_then(TextEdit::parse);
+        let text = match &edit {
+            Some(edit) => edit.new_text.clone(),
+            None => string("insertText").unwrap_or_else(|| label.clone()),
+        };
+        let (insert_text, cursor) = if snippet {
+            expand_snippet(&text)
+        } else {
+            let len = text.chars().count();
+            (text, len)
+        };
+        // documentation 可以是字符串或 MarkupContent
+        let documentation = match value.get("documentation") {
+            Some(Value::String(text)) => Some(text.clone()),
+            Some(markup) => markup.get("value").and_then(Value::as_str).map(str::to_string),
+            None => None,
+        }
+        .filter(|text| !text.trim().is_empty());
+        Some(Self {
+            kind: value.get("kind").and_then(Value::as_u64).and_then(CompletionKind::from_code),
+            detail: string("detail").filter(|detail| !detail.is_empty()),
+            documentation,
+            filter_text: string("filterText").unwrap_or_else(|| label.clone()),
+            sort_text: string("sortText").unwrap_or_else(|| label.clone()),
+            insert_text,
+            cursor,
+            range: edit.map(|edit| (edit.start, edit.end)),
+            additional_edits: value
+                .get("additionalTextEdits")
+                .and_then(Value::as_array)
+                .map(|edits| edits.iter().filter_map(TextEdit::parse).collect())
+                .unwrap_or_default(),
+            label,
+        })
+    }
+}
+
+/// `textDocument/completion` 的参数，`trigger` 为触发补全的字符（手动触发时为 None）
+pub fn request_params(uri: &str, position: Position, trigger: Option<char>) -> Value {
+    let context = match trigger {
+        Some(c) => json!({ "triggerKind": 2, "triggerCharacter": c.to_string() }),
+        None => json!({ "triggerKind": 1 }),
+    };
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": position.line, "character": position.character },
+        "context": context,
+    })
+}
+
+/// 解析补全请求的回复，按 sortText 排序
+pub fn parse_response(result: &Value) -> Vec<CompletionItem> {
+    let items = match result {
+        Value::Array(items) => items,
+        Value::Object(_) => match result.get("items").and_then(Value::as_array) {
+            Some(items) => items,
+            None => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    let mut items: Vec<CompletionItem> = items.iter().filter_map(CompletionItem::parse).collect();
+    items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+    items
+}
+
+/// 服务器的能力中触发补全的字符
+pub fn trigger_characters(capabilities: &Value) -> Vec<char> {
+    capabilities
+        .pointer("/completionProvider/triggerCharacters")
+        .and_then(Value::as_array)
+        .map(|characters| {
+            characters
+                .iter()
+                .filter_map(Value::as_str)
+                .filter_map(|text| text.chars().next())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 把代码片段展开为普通文本，返回 (文本, 光标位置)
+///
+/// `$1`、`${1}`、`${1:默认文本}`、`${1|a,b|}`（取第一个选项）中只保留默认文本，`\` 转义的字符原样保留
+pub fn expand_snippet(snippet: &str) -> (String, usize) {
+    let mut text = String::new();
+    let mut tabstops = Tabstops::default();
+    expand_into(&snippet.chars().collect::<Vec<_>>(), &mut text, &mut tabstops);
+    let cursor = tabstops
+        .last
+        .or(tabstops.first.map(|(_, position)| position))
+        .unwrap_or(text.chars().count());
+    (text, cursor)
+}
+
+/// 代码片段中 `$0` 和编号最小的占位符 (编号, 位置) 的位置
+#[derive(Default)]
+struct Tabstops {
+    first: Option<(usize, usize)>,
+    last: Option<usize>,
+}
+
+impl Tabstops {
+    fn record(&mut self, number: &str, position: usize) {
+        let number = number.parse().unwrap_or(usize::MAX);
+        if number == 0 {
+            self.last = Some(position);
+        } else if self.first.is_none_or(|(first, _)| number < first) {
+            self.first = Some((number, position));
+        }
+    }
+}
+
+/// 展开 `chars` 并追加到 `text`
+fn expand_into(chars: &[char], text: &mut String, tabstops: &mut Tabstops) {
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if c == '\\' && index + 1 < chars.len() {
+            text.push(chars[index + 1]);
+            index += 2;
+            continue;
+        }
+        if c != '$' {
+            text.push(c);
+            index += 1;
+            continue;
+        }
+        let position = text.chars().count();
+        // $1
+        let digits = chars[index + 1..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 {
+            let number: String = chars[index + 1..index + 1 + digits].iter().collect();
+            tabstops.record(&number, position);
+            index += 1 + digits;
+            continue;
+        }
+        // ${1}、${1:...}、${1|a,b|}
+        let end = (chars.get(index + 1) == Some(&'{'))
+            .then(|| matching_brace(chars, index + 1))
+            .flatten();
+        let Some(end) = end else {
+            text.push(c);
+            index += 1;
+            continue;
+        };
+        let body = &chars[index + 2..end];
+        let digits = body.iter().take_while(|c| c.is_ascii_digit()).count();
+        match body.get(digits) {
+            Some(':') => expand_into(&body[digits + 1..], text, tabstops),
+            Some('|') => {
+                let choices: String = body[digits + 1..].iter().collect();
+                text.push_str(choices.trim_end_matches('|').split(',').next().unwrap_or_default());
+            }
+            _ => {}
+        }
+        if digits > 0 {
+            tabstops.record(&body[..digits].iter().collect::<String>(), position);
+        }
+        index = end + 1;
+    }
+}
+
+/// `open` 处的 `{` 对应的 `}` 的下标
+fn matching_brace(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = open;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
@@ -0,0 +1,208 @@
+//! 语言服务器设置
+//!
+//! 按文件扩展名选择服务器，在文件所在的目录及其上级目录中查找标志文件确定项目的根目录。
+//! 内置 Rust、Python、C/C++、Go 和 TypeScript/JavaScript 的服务器，
+//! 可以在 `config.toml` 的 `[lsp.<语言>]` 表中修改或添加，省略的项使用内置的值：
+//!
+//! ```toml
+//! [lsp.python]
+//! command = "pyright-langserver"
+//! args = ["--stdio"]
+//!
+//! [lsp.zig]
+//! command = "zls"
+//! extensions = ["zig"]
+//! root_markers = ["build.zig"]
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// 没有找到语言的标志文件时用作项目根目录的标志
+const VCS_MARKERS: &[&str] = &[".git", ".hg"];
+
+/// 一种语言的服务器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// 语言名称，也是 `[lsp.<语言>]` 中的名字
+    pub name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    /// 使用这个服务器的文件扩展名（不含 `.`）
+    pub extensions: Vec<String>,
+    /// 项目根目录中的标志文件（如 Cargo.toml）
+    pub root_markers: Vec<String>,
+}
+
+impl ServerConfig {
+    fn builtin(name: &str, command: &str, args: &[&str], extensions: &[&str], root_markers: &[&str]) -> Self {
+        let strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+        Self {
+            name: name.to_string(),
+            command: command.to_string(),
+            args: strings(args),
+            extensions: strings(extensions),
+            root_markers: strings(root_markers),
+        }
+    }
+
+    /// 文件的项目根目录：最近的包含标志文件的上级目录，其次是版本库的根目录，都没有时为文件所在的目录
+    pub fn root_dir(&self, file: &Path) -> PathBuf {
+        let file = std::path::absolute(file).unwrap_or_else(|_| file.to_path_buf());
+        let dir = file.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let find = |markers: &[&str]| {
+            dir.ancestors()
+                .find(|ancestor| markers.iter().any(|marker| ancestor.join(marker).exists()))
+                .map(Path::to_path_buf)
+        };
+        let markers: Vec<&str> = self.root_markers.iter().map(String::as_str).collect();
+        find(&markers).or_else(|| find(VCS_MARKERS)).unwrap_or(dir)
+    }
+}
+
+/// 文件的 languageId（`textDocument/didOpen` 中使用），未知的扩展名使用服务器的语言名称
+pub fn language_id(path: &Path, server: &ServerConfig) -> String {
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    let id = match extension {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "cpp",
+        "go" => "go",
+        "ts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        _ => return server.name.clone(),
+    };
+    id.to_string()
+}
+
+/// `config.toml` 中一个语言的设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSettings {
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub extensions: Option<Vec<String>>,
+    pub root_markers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    lsp: BTreeMap<String, ServerSettings>,
+}
+
+/// 各语言的服务器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LspSettings {
+    servers: Vec<ServerConfig>,
+}
+
+impl Default for LspSettings {
+    fn default() -> Self {
+        let servers = vec![
+            ServerConfig::builtin("rust", "rust-analyzer", &[], &["rs"], &["Cargo.toml"]),
+            ServerConfig::builtin("python", "pylsp", &[], &["py", "pyi"], &["pyproject.toml", "setup.py", "setup.cfg"]),
+            ServerConfig::builtin(
+                "c",
+                "clangd",
+                &[],
+                &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+                &["compile_commands.json", ".clangd"],
+            ),
+            ServerConfig::builtin("go", "gopls", &[], &["go"], &["go.mod"]),
+            ServerConfig::builtin(
+                "typescript",
+                "typescript-language-server",
+                &["--stdio"],
+                &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+                &["tsconfig.json", "package.json"],
+            ),
+        ];
+        Self { servers }
+    }
+}
+
+impl LspSettings {
+    /// 解析配置文件的内容，只读取 `[lsp]` 表，错误信息包括出错的位置
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| {
+            let location = e.to_string().lines().next().unwrap_or_default().to_string();
+            format!("{}: {}", location, e.message())
+        })?;
+        let mut settings = Self::default();
+        for (name, server) in file.lsp {
+            settings.apply(&name, server)?;
+        }
+        Ok(settings)
+    }
+
+    /// 读取配置文件
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&text)
+    }
+
+    /// 修改内置的服务器或添加新的服务器，新的服务器必须指定命令和扩展名
+    fn apply(&mut self, name: &str, settings: ServerSettings) -> Result<(), String> {
+        let existing = self.servers.iter().position(|server| server.name == name);
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                let (Some(command), Some(extensions)) = (&settings.command, &settings.extensions) else {
+                    return Err(format!("lsp.{}: command and extensions are required", name));
+                };
+                self.servers.push(ServerConfig {
+                    name: name.to_string(),
+                    command: command.clone(),
+                    args: Vec::new(),
+                    extensions: extensions.clone(),
+                    root_markers: Vec::new(),
+                });
+                self.servers.len() - 1
+            }
+        };
+        let server = &mut self.servers[index];
+        if let Some(command) = settings.command {
+            server.command = command;
+        }
+        if let Some(args) = settings.args {
+            server.args = args;
+        }
+        if let Some(extensions) = settings.extensions {
+            server.extensions = extensions;
+        }
+        if let Some(root_markers) = settings.root_markers {
+            server.root_markers = root_markers;
+        }
+        // 新添加的服务器可以接管内置服务器的扩展名
+        if existing.is_none() {
+            let extensions = self.servers[index].extensions.clone();
+            for other in &mut self.servers[..index] {
+                other.extensions.retain(|extension| !extensions.contains(extension));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn servers(&self) -> &[ServerConfig] {
+        &self.servers
+    }
+
+    /// 语言的服务器
+    pub fn server(&self, name: &str) -> Option<&ServerConfig> {
+        self.servers.iter().find(|server| server.name == name)
+    }
+
+    /// 文件使用的服务器，扩展名没有对应的服务器或命令为空时返回 None
+    pub fn server_for(&self, path: &Path) -> Option<&ServerConfig> {
+        let extension = path.extension()?.to_str()?;
+        self.servers
+            .iter()
+            .find(|server| !server.command.is_empty() && server.extensions.iter().any(|ext| ext == extension))
+    }
+}
@@ -0,0 +1,118 @@
+//! 诊断信息
+//!
+//! 服务器通过 `textDocument/publishDiagnostics` 通知发布一个文件的全部诊断，
+//! 新的通知替换这个文件之前的诊断。位置中的列按 UTF-16 编码单元计数，
+//! 编辑器中的列按字符计数，用 `utf16_to_char` 和 `char_to_utf16` 转换
+
+use serde_json::Value;
+
+/// 文档中的位置，行从 0 开始，列按 UTF-16 编码单元计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, character: usize) -> Self {
+        Self { line, character }
+    }
+
+    pub fn parse(value: &Value) -> Option<Self> {
+        Some(Self {
+            line: value.get("line")?.as_u64()? as usize,
+            character: value.get("character")?.as_u64()? as usize,
+        })
+    }
+}
+
+/// 诊断的严重程度，越严重越小
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [Severity::Error, Severity::Warning, Severity::Information, Severity::Hint];
+
+    /// 协议中的编号（1-4），未知的编号按错误处理
+    pub fn from_code(code: u64) -> Self {
+        match code {
+            2 => Severity::Warning,
+            3 => Severity::Information,
+            4 => Severity::Hint,
+            _ => Severity::Error,
+        }
+    }
+
+    /// 行号左侧标记列和状态栏中使用的字母
+    pub fn sign(&self) -> char {
+        match self {
+            Severity::Error => 'E',
+            Severity::Warning => 'W',
+            Severity::Information => 'I',
+            Severity::Hint => 'H',
+        }
+    }
+}
+
+/// 一条诊断
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub start: Position,
+    /// 结束位置（不包含）
+    pub end: Position,
+    pub severity: Severity,
+    pub message: String,
+    /// 产生诊断的工具（如 rustc、clippy）
+    pub source: Option<String>,
+}
+
+impl Diagnostic {
+    /// 解析协议中的诊断，没有范围时返回 None；没有严重程度时按错误处理
+    pub fn parse(value: &Value) -> Option<Self> {
+        let range = value.get("range")?;
+        Some(Self {
+            start: Position::parse(range.get("start")?)?,
+            end: Position::parse(range.get("end")?)?,
+            severity: Severity::from_code(value.get("severity").and_then(Value::as_u64).unwrap_or(1)),
+            message: value.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+            source: value.get("source").and_then(Value::as_str).map(str::to_string),
+        })
+    }
+}
+
+/// 解析 `publishDiagnostics` 通知的参数：(文件 URI, 按位置排序的诊断)
+pub fn parse_publish(params: &Value) -> Option<(String, Vec<Diagnostic>)> {
+    let uri = params.get("uri")?.as_str()?.to_string();
+    let mut diagnostics: Vec<Diagnostic> = params
+        .get("diagnostics")?
+        .as_array()?
+        .iter()
+        .filter_map(Diagnostic::parse)
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.start, diagnostic.severity));
+    Some((uri, diagnostics))
+}
+
+/// 一行文本中第 `units` 个 UTF-16 编码单元对应的字符下标，超过行尾时为字符数
+///
+/// 位置落在代理对中间时返回这个字符
+pub fn utf16_to_char(text: &str, units: usize) -> usize {
+    let mut count = 0;
+    for (index, c) in text.chars().enumerate() {
+        count += c.len_utf16();
+        if count > units {
+            return index;
+        }
+    }
+    text.chars().count()
+}
+
+/// 一行文本中第 `chars` 个字符之前的 UTF-16 编码单元数
+pub fn char_to_utf16(text: &str, chars: usize) -> usize {
+    text.chars().take(chars).map(char::len_utf16).sum()
+}
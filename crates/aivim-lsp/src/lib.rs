@@ -0,0 +1,21 @@
+//! AIVim 的 LSP (Language Server Protocol) 子系统
+//!
+//! 按文件类型启动语言服务器并与之通信，不依赖编辑器本身：
+//! - config: 各语言使用的服务器命令、文件扩展名和项目根目录的标志文件，可以在 config.toml 中修改
+//! - protocol: JSON-RPC 消息的 `Content-Length` 分帧
+//! - uri: 文件路径与 `file://` URI 之间的转换
+//! - diagnostic: 服务器发布的诊断信息（错误、警告）和 UTF-16 位置的转换
+//! - completion: 补全请求的参数、候选项的解析和代码片段的展开
+//! - client: 在子进程中运行服务器，后台线程读取服务器的消息
+
+pub mod client;
+pub mod completion;
+pub mod config;
+pub mod diagnostic;
+pub mod protocol;
+pub mod uri;
+
+pub use client::{LspClient, LspEvent};
+pub use completion::{CompletionItem, CompletionKind, TextEdit};
+pub use config::{LspSettings, ServerConfig};
+pub use diagnostic::{Diagnostic, Position, Severity};
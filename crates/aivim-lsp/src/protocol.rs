@@ -0,0 +1,68 @@
+//! JSON-RPC 消息的分帧
+//!
+//! 服务器的标准输入和标准输出上每条消息由头部和 JSON 内容组成：
+//! 头部为若干 `Name: value` 行，以空行结束，其中 `Content-Length` 为内容的字节数
+
+use std::io::{self, BufRead};
+
+use serde_json::{json, Value};
+
+/// 加上头部的消息
+pub fn encode(message: &Value) -> Vec<u8> {
+    let content = message.to_string();
+    let mut bytes = format!("Content-Length: {}\r\n\r\n", content.len()).into_bytes();
+    bytes.extend_from_slice(content.as_bytes());
+    bytes
+}
+
+/// 请求消息
+pub fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+/// 通知消息（没有 id，服务器不回复）
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// 对服务器请求的回复
+pub fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// 读取一条消息，输入结束时返回 None
+///
+/// 头部缺少 `Content-Length` 或内容不是 JSON 时返回 `InvalidData` 错误
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    let mut has_header = false;
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let line = header.trim_end();
+        if line.is_empty() {
+            // 消息之间多余的空行
+            if has_header {
+                break;
+            }
+            continue;
+        }
+        has_header = true;
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length"));
+    };
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,194 @@
+//! 语言服务器客户端测试
+//!
+//! 对应源文件: src/client.rs
+//!
+//! 用 Python 脚本模拟语言服务器：每次打开或修改文档时，为包含 `ERROR` 或 `WARN` 的行发布诊断；
+//! 系统没有 python3 时跳过
+
+use aivim_lsp::client::incremental_sync;
+use aivim_lsp::{uri, LspClient, LspEvent, ServerConfig, Severity};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const FAKE_SERVER: &str = r#"
+import json, sys
+
+def read():
+    length = None
+    while True:
+        line = sys.stdin.buffer.readline()
+        if not line:
+            return None
+        line = line.strip()
+        if not line:
+            break
+        name, value = line.split(b":", 1)
+        if name.lower() == b"content-length":
+            length = int(value)
+    return json.loads(sys.stdin.buffer.read(length))
+
+def send(message):
+    body = json.dumps(message).encode()
+    sys.stdout.buffer.write(b"Content-Length: %d\r\n\r\n" % len(body) + body)
+    sys.stdout.buffer.flush()
+
+def publish(uri, text):
+    diagnostics = []
+    for number, line in enumerate(text.split("\n")):
+        for word, severity in (("ERROR", 1), ("WARN", 2)):
+            column = line.find(word)
+            if column >= 0:
+                diagnostics.append({
+                    "range": {"start": {"line": number, "character": column},
+                              "end": {"line": number, "character": column + len(word)}},
+                    "severity": severity, "message": "found " + word, "source": "fake"})
+    send({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics",
+          "params": {"uri": uri, "diagnostics": diagnostics}})
+
+initialized = False
+while True:
+    message = read()
+    if message is None:
+        break
+    method = message.get("method")
+    params = message.get("params") or {}
+    if method == "initialize":
+        send({"jsonrpc": "2.0", "id": message["id"], "result": {"capabilities": {"textDocumentSync": 1}}})
+    elif method == "initialized":
+        initialized = True
+        send({"jsonrpc": "2.0", "id": 99, "method": "workspace/configuration", "params": {"items": [{}]}})
+    elif method in ("textDocument/didOpen", "textDocument/didChange"):
+        if not initialized:
+            raise SystemExit("notification before initialized")
+        document = params["textDocument"]
+        text = document["text"] if "text" in document else params["contentChanges"][-1]["text"]
+        publish(document["uri"], text)
+    elif method == "textDocument/didClose":
+        send({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics",
+              "params": {"uri": params["textDocument"]["uri"], "diagnostics": []}})
+    elif method == "aivim/echo":
+        send({"jsonrpc": "2.0", "id": message["id"], "result": params})
+    elif method == "aivim/fail":
+        send({"jsonrpc": "2.0", "id": message["id"], "error": {"code": -32601, "message": "no such method"}})
+    elif method == "shutdown":
+        send({"jsonrpc": "2.0", "id": message["id"], "result": None})
+    elif method == "exit":
+        break
+"#;
+
+fn has_python() -> bool {
+    Command::new("python3").arg("--version").output().is_ok()
+}
+
+/// 写入模拟服务器的脚本，返回启动它的设置
+fn fake_server(name: &str) -> ServerConfig {
+    let script = PathBuf::from(format!("/tmp/aivim_lsp_client_{}.py", name));
+    fs::write(&script, FAKE_SERVER).unwrap();
+    ServerConfig {
+        name: "fake".to_string(),
+        command: "python3".to_string(),
+        args: vec![script.display().to_string()],
+        extensions: vec!["txt".to_string()],
+        root_markers: Vec::new(),
+    }
+}
+
+/// 收集事件直到 `done` 返回 true 或超时
+fn collect_until(client: &mut LspClient, done: impl Fn(&[LspEvent]) -> bool) -> Vec<LspEvent> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut events = Vec::new();
+    while !done(&events) && Instant::now() < deadline {
+        events.extend(client.poll());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    events
+}
+
+fn diagnostics_of(events: &[LspEvent]) -> Vec<(String, Vec<(usize, Severity)>)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            LspEvent::Diagnostics { uri, diagnostics } => Some((
+                uri.clone(),
+                diagnostics.iter().map(|diagnostic| (diagnostic.start.line, diagnostic.severity)).collect(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_open_and_change_publish_diagnostics() {
+    if !has_python() {
+        return;
+    }
+    let mut client = LspClient::start(&fake_server("diagnostics"), Path::new("/tmp"), || {}).unwrap();
+    let uri = uri::from_path(Path::new("/tmp/aivim_lsp_doc.txt"));
+    // 初始化完成之前发送的通知排队，完成后按顺序发送
+    client.did_open(&uri, "text", 1, "ok\nERROR here\n");
+    let events = collect_until(&mut client, |events| !diagnostics_of(events).is_empty());
+    assert!(matches!(&events[0], LspEvent::Initialized(capabilities) if capabilities["textDocumentSync"] == 1));
+    assert_eq!(diagnostics_of(&events), vec![(uri.clone(), vec![(1, Severity::Error)])]);
+
+    client.did_change(&uri, 2, "WARN\nok\nERROR\n");
+    let events = collect_until(&mut client, |events| !diagnostics_of(events).is_empty());
+    assert_eq!(
+        diagnostics_of(&events),
+        vec![(uri.clone(), vec![(0, Severity::Warning), (2, Severity::Error)])]
+    );
+
+    client.did_close(&uri);
+    let events = collect_until(&mut client, |events| !diagnostics_of(events).is_empty());
+    assert_eq!(diagnostics_of(&events), vec![(uri, vec![])]);
+}
+
+#[test]
+fn test_request_response() {
+    if !has_python() {
+        return;
+    }
+    let mut client = LspClient::start(&fake_server("request"), Path::new("/tmp"), || {}).unwrap();
+    let echo = client.request("aivim/echo", json!({ "value": 42 }));
+    let fail = client.request("aivim/fail", json!({}));
+    assert_ne!(echo, fail);
+    let events = collect_until(&mut client, |events| {
+        events.iter().filter(|event| matches!(event, LspEvent::Response { .. })).count() == 2
+    });
+    assert!(events.contains(&LspEvent::Response { id: echo, result: Ok(json!({ "value": 42 })) }));
+    assert!(events.contains(&LspEvent::Response { id: fail, result: Err("no such method".to_string()) }));
+}
+
+#[test]
+fn test_server_exit() {
+    if !has_python() {
+        return;
+    }
+    let mut client = LspClient::start(&fake_server("exit"), Path::new("/tmp"), || {}).unwrap();
+    collect_until(&mut client, |events| events.iter().any(|event| matches!(event, LspEvent::Initialized(_))));
+    client.notify("exit", serde_json::Value::Null);
+    let events = collect_until(&mut client, |events| events.contains(&LspEvent::Exited));
+    assert_eq!(events.last(), Some(&LspEvent::Exited));
+    assert!(client.has_exited());
+    assert!(client.poll().is_empty());
+}
+
+#[test]
+fn test_start_missing_command() {
+    let config = ServerConfig {
+        command: "aivim-no-such-language-server".to_string(),
+        ..fake_server("missing")
+    };
+    assert!(LspClient::start(&config, Path::new("/tmp"), || {}).is_err());
+}
+
+#[test]
+fn test_incremental_sync_capability() {
+    assert!(incremental_sync(&json!({ "textDocumentSync": 2 })));
+    assert!(incremental_sync(&json!({ "textDocumentSync": { "openClose": true, "change": 2 } })));
+    assert!(!incremental_sync(&json!({ "textDocumentSync": 1 })));
+    assert!(!incremental_sync(&json!({ "textDocumentSync": { "openClose": true } })));
+    assert!(!incremental_sync(&json!({})));
+}
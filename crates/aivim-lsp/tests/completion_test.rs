@@ -0,0 +1,96 @@
+//! 补全回复解析测试
+//!
+//! 对应源文件: src/completion.rs
+
+use aivim_lsp::completion::{expand_snippet, parse_response, request_params, trigger_characters};
+use aivim_lsp::{CompletionKind, Position};
+use serde_json::json;
+
+#[test]
+fn test_parse_completion_list() {
+    let items = parse_response(&json!({
+        "isIncomplete": false,
+        "items": [
+            { "label": "push", "kind": 2, "sortText": "b", "detail": "fn(&mut self, T)" },
+            { "label": "len", "kind": 2, "sortText": "a",
+              "documentation": { "kind": "markdown", "value": "Returns the length" } },
+            { "label": "capacity", "kind": 99, "documentation": "  " },
+        ],
+    }));
+    let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+    // 按 sortText 排序，没有 sortText 时按 label
+    assert_eq!(labels, vec!["len", "push", "capacity"]);
+    assert_eq!(items[0].documentation.as_deref(), Some("Returns the length"));
+    assert_eq!(items[1].detail.as_deref(), Some("fn(&mut self, T)"));
+    assert_eq!(items[1].kind, Some(CompletionKind::Method));
+    // 未知的类型和空白的文档忽略
+    assert_eq!(items[2].kind, None);
+    assert_eq!(items[2].documentation, None);
+    assert_eq!(items[2].insert_text, "capacity");
+    assert_eq!(items[2].cursor, 8);
+
+    // 也可以直接是数组，null 没有候选项
+    assert_eq!(parse_response(&json!([{ "label": "x" }])).len(), 1);
+    assert!(parse_response(&json!(null)).is_empty());
+}
+
+#[test]
+fn test_parse_text_edits() {
+    let range = |start: u64, end: u64| {
+        json!({ "start": { "line": 3, "character": start }, "end": { "line": 3, "character": end } })
+    };
+    let items = parse_response(&json!([
+        { "label": "a", "insertText": "alpha" },
+        { "label": "b", "insertText": "ignored", "textEdit": { "range": range(4, 6), "newText": "beta" } },
+        { "label": "c", "textEdit": { "insert": range(2, 6), "replace": range(2, 8), "newText": "gamma" },
+          "additionalTextEdits": [{ "range": range(0, 0), "newText": "use c;\n" }] },
+    ]));
+    assert_eq!(items[0].insert_text, "alpha");
+    assert_eq!(items[0].range, None);
+    assert_eq!(items[1].insert_text, "beta");
+    assert_eq!(items[1].range, Some((Position::new(3, 4), Position::new(3, 6))));
+    // InsertReplaceEdit 使用插入的范围
+    assert_eq!(items[2].range, Some((Position::new(3, 2), Position::new(3, 6))));
+    assert_eq!(items[2].additional_edits.len(), 1);
+    assert_eq!(items[2].additional_edits[0].new_text, "use c;\n");
+    assert_eq!(items[2].additional_edits[0].start, Position::new(3, 0));
+}
+
+#[test]
+fn test_expand_snippet() {
+    assert_eq!(expand_snippet("foo()"), ("foo()".to_string(), 5));
+    assert_eq!(expand_snippet("foo($0)"), ("foo()".to_string(), 4));
+    // 没有 $0 时光标在编号最小的占位符处
+    assert_eq!(expand_snippet("foo(${2:b}, ${1:a})"), ("foo(b, a)".to_string(), 7));
+    assert_eq!(expand_snippet("if $1 {\n\t$0\n}"), ("if  {\n\t\n}".to_string(), 7));
+    // 嵌套的占位符、选项和转义
+    assert_eq!(expand_snippet("${1:Vec<${2:T}>}"), ("Vec<T>".to_string(), 0));
+    assert_eq!(expand_snippet("${1|one,two|}!"), ("one!".to_string(), 0));
+    assert_eq!(expand_snippet("\\$1 \\}$0"), ("$1 }".to_string(), 4));
+    // 不完整的占位符原样保留
+    assert_eq!(expand_snippet("a${1:b"), ("a${1:b".to_string(), 6));
+
+    let item = &parse_response(&json!([
+        { "label": "vec!", "insertText": "vec![$1]", "insertTextFormat": 2 },
+    ]))[0];
+    assert_eq!(item.insert_text, "vec![]");
+    assert_eq!(item.cursor, 5);
+}
+
+#[test]
+fn test_request_params_and_trigger_characters() {
+    let params = request_params("file:///a.rs", Position::new(1, 4), Some('.'));
+    assert_eq!(params["position"], json!({ "line": 1, "character": 4 }));
+    assert_eq!(params["context"], json!({ "triggerKind": 2, "triggerCharacter": "." }));
+    let params = request_params("file:///a.rs", Position::new(0, 0), None);
+    assert_eq!(params["context"], json!({ "triggerKind": 1 }));
+    assert_eq!(params["textDocument"]["uri"], "file:///a.rs");
+
+    let capabilities = json!({ "completionProvider": { "triggerCharacters": [".", "::", ""] } });
+    assert_eq!(trigger_characters(&capabilities), vec!['.', ':']);
+    assert!(trigger_characters(&json!({})).is_empty());
+
+    assert_eq!(CompletionKind::from_code(3), Some(CompletionKind::Function));
+    assert_eq!(CompletionKind::from_code(0), None);
+    assert_eq!(CompletionKind::Function.icon(), "fn");
+}
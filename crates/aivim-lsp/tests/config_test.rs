@@ -0,0 +1,133 @@
+//! 语言服务器设置和诊断测试
+//!
+//! 对应源文件: src/config.rs, src/diagnostic.rs
+
+use aivim_lsp::config::language_id;
+use aivim_lsp::diagnostic::{self, char_to_utf16, utf16_to_char};
+use aivim_lsp::{LspSettings, Position, Severity};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_default_servers() {
+    let settings = LspSettings::default();
+    let server = |path: &str| settings.server_for(Path::new(path)).map(|server| server.command.as_str());
+    assert_eq!(server("src/main.rs"), Some("rust-analyzer"));
+    assert_eq!(server("a.py"), Some("pylsp"));
+    assert_eq!(server("a.hpp"), Some("clangd"));
+    assert_eq!(server("main.go"), Some("gopls"));
+    assert_eq!(server("app.tsx"), Some("typescript-language-server"));
+    assert_eq!(server("README.md"), None);
+    assert_eq!(server("Makefile"), None);
+    assert_eq!(settings.server("typescript").unwrap().args, vec!["--stdio"]);
+}
+
+#[test]
+fn test_language_id() {
+    let settings = LspSettings::default();
+    let id = |path: &str| {
+        let path = Path::new(path);
+        language_id(path, settings.server_for(path).unwrap())
+    };
+    assert_eq!(id("a.rs"), "rust");
+    assert_eq!(id("a.h"), "c");
+    assert_eq!(id("a.cpp"), "cpp");
+    assert_eq!(id("a.jsx"), "javascriptreact");
+}
+
+#[test]
+fn test_parse_settings() {
+    let settings = LspSettings::parse(
+        r#"
+[ai]
+provider = "ollama"
+
+[lsp.python]
+command = "pyright-langserver"
+args = ["--stdio"]
+
+[lsp.zig]
+command = "zls"
+extensions = ["zig"]
+root_markers = ["build.zig"]
+
+[lsp.jsonc]
+command = "vscode-json-language-server"
+extensions = ["json", "ts"]
+"#,
+    )
+    .unwrap();
+    let python = settings.server_for(Path::new("a.py")).unwrap();
+    assert_eq!((python.command.as_str(), python.args.clone()), ("pyright-langserver", vec!["--stdio".to_string()]));
+    // 省略的项使用内置的值
+    assert_eq!(python.extensions, vec!["py", "pyi"]);
+    let zig = settings.server_for(Path::new("build.zig")).unwrap();
+    assert_eq!((zig.name.as_str(), zig.root_markers.clone()), ("zig", vec!["build.zig".to_string()]));
+    // 新添加的服务器接管内置服务器的扩展名
+    assert_eq!(settings.server_for(Path::new("a.ts")).unwrap().name, "jsonc");
+    assert_eq!(settings.server_for(Path::new("a.tsx")).unwrap().name, "typescript");
+    // 命令为空时不启动服务器
+    let disabled = LspSettings::parse("[lsp.rust]\ncommand = \"\"\n").unwrap();
+    assert!(disabled.server_for(Path::new("a.rs")).is_none());
+}
+
+#[test]
+fn test_parse_settings_errors() {
+    let error = LspSettings::parse("[lsp.zig]\ncommand = \"zls\"\n").unwrap_err();
+    assert!(error.contains("lsp.zig"), "{}", error);
+    let error = LspSettings::parse("[lsp.rust]\ncmd = \"ra\"\n").unwrap_err();
+    assert!(error.contains("line 2"), "{}", error);
+    assert!(LspSettings::load(Path::new("/tmp/aivim_lsp_no_such_config.toml")).is_err());
+}
+
+#[test]
+fn test_root_dir() {
+    let root = PathBuf::from("/tmp/aivim_lsp_root");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("crate/src/nested")).unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("other")).unwrap();
+    fs::write(root.join("crate/Cargo.toml"), "").unwrap();
+    let rust = LspSettings::default().server("rust").unwrap().clone();
+    assert_eq!(rust.root_dir(&root.join("crate/src/nested/a.rs")), root.join("crate"));
+    // 没有语言的标志文件时使用版本库的根目录
+    assert_eq!(rust.root_dir(&root.join("other/a.rs")), root);
+}
+
+#[test]
+fn test_parse_publish_diagnostics() {
+    let params = json!({
+        "uri": "file:///a.rs",
+        "diagnostics": [
+            { "range": { "start": { "line": 3, "character": 1 }, "end": { "line": 3, "character": 4 } },
+              "severity": 2, "message": "unused", "source": "rustc" },
+            { "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 1, "character": 0 } },
+              "message": "no severity" },
+            { "message": "no range" },
+        ],
+    });
+    let (uri, diagnostics) = diagnostic::parse_publish(&params).unwrap();
+    assert_eq!(uri, "file:///a.rs");
+    assert_eq!(diagnostics.len(), 2);
+    // 按位置排序，没有严重程度时按错误处理
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].end, Position::new(1, 0));
+    assert_eq!(diagnostics[1].severity, Severity::Warning);
+    assert_eq!(diagnostics[1].message, "unused");
+    assert_eq!(diagnostics[1].source.as_deref(), Some("rustc"));
+    assert!(diagnostic::parse_publish(&json!({ "diagnostics": [] })).is_none());
+}
+
+#[test]
+fn test_utf16_columns() {
+    // 😀 占两个 UTF-16 编码单元
+    let text = "a😀b中";
+    assert_eq!(utf16_to_char(text, 1), 1);
+    assert_eq!(utf16_to_char(text, 2), 1);
+    assert_eq!(utf16_to_char(text, 3), 2);
+    assert_eq!(utf16_to_char(text, 4), 3);
+    assert_eq!(utf16_to_char(text, 100), 4);
+    assert_eq!(char_to_utf16(text, 2), 3);
+    assert_eq!(char_to_utf16(text, 4), 5);
+}
@@ -0,0 +1,58 @@
+//! 消息分帧和 URI 转换测试
+//!
+//! 对应源文件: src/protocol.rs, src/uri.rs
+
+use aivim_lsp::{protocol, uri};
+use serde_json::json;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_encode_message() {
+    let bytes = protocol::encode(&json!({ "a": "é" }));
+    assert_eq!(String::from_utf8(bytes).unwrap(), "Content-Length: 10\r\n\r\n{\"a\":\"é\"}");
+}
+
+#[test]
+fn test_read_messages() {
+    let mut input = protocol::encode(&protocol::request(1, "initialize", json!({})));
+    // 其他头部被忽略，头部名称不区分大小写
+    input.extend_from_slice(b"content-length: 2\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n{}");
+    input.extend(protocol::encode(&protocol::notification("exit", json!(null))));
+    let mut reader = BufReader::new(Cursor::new(input));
+
+    let first = protocol::read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(first["id"], 1);
+    assert_eq!(first["method"], "initialize");
+    assert_eq!(protocol::read_message(&mut reader).unwrap(), Some(json!({})));
+    let third = protocol::read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(third["method"], "exit");
+    assert!(third.get("id").is_none());
+    assert_eq!(protocol::read_message(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn test_read_invalid_message() {
+    let mut reader = BufReader::new(Cursor::new(b"Content-Type: x\r\n\r\n{}".to_vec()));
+    assert!(protocol::read_message(&mut reader).is_err());
+    let mut reader = BufReader::new(Cursor::new(b"Content-Length: 3\r\n\r\nabc".to_vec()));
+    assert!(protocol::read_message(&mut reader).is_err());
+    // 内容不完整
+    let mut reader = BufReader::new(Cursor::new(b"Content-Length: 10\r\n\r\n{}".to_vec()));
+    assert!(protocol::read_message(&mut reader).is_err());
+}
+
+#[test]
+fn test_uri_round_trip() {
+    assert_eq!(uri::from_path(Path::new("/tmp/a b/文件.rs")), "file:///tmp/a%20b/%E6%96%87%E4%BB%B6.rs");
+    assert_eq!(
+        uri::to_path("file:///tmp/a%20b/%E6%96%87%E4%BB%B6.rs"),
+        Some(PathBuf::from("/tmp/a b/文件.rs"))
+    );
+    assert_eq!(uri::to_path("file:///src/main.rs"), Some(PathBuf::from("/src/main.rs")));
+    assert_eq!(uri::to_path("untitled:1"), None);
+    assert_eq!(uri::to_path("file:///bad%zz"), None);
+    // 相对路径按当前目录转换
+    let relative = uri::from_path(Path::new("src/lib.rs"));
+    assert!(relative.starts_with("file:///") && relative.ends_with("/src/lib.rs"));
+}
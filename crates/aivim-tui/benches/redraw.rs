@@ -0,0 +1,50 @@
+//! 空闲时的重绘开销基准测试
+//!
+//! 事件循环每 50ms 有一次 Tick。空闲的 Tick 只比较一帧依赖的状态（`Damage::take`），
+//! 不重新生成每一行的文本，耗时应远小于完整绘制一帧：
+//! `cargo bench -p aivim-tui --bench redraw`
+
+use aivim_core::{Cursor, Editor};
+use aivim_tui::app::OperatorState;
+use aivim_tui::damage::Damage;
+use aivim_tui::ui;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+const WIDTH: u16 = 160;
+const HEIGHT: u16 = 50;
+
+fn editor() -> Editor {
+    let mut editor = Editor::new();
+    let text: String = (0..10_000).map(|i| format!("fn item_{}() {{ let value = {}; }}\n", i, i)).collect();
+    editor.current_buffer_mut().insert(0, &text);
+    *editor.cursor_mut() = Cursor::new(5_000, 3);
+    editor.set_scroll_offset(4_980);
+    editor
+}
+
+fn bench_idle_tick(c: &mut Criterion) {
+    let editor = editor();
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+    let size = terminal.size().unwrap();
+    c.bench_function("idle tick: full redraw", |b| {
+        b.iter(|| {
+            terminal.draw(|frame| ui::draw(frame, &editor, OperatorState::None)).unwrap();
+        })
+    });
+
+    let mut damage = Damage::new();
+    damage.take(&editor, size);
+    c.bench_function("idle tick: damage check", |b| {
+        b.iter(|| {
+            if damage.take(&editor, size) {
+                terminal.draw(|frame| ui::draw(frame, &editor, OperatorState::None)).unwrap();
+            }
+            black_box(&terminal);
+        })
+    });
+}
+
+criterion_group!(benches, bench_idle_tick);
+criterion_main!(benches);
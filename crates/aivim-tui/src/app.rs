@@ -1,28 +1,45 @@
-use aivim_core::{motion::Motion, Editor, Mode};
+use aivim_core::fillchars;
+use aivim_core::positions;
+use aivim_core::buffer::BufferId;
+use aivim_core::shell::{TerminalRequest, SHELL_WAIT_TIMEOUT};
+use aivim_core::{Editor, KeyInput, Locale, MapKey, Operator};
 use crossterm::{
     event::{KeyCode, KeyEvent, KeyModifiers},
+    event::{DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use ratatui::{
     backend::CrosstermBackend,
+    layout::Rect,
     Terminal,
 };
-use std::io;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use crate::damage::Damage;
 use crate::event::{Event, EventHandler};
-use crate::ui::{self, calculate_scroll_offset};
+use crate::ui::{self, calculate_folded_scroll_offset, calculate_scroll_offset, calculate_wrapped_scroll_offset};
 
-/// 操作符等待状态
+
+/// 操作符等待状态（由按键解析器的状态导出，用于状态栏显示）
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OperatorState {
     None,
     Delete { register: Option<char> },      // d - 等待动作，可指定寄存器
     Yank { register: Option<char> },        // y - 等待动作，可指定寄存器
-    Change { register: Option<char> },      // c - 等待动作 (计划中)
+    Change { register: Option<char> },      // c - 等待动作，可指定寄存器
+    Indent,      // > - 等待动作
+    Outdent,     // < - 等待动作
+    Fold,        // zf - 等待动作
+    Surround,    // ys - 等待动作和包围字符
+    Case,        // gu / gU / g~ - 等待动作
+    Format,      // gq - 等待动作
     G,           // g - 等待第二个g (gg)
+    Z,           // z - 等待第二个键 (zo、zc)
+    Bracket,     // ] / [ - 等待第二个键 (]c、[c)
+    ReplaceChar, // r - 等待替换字符
     TextObject { operator: TextObjectOperator, around: bool, register: Option<char> }, // a/i - 等待文本对象
     RegisterPending(Option<char>), // " - 等待寄存器名，Some(char)表示已选寄存器，等待操作符
 }
@@ -33,605 +50,473 @@ pub enum TextObjectOperator {
     Delete,  // d
     Yank,    // y
     Change,  // c
+    Indent,  // >
+    Outdent, // <
+    Fold,    // zf
+    Surround, // ys
+    Case,     // gu / gU / g~
+    Format,   // gq
+}
+
+impl From<Operator> for TextObjectOperator {
+    fn from(operator: Operator) -> Self {
+        match operator {
+            Operator::Delete => TextObjectOperator::Delete,
+            Operator::Yank => TextObjectOperator::Yank,
+            Operator::Change => TextObjectOperator::Change,
+            Operator::Indent => TextObjectOperator::Indent,
+            Operator::Outdent => TextObjectOperator::Outdent,
+            Operator::Fold => TextObjectOperator::Fold,
+            Operator::Surround(_) => TextObjectOperator::Surround,
+            Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => TextObjectOperator::Case,
+            Operator::Format => TextObjectOperator::Format,
+        }
+    }
 }
 
 pub struct App {
     editor: Editor,
     event_handler: EventHandler,
-    scroll_offset: usize,
     should_quit: bool,
-    operator_state: OperatorState,
+    /// 按键处理的状态（未完成的按键序列、等待映射的按键）
+    input: KeyInput,
+    /// 不启动终端界面（aivim --headless）
+    headless: bool,
+    /// 从标准输入读入的缓冲区，无界面模式结束时输出到标准输出
+    stdin_buffer: Option<BufferId>,
+    /// 下一帧是否需要重绘
+    damage: Damage,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
+        Self::with_editor(Editor::new())
+    }
+
+    pub fn with_file(path: PathBuf) -> io::Result<Self> {
+        // 先设置状态文件再打开文件，才能回到上次的光标位置
+        let mut app = Self::with_editor(Editor::new());
+        app.open_file(&path)?;
+        Ok(app)
+    }
+
+    fn with_editor(mut editor: Editor) -> Self {
+        editor.set_locale(Locale::from_env());
+        editor.set_ascii_glyphs(!fillchars::terminal_supports_unicode());
+        editor.set_positions_file(Some(positions::default_positions_path()));
+        let event_handler = EventHandler::new(Duration::from_millis(50));
+        editor.set_waker(event_handler.waker());
         Self {
-            editor: Editor::new(),
-            event_handler: EventHandler::new(Duration::from_millis(50)),
-            scroll_offset: 0,
+            editor,
+            event_handler,
             should_quit: false,
-            operator_state: OperatorState::None,
+            input: KeyInput::new(),
+            headless: false,
+            stdin_buffer: None,
+            damage: Damage::new(),
         }
     }
 
-    pub fn with_file(path: PathBuf) -> io::Result<Self> {
-        Ok(Self {
-            editor: Editor::with_file(&path)?,
-            event_handler: EventHandler::new(Duration::from_millis(50)),
-            scroll_offset: 0,
-            should_quit: false,
-            operator_state: OperatorState::None,
-        })
+    /// 无界面模式（aivim --headless），用于脚本和管道：
+    /// 不读取配置文件、不记录也不恢复光标位置，执行完启动参数中的命令后用 `run_headless` 结束
+    pub fn headless() -> Self {
+        let mut app = Self::with_editor(Editor::new());
+        app.editor.set_positions_file(None);
+        app.headless = true;
+        app
+    }
+
+    /// 只读模式（aivim -R），需要在打开文件之前设置
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.editor.set_read_only_mode(read_only);
+    }
+
+    pub fn open_file(&mut self, path: &Path) -> io::Result<()> {
+        self.editor.open_file(path)?;
+        self.update_scroll_offset();
+        Ok(())
+    }
+
+    /// 在没有文件名的缓冲区中编辑从标准输入读入的内容（aivim -）
+    pub fn open_bytes(&mut self, bytes: &[u8]) {
+        self.editor.open_bytes(bytes);
+        self.stdin_buffer = Some(self.editor.current_buffer_id());
+        self.update_scroll_offset();
+    }
+
+    /// 依次执行启动参数中的 Ex 命令（aivim +{cmd} / -c {cmd}），在消息栏显示并返回第一个错误
+    ///
+    /// 命令要求退出时不再执行后面的命令；无界面模式下无法回答确认提示，需要确认的操作按错误处理并取消
+    pub fn run_commands(&mut self, commands: &[String]) -> Result<(), String> {
+        let mut first_error = None;
+        for command in commands {
+            let mut result = self.editor.execute_command(command);
+            if let Some(request) = self.editor.take_normal_request() {
+                self.input.execute_normal(&mut self.editor, request);
+            }
+            if self.headless {
+                // 外部命令在后台运行，等它写入缓冲区之后再执行下一个命令
+                result = result.and(self.editor.wait_for_shell_commands(SHELL_WAIT_TIMEOUT));
+                if let Some(confirmation) = self.editor.pending_confirmation() {
+                    let prompt = confirmation.prompt(self.editor.locale());
+                    let _ = self.editor.resolve_confirmation(false);
+                    result = result.and(Err(prompt));
+                }
+            }
+            if let Err(error) = result {
+                first_error.get_or_insert(error);
+            }
+            if self.editor.quit_requested() {
+                self.should_quit = true;
+                break;
+            }
+        }
+        self.update_scroll_offset();
+        match first_error {
+            Some(error) => {
+                self.editor.set_message(error.clone());
+                Err(error)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// 结束无界面模式：把从标准输入读入的缓冲区（如果还在）按读入时的格式写到标准输出
+    pub fn run_headless(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        self.write_stdin_buffer(&mut stdout)?;
+        stdout.flush()
+    }
+
+    /// 把从标准输入读入的缓冲区（如果还在）写到 `out`
+    pub fn write_stdin_buffer(&self, out: &mut impl Write) -> io::Result<()> {
+        match self.stdin_buffer.and_then(|id| self.editor.buffer(id)) {
+            Some(buffer) => buffer.write_contents(out),
+            None => Ok(()),
+        }
+    }
+
+    /// 执行用户配置文件（见 aivim_core::config）
+    pub fn load_config(&mut self) {
+        self.editor.load_config();
+    }
+
+    /// 执行会话文件，恢复 :mksession 保存的会话（aivim -S）
+    pub fn load_session(&mut self, path: &Path) {
+        if let Err(error) = self.editor.source_file(path) {
+            self.editor.set_message(error);
+        }
+        self.update_scroll_offset();
     }
 
     pub fn run(&mut self) -> io::Result<()> {
-        enable_raw_mode()?;
         let mut stdout = io::stdout();
-        stdout.execute(EnterAlternateScreen)?;
+        enter_terminal(&mut stdout)?;
+        self.event_handler.listen_input();
+        self.event_handler.listen_signals()?;
         
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
         
         let result = self.run_loop(&mut terminal);
+        // 记住各文件的光标位置，下次打开时恢复
+        self.editor.save_positions(&[]);
         
-        disable_raw_mode()?;
-        terminal.backend_mut().execute(LeaveAlternateScreen)?;
+        leave_terminal(terminal.backend_mut())?;
         
         result
     }
 
     fn run_loop(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
         loop {
-            terminal.draw(|f| ui::draw(f, &self.editor, self.scroll_offset, self.operator_state))?;
-
-            if self.should_quit {
-                break;
+            if let Some(request) = self.editor.take_terminal_request() {
+                self.hand_over_terminal(terminal, request)?;
             }
-
-            match self.event_handler.next()? {
-                Event::Tick => {}
-                Event::Key(key) => self.handle_key_event(key),
-                Event::Resize(_, height) => {
-                    self.update_scroll_offset(height as usize);
-                }
+            // Ctrl+W 的方向切换和调整大小按当前的屏幕大小计算
+            let size = terminal.size()?;
+            self.editor.set_window_area(ui::windows_rect(&self.editor, size));
+            if self.damage.take(&self.editor, size) {
+                terminal.draw(|f| ui::draw(f, &self.editor, self.operator_state()))?;
             }
-        }
-        
-        Ok(())
-    }
 
-    fn handle_key_event(&mut self, key: KeyEvent) {
-        // 如果寄存器面板正在显示，优先处理关闭操作
-        // 如果寄存器列表面板正在显示，优先处理关闭操作
-        if self.editor.show_registers_panel() {
-            match key.code {
-                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                    self.editor.set_show_registers_panel(false);
-                    return;
-                }
-                _ => {
-                    // 其他按键也关闭面板，但继续处理按键
-                    self.editor.set_show_registers_panel(false);
-                }
-            }
-        }
-
-        // 如果缓冲区列表面板正在显示，优先处理关闭操作
-        if self.editor.show_buffer_list() {
-            match key.code {
-                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                    self.editor.set_show_buffer_list(false);
-                    return;
-                }
-                _ => {
-                    // 其他按键也关闭面板，但继续处理按键
-                    self.editor.set_show_buffer_list(false);
-                }
+            if self.should_quit {
+                break;
             }
-        }
 
-        match self.editor.mode() {
-            Mode::Normal => self.handle_normal_mode(key),
-            Mode::Insert => self.handle_insert_mode(key),
-            Mode::Command => self.handle_command_mode(key),
-            Mode::SearchForward | Mode::SearchBackward => self.handle_search_mode(key),
-            _ => {}
-        }
-    }
-
-    fn handle_normal_mode(&mut self, key: KeyEvent) {
-        // 检查是否有操作符等待状态
-        match self.operator_state {
-            OperatorState::Delete { register } => {
-                // 检查是否是文本对象操作（daw, diw）
-                match key.code {
-                    KeyCode::Char('a') => {
-                        self.operator_state = OperatorState::TextObject { 
-                            operator: TextObjectOperator::Delete, 
-                            around: true,
-                            register,
-                        };
-                        return;
+            let event = self.event_handler.next()?;
+            // 空闲的 Tick 只在定时检查报告了变化时重绘，其他事件之后总是重绘
+            if !matches!(event, Event::Tick) {
+                self.damage.mark();
+            }
+            match event {
+                Event::Tick => {
+                    // :tail 模式下读取文件新追加的内容
+                    let mut changed = self.editor.poll_tail();
+                    if changed {
+                        self.update_scroll_offset();
                     }
-                    KeyCode::Char('i') => {
-                        self.operator_state = OperatorState::TextObject { 
-                            operator: TextObjectOperator::Delete, 
-                            around: false,
-                            register,
-                        };
-                        return;
+                    changed |= self.poll_background();
+                    changed |= self.editor.poll_paste_detection(Instant::now());
+                    // 映射前缀等待后续按键的最长时间由 timeoutlen 选项决定
+                    let timeout = Duration::from_millis(self.editor.options().timeoutlen as u64);
+                    if self.input.pending_since().is_some_and(|since| since.elapsed() >= timeout) {
+                        self.input.timeout(&mut self.editor);
+                        self.after_input();
+                        changed = true;
                     }
-                    _ => {
-                        self.handle_operator_motion(key, OperatorState::Delete { register });
-                        return;
+                    if changed {
+                        self.damage.mark();
                     }
                 }
-            }
-            OperatorState::Yank { register } => {
-                // 检查是否是文本对象操作（yaw, yiw）
-                match key.code {
-                    KeyCode::Char('a') => {
-                        self.operator_state = OperatorState::TextObject { 
-                            operator: TextObjectOperator::Yank, 
-                            around: true,
-                            register,
-                        };
-                        return;
-                    }
-                    KeyCode::Char('i') => {
-                        self.operator_state = OperatorState::TextObject { 
-                            operator: TextObjectOperator::Yank, 
-                            around: false,
-                            register,
-                        };
-                        return;
-                    }
-                    _ => {
-                        self.handle_operator_motion(key, OperatorState::Yank { register });
-                        return;
-                    }
+                Event::Key(key) => self.handle_key_event(key),
+                Event::Paste(text) => self.handle_paste(&text),
+                Event::Wake => {
+                    self.poll_background();
                 }
-            }
-            OperatorState::TextObject { operator, around, register } => {
-                // 处理文本对象（w, W, s, S, p, P）
-                self.handle_text_object(key, operator, around, register);
-                return;
-            }
-            OperatorState::RegisterPending(pending_reg) => {
-                // 处理寄存器选择
-                self.handle_register_selection(key, pending_reg);
-                return;
-            }
-            OperatorState::Change { .. } => {
-                // c - 修改操作符（计划中）
-                self.operator_state = OperatorState::None;
-            }
-            OperatorState::G => {
-                // g - 等待第二个 g (gg)
-                match key.code {
-                    KeyCode::Char('g') => {
-                        // gg - 跳到文件开头
-                        self.editor.execute_motion(Motion::DocumentStart);
-                        // 更新滚动偏移量，确保光标在可视区域内
-                        self.update_scroll_offset(terminal_height());
-                    }
-                    _ => {}
+                // 文件可能在外部被修改，也可能切换过分支
+                Event::FocusGained => self.editor.focus_gained(),
+                Event::Resize(_, _) => {
+                    self.update_scroll_offset();
                 }
-                self.operator_state = OperatorState::None;
-                return;
-            }
-            OperatorState::None => {}
-        }
-
-        match key.code {
-            KeyCode::Char('i') => {
-                self.editor.set_mode(Mode::Insert);
-            }
-            KeyCode::Char('a') => {
-                self.editor.enter_append_mode();
-            }
-            KeyCode::Char('o') => {
-                self.editor.execute_motion(Motion::LineEnd);
-                self.editor.insert_newline();
-                self.editor.set_mode(Mode::Insert);
-            }
-            KeyCode::Char('h') | KeyCode::Left => {
-                self.editor.execute_motion(Motion::Left);
-            }
-            KeyCode::Char('j') | KeyCode::Down => {
-                self.editor.execute_motion(Motion::Down);
-            }
-            KeyCode::Char('k') | KeyCode::Up => {
-                self.editor.execute_motion(Motion::Up);
-            }
-            KeyCode::Char('l') | KeyCode::Right => {
-                self.editor.execute_motion(Motion::Right);
-            }
-            KeyCode::Char('0') => {
-                self.editor.execute_motion(Motion::LineStart);
-            }
-            KeyCode::Char('$') => {
-                self.editor.execute_motion(Motion::LineEnd);
-            }
-            KeyCode::Char('^') => {
-                self.editor.execute_motion(Motion::FirstNonBlank);
-            }
-            KeyCode::Char('g') => {
-                // 进入 g 等待状态，等待第二个 g (gg)
-                self.operator_state = OperatorState::G;
-            }
-            KeyCode::Char('G') => {
-                self.editor.execute_motion(Motion::DocumentEnd);
-                // 更新滚动偏移量，确保光标在可视区域内
-                self.update_scroll_offset(terminal_height());
-            }
-            KeyCode::Char('w') => {
-                self.editor.execute_motion(Motion::WordForward);
-            }
-            KeyCode::Char('b') => {
-                self.editor.execute_motion(Motion::WordBackward);
-            }
-            KeyCode::Char('e') => {
-                self.editor.execute_motion(Motion::WordEnd);
-            }
-            KeyCode::Char('x') => {
-                self.editor.delete_char_to_register(None);
-            }
-            KeyCode::Char('"') => {
-                // " - 进入寄存器选择状态（等待寄存器名）
-                self.operator_state = OperatorState::RegisterPending(None);
-            }
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.editor.execute_motion(Motion::PageDown);
-            }
-            KeyCode::Char('d') => {
-                // d - 进入删除操作符等待状态
-                self.operator_state = OperatorState::Delete { register: None };
-            }
-            KeyCode::Char('y') => {
-                // y - 进入复制操作符等待状态
-                self.operator_state = OperatorState::Yank { register: None };
-            }
-            KeyCode::Char('p') => {
-                // p - 在光标后粘贴（无名寄存器）
-                self.editor.paste(None, false);
+                Event::Suspend => self.editor.suspend(),
             }
-            KeyCode::Char('P') => {
-                // P - 在光标前粘贴（无名寄存器）
-                self.editor.paste(None, true);
-            }
-            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.editor.execute_motion(Motion::PageUp);
-            }
-            KeyCode::Char('u') => {
-                self.editor.undo();
-            }
-            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.editor.redo();
-            }
-            KeyCode::Char(':') => {
-                self.editor.set_mode(Mode::Command);
-                self.editor.command_line_mut().clear();
-            }
-            KeyCode::Char('/') => {
-                self.editor.set_mode(Mode::SearchForward);
-                self.editor.command_line_mut().clear();
-            }
-            KeyCode::Char('?') => {
-                self.editor.set_mode(Mode::SearchBackward);
-                self.editor.command_line_mut().clear();
-            }
-            KeyCode::Char('n') => {
-                self.editor.search_next();
-            }
-            KeyCode::Char('N') => {
-                self.editor.search_prev();
-            }
-            KeyCode::Char(c) if c.is_ascii_digit() => {
-            }
-            _ => {}
         }
         
-        self.update_scroll_offset(terminal_height());
+        Ok(())
     }
 
-    fn handle_insert_mode(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Esc => {
-                self.editor.set_mode(Mode::Normal);
-            }
-            KeyCode::Char(c) => {
-                self.editor.insert_char(c);
-            }
-            KeyCode::Enter => {
-                self.editor.insert_newline();
-            }
-            KeyCode::Backspace => {
-                self.editor.backspace();
-            }
-            KeyCode::Tab => {
-                self.editor.insert_char(' ');
-                self.editor.insert_char(' ');
-                self.editor.insert_char(' ');
-                self.editor.insert_char(' ');
-            }
-            _ => {}
-        }
-        
-        self.update_scroll_offset(terminal_height());
-    }
+    /// 把终端交给 :shell 启动的 shell 或挂起进程 (Ctrl+Z)：恢复终端的正常模式，
+    /// 结束后重新进入全屏界面并完整重绘
+    fn hand_over_terminal(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        request: TerminalRequest,
+    ) -> io::Result<()> {
+        // 输入线程不再读取终端，按键留给 shell
+        self.event_handler.pause_input();
+        leave_terminal(terminal.backend_mut())?;
+        let result = match &request {
+            TerminalRequest::Suspend => suspend_process(),
+            TerminalRequest::Shell(shell) => std::process::Command::new(shell).status().map(Some),
+        };
+        enter_terminal(terminal.backend_mut())?;
+        self.event_handler.resume_input();
+        // 终端上是其他程序的输出，上一帧的内容不再有效
+        terminal.clear()?;
+        self.damage.mark();
 
-    fn handle_command_mode(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Esc => {
-                self.editor.set_mode(Mode::Normal);
-                self.editor.command_line_mut().clear();
-                self.editor.clear_message();
-            }
-            KeyCode::Enter => {
-                let command = self.editor.command_line().to_string();
-                self.editor.command_line_mut().clear();
-                self.editor.set_mode(Mode::Normal);
-                
-                // 执行命令
-                if let Err(e) = self.editor.execute_command(&command) {
-                    self.editor.set_message(e);
-                } else {
-                    match command.as_str() {
-                        "q" | "quit" | "qa" => {
-                            self.should_quit = true;
-                        }
-                        "q!" => {
-                            self.should_quit = true;
-                        }
-                        "wq" => {
-                            self.should_quit = true;
-                        }
-                        _ => {}
-                    }
-                }
+        let locale = self.editor.locale();
+        match (&request, result) {
+            (TerminalRequest::Shell(_), Ok(Some(status))) if !status.success() => {
+                let code = status.code().map_or_else(|| status.to_string(), |code| code.to_string());
+                self.editor.set_message(aivim_core::tr!(locale, "Shell returned {}", code));
             }
-            KeyCode::Char(c) => {
-                self.editor.command_line_mut().push(c);
+            (TerminalRequest::Shell(shell), Err(e)) => {
+                self.editor.set_message(aivim_core::tr!(locale, "Failed to run {}: {}", shell, e));
             }
-            KeyCode::Backspace => {
-                self.editor.command_line_mut().pop();
+            (TerminalRequest::Suspend, Err(e)) => {
+                self.editor.set_message(aivim_core::tr!(locale, "Cannot suspend: {}", e));
             }
             _ => {}
         }
+        // 文件可能在 shell 中被修改，也可能切换了分支
+        self.editor.focus_gained();
+        self.update_scroll_offset();
+        Ok(())
     }
 
-    fn handle_search_mode(&mut self, key: KeyEvent) {
-        use aivim_core::SearchDirection;
-
-        match key.code {
-            KeyCode::Esc => {
-                self.editor.set_mode(Mode::Normal);
-                self.editor.command_line_mut().clear();
-            }
-            KeyCode::Enter => {
-                // 先保存方向，再改变模式
-                let direction = match self.editor.mode() {
-                    Mode::SearchForward => SearchDirection::Forward,
-                    Mode::SearchBackward => SearchDirection::Backward,
-                    _ => SearchDirection::Forward,
-                };
-
-                let pattern = self.editor.command_line().to_string();
-                self.editor.command_line_mut().clear();
-                self.editor.set_mode(Mode::Normal);
-
-                if !pattern.is_empty() {
-                    self.editor.start_search(direction, &pattern);
-                }
-            }
-            KeyCode::Char(c) => {
-                self.editor.command_line_mut().push(c);
-            }
-            KeyCode::Backspace => {
-                self.editor.command_line_mut().pop();
-            }
-            _ => {}
+    /// 取出后台线程的结果：外部命令和终端缓冲区的输出、:grep 的匹配、文件查找的结果、后台读入的文件和被外部修改的文件；
+    /// 返回界面是否需要重绘
+    fn poll_background(&mut self) -> bool {
+        let mut changed = false;
+        // 外部命令的输出可能修改了缓冲区（:r !cmd、:%!cmd）
+        if self.editor.poll_jobs() {
+            self.update_scroll_offset();
+            changed = true;
         }
+        changed |= self.editor.poll_finder();
+        changed |= self.editor.poll_grep();
+        // 终端缓冲区中程序的输出
+        if self.editor.poll_terminals() {
+            self.update_scroll_offset();
+            changed = true;
+        }
+        // 后台读入的大文件读完时切换到它
+        if self.editor.poll_file_load(Instant::now()) {
+            self.update_scroll_offset();
+            changed = true;
+        }
+        // autoread 重新读入了被外部修改的文件
+        if self.editor.poll_file_changes() {
+            self.update_scroll_offset();
+            changed = true;
+        }
+        // 插件的后台工作：AI 回复、语言服务器的诊断、Git 差异标记和分支；
+        // 对话回复追加到对话缓冲区，当前窗口可能正在跟随显示
+        if self.editor.poll_plugins(Instant::now()) {
+            self.update_scroll_offset();
+            changed = true;
+        }
+        // CursorHold 自动命令可能修改了缓冲区
+        if self.editor.poll_cursor_hold(Instant::now()) {
+            self.update_scroll_offset();
+            changed = true;
+        }
+        changed
     }
 
-    /// 处理操作符 + 动作的组合（支持命名寄存器）
-    fn handle_operator_motion(&mut self, key: KeyEvent, operator: OperatorState) {
-        // 重置操作符状态
-        self.operator_state = OperatorState::None;
-
-        // 提取操作符类型和寄存器
-        let (is_delete, register) = match operator {
-            OperatorState::Delete { register } => (true, register),
-            OperatorState::Yank { register } => (false, register),
-            _ => return,
+    /// 处理用户输入的按键：先匹配用户映射，再交给各模式处理（见 aivim_core::input）
+    fn handle_key_event(&mut self, key: KeyEvent) {
+        self.editor.record_key_timing(Instant::now());
+        let Some(key) = to_map_key(key) else {
+            return;
         };
-
-        match key.code {
-            KeyCode::Char('d') => {
-                // dd - 删除当前行
-                if is_delete {
-                    self.editor.delete_line(register);
-                }
-            }
-            KeyCode::Char('y') => {
-                // yy - 复制当前行
-                if !is_delete {
-                    self.editor.yank_line(register);
-                }
-            }
-            KeyCode::Char('w') => {
-                // dw/yw - 删除/复制到下一个单词
-                if is_delete {
-                    self.editor.delete_to_motion_with_register(Motion::WordForward, register);
-                } else {
-                    self.editor.yank_to_motion_with_register(Motion::WordForward, register);
-                }
-            }
-            KeyCode::Char('b') => {
-                // db/yb - 删除/复制到上一个单词
-                if is_delete {
-                    self.editor.delete_to_motion_with_register(Motion::WordBackward, register);
-                } else {
-                    self.editor.yank_to_motion_with_register(Motion::WordBackward, register);
-                }
-            }
-            KeyCode::Char('l') | KeyCode::Right => {
-                // dl/yl - 删除/复制到右边一个字符
-                if is_delete {
-                    self.editor.delete_to_motion_with_register(Motion::Right, register);
-                } else {
-                    self.editor.yank_to_motion_with_register(Motion::Right, register);
-                }
-            }
-            KeyCode::Char('h') | KeyCode::Left => {
-                // dh/yh - 删除/复制到左边一个字符
-                if is_delete {
-                    self.editor.delete_to_motion_with_register(Motion::Left, register);
-                } else {
-                    self.editor.yank_to_motion_with_register(Motion::Left, register);
-                }
-            }
-            KeyCode::Char('$') => {
-                // d$/y$ - 删除/复制到行尾
-                if is_delete {
-                    self.editor.delete_to_motion_with_register(Motion::LineEnd, register);
-                } else {
-                    self.editor.yank_to_motion_with_register(Motion::LineEnd, register);
-                }
-            }
-            KeyCode::Char('0') => {
-                // d0/y0 - 删除/复制到行首
-                if is_delete {
-                    self.editor.delete_to_motion_with_register(Motion::LineStart, register);
-                } else {
-                    self.editor.yank_to_motion_with_register(Motion::LineStart, register);
-                }
-            }
-            KeyCode::Esc => {
-                // 取消操作符
-            }
-            _ => {}
-        }
+        let (width, height) = self.window_text_size();
+        self.editor.set_text_size(width, height);
+        self.input.handle_key(&mut self.editor, key);
+        self.after_input();
     }
 
-    /// 处理文本对象操作（daw, diw, yaw, yiw 等，支持命名寄存器）
-    fn handle_text_object(&mut self, key: KeyEvent, operator: TextObjectOperator, around: bool, register: Option<char>) {
-        use aivim_core::text_object::TextObject;
-
-        // 重置操作符状态
-        self.operator_state = OperatorState::None;
-
-        let text_object = match key.code {
-            KeyCode::Char('w') => {
-                if around {
-                    TextObject::AroundWord
-                } else {
-                    TextObject::InnerWord
-                }
-            }
-            KeyCode::Char('W') => {
-                if around {
-                    TextObject::AroundWord
-                } else {
-                    TextObject::InnerWord
-                }
-            }
-            _ => {
-                // 不支持的文本对象，取消操作
-                return;
-            }
-        };
+    /// 处理括号粘贴
+    fn handle_paste(&mut self, text: &str) {
+        self.input.paste(&mut self.editor, text);
+        self.after_input();
+    }
 
-        match operator {
-            TextObjectOperator::Delete => {
-                if let Some(content) = self.editor.delete_text_object(text_object) {
-                    if let Some(reg) = register {
-                        self.editor.register_manager_mut().set(reg, content, false);
-                    }
-                }
-            }
-            TextObjectOperator::Yank => {
-                if let Some(content) = self.editor.yank_text_object(text_object) {
-                    if let Some(reg) = register {
-                        self.editor.register_manager_mut().set(reg, content, false);
-                    }
-                }
-            }
-            TextObjectOperator::Change => {
-                // TODO: 实现 change 操作
-            }
+    /// 处理完按键之后滚动窗口，命令要求退出时结束事件循环
+    fn after_input(&mut self) {
+        if self.editor.quit_requested() {
+            self.should_quit = true;
         }
+        self.update_scroll_offset();
     }
 
-    /// 处理寄存器选择（"ayy, "ap 等）
-    /// 
-    /// 两步序列：
-    /// 1. " + a → 进入 RegisterPending(Some('a'))，等待操作符
-    /// 2. y + y → 执行操作，使用寄存器 a
-    fn handle_register_selection(&mut self, key: KeyEvent, pending_reg: Option<char>) {
-        match pending_reg {
-            None => {
-                // 第一步：等待寄存器名
-                match key.code {
-                    KeyCode::Char(c) if c.is_ascii_lowercase() || c.is_ascii_digit() => {
-                        // 已选择寄存器名，进入等待操作符状态
-                        self.operator_state = OperatorState::RegisterPending(Some(c));
-                    }
-                    KeyCode::Esc => {
-                        self.operator_state = OperatorState::None;
-                    }
-                    _ => {
-                        // 无效输入，取消
-                        self.operator_state = OperatorState::None;
-                    }
+    /// 当前按键序列对应的操作符等待状态
+    pub fn operator_state(&self) -> OperatorState {
+        let parser = self.input.key_parser();
+        if parser.awaiting_register() {
+            return OperatorState::RegisterPending(None);
+        }
+        let register = parser.register();
+        match parser.operator() {
+            None if parser.awaiting_g() => OperatorState::G,
+            None if parser.awaiting_z() => OperatorState::Z,
+            None if parser.awaiting_bracket() => OperatorState::Bracket,
+            None if parser.awaiting_replace_char() => OperatorState::ReplaceChar,
+            None => match register {
+                Some(reg) => OperatorState::RegisterPending(Some(reg)),
+                None => OperatorState::None,
+            },
+            Some(operator) => {
+                if let Some(around) = parser.text_object_around() {
+                    return OperatorState::TextObject {
+                        operator: operator.into(),
+                        around,
+                        register,
+                    };
                 }
-            }
-            Some(reg) => {
-                // 第二步：已选择寄存器，等待操作符（d/y/p）
-                match key.code {
-                    KeyCode::Char('d') => {
-                        // "ad... 进入删除操作符状态，指定寄存器
-                        self.operator_state = OperatorState::Delete { register: Some(reg) };
-                    }
-                    KeyCode::Char('y') => {
-                        // "ay... 进入复制操作符状态，指定寄存器
-                        self.operator_state = OperatorState::Yank { register: Some(reg) };
-                    }
-                    KeyCode::Char('p') => {
-                        // "ap - 直接从指定寄存器粘贴
-                        self.editor.paste(Some(reg), false);
-                        self.operator_state = OperatorState::None;
-                    }
-                    KeyCode::Char('P') => {
-                        // "aP - 直接从指定寄存器粘贴（前）
-                        self.editor.paste(Some(reg), true);
-                        self.operator_state = OperatorState::None;
-                    }
-                    KeyCode::Esc => {
-                        self.operator_state = OperatorState::None;
-                    }
-                    _ => {
-                        // 无效输入，取消
-                        self.operator_state = OperatorState::None;
-                    }
+                match operator {
+                    Operator::Delete => OperatorState::Delete { register },
+                    Operator::Yank => OperatorState::Yank { register },
+                    Operator::Change => OperatorState::Change { register },
+                    Operator::Indent => OperatorState::Indent,
+                    Operator::Outdent => OperatorState::Outdent,
+                    Operator::Fold => OperatorState::Fold,
+                    Operator::Surround(_) => OperatorState::Surround,
+                    Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => OperatorState::Case,
+                    Operator::Format => OperatorState::Format,
                 }
             }
         }
     }
 
-    fn update_scroll_offset(&mut self, viewport_height: usize) {
+    /// 滚动当前窗口，使光标保持在窗口内
+    fn update_scroll_offset(&mut self) {
+        // 缓冲区修改之后重新计算按缩进或语法的折叠
+        self.editor.update_folds();
         let cursor_line = self.editor.cursor().line;
-        self.scroll_offset = calculate_scroll_offset(
-            cursor_line,
-            viewport_height.saturating_sub(2),
-            self.scroll_offset,
-        );
+        let (width, viewport_height) = self.window_text_size();
+        let current = self.editor.scroll_offset();
+        let offset = calculate_scroll_offset(cursor_line, viewport_height, current);
+        let offset = calculate_folded_scroll_offset(&self.editor, viewport_height, width, offset, current);
+        let offset = calculate_wrapped_scroll_offset(&self.editor, viewport_height, width, offset);
+        self.editor.set_scroll_offset(offset);
     }
+
+    /// 当前窗口文本区域的（宽, 高）
+    fn window_text_size(&self) -> (usize, usize) {
+        let (width, height) = terminal_size();
+        ui::window_text_size(&self.editor, Rect::new(0, 0, width, height))
+    }
+}
+
+/// 进入全屏界面：raw 模式、备用屏幕、括号粘贴和焦点事件
+fn enter_terminal(out: &mut impl Write) -> io::Result<()> {
+    enable_raw_mode()?;
+    out.execute(EnterAlternateScreen)?;
+    // 粘贴的文本作为一个事件整段到达，而不是逐个按键
+    out.execute(EnableBracketedPaste)?;
+    // 重新获得焦点时检查文件是否在外部被修改
+    out.execute(EnableFocusChange)?;
+    Ok(())
+}
+
+/// 恢复终端的正常模式（退出、挂起或启动 shell 之前）
+fn leave_terminal(out: &mut impl Write) -> io::Result<()> {
+    disable_raw_mode()?;
+    out.execute(DisableBracketedPaste)?;
+    out.execute(DisableFocusChange)?;
+    out.execute(LeaveAlternateScreen)?;
+    Ok(())
+}
+
+/// 挂起进程，`fg` 之后返回；返回值与 :shell 的退出状态一致，挂起时没有
+#[cfg(unix)]
+fn suspend_process() -> io::Result<Option<std::process::ExitStatus>> {
+    // SIGTSTP 已经由 listen_signals 处理，按它的默认行为停止进程（SIGSTOP）
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+    Ok(None)
+}
+
+#[cfg(not(unix))]
+fn suspend_process() -> io::Result<Option<std::process::ExitStatus>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "not supported on this platform"))
 }
 
-fn terminal_height() -> usize {
-    crossterm::terminal::size()
-        .map(|(_, h)| h as usize)
-        .unwrap_or(24)
+/// 终端的大小（宽, 高），无法获取时为 80x24
+fn terminal_size() -> (u16, u16) {
+    crossterm::terminal::size().unwrap_or((80, 24))
+}
+
+/// 终端按键转换为映射按键，不支持的按键返回 None
+fn to_map_key(key: KeyEvent) -> Option<MapKey> {
+    let map_key = match key.code {
+        // 终端把 Ctrl+\ 报告为 Ctrl+4
+        KeyCode::Char('4' | '\\') if key.modifiers.contains(KeyModifiers::CONTROL) => MapKey::Ctrl('\\'),
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            MapKey::Ctrl(c.to_ascii_lowercase())
+        }
+        KeyCode::Char(c) => MapKey::Char(c),
+        KeyCode::Enter => MapKey::Enter,
+        KeyCode::Esc => MapKey::Esc,
+        KeyCode::Tab => MapKey::Tab,
+        KeyCode::Backspace => MapKey::Backspace,
+        KeyCode::Up => MapKey::Up,
+        KeyCode::Down => MapKey::Down,
+        KeyCode::Left => MapKey::Left,
+        KeyCode::Right => MapKey::Right,
+        KeyCode::PageUp => MapKey::PageUp,
+        KeyCode::PageDown => MapKey::PageDown,
+        KeyCode::Home => MapKey::Home,
+        KeyCode::End => MapKey::End,
+        KeyCode::BackTab => MapKey::BackTab,
+        _ => return None,
+    };
+    Some(map_key)
 }
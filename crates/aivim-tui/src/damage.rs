@@ -0,0 +1,85 @@
+//! 重绘判断
+//!
+//! 事件循环每 50ms 有一次 Tick，空闲时每次都重新生成所有可见行的文本会白白占用 CPU：
+//! - 按键、粘贴、终端大小变化、重新获得焦点和后台线程的唤醒之后总是重绘（`Damage::mark`）
+//! - Tick 只在定时检查（`poll_*`）报告了变化，或界面依赖的状态（`FrameState`）与上一帧不同时重绘
+
+use aivim_core::buffer::BufferId;
+use aivim_core::window::Layout;
+use aivim_core::{Cursor, Editor, Mode};
+use ratatui::layout::Rect;
+
+/// 一帧界面依赖的状态：与上一帧相同时不需要重绘
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameState {
+    size: Rect,
+    mode: Mode,
+    cursor: Cursor,
+    scroll_offset: usize,
+    layout: Layout,
+    /// 各窗口显示的缓冲区、它的 changedtick、光标和滚动位置，当前窗口的光标和滚动位置在上面
+    windows: Vec<(BufferId, u64, Cursor, usize)>,
+    /// 当前缓冲区和它的 changedtick
+    buffer: (BufferId, u64),
+    message: Option<String>,
+    command_line: String,
+}
+
+impl FrameState {
+    /// 记下编辑器现在的状态，`size` 为终端的大小
+    pub fn capture(editor: &Editor, size: Rect) -> Self {
+        let windows = editor.windows();
+        let window_states = windows
+            .ids()
+            .into_iter()
+            .filter_map(|id| windows.get(id))
+            .map(|window| {
+                let tick = editor.buffer(window.buffer).map_or(0, |buffer| buffer.changedtick());
+                (window.buffer, tick, window.cursor, window.scroll_offset)
+            })
+            .collect();
+        let buffer = editor.current_buffer();
+        Self {
+            size,
+            mode: editor.mode(),
+            cursor: *editor.cursor(),
+            scroll_offset: editor.scroll_offset(),
+            layout: windows.layout().clone(),
+            windows: window_states,
+            buffer: (buffer.id(), buffer.changedtick()),
+            message: editor.message().map(str::to_string),
+            command_line: editor.command_line().to_string(),
+        }
+    }
+}
+
+/// 记录是否需要重绘
+#[derive(Debug, Default)]
+pub struct Damage {
+    /// 有事件要求重绘
+    dirty: bool,
+    /// 上一次绘制时的状态，还没有绘制过时为 None
+    last: Option<FrameState>,
+}
+
+impl Damage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 要求下一帧重绘（输入事件、定时检查报告了变化）
+    pub fn mark(&mut self) {
+        self.dirty = true;
+    }
+
+    /// 这一帧是否需要重绘；需要时记下这一帧的状态并清除重绘要求
+    pub fn take(&mut self, editor: &Editor, size: Rect) -> bool {
+        let state = FrameState::capture(editor, size);
+        if !self.dirty && self.last.as_ref() == Some(&state) {
+            return false;
+        }
+        self.dirty = false;
+        self.last = Some(state);
+        true
+    }
+}
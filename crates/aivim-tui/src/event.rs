@@ -1,31 +1,196 @@
+//! 事件循环的事件来源
+//!
+//! 所有事件通过一个通道送到事件循环：
+//! - 输入线程阻塞读取终端事件（按键、粘贴、窗口大小、获得焦点）并发送
+//! - 编辑器的后台线程通过 `EventHandler::waker` 发送 `Wake`，通知有新结果需要取出
+//! - 一段时间没有事件时返回 `Tick`，用于定时检查（:tail、粘贴检测、映射超时）
+//! - 收到 SIGTSTP（如 `kill -TSTP`）时发送 `Suspend`，由事件循环恢复终端后挂起进程
+//!
+//! 事件循环只在通道上等待，不会因为读取终端或后台工作而阻塞界面。
+//! 终端交给 :shell 或进程挂起期间输入线程暂停读取（`pause_input`），按键留给 shell
+
+use aivim_core::waker::Waker;
 use crossterm::event::{self, Event as CEvent, KeyEvent};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
 use std::time::Duration;
 
+/// 输入线程每次等待终端事件的最长时间，暂停请求最多等待这么久
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
 #[derive(Debug, Clone)]
 pub enum Event {
     Tick,
     Key(KeyEvent),
+    /// 终端的括号粘贴 (bracketed paste)
+    Paste(String),
     Resize(u16, u16),
+    /// 终端窗口重新获得焦点
+    FocusGained,
+    /// 后台线程有新结果（外部命令的输出、:grep、文件查找）
+    Wake,
+    /// 收到 SIGTSTP，需要挂起
+    Suspend,
+}
+
+/// 输入线程的状态
+#[derive(Debug, Default)]
+struct InputState {
+    /// 要求暂停读取终端
+    paused: bool,
+    /// 输入线程正在等待或读取终端事件
+    reading: bool,
+}
+
+/// 暂停和恢复输入线程：暂停之后输入线程不再读取终端，直到恢复
+#[derive(Debug, Default)]
+struct InputGate {
+    state: Mutex<InputState>,
+    changed: Condvar,
+}
+
+impl InputGate {
+    /// 输入线程等到没有暂停时开始读取
+    fn begin_read(&self) {
+        let mut state = self.state.lock().unwrap();
+        while state.paused {
+            state = self.changed.wait(state).unwrap();
+        }
+        state.reading = true;
+    }
+
+    fn end_read(&self) {
+        self.state.lock().unwrap().reading = false;
+        self.changed.notify_all();
+    }
+
+    /// 要求暂停，等到输入线程结束正在进行的读取
+    fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = true;
+        while state.reading {
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    fn resume(&self) {
+        self.state.lock().unwrap().paused = false;
+        self.changed.notify_all();
+    }
 }
 
 pub struct EventHandler {
     tick_rate: Duration,
+    sender: Sender<io::Result<Event>>,
+    receiver: Receiver<io::Result<Event>>,
+    /// 通道中是否已经有未处理的 `Wake`，避免后台线程大量输出时塞满通道
+    wake_pending: Arc<AtomicBool>,
+    input_gate: Arc<InputGate>,
 }
 
 impl EventHandler {
     pub fn new(tick_rate: Duration) -> Self {
-        Self { tick_rate }
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            tick_rate,
+            sender,
+            receiver,
+            wake_pending: Arc::new(AtomicBool::new(false)),
+            input_gate: Arc::default(),
+        }
+    }
+
+    /// 启动读取终端事件的线程，进入 raw 模式后调用
+    ///
+    /// 读取出错时把错误交给事件循环，线程结束
+    pub fn listen_input(&self) {
+        let sender = self.sender.clone();
+        let gate = Arc::clone(&self.input_gate);
+        thread::spawn(move || loop {
+            // 不一直阻塞在 read 中，使暂停请求能在 INPUT_POLL 之内生效
+            gate.begin_read();
+            let event = match event::poll(INPUT_POLL) {
+                Ok(true) => event::read(),
+                Ok(false) => {
+                    gate.end_read();
+                    continue;
+                }
+                Err(e) => Err(e),
+            };
+            gate.end_read();
+            let event = match event {
+                Ok(CEvent::Key(key)) => Ok(Event::Key(key)),
+                Ok(CEvent::Paste(text)) => Ok(Event::Paste(text)),
+                Ok(CEvent::Resize(w, h)) => Ok(Event::Resize(w, h)),
+                Ok(CEvent::FocusGained) => Ok(Event::FocusGained),
+                Ok(_) => continue,
+                Err(e) => Err(e),
+            };
+            let failed = event.is_err();
+            if sender.send(event).is_err() || failed {
+                break;
+            }
+        });
+    }
+
+    /// 暂停读取终端事件，返回时输入线程已经不在读取；终端交给其他程序之前调用
+    pub fn pause_input(&self) {
+        self.input_gate.pause();
+    }
+
+    /// 恢复读取终端事件
+    pub fn resume_input(&self) {
+        self.input_gate.resume();
+    }
+
+    /// 启动等待 SIGTSTP 的线程，收到时发送 `Suspend`
+    ///
+    /// raw 模式下 Ctrl+Z 作为按键到达，只有其他进程发送的 SIGTSTP 会走到这里
+    #[cfg(unix)]
+    pub fn listen_signals(&self) -> io::Result<()> {
+        use signal_hook::consts::SIGTSTP;
+        use signal_hook::iterator::Signals;
+
+        let mut signals = Signals::new([SIGTSTP])?;
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                if sender.send(Ok(Event::Suspend)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn listen_signals(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// 后台线程用来唤醒事件循环的回调，连续多次唤醒在事件循环处理之前只发送一个 `Wake`
+    pub fn waker(&self) -> Waker {
+        let sender = self.sender.clone();
+        let pending = Arc::clone(&self.wake_pending);
+        Waker::new(move || {
+            if !pending.swap(true, Ordering::AcqRel) {
+                let _ = sender.send(Ok(Event::Wake));
+            }
+        })
     }
 
-    pub fn next(&self) -> std::io::Result<Event> {
-        if event::poll(self.tick_rate)? {
-            match event::read()? {
-                CEvent::Key(key) => Ok(Event::Key(key)),
-                CEvent::Resize(w, h) => Ok(Event::Resize(w, h)),
-                _ => Ok(Event::Tick),
+    /// 等待下一个事件，`tick_rate` 内没有事件时返回 `Tick`
+    pub fn next(&self) -> io::Result<Event> {
+        match self.receiver.recv_timeout(self.tick_rate) {
+            Ok(Ok(Event::Wake)) => {
+                self.wake_pending.store(false, Ordering::Release);
+                Ok(Event::Wake)
             }
-        } else {
-            Ok(Event::Tick)
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => Ok(Event::Tick),
         }
     }
 }
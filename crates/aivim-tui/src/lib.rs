@@ -1,5 +1,8 @@
 pub mod app;
+pub mod damage;
 pub mod event;
+pub mod snapshot;
+pub mod theme;
 pub mod ui;
 
 pub use app::App;
@@ -0,0 +1,87 @@
+//! 界面快照测试支持
+//!
+//! 用 ratatui 的 TestBackend 把一帧渲染成文本，与 `tests/snapshots/` 下的 golden 文件比较，
+//! 状态栏、行号、面板布局或光标位置的变化都会让测试失败。
+//!
+//! 界面有意修改后，设置环境变量 `AIVIM_UPDATE_SNAPSHOTS=1` 运行测试即可重新生成 golden 文件：
+//!
+//! ```text
+//! AIVIM_UPDATE_SNAPSHOTS=1 cargo test -p aivim-tui
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use aivim_core::diff::unified_diff;
+use aivim_core::Editor;
+use ratatui::backend::{Backend, TestBackend};
+use ratatui::Terminal;
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::OperatorState;
+use crate::ui;
+
+/// 打开更新模式的环境变量
+pub const UPDATE_ENV: &str = "AIVIM_UPDATE_SNAPSHOTS";
+
+/// 把编辑器渲染成 `width` x `height` 的一帧文本
+///
+/// 每行去掉末尾空格，最后一行记录光标位置（列, 行），只比较字符不比较颜色
+pub fn render(editor: &Editor, width: u16, height: u16, operator_state: OperatorState) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("test backend");
+    terminal
+        .draw(|frame| ui::draw(frame, editor, operator_state))
+        .expect("draw frame");
+
+    let buffer = terminal.backend().buffer();
+    let mut output = String::new();
+    for row in buffer.content.chunks(buffer.area.width as usize) {
+        let mut line = String::new();
+        // 宽字符之后被覆盖的单元格不输出
+        let mut skip = 0;
+        for cell in row {
+            if skip == 0 {
+                line.push_str(&cell.symbol);
+            }
+            skip = skip.max(cell.symbol.width()).saturating_sub(1);
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    let (x, y) = terminal.backend_mut().get_cursor().expect("cursor position");
+    output.push_str(&format!("cursor: {}, {}\n", x, y));
+    output
+}
+
+/// 与 golden 文件 `tests/snapshots/<name>.snap` 比较，不一致时 panic 并显示差异
+///
+/// 更新模式下直接写入 golden 文件
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = snapshot_path(name);
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        fs::create_dir_all(path.parent().expect("snapshot dir")).expect("create snapshot dir");
+        fs::write(&path, actual).expect("write snapshot");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "snapshot {} not found, run with {}=1 to create it",
+            path.display(),
+            UPDATE_ENV
+        )
+    });
+    if let Some(diff) = unified_diff("expected", "actual", &expected, actual, 3) {
+        panic!(
+            "snapshot {} does not match, run with {}=1 to update it\n{}",
+            name, UPDATE_ENV, diff
+        );
+    }
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.snap", name))
+}
@@ -0,0 +1,519 @@
+//! 界面配色
+//!
+//! 所有绘制代码只引用主题中的样式，不直接写颜色：
+//! - 内置主题与 aivim-core 的 `colorscheme::COLORSCHEMES` 一一对应（default、gruvbox、light）
+//! - `Theme::for_editor` 取编辑器当前的配色方案，再应用 `:highlight` 的修改
+//! - 每个字段对应一个高亮组（见 `colorscheme::HIGHLIGHT_GROUPS`），
+//!   其中 Visual 用于选择区域和多光标的其他光标，Search 和语法高亮组留给搜索匹配和语法高亮的绘制
+
+use aivim_core::colorscheme::{Highlight, HighlightColor};
+use aivim_core::git_signs::Sign;
+use aivim_core::lsp::Severity;
+use aivim_core::{Editor, Mode};
+use ratatui::style::{Color, Modifier, Style};
+
+/// 界面的高亮样式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// 文本区域（Normal）
+    pub normal: Style,
+    /// 其他行的行号（LineNr）
+    pub line_number: Style,
+    /// 当前行的行号（CurrentLineNr）
+    pub current_line_number: Style,
+    /// 开启 cursorline 时当前行的行号（CursorLineNr）
+    pub cursor_line_number: Style,
+    /// 行号区域的背景（Gutter）
+    pub gutter: Style,
+    /// 开启 cursorline 时当前行的样式（CursorLine）
+    pub cursor_line: Style,
+    /// 开启 cursorcolumn 时光标所在列的样式（CursorColumn）
+    pub cursor_column: Style,
+    /// 缓冲区末尾之后的 `~`（EndOfBuffer）
+    pub end_of_buffer: Style,
+    /// 关闭的折叠的摘要行（Folded）
+    pub folded: Style,
+    /// 可视模式的选择区域，多光标时的其他光标（Visual）
+    pub visual: Style,
+    /// 搜索匹配（Search）
+    pub search: Style,
+    /// diff 缓冲区的新增行（DiffAdd）
+    pub diff_add: Style,
+    /// diff 缓冲区的删除行（DiffDelete）
+    pub diff_delete: Style,
+    /// diff 缓冲区的块头 `@@`（DiffHunk）
+    pub diff_hunk: Style,
+    /// diff 缓冲区的文件头 `+++` / `---`（DiffHeader）
+    pub diff_header: Style,
+    /// Insert 模式光标之后的 AI 补全建议（AiSuggestion）
+    pub suggestion: Style,
+    /// 行号左侧的诊断标记和状态栏中的诊断数（DiagnosticError 等）
+    pub diagnostic_error: Style,
+    pub diagnostic_warn: Style,
+    pub diagnostic_info: Style,
+    pub diagnostic_hint: Style,
+    /// 标记列中与 HEAD 相比新增、修改、删除的行（GitSignsAdd、GitSignsChange、GitSignsDelete）
+    pub git_add: Style,
+    pub git_change: Style,
+    pub git_delete: Style,
+    /// 行尾显示的 blame 信息 (:GitBlame)
+    pub git_blame: Style,
+    /// 诊断范围的下划线（DiagnosticUnderlineError 等）
+    pub diagnostic_underline_error: Style,
+    pub diagnostic_underline_warn: Style,
+    pub diagnostic_underline_info: Style,
+    pub diagnostic_underline_hint: Style,
+    pub comment: Style,
+    pub string: Style,
+    pub number: Style,
+    pub keyword: Style,
+    pub function: Style,
+    pub type_name: Style,
+    /// 状态栏（StatusLine）
+    pub status_line: Style,
+    /// 非当前窗口的状态栏（StatusLineNC）
+    pub status_line_inactive: Style,
+    /// 左右相邻窗口之间的分隔线（WinSeparator）
+    pub window_separator: Style,
+    /// 标签栏中的其他标签页（TabLine）
+    pub tab_line: Style,
+    /// 标签栏中的当前标签页（TabLineSel）
+    pub tab_line_selected: Style,
+    /// 标签栏中标签页之后的空白（TabLineFill）
+    pub tab_line_fill: Style,
+    /// 状态栏中等待操作符的寄存器（StatusRegister）
+    pub status_register: Style,
+    /// 状态栏中各模式的名称（ModeNormal 等）
+    pub mode_normal: Style,
+    pub mode_insert: Style,
+    pub mode_visual: Style,
+    pub mode_command: Style,
+    pub mode_replace: Style,
+    pub mode_search: Style,
+    /// 命令行中的消息（Message）
+    pub message: Style,
+    /// 错误，如 Quickfix 面板中的 error（ErrorMsg）
+    pub error_msg: Style,
+    /// 警告（WarningMsg）
+    pub warning_msg: Style,
+    /// 补全菜单（Pmenu）
+    pub menu: Style,
+    /// 补全菜单和选择器中选中的一项（PmenuSel）
+    pub menu_selected: Style,
+    /// 候选项的说明文字（PmenuExtra）
+    pub menu_extra: Style,
+    /// 浮动面板的边框（FloatBorder）
+    pub float_border: Style,
+    /// 次要面板（如补全预览）的边框（FloatBorderDim）
+    pub float_border_dim: Style,
+    /// 面板标题（Title）
+    pub title: Style,
+    /// 面板中的表头、寄存器名和任务编号（Label）
+    pub label: Style,
+    /// 面板中的分隔线和次要文字（NonText）
+    pub non_text: Style,
+    /// 面板底部的提示（Hint）
+    pub hint: Style,
+    /// 提示中的按键和命令（HintKey）
+    pub hint_key: Style,
+}
+
+/// 状态栏中模式名称的样式：黑色文字，背景为模式的颜色
+const fn mode(color: Color) -> Style {
+    Style::new().fg(Color::Black).bg(color).add_modifier(Modifier::BOLD)
+}
+
+impl Theme {
+    /// 默认的深色主题（default）
+    pub const DARK: Theme = Theme {
+        normal: Style::new(),
+        line_number: Style::new().fg(Color::DarkGray),
+        current_line_number: Style::new().fg(Color::Yellow),
+        cursor_line_number: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        gutter: Style::new().bg(Color::Black),
+        // 柔和的高亮背景
+        cursor_line: Style::new().bg(Color::Rgb(40, 40, 40)),
+        cursor_column: Style::new().bg(Color::Rgb(40, 40, 40)),
+        end_of_buffer: Style::new().fg(Color::Blue),
+        folded: Style::new().fg(Color::Cyan).bg(Color::Rgb(40, 40, 40)),
+        visual: Style::new().bg(Color::DarkGray),
+        search: Style::new().fg(Color::Black).bg(Color::Yellow),
+        diff_add: Style::new().fg(Color::Green),
+        diff_delete: Style::new().fg(Color::Red),
+        diff_hunk: Style::new().fg(Color::Cyan),
+        diff_header: Style::new().add_modifier(Modifier::BOLD),
+        suggestion: Style::new().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        diagnostic_error: Style::new().fg(Color::Red),
+        diagnostic_warn: Style::new().fg(Color::Yellow),
+        diagnostic_info: Style::new().fg(Color::Blue),
+        diagnostic_hint: Style::new().fg(Color::Cyan),
+        git_add: Style::new().fg(Color::Green),
+        git_change: Style::new().fg(Color::Yellow),
+        git_delete: Style::new().fg(Color::Red),
+        git_blame: Style::new().fg(Color::DarkGray),
+        diagnostic_underline_error: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Red),
+        diagnostic_underline_warn: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Yellow),
+        diagnostic_underline_info: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Blue),
+        diagnostic_underline_hint: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Cyan),
+        comment: Style::new().fg(Color::DarkGray),
+        string: Style::new().fg(Color::Green),
+        number: Style::new().fg(Color::Magenta),
+        keyword: Style::new().fg(Color::Yellow),
+        function: Style::new().fg(Color::Cyan),
+        type_name: Style::new().fg(Color::LightBlue),
+        status_line: Style::new(),
+        status_line_inactive: Style::new().fg(Color::DarkGray),
+        window_separator: Style::new().fg(Color::DarkGray),
+        tab_line: Style::new().fg(Color::Gray).bg(Color::DarkGray),
+        tab_line_selected: Style::new().add_modifier(Modifier::BOLD),
+        tab_line_fill: Style::new().bg(Color::DarkGray),
+        status_register: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        mode_normal: mode(Color::Blue),
+        mode_insert: mode(Color::Green),
+        mode_visual: mode(Color::Yellow),
+        mode_command: mode(Color::Magenta),
+        mode_replace: mode(Color::Red),
+        mode_search: mode(Color::Cyan),
+        message: Style::new().fg(Color::Yellow),
+        error_msg: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        warning_msg: Style::new().fg(Color::Yellow),
+        menu: Style::new().fg(Color::White).bg(Color::DarkGray),
+        menu_selected: Style::new().fg(Color::White).bg(Color::Blue).add_modifier(Modifier::BOLD),
+        menu_extra: Style::new().fg(Color::Gray),
+        float_border: Style::new().fg(Color::Blue),
+        float_border_dim: Style::new().fg(Color::DarkGray),
+        title: Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        label: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        non_text: Style::new().fg(Color::DarkGray),
+        hint: Style::new().fg(Color::Yellow),
+        hint_key: Style::new().fg(Color::Green),
+    };
+
+    /// gruvbox 深色主题
+    pub const GRUVBOX: Theme = {
+        const BG: Color = Color::Rgb(40, 40, 40);
+        const BG1: Color = Color::Rgb(60, 56, 54);
+        const BG2: Color = Color::Rgb(80, 73, 69);
+        const FG: Color = Color::Rgb(235, 219, 178);
+        const GRAY: Color = Color::Rgb(146, 131, 116);
+        const RED: Color = Color::Rgb(251, 73, 52);
+        const GREEN: Color = Color::Rgb(184, 187, 38);
+        const YELLOW: Color = Color::Rgb(250, 189, 47);
+        const BLUE: Color = Color::Rgb(131, 165, 152);
+        const PURPLE: Color = Color::Rgb(211, 134, 155);
+        const AQUA: Color = Color::Rgb(142, 192, 124);
+        const ORANGE: Color = Color::Rgb(254, 128, 25);
+        const fn mode(color: Color) -> Style {
+            Style::new().fg(BG).bg(color).add_modifier(Modifier::BOLD)
+        }
+        Theme {
+            normal: Style::new().fg(FG).bg(BG),
+            line_number: Style::new().fg(GRAY),
+            current_line_number: Style::new().fg(YELLOW),
+            cursor_line_number: Style::new().fg(YELLOW).add_modifier(Modifier::BOLD),
+            gutter: Style::new().bg(BG),
+            cursor_line: Style::new().bg(BG1),
+            cursor_column: Style::new().bg(BG1),
+            end_of_buffer: Style::new().fg(BG2),
+            folded: Style::new().fg(GRAY).bg(BG1),
+            visual: Style::new().bg(BG2),
+            search: Style::new().fg(BG).bg(YELLOW),
+            diff_add: Style::new().fg(GREEN),
+            diff_delete: Style::new().fg(RED),
+            diff_hunk: Style::new().fg(AQUA),
+            diff_header: Style::new().fg(FG).add_modifier(Modifier::BOLD),
+            suggestion: Style::new().fg(GRAY).add_modifier(Modifier::ITALIC),
+            diagnostic_error: Style::new().fg(RED),
+            diagnostic_warn: Style::new().fg(YELLOW),
+            diagnostic_info: Style::new().fg(BLUE),
+            diagnostic_hint: Style::new().fg(AQUA),
+            git_add: Style::new().fg(GREEN),
+            git_change: Style::new().fg(AQUA),
+            git_delete: Style::new().fg(RED),
+            git_blame: Style::new().fg(GRAY),
+            diagnostic_underline_error: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(RED),
+            diagnostic_underline_warn: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(YELLOW),
+            diagnostic_underline_info: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(BLUE),
+            diagnostic_underline_hint: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(AQUA),
+            comment: Style::new().fg(GRAY).add_modifier(Modifier::ITALIC),
+            string: Style::new().fg(GREEN),
+            number: Style::new().fg(PURPLE),
+            keyword: Style::new().fg(RED),
+            function: Style::new().fg(GREEN).add_modifier(Modifier::BOLD),
+            type_name: Style::new().fg(YELLOW),
+            status_line: Style::new().fg(FG).bg(BG1),
+            status_line_inactive: Style::new().fg(GRAY).bg(BG1),
+            window_separator: Style::new().fg(BG2),
+            tab_line: Style::new().fg(GRAY).bg(BG1),
+            tab_line_selected: Style::new().fg(FG).bg(BG).add_modifier(Modifier::BOLD),
+            tab_line_fill: Style::new().bg(BG1),
+            status_register: Style::new().fg(ORANGE).add_modifier(Modifier::BOLD),
+            mode_normal: mode(BLUE),
+            mode_insert: mode(GREEN),
+            mode_visual: mode(ORANGE),
+            mode_command: mode(PURPLE),
+            mode_replace: mode(RED),
+            mode_search: mode(AQUA),
+            message: Style::new().fg(YELLOW),
+            error_msg: Style::new().fg(RED).add_modifier(Modifier::BOLD),
+            warning_msg: Style::new().fg(ORANGE),
+            menu: Style::new().fg(FG).bg(BG1),
+            menu_selected: Style::new().fg(BG).bg(BLUE).add_modifier(Modifier::BOLD),
+            menu_extra: Style::new().fg(GRAY),
+            float_border: Style::new().fg(BLUE),
+            float_border_dim: Style::new().fg(BG2),
+            title: Style::new().fg(AQUA).add_modifier(Modifier::BOLD),
+            label: Style::new().fg(YELLOW).add_modifier(Modifier::BOLD),
+            non_text: Style::new().fg(GRAY),
+            hint: Style::new().fg(YELLOW),
+            hint_key: Style::new().fg(GREEN),
+        }
+    };
+
+    /// 浅色主题，用于浅色背景的终端
+    pub const LIGHT: Theme = Theme {
+        normal: Style::new(),
+        line_number: Style::new().fg(Color::Gray),
+        current_line_number: Style::new().fg(Color::Blue),
+        cursor_line_number: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        gutter: Style::new(),
+        cursor_line: Style::new().bg(Color::Rgb(232, 232, 232)),
+        cursor_column: Style::new().bg(Color::Rgb(232, 232, 232)),
+        end_of_buffer: Style::new().fg(Color::Gray),
+        folded: Style::new().fg(Color::Blue).bg(Color::Rgb(232, 232, 232)),
+        visual: Style::new().bg(Color::Rgb(200, 215, 240)),
+        search: Style::new().bg(Color::LightYellow),
+        diff_add: Style::new().fg(Color::Rgb(0, 128, 0)),
+        diff_delete: Style::new().fg(Color::Rgb(180, 0, 0)),
+        diff_hunk: Style::new().fg(Color::Blue),
+        diff_header: Style::new().add_modifier(Modifier::BOLD),
+        suggestion: Style::new().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+        diagnostic_error: Style::new().fg(Color::Red),
+        diagnostic_warn: Style::new().fg(Color::Rgb(200, 120, 0)),
+        diagnostic_info: Style::new().fg(Color::Blue),
+        diagnostic_hint: Style::new().fg(Color::Rgb(0, 128, 128)),
+        git_add: Style::new().fg(Color::Rgb(0, 140, 0)),
+        git_change: Style::new().fg(Color::Rgb(200, 120, 0)),
+        git_delete: Style::new().fg(Color::Red),
+        git_blame: Style::new().fg(Color::Gray),
+        diagnostic_underline_error: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Red),
+        diagnostic_underline_warn: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Rgb(200, 120, 0)),
+        diagnostic_underline_info: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Blue),
+        diagnostic_underline_hint: Style::new().add_modifier(Modifier::UNDERLINED).underline_color(Color::Rgb(0, 128, 128)),
+        comment: Style::new().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+        string: Style::new().fg(Color::Rgb(0, 128, 0)),
+        number: Style::new().fg(Color::Magenta),
+        keyword: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        function: Style::new().fg(Color::Rgb(0, 96, 160)),
+        type_name: Style::new().fg(Color::Rgb(160, 80, 0)),
+        status_line: Style::new().fg(Color::Black).bg(Color::Rgb(220, 220, 220)),
+        status_line_inactive: Style::new().fg(Color::DarkGray).bg(Color::Rgb(235, 235, 235)),
+        window_separator: Style::new().fg(Color::Gray),
+        tab_line: Style::new().fg(Color::DarkGray).bg(Color::Rgb(220, 220, 220)),
+        tab_line_selected: Style::new().fg(Color::Black).add_modifier(Modifier::BOLD),
+        tab_line_fill: Style::new().bg(Color::Rgb(220, 220, 220)),
+        status_register: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        mode_normal: Style::new().fg(Color::White).bg(Color::Blue).add_modifier(Modifier::BOLD),
+        mode_insert: Style::new().fg(Color::White).bg(Color::Green).add_modifier(Modifier::BOLD),
+        mode_visual: Style::new().fg(Color::White).bg(Color::Magenta).add_modifier(Modifier::BOLD),
+        mode_command: Style::new().fg(Color::White).bg(Color::DarkGray).add_modifier(Modifier::BOLD),
+        mode_replace: Style::new().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+        mode_search: Style::new().fg(Color::White).bg(Color::Cyan).add_modifier(Modifier::BOLD),
+        message: Style::new().fg(Color::Blue),
+        error_msg: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        warning_msg: Style::new().fg(Color::Rgb(200, 120, 0)),
+        menu: Style::new().fg(Color::Black).bg(Color::Rgb(220, 220, 220)),
+        menu_selected: Style::new().fg(Color::White).bg(Color::Blue).add_modifier(Modifier::BOLD),
+        menu_extra: Style::new().fg(Color::DarkGray),
+        float_border: Style::new().fg(Color::Blue),
+        float_border_dim: Style::new().fg(Color::Gray),
+        title: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        label: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        non_text: Style::new().fg(Color::Gray),
+        hint: Style::new().fg(Color::Blue),
+        hint_key: Style::new().fg(Color::Rgb(0, 128, 0)),
+    };
+
+    /// 按配色方案名取内置主题
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Self::DARK),
+            "gruvbox" => Some(Self::GRUVBOX),
+            "light" => Some(Self::LIGHT),
+            _ => None,
+        }
+    }
+
+    /// 编辑器当前的主题：配色方案加上 :highlight 的修改
+    pub fn for_editor(editor: &Editor) -> Theme {
+        let mut theme = Self::builtin(editor.colorscheme()).unwrap_or_default();
+        for (group, highlight) in editor.highlights() {
+            if let Some(style) = theme.group_mut(group) {
+                apply_highlight(style, highlight);
+            }
+        }
+        theme
+    }
+
+    /// 高亮组对应的样式，组名见 `colorscheme::HIGHLIGHT_GROUPS`
+    pub fn group_mut(&mut self, group: &str) -> Option<&mut Style> {
+        let style = match group {
+            "Normal" => &mut self.normal,
+            "LineNr" => &mut self.line_number,
+            "CurrentLineNr" => &mut self.current_line_number,
+            "CursorLineNr" => &mut self.cursor_line_number,
+            "Gutter" => &mut self.gutter,
+            "CursorLine" => &mut self.cursor_line,
+            "CursorColumn" => &mut self.cursor_column,
+            "EndOfBuffer" => &mut self.end_of_buffer,
+            "Folded" => &mut self.folded,
+            "Visual" => &mut self.visual,
+            "Search" => &mut self.search,
+            "DiffAdd" => &mut self.diff_add,
+            "DiffDelete" => &mut self.diff_delete,
+            "DiffHunk" => &mut self.diff_hunk,
+            "DiffHeader" => &mut self.diff_header,
+            "AiSuggestion" => &mut self.suggestion,
+            "DiagnosticError" => &mut self.diagnostic_error,
+            "DiagnosticWarn" => &mut self.diagnostic_warn,
+            "DiagnosticInfo" => &mut self.diagnostic_info,
+            "DiagnosticHint" => &mut self.diagnostic_hint,
+            "GitSignsAdd" => &mut self.git_add,
+            "GitSignsChange" => &mut self.git_change,
+            "GitSignsDelete" => &mut self.git_delete,
+            "GitBlame" => &mut self.git_blame,
+            "DiagnosticUnderlineError" => &mut self.diagnostic_underline_error,
+            "DiagnosticUnderlineWarn" => &mut self.diagnostic_underline_warn,
+            "DiagnosticUnderlineInfo" => &mut self.diagnostic_underline_info,
+            "DiagnosticUnderlineHint" => &mut self.diagnostic_underline_hint,
+            "Comment" => &mut self.comment,
+            "String" => &mut self.string,
+            "Number" => &mut self.number,
+            "Keyword" => &mut self.keyword,
+            "Function" => &mut self.function,
+            "Type" => &mut self.type_name,
+            "StatusLine" => &mut self.status_line,
+            "StatusLineNC" => &mut self.status_line_inactive,
+            "WinSeparator" => &mut self.window_separator,
+            "TabLine" => &mut self.tab_line,
+            "TabLineSel" => &mut self.tab_line_selected,
+            "TabLineFill" => &mut self.tab_line_fill,
+            "StatusRegister" => &mut self.status_register,
+            "ModeNormal" => &mut self.mode_normal,
+            "ModeInsert" => &mut self.mode_insert,
+            "ModeVisual" => &mut self.mode_visual,
+            "ModeCommand" => &mut self.mode_command,
+            "ModeReplace" => &mut self.mode_replace,
+            "ModeSearch" => &mut self.mode_search,
+            "Message" => &mut self.message,
+            "ErrorMsg" => &mut self.error_msg,
+            "WarningMsg" => &mut self.warning_msg,
+            "Pmenu" => &mut self.menu,
+            "PmenuSel" => &mut self.menu_selected,
+            "PmenuExtra" => &mut self.menu_extra,
+            "FloatBorder" => &mut self.float_border,
+            "FloatBorderDim" => &mut self.float_border_dim,
+            "Title" => &mut self.title,
+            "Label" => &mut self.label,
+            "NonText" => &mut self.non_text,
+            "Hint" => &mut self.hint,
+            "HintKey" => &mut self.hint_key,
+            _ => return None,
+        };
+        Some(style)
+    }
+
+    /// 状态栏中模式名称的样式
+    pub fn mode_style(&self, mode: Mode) -> Style {
+        match mode {
+            Mode::Normal => self.mode_normal,
+            // Terminal 模式与 Insert 模式一样是在输入文本
+            Mode::Insert | Mode::Terminal => self.mode_insert,
+            Mode::Visual => self.mode_visual,
+            Mode::Command => self.mode_command,
+            Mode::Replace => self.mode_replace,
+            Mode::SearchForward | Mode::SearchBackward => self.mode_search,
+        }
+    }
+
+    /// 诊断标记的样式
+    pub fn diagnostic(&self, severity: Severity) -> Style {
+        match severity {
+            Severity::Error => self.diagnostic_error,
+            Severity::Warning => self.diagnostic_warn,
+            Severity::Information => self.diagnostic_info,
+            Severity::Hint => self.diagnostic_hint,
+        }
+    }
+
+    /// Git 差异标记的样式
+    pub fn git_sign(&self, sign: Sign) -> Style {
+        match sign {
+            Sign::Added => self.git_add,
+            Sign::Modified => self.git_change,
+            Sign::Removed | Sign::RemovedAbove => self.git_delete,
+        }
+    }
+
+    /// 诊断范围的下划线样式
+    pub fn diagnostic_underline(&self, severity: Severity) -> Style {
+        match severity {
+            Severity::Error => self.diagnostic_underline_error,
+            Severity::Warning => self.diagnostic_underline_warn,
+            Severity::Information => self.diagnostic_underline_info,
+            Severity::Hint => self.diagnostic_underline_hint,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// 把 :highlight 的修改应用到样式上，文字属性整体替换
+fn apply_highlight(style: &mut Style, highlight: &Highlight) {
+    if let Some(fg) = highlight.fg {
+        style.fg = Some(color(fg));
+    }
+    if let Some(bg) = highlight.bg {
+        style.bg = Some(color(bg));
+    }
+    if let Some(attrs) = highlight.attrs {
+        let mut modifier = Modifier::empty();
+        modifier.set(Modifier::BOLD, attrs.bold);
+        modifier.set(Modifier::ITALIC, attrs.italic);
+        modifier.set(Modifier::UNDERLINED, attrs.underline);
+        modifier.set(Modifier::REVERSED, attrs.reverse);
+        style.add_modifier = modifier;
+        style.sub_modifier = Modifier::all() - modifier;
+    }
+}
+
+/// 终端颜色编号 0-15 使用 ratatui 的命名颜色，其余按编号或 RGB
+fn color(color: HighlightColor) -> Color {
+    const ANSI: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Gray,
+        Color::DarkGray,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::White,
+    ];
+    match color {
+        HighlightColor::Reset => Color::Reset,
+        HighlightColor::Indexed(index) => ANSI.get(index as usize).copied().unwrap_or(Color::Indexed(index)),
+        HighlightColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
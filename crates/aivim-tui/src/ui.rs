@@ -1,110 +1,243 @@
-use aivim_core::Editor;
+use aivim_core::display::{self, ScreenRow};
+use aivim_core::encoding::Encoding;
+use aivim_core::git_signs::{self, Sign};
+use aivim_core::hex;
+use aivim_core::line_ending::LineEnding;
+use aivim_core::lsp::{self, Diagnostic};
+use aivim_core::quickfix::{ListKind, QuickfixKind};
+use aivim_core::statusline::{self, Segment};
+use aivim_core::window::{WindowId, WindowRect};
+use aivim_core::editor::{EditorOptions, PendingConfirmation};
+use aivim_core::plugin::{AiPlugin, GitPlugin, LspPlugin};
+use aivim_core::{Buffer, Cursor, Editor, FillChars};
 use crate::app::OperatorState;
+use crate::theme::Theme;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use unicode_width::UnicodeWidthStr;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Style,
     text::{Line, Span, Text},
+    symbols::border,
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
-pub fn draw(frame: &mut Frame, editor: &Editor, scroll_offset: usize, operator_state: OperatorState) {
+pub fn draw(frame: &mut Frame, editor: &Editor, operator_state: OperatorState) {
     let size = frame.size();
-    
+    let theme = Theme::for_editor(editor);
+
+    // 标签栏 + 窗口区域 + Quickfix 面板 + 命令行
+    let windows_area = windows_area(editor, size);
+    let command_area = Rect { y: (size.y + size.height).saturating_sub(1), height: 1, ..size };
+    let quickfix_area = Rect {
+        y: windows_area.y + windows_area.height,
+        height: command_area.y.saturating_sub(windows_area.y + windows_area.height),
+        ..size
+    };
+
+    if editor.tab_count() > 1 {
+        draw_tab_line(frame, editor, &theme, Rect { height: 1, ..size });
+    }
+    for (id, area) in window_areas(editor, windows_area) {
+        draw_window(frame, editor, &theme, id, area, operator_state);
+    }
+    if editor.show_quickfix() {
+        draw_quickfix(frame, editor, &theme, quickfix_area);
+    }
+    draw_command_line(frame, editor, &theme, command_area);
+
+    // 面板浮在窗口之上，不遮住最下面的状态栏
+    let panel_area = Rect { height: windows_area.height.saturating_sub(1), ..windows_area };
     if editor.show_registers_panel() {
-        // 显示寄存器面板时，使用弹出窗口布局
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
-            .split(size);
-
-        draw_editor_area(frame, editor, chunks[0], scroll_offset);
-        draw_status_line(frame, editor, chunks[1], operator_state);
-        draw_command_line(frame, editor, chunks[2]);
-        
-        // 在编辑器区域上方绘制寄存器面板
-        draw_registers_panel(frame, editor, chunks[0]);
+        draw_registers_panel(frame, editor, &theme, panel_area);
     } else if editor.show_buffer_list() {
-        // 显示缓冲区列表面板
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
-            .split(size);
-
-        draw_editor_area(frame, editor, chunks[0], scroll_offset);
-        draw_status_line(frame, editor, chunks[1], operator_state);
-        draw_command_line(frame, editor, chunks[2]);
-        
-        // 在编辑器区域上方绘制缓冲区列表面板
-        draw_buffer_list_panel(frame, editor, chunks[0]);
+        draw_buffer_list_panel(frame, editor, &theme, panel_area);
+    } else if editor.show_jobs_panel() {
+        draw_jobs_panel(frame, editor, &theme, panel_area);
+    } else if editor.show_options_panel() {
+        draw_options_panel(frame, editor, &theme, panel_area);
+    }
+
+    // 模糊选择器浮在所有面板之上
+    if editor.picker().is_some() {
+        draw_picker(frame, editor, &theme, size);
+    }
+}
+
+/// Quickfix 面板的高度（包括最下面一行的状态栏）
+const QUICKFIX_HEIGHT: u16 = 10;
+
+/// 屏幕中显示窗口的区域：去掉最下面的命令行，有多个标签页时还要去掉最上面的标签栏，
+/// 打开 Quickfix 面板时还要去掉窗口下方的面板（至少给窗口留两行）
+fn windows_area(editor: &Editor, screen: Rect) -> Rect {
+    let tab_line = u16::from(editor.tab_count() > 1).min(screen.height);
+    let height = screen.height.saturating_sub(1 + tab_line);
+    let quickfix = if editor.show_quickfix() { QUICKFIX_HEIGHT.min(height.saturating_sub(2)) } else { 0 };
+    Rect {
+        y: screen.y + tab_line,
+        height: height - quickfix,
+        ..screen
+    }
+}
+
+/// 屏幕中显示窗口的区域，用于告知编辑器（见 Editor::set_window_area）
+pub fn windows_rect(editor: &Editor, screen: Rect) -> WindowRect {
+    window_rect(windows_area(editor, screen))
+}
+
+fn window_rect(area: Rect) -> WindowRect {
+    WindowRect {
+        x: area.x as usize,
+        y: area.y as usize,
+        width: area.width as usize,
+        height: area.height as usize,
+    }
+}
+
+/// 各窗口在 `area` 中的区域（包括窗口的状态栏），左右相邻的窗口之间留一列分隔线
+pub fn window_areas(editor: &Editor, area: Rect) -> Vec<(WindowId, Rect)> {
+    editor
+        .windows()
+        .layout_rects(window_rect(area))
+        .into_iter()
+        .map(|(id, r)| (id, Rect::new(r.x as u16, r.y as u16, r.width as u16, r.height as u16)))
+        .collect()
+}
+
+/// 屏幕大小为 `screen` 时当前窗口文本区域的（宽, 高）
+pub fn window_text_size(editor: &Editor, screen: Rect) -> (usize, usize) {
+    let windows_area = windows_area(editor, screen);
+    let current = editor.windows().current_id();
+    let area = window_areas(editor, windows_area)
+        .into_iter()
+        .find(|(id, _)| *id == current)
+        .map_or(windows_area, |(_, area)| area);
+    (text_width(editor, current, area.width), area.height.saturating_sub(1) as usize)
+}
+
+/// 绘制标签栏：每个标签页显示窗口数（多于一个时）、`+`（有修改时）和当前窗口的缓冲区名
+fn draw_tab_line(frame: &mut Frame, editor: &Editor, theme: &Theme, area: Rect) {
+    let current = editor.tab_index();
+    let spans: Vec<Span> = editor
+        .tab_labels()
+        .into_iter()
+        .enumerate()
+        .map(|(index, label)| {
+            let count = if label.windows > 1 { label.windows.to_string() } else { String::new() };
+            let modified = if label.modified { "+" } else { "" };
+            let flags = if count.is_empty() && modified.is_empty() {
+                String::new()
+            } else {
+                format!("{}{} ", count, modified)
+            };
+            let title = label.title.as_deref().unwrap_or(editor.tr("[No Name]"));
+            let style = if index == current { theme.tab_line_selected } else { theme.tab_line };
+            Span::styled(format!(" {}{} ", flags, title), style)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(Line::from(spans)).style(theme.tab_line_fill), area);
+}
+
+/// 绘制一个窗口：文本区域和最下面一行的状态栏，右侧不是屏幕边缘时绘制分隔线
+fn draw_window(frame: &mut Frame, editor: &Editor, theme: &Theme, id: WindowId, area: Rect, operator_state: OperatorState) {
+    if area.width == 0 || area.height == 0 {
+        return;
+    }
+    let text_area = Rect { height: area.height - 1, ..area };
+    let status_area = Rect { y: area.y + area.height - 1, height: 1, ..area };
+
+    draw_editor_area(frame, editor, theme, id, text_area);
+    if id == editor.windows().current_id() {
+        draw_status_line(frame, editor, theme, status_area, operator_state);
     } else {
-        // 正常布局
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
-            .split(size);
+        draw_inactive_status_line(frame, editor, theme, id, status_area);
+    }
 
-        draw_editor_area(frame, editor, chunks[0], scroll_offset);
-        draw_status_line(frame, editor, chunks[1], operator_state);
-        draw_command_line(frame, editor, chunks[2]);
+    let right = area.x + area.width;
+    if right < frame.size().width {
+        let separator = editor.options().fillchars.vert.to_string();
+        for y in area.y..area.y + area.height {
+            frame.buffer_mut().get_mut(right, y).set_symbol(&separator).set_style(theme.window_separator);
+        }
     }
 }
 
 fn draw_editor_area(
     frame: &mut Frame,
     editor: &Editor,
+    theme: &Theme,
+    id: WindowId,
     area: Rect,
-    scroll_offset: usize,
 ) {
-    let options = editor.options();
+    let Some((buffer, cursor)) = editor.window_view(id) else {
+        return;
+    };
+    let is_current_window = id == editor.windows().current_id();
+    let options = editor.window_options(id);
     let show_number = options.number;
     let show_relativenumber = options.relativenumber;
     let show_cursorline = options.cursorline;
 
-    // 计算行号区域宽度
-    let line_number_width = if show_number || show_relativenumber {
-        // 获取文件总行数，计算需要的宽度
-        let total_lines = editor.current_buffer().len_lines().max(1);
-        let digits = total_lines.to_string().len();
-        (digits.max(3) + 1) as u16  // 至少3位宽度，加1个空格
-    } else {
-        0
-    };
+    let line_number_width = line_number_width(&options, buffer);
+    let sign_width = sign_column_width(editor, buffer);
 
-    // 分割区域：行号区域 + 文本区域
+    // 分割区域：标记列 + 行号区域 + 文本区域
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
+            Constraint::Length(sign_width),
             Constraint::Length(line_number_width),
             Constraint::Min(1),
         ])
         .split(area);
 
-    let line_number_area = chunks[0];
-    let text_area = chunks[1];
+    let sign_area = chunks[0];
+    let line_number_area = chunks[1];
+    let text_area = chunks[2];
 
     let visible_height = text_area.height as usize;
-    let visible_lines = editor.visible_lines(visible_height, scroll_offset);
-    let cursor = editor.cursor();
+    let visible_lines = editor.window_visible_lines(id, visible_height, text_area.width as usize);
+    let diagnostics = diagnostics(editor, buffer);
+    let hunks = hunks(editor, buffer);
+    // 光标所在的行被折叠时，折叠的摘要行是当前行
+    let has_cursor = |row: &ScreenRow| {
+        row.line == cursor.line || row.fold_end.is_some_and(|end| (row.line..=end).contains(&cursor.line))
+    };
+
+    // 绘制标记：每行显示最严重的诊断，没有诊断时显示与 HEAD 相比的修改
+    if sign_width > 0 {
+        let sign_lines: Vec<Line> = visible_lines
+            .iter()
+            .map(|row| {
+                if !row.first {
+                    return Line::from("");
+                }
+                if let Some(severity) = lsp::line_severity(diagnostics, row.line) {
+                    return Line::from(Span::styled(format!("{} ", severity.sign()), theme.diagnostic(severity)));
+                }
+                match git_signs::line_sign(hunks, row.line) {
+                    Some(sign) => Line::from(Span::styled(format!("{} ", sign.symbol()), theme.git_sign(sign))),
+                    None => Line::from(""),
+                }
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(Text::from(sign_lines)).style(theme.gutter), sign_area);
+    }
 
     // 绘制行号
     if line_number_width > 0 {
         let mut line_number_lines: Vec<Line> = Vec::new();
         
-        for (line_idx, _) in &visible_lines {
-            let is_current_line = *line_idx == cursor.line;
+        for row in &visible_lines {
+            // 折行的后续屏幕行不显示行号
+            if !row.first {
+                line_number_lines.push(Line::from(""));
+                continue;
+            }
+            let line_idx = row.line;
+            let is_current_line = has_cursor(row);
             
             // 计算要显示的行号
             let display_number = if show_relativenumber {
@@ -118,11 +251,7 @@ fn draw_editor_area(
                     }
                 } else {
                     // 相对行号：显示与当前行的距离
-                    if *line_idx > cursor.line {
-                        line_idx - cursor.line
-                    } else {
-                        cursor.line - line_idx
-                    }
+                    line_idx.abs_diff(cursor.line)
                 }
             } else {
                 // 绝对行号
@@ -131,15 +260,11 @@ fn draw_editor_area(
 
             // 行号样式
             let style = if is_current_line && show_cursorline {
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
+                theme.cursor_line_number
             } else if is_current_line {
-                Style::default()
-                    .fg(Color::Yellow)
+                theme.current_line_number
             } else {
-                Style::default()
-                    .fg(Color::DarkGray)
+                theme.line_number
             };
 
             let line_num_str = format!("{:>width$}", display_number, width = line_number_width as usize - 1);
@@ -156,120 +281,632 @@ fn draw_editor_area(
 
         let line_number_widget = Paragraph::new(Text::from(line_number_lines))
             .block(Block::default())
-            .style(Style::default().bg(Color::Black));
+            .style(theme.gutter);
 
         frame.render_widget(line_number_widget, line_number_area);
     }
 
     // 绘制文本内容
+    let is_diff = buffer.filetype() == Some("diff");
+    // AI 补全建议：第一行显示在光标之后，其余各行作为虚拟行插入到光标所在行之后
+    let suggestion = editor
+        .plugin::<AiPlugin>()
+        .and_then(|ai| ai.suggestion(editor))
+        .filter(|_| is_current_window).map(|text| {
+        let tab = " ".repeat(options.tabstop);
+        text.replace('\t', &tab)
+    });
+    let suggestion_row = visible_lines.iter().rposition(|row| row.line == cursor.line);
+    let mut underlines = diagnostic_underlines(theme, buffer, options.tabstop, diagnostics, &visible_lines);
+    if is_current_window {
+        underlines.extend(substitute_highlights(editor, theme, buffer, options.tabstop, &visible_lines));
+    }
+    // blame 信息显示在每行最后一个屏幕行的文本之后
+    let blame = editor.plugin::<GitPlugin>().and_then(|git| git.blame(buffer));
+    let line_ends: Vec<bool> = (0..visible_lines.len())
+        .map(|idx| visible_lines.get(idx + 1).is_none_or(|next| next.line != visible_lines[idx].line))
+        .collect();
     let mut text_lines: Vec<Line> = visible_lines
         .into_iter()
-        .map(|(line_idx, content)| {
-            let is_current_line = line_idx == cursor.line;
-            let style = if is_current_line && show_cursorline {
-                Style::default().bg(Color::Rgb(40, 40, 40))  // 柔和的高亮背景
+        .zip(line_ends)
+        .map(|(row, line_end)| {
+            let is_current_line = has_cursor(&row);
+            let mut style = if is_current_line && show_cursorline {
+                theme.cursor_line
             } else {
                 Style::default()
             };
-            Line::from(Span::styled(content, style))
+            if row.fold_end.is_some() {
+                return Line::from(Span::styled(row.text, style.patch(theme.folded)));
+            }
+            if is_diff {
+                // 折行的后续屏幕行使用整行的样式
+                let content = buffer.line_text(row.line).unwrap_or_default();
+                style = style.patch(diff_line_style(theme, &content));
+            }
+            let mut spans = vec![Span::styled(row.text, style)];
+            if let Some(info) = blame.filter(|_| line_end).and_then(|blame| blame.line(row.line)) {
+                let annotation = if info.is_committed() {
+                    info.annotation()
+                } else {
+                    editor.tr("Not committed yet").to_string()
+                };
+                spans.push(Span::styled(format!("    {}", annotation), style.patch(theme.git_blame)));
+            }
+            Line::from(spans)
         })
         .collect();
 
+    if let (Some(text), Some(row)) = (&suggestion, suggestion_row) {
+        let virtual_lines = text.split('\n').skip(1).map(|line| Line::from(Span::styled(line.to_string(), theme.suggestion)));
+        let before = text_lines.len();
+        text_lines.splice(row + 1..row + 1, virtual_lines);
+        // 虚拟行之后的屏幕行下移
+        let inserted = text_lines.len() - before;
+        for underline in underlines.iter_mut().filter(|underline| underline.0 > row) {
+            underline.0 += inserted;
+        }
+        text_lines.truncate(visible_height);
+    }
+
     if text_lines.is_empty() {
         text_lines.push(Line::from(""));
     }
+    let text_rows = text_lines.len();
 
-    let editor_widget = Paragraph::new(Text::from(text_lines))
-        .block(Block::default())
-        .wrap(Wrap { trim: false });
+    // 缓冲区末尾之后的行显示 eob 字符（默认为 ~）
+    let eob = options.fillchars.eob.to_string();
+    while text_lines.len() < visible_height {
+        text_lines.push(Line::from(Span::styled(eob.clone(), theme.end_of_buffer)));
+    }
+
+    // 文本已经按屏幕行折好，不再由 Paragraph 折行
+    let editor_widget = Paragraph::new(Text::from(text_lines)).style(theme.normal).block(Block::default());
 
     frame.render_widget(editor_widget, text_area);
 
-    // 设置光标位置（考虑行号区域偏移）
-    let cursor_x = text_area.x + cursor.column as u16;
-    let cursor_y = text_area.y + (cursor.line - scroll_offset) as u16;
-    
-    if cursor_y < text_area.y + text_area.height && cursor_y >= text_area.y {
-        frame.set_cursor(cursor_x, cursor_y);
+    for (row, start, end, style) in underlines {
+        if row >= text_area.height as usize {
+            continue;
+        }
+        for x in start..end.min(text_area.width as usize) {
+            frame.buffer_mut().get_mut(text_area.x + x as u16, text_area.y + row as u16).set_style(style);
+        }
+    }
+
+    // 设置光标位置（考虑行号区域偏移、折行以及 Tab、宽字符的显示宽度）
+    if let Some((x, y)) = editor.window_cursor_position(id, text_area.width as usize) {
+        if is_current_window && y < text_area.height as usize {
+            frame.set_cursor(text_area.x + x as u16, text_area.y + y as u16);
+            if let Some(first) = suggestion.as_deref().and_then(|text| text.split('\n').next()) {
+                draw_inline_suggestion(frame, theme, text_area, text_area.x + x as u16, text_area.y + y as u16, first);
+            }
+        }
+        // Visual 模式的选择区域，多光标时其他光标所在的字符
+        if is_current_window {
+            for (x, y) in editor.visual_cells(text_area.width as usize, text_area.height as usize) {
+                frame.buffer_mut().get_mut(text_area.x + x as u16, text_area.y + y as u16).set_style(theme.visual);
+            }
+            for (x, y) in editor.extra_cursor_positions(text_area.width as usize, text_area.height as usize) {
+                frame.buffer_mut().get_mut(text_area.x + x as u16, text_area.y + y as u16).set_style(theme.visual);
+            }
+        }
+        // cursorcolumn 高亮有文本的屏幕行中光标所在的列
+        if options.cursorcolumn {
+            let x = text_area.x + x as u16;
+            for row in 0..text_rows.min(text_area.height as usize) {
+                frame.buffer_mut().get_mut(x, text_area.y + row as u16).set_style(theme.cursor_column);
+            }
+        }
+    }
+
+    // 补全菜单绘制在文本之上
+    if is_current_window && editor.completion().is_some() {
+        draw_completion_menu(frame, editor, theme, text_area, editor.scroll_offset());
+    }
+    if is_current_window {
+        draw_explanation(frame, editor, theme, text_area);
+        draw_hunk_preview(frame, editor, theme, text_area);
     }
 }
 
-fn draw_status_line(frame: &mut Frame, editor: &Editor, area: Rect, operator_state: OperatorState) {
+/// AI 解释浮动窗口的最大宽度
+const EXPLANATION_WIDTH: u16 = 72;
 
-    let buffer = editor.current_buffer();
-    let mode = editor.mode();
+/// 光标下方（下方空间不足时在上方）显示 `rows` 行内容的浮动窗口区域，空间连一行都放不下时为 None
+fn float_area(editor: &Editor, text_area: Rect, width: u16, rows: usize) -> Option<Rect> {
+    let (cursor_x, cursor_y) = editor.cursor_screen_position(text_area.width as usize, editor.scroll_offset())?;
+    let anchor_y = text_area.y + cursor_y as u16;
+    let below = (text_area.y + text_area.height).saturating_sub(anchor_y + 1);
+    let above = anchor_y - text_area.y;
+    let space = below.max(above);
+    let height = (rows as u16 + 2).min(space);
+    if height < 3 {
+        return None;
+    }
+    let y = if height <= below { anchor_y + 1 } else { anchor_y - height };
+    let x = (text_area.x + cursor_x as u16).min((text_area.x + text_area.width).saturating_sub(width));
+    Some(Rect::new(x, y, width, height))
+}
 
-    let file_name = buffer
-        .file_path()
-        .and_then(|p| p.file_name())
-        .and_then(|n| n.to_str())
-        .unwrap_or("[No Name]");
+/// 在光标附近的浮动窗口中显示 AI 解释，超出窗口高度的部分不显示
+fn draw_explanation(frame: &mut Frame, editor: &Editor, theme: &Theme, text_area: Rect) {
+    let Some(explanation) = editor.plugin::<AiPlugin>().and_then(|ai| ai.explanation(editor)) else {
+        return;
+    };
+    let width = EXPLANATION_WIDTH.min(text_area.width);
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    let text = explanation.text().trim_end();
+    // 按字符数估计折行后的行数，宽字符可能使实际行数更多
+    let rows: usize = text
+        .split('\n')
+        .map(|line| line.chars().count().div_ceil(inner_width).max(1))
+        .sum();
+    let Some(area) = float_area(editor, text_area, width, rows) else {
+        return;
+    };
 
-    let modified_indicator = if buffer.is_modified() { " [+]" } else { "" };
+    let title = if explanation.is_responding() {
+        format!(" {} … ", explanation.title())
+    } else {
+        format!(" {} ", explanation.title())
+    };
+    frame.render_widget(Clear, area);
+    let widget = Paragraph::new(text)
+        .style(theme.menu)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(editor))
+                .border_style(theme.float_border)
+                .title(title),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(widget, area);
+}
+
+/// 在光标附近的浮动窗口中显示修改的预览 (:GitPreviewHunk)，删除的行和新增的行用差异标记的颜色
+fn draw_hunk_preview(frame: &mut Frame, editor: &Editor, theme: &Theme, text_area: Rect) {
+    let Some(preview) = editor.plugin::<GitPlugin>().and_then(|git| git.hunk_preview(editor)) else {
+        return;
+    };
+    let lines = preview.hunk.preview_lines();
+    let longest = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+    let width = (longest as u16 + 2).clamp(12, EXPLANATION_WIDTH).min(text_area.width);
+    let Some(area) = float_area(editor, text_area, width, lines.len()) else {
+        return;
+    };
+    let text: Vec<Line> = lines
+        .into_iter()
+        .map(|line| {
+            let sign = if line.starts_with('-') { Sign::Removed } else { Sign::Added };
+            Line::from(Span::styled(line, theme.git_sign(sign)))
+        })
+        .collect();
+    frame.render_widget(Clear, area);
+    let widget = Paragraph::new(Text::from(text)).style(theme.menu).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(border_set(editor))
+            .border_style(theme.float_border)
+            .title(format!(" {} ", editor.tr("Hunk"))),
+    );
+    frame.render_widget(widget, area);
+}
+
+/// 在光标处插入显示 AI 补全建议的第一行，光标之后的文本右移，超出文本区域的部分不显示
+fn draw_inline_suggestion(frame: &mut Frame, theme: &Theme, text_area: Rect, x: u16, y: u16, text: &str) {
+    let right = text_area.x + text_area.width;
+    if text.is_empty() || x >= right {
+        return;
+    }
+    let buffer = frame.buffer_mut();
+    let rest: Vec<_> = (x..right).map(|cx| buffer.get(cx, y).clone()).collect();
+    let (end, _) = buffer.set_stringn(x, y, text, (right - x) as usize, theme.suggestion);
+    for (cx, cell) in (end..right).zip(rest) {
+        *buffer.get_mut(cx, y) = cell;
+    }
+}
+
+/// 面板边框字符，竖线和横线跟随 fillchars 的 vert / horiz
+///
+/// 使用默认的 Unicode 字符时绘制标准框线，否则拐角统一用 `+`
+fn border_set(editor: &Editor) -> border::Set {
+    let fillchars = editor.options().fillchars;
+    if fillchars.vert == FillChars::UNICODE.vert && fillchars.horiz == FillChars::UNICODE.horiz {
+        return border::PLAIN;
+    }
+    let vert = glyph(fillchars.vert);
+    let horiz = glyph(fillchars.horiz);
+    border::Set {
+        top_left: "+",
+        top_right: "+",
+        bottom_left: "+",
+        bottom_right: "+",
+        vertical_left: vert,
+        vertical_right: vert,
+        horizontal_top: horiz,
+        horizontal_bottom: horiz,
+    }
+}
+
+/// 把字符转换为 `&'static str`（ratatui 的边框字符集要求），每个字符只分配一次
+fn glyph(c: char) -> &'static str {
+    static GLYPHS: OnceLock<Mutex<HashMap<char, &'static str>>> = OnceLock::new();
+    let mut glyphs = GLYPHS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    glyphs
+        .entry(c)
+        .or_insert_with(|| Box::leak(c.to_string().into_boxed_str()))
+}
+
+/// 绘制补全菜单和预览面板
+///
+/// 菜单显示在补全前缀下方（下方空间不足时显示在上方），
+/// 右侧的预览面板显示选中候选项的文档或来源行
+fn draw_completion_menu(frame: &mut Frame, editor: &Editor, theme: &Theme, text_area: Rect, scroll_offset: usize) {
+    let Some(menu) = editor.completion() else {
+        return;
+    };
+    if menu.is_empty() || menu.line() < scroll_offset {
+        return;
+    }
+    // 补全前缀在光标之前，从光标的屏幕位置向左退回前缀的宽度
+    let Some((cursor_x, cursor_y)) = editor.cursor_screen_position(text_area.width as usize, scroll_offset) else {
+        return;
+    };
+    let prefix_width = editor
+        .display_column(menu.line(), editor.cursor().column)
+        .saturating_sub(editor.display_column(menu.line(), menu.start_column()));
 
-    let cursor = editor.cursor();
-    let position = format!("{}:{} ", cursor.line + 1, cursor.column + 1);
+    let visible: Vec<_> = menu.visible_items().collect();
+    // LSP 候选项在标签之前显示两列宽的类型图标
+    let has_kind = visible.iter().any(|(_, item)| item.kind.is_some());
+    let kind_width = if has_kind { 3 } else { 0 };
+    let label_width = visible
+        .iter()
+        .map(|(_, item)| {
+            let detail_len = item.detail.as_ref().map(|d| d.chars().count() + 1).unwrap_or(0);
+            kind_width + item.label.chars().count() + detail_len
+        })
+        .max()
+        .unwrap_or(0);
 
-    // 获取当前选择的寄存器信息
-    let register_info = get_register_info(operator_state);
+    let menu_width = ((label_width + 4) as u16).min(text_area.width);
+    let menu_height = ((visible.len() + 2) as u16).min(text_area.height);
 
-    // 如果有操作符等待状态，显示在模式后面
-    let mode_name = if operator_state != OperatorState::None {
-        format!("{}-OPERATOR", mode.name())
+    // 计算菜单位置：前缀起点的下一行
+    let anchor_y = text_area.y + cursor_y as u16;
+    let below = anchor_y + 1;
+    let menu_y = if below + menu_height <= text_area.y + text_area.height {
+        below
     } else {
-        mode.name().to_string()
+        anchor_y.saturating_sub(menu_height).max(text_area.y)
     };
+    let menu_x = (text_area.x + cursor_x.saturating_sub(prefix_width) as u16)
+        .min((text_area.x + text_area.width).saturating_sub(menu_width));
 
-    let mode_style = Style::default()
-        .fg(Color::Black)
-        .bg(mode_color(mode))
-        .add_modifier(Modifier::BOLD);
+    let menu_area = Rect::new(menu_x, menu_y, menu_width, menu_height);
 
-    let mode_span = Span::styled(format!(" {} ", mode_name), mode_style);
+    let lines: Vec<Line> = visible
+        .iter()
+        .map(|(idx, item)| {
+            let style = if *idx == menu.selected_index() {
+                theme.menu_selected
+            } else {
+                theme.menu
+            };
+            let mut spans = Vec::new();
+            if has_kind {
+                spans.push(Span::styled(
+                    format!(" {}", item.kind.unwrap_or("  ")),
+                    style.patch(theme.menu_extra),
+                ));
+            }
+            spans.push(Span::styled(format!(" {}", item.label), style));
+            if let Some(detail) = &item.detail {
+                spans.push(Span::styled(format!(" {}", detail), style.patch(theme.menu_extra)));
+            }
+            Line::from(spans)
+        })
+        .collect();
 
-    let file_info = format!("{}{}", file_name, modified_indicator);
-    
-    // 如果有寄存器信息，调整布局
-    let status_chunks = if register_info.is_empty() {
-        Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Length(10), Constraint::Min(1), Constraint::Length(15)])
-            .split(area)
+    // 标题显示当前位置，便于在长列表中滚动
+    let title = format!(" {}/{} ", menu.selected_index() + 1, menu.len());
+    frame.render_widget(Clear, menu_area);
+    let menu_widget = Paragraph::new(Text::from(lines))
+        .style(theme.menu)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(editor))
+                .border_style(theme.float_border)
+                .title(title),
+        );
+    frame.render_widget(menu_widget, menu_area);
+
+    // 预览面板：放在菜单右侧，空间不足时省略
+    let Some(preview) = editor.completion_preview() else {
+        return;
+    };
+    let preview_x = menu_area.x + menu_area.width;
+    let available = (text_area.x + text_area.width).saturating_sub(preview_x);
+    if available < 12 {
+        return;
+    }
+    let preview_width = available.min(50);
+    let preview_area = Rect::new(preview_x, menu_area.y, preview_width, menu_area.height.max(3));
+
+    frame.render_widget(Clear, preview_area);
+    let preview_widget = Paragraph::new(preview)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(editor))
+                .border_style(theme.float_border_dim)
+                .title(format!(" {} ", editor.tr("Preview"))),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(preview_widget, preview_area);
+}
+
+/// diff 缓冲区中一行的样式：文件头、新增、删除和块头
+fn diff_line_style(theme: &Theme, content: &str) -> Style {
+    if content.starts_with("+++") || content.starts_with("---") {
+        theme.diff_header
+    } else if content.starts_with('+') {
+        theme.diff_add
+    } else if content.starts_with('-') {
+        theme.diff_delete
+    } else if content.starts_with("@@") {
+        theme.diff_hunk
     } else {
-        Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Length(10), Constraint::Length(5), Constraint::Min(1), Constraint::Length(15)])
-            .split(area)
+        Style::default()
+    }
+}
+
+fn draw_status_line(frame: &mut Frame, editor: &Editor, theme: &Theme, area: Rect, operator_state: OperatorState) {
+    let settings = editor.statusline();
+    let left: Vec<_> = settings.left.iter().map(|&segment| (segment, status_segment(editor, theme, segment, operator_state))).collect();
+    let right: Vec<_> = settings.right.iter().map(|&segment| (segment, status_segment(editor, theme, segment, operator_state))).collect();
+
+    // 每个片段的宽度包括和下一个片段之间的空格
+    let widths: Vec<(Segment, usize)> = left
+        .iter()
+        .chain(&right)
+        .map(|(segment, spans)| {
+            let width: usize = spans.iter().map(|span| span.content.width()).sum();
+            (*segment, if width == 0 { 0 } else { width + 1 })
+        })
+        .collect();
+    let visible = statusline::fit(&widths, area.width as usize);
+    let used: usize = widths.iter().zip(&visible).filter(|(_, shown)| **shown).map(|((_, width), _)| width).sum();
+
+    let line = |segments: Vec<(Segment, Vec<Span<'static>>)>, shown: &[bool]| {
+        let mut spans: Vec<Span> = Vec::new();
+        for ((segment, mut segment_spans), _) in segments.into_iter().zip(shown).filter(|(_, shown)| **shown) {
+            if !spans.is_empty() {
+                spans.push(Span::raw(" "));
+            }
+            // 只剩文件名也放不下时截短文件名的开头
+            if segment == Segment::FileName && used > area.width as usize {
+                let overflow = used - area.width as usize;
+                if let Some(span) = segment_spans.first_mut() {
+                    span.content = truncate_start(&span.content, span.content.width().saturating_sub(overflow)).into();
+                }
+            }
+            spans.extend(segment_spans);
+        }
+        Line::from(spans)
     };
+    let left_line = line(left, &visible[..settings.left.len()]);
+    let right_line = line(right, &visible[settings.left.len()..]);
+
+    frame.render_widget(Paragraph::new(left_line).style(theme.status_line).alignment(Alignment::Left), area);
+    frame.render_widget(Paragraph::new(right_line).alignment(Alignment::Right), area);
+}
 
-    let mode_widget = Paragraph::new(Line::from(mode_span));
-    frame.render_widget(mode_widget, status_chunks[0]);
-
-    // 如果有寄存器信息，显示在第二列
-    if !register_info.is_empty() {
-        let reg_style = Style::default()
-            .fg(Color::Yellow)
-            .add_modifier(Modifier::BOLD);
-        let reg_span = Span::styled(register_info, reg_style);
-        let reg_widget = Paragraph::new(Line::from(reg_span));
-        frame.render_widget(reg_widget, status_chunks[1]);
-
-        let file_widget = Paragraph::new(file_info)
-            .alignment(Alignment::Left);
-        frame.render_widget(file_widget, status_chunks[2]);
-
-        let pos_widget = Paragraph::new(position)
-            .alignment(Alignment::Right);
-        frame.render_widget(pos_widget, status_chunks[3]);
+/// 状态栏中一个片段的内容，没有内容时为空
+fn status_segment(editor: &Editor, theme: &Theme, segment: Segment, operator_state: OperatorState) -> Vec<Span<'static>> {
+    let buffer = editor.current_buffer();
+    match segment {
+        // 如果有操作符等待状态，显示在模式后面
+        Segment::Mode => {
+            let mode = editor.mode();
+            let mode_name = if operator_state != OperatorState::None {
+                format!("{}-OPERATOR", mode.name())
+            } else {
+                mode.name().to_string()
+            };
+            vec![Span::styled(format!(" {} ", mode_name), theme.mode_style(mode))]
+        }
+        Segment::Register => {
+            let register_info = get_register_info(operator_state);
+            if register_info.is_empty() {
+                Vec::new()
+            } else {
+                vec![Span::styled(register_info, theme.status_register)]
+            }
+        }
+        Segment::FileName => {
+            vec![Span::raw(buffer.display_name().unwrap_or(editor.tr("[No Name]")).to_string())]
+        }
+        Segment::Flags => {
+            let encoding = match buffer.encoding() {
+                Encoding::Utf8 => None,
+                encoding => Some(format!("[{}]", encoding.name())),
+            };
+            let flags: Vec<String> = [
+                buffer.is_modified().then(|| "[+]".to_string()),
+                // 跟随模式的缓冲区也是只读的，只显示 [tail]
+                (buffer.is_read_only() && !editor.is_tailing()).then(|| "[RO]".to_string()),
+                buffer.is_binary().then(|| "[hex]".to_string()),
+                editor.is_tailing().then(|| "[tail]".to_string()),
+                editor.options().paste.then(|| "[paste]".to_string()),
+                editor.has_bom().then(|| "[BOM]".to_string()),
+                encoding,
+                (buffer.line_ending() == LineEnding::Crlf).then(|| "[dos]".to_string()),
+                editor.line_ending_stats().is_mixed().then(|| "[mixed EOL]".to_string()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if flags.is_empty() {
+                Vec::new()
+            } else {
+                vec![Span::raw(flags.join(" "))]
+            }
+        }
+        Segment::Diagnostics => {
+            let mut spans = diagnostic_count_spans(editor, theme, buffer);
+            // 片段之间已经有空格
+            if let Some(first) = spans.first_mut() {
+                first.content = first.content.trim_start().to_string().into();
+            }
+            spans
+        }
+        Segment::Loading => match editor.file_load() {
+            Some(load) => {
+                let spinner = aivim_core::loader::spinner(load.frame(), editor.options().asciiglyphs);
+                let name = load.path().file_name().unwrap_or(load.path().as_os_str()).to_string_lossy();
+                let text = aivim_core::tr!(editor.locale(), "Loading {} {}%", name, load.percent());
+                vec![Span::styled(format!("{} {}", spinner, text), theme.status_line)]
+            }
+            None => Vec::new(),
+        },
+        Segment::Recording => match editor.recording_register() {
+            Some(register) => vec![Span::styled(format!("recording @{}", register), theme.status_register)],
+            None => Vec::new(),
+        },
+        Segment::SearchCount => match editor.search_count() {
+            Some((current, total)) => {
+                vec![Span::styled(aivim_core::tr!(editor.locale(), "match {} of {}", current, total), theme.status_line)]
+            }
+            None => Vec::new(),
+        },
+        Segment::Branch => match editor.plugin::<GitPlugin>().and_then(|git| git.branch(buffer)) {
+            Some(branch) => vec![Span::styled(branch.to_string(), theme.status_line)],
+            None => Vec::new(),
+        },
+        Segment::FileType => match buffer.filetype() {
+            Some(filetype) => vec![Span::styled(filetype.to_string(), theme.status_line)],
+            None => Vec::new(),
+        },
+        Segment::Position => {
+            let position = cursor_position(buffer, *editor.cursor(), editor.current_options().tabstop);
+            vec![Span::styled(position.trim_end().to_string(), theme.status_line)]
+        }
+        Segment::Percent => {
+            let lines = buffer.len_lines().max(1);
+            let percent = (editor.cursor().line + 1) * 100 / lines;
+            vec![Span::styled(format!("{}% ", percent.min(100)), theme.status_line)]
+        }
+    }
+}
+
+/// 截短文本的开头使它不超过 `width` 列，开头用 `<` 表示被截掉的部分
+fn truncate_start(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut kept: Vec<char> = Vec::new();
+    let mut kept_width = 1;
+    for ch in text.chars().rev() {
+        let ch_width = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
+        if kept_width + ch_width > width {
+            break;
+        }
+        kept_width += ch_width;
+        kept.push(ch);
+    }
+    if width == 0 {
+        return String::new();
+    }
+    std::iter::once('<').chain(kept.into_iter().rev()).collect()
+}
+
+/// 状态栏中的光标位置 `行:列`，列与屏幕上的列不同时（Tab、宽字符）显示为 `行:列-屏幕列`
+fn cursor_position(buffer: &Buffer, cursor: Cursor, tabstop: usize) -> String {
+    let text = buffer.line_text(cursor.line).unwrap_or_default();
+    // 十六进制视图中显示光标所在字节的偏移
+    if let Some(offset) = buffer.is_binary().then(|| hex::byte_offset(&text, cursor.column)).flatten() {
+        return format!("{}:{} 0x{:x} ", cursor.line + 1, cursor.column + 1, offset);
+    }
+    let screen_column = display::display_column(&text, cursor.column, tabstop);
+    if screen_column == cursor.column {
+        format!("{}:{} ", cursor.line + 1, cursor.column + 1)
     } else {
-        let file_widget = Paragraph::new(file_info)
-            .alignment(Alignment::Left);
-        frame.render_widget(file_widget, status_chunks[1]);
+        format!("{}:{}-{} ", cursor.line + 1, cursor.column + 1, screen_column + 1)
+    }
+}
+
+/// 非当前窗口的状态栏：文件名和光标位置
+fn draw_inactive_status_line(frame: &mut Frame, editor: &Editor, theme: &Theme, id: WindowId, area: Rect) {
+    let Some((buffer, cursor)) = editor.window_view(id) else {
+        return;
+    };
+    let file_name = buffer.display_name().unwrap_or(editor.tr("[No Name]"));
+    let modified_indicator = if buffer.is_modified() { " [+]" } else { "" };
+    let position = cursor_position(buffer, cursor, editor.window_options(id).tabstop);
+
+    frame.render_widget(Paragraph::new(format!(" {}{}", file_name, modified_indicator)).style(theme.status_line_inactive), area);
+    frame.render_widget(Paragraph::new(position).alignment(Alignment::Right).style(theme.status_line_inactive), area);
+}
 
-        let pos_widget = Paragraph::new(position)
-            .alignment(Alignment::Right);
-        frame.render_widget(pos_widget, status_chunks[2]);
+/// 绘制列表面板（Quickfix 或位置列表）：每项一行 `文件:行:列 类型: 说明`，最下面一行是状态栏
+fn draw_quickfix(frame: &mut Frame, editor: &Editor, theme: &Theme, area: Rect) {
+    let Some((kind, quickfix)) = editor.quickfix_panel() else {
+        return;
+    };
+    if area.height == 0 {
+        return;
     }
+    let focused = editor.quickfix_focused();
+    let list_height = area.height as usize - 1;
+    // 保持当前项可见
+    let current = quickfix.current_index();
+    let offset = (current + 1).saturating_sub(list_height);
+
+    let lines: Vec<Line> = quickfix
+        .entries()
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+        .map(|(index, entry)| {
+            let location = format!("{}:{}:{}", entry.path.display(), entry.line + 1, entry.column + 1);
+            let mut spans = vec![Span::styled(location, theme.label), Span::raw(" ")];
+            if let Some(kind) = entry.kind {
+                let style = match kind {
+                    QuickfixKind::Error => theme.error_msg,
+                    QuickfixKind::Warning => theme.warning_msg,
+                    QuickfixKind::Info | QuickfixKind::Note => theme.hint,
+                };
+                spans.push(Span::styled(format!("{}:", kind.name()), style));
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::raw(entry.text.as_str()));
+            let mut line = Line::from(spans);
+            if index == current {
+                line.patch_style(if focused { theme.menu_selected } else { theme.cursor_line });
+            }
+            line
+        })
+        .collect();
+    let list_area = Rect { height: area.height - 1, ..area };
+    frame.render_widget(Clear, list_area);
+    frame.render_widget(Paragraph::new(Text::from(lines)).style(theme.normal), list_area);
+
+    let status_area = Rect { y: area.y + area.height - 1, height: 1, ..area };
+    let style = if focused { theme.status_line } else { theme.status_line_inactive };
+    let searching = if kind == ListKind::Quickfix && editor.is_grepping() { " …" } else { "" };
+    let name = match kind {
+        ListKind::Quickfix => editor.tr("Quickfix"),
+        ListKind::Location => editor.tr("Location List"),
+    };
+    let title = format!(" [{}] {}{}", name, quickfix.title(), searching);
+    let position = if quickfix.is_empty() { String::new() } else { format!("{}/{} ", current + 1, quickfix.len()) };
+    frame.render_widget(Paragraph::new(title).style(style), status_area);
+    frame.render_widget(Paragraph::new(position).alignment(Alignment::Right).style(style), status_area);
 }
 
 /// 根据操作符状态获取寄存器信息显示
@@ -294,18 +931,24 @@ fn get_register_info(operator_state: OperatorState) -> String {
     }
 }
 
-fn draw_command_line(frame: &mut Frame, editor: &Editor, area: Rect) {
+fn draw_command_line(frame: &mut Frame, editor: &Editor, theme: &Theme, area: Rect) {
     use aivim_core::Mode;
 
+    if let Some(confirmation) = editor.pending_confirmation() {
+        frame.render_widget(Paragraph::new(confirmation_line(editor, theme, confirmation)), area);
+        return;
+    }
+
     let (text, style) = match editor.mode() {
+        _ if editor.expression_prompt_active() => (format!("={}", editor.command_line()), Style::default()),
         Mode::Command => (format!(":{}", editor.command_line()), Style::default()),
         Mode::SearchForward => (format!("/{}", editor.command_line()), Style::default()),
         Mode::SearchBackward => (format!("?{}", editor.command_line()), Style::default()),
         _ => {
             if let Some(msg) = editor.message() {
-                (msg.to_string(), Style::default().fg(Color::Yellow))
+                (msg.to_string(), theme.message)
             } else {
-                (String::new(), Style::default().fg(Color::Yellow))
+                (String::new(), theme.message)
             }
         }
     };
@@ -320,15 +963,165 @@ fn draw_command_line(frame: &mut Frame, editor: &Editor, area: Rect) {
     frame.render_widget(widget, area);
 }
 
-fn mode_color(mode: aivim_core::Mode) -> Color {
-    match mode {
-        aivim_core::Mode::Normal => Color::Blue,
-        aivim_core::Mode::Insert => Color::Green,
-        aivim_core::Mode::Visual => Color::Yellow,
-        aivim_core::Mode::Command => Color::Magenta,
-        aivim_core::Mode::Replace => Color::Red,
-        aivim_core::Mode::SearchForward | aivim_core::Mode::SearchBackward => Color::Cyan,
+/// 确认提示：问题之后列出可以选择的回答，如 `Save changes to a.txt? [y] Yes [n] No [a] All [q] Quit`
+fn confirmation_line(editor: &Editor, theme: &Theme, confirmation: &PendingConfirmation) -> Line<'static> {
+    let prompt = confirmation.prompt(editor.locale());
+    // 提示末尾的 `(y/n)` 换成回答的说明
+    let question = prompt.rfind('(').map_or(prompt.as_str(), |index| prompt[..index].trim_end());
+    let mut spans = vec![Span::styled(question.to_string(), theme.message)];
+    for answer in confirmation.choices() {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(format!("[{}]", answer.key()), theme.label));
+        spans.push(Span::styled(format!(" {}", answer.label(editor.locale())), theme.message));
+    }
+    Line::from(spans)
+}
+
+/// 按窗口中生效的选项 `options` 显示 `buffer` 时行号区域的宽度，不显示行号时为 0
+fn line_number_width(options: &EditorOptions, buffer: &Buffer) -> u16 {
+    if options.number || options.relativenumber {
+        // 获取文件总行数，计算需要的宽度
+        let total_lines = buffer.len_lines().max(1);
+        let digits = total_lines.to_string().len();
+        (digits.max(3) + 1) as u16  // 至少3位宽度，加1个空格
+    } else {
+        0
+    }
+}
+
+/// 状态栏中各严重程度的诊断数（如 ` E:2 W:1`），没有诊断时为空
+fn diagnostic_count_spans(editor: &Editor, theme: &Theme, buffer: &Buffer) -> Vec<Span<'static>> {
+    let counts = editor.plugin::<LspPlugin>().map_or([0; 4], |lsp| lsp.manager().diagnostic_counts(buffer));
+    lsp::Severity::ALL
+        .into_iter()
+        .zip(counts)
+        .filter(|&(_, count)| count > 0)
+        .map(|(severity, count)| Span::styled(format!(" {}:{}", severity.sign(), count), theme.diagnostic(severity)))
+        .collect()
+}
+
+/// 语言服务器插件为缓冲区发布的诊断，没有注册该插件时为空
+fn diagnostics<'a>(editor: &'a Editor, buffer: &Buffer) -> &'a [Diagnostic] {
+    editor.plugin::<LspPlugin>().map_or(&[], |lsp| lsp.manager().diagnostics(buffer))
+}
+
+/// Git 插件计算的缓冲区与 HEAD 的差异，没有注册该插件时为空
+fn hunks<'a>(editor: &'a Editor, buffer: &Buffer) -> &'a [git_signs::Hunk] {
+    editor.plugin::<GitPlugin>().map_or(&[], |git| git.hunks(editor, buffer))
+}
+
+/// 标记列的宽度：开启 lsp 且缓冲区已发送给语言服务器或有诊断时、或者缓冲区与 HEAD 有差异时为 2，否则为 0
+fn sign_column_width(editor: &Editor, buffer: &Buffer) -> u16 {
+    let attached = editor.plugin::<LspPlugin>().is_some_and(|lsp| lsp.manager().is_attached(buffer.id()));
+    if editor.options().lsp && (attached || !diagnostics(editor, buffer).is_empty()) || !hunks(editor, buffer).is_empty() {
+        2
+    } else {
+        0
+    }
+}
+
+/// 窗口 `id` 的宽度为 `window_width` 时文本区域的宽度（去掉诊断标记列和行号区域）
+pub fn text_width(editor: &Editor, id: WindowId, window_width: u16) -> usize {
+    let buffer = editor.window_view(id).map_or(editor.current_buffer(), |(buffer, _)| buffer);
+    let options = editor.window_options(id);
+    window_width
+        .saturating_sub(sign_column_width(editor, buffer) + line_number_width(&options, buffer))
+        .max(1) as usize
+}
+
+/// 诊断范围在文本区域中的下划线：(屏幕行, 起始列, 结束列, 样式)
+///
+/// 严重的诊断排在后面，重叠时覆盖较轻的诊断
+fn diagnostic_underlines(
+    theme: &Theme,
+    buffer: &Buffer,
+    tabstop: usize,
+    diagnostics: &[Diagnostic],
+    rows: &[ScreenRow],
+) -> Vec<(usize, usize, usize, Style)> {
+    let mut underlines = Vec::new();
+    let mut line_text = (usize::MAX, String::new());
+    for (index, row) in rows.iter().enumerate() {
+        if row.fold_end.is_some() {
+            continue;
+        }
+        if line_text.0 != row.line {
+            line_text = (row.line, buffer.line_text(row.line).unwrap_or_default());
+        }
+        let text = line_text.1.as_str();
+        let row_end = row.column + row.text.width();
+        for diagnostic in diagnostics {
+            let Some((start, end)) = lsp::diagnostic_columns(diagnostic, row.line, text) else {
+                continue;
+            };
+            let start = display::display_column(text, start, tabstop).max(row.column);
+            let end = display::display_column(text, end, tabstop).min(row_end);
+            if start < end {
+                let style = theme.diagnostic_underline(diagnostic.severity);
+                underlines.push((index, start - row.column, end - row.column, style, diagnostic.severity));
+            }
+        }
+    }
+    underlines.sort_by_key(|underline| std::cmp::Reverse(underline.4));
+    underlines.into_iter().map(|(row, start, end, style, _)| (row, start, end, style)).collect()
+}
+
+/// 逐个确认的替换（:s///c）中当前候选匹配的高亮：(屏幕行, 起始列, 结束列, 样式)
+fn substitute_highlights(
+    editor: &Editor,
+    theme: &Theme,
+    buffer: &Buffer,
+    tabstop: usize,
+    rows: &[ScreenRow],
+) -> Vec<(usize, usize, usize, Style)> {
+    let Some((_, line, start, end)) = editor.substitute_candidate().filter(|candidate| candidate.0 == buffer.id()) else {
+        return Vec::new();
+    };
+    let text = buffer.line_text(line).unwrap_or_default();
+    let (start, end) = (display::display_column(&text, start, tabstop), display::display_column(&text, end, tabstop));
+    rows.iter()
+        .enumerate()
+        .filter(|(_, row)| row.line == line && row.fold_end.is_none())
+        .filter_map(|(index, row)| {
+            let row_end = row.column + row.text.width();
+            let (start, end) = (start.max(row.column), end.min(row_end));
+            (start < end).then(|| (index, start - row.column, end - row.column, theme.search))
+        })
+        .collect()
+}
+
+/// 折行时继续向下滚动，直到光标所在的屏幕行也在视口内
+///
+/// `current_offset` 为按缓冲区行计算出的滚动位置（见 calculate_scroll_offset）
+pub fn calculate_wrapped_scroll_offset(editor: &Editor, viewport_height: usize, width: usize, current_offset: usize) -> usize {
+    let mut offset = current_offset;
+    while offset < editor.cursor().line {
+        match editor.cursor_screen_position(width, offset) {
+            Some((_, row)) if row >= viewport_height => offset += 1,
+            _ => break,
+        }
+    }
+    offset
+}
+
+/// 向下滚动之后，关闭的折叠使窗口底部留出空行时向上回退，直到光标所在的屏幕行位于窗口底部
+///
+/// `offset` 为按缓冲区行计算出的滚动位置，不会回退到滚动之前的位置 `current_offset` 之前
+pub fn calculate_folded_scroll_offset(
+    editor: &Editor,
+    viewport_height: usize,
+    width: usize,
+    offset: usize,
+    current_offset: usize,
+) -> usize {
+    let mut offset = offset;
+    while offset > current_offset {
+        match editor.cursor_screen_position(width, offset - 1) {
+            Some((_, row)) if row < viewport_height => offset -= 1,
+            _ => break,
+        }
     }
+    offset
 }
 
 pub fn calculate_scroll_offset(cursor_line: usize, viewport_height: usize, current_offset: usize) -> usize {
@@ -342,7 +1135,94 @@ pub fn calculate_scroll_offset(cursor_line: usize, viewport_height: usize, curre
 }
 
 /// 绘制寄存器内容面板
-fn draw_registers_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect) {
+fn draw_jobs_panel(frame: &mut Frame, editor: &Editor, theme: &Theme, editor_area: Rect) {
+    // 计算面板大小（占据编辑器区域的 80%）
+    let panel_width = (editor_area.width as f32 * 0.8) as u16;
+    let panel_height = (editor_area.height as f32 * 0.8) as u16;
+
+    let panel_x = editor_area.x + (editor_area.width - panel_width) / 2;
+    let panel_y = editor_area.y + (editor_area.height - panel_height) / 2;
+
+    let panel_area = Rect::new(panel_x, panel_y, panel_width, panel_height);
+
+    frame.render_widget(Clear, panel_area);
+
+    let jobs_text = editor.format_jobs();
+    let lines: Vec<Line> = jobs_text
+        .lines()
+        .map(|line| {
+            if line.starts_with('[') {
+                // 高亮任务 ID（如 [1]）
+                let split = line.find(']').map(|i| i + 1).unwrap_or(0);
+                Line::from(vec![
+                    Span::styled(&line[..split], theme.label),
+                    Span::styled(&line[split..], Style::default()),
+                ])
+            } else if line.starts_with(editor.tr("Jobs:")) || line.starts_with("---") {
+                Line::from(Span::styled(line, theme.title))
+            } else {
+                Line::from(Span::styled(line, theme.non_text))
+            }
+        })
+        .collect();
+
+    let panel = Paragraph::new(Text::from(lines))
+        .block(
+            Block::default()
+                .title(format!(" {} ", editor.tr("Jobs (press q or Esc to close)")))
+                .borders(Borders::ALL)
+                .border_set(border_set(editor))
+                .border_style(theme.float_border)
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(panel, panel_area);
+}
+
+/// 绘制选项面板 (:set all)：选项按列排列，先从上到下再从左到右
+fn draw_options_panel(frame: &mut Frame, editor: &Editor, theme: &Theme, editor_area: Rect) {
+    let panel_width = (editor_area.width as f32 * 0.8) as u16;
+    let panel_height = (editor_area.height as f32 * 0.8) as u16;
+
+    let panel_x = editor_area.x + (editor_area.width - panel_width) / 2;
+    let panel_y = editor_area.y + (editor_area.height - panel_height) / 2;
+
+    let panel_area = Rect::new(panel_x, panel_y, panel_width, panel_height);
+
+    frame.render_widget(Clear, panel_area);
+
+    let values = editor.option_values();
+    let column_width = values.iter().map(|value| value.width()).max().unwrap_or(0) + 2;
+    let columns = (usize::from(panel_width.saturating_sub(2)) / column_width).max(1);
+    let rows = values.len().div_ceil(columns);
+    let lines: Vec<Line> = (0..rows)
+        .map(|row| {
+            let spans = values
+                .iter()
+                .skip(row)
+                .step_by(rows)
+                .map(|value| {
+                    let style = if value.starts_with("no") { theme.non_text } else { Style::default() };
+                    let padding = " ".repeat(column_width - value.width());
+                    Span::styled(format!("{}{}", value, padding), style)
+                })
+                .collect::<Vec<_>>();
+            Line::from(spans)
+        })
+        .collect();
+
+    let panel = Paragraph::new(Text::from(lines)).block(
+        Block::default()
+            .title(format!(" {} ", editor.tr("Options (press q or Esc to close)")))
+            .borders(Borders::ALL)
+            .border_set(border_set(editor))
+            .border_style(theme.float_border),
+    );
+
+    frame.render_widget(panel, panel_area);
+}
+
+fn draw_registers_panel(frame: &mut Frame, editor: &Editor, theme: &Theme, editor_area: Rect) {
     // 计算面板大小（占据编辑器区域的 80%）
     let panel_width = (editor_area.width as f32 * 0.8) as u16;
     let panel_height = (editor_area.height as f32 * 0.8) as u16;
@@ -367,12 +1247,12 @@ fn draw_registers_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect) {
                 let reg_name = &line[..2]; // "a
                 let rest = &line[2..];
                 Line::from(vec![
-                    Span::styled(reg_name, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                    Span::styled(reg_name, theme.label),
                     Span::styled(rest, Style::default()),
                 ])
-            } else if line.starts_with("Registers:") || line.starts_with("---") {
+            } else if line.starts_with(editor.tr("Registers:")) || line.starts_with("---") {
                 // 标题和分隔线使用不同颜色
-                Line::from(Span::styled(line, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)))
+                Line::from(Span::styled(line, theme.title))
             } else {
                 Line::from(Span::styled(line, Style::default()))
             }
@@ -383,9 +1263,10 @@ fn draw_registers_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect) {
     let panel = Paragraph::new(Text::from(lines))
         .block(
             Block::default()
-                .title(" Registers (press q or Esc to close) ")
+                .title(format!(" {} ", editor.tr("Registers (press q or Esc to close)")))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Blue))
+                .border_set(border_set(editor))
+                .border_style(theme.float_border)
         )
         .wrap(Wrap { trim: false });
     
@@ -393,16 +1274,20 @@ fn draw_registers_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect) {
 }
 
 /// 绘制缓冲区列表面板
-fn draw_buffer_list_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect) {
+fn draw_buffer_list_panel(frame: &mut Frame, editor: &Editor, theme: &Theme, editor_area: Rect) {
     // 计算面板大小（占据编辑器区域的 60% 宽度，自适应高度）
-    let panel_width = (editor_area.width as f32 * 0.6).min(80.0).max(50.0) as u16;
+    // 终端比最小尺寸还小时不超出编辑器区域
+    let panel_width = ((editor_area.width as f32 * 0.6).clamp(50.0, 80.0) as u16).min(editor_area.width);
     
     // 获取缓冲区列表
     let buffers = editor.list_buffers();
     
     // 计算面板高度：标题(1) + 表头(1) + 分隔线(1) + 每个缓冲区(1) + 底部提示(2) + 边框(2)
     let content_height = buffers.len().max(1) as u16 + 7;
-    let panel_height = content_height.min(editor_area.height - 4).max(10);
+    let panel_height = content_height
+        .min(editor_area.height.saturating_sub(4))
+        .max(10)
+        .min(editor_area.height);
     
     let panel_x = editor_area.x + (editor_area.width - panel_width) / 2;
     let panel_y = editor_area.y + (editor_area.height - panel_height) / 2;
@@ -418,20 +1303,20 @@ fn draw_buffer_list_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect)
     // 标题
     lines.push(Line::from(vec![
         Span::styled("  ", Style::default()),
-        Span::styled("缓冲区列表", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::styled(editor.tr("Buffer List"), theme.title),
     ]));
     lines.push(Line::from(""));
     
     // 表头
-    let header = format!("  {:<4} {:<3} {:<3}  {}", "ID", "", "", "文件名");
-    lines.push(Line::from(Span::styled(header, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+    let header = format!("  {:<4} {:<3} {:<3}  {}", "ID", "", "", editor.tr("File name"));
+    lines.push(Line::from(Span::styled(header, theme.label)));
     
     // 分隔线
-    let separator = "─".repeat(panel_width as usize - 2);
-    lines.push(Line::from(Span::styled(separator, Style::default().fg(Color::DarkGray))));
+    let separator = editor.options().fillchars.horiz.to_string().repeat(panel_width as usize - 2);
+    lines.push(Line::from(Span::styled(separator, theme.non_text)));
     
     if buffers.is_empty() {
-        lines.push(Line::from(Span::styled("  没有缓冲区", Style::default().fg(Color::DarkGray))));
+        lines.push(Line::from(Span::styled(format!("  {}", editor.tr("No buffers")), theme.non_text)));
     } else {
         for (id, name, is_current) in buffers {
             // 状态标记
@@ -449,7 +1334,7 @@ fn draw_buffer_list_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect)
             
             // 当前缓冲区高亮显示
             let line_style = if is_current {
-                Style::default().bg(Color::DarkGray).fg(Color::White)
+                theme.menu
             } else {
                 Style::default()
             };
@@ -471,21 +1356,88 @@ fn draw_buffer_list_panel(frame: &mut Frame, editor: &Editor, editor_area: Rect)
     // 底部提示
     lines.push(Line::from(vec![
         Span::styled("  ", Style::default()),
-        Span::styled("提示: ", Style::default().fg(Color::Yellow)),
-        Span::styled("按 q 或 Esc 关闭, ", Style::default().fg(Color::DarkGray)),
-        Span::styled(":b <id> ", Style::default().fg(Color::Green)),
-        Span::styled("切换缓冲区", Style::default().fg(Color::DarkGray)),
+        Span::styled(editor.tr("Hint: "), theme.hint),
+        Span::styled(editor.tr("press q or Esc to close, "), theme.non_text),
+        Span::styled(":b <id> ", theme.hint_key),
+        Span::styled(editor.tr("switch buffer"), theme.non_text),
     ]));
     
     // 创建带边框的面板
     let panel = Paragraph::new(Text::from(lines))
         .block(
             Block::default()
-                .title(" Buffer List ")
+                .title(format!(" {} ", editor.tr("Buffer List")))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Blue))
-                .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+                .border_set(border_set(editor))
+                .border_style(theme.float_border)
+                .title_style(theme.title)
         );
     
     frame.render_widget(panel, panel_area);
 }
+
+/// 绘制模糊选择器（如 :symbols）
+///
+/// 居中显示：第一行是查询输入，下面是按得分排序的匹配项，
+/// 标题显示匹配数量和候选总数
+fn draw_picker(frame: &mut Frame, editor: &Editor, theme: &Theme, area: Rect) {
+    let Some(picker) = editor.picker() else {
+        return;
+    };
+
+    let width = (area.width as f32 * 0.6).clamp(40.0, 100.0) as u16;
+    let width = width.min(area.width);
+    // 查询行(1) + 分隔线(1) + 候选项 + 边框(2)
+    let height = (picker.max_visible() as u16 + 4).min(area.height);
+    let x = area.x + (area.width - width) / 2;
+    let y = area.y + (area.height - height) / 3;
+    let picker_area = Rect::new(x, y, width, height);
+
+    let inner_width = width.saturating_sub(2) as usize;
+    let mut lines: Vec<Line> = Vec::new();
+
+    lines.push(Line::from(vec![
+        Span::styled("> ", theme.title),
+        Span::raw(picker.query().to_string()),
+    ]));
+    lines.push(Line::from(Span::styled(
+        editor.options().fillchars.horiz.to_string().repeat(inner_width),
+        theme.non_text,
+    )));
+
+    if picker.match_count() == 0 {
+        lines.push(Line::from(Span::styled(format!("  {}", editor.tr("No matches")), theme.non_text)));
+    }
+
+    for (idx, item) in picker.visible_items() {
+        let style = if idx == picker.selected_index() {
+            theme.menu_selected
+        } else {
+            Style::default()
+        };
+        let label = format!(" {}", item.label);
+        let detail = item.detail.clone().unwrap_or_default();
+        // 说明文字右对齐，空间不足时省略
+        let padding = inner_width.saturating_sub(label.chars().count() + detail.chars().count() + 1);
+        let mut spans = vec![Span::styled(label, style)];
+        if padding > 0 {
+            spans.push(Span::styled(" ".repeat(padding), style));
+            spans.push(Span::styled(detail, style.patch(theme.menu_extra)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    // 还在加载候选项时在数量后面显示 …
+    let loading = if picker.is_loading() { "…" } else { "" };
+    let title = format!(" {} {}/{}{} ", picker.title(), picker.match_count(), picker.total(), loading);
+    frame.render_widget(Clear, picker_area);
+    let widget = Paragraph::new(Text::from(lines)).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_set(border_set(editor))
+            .border_style(theme.float_border)
+            .title_style(theme.title),
+    );
+    frame.render_widget(widget, picker_area);
+}
@@ -0,0 +1,59 @@
+//! 重绘判断测试
+//!
+//! 对应源文件: src/damage.rs
+
+use aivim_core::{Cursor, Editor};
+use aivim_tui::damage::Damage;
+use ratatui::layout::Rect;
+
+const SIZE: Rect = Rect { x: 0, y: 0, width: 80, height: 24 };
+
+fn editor() -> Editor {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, "one\ntwo\nthree\n");
+    editor
+}
+
+#[test]
+fn test_idle_frames_are_skipped() {
+    let editor = editor();
+    let mut damage = Damage::new();
+    // 第一帧总是绘制
+    assert!(damage.take(&editor, SIZE));
+    for _ in 0..10 {
+        assert!(!damage.take(&editor, SIZE));
+    }
+}
+
+#[test]
+fn test_state_changes_redraw() {
+    let mut editor = editor();
+    let mut damage = Damage::new();
+    damage.take(&editor, SIZE);
+
+    *editor.cursor_mut() = Cursor::new(1, 0);
+    assert!(damage.take(&editor, SIZE));
+    assert!(!damage.take(&editor, SIZE));
+
+    editor.current_buffer_mut().insert(0, "zero\n");
+    assert!(damage.take(&editor, SIZE));
+
+    editor.set_message("hello");
+    assert!(damage.take(&editor, SIZE));
+
+    editor.execute_command("split").unwrap();
+    assert!(damage.take(&editor, SIZE));
+
+    assert!(damage.take(&editor, Rect { width: 100, ..SIZE }));
+    assert!(!damage.take(&editor, Rect { width: 100, ..SIZE }));
+}
+
+#[test]
+fn test_mark_forces_redraw() {
+    let editor = editor();
+    let mut damage = Damage::new();
+    damage.take(&editor, SIZE);
+    damage.mark();
+    assert!(damage.take(&editor, SIZE));
+    assert!(!damage.take(&editor, SIZE));
+}
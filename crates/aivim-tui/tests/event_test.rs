@@ -0,0 +1,36 @@
+//! 事件循环事件来源测试
+//!
+//! 对应源文件: src/event.rs
+
+use aivim_tui::event::{Event, EventHandler};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_next_returns_tick_without_events() {
+    let handler = EventHandler::new(Duration::from_millis(1));
+    assert!(matches!(handler.next().unwrap(), Event::Tick));
+}
+
+#[test]
+fn test_waker_from_background_thread() {
+    let handler = EventHandler::new(Duration::from_secs(5));
+    let waker = handler.waker();
+    thread::spawn(move || waker.wake()).join().unwrap();
+    assert!(matches!(handler.next().unwrap(), Event::Wake));
+}
+
+#[test]
+fn test_repeated_wakes_are_coalesced() {
+    let handler = EventHandler::new(Duration::from_millis(1));
+    let waker = handler.waker();
+    for _ in 0..100 {
+        waker.wake();
+    }
+    assert!(matches!(handler.next().unwrap(), Event::Wake));
+    assert!(matches!(handler.next().unwrap(), Event::Tick));
+
+    // 处理之后的唤醒再次发送
+    waker.wake();
+    assert!(matches!(handler.next().unwrap(), Event::Wake));
+}
@@ -0,0 +1,50 @@
+//! 无界面模式测试
+//!
+//! 对应源文件: src/app.rs (App::headless、run_commands 等待外部命令、write_stdin_buffer)
+
+use aivim_tui::App;
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("aivim_headless_{}_{}.txt", name, std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn commands(commands: &[&str]) -> Vec<String> {
+    commands.iter().map(|command| command.to_string()).collect()
+}
+
+#[test]
+fn test_filter_then_write() {
+    let path = temp_file("filter", "c\nb\na\n");
+    let mut app = App::headless();
+    app.open_file(&path).unwrap();
+
+    assert_eq!(app.run_commands(&commands(&["%!sort", "wq"])), Ok(()));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\nc\n");
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_read_command_output_into_stdin_buffer() {
+    let mut app = App::headless();
+    app.open_bytes(b"first\n");
+
+    assert_eq!(app.run_commands(&commands(&["r !echo hi"])), Ok(()));
+    let mut out = Vec::new();
+    app.write_stdin_buffer(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "first\nhi\n");
+}
+
+#[test]
+fn test_failed_filter_is_an_error() {
+    let path = temp_file("failed", "b\na\n");
+    let mut app = App::headless();
+    app.open_file(&path).unwrap();
+
+    assert!(app.run_commands(&commands(&["%!exit 3", "wq"])).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "b\na\n");
+    fs::remove_file(path).ok();
+}
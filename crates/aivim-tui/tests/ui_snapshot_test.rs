@@ -0,0 +1,257 @@
+//! 界面快照测试
+//!
+//! 渲染整帧并与 tests/snapshots/ 下的 golden 文件比较，
+//! 界面有意修改后用 AIVIM_UPDATE_SNAPSHOTS=1 cargo test -p aivim-tui 更新
+
+use aivim_core::plugin::AiPlugin;
+use aivim_core::{Cursor, Editor, Mode};
+use aivim_tui::app::OperatorState;
+use aivim_tui::snapshot::{assert_snapshot, render};
+
+const WIDTH: u16 = 40;
+const HEIGHT: u16 = 10;
+
+fn editor_with(content: &str, line: usize, column: usize) -> Editor {
+    let mut editor = Editor::new();
+    editor.current_buffer_mut().insert(0, content);
+    *editor.cursor_mut() = Cursor::new(line, column);
+    editor
+}
+
+fn render_frame(editor: &Editor) -> String {
+    render(editor, WIDTH, HEIGHT, OperatorState::None)
+}
+
+#[test]
+fn test_snapshot_normal_mode() {
+    let editor = editor_with("fn main() {\n    println!(\"hi\");\n}\n", 1, 4);
+    assert_snapshot("normal_mode", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_line_numbers() {
+    let mut editor = editor_with("one\ntwo\nthree\n", 2, 0);
+    editor.options_mut().number = true;
+    editor.options_mut().relativenumber = true;
+    assert_snapshot("line_numbers", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_command_line() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.set_mode(Mode::Command);
+    editor.command_line_mut().push_str("set number");
+    assert_snapshot("command_line", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_ai_suggestion() {
+    let mut editor = editor_with("let x;\nlast\n", 0, 5);
+    editor.set_mode(Mode::Insert);
+    editor.with_plugin::<AiPlugin, _>(|ai, editor| ai.show_suggestion(editor, " = compute(\n    1,\n)"));
+    assert_snapshot("ai_suggestion", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_register_pending() {
+    let editor = editor_with("text\n", 0, 0);
+    let frame = render(&editor, WIDTH, HEIGHT, OperatorState::RegisterPending(Some('a')));
+    assert_snapshot("register_pending", &frame);
+}
+
+#[test]
+fn test_snapshot_buffer_list_panel() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.create_new_buffer();
+    editor.set_show_buffer_list(true);
+    assert_snapshot("buffer_list_panel", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_registers_panel() {
+    let mut editor = editor_with("alpha\nbeta\n", 0, 0);
+    assert!(editor.execute_command("2y a").is_ok());
+    editor.set_show_registers_panel(true);
+    assert_snapshot("registers_panel", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_ascii_glyphs() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.set_ascii_glyphs(true);
+    editor.set_show_buffer_list(true);
+    assert_snapshot("ascii_glyphs", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_tabs() {
+    let mut editor = editor_with("\tfoo\n\tbar\tbaz\n", 1, 4);
+    editor.options_mut().tabstop = 4;
+    assert_snapshot("tabs", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_wide_chars() {
+    // 光标在两个 CJK 字符和两个 emoji 之后，列按显示宽度计算
+    let editor = editor_with("你好 world\n😀🎉 end\n", 1, 3);
+    assert_snapshot("wide_chars", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_wrap() {
+    let mut editor = editor_with(&format!("{}\nshort\n", "x".repeat(50)), 0, 45);
+    editor.options_mut().number = true;
+    assert_snapshot("wrap", &render_frame(&editor));
+
+    editor.options_mut().wrap = false;
+    assert_snapshot("nowrap", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_split_windows() {
+    let mut editor = editor_with("one\ntwo\nthree\n", 2, 0);
+    editor.execute_command("split").unwrap();
+    *editor.cursor_mut() = Cursor::new(0, 1);
+    assert_snapshot("split", &render_frame(&editor));
+
+    editor.execute_command("vsplit").unwrap();
+    assert_snapshot("vsplit", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_quickfix_panel() {
+    use aivim_core::quickfix::{QuickfixEntry, QuickfixList};
+
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    let mut quickfix = QuickfixList::new(":grep two");
+    quickfix.extend([
+        QuickfixEntry::new("src/a.rs", 1, 0, "two"),
+        QuickfixEntry::new("src/b.rs", 9, 4, "let two = 2;"),
+    ]);
+    editor.set_quickfix(quickfix);
+    editor.execute_command("copen").unwrap();
+    editor.select_quickfix(1);
+    assert_snapshot("quickfix", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_location_list_panel() {
+    use aivim_core::quickfix::{QuickfixEntry, QuickfixKind, QuickfixList};
+
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    let mut list = QuickfixList::new("diagnostics");
+    list.extend([
+        QuickfixEntry::new("a.rs", 0, 0, "bad").with_kind(QuickfixKind::Error),
+        QuickfixEntry::new("a.rs", 1, 2, "unused").with_kind(QuickfixKind::Warning),
+    ]);
+    editor.set_location_list(Some(list));
+    editor.execute_command("lopen").unwrap();
+    assert_snapshot("location_list", &render_frame(&editor));
+}
+
+#[test]
+fn test_snapshot_tab_line() {
+    let mut editor = editor_with("one\ntwo\n", 0, 0);
+    editor.execute_command("tabnew").unwrap();
+    editor.execute_command("vsplit").unwrap();
+    editor.current_buffer_mut().insert(0, "new");
+    editor.previous_tab(1);
+    assert_snapshot("tab_line", &render_frame(&editor));
+}
+
+#[test]
+fn test_cursorline_and_cursorcolumn_styles() {
+    use aivim_tui::theme::Theme;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let mut editor = editor_with("abc\nabc\n", 1, 1);
+    let draw = |editor: &Editor| {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        terminal
+            .draw(|frame| aivim_tui::ui::draw(frame, editor, OperatorState::None))
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+    let theme = Theme::default();
+
+    // 默认不高亮
+    let buffer = draw(&editor);
+    assert_eq!(buffer.get(0, 1).bg, ratatui::style::Color::Reset);
+
+    editor.execute_command("set cursorline cursorcolumn").unwrap();
+    let buffer = draw(&editor);
+    assert_eq!(buffer.get(0, 1).bg, theme.cursor_line.bg.unwrap());
+    assert_eq!(buffer.get(0, 0).bg, ratatui::style::Color::Reset);
+    assert_eq!(buffer.get(1, 0).bg, theme.cursor_column.bg.unwrap());
+    // 缓冲区末尾之后的行不高亮
+    assert_eq!(buffer.get(1, 3).bg, ratatui::style::Color::Reset);
+}
+
+#[test]
+fn test_builtin_themes_cover_colorschemes_and_groups() {
+    use aivim_core::colorscheme::{COLORSCHEMES, HIGHLIGHT_GROUPS};
+    use aivim_tui::theme::Theme;
+
+    for name in COLORSCHEMES {
+        let mut theme = Theme::builtin(name).unwrap_or_else(|| panic!("no theme for {}", name));
+        for group in HIGHLIGHT_GROUPS {
+            assert!(theme.group_mut(group).is_some(), "no style for {}", group);
+        }
+    }
+    assert_eq!(Theme::builtin("nonexistent"), None);
+}
+
+#[test]
+fn test_colorscheme_and_highlight_styles() {
+    use aivim_tui::theme::Theme;
+    use ratatui::backend::TestBackend;
+    use ratatui::style::{Color, Modifier};
+    use ratatui::Terminal;
+
+    let mut editor = editor_with("abc\n", 0, 0);
+    let draw = |editor: &Editor| {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        terminal
+            .draw(|frame| aivim_tui::ui::draw(frame, editor, OperatorState::None))
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+
+    // 状态栏第一格是模式名称
+    let status_row = HEIGHT - 2;
+    assert_eq!(draw(&editor).get(0, status_row).bg, Theme::DARK.mode_normal.bg.unwrap());
+
+    editor.execute_command("colorscheme gruvbox").unwrap();
+    let buffer = draw(&editor);
+    assert_eq!(buffer.get(0, status_row).bg, Theme::GRUVBOX.mode_normal.bg.unwrap());
+    assert_eq!(buffer.get(0, 0).bg, Theme::GRUVBOX.normal.bg.unwrap());
+
+    editor.execute_command("highlight ModeNormal bg=#123456 attr=italic").unwrap();
+    let cell = draw(&editor).get(0, status_row).clone();
+    assert_eq!(cell.bg, Color::Rgb(0x12, 0x34, 0x56));
+    assert_eq!(cell.fg, Theme::GRUVBOX.mode_normal.fg.unwrap());
+    assert!(cell.modifier.contains(Modifier::ITALIC));
+    assert!(!cell.modifier.contains(Modifier::BOLD));
+
+    // 切换配色方案清除 :highlight 的修改
+    editor.execute_command("colorscheme default").unwrap();
+    assert_eq!(draw(&editor).get(0, status_row).bg, Theme::DARK.mode_normal.bg.unwrap());
+}
+
+#[test]
+fn test_snapshot_statusline_segments() {
+    let mut editor = editor_with("fn main() {}\n", 0, 3);
+    editor.current_buffer_mut().set_filetype("rust");
+    editor.start_recording('q').unwrap();
+    assert_snapshot("statusline_segments", &render_frame(&editor));
+    // 终端太窄时先省略优先级低的片段
+    assert_snapshot("statusline_narrow", &render(&editor, 20, 4, OperatorState::None));
+}
+
+#[test]
+fn test_snapshot_confirmation_prompt() {
+    let mut editor = editor_with("text\n", 0, 0);
+    editor.execute_command("q").unwrap();
+    assert_snapshot("confirmation_prompt", &render(&editor, 60, 4, OperatorState::None));
+}
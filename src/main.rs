@@ -1,20 +1,107 @@
+use aivim_core::session::DEFAULT_SESSION_FILE;
 use aivim_tui::App;
+use clap::Parser;
 use std::env;
+use std::io::{self, Read};
 use std::path::PathBuf;
 
+/// 命令行参数，`+{cmd}` 形式的参数在解析之前取出（见 `split_plus_commands`）
+#[derive(Parser, Debug)]
+#[command(
+    name = "aivim",
+    version,
+    about = "A Vim-compatible text editor written in Rust",
+    override_usage = "aivim [OPTIONS] [+N | +{command}] [FILE | -]",
+    after_help = "  +N          Jump to line N after loading the file (+ alone jumps to the last line)\n  +{command}  Run an ex command after loading the file"
+)]
+struct Cli {
+    /// 要打开的文件，`-` 从标准输入读取
+    #[arg(value_name = "FILE", help = "File to edit, or - to read the buffer from stdin")]
+    file: Option<PathBuf>,
+
+    #[arg(short = 'R', help = "Open buffers read-only (:w needs ! to write them)")]
+    read_only: bool,
+
+    #[arg(short = 'c', value_name = "COMMAND", help = "Run an ex command after loading the file (can be repeated)")]
+    commands: Vec<String>,
+
+    #[arg(long, help = "Read the buffer from stdin (same as FILE -)")]
+    stdin: bool,
+
+    /// 无界面模式，用于脚本和管道：`cat file | aivim --headless -c '%s/a/b/g' -`
+    #[arg(
+        long,
+        help = "Run the commands without starting the terminal UI, then exit; a buffer read from stdin is written to stdout"
+    )]
+    headless: bool,
+
+    /// aivim -S [file] 恢复会话，会话文件在配置文件之后执行
+    #[arg(
+        short = 'S',
+        value_name = "SESSION",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_SESSION_FILE,
+        help = "Restore a session saved with :mksession (default Session.vim)"
+    )]
+    session: Option<PathBuf>,
+}
+
+/// 取出 `+N`、`+` 和 `+{cmd}` 参数，转换为打开文件后执行的 Ex 命令（`+` 跳到最后一行）
+fn split_plus_commands(args: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let (plus, rest): (Vec<String>, Vec<String>) = args.into_iter().partition(|arg| arg.starts_with('+'));
+    let commands = plus
+        .into_iter()
+        .map(|arg| match &arg[1..] {
+            "" => "$".to_string(),
+            command => command.to_string(),
+        })
+        .collect();
+    (commands, rest)
+}
+
 fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+    // 日志写到标准错误，无界面模式的标准输出只有缓冲区的内容
+    tracing_subscriber::fmt().with_writer(io::stderr).init();
+
+    let (mut commands, args) = split_plus_commands(env::args().collect());
+    let cli = Cli::parse_from(args);
+    commands.extend(cli.commands);
 
-    let args: Vec<String> = env::args().collect();
-    
-    let mut app = if args.len() > 1 {
-        let file_path = PathBuf::from(&args[1]);
-        App::with_file(file_path)?
+    let read_stdin = cli.stdin || cli.file.as_deref().is_some_and(|file| file.as_os_str() == "-");
+    // 终端界面启动之前读完标准输入，之后的按键从终端读取
+    let input = if read_stdin {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        Some(bytes)
     } else {
-        App::new()
+        None
     };
 
-    app.run()?;
-    
+    let mut app = if cli.headless { App::headless() } else { App::new() };
+    app.set_read_only(cli.read_only);
+    match (input, cli.file) {
+        (Some(bytes), _) => app.open_bytes(&bytes),
+        (None, Some(file)) => app.open_file(&file)?,
+        (None, None) => {}
+    }
+    if !cli.headless {
+        app.load_config();
+    }
+    if let Some(session) = cli.session {
+        app.load_session(&session);
+    }
+    let result = app.run_commands(&commands);
+
+    if cli.headless {
+        // 命令出错时仍然输出缓冲区，但以非零状态退出
+        app.run_headless()?;
+        if let Err(error) = result {
+            eprintln!("aivim: {}", error);
+            std::process::exit(1);
+        }
+    } else {
+        app.run()?;
+    }
+
     Ok(())
 }